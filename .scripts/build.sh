@@ -8,4 +8,10 @@ rdx() {
 rdx cargo build --verbose || exit 1
 echo
 rdx cd event || exit 1
-rdx cargo test --tests --verbose --features "crossbeam-channel"
+rdx cargo test --tests --verbose --features "crossbeam-channel tokio"
+echo
+rdx cd ../reclutch || exit 1
+rdx cargo test --tests --verbose --features "widgets"
+echo
+rdx cd ../core || exit 1
+rdx cargo test --tests --verbose --features "lottie svg"
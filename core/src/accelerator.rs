@@ -0,0 +1,267 @@
+//! Keyboard shortcuts ("accelerators"), which map key chords to named commands.
+//!
+//! Accelerators belong to a context, which can be disabled as a whole; for instance, single-key shortcuts
+//! can be registered in their own context which is disabled while a text box has focus.
+//!
+//! ```ignore
+//! let mut accelerators = Accelerators::new(window_q);
+//! accelerators.register("Ctrl+S".parse()?, "save")?;
+//! accelerators.register_in("canvas", "Delete".parse()?, "delete_selection")?;
+//! let commands = accelerators.event.listen();
+//!
+//! // in update:
+//! accelerators.update();
+//! for command in commands.peek() { /* ... */ }
+//! ```
+
+use {
+    crate::{
+        error::AcceleratorError,
+        event::{RcEventListener, RcEventQueue},
+        input::{Key, Modifiers, WindowEvent},
+        prelude::*,
+    },
+    std::{collections::HashSet, fmt, str::FromStr},
+};
+
+/// The context accelerators are registered in by [`register`](Accelerators::register).
+pub const GLOBAL_CONTEXT: &str = "global";
+
+/// A key along with the modifiers which have to be held, such as `Ctrl+Shift+S`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub key: Key,
+    pub modifiers: Modifiers,
+}
+
+impl KeyChord {
+    pub const fn new(key: Key, modifiers: Modifiers) -> Self {
+        KeyChord { key, modifiers }
+    }
+}
+
+impl fmt::Display for KeyChord {
+    /// Formats the chord as shown in menus, e.g. `Ctrl+Shift+S`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let modifiers = [
+            (self.modifiers.ctrl, "Ctrl"),
+            (self.modifiers.alt, "Alt"),
+            (self.modifiers.shift, "Shift"),
+            (self.modifiers.logo, "Logo"),
+        ];
+        for (_, name) in modifiers.iter().filter(|(held, _)| *held) {
+            write!(f, "{}+", name)?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+impl FromStr for KeyChord {
+    type Err = AcceleratorError;
+
+    /// Parses a chord such as `Ctrl+Shift+S`, ignoring case and whitespace.
+    ///
+    /// Besides the names used by `Display`, `Control`, `Option`, `Super`, `Cmd` and `Meta` are accepted as modifiers.
+    fn from_str(s: &str) -> Result<Self, AcceleratorError> {
+        let invalid = || AcceleratorError::InvalidChord(s.to_string());
+        let mut parts: Vec<_> = s.split('+').map(str::trim).collect();
+        let key = parts.pop().ok_or_else(invalid)?.parse().map_err(|_| invalid())?;
+
+        let mut modifiers = Modifiers::NONE;
+        for part in parts {
+            modifiers = modifiers
+                | match part.to_ascii_lowercase().as_str() {
+                    "ctrl" | "control" => Modifiers::CTRL,
+                    "alt" | "option" => Modifiers::ALT,
+                    "shift" => Modifiers::SHIFT,
+                    "logo" | "super" | "cmd" | "meta" => Modifiers::LOGO,
+                    _ => return Err(invalid()),
+                };
+        }
+
+        Ok(KeyChord { key, modifiers })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Accelerator {
+    chord: KeyChord,
+    command: &'static str,
+    context: &'static str,
+}
+
+/// A registry of accelerators, which emits the command of every accelerator pressed into [`event`](Accelerators::event).
+///
+/// Where a chord is bound in multiple enabled contexts, the accelerator registered last wins.
+pub struct Accelerators {
+    pub event: RcEventQueue<&'static str>,
+    listener: RcEventListener<WindowEvent>,
+    accelerators: Vec<Accelerator>,
+    disabled: HashSet<&'static str>,
+}
+
+impl Accelerators {
+    /// Creates an empty registry, handling key presses from the global window event queue.
+    pub fn new(window_q: &RcEventQueue<WindowEvent>) -> Self {
+        Accelerators {
+            event: RcEventQueue::new(),
+            listener: window_q.listen(),
+            accelerators: Vec::new(),
+            disabled: HashSet::new(),
+        }
+    }
+
+    /// Binds `chord` to `command` in the [global context](GLOBAL_CONTEXT).
+    #[inline]
+    pub fn register(
+        &mut self,
+        chord: KeyChord,
+        command: &'static str,
+    ) -> Result<(), AcceleratorError> {
+        self.register_in(GLOBAL_CONTEXT, chord, command)
+    }
+
+    /// Binds `chord` to `command` in `context`.
+    ///
+    /// Fails if the chord is already bound to another command in the same context.
+    pub fn register_in(
+        &mut self,
+        context: &'static str,
+        chord: KeyChord,
+        command: &'static str,
+    ) -> Result<(), AcceleratorError> {
+        if let Some(existing) =
+            self.accelerators.iter().find(|acc| acc.context == context && acc.chord == chord)
+        {
+            return if existing.command == command {
+                Ok(())
+            } else {
+                Err(AcceleratorError::Conflict { chord, context, existing: existing.command })
+            };
+        }
+        self.accelerators.push(Accelerator { chord, command, context });
+        Ok(())
+    }
+
+    /// Removes every accelerator of `command`, returning `true` if there were any.
+    pub fn unregister(&mut self, command: &'static str) -> bool {
+        let len = self.accelerators.len();
+        self.accelerators.retain(|acc| acc.command != command);
+        self.accelerators.len() != len
+    }
+
+    /// Returns the chords bound to `command` in any context, e.g. to show them in a menu.
+    pub fn chords(&self, command: &str) -> Vec<KeyChord> {
+        self.accelerators.iter().filter(|acc| acc.command == command).map(|acc| acc.chord).collect()
+    }
+
+    /// Returns the command `chord` currently triggers, taking disabled contexts into account.
+    pub fn command(&self, chord: KeyChord) -> Option<&'static str> {
+        self.accelerators
+            .iter()
+            .rev()
+            .find(|acc| acc.chord == chord && !self.disabled.contains(acc.context))
+            .map(|acc| acc.command)
+    }
+
+    /// Returns every chord bound to more than one command across contexts, along with those commands.
+    ///
+    /// These aren't errors (the accelerator registered last wins), but may be unintended.
+    pub fn conflicts(&self) -> Vec<(KeyChord, Vec<&'static str>)> {
+        let mut conflicts: Vec<(KeyChord, Vec<&'static str>)> = Vec::new();
+        for acc in &self.accelerators {
+            match conflicts.iter_mut().find(|(chord, _)| *chord == acc.chord) {
+                Some((_, commands)) => {
+                    if !commands.contains(&acc.command) {
+                        commands.push(acc.command);
+                    }
+                }
+                None => conflicts.push((acc.chord, vec![acc.command])),
+            }
+        }
+        conflicts.retain(|(_, commands)| commands.len() > 1);
+        conflicts
+    }
+
+    /// Enables or disables every accelerator in `context`. Contexts are enabled by default.
+    pub fn set_context_enabled(&mut self, context: &'static str, enabled: bool) {
+        if enabled {
+            self.disabled.remove(context);
+        } else {
+            self.disabled.insert(context);
+        }
+    }
+
+    /// Returns `true` unless `context` was disabled.
+    pub fn is_context_enabled(&self, context: &str) -> bool {
+        !self.disabled.contains(context)
+    }
+
+    /// Handles the pending key presses, emitting the commands of the matching accelerators.
+    pub fn update(&mut self) {
+        for event in self.listener.peek() {
+            if let WindowEvent::KeyPress(key, modifiers) = event {
+                if let Some(command) = self.command(KeyChord::new(key, modifiers)) {
+                    self.event.emit_owned(command);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_chord() {
+        let chord: KeyChord = "ctrl + Shift+s".parse().unwrap();
+        assert_eq!(chord, KeyChord::new(Key::S, Modifiers::CTRL | Modifiers::SHIFT));
+        assert_eq!(chord.to_string(), "Ctrl+Shift+S");
+        assert_eq!("Cmd+PageUp".parse(), Ok(KeyChord::new(Key::PageUp, Modifiers::LOGO)));
+        assert_eq!("F5".parse::<KeyChord>().unwrap().to_string(), "F5");
+        assert_eq!("Alt+0".parse(), Ok(KeyChord::new(Key::Key0, Modifiers::ALT)));
+        assert_eq!(
+            "Hyper+S".parse::<KeyChord>(),
+            Err(AcceleratorError::InvalidChord("Hyper+S".to_string()))
+        );
+        assert!("Ctrl+".parse::<KeyChord>().is_err());
+    }
+
+    #[test]
+    fn test_accelerators() {
+        let window_q = RcEventQueue::new();
+        let mut accelerators = Accelerators::new(&window_q);
+        let commands = accelerators.event.listen();
+        let save = KeyChord::new(Key::S, Modifiers::CTRL);
+        let delete = KeyChord::new(Key::Delete, Modifiers::NONE);
+
+        accelerators.register(save, "save").unwrap();
+        accelerators.register(save, "save").unwrap();
+        assert_eq!(
+            accelerators.register(save, "save_as"),
+            Err(AcceleratorError::Conflict {
+                chord: save,
+                context: GLOBAL_CONTEXT,
+                existing: "save"
+            })
+        );
+        accelerators.register(delete, "delete").unwrap();
+        accelerators.register_in("text", delete, "delete_character").unwrap();
+        assert_eq!(accelerators.conflicts(), vec![(delete, vec!["delete", "delete_character"])]);
+        assert_eq!(accelerators.chords("save"), vec![save]);
+
+        window_q.emit_owned(WindowEvent::KeyPress(Key::S, Modifiers::CTRL));
+        window_q.emit_owned(WindowEvent::KeyPress(Key::S, Modifiers::NONE));
+        window_q.emit_owned(WindowEvent::KeyPress(Key::Delete, Modifiers::NONE));
+        accelerators.update();
+        assert_eq!(commands.peek(), &["save", "delete_character"]);
+
+        accelerators.set_context_enabled("text", false);
+        assert!(!accelerators.is_context_enabled("text"));
+        assert_eq!(accelerators.command(delete), Some("delete"));
+
+        assert!(accelerators.unregister("delete"));
+        assert_eq!(accelerators.command(delete), None);
+    }
+}
@@ -0,0 +1,292 @@
+//! Copying and pasting text and images, through the system clipboard or an in-memory one.
+//!
+//! The application owns a [`SharedClipboard`](SharedClipboard), which is handed to the widgets that copy or paste.
+//! Widgets don't bind to the clipboard shortcuts themselves; the window emits
+//! [`WindowEvent::Clipboard`](crate::input::WindowEvent::Clipboard) after the key press of a clipboard shortcut
+//! (see [`ClipboardAction::from_chord`](ClipboardAction::from_chord)), which the focused widget handles by
+//! reading from or writing to the clipboard:
+//!
+//! ```ignore
+//! // with the `clipboard` feature; otherwise `SharedClipboard::default()` is an in-memory clipboard.
+//! let clipboard = SharedClipboard::system()?;
+//! text_box.set_clipboard(clipboard.clone());
+//!
+//! // in the text box:
+//! WindowEvent::Clipboard(ClipboardAction::Paste) if focused => {
+//!     if let Ok(text) = clipboard.get_text() {
+//!         insert(&text);
+//!     }
+//! }
+//! ```
+
+use {
+    crate::{
+        display::{RasterImage, RasterImageFormat},
+        error::ClipboardError,
+        input::{Key, Modifiers},
+    },
+    std::{cell::RefCell, fmt, rc::Rc},
+};
+
+/// What the focused widget should do with the clipboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClipboardAction {
+    Copy,
+    Cut,
+    Paste,
+}
+
+impl ClipboardAction {
+    /// Returns the action of a clipboard shortcut; Ctrl (or Logo, i.e. Command on macOS) with C, X or V,
+    /// along with the older Ctrl+Insert, Shift+Delete and Shift+Insert.
+    pub fn from_chord(key: Key, modifiers: Modifiers) -> Option<Self> {
+        let command = (modifiers.ctrl || modifiers.logo) && !modifiers.shift && !modifiers.alt;
+        let shift = modifiers.shift && !(modifiers.ctrl || modifiers.logo || modifiers.alt);
+        match key {
+            Key::C if command => Some(ClipboardAction::Copy),
+            Key::X if command => Some(ClipboardAction::Cut),
+            Key::V if command => Some(ClipboardAction::Paste),
+            Key::Insert if command => Some(ClipboardAction::Copy),
+            Key::Delete if shift => Some(ClipboardAction::Cut),
+            Key::Insert if shift => Some(ClipboardAction::Paste),
+            _ => None,
+        }
+    }
+}
+
+/// Contents of a clipboard.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClipboardContent {
+    Text(String),
+    Image(RasterImage),
+}
+
+/// Access to a clipboard.
+///
+/// Only text is required; images are unsupported unless implemented.
+pub trait Clipboard {
+    fn get_text(&mut self) -> Result<String, ClipboardError>;
+    fn set_text(&mut self, text: String) -> Result<(), ClipboardError>;
+
+    fn get_image(&mut self) -> Result<RasterImage, ClipboardError> {
+        Err(ClipboardError::Unsupported)
+    }
+
+    fn set_image(&mut self, _image: RasterImage) -> Result<(), ClipboardError> {
+        Err(ClipboardError::Unsupported)
+    }
+}
+
+/// Clipboard which only lives within the application, e.g. for tests or platforms without a system clipboard.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MemoryClipboard {
+    pub content: Option<ClipboardContent>,
+}
+
+impl Clipboard for MemoryClipboard {
+    fn get_text(&mut self) -> Result<String, ClipboardError> {
+        match &self.content {
+            Some(ClipboardContent::Text(text)) => Ok(text.clone()),
+            _ => Err(ClipboardError::Empty),
+        }
+    }
+
+    fn set_text(&mut self, text: String) -> Result<(), ClipboardError> {
+        self.content = Some(ClipboardContent::Text(text));
+        Ok(())
+    }
+
+    fn get_image(&mut self) -> Result<RasterImage, ClipboardError> {
+        match &self.content {
+            Some(ClipboardContent::Image(image)) => Ok(image.clone()),
+            _ => Err(ClipboardError::Empty),
+        }
+    }
+
+    fn set_image(&mut self, image: RasterImage) -> Result<(), ClipboardError> {
+        self.content = Some(ClipboardContent::Image(image));
+        Ok(())
+    }
+}
+
+/// The clipboard of the operating system, through [`arboard`](https://docs.rs/arboard).
+#[cfg(feature = "clipboard")]
+pub struct SystemClipboard(arboard::Clipboard);
+
+#[cfg(feature = "clipboard")]
+impl SystemClipboard {
+    pub fn new() -> Result<Self, ClipboardError> {
+        Ok(SystemClipboard(arboard::Clipboard::new()?))
+    }
+}
+
+#[cfg(feature = "clipboard")]
+impl Clipboard for SystemClipboard {
+    fn get_text(&mut self) -> Result<String, ClipboardError> {
+        Ok(self.0.get_text()?)
+    }
+
+    fn set_text(&mut self, text: String) -> Result<(), ClipboardError> {
+        Ok(self.0.set_text(text)?)
+    }
+
+    fn get_image(&mut self) -> Result<RasterImage, ClipboardError> {
+        let image = self.0.get_image()?;
+        Ok(RasterImage {
+            data: image.bytes.into_owned(),
+            info: crate::display::RasterImageInfo {
+                size: (image.width as _, image.height as _),
+                format: RasterImageFormat::Rgba8,
+                color_space: crate::display::ColorSpace::Srgb,
+            },
+        })
+    }
+
+    fn set_image(&mut self, image: RasterImage) -> Result<(), ClipboardError> {
+        let image = to_rgba8(image)?;
+        Ok(self.0.set_image(arboard::ImageData {
+            width: image.info.size.0 as _,
+            height: image.info.size.1 as _,
+            bytes: image.data.into(),
+        })?)
+    }
+}
+
+#[cfg(feature = "clipboard")]
+impl From<arboard::Error> for ClipboardError {
+    fn from(err: arboard::Error) -> Self {
+        match err {
+            arboard::Error::ContentNotAvailable => ClipboardError::Empty,
+            arboard::Error::ClipboardNotSupported => ClipboardError::Unsupported,
+            err => ClipboardError::System(err.to_string()),
+        }
+    }
+}
+
+/// Converts an image to RGBA, failing if it has an unexpected length.
+fn to_rgba8(mut image: RasterImage) -> Result<RasterImage, ClipboardError> {
+    let (width, height) = image.info.size;
+    if image.data.len() != width as usize * height as usize * 4 {
+        return Err(ClipboardError::InvalidImage);
+    }
+    if image.info.format == RasterImageFormat::Bgra8 {
+        for pixel in image.data.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+        image.info.format = RasterImageFormat::Rgba8;
+    }
+    Ok(image)
+}
+
+/// A clipboard shared between the application and its widgets.
+///
+/// Cloning the handle is cheap; all clones access the same clipboard.
+#[derive(Clone)]
+pub struct SharedClipboard(Rc<RefCell<dyn Clipboard>>);
+
+impl Default for SharedClipboard {
+    /// Creates an empty [`MemoryClipboard`](MemoryClipboard).
+    fn default() -> Self {
+        SharedClipboard::new(MemoryClipboard::default())
+    }
+}
+
+impl SharedClipboard {
+    pub fn new(clipboard: impl Clipboard + 'static) -> Self {
+        SharedClipboard(Rc::new(RefCell::new(clipboard)))
+    }
+
+    /// Shares the clipboard of the operating system.
+    #[cfg(feature = "clipboard")]
+    pub fn system() -> Result<Self, ClipboardError> {
+        Ok(SharedClipboard::new(SystemClipboard::new()?))
+    }
+
+    pub fn get_text(&self) -> Result<String, ClipboardError> {
+        self.0.borrow_mut().get_text()
+    }
+
+    pub fn set_text(&self, text: impl Into<String>) -> Result<(), ClipboardError> {
+        self.0.borrow_mut().set_text(text.into())
+    }
+
+    pub fn get_image(&self) -> Result<RasterImage, ClipboardError> {
+        self.0.borrow_mut().get_image()
+    }
+
+    /// Copies an image, which is converted to RGBA first.
+    pub fn set_image(&self, image: RasterImage) -> Result<(), ClipboardError> {
+        self.0.borrow_mut().set_image(to_rgba8(image)?)
+    }
+
+    /// Returns the contents of the clipboard, preferring text over images.
+    pub fn get(&self) -> Result<ClipboardContent, ClipboardError> {
+        match self.get_text() {
+            Ok(text) => Ok(ClipboardContent::Text(text)),
+            Err(_) => self.get_image().map(ClipboardContent::Image),
+        }
+    }
+
+    pub fn set(&self, content: ClipboardContent) -> Result<(), ClipboardError> {
+        match content {
+            ClipboardContent::Text(text) => self.set_text(text),
+            ClipboardContent::Image(image) => self.set_image(image),
+        }
+    }
+}
+
+impl fmt::Debug for SharedClipboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SharedClipboard").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::display::{ColorSpace, RasterImageInfo},
+    };
+
+    #[test]
+    fn test_clipboard() {
+        let ctrl = Modifiers { ctrl: true, ..Default::default() };
+        let shift = Modifiers { shift: true, ..Default::default() };
+        assert_eq!(ClipboardAction::from_chord(Key::V, ctrl), Some(ClipboardAction::Paste));
+        assert_eq!(
+            ClipboardAction::from_chord(Key::X, Modifiers { logo: true, ..Default::default() }),
+            Some(ClipboardAction::Cut)
+        );
+        assert_eq!(ClipboardAction::from_chord(Key::Insert, shift), Some(ClipboardAction::Paste));
+        assert_eq!(ClipboardAction::from_chord(Key::V, ctrl | shift), None);
+        assert_eq!(ClipboardAction::from_chord(Key::C, Modifiers::default()), None);
+
+        let clipboard = SharedClipboard::default();
+        let other = clipboard.clone();
+        assert!(matches!(clipboard.get(), Err(ClipboardError::Empty)));
+        other.set_text("copied").unwrap();
+        assert_eq!(clipboard.get_text().unwrap(), "copied");
+
+        let image = RasterImage {
+            data: vec![1, 2, 3, 4],
+            info: RasterImageInfo {
+                size: (1, 1),
+                format: RasterImageFormat::Bgra8,
+                color_space: ColorSpace::Srgb,
+            },
+        };
+        clipboard.set_image(image.clone()).unwrap();
+        assert!(matches!(clipboard.get_text(), Err(ClipboardError::Empty)));
+        match other.get().unwrap() {
+            ClipboardContent::Image(copied) => {
+                assert_eq!(copied.data, vec![3, 2, 1, 4]);
+                assert_eq!(copied.info.format, RasterImageFormat::Rgba8);
+            }
+            _ => panic!("expected an image"),
+        }
+        assert!(matches!(
+            clipboard.set_image(RasterImage { data: vec![], ..image }),
+            Err(ClipboardError::InvalidImage)
+        ));
+    }
+}
@@ -0,0 +1,265 @@
+//! Commands, the layer between input (buttons, menus, [accelerators](crate::accelerator)) and application logic.
+//!
+//! A command has an ID, a title and an enabled state, and either runs a closure on some context (`C`),
+//! or is emitted by ID into [`CommandRegistry::event`](CommandRegistry::event) when triggered.
+//! Input sources trigger commands by ID, so that a menu item, a toolbar button and a shortcut all share
+//! the same behaviour and enabled state.
+//!
+//! ```ignore
+//! let mut commands = CommandRegistry::new();
+//! commands.add(Command::new("save", "Save").with_run(|app: &mut App| app.save()))?;
+//! commands.listen_to(&accelerators);
+//!
+//! // in update:
+//! accelerators.update();
+//! commands.update(&mut app);
+//! ```
+
+use {
+    crate::{
+        accelerator::Accelerators,
+        error::CommandError,
+        event::{RcEventListener, RcEventQueue},
+        prelude::*,
+    },
+    std::fmt,
+};
+
+type RunCommand<C> = Box<dyn FnMut(&mut C)>;
+
+/// A command which can be triggered through a [`CommandRegistry`](CommandRegistry).
+pub struct Command<C = ()> {
+    id: &'static str,
+    title: String,
+    enabled: bool,
+    run: Option<RunCommand<C>>,
+}
+
+impl<C> Command<C> {
+    /// Creates an enabled command, which is emitted into [`CommandRegistry::event`](CommandRegistry::event)
+    /// when triggered.
+    pub fn new(id: &'static str, title: impl Into<String>) -> Self {
+        Command { id, title: title.into(), enabled: true, run: None }
+    }
+
+    /// Runs `run` when triggered, instead of emitting the command.
+    pub fn with_run(mut self, run: impl FnMut(&mut C) + 'static) -> Self {
+        self.run = Some(Box::new(run));
+        self
+    }
+
+    /// Sets whether the command is initially enabled.
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Returns the ID.
+    #[inline]
+    pub fn id(&self) -> &'static str {
+        self.id
+    }
+
+    /// Returns the title shown to the user, e.g. in a menu.
+    #[inline]
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Returns `true` if the command can be triggered.
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+impl<C> fmt::Debug for Command<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Command")
+            .field("id", &self.id)
+            .field("title", &self.title)
+            .field("enabled", &self.enabled)
+            .field("runs", &self.run.is_some())
+            .finish()
+    }
+}
+
+/// A set of commands, triggered by ID.
+///
+/// Commands without a closure are emitted by ID into [`event`](CommandRegistry::event) when triggered.
+/// Every successfully triggered command, whether it runs a closure or not, is also emitted into
+/// [`triggered`](CommandRegistry::triggered), e.g. for [feedback](crate::feedback).
+pub struct CommandRegistry<C = ()> {
+    pub event: RcEventQueue<&'static str>,
+    pub triggered: RcEventQueue<&'static str>,
+    commands: Vec<Command<C>>,
+    accelerators: Option<RcEventListener<&'static str>>,
+}
+
+impl<C> Default for CommandRegistry<C> {
+    fn default() -> Self {
+        CommandRegistry {
+            event: RcEventQueue::new(),
+            triggered: RcEventQueue::new(),
+            commands: Vec::new(),
+            accelerators: None,
+        }
+    }
+}
+
+impl<C> CommandRegistry<C> {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a command. Fails if a command with the same ID was already added.
+    pub fn add(&mut self, command: Command<C>) -> Result<(), CommandError> {
+        if self.get(command.id).is_some() {
+            return Err(CommandError::Duplicate(command.id));
+        }
+        self.commands.push(command);
+        Ok(())
+    }
+
+    /// Removes a command, returning it if it was present.
+    pub fn remove(&mut self, id: &str) -> Option<Command<C>> {
+        let index = self.commands.iter().position(|command| command.id == id)?;
+        Some(self.commands.remove(index))
+    }
+
+    /// Returns a command.
+    pub fn get(&self, id: &str) -> Option<&Command<C>> {
+        self.commands.iter().find(|command| command.id == id)
+    }
+
+    /// Returns every command, in the order they were added.
+    pub fn commands(&self) -> &[Command<C>] {
+        &self.commands
+    }
+
+    /// Returns `true` if the command exists and is enabled.
+    pub fn is_enabled(&self, id: &str) -> bool {
+        self.get(id).map(Command::is_enabled).unwrap_or(false)
+    }
+
+    /// Enables or disables a command.
+    pub fn set_enabled(&mut self, id: &str, enabled: bool) -> Result<(), CommandError> {
+        self.get_mut(id)?.enabled = enabled;
+        Ok(())
+    }
+
+    /// Changes the title of a command.
+    pub fn set_title(&mut self, id: &str, title: impl Into<String>) -> Result<(), CommandError> {
+        self.get_mut(id)?.title = title.into();
+        Ok(())
+    }
+
+    /// Triggers a command; runs its closure on `context`, or emits it into [`event`](CommandRegistry::event).
+    pub fn trigger(&mut self, id: &str, context: &mut C) -> Result<(), CommandError> {
+        let command = self
+            .commands
+            .iter_mut()
+            .find(|command| command.id == id)
+            .ok_or_else(|| CommandError::Unknown(id.to_string()))?;
+        if !command.enabled {
+            return Err(CommandError::Disabled(command.id));
+        }
+        match &mut command.run {
+            Some(run) => run(context),
+            None => {
+                self.event.emit_owned(command.id);
+            }
+        }
+        self.triggered.emit_owned(command.id);
+        Ok(())
+    }
+
+    /// Triggers the commands of accelerators pressed from now on (during [`update`](CommandRegistry::update)).
+    pub fn listen_to(&mut self, accelerators: &Accelerators) {
+        self.accelerators = Some(accelerators.event.listen());
+    }
+
+    /// Triggers the commands of the accelerators pressed since the last update.
+    ///
+    /// Accelerators of unknown or disabled commands are ignored.
+    pub fn update(&mut self, context: &mut C) {
+        let pressed = match &self.accelerators {
+            Some(listener) => listener.peek(),
+            None => return,
+        };
+        for id in pressed {
+            let _ = self.trigger(id, context);
+        }
+    }
+
+    /// Returns the first shortcut of a command as shown in menus (e.g. `Ctrl+S`), if any.
+    pub fn shortcut(&self, id: &str, accelerators: &Accelerators) -> Option<String> {
+        accelerators.chords(id).first().map(ToString::to_string)
+    }
+
+    fn get_mut(&mut self, id: &str) -> Result<&mut Command<C>, CommandError> {
+        self.commands
+            .iter_mut()
+            .find(|command| command.id == id)
+            .ok_or_else(|| CommandError::Unknown(id.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            accelerator::KeyChord,
+            input::{Key, Modifiers, WindowEvent},
+        },
+    };
+
+    #[test]
+    fn test_command_registry() {
+        let mut commands = CommandRegistry::new();
+        commands.add(Command::new("save", "Save").with_run(|saves: &mut u32| *saves += 1)).unwrap();
+        commands.add(Command::new("quit", "Quit")).unwrap();
+        commands.add(Command::new("undo", "Undo").with_enabled(false)).unwrap();
+        assert_eq!(
+            commands.add(Command::new("quit", "Exit")),
+            Err(CommandError::Duplicate("quit"))
+        );
+        let emitted = commands.event.listen();
+        let triggered = commands.triggered.listen();
+
+        let mut saves = 0;
+        commands.trigger("save", &mut saves).unwrap();
+        commands.trigger("quit", &mut saves).unwrap();
+        assert_eq!(commands.trigger("undo", &mut saves), Err(CommandError::Disabled("undo")));
+        assert_eq!(
+            commands.trigger("redo", &mut saves),
+            Err(CommandError::Unknown("redo".to_string()))
+        );
+        assert_eq!(saves, 1);
+        assert_eq!(emitted.peek(), &["quit"]);
+        assert_eq!(triggered.peek(), &["save", "quit"]);
+
+        // accelerators trigger commands on update.
+        let window_q = RcEventQueue::new();
+        let mut accelerators = Accelerators::new(&window_q);
+        accelerators.register(KeyChord::new(Key::S, Modifiers::CTRL), "save").unwrap();
+        accelerators.register(KeyChord::new(Key::Z, Modifiers::CTRL), "undo").unwrap();
+        commands.listen_to(&accelerators);
+        assert_eq!(commands.shortcut("save", &accelerators), Some("Ctrl+S".to_string()));
+
+        window_q.emit_owned(WindowEvent::KeyPress(Key::S, Modifiers::CTRL));
+        window_q.emit_owned(WindowEvent::KeyPress(Key::Z, Modifiers::CTRL));
+        accelerators.update();
+        commands.update(&mut saves);
+        assert_eq!(saves, 2);
+
+        commands.set_enabled("undo", true).unwrap();
+        commands.set_title("undo", "Undo Typing").unwrap();
+        assert!(commands.is_enabled("undo"));
+        assert_eq!(commands.get("undo").unwrap().title(), "Undo Typing");
+        assert_eq!(commands.remove("undo").map(|command| command.id()), Some("undo"));
+        assert_eq!(commands.commands().len(), 2);
+    }
+}
@@ -0,0 +1,170 @@
+//! Cursor icon requests, so that widgets can change the cursor while hovered (e.g. a text beam over a text box).
+//!
+//! Widgets request a cursor every update through an [`UpdateAux`](crate::widget::Widget::UpdateAux) implementing
+//! [`CursorAux`](CursorAux). The topmost request wins, and the application runner applies the result after the update
+//! (`reclutch::app::App` does so once enabled through `with_cursor_icons`):
+//!
+//! ```ignore
+//! fn update(&mut self, aux: &mut CursorResolver) {
+//!     if self.hovered {
+//!         aux.request_cursor(CursorIcon::Text, ZOrder::default());
+//!     }
+//! }
+//! ```
+
+use crate::{display::ZOrder, pacer::FrameTime};
+
+/// Cursor icons, named after the common CSS cursors.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CursorIcon {
+    /// The platform-dependent default cursor, usually an arrow.
+    #[default]
+    Default,
+    /// Text can be selected or edited.
+    Text,
+    /// A link or a clickable control.
+    Hand,
+    Crosshair,
+    Move,
+    Grab,
+    Grabbing,
+    NotAllowed,
+    Wait,
+    /// Busy in the background, but still interactive.
+    Progress,
+    Help,
+    /// Horizontal (east-west) resizing.
+    ResizeHorizontal,
+    /// Vertical (north-south) resizing.
+    ResizeVertical,
+    /// Diagonal resizing, from the top-right to the bottom-left corner.
+    ResizeNeSw,
+    /// Diagonal resizing, from the top-left to the bottom-right corner.
+    ResizeNwSe,
+    /// The cursor isn't shown at all.
+    Hidden,
+}
+
+/// A cursor icon requested by a widget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CursorRequest {
+    pub icon: CursorIcon,
+    /// Stacking order of the requesting widget, typically that of its command group.
+    pub z_order: ZOrder,
+}
+
+/// Collects the cursor requests of an update and picks the topmost one.
+///
+/// Requests with a higher [`z_order`](CursorRequest::z_order) win. Among equal orders the last request wins,
+/// since widgets updated later (e.g. children after their parent) are usually drawn on top.
+#[derive(Debug, Clone, Default)]
+pub struct CursorResolver {
+    request: Option<CursorRequest>,
+    current: CursorIcon,
+}
+
+impl CursorResolver {
+    /// Creates a resolver showing the default cursor.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Requests a cursor icon for the current update.
+    pub fn request(&mut self, icon: CursorIcon, z_order: ZOrder) {
+        match self.request {
+            Some(request) if request.z_order > z_order => {}
+            _ => self.request = Some(CursorRequest { icon, z_order }),
+        }
+    }
+
+    /// Returns the topmost request made since the last [`resolve`](CursorResolver::resolve).
+    pub fn requested(&self) -> Option<CursorRequest> {
+        self.request
+    }
+
+    /// Returns the cursor icon as of the last [`resolve`](CursorResolver::resolve).
+    pub fn current(&self) -> CursorIcon {
+        self.current
+    }
+
+    /// Picks the topmost request (or the default cursor if there was none) and clears the requests.
+    ///
+    /// Returns the new cursor icon if it changed since the last resolve.
+    pub fn resolve(&mut self) -> Option<CursorIcon> {
+        let icon = self.request.take().map(|request| request.icon).unwrap_or_default();
+        if icon != self.current {
+            self.current = icon;
+            Some(icon)
+        } else {
+            None
+        }
+    }
+}
+
+/// An `UpdateAux` through which widgets can request a cursor icon.
+///
+/// Implemented for `()` and [`FrameTime`](crate::pacer::FrameTime) (which ignore requests), and for
+/// [`CursorResolver`](CursorResolver) itself.
+/// An `UpdateAux` which needs both should forward to a `CursorResolver` field.
+pub trait CursorAux {
+    /// Returns the resolver collecting the cursor requests, if any.
+    fn cursor_resolver(&mut self) -> Option<&mut CursorResolver>;
+
+    /// Requests a cursor icon for the current update; see [`CursorResolver::request`](CursorResolver::request).
+    #[inline]
+    fn request_cursor(&mut self, icon: CursorIcon, z_order: ZOrder) {
+        if let Some(resolver) = self.cursor_resolver() {
+            resolver.request(icon, z_order);
+        }
+    }
+}
+
+impl CursorAux for () {
+    #[inline]
+    fn cursor_resolver(&mut self) -> Option<&mut CursorResolver> {
+        None
+    }
+}
+
+impl CursorAux for FrameTime {
+    #[inline]
+    fn cursor_resolver(&mut self) -> Option<&mut CursorResolver> {
+        None
+    }
+}
+
+impl CursorAux for CursorResolver {
+    #[inline]
+    fn cursor_resolver(&mut self) -> Option<&mut CursorResolver> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_resolver() {
+        let mut resolver = CursorResolver::new();
+        assert_eq!(resolver.resolve(), None);
+
+        resolver.request_cursor(CursorIcon::Hand, ZOrder(1));
+        resolver.request_cursor(CursorIcon::Text, ZOrder(0));
+        assert_eq!(resolver.requested().unwrap().icon, CursorIcon::Hand);
+        resolver.request_cursor(CursorIcon::Move, ZOrder(1));
+        assert_eq!(resolver.resolve(), Some(CursorIcon::Move));
+        assert_eq!(resolver.current(), CursorIcon::Move);
+
+        // unchanged requests don't need to be applied again.
+        resolver.request_cursor(CursorIcon::Move, ZOrder(0));
+        assert_eq!(resolver.resolve(), None);
+
+        // without any requests, the cursor returns to the default.
+        assert_eq!(resolver.resolve(), Some(CursorIcon::Default));
+        assert_eq!(resolver.requested(), None);
+
+        let mut aux = ();
+        aux.request_cursor(CursorIcon::Text, ZOrder::default());
+    }
+}
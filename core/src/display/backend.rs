@@ -0,0 +1,180 @@
+//! Runtime selection of the display implementation, among the backends compiled in.
+//!
+//! Each backend is gated behind its own feature (e.g. `skia`); [`create_display`](create_display)
+//! tries the backends in order of preference and moves on to the next one if a backend isn't compiled in
+//! or fails to initialize.
+//!
+//! Skia over OpenGL is currently the only backend which draws anything; there is no CPU raster or wgpu backend
+//! yet. [`Headless`](DisplayBackend::Headless) only records commands, so it's never picked by
+//! [`Auto`](BackendPreference::Auto) and should only be requested explicitly, e.g. for tests or servers.
+//! Without a usable OpenGL context, [`Auto`](BackendPreference::Auto) returns an error rather than an app which
+//! silently shows nothing.
+//!
+//!
+//! ```ignore
+//! let (backend, mut display) = create_display(
+//!     &BackendPreference::Auto,
+//!     DisplayTarget { size, gl: Some(GlTarget { loader: &mut |s| context.get_proc_address(s), framebuffer_id }) },
+//! )?;
+//! if !display.supports_backdrop_filter() {
+//!     // fall back to translucent backgrounds.
+//! }
+//! ```
+
+use {super::*, crate::error::DisplayError, std::ffi::c_void};
+
+/// A display implementation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DisplayBackend {
+    /// [`SkiaGraphicsDisplay`](super::skia::SkiaGraphicsDisplay), drawing with OpenGL. Requires the `skia` feature.
+    SkiaGl,
+    /// [`RecordingDisplay`](super::recording::RecordingDisplay), which only records commands and draws nothing;
+    /// useful for headless runs and tests.
+    Headless,
+}
+
+impl DisplayBackend {
+    /// Every backend, in the order [`Auto`](BackendPreference::Auto) tries them (where applicable).
+    pub const ALL: [DisplayBackend; 2] = [DisplayBackend::SkiaGl, DisplayBackend::Headless];
+
+    /// Returns `true` if the backend is compiled in.
+    pub fn is_available(self) -> bool {
+        match self {
+            DisplayBackend::SkiaGl => cfg!(feature = "skia"),
+            DisplayBackend::Headless => true,
+        }
+    }
+
+    /// Returns the backends which are compiled in.
+    pub fn available() -> Vec<DisplayBackend> {
+        Self::ALL.iter().copied().filter(|backend| backend.is_available()).collect()
+    }
+
+    /// Returns `true` if the backend puts pixels on the screen.
+    pub fn is_presenting(self) -> bool {
+        match self {
+            DisplayBackend::SkiaGl => true,
+            DisplayBackend::Headless => false,
+        }
+    }
+}
+
+/// Which backends [`create_display`](create_display) tries, and in which order.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub enum BackendPreference {
+    /// Every backend which puts pixels on the screen, in the order of [`DisplayBackend::ALL`](DisplayBackend::ALL).
+    #[default]
+    Auto,
+    /// The given backends, in order.
+    Order(Vec<DisplayBackend>),
+}
+
+impl BackendPreference {
+    /// Returns the backends to try, in order, including those which aren't compiled in.
+    pub fn candidates(&self) -> Vec<DisplayBackend> {
+        match self {
+            BackendPreference::Auto => DisplayBackend::ALL
+                .iter()
+                .copied()
+                .filter(|backend| backend.is_presenting())
+                .collect(),
+            BackendPreference::Order(backends) => backends.clone(),
+        }
+    }
+}
+
+/// An OpenGL framebuffer in a context which is current.
+pub struct GlTarget<'a> {
+    /// Loads OpenGL functions of the context, e.g. through the `get_proc_address` of the windowing library.
+    pub loader: &'a mut dyn FnMut(&str) -> *const c_void,
+    pub framebuffer_id: u32,
+}
+
+/// What a display created through [`create_display`](create_display) draws into.
+pub struct DisplayTarget<'a> {
+    /// Size of the surface, in physical pixels.
+    pub size: (u32, u32),
+    /// Required by OpenGL-based backends, which are skipped without it.
+    pub gl: Option<GlTarget<'a>>,
+}
+
+/// Creates a display with the first of the preferred backends which is compiled in and initializes successfully.
+///
+/// Returns the error of the last backend which was tried if none succeeded,
+/// or [`DisplayError::Unsupported`](DisplayError::Unsupported) if none could be tried.
+pub fn create_display(
+    preference: &BackendPreference,
+    mut target: DisplayTarget,
+) -> Result<(DisplayBackend, Box<dyn GraphicsDisplay>), DisplayError> {
+    let mut error = DisplayError::Unsupported("every preferred display backend");
+    for backend in preference.candidates() {
+        if !backend.is_available() {
+            continue;
+        }
+        match create(backend, &mut target) {
+            Some(Ok(display)) => return Ok((backend, display)),
+            Some(Err(err)) => error = err,
+            None => {}
+        }
+    }
+    Err(error)
+}
+
+/// Creates a display with a given backend, or returns `None` if the target is unsuitable.
+fn create(
+    backend: DisplayBackend,
+    target: &mut DisplayTarget,
+) -> Option<Result<Box<dyn GraphicsDisplay>, DisplayError>> {
+    match backend {
+        #[cfg(feature = "skia")]
+        DisplayBackend::SkiaGl => {
+            let gl = target.gl.as_mut()?;
+            let framebuffer = skia::SkiaOpenGlFramebuffer {
+                framebuffer_id: gl.framebuffer_id,
+                size: (target.size.0 as _, target.size.1 as _),
+                stencil_bits: 8,
+                ..Default::default()
+            };
+            Some(
+                skia::SkiaGraphicsDisplay::new_gl_framebuffer(&mut *gl.loader, &framebuffer)
+                    .map(|display| Box::new(display) as _)
+                    .map_err(DisplayError::from),
+            )
+        }
+        #[cfg(not(feature = "skia"))]
+        DisplayBackend::SkiaGl => None,
+        DisplayBackend::Headless => {
+            let mut display = recording::RecordingDisplay::default();
+            Some(display.resize(target.size).map(|_| Box::new(display) as _))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_display() {
+        assert!(DisplayBackend::available().contains(&DisplayBackend::Headless));
+        assert!(!BackendPreference::Auto.candidates().contains(&DisplayBackend::Headless));
+
+        // without an OpenGL target, the Skia backend is skipped.
+        let preference =
+            BackendPreference::Order(vec![DisplayBackend::SkiaGl, DisplayBackend::Headless]);
+        let (backend, display) =
+            create_display(&preference, DisplayTarget { size: (100, 100), gl: None }).unwrap();
+        assert_eq!(backend, DisplayBackend::Headless);
+        assert!(!display.supports_backdrop_filter());
+        assert_eq!(display.max_texture_size(), None);
+
+        assert!(!DisplayBackend::Headless.is_presenting());
+        assert!(matches!(
+            create_display(
+                &BackendPreference::Order(vec![DisplayBackend::SkiaGl]),
+                DisplayTarget { size: (100, 100), gl: None }
+            ),
+            Err(DisplayError::Unsupported(_))
+        ));
+    }
+}
@@ -0,0 +1,167 @@
+//! Keeping display items in sync with [`Observed`](crate::event::Observed) values.
+//!
+//! Rather than listening to a "changed" queue, updating a display item and repainting its command group by
+//! hand for every piece of state, a widget registers each dependency once in a [`Binding`](Binding) and calls
+//! [`update`](Binding::update) from its own update:
+//!
+//! ```ignore
+//! let mut binding = Binding::new();
+//! binding.bind_text(&self.count_text, |counter: &mut Counter| {
+//!     (&mut counter.count_item, &mut counter.command_group)
+//! });
+//!
+//! // in `Widget::update`:
+//! binding.update(self);
+//! ```
+
+use {
+    super::{CommandGroup, DisplayText, TextDisplayItem},
+    crate::event::{prelude::*, Observed, RcEventListener},
+};
+
+/// Sets the text of `item` to the current value of `observed`, repainting `group` if the text changed.
+///
+/// Returns `true` if the text changed.
+pub fn bind_text(
+    observed: &Observed<String>,
+    item: &mut TextDisplayItem,
+    group: &mut CommandGroup,
+) -> bool {
+    set_text(item, group, observed.get().clone())
+}
+
+fn set_text(item: &mut TextDisplayItem, group: &mut CommandGroup, text: String) -> bool {
+    let text = DisplayText::Simple(text);
+    if item.text != text {
+        item.text = text;
+        group.repaint();
+        true
+    } else {
+        false
+    }
+}
+
+type Apply<T> = Box<dyn FnMut(&mut T) -> bool>;
+
+/// A registry of observed values, each applied to an object of type `T` (typically a widget) when it changes.
+pub struct Binding<T> {
+    bindings: Vec<Apply<T>>,
+}
+
+impl<T> Default for Binding<T> {
+    fn default() -> Self {
+        Binding { bindings: Vec::new() }
+    }
+}
+
+impl<T> Binding<T> {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Registers an observed value, passing its latest value to `apply` whenever it changes.
+    ///
+    /// The current value is applied in the first [`update`](Binding::update). If the value changed several times
+    /// between updates, only the latest value is applied.
+    pub fn bind<V: Clone + 'static>(
+        &mut self,
+        observed: &Observed<V>,
+        mut apply: impl FnMut(&mut T, V) + 'static,
+    ) {
+        let listener: RcEventListener<V> = observed.listen();
+        let mut initial = Some(observed.get().clone());
+        self.bindings.push(Box::new(move |target| {
+            let latest = listener.with(|values| values.last().cloned());
+            match latest.or_else(|| initial.take()) {
+                Some(value) => {
+                    initial = None;
+                    apply(target, value);
+                    true
+                }
+                None => false,
+            }
+        }));
+    }
+
+    /// Registers an observed string as the text of a display item.
+    ///
+    /// `select` returns the display item and the command group it's drawn in, which is repainted whenever the
+    /// text changes.
+    pub fn bind_text<F>(&mut self, observed: &Observed<String>, mut select: F)
+    where
+        F: for<'a> FnMut(&'a mut T) -> (&'a mut TextDisplayItem, &'a mut CommandGroup) + 'static,
+    {
+        self.bind(observed, move |target, text| {
+            let (item, group) = select(target);
+            set_text(item, group, text);
+        })
+    }
+
+    /// Applies the bound values which changed since the last update to `target`.
+    ///
+    /// Returns `true` if any value was applied.
+    pub fn update(&mut self, target: &mut T) -> bool {
+        let mut changed = false;
+        for binding in &mut self.bindings {
+            changed |= binding(target);
+        }
+        changed
+    }
+
+    /// Returns the number of registered values.
+    pub fn len(&self) -> usize {
+        self.bindings.len()
+    }
+
+    /// Returns `true` if no values are registered.
+    pub fn is_empty(&self) -> bool {
+        self.bindings.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::display::{recording::RecordingDisplay, test_text, DisplayCommand},
+    };
+
+    struct Counter {
+        count: i32,
+        label: TextDisplayItem,
+        group: CommandGroup,
+    }
+
+    #[test]
+    fn test_binding() {
+        let mut text = Observed::new("Count: 0".to_string());
+        let mut count = Observed::new(0);
+        let mut counter = Counter { count: 0, label: test_text(""), group: CommandGroup::new() };
+
+        let mut label = test_text("");
+        let mut group = CommandGroup::new();
+        assert!(bind_text(&text, &mut label, &mut group));
+        assert!(!bind_text(&text, &mut label, &mut group));
+
+        let mut binding = Binding::new();
+        binding.bind_text(&text, |counter: &mut Counter| (&mut counter.label, &mut counter.group));
+        binding.bind(&count, |counter: &mut Counter, count| counter.count = count);
+        assert_eq!(binding.len(), 2);
+
+        // the initial values are applied first.
+        let mut display = RecordingDisplay::<DisplayCommand>::default();
+        counter.group.push(&mut display, &[], Default::default(), None, None).unwrap();
+        assert!(binding.update(&mut counter));
+        assert_eq!(counter.label.text, "Count: 0".into());
+        assert!(counter.group.will_repaint());
+        assert!(!binding.update(&mut counter));
+
+        count.set(1);
+        count.set(2);
+        text.set("Count: 2".to_string());
+        assert!(binding.update(&mut counter));
+        assert_eq!(counter.count, 2);
+        assert_eq!(counter.label.text, "Count: 2".into());
+    }
+}
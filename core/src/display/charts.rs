@@ -0,0 +1,520 @@
+//! Helpers generating the display commands of common plots from typed data.
+//!
+//! Data is mapped into the display through a [`ChartArea`](ChartArea), and every helper returns a
+//! [`Picture`](super::picture::Picture), so a chart is built once and replayed until its data changes:
+//!
+//! ```ignore
+//! let y = DataRange::of(samples.iter().map(|p| p.y)).nice(5);
+//! let area = ChartArea::new(plot_bounds, DataRange::of(samples.iter().map(|p| p.x)), y);
+//!
+//! let mut recorder = PictureRecorder::new();
+//! recorder.push_picture(&charts::line_series(&samples, &area, stroke), None);
+//! recorder.push_picture(&charts::y_axis(&area, &charts::ticks(&y.ticks(5), |v| format!("{}", v)), &style)?, None);
+//! let chart = recorder.finish();
+//! ```
+
+use {
+    super::{picture::Picture, *},
+    crate::error::FontError,
+};
+
+/// Inclusive range of data values along one axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DataRange {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl DataRange {
+    pub fn new(min: f32, max: f32) -> Self {
+        DataRange { min, max }
+    }
+
+    /// Returns the smallest range containing all the finite `values`, or `0..1` if there are none.
+    pub fn of(values: impl IntoIterator<Item = f32>) -> Self {
+        values
+            .into_iter()
+            .filter(|value| value.is_finite())
+            .fold(None, |range: Option<DataRange>, value| {
+                Some(match range {
+                    Some(range) => DataRange::new(range.min.min(value), range.max.max(value)),
+                    None => DataRange::new(value, value),
+                })
+            })
+            .unwrap_or(DataRange::new(0.0, 1.0))
+    }
+
+    /// Returns `max - min`.
+    #[inline]
+    pub fn span(self) -> f32 {
+        self.max - self.min
+    }
+
+    /// Returns where `value` lies within the range, `0` being `min` and `1` being `max`.
+    ///
+    /// Empty ranges map everything to `0.5`.
+    pub fn normalize(self, value: f32) -> f32 {
+        if self.span() == 0.0 {
+            0.5
+        } else {
+            (value - self.min) / self.span()
+        }
+    }
+
+    /// Returns the distance between roughly `count` evenly spaced ticks, rounded to 1, 2 or 5 times a power of ten.
+    pub fn tick_step(self, count: usize) -> f32 {
+        let rough = self.span().abs() / count.max(1) as f32;
+        if rough == 0.0 || !rough.is_finite() {
+            return 1.0;
+        }
+        let magnitude = 10f32.powf(rough.log10().floor());
+        let step = match rough / magnitude {
+            fraction if fraction <= 1.0 => 1.0,
+            fraction if fraction <= 2.0 => 2.0,
+            fraction if fraction <= 5.0 => 5.0,
+            _ => 10.0,
+        };
+        step * magnitude
+    }
+
+    /// Extends the range outwards to multiples of the [tick step](DataRange::tick_step).
+    pub fn nice(self, count: usize) -> Self {
+        let step = self.tick_step(count);
+        DataRange::new((self.min / step).floor() * step, (self.max / step).ceil() * step)
+    }
+
+    /// Returns the multiples of the [tick step](DataRange::tick_step) which lie within the range.
+    pub fn ticks(self, count: usize) -> Vec<f32> {
+        let step = self.tick_step(count);
+        let (first, last) = ((self.min / step).ceil() as i64, (self.max / step).floor() as i64);
+        // multiplying (rather than accumulating) the step avoids drift, e.g. 0.30000000000000004.
+        (first..=last).map(|i| i as f32 * step).collect()
+    }
+}
+
+/// Maps data coordinates into a rectangle of the display, with y increasing upwards.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChartArea {
+    pub bounds: Rect,
+    pub x: DataRange,
+    pub y: DataRange,
+}
+
+impl ChartArea {
+    pub fn new(bounds: Rect, x: DataRange, y: DataRange) -> Self {
+        ChartArea { bounds, x, y }
+    }
+
+    /// Returns the horizontal display position of the data value `x`.
+    pub fn map_x(&self, x: f32) -> f32 {
+        self.bounds.min_x() + self.x.normalize(x) * self.bounds.size.width
+    }
+
+    /// Returns the vertical display position of the data value `y`.
+    pub fn map_y(&self, y: f32) -> f32 {
+        self.bounds.max_y() - self.y.normalize(y) * self.bounds.size.height
+    }
+
+    /// Maps a data point to a display point.
+    pub fn map(&self, point: Point) -> Point {
+        Point::new(self.map_x(point.x), self.map_y(point.y))
+    }
+}
+
+/// Reduces `points` (sorted by x) to at most four per pixel column of the chart area; the first, lowest,
+/// highest and last points of each column.
+///
+/// This keeps the drawn line identical to that of every point, including any spikes, while bounding the
+/// number of drawn segments by the width of the chart. Non-finite points are dropped.
+pub fn decimate(points: &[Point], area: &ChartArea) -> Vec<Point> {
+    let mut decimated = Vec::with_capacity(points.len().min(area.bounds.size.width as usize * 4));
+    let mut column: Option<(i64, [usize; 4])> = None;
+
+    let flush = |decimated: &mut Vec<Point>, indices: [usize; 4]| {
+        let mut indices = indices;
+        indices.sort_unstable();
+        let mut last = None;
+        for index in indices.iter().copied() {
+            if last != Some(index) {
+                decimated.push(points[index]);
+                last = Some(index);
+            }
+        }
+    };
+
+    for (index, point) in points.iter().enumerate() {
+        if !(point.x.is_finite() && point.y.is_finite()) {
+            continue;
+        }
+        let x = area.map_x(point.x).floor() as i64;
+        column = Some(match column {
+            Some((current, [first, min, max, _])) if current == x => (
+                current,
+                [
+                    first,
+                    if point.y < points[min].y { index } else { min },
+                    if point.y > points[max].y { index } else { max },
+                    index,
+                ],
+            ),
+            previous => {
+                if let Some((_, indices)) = previous {
+                    flush(&mut decimated, indices);
+                }
+                (x, [index; 4])
+            }
+        });
+    }
+    if let Some((_, indices)) = column {
+        flush(&mut decimated, indices);
+    }
+
+    decimated
+}
+
+/// Draws `points` (sorted by x) as a line, [decimated](decimate) and clipped to the chart area.
+pub fn line_series(points: &[Point], area: &ChartArea, stroke: GraphicsDisplayStroke) -> Picture {
+    let points = decimate(points, area);
+    if points.len() < 2 {
+        return Picture::default();
+    }
+
+    let mut path = VectorPathBuilder::new();
+    path.move_to(area.map(points[0]));
+    for point in &points[1..] {
+        path.line_to(area.map(*point));
+    }
+
+    let mut builder = DisplayListBuilder::new();
+    builder.save();
+    builder.push_rectangle_clip(area.bounds, true);
+    builder.push_path(path.build(), false, GraphicsDisplayPaint::Stroke(stroke), vec![]);
+    builder.restore();
+    Picture::new(builder.build())
+}
+
+/// Spacing of the bars drawn by [`bar_groups`](bar_groups).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BarStyle {
+    /// Fraction (from 0 to 1) of each group's slot left empty, split between both sides.
+    pub group_padding: f32,
+    /// Space between the bars of a group, in pixels.
+    pub bar_gap: f32,
+    /// Corner radius of the bars.
+    pub radius: f32,
+}
+
+impl Default for BarStyle {
+    fn default() -> Self {
+        BarStyle { group_padding: 0.2, bar_gap: 2.0, radius: 0.0 }
+    }
+}
+
+/// Draws groups of bars, the groups spread evenly across the width of the chart area and the bars
+/// extending from zero (or the nearest end of the y range) to their value.
+///
+/// The `i`th bar of every group is filled with `colors[i % colors.len()]`.
+pub fn bar_groups<G: AsRef<[f32]>>(
+    groups: &[G],
+    area: &ChartArea,
+    colors: &[StyleColor],
+    style: BarStyle,
+) -> Picture {
+    if groups.is_empty() || colors.is_empty() {
+        return Picture::default();
+    }
+
+    let slot = area.bounds.size.width / groups.len() as f32;
+    let padding = slot * style.group_padding.clamp(0.0, 1.0);
+    let baseline = area.map_y(0f32.clamp(area.y.min.min(area.y.max), area.y.max.max(area.y.min)));
+
+    let mut builder = DisplayListBuilder::new();
+    for (group_index, group) in groups.iter().enumerate() {
+        let group = group.as_ref();
+        if group.is_empty() {
+            continue;
+        }
+
+        let width = ((slot - padding - style.bar_gap * (group.len() - 1) as f32)
+            / group.len() as f32)
+            .max(0.0);
+        let left = area.bounds.min_x() + slot * group_index as f32 + padding / 2.0;
+
+        for (index, value) in group.iter().enumerate() {
+            if !value.is_finite() {
+                continue;
+            }
+            let x = left + (width + style.bar_gap) * index as f32;
+            let y = area.map_y(*value);
+            let rect =
+                Rect::new(Point::new(x, y.min(baseline)), Size::new(width, (y - baseline).abs()));
+            let paint = GraphicsDisplayPaint::Fill(colors[index % colors.len()].clone());
+            if style.radius > 0.0 {
+                builder.push_round_rectangle(rect, [style.radius; 4], paint, vec![]);
+            } else {
+                builder.push_rectangle(rect, paint, vec![]);
+            }
+        }
+    }
+    Picture::new(builder.build())
+}
+
+/// A labelled position along an axis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tick {
+    pub value: f32,
+    pub label: String,
+}
+
+/// Creates ticks at `values`, labelled by `label`.
+pub fn ticks(values: &[f32], label: impl Fn(f32) -> String) -> Vec<Tick> {
+    values.iter().map(|&value| Tick { value, label: label(value) }).collect()
+}
+
+/// Appearance of the axes drawn by [`x_axis`](x_axis) and [`y_axis`](y_axis).
+#[derive(Debug, Clone)]
+pub struct AxisStyle {
+    pub font: ResourceReference,
+    pub font_info: FontInfo,
+    pub text_size: f32,
+    pub text_color: StyleColor,
+    /// Stroke of the axis line and tick marks.
+    pub stroke: GraphicsDisplayStroke,
+    /// Length of the tick marks, outwards from the axis.
+    pub tick_length: f32,
+    /// Space between the tick marks and their labels, and the minimum space between labels.
+    pub label_gap: f32,
+}
+
+impl AxisStyle {
+    fn label(&self, text: &str) -> Result<(TextDisplayItem, Size), FontError> {
+        let mut item = TextDisplayItem::new(
+            text,
+            self.font,
+            self.font_info.clone(),
+            self.text_size,
+            Point::zero(),
+            self.text_color.clone(),
+        );
+        item.set_top_left(Point::zero());
+        let size = item.bounds()?.size;
+        Ok((item, size))
+    }
+}
+
+/// Pushes the labels which don't overlap the previously placed label, in order.
+fn push_labels(
+    builder: &mut DisplayListBuilder,
+    labels: impl IntoIterator<Item = (TextDisplayItem, Rect)>,
+    gap: f32,
+) {
+    let mut previous: Option<Rect> = None;
+    for (mut item, rect) in labels {
+        if previous.is_some_and(|previous| previous.inflate(gap, gap).intersects(&rect)) {
+            continue;
+        }
+        item.set_top_left(rect.origin);
+        builder.push_text(item, vec![]);
+        previous = Some(rect);
+    }
+}
+
+/// Draws a horizontal axis along the bottom of the chart area, with labels centered below the ticks.
+///
+/// Ticks outside the x range are skipped, as are labels which would overlap the label of an earlier tick.
+pub fn x_axis(area: &ChartArea, ticks: &[Tick], style: &AxisStyle) -> Result<Picture, FontError> {
+    let y = area.bounds.max_y();
+    let mut builder = DisplayListBuilder::new();
+    builder.push_line(
+        Point::new(area.bounds.min_x(), y),
+        Point::new(area.bounds.max_x(), y),
+        style.stroke.clone(),
+        vec![],
+    );
+
+    let mut labels = Vec::with_capacity(ticks.len());
+    for tick in ticks.iter().filter(|tick| in_range(area.x, tick.value)) {
+        let x = area.map_x(tick.value);
+        builder.push_line(
+            Point::new(x, y),
+            Point::new(x, y + style.tick_length),
+            style.stroke.clone(),
+            vec![],
+        );
+
+        let (item, size) = style.label(&tick.label)?;
+        let origin = Point::new(x - size.width / 2.0, y + style.tick_length + style.label_gap);
+        labels.push((item, Rect::new(origin, size)));
+    }
+    labels.sort_by(|(_, a), (_, b)| a.origin.x.total_cmp(&b.origin.x));
+    push_labels(&mut builder, labels, style.label_gap);
+
+    Ok(Picture::new(builder.build()))
+}
+
+/// Draws a vertical axis along the left of the chart area, with labels right-aligned and vertically centered
+/// beside the ticks.
+///
+/// Ticks outside the y range are skipped, as are labels which would overlap the label of an earlier tick.
+pub fn y_axis(area: &ChartArea, ticks: &[Tick], style: &AxisStyle) -> Result<Picture, FontError> {
+    let x = area.bounds.min_x();
+    let mut builder = DisplayListBuilder::new();
+    builder.push_line(
+        Point::new(x, area.bounds.min_y()),
+        Point::new(x, area.bounds.max_y()),
+        style.stroke.clone(),
+        vec![],
+    );
+
+    let mut labels = Vec::with_capacity(ticks.len());
+    for tick in ticks.iter().filter(|tick| in_range(area.y, tick.value)) {
+        let y = area.map_y(tick.value);
+        builder.push_line(
+            Point::new(x - style.tick_length, y),
+            Point::new(x, y),
+            style.stroke.clone(),
+            vec![],
+        );
+
+        let (item, size) = style.label(&tick.label)?;
+        let origin =
+            Point::new(x - style.tick_length - style.label_gap - size.width, y - size.height / 2.0);
+        labels.push((item, Rect::new(origin, size)));
+    }
+    // bottom to top, so the labels at the origin take precedence.
+    labels.sort_by(|(_, a), (_, b)| b.origin.y.total_cmp(&a.origin.y));
+    push_labels(&mut builder, labels, style.label_gap);
+
+    Ok(Picture::new(builder.build()))
+}
+
+fn in_range(range: DataRange, value: f32) -> bool {
+    let epsilon = range.span().abs() * 1e-4;
+    value >= range.min.min(range.max) - epsilon && value <= range.max.max(range.min) + epsilon
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::display::picture::IdentityResolver};
+
+    fn area() -> ChartArea {
+        ChartArea::new(
+            Rect::new(Point::new(10.0, 10.0), Size::new(100.0, 50.0)),
+            DataRange::new(0.0, 10.0),
+            DataRange::new(-5.0, 5.0),
+        )
+    }
+
+    #[test]
+    fn test_ranges_and_decimation() {
+        let range = DataRange::of(vec![0.3, 9.2, f32::NAN, 4.0]);
+        assert_eq!(range, DataRange::new(0.3, 9.2));
+        assert_eq!(range.tick_step(5), 2.0);
+        assert_eq!(range.nice(5), DataRange::new(0.0, 10.0));
+        assert_eq!(range.ticks(5), vec![2.0, 4.0, 6.0, 8.0]);
+        assert_eq!(DataRange::new(0.0, 1.0).ticks(10).len(), 11);
+
+        let area = area();
+        assert_eq!(area.map(Point::new(0.0, 5.0)), Point::new(10.0, 10.0));
+        assert_eq!(area.map(Point::new(10.0, -5.0)), Point::new(110.0, 60.0));
+
+        // 10,000 points over 100 columns, with a spike in the middle.
+        let points: Vec<_> = (0..10_000)
+            .map(|i| Point::new(i as f32 / 1000.0, if i == 5_050 { 5.0 } else { 0.0 }))
+            .collect();
+        let decimated = decimate(&points, &area);
+        assert!(decimated.len() <= 101 * 4);
+        assert!(decimated.contains(&points[5_050]));
+        assert_eq!(decimated.first(), points.first());
+        assert_eq!(decimated.last(), points.last());
+        assert!(decimated.windows(2).all(|pair| pair[0].x <= pair[1].x));
+
+        let line =
+            line_series(&points, &area, Default::default()).flatten(None, &mut IdentityResolver);
+        assert!(matches!(
+            line[..],
+            [
+                DisplayCommand::Save,
+                DisplayCommand::Clip(..),
+                DisplayCommand::Item(DisplayItem::Graphics(GraphicsDisplayItem::Path { .. }), _),
+                DisplayCommand::Restore
+            ]
+        ));
+        assert!(line_series(&points[..1], &area, Default::default()).is_empty());
+
+        let bars = bar_groups(
+            &[vec![2.0, -2.0], vec![5.0, 0.0]],
+            &area,
+            &[Color::new(1.0, 0.0, 0.0, 1.0).into()],
+            BarStyle { group_padding: 0.0, bar_gap: 0.0, radius: 0.0 },
+        )
+        .flatten(None, &mut IdentityResolver);
+        let rects: Vec<_> = bars
+            .iter()
+            .map(|command| match command {
+                DisplayCommand::Item(
+                    DisplayItem::Graphics(GraphicsDisplayItem::Rectangle { rect, .. }),
+                    _,
+                ) => *rect,
+                _ => panic!("expected a rectangle"),
+            })
+            .collect();
+        assert_eq!(
+            rects,
+            vec![
+                Rect::new(Point::new(10.0, 25.0), Size::new(25.0, 10.0)),
+                Rect::new(Point::new(35.0, 35.0), Size::new(25.0, 10.0)),
+                Rect::new(Point::new(60.0, 10.0), Size::new(25.0, 25.0)),
+                Rect::new(Point::new(85.0, 35.0), Size::new(25.0, 0.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_axis_labels() {
+        let style = AxisStyle {
+            font: ResourceReference::Font(0),
+            font_info: test_font(),
+            text_size: 12.0,
+            text_color: Color::new(0.0, 0.0, 0.0, 1.0).into(),
+            stroke: Default::default(),
+            tick_length: 4.0,
+            label_gap: 2.0,
+        };
+        let area = area();
+        let texts = |picture: Picture| -> Vec<TextDisplayItem> {
+            picture
+                .flatten(None, &mut IdentityResolver)
+                .into_iter()
+                .filter_map(|command| match command {
+                    DisplayCommand::Item(DisplayItem::Text(text), _) => Some(text),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        // labels fit at every other unit, but not at every tenth.
+        let sparse =
+            texts(x_axis(&area, &ticks(&area.x.ticks(5), |v| format!("{}", v)), &style).unwrap());
+        assert_eq!(sparse.len(), 6);
+        let first = sparse[0].bounds().unwrap();
+        assert!((first.center().x - 10.0).abs() < 0.01);
+        assert!(first.min_y() >= 60.0 + 4.0 + 2.0 - 0.01);
+
+        let dense = ticks(&area.x.ticks(100), |v| format!("{:.1}", v));
+        assert_eq!(dense.len(), 101);
+        let dense = texts(x_axis(&area, &dense, &style).unwrap());
+        assert!(dense.len() < 101);
+        for pair in dense.windows(2) {
+            assert!(!pair[0].bounds().unwrap().intersects(&pair[1].bounds().unwrap()));
+        }
+
+        let y_labels = texts(
+            y_axis(&area, &ticks(&[-5.0, 0.0, 5.0, 100.0], |v| format!("{}", v)), &style).unwrap(),
+        );
+        assert_eq!(y_labels.len(), 3);
+        for label in &y_labels {
+            assert!((label.bounds().unwrap().max_x() - (10.0 - 4.0 - 2.0)).abs() < 0.01);
+        }
+    }
+}
@@ -0,0 +1,417 @@
+//! Rendering widgets into sub-displays which are composited into a parent display.
+//!
+//! This implements the pattern described in [`Widget::draw`](crate::widget::Widget::draw): a parent widget uses
+//! [`CompositeAux`](CompositeAux) as its `GraphicalAux`, hands a [`SubDisplay`](SubDisplay) to each child to draw
+//! into, and then [`composite`](CompositeAux::composite)s every sub-display into the real display, each as a
+//! single command group drawn into an offscreen layer (i.e. a texture, in GPU backends) with its own
+//! opacity and transform.
+//!
+//! ```ignore
+//! fn draw(&mut self, display: &mut dyn GraphicsDisplay, aux: &mut CompositeAux) {
+//!     let layer = aux.layer(self.sidebar_layer);
+//!     layer.opacity = self.sidebar_fade;
+//!     self.sidebar.draw(layer, &mut ());
+//!
+//!     aux.composite(display, ZOrder(0)).unwrap();
+//! }
+//! ```
+
+use {
+    super::{recording::RecordingDisplay, *},
+    crate::widget::WidgetTransform,
+};
+
+/// A display which records the command groups of a widget tree, to be composited into a parent display.
+///
+/// It has the same retained semantics as a regular display (z-ordering, maintenance and eviction).
+/// Resources can't be created through a sub-display; resources of the parent display are referenced directly.
+pub struct SubDisplay {
+    inner: RecordingDisplay,
+    size: Size,
+    /// Opacity of the entire sub-display, from `0.0` to `1.0`.
+    pub opacity: f32,
+    /// Placement of the sub-display within the parent display.
+    pub transform: WidgetTransform,
+    dirty: bool,
+    composited: Option<(f32, WidgetTransform, Size)>,
+    handle: Option<CommandGroupHandle>,
+}
+
+impl SubDisplay {
+    /// Creates a new, empty sub-display of a given size, placed at the origin of the parent display.
+    pub fn new(size: Size) -> Self {
+        SubDisplay {
+            inner: RecordingDisplay::new((size.width as _, size.height as _)),
+            size,
+            opacity: 1.0,
+            transform: WidgetTransform::identity(),
+            dirty: true,
+            composited: None,
+            handle: None,
+        }
+    }
+
+    /// Returns the size of the sub-display, which its contents are clipped to.
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// Returns `true` if any command group changed since the last composite.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Returns the command group of the sub-display within the parent display, if it was composited.
+    pub fn handle(&self) -> Option<CommandGroupHandle> {
+        self.handle
+    }
+
+    /// Presents the command groups into a single list of commands, drawn into a layer
+    /// which is clipped, faded and transformed.
+    fn composite_commands(&mut self) -> Result<(Vec<DisplayCommand>, bool), error::DisplayError> {
+        let info = self.inner.present(None)?;
+        let changed = self.dirty
+            || info.evicted > 0
+            || self.composited != Some((self.opacity, self.transform, self.size));
+
+        let mut commands = Vec::with_capacity(self.inner.presented().len() + 6);
+        commands.push(DisplayCommand::Save);
+        commands.push(DisplayCommand::Translate(self.transform.translation));
+        commands.push(DisplayCommand::Scale(self.transform.scale));
+        commands.push(DisplayCommand::Clip(
+            DisplayClip::Rectangle { rect: Rect::new(Point::zero(), self.size), antialias: true },
+            ClipOp::Intersect,
+        ));
+        commands.push(DisplayCommand::SaveLayer(self.opacity, BlendMode::Normal));
+        commands.extend(self.inner.presented().iter().cloned());
+        commands.push(DisplayCommand::Restore);
+        commands.push(DisplayCommand::Restore);
+        Ok((commands, changed))
+    }
+}
+
+impl GraphicsDisplay for SubDisplay {
+    fn resize(&mut self, size: (u32, u32)) -> Result<(), error::DisplayError> {
+        self.size = Size::new(size.0 as _, size.1 as _);
+        self.inner.resize(size)
+    }
+
+    fn new_resource(
+        &mut self,
+        _descriptor: ResourceDescriptor,
+    ) -> Result<ResourceReference, error::ResourceError> {
+        Err(error::ResourceError::InternalError(
+            "resources must be created in the parent display of a sub-display".into(),
+        ))
+    }
+
+    fn replace_resource(
+        &mut self,
+        _reference: ResourceReference,
+        _descriptor: ResourceDescriptor,
+    ) -> Result<(), error::DisplayError> {
+        Err(error::ResourceError::InternalError(
+            "resources must be replaced in the parent display of a sub-display".into(),
+        )
+        .into())
+    }
+
+    fn update_resource(
+        &mut self,
+        _reference: ResourceReference,
+        _data: ImageData,
+    ) -> Result<(), error::DisplayError> {
+        Err(error::ResourceError::InternalError(
+            "resources must be updated in the parent display of a sub-display".into(),
+        )
+        .into())
+    }
+
+    fn remove_resource(&mut self, _reference: ResourceReference) {}
+
+    fn push_command_group(
+        &mut self,
+        commands: &[DisplayCommand],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        needs_maintain: Option<bool>,
+    ) -> Result<CommandGroupHandle, error::DisplayError> {
+        self.dirty = true;
+        self.inner.push_command_group(commands, z_order, protected, needs_maintain)
+    }
+
+    fn get_command_group(&self, handle: CommandGroupHandle) -> Option<&[DisplayCommand]> {
+        self.inner.get_command_group(handle)
+    }
+
+    fn modify_command_group(
+        &mut self,
+        handle: CommandGroupHandle,
+        commands: &[DisplayCommand],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        needs_maintain: Option<bool>,
+    ) -> Result<(), error::DisplayError> {
+        self.dirty = true;
+        self.inner.modify_command_group(handle, commands, z_order, protected, needs_maintain)
+    }
+
+    fn remove_command_group(&mut self, handle: CommandGroupHandle) -> Option<Vec<DisplayCommand>> {
+        self.dirty = true;
+        self.inner.remove_command_group(handle)
+    }
+
+    fn label_command_group(&mut self, handle: CommandGroupHandle, label: Option<&'static str>) {
+        self.inner.label_command_group(handle, label)
+    }
+
+    fn debug_dump(&self) -> Vec<CommandGroupInfo> {
+        self.inner.debug_dump()
+    }
+
+    fn maintain_command_group(&mut self, handle: CommandGroupHandle) {
+        self.inner.maintain_command_group(handle)
+    }
+
+    fn begin_update(&mut self) {
+        self.inner.begin_update()
+    }
+
+    fn commit_update(&mut self) -> Result<(), error::DisplayError> {
+        self.inner.commit_update()
+    }
+
+    fn set_eviction_policy(&mut self, policy: EvictionPolicy) -> Result<(), error::DisplayError> {
+        self.inner.set_eviction_policy(policy)
+    }
+
+    fn set_eviction_callback(
+        &mut self,
+        callback: Option<EvictionCallback>,
+    ) -> Result<(), error::DisplayError> {
+        self.inner.set_eviction_callback(callback)
+    }
+
+    fn supports_backdrop_filter(&self) -> bool {
+        self.inner.supports_backdrop_filter()
+    }
+
+    fn max_texture_size(&self) -> Option<u32> {
+        self.inner.max_texture_size()
+    }
+
+    fn capabilities(&self) -> DisplayCapabilities {
+        self.inner.capabilities()
+    }
+
+    fn before_exit(&mut self) {}
+
+    /// Sub-displays are presented through [`composite`](CompositeAux::composite); this does nothing.
+    fn present(&mut self, _cull: Option<Rect>) -> Result<PresentInfo, error::DisplayError> {
+        Ok(Default::default())
+    }
+
+    /// Like [`present`](GraphicsDisplay::present), this does nothing besides invoking `callback` immediately.
+    fn present_with_callback(
+        &mut self,
+        _cull: Option<Rect>,
+        callback: Box<dyn FnOnce(PresentFeedback)>,
+    ) -> Result<PresentInfo, error::DisplayError> {
+        let info = PresentInfo::default();
+        callback(PresentFeedback { info, submitted: Instant::now(), displayed: None });
+        Ok(info)
+    }
+
+    fn read_pixels(&mut self, rect: PixelRect) -> Result<RasterImage, error::DisplayError> {
+        self.inner.read_pixels(rect)
+    }
+
+    fn set_hole_callback(
+        &mut self,
+        id: u64,
+        callback: Option<HoleCallback>,
+    ) -> Result<(), error::DisplayError> {
+        self.inner.set_hole_callback(id, callback)
+    }
+
+    fn set_virtual_resolution(
+        &mut self,
+        resolution: Option<VirtualResolution>,
+    ) -> Result<(), error::DisplayError> {
+        self.inner.set_virtual_resolution(resolution)
+    }
+
+    fn set_color_correction(
+        &mut self,
+        correction: ColorCorrection,
+    ) -> Result<(), error::DisplayError> {
+        self.inner.set_color_correction(correction)
+    }
+}
+
+/// Identifies a layer of a [`CompositeAux`](CompositeAux).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LayerId(u64);
+
+/// Graphical auxiliary which owns a sub-display per layer and composites them into a parent display.
+#[derive(Default)]
+pub struct CompositeAux {
+    layers: Vec<(LayerId, SubDisplay)>,
+    next_id: u64,
+}
+
+impl CompositeAux {
+    /// Creates a compositor without any layers.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a layer on top of the existing layers.
+    pub fn add_layer(&mut self, sub_display: SubDisplay) -> LayerId {
+        let id = LayerId(self.next_id);
+        self.next_id += 1;
+        self.layers.push((id, sub_display));
+        id
+    }
+
+    /// Removes a layer, along with its command group in `parent`.
+    pub fn remove_layer(
+        &mut self,
+        id: LayerId,
+        parent: &mut dyn GraphicsDisplay,
+    ) -> Option<SubDisplay> {
+        let index = self.layers.iter().position(|(layer, _)| *layer == id)?;
+        let (_, mut sub_display) = self.layers.remove(index);
+        if let Some(handle) = sub_display.handle.take() {
+            parent.remove_command_group(handle);
+        }
+        sub_display.composited = None;
+        Some(sub_display)
+    }
+
+    /// Returns the sub-display of a layer, to draw into.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the layer doesn't exist.
+    pub fn layer(&mut self, id: LayerId) -> &mut SubDisplay {
+        self.get_mut(id).expect("no such layer")
+    }
+
+    /// Returns the sub-display of a layer, if it exists.
+    pub fn get_mut(&mut self, id: LayerId) -> Option<&mut SubDisplay> {
+        self.layers.iter_mut().find(|(layer, _)| *layer == id).map(|(_, sub_display)| sub_display)
+    }
+
+    /// Composites every layer into `parent`, in order, starting at `z_order`.
+    ///
+    /// Layers which didn't change since the last composite are only maintained in `parent`.
+    pub fn composite(
+        &mut self,
+        parent: &mut dyn GraphicsDisplay,
+        z_order: ZOrder,
+    ) -> Result<(), error::DisplayError> {
+        for (index, (_, sub_display)) in self.layers.iter_mut().enumerate() {
+            let (commands, changed) = sub_display.composite_commands()?;
+            let z_order = ZOrder(z_order.0.saturating_add(index as i32));
+            match sub_display.handle {
+                Some(handle) if !changed => parent.maintain_command_group(handle),
+                _ => {
+                    ok_or_push(&mut sub_display.handle, parent, &commands, z_order, None, None)?;
+                    sub_display.dirty = false;
+                    sub_display.composited =
+                        Some((sub_display.opacity, sub_display.transform, sub_display.size));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect_commands(x: f32) -> Vec<DisplayCommand> {
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(
+            Rect::new(Point::new(x, 0.0), Size::new(10.0, 10.0)),
+            GraphicsDisplayPaint::Fill(Color::new(1.0, 0.0, 0.0, 1.0).into()),
+            vec![],
+        );
+        builder.build()
+    }
+
+    #[test]
+    fn test_composite_aux() {
+        let mut parent = RecordingDisplay::default();
+        let mut aux = CompositeAux::new();
+        let a = aux.add_layer(SubDisplay::new(Size::new(100.0, 100.0)));
+        let b = aux.add_layer(SubDisplay::new(Size::new(50.0, 50.0)));
+
+        let mut group_a = CommandGroup::new();
+        group_a.push(aux.layer(a), &rect_commands(0.0), ZOrder(0), None, None).unwrap();
+        aux.layer(b).opacity = 0.5;
+        aux.layer(b).transform = WidgetTransform::from_translation(Vector::new(100.0, 0.0));
+        aux.layer(b).push_command_group(&rect_commands(5.0), ZOrder(0), None, Some(false)).unwrap();
+
+        aux.composite(&mut parent, ZOrder(10)).unwrap();
+        parent.present(None).unwrap();
+        assert_eq!(parent.command_group_count(), 2);
+        assert!(!aux.layer(a).is_dirty());
+        let handle_b = aux.layer(b).handle().unwrap();
+        let commands = parent.get_command_group(handle_b).unwrap();
+        assert_eq!(commands.len(), 8);
+        validate::validate_display_list(commands).unwrap();
+        assert!(matches!(commands[4], DisplayCommand::SaveLayer(opacity, _) if opacity == 0.5));
+
+        // unchanged layers are only maintained; `a` keeps its content by being maintained.
+        group_a.push(aux.layer(a), &rect_commands(0.0), ZOrder(0), None, None).unwrap();
+        aux.composite(&mut parent, ZOrder(10)).unwrap();
+        parent.present(None).unwrap();
+        assert_eq!(parent.command_group_count(), 2);
+        assert_eq!(parent.presented().len(), 16);
+
+        // `a` isn't drawn (nor maintained) anymore, so its content is evicted.
+        aux.composite(&mut parent, ZOrder(10)).unwrap();
+        parent.present(None).unwrap();
+        assert_eq!(parent.presented().len(), 15);
+
+        assert!(aux.remove_layer(b, &mut parent).is_some());
+        assert!(parent.get_command_group(handle_b).is_none());
+        assert!(aux.get_mut(b).is_none());
+    }
+
+    #[test]
+    fn test_sub_display_forwarding() {
+        use std::{cell::Cell, rc::Rc};
+
+        let mut sub_display = SubDisplay::new(Size::new(100.0, 100.0));
+        let resolution = VirtualResolution::new((50, 50));
+        sub_display.set_virtual_resolution(Some(resolution)).unwrap();
+        sub_display
+            .set_color_correction(ColorCorrection { gamma: 2.0, ..Default::default() })
+            .unwrap();
+        sub_display.set_hole_callback(1, Some(Box::new(|_| {}))).unwrap();
+        assert_eq!(sub_display.inner.virtual_resolution(), Some(resolution));
+        assert_eq!(sub_display.inner.color_correction().gamma, 2.0);
+        assert!(sub_display.inner.has_hole_callback(1));
+        sub_display.set_hole_callback(1, None).unwrap();
+        assert!(!sub_display.inner.has_hole_callback(1));
+
+        let presented = Rc::new(Cell::new(false));
+        sub_display
+            .present_with_callback(None, {
+                let presented = presented.clone();
+                Box::new(move |_| presented.set(true))
+            })
+            .unwrap();
+        assert!(presented.get());
+        assert!(sub_display
+            .update_resource(
+                ResourceReference::Image(0),
+                ImageData::Encoded(ResourceData::Data(SharedData::Static(&[])))
+            )
+            .is_err());
+    }
+}
@@ -0,0 +1,839 @@
+//! High contrast mode, which raises the contrast of text and strokes against what's behind them.
+//!
+//! A [`HighContrastDisplay`](HighContrastDisplay) wraps a display and rewrites the colors of every command group
+//! pushed through it, so that widgets need no knowledge of the mode. Since the original command groups are kept,
+//! the mode can be toggled at runtime without any widget repainting:
+//!
+//! ```ignore
+//! let mut display = HighContrastDisplay::new(display, HighContrastTransform::default());
+//! // ... later, as per the accessibility settings of the platform:
+//! display.set_enabled(true)?;
+//! ```
+//!
+//! Backgrounds are detected within each command group, as the topmost opaque filled rectangle (or clear) drawn
+//! before an item and covering it entirely; anything else is assumed to be drawn on
+//! [`background`](HighContrastTransform::background).
+
+use {super::*, std::collections::HashMap};
+
+type Transform = euclid::Transform2D<f32, LogicalPixel, LogicalPixel>;
+
+/// Returns the relative luminance of an sRGB color, as defined by WCAG (ignoring alpha).
+pub fn relative_luminance(color: Color) -> f32 {
+    let linear = |c: f32| {
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * linear(color.red) + 0.7152 * linear(color.green) + 0.0722 * linear(color.blue)
+}
+
+/// Returns the WCAG contrast ratio between two sRGB colors (ignoring alpha), from `1.0` to `21.0`.
+pub fn contrast_ratio(a: Color, b: Color) -> f32 {
+    let (a, b) = (relative_luminance(a), relative_luminance(b));
+    (a.max(b) + 0.05) / (a.min(b) + 0.05)
+}
+
+fn mix(a: Color, b: Color, t: f32) -> Color {
+    Color::new(
+        a.red + (b.red - a.red) * t,
+        a.green + (b.green - a.green) * t,
+        a.blue + (b.blue - a.blue) * t,
+        a.alpha + (b.alpha - a.alpha) * t,
+    )
+}
+
+/// Rewrites display lists so that text and strokes meet a minimum contrast ratio against their backgrounds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HighContrastTransform {
+    /// Minimum contrast ratio, as defined by WCAG; `4.5` and `7.0` are the AA and AAA levels for regular text.
+    pub min_ratio: f32,
+    /// Background assumed behind items without a detected background, i.e. the window background.
+    pub background: Color,
+}
+
+impl Default for HighContrastTransform {
+    fn default() -> Self {
+        HighContrastTransform { min_ratio: 7.0, background: Color::new(1.0, 1.0, 1.0, 1.0) }
+    }
+}
+
+impl HighContrastTransform {
+    /// Returns `foreground` (drawn over the opaque `background`) adjusted to meet the minimum contrast ratio.
+    ///
+    /// The color is moved towards black or white, whichever contrasts more with the background, only as far as
+    /// needed, so that hues are kept where possible. The result is opaque.
+    pub fn adjust(&self, foreground: Color, background: Color) -> Color {
+        let background = Color::new(background.red, background.green, background.blue, 1.0);
+        let foreground = mix(background, foreground, foreground.alpha);
+        if contrast_ratio(foreground, background) >= self.min_ratio {
+            return foreground;
+        }
+
+        let black = Color::new(0.0, 0.0, 0.0, 1.0);
+        let white = Color::new(1.0, 1.0, 1.0, 1.0);
+        let target = if contrast_ratio(black, background) > contrast_ratio(white, background) {
+            black
+        } else {
+            white
+        };
+
+        // contrast increases monotonically towards the target, so the least change is found by bisection.
+        let mut range = 0.0..1.0;
+        for _ in 0..16 {
+            let t = (range.start + range.end) / 2.0;
+            if contrast_ratio(mix(foreground, target, t), background) >= self.min_ratio {
+                range.end = t;
+            } else {
+                range.start = t;
+            }
+        }
+        mix(foreground, target, range.end)
+    }
+
+    fn adjust_style(&self, style: &StyleColor, background: Color) -> StyleColor {
+        match style {
+            StyleColor::Color(color) => StyleColor::Color(self.adjust(*color, background)),
+            StyleColor::TaggedColor(color, space) => {
+                StyleColor::Color(self.adjust(space.to_srgb(*color), background))
+            }
+            StyleColor::LinearGradient(gradient) => {
+                StyleColor::LinearGradient(self.adjust_gradient(gradient, background))
+            }
+            StyleColor::RadialGradient(gradient) => {
+                StyleColor::RadialGradient(self.adjust_gradient(gradient, background))
+            }
+        }
+    }
+
+    fn adjust_gradient(&self, gradient: &Gradient, background: Color) -> Gradient {
+        Gradient {
+            stops: gradient
+                .stops
+                .iter()
+                .map(|(offset, color)| {
+                    (*offset, self.adjust(gradient.color_space.to_srgb(*color), background))
+                })
+                .collect(),
+            color_space: ColorSpace::Srgb,
+            ..gradient.clone()
+        }
+    }
+
+    fn adjust_paint(
+        &self,
+        paint: &GraphicsDisplayPaint,
+        background: Color,
+    ) -> GraphicsDisplayPaint {
+        match paint {
+            GraphicsDisplayPaint::Fill(style) => {
+                GraphicsDisplayPaint::Fill(self.adjust_style(style, background))
+            }
+            GraphicsDisplayPaint::Stroke(stroke) => {
+                GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    color: self.adjust_style(&stroke.color, background),
+                    ..stroke.clone()
+                })
+            }
+            GraphicsDisplayPaint::Blend(inner, mode) => {
+                GraphicsDisplayPaint::Blend(Box::new(self.adjust_paint(inner, background)), *mode)
+            }
+        }
+    }
+
+    /// Returns the item with its foreground colors adjusted against `background`, or `None` for images, meshes and
+    /// fills other than paths, which are left as is.
+    fn adjust_item(&self, item: &DisplayItem, background: Color) -> Option<DisplayItem> {
+        Some(match item {
+            DisplayItem::Text(text) => DisplayItem::Text(TextDisplayItem {
+                color: self.adjust_style(&text.color, background),
+                ..text.clone()
+            }),
+            DisplayItem::TextBlob(blob) => DisplayItem::TextBlob(TextBlobDisplayItem {
+                color: self.adjust_style(&blob.color, background),
+                ..blob.clone()
+            }),
+            DisplayItem::Graphics(GraphicsDisplayItem::Line { a, b, stroke }) => {
+                DisplayItem::Graphics(GraphicsDisplayItem::Line {
+                    a: *a,
+                    b: *b,
+                    stroke: GraphicsDisplayStroke {
+                        color: self.adjust_style(&stroke.color, background),
+                        ..stroke.clone()
+                    },
+                })
+            }
+            // filled paths are most often icons, whereas other fills are most often backgrounds themselves.
+            DisplayItem::Graphics(GraphicsDisplayItem::Path { path, is_closed, paint }) => {
+                DisplayItem::Graphics(GraphicsDisplayItem::Path {
+                    path: path.clone(),
+                    is_closed: *is_closed,
+                    paint: self.adjust_paint(paint, background),
+                })
+            }
+            DisplayItem::Graphics(item) => {
+                let paint = match item {
+                    GraphicsDisplayItem::Rectangle { paint, .. }
+                    | GraphicsDisplayItem::RoundRectangle { paint, .. }
+                    | GraphicsDisplayItem::Ellipse { paint, .. } => paint,
+                    _ => return None,
+                };
+                let stroke = match paint {
+                    GraphicsDisplayPaint::Stroke(_) => self.adjust_paint(paint, background),
+                    _ => return None,
+                };
+                DisplayItem::Graphics(match item.clone() {
+                    GraphicsDisplayItem::Rectangle { rect, .. } => {
+                        GraphicsDisplayItem::Rectangle { rect, paint: stroke }
+                    }
+                    GraphicsDisplayItem::RoundRectangle { rect, radii, .. } => {
+                        GraphicsDisplayItem::RoundRectangle { rect, radii, paint: stroke }
+                    }
+                    GraphicsDisplayItem::Ellipse { center, radii, .. } => {
+                        GraphicsDisplayItem::Ellipse { center, radii, paint: stroke }
+                    }
+                    _ => return None,
+                })
+            }
+        })
+    }
+
+    /// Returns `commands` with the colors of text and strokes adjusted against their detected backgrounds.
+    pub fn apply(&self, commands: &[DisplayCommand]) -> Vec<DisplayCommand> {
+        // backgrounds are kept in the space of the display list, along with the length of the list when saving,
+        // so that backgrounds drawn within a saved state (e.g. clipped) don't outlive it.
+        let mut backgrounds: Vec<(Rect, Color)> = Vec::new();
+        let mut transform = Transform::identity();
+        let mut stack: Vec<(Transform, usize)> = Vec::new();
+        let everything =
+            Rect::new(Point::new(f32::MIN, f32::MIN) / 2.0, Size::new(f32::MAX, f32::MAX));
+
+        commands
+            .iter()
+            .map(|command| match command {
+                DisplayCommand::Item(item, filters) => {
+                    let bounds = item.bounds().ok().map(|bounds| transform.transform_rect(&bounds));
+                    let background = bounds.and_then(|bounds| {
+                        backgrounds.iter().rev().find(|(rect, _)| rect.contains_rect(&bounds))
+                    });
+                    let background = background.map_or(self.background, |(_, color)| *color);
+
+                    if let Some(item) = self.adjust_item(item, background) {
+                        return DisplayCommand::Item(item, filters.clone());
+                    }
+                    if let (
+                        DisplayItem::Graphics(
+                            GraphicsDisplayItem::Rectangle {
+                                paint: GraphicsDisplayPaint::Fill(StyleColor::Color(color)),
+                                ..
+                            }
+                            | GraphicsDisplayItem::RoundRectangle {
+                                paint: GraphicsDisplayPaint::Fill(StyleColor::Color(color)),
+                                ..
+                            },
+                        ),
+                        Some(bounds),
+                    ) = (item, bounds)
+                    {
+                        // rounded corners are small enough not to matter for the contrast of the contents.
+                        if color.alpha >= 1.0 && filters.is_empty() {
+                            backgrounds.push((bounds, *color));
+                        }
+                    }
+                    command.clone()
+                }
+                DisplayCommand::Clear(color) => {
+                    if color.alpha >= 1.0 {
+                        backgrounds.push((everything, *color));
+                    }
+                    command.clone()
+                }
+                DisplayCommand::Save | DisplayCommand::SaveLayer(..) => {
+                    stack.push((transform, backgrounds.len()));
+                    command.clone()
+                }
+                DisplayCommand::Restore => {
+                    if let Some((saved, len)) = stack.pop() {
+                        transform = saved;
+                        backgrounds.truncate(len);
+                    }
+                    command.clone()
+                }
+                DisplayCommand::Translate(v) => {
+                    transform = transform.pre_translate(*v);
+                    command.clone()
+                }
+                DisplayCommand::Scale(v) => {
+                    transform = transform.pre_scale(v.x, v.y);
+                    command.clone()
+                }
+                DisplayCommand::Rotate(angle) => {
+                    transform = transform.pre_rotate(*angle);
+                    command.clone()
+                }
+                _ => command.clone(),
+            })
+            .collect()
+    }
+}
+
+struct OriginalGroup {
+    commands: Vec<DisplayCommand>,
+    z_order: ZOrder,
+    protected: Option<bool>,
+    needs_maintain: Option<bool>,
+}
+
+impl OriginalGroup {
+    /// Replaces the command group in `display` with the original commands, transformed if `enabled`.
+    fn rewrite(
+        &self,
+        display: &mut impl GraphicsDisplay,
+        handle: CommandGroupHandle,
+        enabled: bool,
+        transform: HighContrastTransform,
+    ) -> Result<(), error::DisplayError> {
+        let commands =
+            if enabled { transform.apply(&self.commands) } else { self.commands.clone() };
+        display.modify_command_group(
+            handle,
+            &commands,
+            self.z_order,
+            self.protected,
+            self.needs_maintain,
+        )
+    }
+}
+
+/// Display which applies a [`HighContrastTransform`](HighContrastTransform) to every command group pushed through it
+/// while enabled.
+pub struct HighContrastDisplay<D: GraphicsDisplay> {
+    display: D,
+    transform: HighContrastTransform,
+    enabled: bool,
+    originals: HashMap<CommandGroupHandle, OriginalGroup>,
+    /// Whether an update was begun through this display and not yet committed.
+    updating: bool,
+}
+
+impl<D: GraphicsDisplay> HighContrastDisplay<D> {
+    /// Wraps `display`, with high contrast mode initially disabled.
+    pub fn new(display: D, transform: HighContrastTransform) -> Self {
+        HighContrastDisplay {
+            display,
+            transform,
+            enabled: false,
+            originals: HashMap::new(),
+            updating: false,
+        }
+    }
+
+    /// Returns the wrapped display.
+    #[inline]
+    pub fn inner(&self) -> &D {
+        &self.display
+    }
+
+    /// Returns the wrapped display mutably.
+    ///
+    /// Command groups pushed directly into the wrapped display aren't transformed.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut D {
+        &mut self.display
+    }
+
+    /// Returns `true` if high contrast mode is enabled.
+    #[inline]
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Returns the current transform.
+    #[inline]
+    pub fn transform(&self) -> HighContrastTransform {
+        self.transform
+    }
+
+    /// Enables or disables high contrast mode, rewriting every existing command group accordingly.
+    ///
+    /// If a command group can't be rewritten, the mode is left unchanged and the error is returned.
+    pub fn set_enabled(&mut self, enabled: bool) -> Result<(), error::DisplayError> {
+        if self.enabled != enabled {
+            self.rewrite(enabled, self.transform)?;
+            self.enabled = enabled;
+        }
+        Ok(())
+    }
+
+    /// Changes the transform, rewriting every existing command group if high contrast mode is enabled.
+    ///
+    /// If a command group can't be rewritten, the transform is left unchanged and the error is returned.
+    pub fn set_transform(
+        &mut self,
+        transform: HighContrastTransform,
+    ) -> Result<(), error::DisplayError> {
+        if self.transform != transform {
+            if self.enabled {
+                self.rewrite(true, transform)?;
+            }
+            self.transform = transform;
+        }
+        Ok(())
+    }
+
+    fn transformed(&self, commands: &[DisplayCommand]) -> Vec<DisplayCommand> {
+        if self.enabled {
+            self.transform.apply(commands)
+        } else {
+            commands.to_vec()
+        }
+    }
+
+    /// Drops the original commands of command groups evicted by the inner display.
+    fn forget_evicted(&mut self, info: &PresentInfo) {
+        if info.evicted > 0 {
+            let display = &self.display;
+            self.originals.retain(|handle, _| display.get_command_group(*handle).is_some());
+        }
+    }
+
+    /// Rewrites every command group for the given mode and transform, within a single update.
+    ///
+    /// Updates can't be nested, so if the caller already began one (e.g. through a
+    /// [`DisplayTransaction`](super::transaction::DisplayTransaction)), the rewrite joins it rather than committing it
+    /// early; otherwise, the rewrite is wrapped in an update of its own.
+    ///
+    /// If a command group can't be rewritten, the ones rewritten before it are restored for the current mode and
+    /// transform, so that the display stays consistent with them.
+    fn rewrite(
+        &mut self,
+        enabled: bool,
+        transform: HighContrastTransform,
+    ) -> Result<(), error::DisplayError> {
+        let own_update = !self.updating;
+        if own_update {
+            self.display.begin_update();
+        }
+        let mut result = Ok(());
+        let mut rewritten = Vec::with_capacity(self.originals.len());
+        for (handle, original) in &self.originals {
+            if let Err(err) = original.rewrite(&mut self.display, *handle, enabled, transform) {
+                result = Err(err);
+                break;
+            }
+            rewritten.push(*handle);
+        }
+        if result.is_err() {
+            for handle in rewritten {
+                // restoring is best-effort; the error which stopped the rewrite is the one reported.
+                let _ = self.originals[&handle].rewrite(
+                    &mut self.display,
+                    handle,
+                    self.enabled,
+                    self.transform,
+                );
+            }
+        }
+        if !own_update {
+            return result;
+        }
+        // the update is always closed, even if the rewrite failed.
+        let committed = self.display.commit_update();
+        result.and(committed)
+    }
+}
+
+impl<D: GraphicsDisplay> GraphicsDisplay for HighContrastDisplay<D> {
+    fn resize(&mut self, size: (u32, u32)) -> Result<(), error::DisplayError> {
+        self.display.resize(size)
+    }
+
+    fn new_resource(
+        &mut self,
+        descriptor: ResourceDescriptor,
+    ) -> Result<ResourceReference, error::ResourceError> {
+        self.display.new_resource(descriptor)
+    }
+
+    fn replace_resource(
+        &mut self,
+        reference: ResourceReference,
+        descriptor: ResourceDescriptor,
+    ) -> Result<(), error::DisplayError> {
+        self.display.replace_resource(reference, descriptor)
+    }
+
+    fn update_resource(
+        &mut self,
+        reference: ResourceReference,
+        data: ImageData,
+    ) -> Result<(), error::DisplayError> {
+        self.display.update_resource(reference, data)
+    }
+
+    fn remove_resource(&mut self, reference: ResourceReference) {
+        self.display.remove_resource(reference)
+    }
+
+    fn push_command_group(
+        &mut self,
+        commands: &[DisplayCommand],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        needs_maintain: Option<bool>,
+    ) -> Result<CommandGroupHandle, error::DisplayError> {
+        let transformed = self.transformed(commands);
+        let handle =
+            self.display.push_command_group(&transformed, z_order, protected, needs_maintain)?;
+        self.originals.insert(
+            handle,
+            OriginalGroup { commands: commands.to_vec(), z_order, protected, needs_maintain },
+        );
+        Ok(handle)
+    }
+
+    /// Returns the command group as transformed, if high contrast mode is enabled.
+    fn get_command_group(&self, handle: CommandGroupHandle) -> Option<&[DisplayCommand]> {
+        self.display.get_command_group(handle)
+    }
+
+    fn modify_command_group(
+        &mut self,
+        handle: CommandGroupHandle,
+        commands: &[DisplayCommand],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        needs_maintain: Option<bool>,
+    ) -> Result<(), error::DisplayError> {
+        let transformed = self.transformed(commands);
+        self.display.modify_command_group(
+            handle,
+            &transformed,
+            z_order,
+            protected,
+            needs_maintain,
+        )?;
+        self.originals.insert(
+            handle,
+            OriginalGroup { commands: commands.to_vec(), z_order, protected, needs_maintain },
+        );
+        Ok(())
+    }
+
+    /// Returns the command group as originally pushed.
+    fn remove_command_group(&mut self, handle: CommandGroupHandle) -> Option<Vec<DisplayCommand>> {
+        let removed = self.display.remove_command_group(handle);
+        self.originals.remove(&handle).map(|original| original.commands).or(removed)
+    }
+
+    fn label_command_group(&mut self, handle: CommandGroupHandle, label: Option<&'static str>) {
+        self.display.label_command_group(handle, label)
+    }
+
+    fn debug_dump(&self) -> Vec<CommandGroupInfo> {
+        self.display.debug_dump()
+    }
+
+    fn maintain_command_group(&mut self, handle: CommandGroupHandle) {
+        self.display.maintain_command_group(handle)
+    }
+
+    fn begin_update(&mut self) {
+        self.updating = true;
+        self.display.begin_update()
+    }
+
+    fn commit_update(&mut self) -> Result<(), error::DisplayError> {
+        self.updating = false;
+        self.display.commit_update()
+    }
+
+    fn set_eviction_policy(&mut self, policy: EvictionPolicy) -> Result<(), error::DisplayError> {
+        self.display.set_eviction_policy(policy)
+    }
+
+    fn set_eviction_callback(
+        &mut self,
+        callback: Option<EvictionCallback>,
+    ) -> Result<(), error::DisplayError> {
+        self.display.set_eviction_callback(callback)
+    }
+
+    fn supports_backdrop_filter(&self) -> bool {
+        self.display.supports_backdrop_filter()
+    }
+
+    fn max_texture_size(&self) -> Option<u32> {
+        self.display.max_texture_size()
+    }
+
+    fn capabilities(&self) -> DisplayCapabilities {
+        self.display.capabilities()
+    }
+
+    fn before_exit(&mut self) {
+        self.display.before_exit()
+    }
+
+    fn present(&mut self, cull: Option<Rect>) -> Result<PresentInfo, error::DisplayError> {
+        let info = self.display.present(cull)?;
+        self.forget_evicted(&info);
+        Ok(info)
+    }
+
+    fn present_with_callback(
+        &mut self,
+        cull: Option<Rect>,
+        callback: Box<dyn FnOnce(PresentFeedback)>,
+    ) -> Result<PresentInfo, error::DisplayError> {
+        let info = self.display.present_with_callback(cull, callback)?;
+        self.forget_evicted(&info);
+        Ok(info)
+    }
+
+    fn read_pixels(&mut self, rect: PixelRect) -> Result<RasterImage, error::DisplayError> {
+        self.display.read_pixels(rect)
+    }
+
+    fn set_hole_callback(
+        &mut self,
+        id: u64,
+        callback: Option<HoleCallback>,
+    ) -> Result<(), error::DisplayError> {
+        self.display.set_hole_callback(id, callback)
+    }
+
+    fn set_virtual_resolution(
+        &mut self,
+        resolution: Option<VirtualResolution>,
+    ) -> Result<(), error::DisplayError> {
+        self.display.set_virtual_resolution(resolution)
+    }
+
+    fn set_color_correction(
+        &mut self,
+        correction: ColorCorrection,
+    ) -> Result<(), error::DisplayError> {
+        self.display.set_color_correction(correction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::display::recording::RecordingDisplay};
+
+    fn line(color: Color) -> DisplayCommand {
+        DisplayCommand::Item(
+            DisplayItem::Graphics(GraphicsDisplayItem::Line {
+                a: Point::new(10.0, 10.0),
+                b: Point::new(20.0, 10.0),
+                stroke: GraphicsDisplayStroke {
+                    color: StyleColor::Color(color),
+                    ..Default::default()
+                },
+            }),
+            Vec::new(),
+        )
+    }
+
+    fn stroke_color(command: &DisplayCommand) -> Color {
+        match command {
+            DisplayCommand::Item(
+                DisplayItem::Graphics(GraphicsDisplayItem::Line { stroke, .. }),
+                _,
+            ) => stroke.color.color_or_black(),
+            _ => panic!("expected a line"),
+        }
+    }
+
+    #[test]
+    fn test_contrast_ratio() {
+        let black = Color::new(0.0, 0.0, 0.0, 1.0);
+        let white = Color::new(1.0, 1.0, 1.0, 1.0);
+        assert!((contrast_ratio(black, white) - 21.0).abs() < 0.01);
+        assert_eq!(contrast_ratio(white, white), 1.0);
+
+        let transform = HighContrastTransform::default();
+        let gray = Color::new(0.6, 0.6, 0.6, 1.0);
+        let adjusted = transform.adjust(gray, white);
+        assert!(contrast_ratio(adjusted, white) >= 7.0);
+        // only as far as needed, rather than all the way to black.
+        assert!(adjusted.red > 0.1);
+        assert_eq!(transform.adjust(black, white), black);
+        assert!(contrast_ratio(transform.adjust(gray, black), black) >= 7.0);
+    }
+
+    #[test]
+    fn test_high_contrast_display() {
+        let gray = Color::new(0.4, 0.4, 0.4, 1.0);
+        let dark = Color::new(0.1, 0.1, 0.2, 1.0);
+        let commands = vec![
+            line(gray),
+            DisplayCommand::Save,
+            DisplayCommand::Item(
+                DisplayItem::Graphics(GraphicsDisplayItem::Rectangle {
+                    rect: Rect::new(Point::new(-10.0, -10.0), Size::new(50.0, 50.0)),
+                    paint: GraphicsDisplayPaint::Fill(StyleColor::Color(dark)),
+                }),
+                Vec::new(),
+            ),
+            DisplayCommand::Translate(Vector::new(5.0, 5.0)),
+            line(gray),
+            DisplayCommand::Restore,
+        ];
+
+        let mut display =
+            HighContrastDisplay::new(RecordingDisplay::default(), HighContrastTransform::default());
+        let handle = display.push_command_group(&commands, ZOrder(0), None, None).unwrap();
+        assert_eq!(stroke_color(&display.get_command_group(handle).unwrap()[0]), gray);
+
+        display.set_enabled(true).unwrap();
+        let group = display.get_command_group(handle).unwrap();
+        // the first line is against the default white background, the second one against the dark rectangle.
+        let white = Color::new(1.0, 1.0, 1.0, 1.0);
+        assert!(contrast_ratio(stroke_color(&group[0]), white) >= 7.0);
+        assert!(stroke_color(&group[0]).red < gray.red);
+        assert!(contrast_ratio(stroke_color(&group[4]), dark) >= 7.0);
+        assert!(stroke_color(&group[4]).red > gray.red);
+
+        display.set_enabled(false).unwrap();
+        assert_eq!(stroke_color(&display.get_command_group(handle).unwrap()[4]), gray);
+        assert_eq!(display.remove_command_group(handle).unwrap().len(), commands.len());
+    }
+
+    /// Display which refuses to modify one command group, and tracks whether an update is open.
+    #[derive(Default)]
+    struct FailingDisplay {
+        inner: RecordingDisplay,
+        refused: Option<CommandGroupHandle>,
+        updating: bool,
+    }
+
+    impl GraphicsDisplay for FailingDisplay {
+        fn resize(&mut self, size: (u32, u32)) -> Result<(), error::DisplayError> {
+            self.inner.resize(size)
+        }
+
+        fn new_resource(
+            &mut self,
+            descriptor: ResourceDescriptor,
+        ) -> Result<ResourceReference, error::ResourceError> {
+            self.inner.new_resource(descriptor)
+        }
+
+        fn replace_resource(
+            &mut self,
+            reference: ResourceReference,
+            descriptor: ResourceDescriptor,
+        ) -> Result<(), error::DisplayError> {
+            self.inner.replace_resource(reference, descriptor)
+        }
+
+        fn remove_resource(&mut self, reference: ResourceReference) {
+            self.inner.remove_resource(reference)
+        }
+
+        fn push_command_group(
+            &mut self,
+            commands: &[DisplayCommand],
+            z_order: ZOrder,
+            protected: Option<bool>,
+            needs_maintain: Option<bool>,
+        ) -> Result<CommandGroupHandle, error::DisplayError> {
+            self.inner.push_command_group(commands, z_order, protected, needs_maintain)
+        }
+
+        fn get_command_group(&self, handle: CommandGroupHandle) -> Option<&[DisplayCommand]> {
+            self.inner.get_command_group(handle)
+        }
+
+        fn modify_command_group(
+            &mut self,
+            handle: CommandGroupHandle,
+            commands: &[DisplayCommand],
+            z_order: ZOrder,
+            protected: Option<bool>,
+            needs_maintain: Option<bool>,
+        ) -> Result<(), error::DisplayError> {
+            if self.refused == Some(handle) {
+                return Err(error::DisplayError::InternalError("refused".into()));
+            }
+            self.inner.modify_command_group(handle, commands, z_order, protected, needs_maintain)
+        }
+
+        fn remove_command_group(
+            &mut self,
+            handle: CommandGroupHandle,
+        ) -> Option<Vec<DisplayCommand>> {
+            self.inner.remove_command_group(handle)
+        }
+
+        fn maintain_command_group(&mut self, handle: CommandGroupHandle) {
+            self.inner.maintain_command_group(handle)
+        }
+
+        fn begin_update(&mut self) {
+            assert!(!self.updating, "updates can't be nested");
+            self.updating = true;
+        }
+
+        fn commit_update(&mut self) -> Result<(), error::DisplayError> {
+            self.updating = false;
+            Ok(())
+        }
+
+        fn before_exit(&mut self) {}
+
+        fn present(&mut self, cull: Option<Rect>) -> Result<PresentInfo, error::DisplayError> {
+            self.inner.present(cull)
+        }
+    }
+
+    #[test]
+    fn test_failed_rewrite() {
+        let gray = Color::new(0.6, 0.6, 0.6, 1.0);
+        let mut display =
+            HighContrastDisplay::new(FailingDisplay::default(), HighContrastTransform::default());
+        let handles: Vec<_> = (0..4)
+            .map(|_| display.push_command_group(&[line(gray)], ZOrder(0), None, None).unwrap())
+            .collect();
+        display.inner_mut().refused = Some(handles[2]);
+
+        // every group is left as it was, and the update is closed.
+        assert!(display.set_enabled(true).is_err());
+        assert!(!display.enabled());
+        assert!(!display.inner().updating);
+        for handle in &handles {
+            assert_eq!(stroke_color(&display.get_command_group(*handle).unwrap()[0]), gray);
+        }
+
+        display.inner_mut().refused = None;
+        display.set_enabled(true).unwrap();
+        assert!(display.enabled());
+        assert!(stroke_color(&display.get_command_group(handles[2]).unwrap()[0]).red < gray.red);
+
+        display.inner_mut().refused = Some(handles[0]);
+        let transform = HighContrastTransform { min_ratio: 4.5, ..display.transform() };
+        assert!(display.set_transform(transform).is_err());
+        assert_eq!(display.transform(), HighContrastTransform::default());
+        assert!(!display.inner().updating);
+    }
+
+    #[test]
+    fn test_rewrite_within_update() {
+        let gray = Color::new(0.6, 0.6, 0.6, 1.0);
+        let mut display =
+            HighContrastDisplay::new(FailingDisplay::default(), HighContrastTransform::default());
+        let handle = display.push_command_group(&[line(gray)], ZOrder(0), None, None).unwrap();
+
+        // toggling the mode inside the caller's update joins it, rather than committing it.
+        display.begin_update();
+        display.set_enabled(true).unwrap();
+        assert!(display.inner().updating);
+        display.commit_update().unwrap();
+        assert!(!display.inner().updating);
+        assert!(stroke_color(&display.get_command_group(handle).unwrap()[0]).red < gray.red);
+    }
+}
@@ -0,0 +1,434 @@
+//! Rewriting of display lists for displays which lack some features.
+//!
+//! Widgets build their display lists without regard for the display; before pushing, unsupported commands
+//! are replaced with the closest approximation, as per the [capabilities](super::GraphicsDisplay::capabilities) of the display:
+//!
+//! ```ignore
+//! let commands = degrade::degrade(&builder.build(), &display.capabilities());
+//! display.push_command_group(&commands, Default::default(), None, None)?;
+//! ```
+//!
+//! The approximations are:
+//! - Item filters are removed, leaving the item unfiltered, and backdrop filters are removed entirely.
+//! - Blended paints and layers use [`Normal`](super::BlendMode::Normal) blending.
+//! - Layers are replaced by regular saved states, losing their opacity.
+//! - Rounded rectangle, elliptical and path clips become rectangular clips of their bounds.
+//! - [Difference](super::ClipOp::Difference) clips become path clips with a hole in the shape of the clip,
+//!   or are removed if path clips aren't supported either (except for paths, whose holes can't be cut out).
+//! - Meshes become flat triangles, each filled with the average color of its vertices.
+//! - Shaped text becomes simple text of the characters which map to its glyphs in the font
+//!   (see [`FontInfo::character`](super::FontInfo::character)), or is removed if the font can't be loaded.
+//! - Holes become rectangles cleared to transparent, without invoking any callback.
+//! - Custom commands become their (degraded) [fallback](super::CustomCommand::fallback).
+
+use {super::*, std::f32::consts::FRAC_1_SQRT_2};
+
+/// Half the width and height of the path surrounding the hole of an emulated difference clip.
+const DIFFERENCE_EXTENT: f32 = 1.0e7;
+
+/// Returns `commands`, with everything not supported as per `capabilities` replaced with an approximation.
+pub fn degrade(
+    commands: &[DisplayCommand],
+    capabilities: &DisplayCapabilities,
+) -> Vec<DisplayCommand> {
+    let filters = |filters: &Vec<FilterOp>| {
+        if capabilities.filters {
+            filters.clone()
+        } else {
+            Vec::new()
+        }
+    };
+
+    let mut degraded = Vec::with_capacity(commands.len());
+    for command in commands {
+        degraded.push(match command {
+            DisplayCommand::Item(
+                DisplayItem::Graphics(GraphicsDisplayItem::Mesh { vertices, indices }),
+                item_filters,
+            ) if !capabilities.meshes => {
+                degraded.extend(flat_triangles(vertices, indices, filters(item_filters)));
+                continue;
+            }
+            DisplayCommand::Item(item, item_filters) => match degrade_item(item, capabilities) {
+                Some(item) => DisplayCommand::Item(item, filters(item_filters)),
+                None => continue,
+            },
+            DisplayCommand::BackdropFilter(..) if !capabilities.backdrop_filter => continue,
+            DisplayCommand::BackdropFilter(clip, filters) => {
+                DisplayCommand::BackdropFilter(degrade_clip(clip, capabilities), filters.clone())
+            }
+            DisplayCommand::Clip(clip, ClipOp::Difference) if !capabilities.difference_clips => {
+                match difference_path(clip, capabilities) {
+                    Some(clip) => DisplayCommand::Clip(clip, ClipOp::Intersect),
+                    None => continue,
+                }
+            }
+            DisplayCommand::Clip(clip, op) => {
+                DisplayCommand::Clip(degrade_clip(clip, capabilities), *op)
+            }
+            DisplayCommand::SaveLayer(..) if !capabilities.layers => DisplayCommand::Save,
+            DisplayCommand::SaveLayer(opacity, _) if !capabilities.blend_modes => {
+                DisplayCommand::SaveLayer(*opacity, BlendMode::Normal)
+            }
+            DisplayCommand::Hole(_, rect) if !capabilities.holes => {
+                // still cut the hole, so that content composited behind the surface shows through.
+                degraded.extend(vec![
+                    DisplayCommand::Save,
+                    DisplayCommand::Clip(
+                        DisplayClip::Rectangle { rect: *rect, antialias: false },
+                        ClipOp::Intersect,
+                    ),
+                    DisplayCommand::Clear(Color::new(0.0, 0.0, 0.0, 0.0)),
+                    DisplayCommand::Restore,
+                ]);
+                continue;
+            }
+            DisplayCommand::Extension(command) if !capabilities.extensions => {
+                degraded.extend(degrade(&command.fallback(), capabilities));
+                continue;
+            }
+            command => command.clone(),
+        });
+    }
+    degraded
+}
+
+fn degrade_item(item: &DisplayItem, capabilities: &DisplayCapabilities) -> Option<DisplayItem> {
+    Some(match item {
+        DisplayItem::Graphics(item) if !capabilities.blend_modes => {
+            let mut item = item.clone();
+            match &mut item {
+                GraphicsDisplayItem::Rectangle { paint, .. }
+                | GraphicsDisplayItem::RoundRectangle { paint, .. }
+                | GraphicsDisplayItem::Ellipse { paint, .. }
+                | GraphicsDisplayItem::Path { paint, .. } => *paint = unblend(paint.clone()),
+                GraphicsDisplayItem::Line { .. }
+                | GraphicsDisplayItem::Image { .. }
+                | GraphicsDisplayItem::Mesh { .. } => {}
+            }
+            DisplayItem::Graphics(item)
+        }
+        DisplayItem::Text(item) if !capabilities.text_shaping => {
+            let mut item = item.clone();
+            if let DisplayText::Shaped(glyphs) = &item.text {
+                // glyphs without a character of their own (e.g. ligatures) are replaced rather than dropped.
+                item.text = DisplayText::Simple(
+                    glyphs
+                        .iter()
+                        .map(|glyph| {
+                            item.font_info
+                                .character(glyph.codepoint)
+                                .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                        })
+                        .collect::<Result<_, _>>()
+                        .ok()?,
+                );
+            }
+            DisplayItem::Text(item)
+        }
+        item => item.clone(),
+    })
+}
+
+fn flat_triangles(
+    vertices: &[(Point, Color)],
+    indices: &[u32],
+    filters: Vec<FilterOp>,
+) -> Vec<DisplayCommand> {
+    indices
+        .chunks_exact(3)
+        .filter_map(|triangle| {
+            let [a, b, c] =
+                [triangle[0], triangle[1], triangle[2]].map(|i| vertices.get(i as usize).copied());
+            let (a, b, c) = (a?, b?, c?);
+            let average = |f: fn(&Color) -> f32| (f(&a.1) + f(&b.1) + f(&c.1)) / 3.0;
+            let color = Color::new(
+                average(|c| c.red),
+                average(|c| c.green),
+                average(|c| c.blue),
+                average(|c| c.alpha),
+            );
+
+            let mut builder = VectorPathBuilder::new();
+            builder.move_to(a.0);
+            builder.line_to(b.0);
+            builder.line_to(c.0);
+            Some(DisplayCommand::Item(
+                DisplayItem::Graphics(GraphicsDisplayItem::Path {
+                    path: builder.build(),
+                    is_closed: true,
+                    paint: GraphicsDisplayPaint::Fill(color.into()),
+                }),
+                filters.clone(),
+            ))
+        })
+        .collect()
+}
+
+fn unblend(paint: GraphicsDisplayPaint) -> GraphicsDisplayPaint {
+    match paint {
+        GraphicsDisplayPaint::Blend(paint, _) => unblend(*paint),
+        paint => paint,
+    }
+}
+
+fn degrade_clip(clip: &DisplayClip, capabilities: &DisplayCapabilities) -> DisplayClip {
+    match clip {
+        DisplayClip::RoundRectangle { .. } | DisplayClip::Ellipse { .. }
+            if !capabilities.rounded_clips =>
+        {
+            DisplayClip::Rectangle { rect: clip.bounds(), antialias: clip.antialias() }
+        }
+        DisplayClip::Path { .. } if !capabilities.path_clips => {
+            DisplayClip::Rectangle { rect: clip.bounds(), antialias: clip.antialias() }
+        }
+        clip => clip.clone(),
+    }
+}
+
+/// Returns a path clip which covers everything but `clip`; the outline of `clip` is traced
+/// counter-clockwise within a large clockwise rectangle, so it's left out of the (non-zero) fill.
+/// Contours are closed implicitly when filled.
+fn difference_path(clip: &DisplayClip, capabilities: &DisplayCapabilities) -> Option<DisplayClip> {
+    if !capabilities.path_clips {
+        return None;
+    }
+
+    let (rect, radii) = match *clip {
+        DisplayClip::Rectangle { rect, .. } => (rect, [Vector::zero(); 4]),
+        DisplayClip::RoundRectangle { rect, radii, .. } if capabilities.rounded_clips => {
+            (rect, radii.map(|r| Vector::new(r, r)))
+        }
+        DisplayClip::Ellipse { radii, .. } if capabilities.rounded_clips => {
+            (clip.bounds(), [radii; 4])
+        }
+        DisplayClip::RoundRectangle { .. } | DisplayClip::Ellipse { .. } => {
+            (clip.bounds(), [Vector::zero(); 4])
+        }
+        DisplayClip::Path { .. } => return None,
+    };
+    let [top_left, top_right, bottom_left, bottom_right] = radii;
+    let (min, max) = (rect.min(), rect.max());
+
+    let mut builder = VectorPathBuilder::new();
+    builder.move_to(Point::new(-DIFFERENCE_EXTENT, -DIFFERENCE_EXTENT));
+    builder.line_to(Point::new(DIFFERENCE_EXTENT, -DIFFERENCE_EXTENT));
+    builder.line_to(Point::new(DIFFERENCE_EXTENT, DIFFERENCE_EXTENT));
+    builder.line_to(Point::new(-DIFFERENCE_EXTENT, DIFFERENCE_EXTENT));
+
+    // quarter ellipses are exactly conics with a weight of 1/sqrt(2).
+    builder.move_to(Point::new(min.x, min.y + top_left.y));
+    builder.line_to(Point::new(min.x, max.y - bottom_left.y));
+    builder.conic_to(
+        Point::new(min.x, max.y),
+        Point::new(min.x + bottom_left.x, max.y),
+        FRAC_1_SQRT_2,
+    );
+    builder.line_to(Point::new(max.x - bottom_right.x, max.y));
+    builder.conic_to(
+        Point::new(max.x, max.y),
+        Point::new(max.x, max.y - bottom_right.y),
+        FRAC_1_SQRT_2,
+    );
+    builder.line_to(Point::new(max.x, min.y + top_right.y));
+    builder.conic_to(
+        Point::new(max.x, min.y),
+        Point::new(max.x - top_right.x, min.y),
+        FRAC_1_SQRT_2,
+    );
+    builder.line_to(Point::new(min.x + top_left.x, min.y));
+    builder.conic_to(
+        Point::new(min.x, min.y),
+        Point::new(min.x, min.y + top_left.y),
+        FRAC_1_SQRT_2,
+    );
+
+    Some(DisplayClip::Path { path: builder.build(), is_closed: true, antialias: clip.antialias() })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect() -> Rect {
+        Rect::new(Point::new(0.0, 0.0), Size::new(10.0, 10.0))
+    }
+
+    #[test]
+    fn test_degrade() {
+        let font_info = test_font();
+        let font = font_info.font().unwrap();
+        // glyph IDs rarely equal the code-points of their characters, and glyph 0 has no character.
+        let (h, i) = (font.glyph_for_char('h').unwrap(), font.glyph_for_char('i').unwrap());
+        assert_ne!(h, 'h' as u32);
+        let glyph = |codepoint| ShapedGlyph {
+            codepoint,
+            advance: Vector::new(8.0, 0.0),
+            offset: Vector::new(0.0, 0.0),
+        };
+
+        let commands = vec![
+            DisplayCommand::SaveLayer(0.5, BlendMode::Multiply),
+            DisplayCommand::Clip(
+                DisplayClip::RoundRectangle { rect: rect(), radii: [2.0; 4], antialias: false },
+                ClipOp::Intersect,
+            ),
+            DisplayCommand::BackdropFilter(
+                DisplayClip::Rectangle { rect: rect(), antialias: false },
+                vec![FilterOp::Blur(4.0, 4.0)],
+            ),
+            DisplayCommand::Item(
+                DisplayItem::Graphics(GraphicsDisplayItem::Rectangle {
+                    rect: rect(),
+                    paint: GraphicsDisplayPaint::Fill(Color::new(1.0, 0.0, 0.0, 1.0).into())
+                        .with_blend_mode(BlendMode::Screen),
+                }),
+                vec![FilterOp::Invert],
+            ),
+            DisplayCommand::Item(
+                DisplayItem::Text(TextDisplayItem::new(
+                    DisplayText::Shaped(vec![glyph(h), glyph(i), glyph(0)]),
+                    ResourceReference::Font(0),
+                    font_info,
+                    16.0,
+                    Point::new(0.0, 20.0),
+                    Color::new(0.0, 0.0, 0.0, 1.0),
+                )),
+                vec![],
+            ),
+            DisplayCommand::Restore,
+        ];
+
+        // nothing is changed with every capability.
+        let all = degrade(&commands, &DisplayCapabilities::all());
+        assert_eq!(all.len(), commands.len());
+        assert!(matches!(all[0], DisplayCommand::SaveLayer(_, BlendMode::Multiply)));
+        assert!(matches!(all[1], DisplayCommand::Clip(DisplayClip::RoundRectangle { .. }, _)));
+        assert!(matches!(&all[3], DisplayCommand::Item(_, filters) if filters.len() == 1));
+
+        let none = degrade(&commands, &Default::default());
+        // the backdrop filter is removed.
+        assert_eq!(none.len(), commands.len() - 1);
+        assert!(matches!(none[0], DisplayCommand::Save));
+        assert!(matches!(
+            none[1],
+            DisplayCommand::Clip(DisplayClip::Rectangle { rect: r, antialias: false }, ClipOp::Intersect)
+                if r == rect()
+        ));
+        match &none[2] {
+            DisplayCommand::Item(
+                DisplayItem::Graphics(GraphicsDisplayItem::Rectangle { paint, .. }),
+                filters,
+            ) => {
+                assert!(filters.is_empty());
+                assert!(matches!(paint, GraphicsDisplayPaint::Fill(_)));
+            }
+            _ => panic!("expected a rectangle"),
+        }
+        match &none[3] {
+            DisplayCommand::Item(DisplayItem::Text(text), _) => {
+                assert_eq!(text.text, DisplayText::Simple("hi\u{FFFD}".into()))
+            }
+            _ => panic!("expected text"),
+        }
+
+        // meshes become flat triangles of the average color.
+        let red = Color::new(1.0, 0.0, 0.0, 1.0);
+        let blue = Color::new(0.0, 0.0, 1.0, 1.0);
+        let mesh = [DisplayCommand::Item(
+            DisplayItem::Graphics(GraphicsDisplayItem::Mesh {
+                vertices: vec![
+                    (Point::new(0.0, 0.0), red),
+                    (Point::new(10.0, 0.0), red),
+                    (Point::new(0.0, 10.0), blue),
+                    (Point::new(10.0, 10.0), blue),
+                ],
+                indices: vec![0, 1, 2, 1, 3, 2],
+            }),
+            vec![FilterOp::Invert],
+        )];
+        assert!(matches!(
+            degrade(&mesh, &DisplayCapabilities::all())[0],
+            DisplayCommand::Item(DisplayItem::Graphics(GraphicsDisplayItem::Mesh { .. }), _)
+        ));
+        let triangles = degrade(&mesh, &Default::default());
+        assert_eq!(triangles.len(), 2);
+        match &triangles[0] {
+            DisplayCommand::Item(
+                DisplayItem::Graphics(GraphicsDisplayItem::Path {
+                    path,
+                    paint: GraphicsDisplayPaint::Fill(StyleColor::Color(color)),
+                    ..
+                }),
+                filters,
+            ) => {
+                assert_eq!(path.len(), 3);
+                assert_eq!(*color, Color::new(2.0 / 3.0, 0.0, 1.0 / 3.0, 1.0));
+                assert!(filters.is_empty());
+            }
+            _ => panic!("expected a triangle"),
+        }
+
+        // layers without blend modes keep their opacity.
+        let layers = DisplayCapabilities { layers: true, ..Default::default() };
+        assert!(matches!(
+            degrade(&commands[..1], &layers)[0],
+            DisplayCommand::SaveLayer(opacity, BlendMode::Normal) if opacity == 0.5
+        ));
+
+        // difference clips are emulated with a path clip, which contains the corners of the rectangle but not its center.
+        let difference = [DisplayCommand::Clip(
+            DisplayClip::Ellipse {
+                center: Point::new(5.0, 5.0),
+                radii: Vector::new(5.0, 5.0),
+                antialias: true,
+            },
+            ClipOp::Difference,
+        )];
+        let path_clips = DisplayCapabilities { path_clips: true, ..Default::default() };
+        match &degrade(&difference, &path_clips)[0] {
+            DisplayCommand::Clip(
+                DisplayClip::Path { path, antialias: true, .. },
+                ClipOp::Intersect,
+            ) => {
+                assert!(path.contains(&VectorPathEvent::MoveTo { to: Point::new(0.0, 0.0) }));
+                assert!(path.contains(&VectorPathEvent::LineTo { to: Point::new(10.0, 10.0) }));
+            }
+            _ => panic!("expected a path clip"),
+        }
+        let rounded = DisplayCapabilities { rounded_clips: true, ..path_clips };
+        match &degrade(&difference, &rounded)[0] {
+            DisplayCommand::Clip(DisplayClip::Path { path, .. }, _) => {
+                assert!(path.contains(&VectorPathEvent::MoveTo { to: Point::new(0.0, 5.0) }));
+                assert_eq!(
+                    path.iter()
+                        .filter(|event| matches!(event, VectorPathEvent::ConicTo { .. }))
+                        .count(),
+                    4
+                );
+            }
+            _ => panic!("expected a path clip"),
+        }
+        assert!(degrade(&difference, &Default::default()).is_empty());
+        assert!(matches!(
+            degrade(&difference, &DisplayCapabilities::all())[0],
+            DisplayCommand::Clip(DisplayClip::Ellipse { .. }, ClipOp::Difference)
+        ));
+
+        // holes are still cut out, within their own saved state.
+        let hole = [DisplayCommand::Hole(7, rect())];
+        assert!(matches!(
+            degrade(&hole, &DisplayCapabilities::all())[..],
+            [DisplayCommand::Hole(7, _)]
+        ));
+        assert!(matches!(
+            degrade(&hole, &Default::default())[..],
+            [
+                DisplayCommand::Save,
+                DisplayCommand::Clip(DisplayClip::Rectangle { antialias: false, .. }, ClipOp::Intersect),
+                DisplayCommand::Clear(Color { alpha, .. }),
+                DisplayCommand::Restore,
+            ] if alpha == 0.0
+        ));
+    }
+}
@@ -0,0 +1,328 @@
+//! A stateful drawing interface in the style of HTML canvas and Cairo.
+//!
+//! Rather than passing a paint to every call like [`DisplayListBuilder`](DisplayListBuilder), a
+//! [`DrawContext`](DrawContext) keeps the current fill, stroke, font, filters and blend mode, which are saved
+//! and restored along with the transformation and clip.
+//!
+//! ```ignore
+//! let mut cx = DrawContext::new();
+//! cx.set_fill(Color::new(0.9, 0.9, 0.9, 1.0));
+//! cx.fill_round_rect(bounds, [4.0; 4]);
+//! cx.save();
+//! cx.translate(Vector::new(8.0, 8.0));
+//! cx.set_font(font, font_info, 14.0);
+//! cx.fill_text("Hello", Point::new(0.0, 14.0));
+//! cx.restore();
+//! let commands = cx.build();
+//! ```
+
+use super::*;
+
+#[derive(Clone)]
+struct DrawState {
+    fill: StyleColor,
+    stroke: GraphicsDisplayStroke,
+    font: Option<(ResourceReference, FontInfo, f32)>,
+    filters: Vec<FilterOp>,
+    blend_mode: BlendMode,
+}
+
+impl Default for DrawState {
+    fn default() -> Self {
+        DrawState {
+            fill: Color::new(0.0, 0.0, 0.0, 1.0).into(),
+            stroke: Default::default(),
+            font: None,
+            filters: Vec::new(),
+            blend_mode: BlendMode::Normal,
+        }
+    }
+}
+
+/// Stateful wrapper around a [`DisplayListBuilder`](DisplayListBuilder).
+///
+/// The fill and stroke default to opaque black, with a 1px stroke width.
+#[derive(Clone, Default)]
+pub struct DrawContext {
+    builder: DisplayListBuilder,
+    state: DrawState,
+    saved: Vec<DrawState>,
+}
+
+impl DrawContext {
+    /// Creates a context with an empty display list.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Creates a context which appends to the display list of `builder`.
+    pub fn from_builder(builder: DisplayListBuilder) -> Self {
+        DrawContext { builder, ..Default::default() }
+    }
+
+    /// Returns the underlying builder, to push commands which don't use the context state.
+    #[inline]
+    pub fn builder(&mut self) -> &mut DisplayListBuilder {
+        &mut self.builder
+    }
+
+    /// Sets the color (or gradient) shapes and text are filled with.
+    pub fn set_fill(&mut self, fill: impl Into<StyleColor>) {
+        self.state.fill = fill.into();
+    }
+
+    /// Sets the color (or gradient) of strokes.
+    pub fn set_stroke_color(&mut self, color: impl Into<StyleColor>) {
+        self.state.stroke.color = color.into();
+    }
+
+    /// Sets the thickness of strokes.
+    pub fn set_line_width(&mut self, width: f32) {
+        self.state.stroke.thickness = width;
+    }
+
+    /// Sets the shape of the ends of strokes.
+    pub fn set_line_cap(&mut self, cap: LineCap) {
+        self.state.stroke.cap = cap;
+    }
+
+    /// Sets the shape of the corners of strokes.
+    pub fn set_line_join(&mut self, join: LineJoin) {
+        self.state.stroke.join = join;
+    }
+
+    /// Replaces every stroke property at once.
+    pub fn set_stroke(&mut self, stroke: GraphicsDisplayStroke) {
+        self.state.stroke = stroke;
+    }
+
+    /// Sets the font text is drawn with.
+    pub fn set_font(&mut self, font: ResourceReference, font_info: FontInfo, size: f32) {
+        self.state.font = Some((font, font_info, size));
+    }
+
+    /// Sets the filters subsequent items are drawn through.
+    pub fn set_filters(&mut self, filters: Vec<FilterOp>) {
+        self.state.filters = filters;
+    }
+
+    /// Sets the mode subsequent shapes are blended with.
+    pub fn set_blend_mode(&mut self, mode: BlendMode) {
+        self.state.blend_mode = mode;
+    }
+
+    /// Fills a rectangle.
+    pub fn fill_rect(&mut self, rect: Rect) {
+        let (paint, filters) = self.fill_paint();
+        self.builder.push_rectangle(rect, paint, filters);
+    }
+
+    /// Strokes the outline of a rectangle.
+    pub fn stroke_rect(&mut self, rect: Rect) {
+        let (paint, filters) = self.stroke_paint();
+        self.builder.push_rectangle(rect, paint, filters);
+    }
+
+    /// Fills a rectangle with rounded corners (radii from top-left, top-right, bottom-left, bottom-right).
+    pub fn fill_round_rect(&mut self, rect: Rect, radii: [f32; 4]) {
+        let (paint, filters) = self.fill_paint();
+        self.builder.push_round_rectangle(rect, radii, paint, filters);
+    }
+
+    /// Strokes the outline of a rectangle with rounded corners.
+    pub fn stroke_round_rect(&mut self, rect: Rect, radii: [f32; 4]) {
+        let (paint, filters) = self.stroke_paint();
+        self.builder.push_round_rectangle(rect, radii, paint, filters);
+    }
+
+    /// Fills an ellipse.
+    pub fn fill_ellipse(&mut self, center: Point, radii: Vector) {
+        let (paint, filters) = self.fill_paint();
+        self.builder.push_ellipse(center, radii, paint, filters);
+    }
+
+    /// Strokes the outline of an ellipse.
+    pub fn stroke_ellipse(&mut self, center: Point, radii: Vector) {
+        let (paint, filters) = self.stroke_paint();
+        self.builder.push_ellipse(center, radii, paint, filters);
+    }
+
+    /// Strokes a line from `a` to `b`.
+    ///
+    /// Lines are always drawn with normal blending.
+    pub fn stroke_line(&mut self, a: Point, b: Point) {
+        let stroke = self.state.stroke.clone();
+        let filters = self.state.filters.clone();
+        self.builder.push_line(a, b, stroke, filters);
+    }
+
+    /// Fills a vector path, which is implicitly closed.
+    pub fn fill_path(&mut self, path: VectorPath) {
+        let (paint, filters) = self.fill_paint();
+        self.builder.push_path(path, true, paint, filters);
+    }
+
+    /// Strokes a vector path.
+    pub fn stroke_path(&mut self, path: VectorPath, is_closed: bool) {
+        let (paint, filters) = self.stroke_paint();
+        self.builder.push_path(path, is_closed, paint, filters);
+    }
+
+    /// Draws (a part of) an image into `dst`.
+    pub fn draw_image(
+        &mut self,
+        src: impl Into<Option<Rect>>,
+        dst: Rect,
+        image: ResourceReference,
+    ) {
+        let filters = self.state.filters.clone();
+        self.builder.push_image(src, dst, image, filters);
+    }
+
+    /// Draws a line of text in the current font and fill.
+    ///
+    /// Returns `false`, drawing nothing, if no font was set.
+    pub fn fill_text(&mut self, text: impl Into<DisplayText>, bottom_left: Point) -> bool {
+        let (font, font_info, size) = match &self.state.font {
+            Some(font) => font.clone(),
+            None => return false,
+        };
+        let item =
+            TextDisplayItem::new(text, font, font_info, size, bottom_left, self.state.fill.clone());
+        let filters = self.state.filters.clone();
+        self.builder.push_text(item, filters);
+        true
+    }
+
+    /// Fills the clipped region with a solid color.
+    pub fn clear(&mut self, color: Color) {
+        self.builder.push_clear(color);
+    }
+
+    /// Clips subsequent drawing to a rectangle.
+    pub fn clip_rect(&mut self, rect: Rect) {
+        self.builder.push_rectangle_clip(rect, true);
+    }
+
+    /// Clips subsequent drawing to a rectangle with rounded corners.
+    pub fn clip_round_rect(&mut self, rect: Rect, radii: [f32; 4]) {
+        self.builder.push_round_rectangle_clip(rect, radii);
+    }
+
+    /// Clips subsequent drawing to a vector path.
+    pub fn clip_path(&mut self, path: VectorPath) {
+        self.builder.push_path_clip(path, true);
+    }
+
+    /// Offsets subsequent drawing.
+    pub fn translate(&mut self, offset: Vector) {
+        self.builder.push_translation(offset);
+    }
+
+    /// Scales subsequent drawing.
+    pub fn scale(&mut self, scale: Vector) {
+        self.builder.push_scaling(scale);
+    }
+
+    /// Rotates subsequent drawing.
+    pub fn rotate(&mut self, angle: Angle) {
+        self.builder.push_rotation(angle);
+    }
+
+    /// Saves the transformation, clip and drawing state (fill, stroke, font, filters and blend mode).
+    pub fn save(&mut self) {
+        self.saved.push(self.state.clone());
+        self.builder.save();
+    }
+
+    /// Saves like [`save`](DrawContext::save), drawing into a new layer which is composited with
+    /// `opacity` and the current blend mode once restored.
+    pub fn save_layer(&mut self, opacity: f32) {
+        self.saved.push(self.state.clone());
+        self.builder.save_blend_layer(opacity, self.state.blend_mode);
+    }
+
+    /// Restores the last saved state. Does nothing if there is no saved state.
+    pub fn restore(&mut self) {
+        if let Some(state) = self.saved.pop() {
+            self.state = state;
+            self.builder.restore();
+        }
+    }
+
+    /// Returns the display list, restoring any state left saved.
+    pub fn build(mut self) -> Vec<DisplayCommand> {
+        while !self.saved.is_empty() {
+            self.restore();
+        }
+        self.builder.build()
+    }
+
+    fn fill_paint(&self) -> (GraphicsDisplayPaint, Vec<FilterOp>) {
+        self.paint(GraphicsDisplayPaint::Fill(self.state.fill.clone()))
+    }
+
+    fn stroke_paint(&self) -> (GraphicsDisplayPaint, Vec<FilterOp>) {
+        self.paint(GraphicsDisplayPaint::Stroke(self.state.stroke.clone()))
+    }
+
+    fn paint(&self, paint: GraphicsDisplayPaint) -> (GraphicsDisplayPaint, Vec<FilterOp>) {
+        let paint = match self.state.blend_mode {
+            BlendMode::Normal => paint,
+            mode => paint.with_blend_mode(mode),
+        };
+        (paint, self.state.filters.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draw_context() {
+        let red = Color::new(1.0, 0.0, 0.0, 1.0);
+        let rect = Rect::new(Point::new(0.0, 0.0), Size::new(10.0, 10.0));
+
+        let mut cx = DrawContext::new();
+        cx.set_fill(red);
+        cx.save();
+        cx.translate(Vector::new(5.0, 5.0));
+        cx.set_blend_mode(BlendMode::Multiply);
+        cx.set_line_width(3.0);
+        cx.stroke_rect(rect);
+        cx.restore();
+        cx.fill_rect(rect);
+        assert!(!cx.fill_text("no font", Point::new(0.0, 10.0)));
+        cx.save_layer(0.5);
+        let commands = cx.build();
+
+        assert_eq!(commands.len(), 7);
+        assert!(matches!(commands[0], DisplayCommand::Save));
+        match &commands[2] {
+            DisplayCommand::Item(
+                DisplayItem::Graphics(GraphicsDisplayItem::Rectangle { paint, .. }),
+                _,
+            ) => {
+                assert_eq!(paint.blend_mode(), BlendMode::Multiply);
+                assert_eq!(paint.stroke().unwrap().thickness, 3.0);
+            }
+            _ => panic!("expected a stroked rectangle"),
+        }
+        assert!(matches!(commands[3], DisplayCommand::Restore));
+        // the fill survives the restore, whereas the blend mode doesn't.
+        match &commands[4] {
+            DisplayCommand::Item(
+                DisplayItem::Graphics(GraphicsDisplayItem::Rectangle {
+                    paint: GraphicsDisplayPaint::Fill(StyleColor::Color(color)),
+                    ..
+                }),
+                _,
+            ) => assert_eq!(*color, red),
+            _ => panic!("expected a filled rectangle"),
+        }
+        assert!(matches!(commands[5], DisplayCommand::SaveLayer(_, BlendMode::Normal)));
+        // unbalanced saves are restored when the list is built.
+        assert!(matches!(commands[6], DisplayCommand::Restore));
+    }
+}
@@ -0,0 +1,314 @@
+//! [`arbitrary`](https://docs.rs/arbitrary) implementations for display commands, for fuzzing backends.
+//!
+//! Only graphical items are generated; text items require a loaded [`FontInfo`](FontInfo), which can't be
+//! constructed from arbitrary data. Floating-point values are unrestricted, so generated display lists
+//! frequently contain NaN/infinite geometry or unbalanced saves, which is exactly what
+//! [`validate_display_list`](super::validate::validate_display_list) is meant to catch.
+//!
+//! ```ignore
+//! fuzz_target!(|data: &[u8]| {
+//!     reclutch::display::fuzz::fuzz_display(data, &mut display);
+//! });
+//! ```
+
+use {
+    super::{validate::ValidatedDisplay, *},
+    arbitrary::{Arbitrary, Result, Unstructured},
+};
+
+fn point(u: &mut Unstructured<'_>) -> Result<Point> {
+    Ok(Point::new(u.arbitrary()?, u.arbitrary()?))
+}
+
+fn vector(u: &mut Unstructured<'_>) -> Result<Vector> {
+    Ok(Vector::new(u.arbitrary()?, u.arbitrary()?))
+}
+
+fn rect(u: &mut Unstructured<'_>) -> Result<Rect> {
+    Ok(Rect::new(point(u)?, Size::new(u.arbitrary()?, u.arbitrary()?)))
+}
+
+fn color(u: &mut Unstructured<'_>) -> Result<Color> {
+    Ok(Color::new(u.arbitrary()?, u.arbitrary()?, u.arbitrary()?, u.arbitrary()?))
+}
+
+fn path(u: &mut Unstructured<'_>) -> Result<VectorPath> {
+    u.arbitrary_iter()?.collect()
+}
+
+fn gradient(u: &mut Unstructured<'_>) -> Result<Gradient> {
+    Ok(Gradient {
+        start: point(u)?,
+        end: point(u)?,
+        stops: u
+            .arbitrary::<Vec<(f32, [f32; 4])>>()?
+            .into_iter()
+            .map(|(pos, [r, g, b, a])| (pos, Color::new(r, g, b, a)))
+            .collect(),
+        color_space: u.arbitrary()?,
+    })
+}
+
+impl<'a> Arbitrary<'a> for ColorSpace {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(*u.choose(&[ColorSpace::Srgb, ColorSpace::DisplayP3, ColorSpace::LinearSrgb])?)
+    }
+}
+
+impl<'a> Arbitrary<'a> for LineCap {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(*u.choose(&[LineCap::Flat, LineCap::Square, LineCap::Round])?)
+    }
+}
+
+impl<'a> Arbitrary<'a> for LineJoin {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(*u.choose(&[LineJoin::Miter, LineJoin::Round, LineJoin::Bevel])?)
+    }
+}
+
+impl<'a> Arbitrary<'a> for VectorPathEvent {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=5)? {
+            0 => VectorPathEvent::MoveTo { to: point(u)? },
+            1 => VectorPathEvent::LineTo { to: point(u)? },
+            2 => VectorPathEvent::QuadTo { control: point(u)?, to: point(u)? },
+            3 => VectorPathEvent::ConicTo {
+                control: point(u)?,
+                to: point(u)?,
+                weight: u.arbitrary()?,
+            },
+            4 => VectorPathEvent::CubicTo { c1: point(u)?, c2: point(u)?, to: point(u)? },
+            _ => VectorPathEvent::ArcTo {
+                center: point(u)?,
+                radii: vector(u)?,
+                start_angle: u.arbitrary()?,
+                sweep_angle: u.arbitrary()?,
+            },
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for StyleColor {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=3)? {
+            0 => StyleColor::Color(color(u)?),
+            1 => StyleColor::TaggedColor(color(u)?, u.arbitrary()?),
+            2 => StyleColor::LinearGradient(gradient(u)?),
+            _ => StyleColor::RadialGradient(gradient(u)?),
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for GraphicsDisplayStroke {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(GraphicsDisplayStroke {
+            color: u.arbitrary()?,
+            thickness: u.arbitrary()?,
+            cap: u.arbitrary()?,
+            join: u.arbitrary()?,
+            miter_limit: u.arbitrary()?,
+            antialias: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for BlendMode {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(*u.choose(&[
+            BlendMode::Normal,
+            BlendMode::Multiply,
+            BlendMode::Screen,
+            BlendMode::Overlay,
+            BlendMode::Darken,
+            BlendMode::Lighten,
+            BlendMode::ColorDodge,
+            BlendMode::ColorBurn,
+            BlendMode::HardLight,
+            BlendMode::SoftLight,
+            BlendMode::Difference,
+            BlendMode::Exclusion,
+            BlendMode::Hue,
+            BlendMode::Saturation,
+            BlendMode::Color,
+            BlendMode::Luminosity,
+            BlendMode::Plus,
+        ])?)
+    }
+}
+
+impl<'a> Arbitrary<'a> for GraphicsDisplayPaint {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let paint = if u.arbitrary()? {
+            GraphicsDisplayPaint::Fill(u.arbitrary()?)
+        } else {
+            GraphicsDisplayPaint::Stroke(u.arbitrary()?)
+        };
+        Ok(if u.arbitrary()? { paint.with_blend_mode(u.arbitrary()?) } else { paint })
+    }
+}
+
+impl<'a> Arbitrary<'a> for ResourceReference {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(if u.arbitrary()? {
+            ResourceReference::Image(u.arbitrary()?)
+        } else {
+            ResourceReference::Font(u.arbitrary()?)
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for GraphicsDisplayItem {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=6)? {
+            0 => GraphicsDisplayItem::Line { a: point(u)?, b: point(u)?, stroke: u.arbitrary()? },
+            1 => GraphicsDisplayItem::Rectangle { rect: rect(u)?, paint: u.arbitrary()? },
+            2 => GraphicsDisplayItem::RoundRectangle {
+                rect: rect(u)?,
+                radii: u.arbitrary()?,
+                paint: u.arbitrary()?,
+            },
+            3 => GraphicsDisplayItem::Ellipse {
+                center: point(u)?,
+                radii: vector(u)?,
+                paint: u.arbitrary()?,
+            },
+            4 => GraphicsDisplayItem::Image {
+                src: if u.arbitrary()? { Some(rect(u)?) } else { None },
+                dst: rect(u)?,
+                resource: u.arbitrary()?,
+            },
+            5 => GraphicsDisplayItem::Path {
+                path: path(u)?,
+                is_closed: u.arbitrary()?,
+                paint: u.arbitrary()?,
+            },
+            _ => GraphicsDisplayItem::Mesh {
+                vertices: u
+                    .arbitrary_iter::<([f32; 2], [f32; 4])>()?
+                    .map(|vertex| {
+                        vertex.map(|([x, y], [r, g, b, a])| {
+                            (Point::new(x, y), Color::new(r, g, b, a))
+                        })
+                    })
+                    .collect::<Result<_>>()?,
+                indices: u.arbitrary()?,
+            },
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for DisplayClip {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=3)? {
+            0 => DisplayClip::Rectangle { rect: rect(u)?, antialias: u.arbitrary()? },
+            1 => DisplayClip::RoundRectangle {
+                rect: rect(u)?,
+                radii: u.arbitrary()?,
+                antialias: u.arbitrary()?,
+            },
+            2 => DisplayClip::Ellipse {
+                center: point(u)?,
+                radii: vector(u)?,
+                antialias: u.arbitrary()?,
+            },
+            _ => DisplayClip::Path {
+                path: path(u)?,
+                is_closed: u.arbitrary()?,
+                antialias: u.arbitrary()?,
+            },
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for ClipOp {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(*u.choose(&[ClipOp::Intersect, ClipOp::Difference])?)
+    }
+}
+
+impl<'a> Arbitrary<'a> for FilterOp {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=7)? {
+            0 => FilterOp::Blur(u.arbitrary()?, u.arbitrary()?),
+            1 => FilterOp::Invert,
+            2 => FilterOp::Grayscale(u.arbitrary()?),
+            3 => FilterOp::Brightness(u.arbitrary()?),
+            4 => FilterOp::Contrast(u.arbitrary()?),
+            5 => FilterOp::HueRotate(Angle::radians(u.arbitrary()?)),
+            6 => {
+                FilterOp::DropShadow { offset: vector(u)?, sigma: u.arbitrary()?, color: color(u)? }
+            }
+            _ => FilterOp::ColorMatrix(u.arbitrary()?),
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for DisplayCommand {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(match u.int_in_range(0..=10)? {
+            0 => DisplayCommand::Item(DisplayItem::Graphics(u.arbitrary()?), u.arbitrary()?),
+            1 => DisplayCommand::BackdropFilter(u.arbitrary()?, u.arbitrary()?),
+            2 => DisplayCommand::Clip(u.arbitrary()?, u.arbitrary()?),
+            3 => DisplayCommand::Save,
+            4 => DisplayCommand::SaveLayer(u.arbitrary()?, u.arbitrary()?),
+            5 => DisplayCommand::Restore,
+            6 => DisplayCommand::Translate(vector(u)?),
+            7 => DisplayCommand::Scale(vector(u)?),
+            8 => DisplayCommand::Rotate(Angle::radians(u.arbitrary()?)),
+            9 => DisplayCommand::Hole(u.arbitrary()?, rect(u)?),
+            _ => DisplayCommand::Clear(color(u)?),
+        })
+    }
+}
+
+/// Fuzzing entry point; builds a display list from `data` and submits it through
+/// [`validate_and_execute`](ValidatedDisplay::validate_and_execute), then presents.
+///
+/// Returns `true` if the display list passed validation. A backend should never panic here.
+pub fn fuzz_display(data: &[u8], display: &mut dyn GraphicsDisplay) -> bool {
+    let commands: Vec<DisplayCommand> = match Unstructured::new(data).arbitrary() {
+        Ok(commands) => commands,
+        Err(_) => return false,
+    };
+
+    let valid = display.validate_and_execute(&commands, ZOrder::default(), None, None).is_ok();
+    let _ = display.present(None);
+    valid
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, recording::RecordingDisplay};
+
+    #[test]
+    fn test_fuzz_display() {
+        let mut display = RecordingDisplay::default();
+        // simple deterministic xorshift, to avoid a dev-dependency on a random number generator.
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut accepted = 0;
+
+        for _ in 0..500 {
+            let data: Vec<u8> = (0..256)
+                .map(|_| {
+                    state ^= state << 13;
+                    state ^= state >> 7;
+                    state ^= state << 17;
+                    state as u8
+                })
+                .collect();
+
+            let commands: Vec<DisplayCommand> = Unstructured::new(&data).arbitrary().unwrap();
+            let valid = validate::validate_display_list(&commands).is_ok();
+            assert_eq!(fuzz_display(&data, &mut display), valid);
+            if valid {
+                accepted += 1;
+                assert_eq!(display.presented().len(), commands.len());
+            }
+        }
+
+        // groups are never maintained, so only the last accepted one may still be alive.
+        assert!(display.command_group_count() <= 1);
+        assert!(accepted > 0);
+    }
+}
@@ -0,0 +1,115 @@
+//! Icons drawn from a single glyph of an icon font (e.g. Font Awesome or Material Icons).
+//!
+//! The glyph is drawn as a filled path centered on its ink, rather than as text positioned by its baseline,
+//! so an icon lines up in a square button regardless of the metrics of the font. Outlines are cached per font
+//! (see [`FontInfo::outline`](FontInfo::outline)).
+//!
+//! ```ignore
+//! let save = Icon::new(icon_font.clone(), '\u{f0c7}', 16.0, theme.foreground);
+//! save.push(&mut builder, button_bounds, vec![])?;
+//! ```
+
+use {super::*, crate::error::FontError};
+
+/// A glyph of an icon font, with a size and color.
+#[derive(Debug, Clone)]
+pub struct Icon {
+    pub font: FontInfo,
+    pub codepoint: char,
+    /// Font size, in pixels per em.
+    pub size: f32,
+    pub color: StyleColor,
+}
+
+impl Icon {
+    pub fn new(font: FontInfo, codepoint: char, size: f32, color: impl Into<StyleColor>) -> Self {
+        Icon { font, codepoint, size, color: color.into() }
+    }
+
+    /// Returns the size of the glyph as drawn.
+    pub fn extents(&self) -> Result<Size, FontError> {
+        Ok(self.font.outline(self.codepoint)?.bounds.size * self.scale())
+    }
+
+    /// Returns a filled path of the glyph, centered within `rect`.
+    pub fn display_item(&self, rect: Rect) -> Result<DisplayItem, FontError> {
+        Ok(DisplayItem::Graphics(GraphicsDisplayItem::Path {
+            path: self.path(rect)?,
+            is_closed: true,
+            paint: GraphicsDisplayPaint::Fill(self.color.clone()),
+        }))
+    }
+
+    /// Pushes the glyph, centered within `rect`.
+    pub fn push(
+        &self,
+        builder: &mut DisplayListBuilder,
+        rect: Rect,
+        filters: Vec<FilterOp>,
+    ) -> Result<(), FontError> {
+        builder.push_path(
+            self.path(rect)?,
+            true,
+            GraphicsDisplayPaint::Fill(self.color.clone()),
+            filters,
+        );
+        Ok(())
+    }
+
+    fn path(&self, rect: Rect) -> Result<VectorPath, FontError> {
+        let outline = self.font.outline(self.codepoint)?;
+        let scale = self.scale();
+        let offset = center(outline.bounds.size * scale, rect) - outline.bounds.origin * scale;
+        let point = |p: Point| p * scale + offset;
+
+        Ok(outline
+            .path
+            .iter()
+            .map(|event| match *event {
+                VectorPathEvent::MoveTo { to } => VectorPathEvent::MoveTo { to: point(to) },
+                VectorPathEvent::LineTo { to } => VectorPathEvent::LineTo { to: point(to) },
+                VectorPathEvent::QuadTo { control, to } => {
+                    VectorPathEvent::QuadTo { control: point(control), to: point(to) }
+                }
+                VectorPathEvent::CubicTo { c1, c2, to } => {
+                    VectorPathEvent::CubicTo { c1: point(c1), c2: point(c2), to: point(to) }
+                }
+                // outlines consist of lines and curves only.
+                event => event,
+            })
+            .collect())
+    }
+
+    fn scale(&self) -> f32 {
+        self.size / self.font.metrics().units_per_em as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, float_cmp::approx_eq};
+
+    #[test]
+    fn test_icon() {
+        let font = test_font();
+        let icon = Icon::new(font.clone(), 'o', 32.0, Color::new(0.0, 0.0, 0.0, 1.0));
+
+        // outlines are cached.
+        assert!(Rc::ptr_eq(&font.outline('o').unwrap(), &font.outline('o').unwrap()));
+        assert!(Icon::new(font, '\u{f0c7}', 32.0, Color::new(0.0, 0.0, 0.0, 1.0))
+            .extents()
+            .is_err());
+
+        let rect = Rect::new(Point::new(10.0, 10.0), Size::new(40.0, 40.0));
+        let extents = icon.extents().unwrap();
+        let bounds = icon.display_item(rect).unwrap().bounds().unwrap();
+        // the ink is centered, regardless of the baseline.
+        assert!(approx_eq!(f32, bounds.center().x, rect.center().x, epsilon = 0.5));
+        assert!(approx_eq!(f32, bounds.center().y, rect.center().y, epsilon = 0.5));
+        assert!(approx_eq!(f32, bounds.size.height, extents.height, epsilon = 0.5));
+
+        let mut builder = DisplayListBuilder::new();
+        icon.push(&mut builder, rect, vec![]).unwrap();
+        assert_eq!(builder.build().len(), 1);
+    }
+}
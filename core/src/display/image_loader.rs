@@ -0,0 +1,358 @@
+//! Decoding images on background threads, with a blurred low-resolution placeholder shown until they're ready.
+//!
+//! [`ImageLoader::load`](ImageLoader::load) returns an image resource straight away, which starts out as a single
+//! transparent pixel. The image is decoded on a background thread, and [`update`](ImageLoader::update) swaps the
+//! decoded pixels into the same resource (through [`update_resource`](GraphicsDisplay::update_resource)), so
+//! the commands drawing it don't change; only their command groups need repainting, as signalled through
+//! [`ImageLoader::event`](ImageLoader::event).
+//!
+//! Decoders of progressive formats (e.g. progressive JPEG or interlaced PNG) can report the early, coarse passes,
+//! the first of which is shrunk into a placeholder, to be drawn blurred (see
+//! [`placeholder_filters`](ImageLoader::placeholder_filters)) until the full image replaces it.
+
+use {
+    super::{workers::WorkerPool, *},
+    crate::{event::RcEventQueue, prelude::*},
+    std::{
+        collections::HashMap,
+        sync::mpsc::{self, Receiver, Sender},
+    },
+};
+
+/// Largest width and height of placeholders, in pixels.
+pub const PLACEHOLDER_SIZE: u32 = 16;
+
+/// Error returned by an [`ImageDecoder`](ImageDecoder).
+pub type DecodeError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Decodes encoded images (e.g. PNG or JPEG) into pixels, on the threads of an [`ImageLoader`](ImageLoader).
+///
+/// Implemented for closures with the same signature as [`decode`](ImageDecoder::decode).
+pub trait ImageDecoder: Send + Sync + 'static {
+    /// Decodes `data`, passing each intermediate image of a progressive format to `progress`.
+    fn decode(
+        &self,
+        data: &[u8],
+        progress: &mut dyn FnMut(RasterImage),
+    ) -> Result<RasterImage, DecodeError>;
+}
+
+impl<F> ImageDecoder for F
+where
+    F: Fn(&[u8], &mut dyn FnMut(RasterImage)) -> Result<RasterImage, DecodeError>
+        + Send
+        + Sync
+        + 'static,
+{
+    #[inline]
+    fn decode(
+        &self,
+        data: &[u8],
+        progress: &mut dyn FnMut(RasterImage),
+    ) -> Result<RasterImage, DecodeError> {
+        self(data, progress)
+    }
+}
+
+/// How far an image of an [`ImageLoader`](ImageLoader) has loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LoadStage {
+    /// Still decoding; the image is a transparent pixel.
+    Pending,
+    /// The image is a low-resolution placeholder.
+    Placeholder,
+    Loaded,
+    /// Decoding failed; the image is left as it was (either transparent or the placeholder).
+    Failed,
+}
+
+/// Events emitted by an [`ImageLoader`](ImageLoader), after the image resource changed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageLoadEvent {
+    Placeholder(ResourceReference),
+    Loaded(ResourceReference),
+    Failed(ResourceReference, String),
+}
+
+enum Decoded {
+    Placeholder(RasterImage),
+    Complete(Result<RasterImage, DecodeError>),
+}
+
+/// Loads images, decoding them on a bounded number of background threads.
+pub struct ImageLoader {
+    pub event: RcEventQueue<ImageLoadEvent>,
+    workers: WorkerPool<(ResourceReference, ResourceData)>,
+    receiver: Receiver<(ResourceReference, Decoded)>,
+    stages: HashMap<ResourceReference, LoadStage>,
+}
+
+impl ImageLoader {
+    /// Creates a loader decoding images with `decoder`.
+    ///
+    /// By default, as many images are decoded at once as there are CPU cores.
+    pub fn new(decoder: impl ImageDecoder) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let max_workers = std::thread::available_parallelism().map_or(4, |count| count.get());
+        ImageLoader {
+            event: RcEventQueue::new(),
+            workers: WorkerPool::new(max_workers, move |(reference, data)| {
+                decode(&decoder, &sender, reference, data)
+            }),
+            receiver,
+            stages: HashMap::new(),
+        }
+    }
+
+    /// Sets how many images are decoded at once; the others wait for their turn in the order they were loaded.
+    pub fn set_max_workers(&mut self, max_workers: usize) {
+        self.workers.set_max_workers(max_workers);
+    }
+
+    /// Returns the number of images waiting for a thread to decode them.
+    pub fn queued(&self) -> usize {
+        self.workers.queued()
+    }
+
+    /// Creates an image resource in `display`, and starts decoding `data` into it.
+    pub fn load(
+        &mut self,
+        display: &mut dyn GraphicsDisplay,
+        data: ResourceData,
+    ) -> Result<ResourceReference, error::ResourceError> {
+        let reference = display.new_resource(ResourceDescriptor::Image(ImageData::Raw(
+            ResourceData::Data(SharedData::Static(&[0, 0, 0, 0])),
+            RasterImageInfo {
+                size: (1, 1),
+                format: RasterImageFormat::Rgba8,
+                color_space: ColorSpace::Srgb,
+            },
+        )))?;
+        self.stages.insert(reference, LoadStage::Pending);
+
+        self.workers.push((reference, data));
+        Ok(reference)
+    }
+
+    /// Swaps the images decoded since the last update into their resources, emitting an event for each.
+    ///
+    /// Results for resources which were [removed](ImageLoader::remove) in the meantime are discarded.
+    pub fn update(&mut self, display: &mut dyn GraphicsDisplay) {
+        while let Ok((reference, decoded)) = self.receiver.try_recv() {
+            let stage = match self.stages.get_mut(&reference) {
+                Some(stage) => stage,
+                None => continue,
+            };
+            let (image, complete) = match decoded {
+                Decoded::Placeholder(image) => (Ok(image), false),
+                Decoded::Complete(result) => (result, true),
+            };
+
+            let result = image.map_err(|err| err.to_string()).and_then(|image| {
+                display.update_resource(reference, image_data(image)).map_err(|err| err.to_string())
+            });
+            match (result, complete) {
+                (Ok(()), false) => {
+                    *stage = LoadStage::Placeholder;
+                    self.event.emit_owned(ImageLoadEvent::Placeholder(reference));
+                }
+                (Ok(()), true) => {
+                    *stage = LoadStage::Loaded;
+                    self.event.emit_owned(ImageLoadEvent::Loaded(reference));
+                }
+                (Err(err), _) => {
+                    *stage = LoadStage::Failed;
+                    self.event.emit_owned(ImageLoadEvent::Failed(reference, err));
+                }
+            }
+        }
+    }
+
+    /// Returns how far an image has loaded, or `None` if it wasn't loaded through this loader.
+    pub fn stage(&self, reference: ResourceReference) -> Option<LoadStage> {
+        self.stages.get(&reference).copied()
+    }
+
+    /// Returns the filters to draw an image with at `size`, which blur it while it's a placeholder.
+    ///
+    /// The blur spans about a pixel of the placeholder, hiding its blockiness.
+    pub fn placeholder_filters(&self, reference: ResourceReference, size: Size) -> Vec<FilterOp> {
+        if self.stage(reference) == Some(LoadStage::Placeholder) {
+            let sigma = size.width.max(size.height) / PLACEHOLDER_SIZE as f32;
+            vec![FilterOp::Blur(sigma, sigma)]
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Removes an image resource, discarding the result of its decoding if it's still in progress,
+    /// or not decoding it at all if it hasn't started yet.
+    pub fn remove(&mut self, display: &mut dyn GraphicsDisplay, reference: ResourceReference) {
+        self.workers.retain(|(queued, _)| *queued != reference);
+        self.stages.remove(&reference);
+        display.remove_resource(reference);
+    }
+}
+
+impl std::fmt::Debug for ImageLoader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ImageLoader").field("stages", &self.stages).finish()
+    }
+}
+
+/// Decodes an image on a thread of the pool, sending the placeholder and the result to the loader.
+fn decode(
+    decoder: &impl ImageDecoder,
+    sender: &Sender<(ResourceReference, Decoded)>,
+    reference: ResourceReference,
+    data: ResourceData,
+) {
+    let placeholder_sender = sender.clone();
+    let mut progress = Some(move |image: RasterImage| {
+        let _ = placeholder_sender.send((reference, Decoded::Placeholder(shrink(&image))));
+    });
+    let mut progress = |image| {
+        if let Some(progress) = progress.take() {
+            progress(image);
+        }
+    };
+    let result = match data {
+        ResourceData::File(path) => std::fs::read(path)
+            .map_err(DecodeError::from)
+            .and_then(|data| decoder.decode(&data, &mut progress)),
+        ResourceData::Data(SharedData::RefCount(data)) => decoder.decode(&data, &mut progress),
+        ResourceData::Data(SharedData::Static(data)) => decoder.decode(data, &mut progress),
+    };
+    // the loader may have been dropped, in which case nobody is interested anymore.
+    let _ = sender.send((reference, Decoded::Complete(result)));
+}
+
+pub(super) fn image_data(image: RasterImage) -> ImageData {
+    ImageData::Raw(ResourceData::Data(SharedData::RefCount(Arc::new(image.data))), image.info)
+}
+
+/// Box-filters `image` down to at most [`PLACEHOLDER_SIZE`](PLACEHOLDER_SIZE) pixels along either axis.
+fn shrink(image: &RasterImage) -> RasterImage {
+    let (width, height) = image.info.size;
+    let factor = width.max(height).div_ceil(PLACEHOLDER_SIZE).max(1);
+    let size = (width.div_ceil(factor), height.div_ceil(factor));
+
+    let mut data = Vec::with_capacity(size.0 as usize * size.1 as usize * 4);
+    for y in 0..size.1 {
+        for x in 0..size.0 {
+            let (mut sum, mut count) = ([0u32; 4], 0);
+            for sy in y * factor..((y + 1) * factor).min(height) {
+                for sx in x * factor..((x + 1) * factor).min(width) {
+                    if let Some(pixel) = image.pixel(sx, sy) {
+                        for (sum, component) in sum.iter_mut().zip(&pixel) {
+                            *sum += *component as u32;
+                        }
+                        count += 1;
+                    }
+                }
+            }
+            data.extend(sum.iter().map(|sum| (sum / count.max(1)) as u8));
+        }
+    }
+
+    RasterImage { data, info: RasterImageInfo { size, ..image.info } }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::display::recording::RecordingDisplay,
+        std::time::{Duration, Instant},
+    };
+
+    fn solid(size: u32, value: u8) -> RasterImage {
+        RasterImage {
+            data: vec![value; size as usize * size as usize * 4],
+            info: RasterImageInfo {
+                size: (size, size),
+                format: RasterImageFormat::Rgba8,
+                color_space: ColorSpace::Srgb,
+            },
+        }
+    }
+
+    fn load_until_done(
+        loader: &mut ImageLoader,
+        display: &mut RecordingDisplay,
+        reference: ResourceReference,
+    ) {
+        let start = Instant::now();
+        while matches!(loader.stage(reference), Some(LoadStage::Pending | LoadStage::Placeholder)) {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            std::thread::sleep(Duration::from_millis(1));
+            loader.update(display);
+        }
+    }
+
+    #[test]
+    fn test_image_loader() {
+        let mut loader = ImageLoader::new(
+            |data: &[u8], progress: &mut dyn FnMut(RasterImage)| -> Result<_, DecodeError> {
+                if data == b"broken" {
+                    return Err("unrecognized image format".into());
+                }
+                progress(solid(100, 10));
+                progress(solid(100, 20));
+                Ok(solid(100, 30))
+            },
+        );
+        let listener = loader.event.listen();
+        let mut display = RecordingDisplay::new((100, 100));
+
+        let image =
+            loader.load(&mut display, ResourceData::Data(SharedData::Static(b"image"))).unwrap();
+        assert_eq!(loader.stage(image), Some(LoadStage::Pending));
+        assert!(loader.placeholder_filters(image, Size::new(64.0, 32.0)).is_empty());
+        load_until_done(&mut loader, &mut display, image);
+
+        assert_eq!(
+            listener.peek(),
+            &[ImageLoadEvent::Placeholder(image), ImageLoadEvent::Loaded(image)]
+        );
+        match display.resource(image) {
+            Some(ResourceDescriptor::Image(ImageData::Raw(_, info))) => {
+                assert_eq!(info.size, (100, 100))
+            }
+            _ => panic!("the image should be replaced by the decoded pixels"),
+        }
+
+        let broken =
+            loader.load(&mut display, ResourceData::Data(SharedData::Static(b"broken"))).unwrap();
+        load_until_done(&mut loader, &mut display, broken);
+        assert_eq!(loader.stage(broken), Some(LoadStage::Failed));
+        assert_eq!(
+            listener.peek(),
+            &[ImageLoadEvent::Failed(broken, "unrecognized image format".into())]
+        );
+    }
+
+    #[test]
+    fn test_placeholder() {
+        let placeholder = shrink(&solid(100, 30));
+        assert_eq!(placeholder.info.size, (15, 15));
+        assert!(placeholder.data.iter().all(|&component| component == 30));
+        assert_eq!(shrink(&solid(4, 1)).info.size, (4, 4));
+
+        let (sender, receiver) = mpsc::channel();
+        let mut loader =
+            ImageLoader::new(|_: &[u8], _: &mut dyn FnMut(RasterImage)| Ok(solid(1, 0)));
+        loader.receiver = receiver;
+        let mut display = RecordingDisplay::new((100, 100));
+        let image =
+            display.new_resource(ResourceDescriptor::Image(image_data(solid(1, 0)))).unwrap();
+        loader.stages.insert(image, LoadStage::Pending);
+        sender.send((image, Decoded::Placeholder(placeholder))).unwrap();
+        loader.update(&mut display);
+        assert_eq!(loader.stage(image), Some(LoadStage::Placeholder));
+        assert_eq!(
+            loader.placeholder_filters(image, Size::new(64.0, 32.0)),
+            vec![FilterOp::Blur(4.0, 4.0)]
+        );
+    }
+}
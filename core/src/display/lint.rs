@@ -0,0 +1,440 @@
+//! Detection of wasteful display lists, such as items which are drawn but never seen.
+//!
+//! Unlike [validation](super::validate), nothing reported here is an error; the display list renders correctly,
+//! but does more work than needed. This is meant to be used in debug builds or tests of widgets:
+//!
+//! ```ignore
+//! let commands = builder.build();
+//! debug_assert!(lint::analyze(&commands).is_empty(), "{:?}", lint::analyze(&commands));
+//! ```
+//!
+//! The analysis is conservative: an item is only reported as overdrawn when it's certainly hidden,
+//! which is only known for opaque solid rectangles drawn with axis-aligned transformations and rectangular clips.
+
+use {super::*, std::fmt};
+
+/// Number of nested [`SaveLayer`](DisplayCommand::SaveLayer)s above which [`DeepLayerNesting`](LintWarning::DeepLayerNesting)
+/// is reported. Every layer is an offscreen buffer which is composited when restored.
+pub const MAX_LAYER_NESTING: usize = 3;
+
+/// A wasteful command in a display list, identified by its index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LintWarning {
+    /// The item covers no area (e.g. a zero-sized rectangle or empty text), so it draws nothing.
+    ZeroArea { index: usize },
+    /// The item is entirely outside the clip.
+    ClippedOut { index: usize },
+    /// The item is entirely covered by the opaque item at `covered_by`, which is drawn later.
+    Overdrawn { index: usize, covered_by: usize },
+    /// The layer is nested `depth` layers deep, exceeding [`MAX_LAYER_NESTING`](MAX_LAYER_NESTING).
+    DeepLayerNesting { index: usize, depth: usize },
+    /// The clip contains everything drawn while it applies (or nothing is drawn at all), so it clips nothing.
+    RedundantClip { index: usize },
+}
+
+impl LintWarning {
+    /// Returns the index of the offending command.
+    pub fn index(&self) -> usize {
+        match *self {
+            LintWarning::ZeroArea { index }
+            | LintWarning::ClippedOut { index }
+            | LintWarning::Overdrawn { index, .. }
+            | LintWarning::DeepLayerNesting { index, .. }
+            | LintWarning::RedundantClip { index } => index,
+        }
+    }
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            LintWarning::ZeroArea { index } => write!(f, "item at {} covers no area", index),
+            LintWarning::ClippedOut { index } => write!(f, "item at {} is clipped out", index),
+            LintWarning::Overdrawn { index, covered_by } => {
+                write!(f, "item at {} is overdrawn by the opaque item at {}", index, covered_by)
+            }
+            LintWarning::DeepLayerNesting { index, depth } => {
+                write!(f, "layer at {} is nested {} layers deep", index, depth)
+            }
+            LintWarning::RedundantClip { index } => write!(f, "clip at {} clips nothing", index),
+        }
+    }
+}
+
+type Transform = euclid::Transform2D<f32, LogicalPixel, LogicalPixel>;
+
+#[derive(Clone)]
+struct State {
+    transform: Transform,
+    /// Bounds of the clip, in the space of the display list.
+    clip: Option<Rect>,
+    /// Whether the clip is exactly `clip`, rather than some shape within it.
+    clip_exact: bool,
+    /// Indices of the layers drawn into, innermost last.
+    layers: Vec<usize>,
+}
+
+impl State {
+    fn axis_aligned(&self) -> bool {
+        self.transform.m12 == 0.0 && self.transform.m21 == 0.0
+    }
+}
+
+struct Drawn {
+    index: usize,
+    /// Visible bounds, in the space of the display list.
+    bounds: Rect,
+    layers: Vec<usize>,
+}
+
+struct ActiveClip {
+    index: usize,
+    /// Exact clip rectangle, if known.
+    rect: Option<Rect>,
+    depth: usize,
+    /// Union of the bounds of everything drawn within the clip, or `None` if unknown.
+    drawn: Option<Rect>,
+    any_drawn: bool,
+}
+
+fn is_opaque(paint: &GraphicsDisplayPaint) -> bool {
+    matches!(paint, GraphicsDisplayPaint::Fill(StyleColor::Color(color)) if color.alpha >= 1.0)
+}
+
+fn is_fill(paint: &GraphicsDisplayPaint) -> bool {
+    match paint {
+        GraphicsDisplayPaint::Fill(_) => true,
+        GraphicsDisplayPaint::Stroke(_) => false,
+        GraphicsDisplayPaint::Blend(paint, _) => is_fill(paint),
+    }
+}
+
+fn is_zero_area(item: &DisplayItem) -> bool {
+    let empty = |rect: &Rect| rect.size.width <= 0.0 || rect.size.height <= 0.0;
+    match item {
+        DisplayItem::Graphics(item) => match item {
+            GraphicsDisplayItem::Line { a, b, .. } => a == b,
+            GraphicsDisplayItem::Rectangle { rect, paint }
+            | GraphicsDisplayItem::RoundRectangle { rect, paint, .. } => {
+                is_fill(paint) && empty(rect)
+            }
+            GraphicsDisplayItem::Ellipse { radii, paint, .. } => {
+                is_fill(paint) && (radii.x <= 0.0 || radii.y <= 0.0)
+            }
+            GraphicsDisplayItem::Image { dst, .. } => empty(dst),
+            GraphicsDisplayItem::Path { path, paint, .. } => {
+                path.is_empty() || (is_fill(paint) && empty(&vector_path_bounds(path)))
+            }
+            GraphicsDisplayItem::Mesh { indices, .. } => indices.len() < 3 || empty(&item.bounds()),
+        },
+        DisplayItem::Text(text) => {
+            text.size <= 0.0
+                || match &text.text {
+                    // decorations of whitespace are still visible.
+                    DisplayText::Simple(string) => {
+                        string.is_empty()
+                            || (string.trim().is_empty() && text.decorations.is_empty())
+                    }
+                    DisplayText::Shaped(glyphs) => glyphs.is_empty(),
+                }
+        }
+        DisplayItem::TextBlob(blob) => empty(&blob.extents),
+    }
+}
+
+/// Reports the clips pushed at `depth` or deeper which turned out to be redundant, and stops tracking them.
+fn end_clips(clips: &mut Vec<ActiveClip>, depth: usize, warnings: &mut Vec<LintWarning>) {
+    while clips.last().is_some_and(|clip| clip.depth >= depth) {
+        let clip = clips.pop().unwrap();
+        let redundant = match (clip.rect, clip.drawn) {
+            _ if !clip.any_drawn => true,
+            (Some(rect), Some(drawn)) => rect.contains_rect(&drawn),
+            _ => false,
+        };
+        if redundant {
+            warnings.push(LintWarning::RedundantClip { index: clip.index });
+        }
+    }
+}
+
+/// Analyzes a display list, returning the warnings ordered by the index of the offending command.
+///
+/// The display list should be [valid](super::validate::validate_display_list); commands after an unmatched
+/// [`Restore`](DisplayCommand::Restore) are analyzed as if at the top level.
+pub fn analyze(display_list: &[DisplayCommand]) -> Vec<LintWarning> {
+    let mut warnings = Vec::new();
+    let mut state = State {
+        transform: Transform::identity(),
+        clip: None,
+        clip_exact: true,
+        layers: Vec::new(),
+    };
+    let mut stack = Vec::new();
+    let mut drawn: Vec<Drawn> = Vec::new();
+    let mut clips: Vec<ActiveClip> = Vec::new();
+
+    for (index, command) in display_list.iter().enumerate() {
+        // bounds of what this command draws, before clipping, and the area it certainly covers.
+        let (bounds, opaque) = match command {
+            DisplayCommand::Item(item, filters) => {
+                if is_zero_area(item) {
+                    warnings.push(LintWarning::ZeroArea { index });
+                    continue;
+                }
+                let opaque = match item {
+                    DisplayItem::Graphics(GraphicsDisplayItem::Rectangle { rect, paint })
+                        if filters.is_empty() && is_opaque(paint) && state.axis_aligned() =>
+                    {
+                        Some(state.transform.transform_rect(rect))
+                    }
+                    _ => None,
+                };
+                // filters such as blurs and shadows draw outside of the bounds of the item.
+                let bounds = if filters.is_empty() { item.bounds().ok() } else { None };
+                (bounds.map(|bounds| state.transform.transform_rect(&bounds)), opaque)
+            }
+            DisplayCommand::BackdropFilter(clip, _) => {
+                (Some(state.transform.transform_rect(&clip.bounds())), None)
+            }
+            DisplayCommand::Clear(color) => {
+                let everything =
+                    Rect::new(Point::new(f32::MIN, f32::MIN) / 2.0, Size::new(f32::MAX, f32::MAX));
+                let opaque = if color.alpha >= 1.0 { Some(everything) } else { None };
+                // the clip is what limits a clear, so any clip around it is meaningful.
+                for clip in &mut clips {
+                    clip.any_drawn = true;
+                    clip.drawn = None;
+                }
+                (None, opaque)
+            }
+            DisplayCommand::Hole(_, rect) => (Some(state.transform.transform_rect(rect)), None),
+            DisplayCommand::Extension(command) => {
+                (command.bounds().map(|bounds| state.transform.transform_rect(&bounds)), None)
+            }
+            DisplayCommand::Clip(_, ClipOp::Difference) => {
+                // a hole doesn't limit the bounds of what's drawn, but what's drawn is no longer certainly opaque.
+                state.clip_exact = false;
+                continue;
+            }
+            DisplayCommand::Clip(clip, ClipOp::Intersect) => {
+                let rect = match clip {
+                    DisplayClip::Rectangle { rect, .. } if state.axis_aligned() => {
+                        Some(state.transform.transform_rect(rect))
+                    }
+                    _ => None,
+                };
+                let bounds = state.transform.transform_rect(&clip.bounds());
+                state.clip_exact = state.clip_exact && rect.is_some();
+                state.clip = Some(match state.clip {
+                    Some(outer) => outer.intersection(&bounds).unwrap_or_default(),
+                    None => bounds,
+                });
+                clips.push(ActiveClip {
+                    index,
+                    rect,
+                    depth: stack.len(),
+                    drawn: Some(Rect::default()),
+                    any_drawn: false,
+                });
+                continue;
+            }
+            DisplayCommand::Save => {
+                stack.push(state.clone());
+                continue;
+            }
+            DisplayCommand::SaveLayer(..) => {
+                stack.push(state.clone());
+                state.layers.push(index);
+                if state.layers.len() > MAX_LAYER_NESTING {
+                    warnings
+                        .push(LintWarning::DeepLayerNesting { index, depth: state.layers.len() });
+                }
+                continue;
+            }
+            DisplayCommand::Restore => {
+                end_clips(&mut clips, stack.len(), &mut warnings);
+                if let Some(saved) = stack.pop() {
+                    state = saved;
+                }
+                continue;
+            }
+            DisplayCommand::Translate(v) => {
+                state.transform = state.transform.pre_translate(*v);
+                continue;
+            }
+            DisplayCommand::Scale(v) => {
+                state.transform = state.transform.pre_scale(v.x, v.y);
+                continue;
+            }
+            DisplayCommand::Rotate(angle) => {
+                state.transform = state.transform.pre_rotate(*angle);
+                continue;
+            }
+        };
+
+        if let DisplayCommand::Item(..)
+        | DisplayCommand::BackdropFilter(..)
+        | DisplayCommand::Hole(..)
+        | DisplayCommand::Extension(..) = command
+        {
+            for clip in &mut clips {
+                clip.any_drawn = true;
+                clip.drawn = match (clip.drawn, bounds) {
+                    (Some(drawn), Some(bounds)) => Some(drawn.union(&bounds)),
+                    _ => None,
+                };
+            }
+        }
+
+        let visible = match (bounds, state.clip) {
+            (Some(bounds), Some(clip)) => match bounds.intersection(&clip) {
+                Some(visible) if !visible.is_empty_or_negative() => Some(visible),
+                _ => {
+                    warnings.push(LintWarning::ClippedOut { index });
+                    continue;
+                }
+            },
+            (bounds, _) => bounds,
+        };
+
+        let opaque = match (opaque, state.clip) {
+            (opaque, None) if state.clip_exact => opaque,
+            (Some(opaque), Some(clip)) if state.clip_exact => opaque.intersection(&clip),
+            _ => None,
+        };
+        if let Some(opaque) = opaque {
+            for earlier in drawn.iter_mut().filter(|earlier| {
+                // a layer with an opaque item may still be translucent as a whole.
+                earlier.layers.starts_with(&state.layers) && opaque.contains_rect(&earlier.bounds)
+            }) {
+                warnings.push(LintWarning::Overdrawn { index: earlier.index, covered_by: index });
+                earlier.index = usize::MAX;
+            }
+            drawn.retain(|earlier| earlier.index != usize::MAX);
+        }
+
+        if let Some(bounds) = visible {
+            drawn.push(Drawn { index, bounds, layers: state.layers.clone() });
+        }
+    }
+    end_clips(&mut clips, 0, &mut warnings);
+
+    warnings.sort_by_key(LintWarning::index);
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f32, y: f32, w: f32, h: f32) -> Rect {
+        Rect::new(Point::new(x, y), Size::new(w, h))
+    }
+
+    fn fill(rect: Rect, alpha: f32) -> DisplayCommand {
+        DisplayCommand::Item(
+            DisplayItem::Graphics(GraphicsDisplayItem::Rectangle {
+                rect,
+                paint: GraphicsDisplayPaint::Fill(Color::new(1.0, 0.0, 0.0, alpha).into()),
+            }),
+            vec![],
+        )
+    }
+
+    fn clip(rect: Rect) -> DisplayCommand {
+        DisplayCommand::Clip(DisplayClip::Rectangle { rect, antialias: false }, ClipOp::Intersect)
+    }
+
+    #[test]
+    fn test_lint() {
+        assert!(analyze(&[
+            fill(rect(0.0, 0.0, 10.0, 10.0), 1.0),
+            fill(rect(5.0, 5.0, 10.0, 10.0), 1.0),
+            DisplayCommand::Save,
+            clip(rect(0.0, 0.0, 10.0, 10.0)),
+            fill(rect(5.0, 5.0, 10.0, 10.0), 0.5),
+            DisplayCommand::Restore,
+        ])
+        .is_empty());
+
+        assert_eq!(
+            analyze(&[
+                fill(rect(0.0, 0.0, 0.0, 10.0), 1.0),
+                fill(rect(2.0, 2.0, 5.0, 5.0), 0.5),
+                DisplayCommand::Save,
+                DisplayCommand::Translate(Vector::new(10.0, 10.0)),
+                // covers (10, 10) to (30, 30).
+                fill(rect(0.0, 0.0, 20.0, 20.0), 1.0),
+                DisplayCommand::Restore,
+                fill(rect(0.0, 0.0, 40.0, 40.0), 1.0),
+            ]),
+            &[
+                LintWarning::ZeroArea { index: 0 },
+                LintWarning::Overdrawn { index: 1, covered_by: 6 },
+                LintWarning::Overdrawn { index: 4, covered_by: 6 },
+            ]
+        );
+
+        // translucent and rotated items don't cover anything, and neither do layers for items outside of them.
+        assert!(analyze(&[
+            fill(rect(0.0, 0.0, 10.0, 10.0), 1.0),
+            fill(rect(0.0, 0.0, 20.0, 20.0), 0.5),
+            DisplayCommand::Save,
+            DisplayCommand::Rotate(Angle::degrees(45.0)),
+            fill(rect(-100.0, -100.0, 200.0, 200.0), 1.0),
+            DisplayCommand::Restore,
+            DisplayCommand::SaveLayer(0.5, BlendMode::Normal),
+            fill(rect(0.0, 0.0, 20.0, 20.0), 1.0),
+            DisplayCommand::Restore,
+        ])
+        .is_empty());
+
+        // items with a hole cut into them don't cover anything either.
+        assert!(analyze(&[
+            fill(rect(0.0, 0.0, 10.0, 10.0), 1.0),
+            DisplayCommand::Save,
+            DisplayCommand::Clip(
+                DisplayClip::Rectangle { rect: rect(2.0, 2.0, 5.0, 5.0), antialias: false },
+                ClipOp::Difference,
+            ),
+            fill(rect(0.0, 0.0, 20.0, 20.0), 1.0),
+            DisplayCommand::Restore,
+        ])
+        .is_empty());
+
+        assert_eq!(
+            analyze(&[
+                DisplayCommand::Save,
+                clip(rect(0.0, 0.0, 10.0, 10.0)),
+                fill(rect(20.0, 0.0, 10.0, 10.0), 1.0),
+                DisplayCommand::Restore,
+                DisplayCommand::Save,
+                clip(rect(0.0, 0.0, 100.0, 100.0)),
+                fill(rect(10.0, 10.0, 10.0, 10.0), 0.5),
+                DisplayCommand::Restore,
+                DisplayCommand::Save,
+                clip(rect(0.0, 0.0, 100.0, 100.0)),
+                DisplayCommand::Clear(Color::new(0.0, 0.0, 0.0, 0.5)),
+                DisplayCommand::Restore,
+                clip(rect(0.0, 0.0, 100.0, 100.0)),
+                fill(rect(0.0, 0.0, 10.0, 10.0), 0.5),
+            ]),
+            &[
+                LintWarning::ClippedOut { index: 2 },
+                LintWarning::RedundantClip { index: 5 },
+                LintWarning::RedundantClip { index: 12 },
+            ]
+        );
+
+        let mut nested = vec![DisplayCommand::SaveLayer(0.5, BlendMode::Normal); 5];
+        nested.push(fill(rect(0.0, 0.0, 10.0, 10.0), 0.5));
+        nested.extend(vec![DisplayCommand::Restore; 5]);
+        assert_eq!(
+            analyze(&nested),
+            &[
+                LintWarning::DeepLayerNesting { index: 3, depth: 4 },
+                LintWarning::DeepLayerNesting { index: 4, depth: 5 },
+            ]
+        );
+    }
+}
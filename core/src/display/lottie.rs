@@ -0,0 +1,789 @@
+//! Playback of Lottie (Bodymovin) vector animations as display commands.
+//!
+//! Frames are produced as plain [`DisplayCommand`](DisplayCommand)s (paths, fills, strokes and transforms),
+//! so animations play on any display. Time is driven by the caller, typically by accumulating the
+//! [`FrameTime`](crate::pacer::FrameTime) delta:
+//!
+//! ```ignore
+//! let animation = Animation::from_json(include_str!("loader.json"))?;
+//!
+//! // in update:
+//! self.elapsed += frame_time.delta_time;
+//! self.command_group.repaint();
+//!
+//! // in draw:
+//! self.command_group.push(display, &animation.render_at(self.elapsed), Default::default(), None, None)?;
+//! ```
+//!
+//! Only a subset of the format is supported: shape layers (groups, rectangles, ellipses, paths, fills,
+//! strokes and group transforms) and solid layers, with layer parenting and keyframes eased through
+//! their cubic Bézier curves. Masks, mattes, precompositions, images, text, effects, trim paths,
+//! gradients, skew and rounded rectangle corners are ignored.
+
+use {
+    super::*, crate::error::LottieError, serde::Deserialize, serde_json::Value, std::time::Duration,
+};
+
+type Matrix = euclid::Transform2D<f32, LogicalPixel, LogicalPixel>;
+
+/// A parsed Lottie animation.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Animation {
+    /// Frames per second.
+    #[serde(rename = "fr")]
+    pub frame_rate: f32,
+    /// The first frame of the animation.
+    #[serde(rename = "ip")]
+    pub in_point: f32,
+    /// The frame the animation ends at (exclusive).
+    #[serde(rename = "op")]
+    pub out_point: f32,
+    #[serde(rename = "w")]
+    pub width: f32,
+    #[serde(rename = "h")]
+    pub height: f32,
+    #[serde(default)]
+    layers: Vec<Layer>,
+}
+
+impl Animation {
+    /// Parses an animation from Lottie JSON.
+    pub fn from_json(json: &str) -> Result<Self, LottieError> {
+        Self::validate(serde_json::from_str(json)?)
+    }
+
+    /// Parses an animation from Lottie JSON bytes.
+    pub fn from_slice(json: &[u8]) -> Result<Self, LottieError> {
+        Self::validate(serde_json::from_slice(json)?)
+    }
+
+    fn validate(animation: Animation) -> Result<Self, LottieError> {
+        if !(animation.frame_rate > 0.0 && animation.out_point > animation.in_point) {
+            return Err(LottieError::InvalidTiming);
+        }
+        Ok(animation)
+    }
+
+    /// Returns the size of the animation canvas.
+    #[inline]
+    pub fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+
+    /// Returns the length of a single play-through.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f32((self.out_point - self.in_point) / self.frame_rate)
+    }
+
+    /// Returns the frame shown after `elapsed` time, looping the animation.
+    pub fn frame_at(&self, elapsed: Duration) -> f32 {
+        let frames = self.out_point - self.in_point;
+        self.in_point + (elapsed.as_secs_f32() * self.frame_rate) % frames
+    }
+
+    /// Returns the display commands of the frame shown after `elapsed` time, looping the animation.
+    pub fn render_at(&self, elapsed: Duration) -> Vec<DisplayCommand> {
+        self.render(self.frame_at(elapsed))
+    }
+
+    /// Returns the display commands of a (possibly fractional) frame.
+    ///
+    /// The commands draw into a `width` by `height` canvas at the origin, and leave the draw state untouched.
+    pub fn render(&self, frame: f32) -> Vec<DisplayCommand> {
+        let mut commands = Vec::new();
+        // the first layer is the top-most.
+        for layer in self.layers.iter().rev() {
+            // only solid and shape layers have content; null layers only serve as parents.
+            if layer.hidden
+                || (layer.kind != 1 && layer.kind != 4)
+                || frame < layer.in_point
+                || frame >= layer.out_point
+            {
+                continue;
+            }
+
+            let time = frame - layer.start_time;
+            let opacity = layer.transform.opacity(time);
+            if opacity <= 0.0 {
+                continue;
+            }
+
+            commands.push(if opacity < 1.0 {
+                DisplayCommand::SaveLayer(opacity, BlendMode::Normal)
+            } else {
+                DisplayCommand::Save
+            });
+            for ancestor in self.ancestors(layer).iter().rev() {
+                ancestor.transform.push_commands(frame - ancestor.start_time, &mut commands);
+            }
+            layer.transform.push_commands(time, &mut commands);
+
+            if layer.kind == 1 {
+                commands.push(DisplayCommand::Item(
+                    DisplayItem::Graphics(GraphicsDisplayItem::Rectangle {
+                        rect: Rect::new(
+                            Point::zero(),
+                            Size::new(layer.solid_width, layer.solid_height),
+                        ),
+                        paint: GraphicsDisplayPaint::Fill(parse_hex(&layer.solid_color).into()),
+                    }),
+                    Vec::new(),
+                ));
+            } else {
+                render_shapes(&layer.shapes, time, &Matrix::identity(), &mut commands);
+            }
+
+            commands.push(DisplayCommand::Restore);
+        }
+        commands
+    }
+
+    /// Returns the parent, grandparent, etc. of a layer.
+    fn ancestors(&self, layer: &Layer) -> Vec<&Layer> {
+        let mut ancestors = Vec::new();
+        let mut parent = layer.parent;
+        // guards against cycles.
+        while let Some(index) = parent.filter(|_| ancestors.len() < self.layers.len()) {
+            match self.layers.iter().find(|layer| layer.index == Some(index)) {
+                Some(layer) => {
+                    ancestors.push(layer);
+                    parent = layer.parent;
+                }
+                None => break,
+            }
+        }
+        ancestors
+    }
+}
+
+fn far() -> f32 {
+    f32::INFINITY
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Layer {
+    /// 0 = precomposition, 1 = solid, 2 = image, 3 = null, 4 = shape, 5 = text.
+    #[serde(rename = "ty")]
+    kind: u32,
+    #[serde(rename = "ind")]
+    index: Option<i64>,
+    parent: Option<i64>,
+    #[serde(rename = "ip", default)]
+    in_point: f32,
+    #[serde(rename = "op", default = "far")]
+    out_point: f32,
+    #[serde(rename = "st", default)]
+    start_time: f32,
+    #[serde(rename = "hd", default)]
+    hidden: bool,
+    #[serde(rename = "ks", default)]
+    transform: Transform,
+    #[serde(default)]
+    shapes: Vec<Shape>,
+    #[serde(rename = "sw", default)]
+    solid_width: f32,
+    #[serde(rename = "sh", default)]
+    solid_height: f32,
+    #[serde(rename = "sc", default)]
+    solid_color: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct Transform {
+    #[serde(rename = "a", default)]
+    anchor: Property,
+    #[serde(rename = "p", default)]
+    position: Property,
+    #[serde(rename = "s", default)]
+    scale: Property,
+    #[serde(rename = "r", default)]
+    rotation: Property,
+    #[serde(rename = "o", default)]
+    opacity: Property,
+}
+
+impl Transform {
+    fn opacity(&self, time: f32) -> f32 {
+        self.opacity.value(time, 100.0) / 100.0
+    }
+
+    fn components(&self, time: f32) -> (Vector, Vector, Angle, Vector) {
+        let anchor = self.anchor.vector(time, 0.0);
+        let position = self.position.vector(time, 0.0);
+        let scale = self.scale.vector(time, 100.0) / 100.0;
+        let rotation = Angle::degrees(self.rotation.value(time, 0.0));
+        (anchor, position, rotation, scale)
+    }
+
+    fn push_commands(&self, time: f32, commands: &mut Vec<DisplayCommand>) {
+        let (anchor, position, rotation, scale) = self.components(time);
+        commands.push(DisplayCommand::Translate(position));
+        commands.push(DisplayCommand::Rotate(rotation));
+        commands.push(DisplayCommand::Scale(scale));
+        commands.push(DisplayCommand::Translate(-anchor));
+    }
+
+    fn matrix(&self, time: f32) -> Matrix {
+        let (anchor, position, rotation, scale) = self.components(time);
+        Matrix::create_translation(-anchor.x, -anchor.y)
+            .post_scale(scale.x, scale.y)
+            .post_rotate(rotation)
+            .post_translate(position)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "ty")]
+enum Shape {
+    #[serde(rename = "gr")]
+    Group {
+        #[serde(rename = "it", default)]
+        items: Vec<Shape>,
+    },
+    #[serde(rename = "rc")]
+    Rectangle {
+        #[serde(rename = "p", default)]
+        position: Property,
+        #[serde(rename = "s", default)]
+        size: Property,
+    },
+    #[serde(rename = "el")]
+    Ellipse {
+        #[serde(rename = "p", default)]
+        position: Property,
+        #[serde(rename = "s", default)]
+        size: Property,
+    },
+    #[serde(rename = "sh")]
+    Path {
+        #[serde(rename = "ks", default)]
+        bezier: Property,
+    },
+    #[serde(rename = "fl")]
+    Fill {
+        #[serde(rename = "c", default)]
+        color: Property,
+        #[serde(rename = "o", default)]
+        opacity: Property,
+    },
+    #[serde(rename = "st")]
+    Stroke {
+        #[serde(rename = "c", default)]
+        color: Property,
+        #[serde(rename = "o", default)]
+        opacity: Property,
+        #[serde(rename = "w", default)]
+        width: Property,
+        #[serde(rename = "lc", default)]
+        cap: u8,
+        #[serde(rename = "lj", default)]
+        join: u8,
+    },
+    #[serde(rename = "tr")]
+    Transform(Transform),
+    #[serde(other)]
+    Unsupported,
+}
+
+/// A single sub-path, in layer space.
+struct SubPath {
+    path: VectorPath,
+    is_closed: bool,
+}
+
+/// Draws a group, returning its geometry for the styles of the parent group.
+fn render_shapes(
+    items: &[Shape],
+    time: f32,
+    parent: &Matrix,
+    commands: &mut Vec<DisplayCommand>,
+) -> Vec<SubPath> {
+    let matrix = items
+        .iter()
+        .find_map(|item| match item {
+            Shape::Transform(transform) => Some(transform.matrix(time).post_transform(parent)),
+            _ => None,
+        })
+        .unwrap_or(*parent);
+    let stroke_scale = matrix.determinant().abs().sqrt();
+
+    // styles apply to the geometry listed above them, whereas the first item is drawn on top.
+    let mut geometry = Vec::new();
+    let mut blocks: Vec<Vec<DisplayCommand>> = Vec::new();
+    for item in items {
+        match item {
+            Shape::Group { items } => {
+                let mut block = Vec::new();
+                geometry.extend(render_shapes(items, time, &matrix, &mut block));
+                blocks.push(block);
+            }
+            Shape::Rectangle { position, size } => {
+                let center = position.vector(time, 0.0).to_point();
+                let half = size.vector(time, 0.0) / 2.0;
+                let corners = [
+                    Point::new(center.x + half.x, center.y - half.y),
+                    Point::new(center.x + half.x, center.y + half.y),
+                    Point::new(center.x - half.x, center.y + half.y),
+                    Point::new(center.x - half.x, center.y - half.y),
+                ];
+                let mut path = vec![VectorPathEvent::MoveTo { to: corners[0] }];
+                path.extend(corners[1..].iter().map(|&to| VectorPathEvent::LineTo { to }));
+                geometry.push(SubPath { path: transform_path(path, &matrix), is_closed: true });
+            }
+            Shape::Ellipse { position, size } => {
+                let center = position.vector(time, 0.0).to_point();
+                let radii = size.vector(time, 0.0) / 2.0;
+                geometry.push(SubPath {
+                    path: transform_path(ellipse_path(center, radii), &matrix),
+                    is_closed: true,
+                });
+            }
+            Shape::Path { bezier } => {
+                if let Some(sub_path) = bezier_path(&bezier.values(time), bezier.closed()) {
+                    geometry.push(SubPath {
+                        path: transform_path(sub_path.path, &matrix),
+                        is_closed: sub_path.is_closed,
+                    });
+                }
+            }
+            Shape::Fill { color, opacity } => {
+                let paint = GraphicsDisplayPaint::Fill(color_of(color, opacity, time).into());
+                blocks.push(
+                    geometry
+                        .iter()
+                        .map(|sub_path| path_command(sub_path, true, paint.clone()))
+                        .collect(),
+                );
+            }
+            Shape::Stroke { color, opacity, width, cap, join } => {
+                let paint = GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                    color: color_of(color, opacity, time).into(),
+                    thickness: width.value(time, 1.0) * stroke_scale,
+                    cap: match cap {
+                        2 => LineCap::Round,
+                        3 => LineCap::Square,
+                        _ => LineCap::Flat,
+                    },
+                    join: match join {
+                        2 => LineJoin::Round,
+                        3 => LineJoin::Bevel,
+                        _ => LineJoin::Miter,
+                    },
+                    ..Default::default()
+                });
+                blocks.push(
+                    geometry
+                        .iter()
+                        .map(|sub_path| path_command(sub_path, sub_path.is_closed, paint.clone()))
+                        .collect(),
+                );
+            }
+            Shape::Transform(_) | Shape::Unsupported => {}
+        }
+    }
+
+    commands.extend(blocks.into_iter().rev().flatten());
+    geometry
+}
+
+fn path_command(
+    sub_path: &SubPath,
+    is_closed: bool,
+    paint: GraphicsDisplayPaint,
+) -> DisplayCommand {
+    DisplayCommand::Item(
+        DisplayItem::Graphics(GraphicsDisplayItem::Path {
+            path: sub_path.path.clone(),
+            is_closed,
+            paint,
+        }),
+        Vec::new(),
+    )
+}
+
+fn color_of(color: &Property, opacity: &Property, time: f32) -> Color {
+    let components = color.values(time);
+    let component = |i: usize| components.get(i).copied().unwrap_or(0.0);
+    let alpha = components.get(3).copied().unwrap_or(1.0) * opacity.value(time, 100.0) / 100.0;
+    Color::new(component(0), component(1), component(2), alpha)
+}
+
+fn parse_hex(hex: &str) -> Color {
+    let hex = hex.trim_start_matches('#');
+    let component =
+        |i: usize| hex.get(i..i + 2).and_then(|c| u8::from_str_radix(c, 16).ok()).unwrap_or(0);
+    Color::new(
+        component(0) as f32 / 255.0,
+        component(2) as f32 / 255.0,
+        component(4) as f32 / 255.0,
+        1.0,
+    )
+}
+
+fn transform_path(path: VectorPath, matrix: &Matrix) -> VectorPath {
+    let point = |p: Point| matrix.transform_point(p);
+    path.into_iter()
+        .map(|event| match event {
+            VectorPathEvent::MoveTo { to } => VectorPathEvent::MoveTo { to: point(to) },
+            VectorPathEvent::LineTo { to } => VectorPathEvent::LineTo { to: point(to) },
+            VectorPathEvent::CubicTo { c1, c2, to } => {
+                VectorPathEvent::CubicTo { c1: point(c1), c2: point(c2), to: point(to) }
+            }
+            event => event,
+        })
+        .collect()
+}
+
+fn ellipse_path(center: Point, radii: Vector) -> VectorPath {
+    // control point distance approximating a quarter circle.
+    const KAPPA: f32 = 0.552_284_8;
+    let (rx, ry) = (radii.x, radii.y);
+    let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+    let p = |x: f32, y: f32| Point::new(center.x + x, center.y + y);
+    vec![
+        VectorPathEvent::MoveTo { to: p(rx, 0.0) },
+        VectorPathEvent::CubicTo { c1: p(rx, ky), c2: p(kx, ry), to: p(0.0, ry) },
+        VectorPathEvent::CubicTo { c1: p(-kx, ry), c2: p(-rx, ky), to: p(-rx, 0.0) },
+        VectorPathEvent::CubicTo { c1: p(-rx, -ky), c2: p(-kx, -ry), to: p(0.0, -ry) },
+        VectorPathEvent::CubicTo { c1: p(kx, -ry), c2: p(rx, -ky), to: p(rx, 0.0) },
+    ]
+}
+
+/// Builds a path from flattened Bézier values (see [`flatten_bezier`]).
+fn bezier_path(values: &[f32], is_closed: bool) -> Option<SubPath> {
+    let count = values.len() / 6;
+    if count == 0 {
+        return None;
+    }
+    let point =
+        |offset: usize, i: usize| Point::new(values[offset + i * 2], values[offset + i * 2 + 1]);
+    let vertex = |i| point(0, i);
+    let in_tangent = |i| point(count * 2, i).to_vector();
+    let out_tangent = |i| point(count * 4, i).to_vector();
+
+    let mut path = vec![VectorPathEvent::MoveTo { to: vertex(0) }];
+    let segments = if is_closed { count } else { count - 1 };
+    for i in 0..segments {
+        let next = (i + 1) % count;
+        path.push(VectorPathEvent::CubicTo {
+            c1: vertex(i) + out_tangent(i),
+            c2: vertex(next) + in_tangent(next),
+            to: vertex(next),
+        });
+    }
+    Some(SubPath { path, is_closed })
+}
+
+/// An animatable property, with every value flattened into numbers.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(from = "RawProperty")]
+enum Property {
+    Static(Vec<f32>),
+    Animated(Vec<Keyframe>),
+    /// Position given as separately animated X and Y components.
+    Split(Box<Property>, Box<Property>),
+    /// A Bézier path; see [`flatten_bezier`].
+    Bezier(Box<Property>, bool),
+}
+
+impl Default for Property {
+    fn default() -> Self {
+        Property::Static(Vec::new())
+    }
+}
+
+impl Property {
+    fn values(&self, time: f32) -> Vec<f32> {
+        match self {
+            Property::Static(values) => values.clone(),
+            Property::Animated(keyframes) => interpolate(keyframes, time),
+            Property::Split(x, y) => vec![x.value(time, 0.0), y.value(time, 0.0)],
+            Property::Bezier(property, _) => property.values(time),
+        }
+    }
+
+    fn value(&self, time: f32, default: f32) -> f32 {
+        self.values(time).first().copied().unwrap_or(default)
+    }
+
+    fn vector(&self, time: f32, default: f32) -> Vector {
+        let values = self.values(time);
+        Vector::new(
+            values.first().copied().unwrap_or(default),
+            values.get(1).copied().unwrap_or(default),
+        )
+    }
+
+    fn closed(&self) -> bool {
+        match self {
+            Property::Bezier(_, closed) => *closed,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Keyframe {
+    time: f32,
+    start: Vec<f32>,
+    /// Explicit end value, used by older exports instead of the start value of the next keyframe.
+    end: Option<Vec<f32>>,
+    hold: bool,
+    /// Easing curve control points, leaving this keyframe and entering the next.
+    ease_out: (f32, f32),
+    ease_in: (f32, f32),
+}
+
+fn interpolate(keyframes: &[Keyframe], time: f32) -> Vec<f32> {
+    let index = match keyframes.iter().rposition(|keyframe| keyframe.time <= time) {
+        Some(index) => index,
+        None => {
+            return keyframes.first().map(|keyframe| keyframe.start.clone()).unwrap_or_default()
+        }
+    };
+    let keyframe = &keyframes[index];
+    let next = match keyframes.get(index + 1) {
+        Some(next) if !keyframe.hold && next.time > keyframe.time => next,
+        _ => return keyframe.start.clone(),
+    };
+
+    let end = keyframe.end.as_ref().unwrap_or(&next.start);
+    let progress = ease(
+        keyframe.ease_out,
+        keyframe.ease_in,
+        (time - keyframe.time) / (next.time - keyframe.time),
+    );
+    keyframe.start.iter().zip(end).map(|(a, b)| a + (b - a) * progress).collect()
+}
+
+/// Evaluates a CSS-style cubic Bézier easing curve (from (0, 0) to (1, 1)) at `x`.
+fn ease((x1, y1): (f32, f32), (x2, y2): (f32, f32), x: f32) -> f32 {
+    let bezier = |a: f32, b: f32, t: f32| {
+        let u = 1.0 - t;
+        3.0 * u * u * t * a + 3.0 * u * t * t * b + t * t * t
+    };
+    // the curve is monotonic in x, so bisect for the parameter.
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..24 {
+        let t = (low + high) / 2.0;
+        if bezier(x1, x2, t) < x {
+            low = t;
+        } else {
+            high = t;
+        }
+    }
+    bezier(y1, y2, (low + high) / 2.0)
+}
+
+#[derive(Deserialize)]
+struct RawProperty {
+    #[serde(default)]
+    k: Value,
+    x: Option<Box<Property>>,
+    y: Option<Box<Property>>,
+}
+
+impl From<RawProperty> for Property {
+    fn from(raw: RawProperty) -> Self {
+        if let (Some(x), Some(y)) = (raw.x, raw.y) {
+            return Property::Split(x, y);
+        }
+
+        let keyframes = match &raw.k {
+            Value::Array(values) if values.iter().any(Value::is_object) => values,
+            Value::Object(_) => {
+                let (values, closed) = flatten_bezier(&raw.k);
+                return Property::Bezier(Box::new(Property::Static(values)), closed);
+            }
+            value => return Property::Static(numbers(value)),
+        };
+
+        let mut closed = false;
+        let mut previous_end: Option<Vec<f32>> = None;
+        let keyframes: Vec<_> = keyframes
+            .iter()
+            .map(|keyframe| {
+                let mut flatten = |value: &Value| match value {
+                    // Bézier keyframes are given as single-element arrays.
+                    Value::Array(values) if values.first().is_some_and(Value::is_object) => {
+                        let (values, is_closed) = flatten_bezier(&values[0]);
+                        closed = closed || is_closed;
+                        values
+                    }
+                    Value::Object(_) => {
+                        let (values, is_closed) = flatten_bezier(value);
+                        closed = closed || is_closed;
+                        values
+                    }
+                    value => numbers(value),
+                };
+                let start = match keyframe.get("s") {
+                    Some(start) => flatten(start),
+                    None => previous_end.clone().unwrap_or_default(),
+                };
+                let end = keyframe.get("e").map(&mut flatten);
+                previous_end = end.clone();
+                Keyframe {
+                    time: keyframe.get("t").and_then(Value::as_f64).unwrap_or(0.0) as f32,
+                    start,
+                    end,
+                    hold: keyframe.get("h").and_then(Value::as_f64).is_some_and(|h| h != 0.0),
+                    ease_out: control_point(keyframe.get("o"), (0.0, 0.0)),
+                    ease_in: control_point(keyframe.get("i"), (1.0, 1.0)),
+                }
+            })
+            .collect();
+
+        let animated = Property::Animated(keyframes);
+        if closed {
+            Property::Bezier(Box::new(animated), true)
+        } else {
+            animated
+        }
+    }
+}
+
+fn numbers(value: &Value) -> Vec<f32> {
+    match value {
+        Value::Number(n) => n.as_f64().into_iter().map(|n| n as f32).collect(),
+        Value::Array(values) => values.iter().filter_map(Value::as_f64).map(|n| n as f32).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Flattens a Lottie Bézier (`{"c": closed, "v": vertices, "i": in tangents, "o": out tangents}`) into
+/// the coordinates of the vertices, followed by those of the in tangents, then those of the out tangents.
+fn flatten_bezier(bezier: &Value) -> (Vec<f32>, bool) {
+    let points = |key: &str| -> Vec<f32> {
+        bezier
+            .get(key)
+            .and_then(Value::as_array)
+            .map(|points| {
+                points.iter().flat_map(|point| numbers(point).into_iter().take(2)).collect()
+            })
+            .unwrap_or_default()
+    };
+    let (vertices, in_tangents, out_tangents) = (points("v"), points("i"), points("o"));
+    let mut values = vertices;
+    let count = values.len();
+    values.extend(in_tangents.into_iter().chain(std::iter::repeat(0.0)).take(count));
+    values.extend(out_tangents.into_iter().chain(std::iter::repeat(0.0)).take(count));
+    (values, bezier.get("c").and_then(Value::as_bool).unwrap_or(false))
+}
+
+fn control_point(value: Option<&Value>, default: (f32, f32)) -> (f32, f32) {
+    let component = |key: &str, default: f32| {
+        value
+            .and_then(|value| value.get(key))
+            .and_then(|c| numbers(c).first().copied())
+            .unwrap_or(default)
+    };
+    (component("x", default.0), component("y", default.1))
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, float_cmp::approx_eq};
+
+    const ANIMATION: &str = r##"{
+        "fr": 30, "ip": 0, "op": 60, "w": 100, "h": 100,
+        "layers": [
+            {
+                "ty": 4, "ind": 2, "parent": 1, "ip": 0, "op": 60, "st": 0,
+                "ks": { "o": { "k": 100 }, "p": { "k": [10, 0] } },
+                "shapes": [
+                    { "ty": "el", "p": { "k": [0, 0] }, "s": { "k": [20, 20] } },
+                    { "ty": "gr", "it": [
+                        { "ty": "rc", "p": { "k": [0, 0] }, "s": { "k": [10, 10] } },
+                        { "ty": "fl", "c": { "k": [1, 0, 0, 1] }, "o": { "k": 100 } },
+                        { "ty": "tr", "s": { "k": [200, 200] } }
+                    ] },
+                    { "ty": "st", "c": { "k": [0, 0, 1, 1] }, "o": { "k": 50 }, "w": { "k": 2 }, "lc": 2 },
+                    { "ty": "gf" }
+                ]
+            },
+            {
+                "ty": 3, "ind": 1, "ip": 0, "op": 60, "st": 0,
+                "ks": { "p": { "a": 1, "k": [
+                    { "t": 0, "s": [0, 0], "o": { "x": 0, "y": 0 }, "i": { "x": 1, "y": 1 } },
+                    { "t": 30, "s": [30, 60] }
+                ] } }
+            },
+            { "ty": 1, "ip": 30, "op": 60, "sw": 100, "sh": 100, "sc": "#00ff00", "ks": { "o": { "k": 50 } } }
+        ]
+    }"##;
+
+    fn translations(commands: &[DisplayCommand]) -> Vec<Vector> {
+        commands
+            .iter()
+            .filter_map(|command| match command {
+                DisplayCommand::Translate(offset) if *offset != Vector::zero() => Some(*offset),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_lottie() {
+        let animation = Animation::from_json(ANIMATION).unwrap();
+        assert_eq!(animation.size(), Size::new(100.0, 100.0));
+        assert_eq!(animation.duration(), Duration::from_secs(2));
+        assert_eq!(animation.frame_at(Duration::from_secs(3)), 30.0);
+        assert!(matches!(
+            Animation::from_json(r#"{ "fr": 0, "ip": 0, "op": 10, "w": 1, "h": 1 }"#),
+            Err(LottieError::InvalidTiming)
+        ));
+
+        // the parent (null) layer moves linearly, the solid layer isn't shown yet.
+        let commands = animation.render(15.0);
+        assert!(matches!(commands.first(), Some(DisplayCommand::Save)));
+        assert!(matches!(commands.last(), Some(DisplayCommand::Restore)));
+        let offsets = translations(&commands);
+        assert_eq!(offsets.len(), 2);
+        assert!(approx_eq!(f32, offsets[0].x, 15.0, epsilon = 0.001));
+        assert!(approx_eq!(f32, offsets[0].y, 30.0, epsilon = 0.001));
+        assert_eq!(offsets[1], Vector::new(10.0, 0.0));
+
+        let items: Vec<_> = commands
+            .iter()
+            .filter_map(|command| match command {
+                DisplayCommand::Item(
+                    DisplayItem::Graphics(GraphicsDisplayItem::Path { path, is_closed, paint }),
+                    _,
+                ) => Some((path, *is_closed, paint)),
+                _ => None,
+            })
+            .collect();
+        // the fill of the group is drawn above the stroke of the ellipse and rectangle.
+        assert_eq!(items.len(), 3);
+        match items[0].2 {
+            GraphicsDisplayPaint::Stroke(stroke) => {
+                assert!(
+                    matches!(stroke.color, StyleColor::Color(c) if c == Color::new(0.0, 0.0, 1.0, 0.5))
+                );
+                assert_eq!(stroke.cap, LineCap::Round);
+                assert_eq!(stroke.thickness, 2.0);
+            }
+            _ => panic!("expected a stroke"),
+        }
+        // the group transform scales the rectangle.
+        let (path, is_closed, paint) = &items[2];
+        assert!(is_closed);
+        assert!(
+            matches!(paint, GraphicsDisplayPaint::Fill(StyleColor::Color(c)) if *c == Color::new(1.0, 0.0, 0.0, 1.0))
+        );
+        assert!(matches!(path[0], VectorPathEvent::MoveTo { to } if to == Point::new(10.0, -10.0)));
+
+        // the solid layer (listed last) is drawn below, through a translucent layer.
+        let commands = animation.render(45.0);
+        assert!(matches!(commands[0], DisplayCommand::SaveLayer(opacity, _) if opacity == 0.5));
+        assert!(matches!(
+            commands.iter().find(|command| matches!(command, DisplayCommand::Item(..))),
+            Some(DisplayCommand::Item(
+                DisplayItem::Graphics(GraphicsDisplayItem::Rectangle { .. }),
+                _
+            ))
+        ));
+        assert_eq!(translations(&commands)[0], Vector::new(30.0, 60.0));
+    }
+}
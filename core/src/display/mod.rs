@@ -1,18 +1,82 @@
 //! Generic high-level vector graphics interface
 
+pub mod backend;
+pub mod binding;
+pub mod charts;
+pub mod composite;
+pub mod contrast;
+pub mod degrade;
+pub mod draw_context;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+pub mod icon;
+pub mod image_loader;
+pub mod lint;
+#[cfg(feature = "lottie")]
+pub mod lottie;
+pub mod picture;
+pub mod proxy;
+pub mod recording;
 #[cfg(feature = "skia")]
 pub mod skia;
+#[cfg(feature = "svg")]
+pub mod svg_import;
+pub mod text_layout;
+pub mod tiles;
+pub mod transaction;
+pub mod validate;
+mod workers;
+pub mod z_order;
+
+use {
+    crate::error,
+    palette::Srgba,
+    std::{
+        cell::RefCell,
+        collections::{hash_map::Entry, HashMap},
+        hash::{Hash, Hasher},
+        rc::Rc,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex, Weak,
+        },
+        time::{Duration, Instant},
+    },
+    unicode_segmentation::GraphemeCursor,
+};
+
+/// Unit of UI coordinates, which are independent of the pixel density of the display.
+///
+/// Widgets are laid out and drawn in logical pixels.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LogicalPixel;
 
-use {crate::error, palette::Srgba, std::sync::Arc};
+/// Unit of window coordinates, in actual pixels of the display surface.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PhysicalPixel;
 
 /// Two-dimensional floating-point absolute point.
-pub type Point = euclid::Point2D<f32, euclid::UnknownUnit>;
+pub type Point = euclid::Point2D<f32, LogicalPixel>;
 /// Two-dimensional floating-point relative vector.
-pub type Vector = euclid::Vector2D<f32, euclid::UnknownUnit>;
+pub type Vector = euclid::Vector2D<f32, LogicalPixel>;
 /// Two-dimensional floating-point size.
-pub type Size = euclid::Size2D<f32, euclid::UnknownUnit>;
+pub type Size = euclid::Size2D<f32, LogicalPixel>;
 /// Two-dimensional floating-point rectangle.
-pub type Rect = euclid::Rect<f32, euclid::UnknownUnit>;
+pub type Rect = euclid::Rect<f32, LogicalPixel>;
+/// Two-dimensional floating-point absolute point, in physical pixels.
+pub type PhysicalPoint = euclid::Point2D<f32, PhysicalPixel>;
+/// Two-dimensional floating-point relative vector, in physical pixels.
+pub type PhysicalVector = euclid::Vector2D<f32, PhysicalPixel>;
+/// Two-dimensional floating-point size, in physical pixels.
+pub type PhysicalSize = euclid::Size2D<f32, PhysicalPixel>;
+/// Two-dimensional floating-point rectangle, in physical pixels.
+pub type PhysicalRect = euclid::Rect<f32, PhysicalPixel>;
+/// Two-dimensional integer rectangle, in whole physical pixels of a surface.
+pub type PixelRect = euclid::Rect<u32, PhysicalPixel>;
+/// Number of physical pixels per logical pixel.
+///
+/// Points, vectors and sizes are converted by multiplying (to physical pixels) or dividing (to logical pixels) by the scale factor.
+pub type ScaleFactor = euclid::Scale<f32, LogicalPixel, PhysicalPixel>;
 /// An angle in radians.
 pub type Angle = euclid::Angle<f32>;
 
@@ -36,7 +100,7 @@ pub struct ZOrder(pub i32);
 /// however this means implementing [`GraphicsDisplay`](GraphicsDisplay) yourself.
 pub trait GraphicsDisplay<D: Sized = DisplayCommand> {
     /// Resizes the underlying surface.
-    fn resize(&mut self, size: (u32, u32)) -> Result<(), Box<dyn std::error::Error>>;
+    fn resize(&mut self, size: (u32, u32)) -> Result<(), error::DisplayError>;
 
     /// Creates a new resource for use in rendering.
     fn new_resource(
@@ -44,6 +108,29 @@ pub trait GraphicsDisplay<D: Sized = DisplayCommand> {
         descriptor: ResourceDescriptor,
     ) -> Result<ResourceReference, error::ResourceError>;
 
+    /// Replaces an existing resource in place, so that the commands referencing it draw the new resource from then on.
+    ///
+    /// `descriptor` must describe the same kind of resource as `reference`.
+    /// If this fails, the existing resource must be left untouched.
+    fn replace_resource(
+        &mut self,
+        reference: ResourceReference,
+        descriptor: ResourceDescriptor,
+    ) -> Result<(), error::DisplayError>;
+
+    /// Replaces the pixels of an existing image resource in place, e.g. with the next frame of a video.
+    ///
+    /// As with [`replace_resource`](GraphicsDisplay::replace_resource), the command groups referencing the image stay
+    /// valid and draw the new pixels, without being modified.
+    #[inline]
+    fn update_resource(
+        &mut self,
+        reference: ResourceReference,
+        data: ImageData,
+    ) -> Result<(), error::DisplayError> {
+        self.replace_resource(reference, ResourceDescriptor::Image(data))
+    }
+
     /// Removes an existing resource.
     fn remove_resource(&mut self, reference: ResourceReference);
 
@@ -59,12 +146,14 @@ pub trait GraphicsDisplay<D: Sized = DisplayCommand> {
         z_order: ZOrder,
         protected: Option<bool>,
         needs_maintain: Option<bool>,
-    ) -> Result<CommandGroupHandle, Box<dyn std::error::Error>>;
+    ) -> Result<CommandGroupHandle, error::DisplayError>;
 
     /// Returns an existing command group by the handle returned from [`push_command_group`](GraphicsDisplay::push_command_group).
     fn get_command_group(&self, handle: CommandGroupHandle) -> Option<&[D]>;
 
     /// Overwrites an existing command group by the handle returned from [`push_command_group`](GraphicsDisplay::push_command_group).
+    ///
+    /// If this fails (e.g. the bounds of a text item can't be computed), the existing command group must be left untouched.
     fn modify_command_group(
         &mut self,
         handle: CommandGroupHandle,
@@ -72,21 +161,297 @@ pub trait GraphicsDisplay<D: Sized = DisplayCommand> {
         z_order: ZOrder,
         protected: Option<bool>,
         needs_maintain: Option<bool>,
-    ) -> Result<(), Box<dyn std::error::Error>>;
+    ) -> Result<(), error::DisplayError>;
 
     /// Removes an existing command group.
     fn remove_command_group(&mut self, handle: CommandGroupHandle) -> Option<Vec<DisplayCommand>>;
 
+    /// Attaches a debugging label to an existing command group, which is reported by [`debug_dump`](GraphicsDisplay::debug_dump).
+    ///
+    /// The default implementation discards the label.
+    fn label_command_group(&mut self, handle: CommandGroupHandle, label: Option<&'static str>) {
+        let _ = (handle, label);
+    }
+
+    /// Returns a description of all the live command groups, in the order they are drawn.
+    ///
+    /// The default implementation returns nothing.
+    fn debug_dump(&self) -> Vec<CommandGroupInfo> {
+        Vec::new()
+    }
+
     /// Keeps a command group alive, additionally possibly moving it to the front (depending on implementation).
     fn maintain_command_group(&mut self, handle: CommandGroupHandle);
 
+    /// Starts batching command group updates.
+    ///
+    /// Until [`commit_update`](GraphicsDisplay::commit_update), pushed, modified and removed command groups may be
+    /// deferred and applied all at once, so that bookkeeping (e.g. z-order sorting and bounds computation) is done
+    /// a single time. Deferred updates aren't visible through [`get_command_group`](GraphicsDisplay::get_command_group)
+    /// until they are committed, and removals within a batch return `None`. Batches can't be nested.
+    ///
+    /// Prefer [`DisplayTransaction`](transaction::DisplayTransaction), which commits when dropped.
+    /// The default implementation does nothing, applying updates immediately.
+    fn begin_update(&mut self) {}
+
+    /// Applies all the updates since [`begin_update`](GraphicsDisplay::begin_update).
+    ///
+    /// If this fails (e.g. the bounds of a pushed command group can't be computed), none of the updates are applied.
+    /// The default implementation does nothing.
+    fn commit_update(&mut self) -> Result<(), error::DisplayError> {
+        Ok(())
+    }
+
+    /// Changes what happens to command groups which weren't maintained between presents (see [`EvictionPolicy`](EvictionPolicy)).
+    ///
+    /// The default implementation returns [`DisplayError::Unsupported`](error::DisplayError::Unsupported).
+    fn set_eviction_policy(&mut self, policy: EvictionPolicy) -> Result<(), error::DisplayError> {
+        let _ = policy;
+        Err(error::DisplayError::Unsupported("set_eviction_policy"))
+    }
+
+    /// Sets a callback which is invoked during [`present`](GraphicsDisplay::present) with every command group which is evicted.
+    ///
+    /// The default implementation returns [`DisplayError::Unsupported`](error::DisplayError::Unsupported).
+    fn set_eviction_callback(
+        &mut self,
+        callback: Option<EvictionCallback>,
+    ) -> Result<(), error::DisplayError> {
+        let _ = callback;
+        Err(error::DisplayError::Unsupported("set_eviction_callback"))
+    }
+
+    /// Returns `true` if [`BackdropFilter`](DisplayCommand::BackdropFilter) commands are applied,
+    /// rather than ignored. The default implementation returns `false`.
+    fn supports_backdrop_filter(&self) -> bool {
+        false
+    }
+
+    /// Returns the largest width and height of an image resource, if limited.
+    /// The default implementation returns `None`.
+    fn max_texture_size(&self) -> Option<u32> {
+        None
+    }
+
+    /// Returns the features which the display implements, for adapting display lists through [`degrade`](degrade::degrade).
+    ///
+    /// The default implementation only reports [`supports_backdrop_filter`](GraphicsDisplay::supports_backdrop_filter)
+    /// and [`max_texture_size`](GraphicsDisplay::max_texture_size), and nothing else as supported.
+    fn capabilities(&self) -> DisplayCapabilities {
+        DisplayCapabilities {
+            backdrop_filter: self.supports_backdrop_filter(),
+            max_texture_size: self.max_texture_size(),
+            ..Default::default()
+        }
+    }
+
     /// Executes pre-exit routines.
     ///
     /// In a GPU implementation, for example, this may wait for the device to finish any remaining draw calls.
     fn before_exit(&mut self);
 
-    /// Displays the entire scene, optionally with a cull.
-    fn present(&mut self, cull: Option<Rect>) -> Result<(), error::DisplayError>;
+    /// Displays the entire scene, optionally with a cull, returning statistics about the presentation.
+    fn present(&mut self, cull: Option<Rect>) -> Result<PresentInfo, error::DisplayError>;
+
+    /// Reads back the pixels of the last presented frame within `rect`, clipped to the surface.
+    ///
+    /// This reads the surface as presented, i.e. in physical pixels (see [`to_physical`](to_physical)), after scaling
+    /// a [virtual resolution](GraphicsDisplay::set_virtual_resolution) (map regions of the frame with
+    /// [`to_surface`](VirtualResolution::to_surface)) and applying color correction.
+    /// This is useful for screenshots, color pickers and visual tests.
+    /// Fails with [`InvalidRegion`](error::DisplayError::InvalidRegion) if `rect` lies entirely outside the surface.
+    /// The default implementation returns [`DisplayError::Unsupported`](error::DisplayError::Unsupported).
+    fn read_pixels(&mut self, rect: PixelRect) -> Result<RasterImage, error::DisplayError> {
+        let _ = rect;
+        Err(error::DisplayError::Unsupported("read_pixels"))
+    }
+
+    /// Sets the callback invoked where [`Hole`](DisplayCommand::Hole) commands with the ID `id` are drawn,
+    /// or removes it if `callback` is `None`.
+    ///
+    /// The callback is given the hole in surface pixels, after everything before it has been submitted to the GPU,
+    /// so that it can render straight into the surface (e.g. with a scissor test on the rectangle).
+    /// When the frame is drawn offscreen (for a [virtual resolution](GraphicsDisplay::set_virtual_resolution) or
+    /// [color correction](GraphicsDisplay::set_color_correction)), callbacks are invoked once the frame has been
+    /// copied onto the surface instead, still with the hole in surface pixels.
+    /// The default implementation returns [`DisplayError::Unsupported`](error::DisplayError::Unsupported).
+    fn set_hole_callback(
+        &mut self,
+        id: u64,
+        callback: Option<HoleCallback>,
+    ) -> Result<(), error::DisplayError> {
+        let _ = (id, callback);
+        Err(error::DisplayError::Unsupported("set_hole_callback"))
+    }
+
+    /// Sets the color correction applied to every frame at the end of [`present`](GraphicsDisplay::present).
+    ///
+    /// The default implementation returns [`DisplayError::Unsupported`](error::DisplayError::Unsupported).
+    fn set_color_correction(
+        &mut self,
+        correction: ColorCorrection,
+    ) -> Result<(), error::DisplayError> {
+        let _ = correction;
+        Err(error::DisplayError::Unsupported("set_color_correction"))
+    }
+
+    /// Draws every frame at a fixed resolution, scaled to fit the surface on present, or draws at the size of the
+    /// surface again if `resolution` is `None`.
+    ///
+    /// While set, command groups are in the coordinates of the virtual resolution, so input positions have to be mapped
+    /// through [`to_virtual`](VirtualResolution::to_virtual).
+    /// The default implementation returns [`DisplayError::Unsupported`](error::DisplayError::Unsupported).
+    fn set_virtual_resolution(
+        &mut self,
+        resolution: Option<VirtualResolution>,
+    ) -> Result<(), error::DisplayError> {
+        let _ = resolution;
+        Err(error::DisplayError::Unsupported("set_virtual_resolution"))
+    }
+
+    /// Same as [`present`](GraphicsDisplay::present), but also invokes `callback` once the frame has been presented.
+    ///
+    /// Implementations which can know when the frame actually hit the screen report it in
+    /// [`PresentFeedback::displayed`](PresentFeedback::displayed) (and may invoke the callback later);
+    /// the default implementation invokes the callback immediately, without that timestamp.
+    fn present_with_callback(
+        &mut self,
+        cull: Option<Rect>,
+        callback: Box<dyn FnOnce(PresentFeedback)>,
+    ) -> Result<PresentInfo, error::DisplayError> {
+        let info = self.present(cull)?;
+        callback(PresentFeedback { info, submitted: Instant::now(), displayed: None });
+        Ok(info)
+    }
+}
+
+/// Callback drawing external content into a [`Hole`](DisplayCommand::Hole), see
+/// [`set_hole_callback`](GraphicsDisplay::set_hole_callback).
+pub type HoleCallback = Box<dyn FnMut(HoleInfo)>;
+
+/// A [`Hole`](DisplayCommand::Hole) as drawn, passed to its [callback](HoleCallback).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HoleInfo {
+    pub id: u64,
+    /// The hole in surface pixels, transformed and clipped.
+    pub rect: Rect,
+}
+
+/// A domain-specific display command (e.g. a chart candle or a map tile) added by a downstream crate, drawn through
+/// [`DisplayCommand::Extension`](DisplayCommand::Extension).
+///
+/// Backends draw extensions with interpreters registered for their [`name`](CustomCommand::name) (e.g.
+/// [`register_command_interpreter`](crate::display::skia::SkiaGraphicsDisplay::register_command_interpreter) of the
+/// Skia display), which downcast the command through [`as_any`](CustomCommandBase::as_any). Backends without an
+/// interpreter for the command draw its [`fallback`](CustomCommand::fallback) instead, or skip it.
+pub trait CustomCommand: CustomCommandBase + std::fmt::Debug + Send + Sync {
+    /// Identifies the kind of command, which interpreters are registered for.
+    fn name(&self) -> &'static str;
+
+    /// Maximum bounds of what the command draws, or `None` if it doesn't draw anything.
+    fn bounds(&self) -> Option<Rect>;
+
+    /// Commands drawn in place of this one by backends without an interpreter for it.
+    ///
+    /// Defaults to nothing, so the command is skipped.
+    fn fallback(&self) -> Vec<DisplayCommand> {
+        Vec::new()
+    }
+}
+
+/// Cloning and downcasting of [`CustomCommand`](CustomCommand)s, implemented for every `Clone` type.
+pub trait CustomCommandBase: std::any::Any {
+    fn clone_box(&self) -> Box<dyn CustomCommand>;
+    fn as_any(&self) -> &dyn std::any::Any;
+}
+
+impl<T: CustomCommand + Clone> CustomCommandBase for T {
+    fn clone_box(&self) -> Box<dyn CustomCommand> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+impl Clone for Box<dyn CustomCommand> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl dyn CustomCommand {
+    /// Returns the command as a `T` if it is one.
+    pub fn downcast_ref<T: CustomCommand>(&self) -> Option<&T> {
+        self.as_any().downcast_ref()
+    }
+}
+
+/// Description of a live command group, as returned from [`debug_dump`](GraphicsDisplay::debug_dump).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CommandGroupInfo {
+    pub handle: CommandGroupHandle,
+    /// Label attached with [`label_command_group`](GraphicsDisplay::label_command_group).
+    pub label: Option<&'static str>,
+    pub z_order: ZOrder,
+    pub bounds: Rect,
+    pub protected: bool,
+    /// `None` if the command group isn't subject to maintenance,
+    /// otherwise whether it has been maintained since the last present.
+    pub maintained: Option<bool>,
+}
+
+/// What happens to a command group which needs maintenance, but wasn't maintained between presents.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EvictionPolicy {
+    /// The command group is removed at the next present (the default).
+    #[default]
+    EvictUnmaintained,
+    /// The command group keeps being drawn for up to `n` presents without maintenance, then it is removed.
+    ///
+    /// This tolerates widgets which skip a draw every now and then.
+    KeepForNFrames(u32),
+    /// Command groups are never removed by the display; maintenance has no effect.
+    Never,
+}
+
+impl EvictionPolicy {
+    /// Returns `true` if a command group which hasn't been maintained for `missed` presents should be removed.
+    pub fn should_evict(self, missed: u32) -> bool {
+        match self {
+            EvictionPolicy::EvictUnmaintained => missed > 0,
+            EvictionPolicy::KeepForNFrames(n) => missed > n,
+            EvictionPolicy::Never => false,
+        }
+    }
+}
+
+/// Callback invoked with every evicted command group, see [`set_eviction_callback`](GraphicsDisplay::set_eviction_callback).
+pub type EvictionCallback = Box<dyn FnMut(CommandGroupInfo)>;
+
+/// Statistics about a single [`present`](GraphicsDisplay::present).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PresentInfo {
+    /// Number of command groups skipped because they were outside the cull.
+    pub culled: usize,
+    /// Number of command groups drawn.
+    pub drawn: usize,
+    /// Number of command groups removed because they weren't maintained (see [`EvictionPolicy`](EvictionPolicy)).
+    pub evicted: usize,
+    /// Time spent waiting on the GPU (e.g. flushing), if applicable.
+    pub gpu_wait: Duration,
+}
+
+/// Passed to the callback of [`present_with_callback`](GraphicsDisplay::present_with_callback).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PresentFeedback {
+    /// The statistics of the presentation.
+    pub info: PresentInfo,
+    /// When the frame was submitted.
+    pub submitted: Instant,
+    /// When the frame actually hit the screen, if the implementation can know.
+    pub displayed: Option<Instant>,
 }
 
 /// Resource data, either as a file or an in-memory buffer.
@@ -118,13 +483,53 @@ pub enum RasterImageFormat {
 pub struct RasterImageInfo {
     pub size: (u32, u32),
     pub format: RasterImageFormat,
+    /// Color space the pixels are encoded in.
+    pub color_space: ColorSpace,
+}
+
+/// An image in memory, e.g. read back from a display with [`read_pixels`](GraphicsDisplay::read_pixels).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RasterImage {
+    /// Tightly packed pixels, row by row.
+    pub data: Vec<u8>,
+    pub info: RasterImageInfo,
+}
+
+impl RasterImage {
+    /// Returns the components of the pixel at (`x`, `y`), in the order of the image format.
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        if x >= self.info.size.0 || y >= self.info.size.1 {
+            return None;
+        }
+        let i = (y as usize * self.info.size.0 as usize + x as usize) * 4;
+        let px = self.data.get(i..i + 4)?;
+        Some([px[0], px[1], px[2], px[3]])
+    }
 }
 
 /// Contains information required to load a resource through [`new_resource`](GraphicsDisplay::new_resource).
+// text blobs are kept inline, like text items in display commands.
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone)]
 pub enum ResourceDescriptor {
     Image(ImageData),
     Font(ResourceData),
+    /// Text whose glyph runs are built once and retained, to be drawn through [`TextBlobDisplayItem`](TextBlobDisplayItem).
+    ///
+    /// The font of the text item must be an existing font resource of the same display.
+    /// The position and color of the text item are ignored; these are given by the display item instead.
+    TextBlob(TextDisplayItem),
+}
+
+impl ResourceDescriptor {
+    /// Returns the reference to the resource described by this, given its ID.
+    pub fn reference(&self, id: u64) -> ResourceReference {
+        match self {
+            ResourceDescriptor::Image(_) => ResourceReference::Image(id),
+            ResourceDescriptor::Font(_) => ResourceReference::Font(id),
+            ResourceDescriptor::TextBlob(_) => ResourceReference::TextBlob(id),
+        }
+    }
 }
 
 /// Contains a tagged ID to an existing resource, created through [`new_resource`](GraphicsDisplay::new_resource).
@@ -134,13 +539,16 @@ pub enum ResourceDescriptor {
 pub enum ResourceReference {
     Image(u64),
     Font(u64),
+    TextBlob(u64),
 }
 
 impl ResourceReference {
     /// Returns the inner ID of the resource reference.
     pub fn id(&self) -> u64 {
         match self {
-            ResourceReference::Image(id) | ResourceReference::Font(id) => *id,
+            ResourceReference::Image(id)
+            | ResourceReference::Font(id)
+            | ResourceReference::TextBlob(id) => *id,
         }
     }
 }
@@ -161,7 +569,7 @@ pub fn ok_or_push<D: Sized>(
     z_order: ZOrder,
     protected: impl Into<Option<bool>>,
     needs_maintain: impl Into<Option<bool>>,
-) -> Result<(), Box<dyn std::error::Error>> {
+) -> Result<(), error::DisplayError> {
     match handle {
         Some(ref handle) => display.modify_command_group(
             *handle,
@@ -209,7 +617,7 @@ impl CommandGroupHandle {
 
 /// Helper wrapper around [`CommandGroupHandle`](CommandGroupHandle).
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct CommandGroup(Option<CommandGroupHandle>, bool);
+pub struct CommandGroup(Option<CommandGroupHandle>, bool, Option<&'static str>, Option<u64>);
 
 impl Default for CommandGroup {
     fn default() -> Self {
@@ -221,11 +629,28 @@ impl CommandGroup {
     /// Creates a new, empty command group.
     #[inline]
     pub fn new() -> Self {
-        CommandGroup(None, true)
+        CommandGroup(None, true, None, None)
+    }
+
+    /// Creates a new, empty command group with a debugging label.
+    ///
+    /// See [`label_command_group`](GraphicsDisplay::label_command_group).
+    #[inline]
+    pub fn with_label(label: &'static str) -> Self {
+        CommandGroup(None, true, Some(label), None)
+    }
+
+    /// Returns the debugging label.
+    #[inline]
+    pub fn label(&self) -> Option<&'static str> {
+        self.2
     }
 
     /// Pushes a list of commands if the repaint flag is set, and resets repaint flag if so.
     ///
+    /// Returns `true` if the commands were pushed, or `false` if the existing command group was only maintained.
+    /// If pushing fails, the repaint flag remains set so that the next call tries again.
+    ///
     /// See [`push_command_group`](GraphicsDisplay::push_command_group).
     /// Also see [`push_with`](CommandGroup::push_with), which is more efficient.
     pub fn push<D: Sized>(
@@ -235,13 +660,14 @@ impl CommandGroup {
         z_order: ZOrder,
         protected: impl Into<Option<bool>>,
         needs_maintain: impl Into<Option<bool>>,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<bool, error::DisplayError> {
         if self.1 {
-            self.1 = false;
-            ok_or_push(&mut self.0, display, commands, z_order, protected, needs_maintain)
+            self.commit(display, commands, z_order, protected.into(), needs_maintain.into())?;
+            self.3 = None;
+            Ok(true)
         } else {
-            display.maintain_command_group(self.0.unwrap());
-            Ok(())
+            self.maintain(display);
+            Ok(false)
         }
     }
 
@@ -258,16 +684,68 @@ impl CommandGroup {
         z_order: ZOrder,
         protected: impl Into<Option<bool>>,
         needs_maintain: impl Into<Option<bool>>,
-    ) -> Result<(), Box<dyn std::error::Error>>
+    ) -> Result<bool, error::DisplayError>
     where
         F: FnOnce() -> Vec<D>,
     {
         if self.1 {
-            self.1 = false;
-            ok_or_push(&mut self.0, display, &f(), z_order, protected, needs_maintain)
+            self.commit(display, &f(), z_order, protected.into(), needs_maintain.into())?;
+            self.3 = None;
+            Ok(true)
+        } else {
+            self.maintain(display);
+            Ok(false)
+        }
+    }
+
+    /// Like [`push_with`](CommandGroup::push_with), but also pushes when `hash` differs from the hash
+    /// given to the previous successful call, regardless of the repaint flag.
+    ///
+    /// `hash` should be a hash of everything the commands are built from, so that widgets don't have to
+    /// track changes with [`repaint`](CommandGroup::repaint) manually.
+    pub fn push_if_changed<F, D: Sized>(
+        &mut self,
+        display: &mut dyn GraphicsDisplay<D>,
+        hash: u64,
+        f: F,
+        z_order: ZOrder,
+        protected: impl Into<Option<bool>>,
+        needs_maintain: impl Into<Option<bool>>,
+    ) -> Result<bool, error::DisplayError>
+    where
+        F: FnOnce() -> Vec<D>,
+    {
+        if self.1 || self.3 != Some(hash) {
+            self.commit(display, &f(), z_order, protected.into(), needs_maintain.into())?;
+            self.3 = Some(hash);
+            Ok(true)
         } else {
-            display.maintain_command_group(self.0.unwrap());
-            Ok(())
+            self.maintain(display);
+            Ok(false)
+        }
+    }
+
+    fn commit<D: Sized>(
+        &mut self,
+        display: &mut dyn GraphicsDisplay<D>,
+        commands: &[D],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        needs_maintain: Option<bool>,
+    ) -> Result<(), error::DisplayError> {
+        let pushed = self.0.is_none();
+        ok_or_push(&mut self.0, display, commands, z_order, protected, needs_maintain)?;
+        // only reset the repaint flag once the commands made it into the display, so failures are retried.
+        self.1 = false;
+        if let (true, Some(handle), Some(label)) = (pushed, self.0, self.2) {
+            display.label_command_group(handle, Some(label));
+        }
+        Ok(())
+    }
+
+    fn maintain<D: Sized>(&self, display: &mut dyn GraphicsDisplay<D>) {
+        if let Some(handle) = self.0 {
+            display.maintain_command_group(handle);
         }
     }
 
@@ -283,17 +761,32 @@ impl CommandGroup {
         self.1
     }
 
+    /// Sets the repaint flag of every command group, e.g. once the scale factor changes
+    /// (see [`ScaleChanged`](crate::input::WindowEvent::ScaleChanged)).
+    pub fn repaint_all<'a>(groups: impl IntoIterator<Item = &'a mut CommandGroup>) {
+        for group in groups {
+            group.repaint();
+        }
+    }
+
+    /// Removes the command group from the display.
+    ///
+    /// The repaint flag is left as is, so [`push`](CommandGroup::push) only recreates the command group once
+    /// [`repaint`](CommandGroup::repaint) is called. The hash of [`push_if_changed`](CommandGroup::push_if_changed)
+    /// is forgotten though, since it describes commands which aren't in the display anymore.
     pub fn remove<D: Sized>(&mut self, display: &mut dyn GraphicsDisplay<D>) {
         if let Some(handle) = self.0.take() {
             display.remove_command_group(handle);
         }
+        self.3 = None;
     }
 }
 
 /// Stroke cap (stroke start/end) appearance.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum LineCap {
     /// The cap of the stroke will appear as expected.
+    #[default]
     Flat,
     /// The cap of the stroke will extend tangentially with dimensions square to the stroke width.
     Square,
@@ -301,16 +794,11 @@ pub enum LineCap {
     Round,
 }
 
-impl Default for LineCap {
-    fn default() -> Self {
-        LineCap::Flat
-    }
-}
-
 /// Path corner appearance.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum LineJoin {
     /// The corner will appear as expected.
+    #[default]
     Miter,
     /// The corner will be rounded off.
     Round,
@@ -318,12 +806,6 @@ pub enum LineJoin {
     Bevel,
 }
 
-impl Default for LineJoin {
-    fn default() -> Self {
-        LineJoin::Miter
-    }
-}
-
 /// An "event"/segment within a vector path.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum VectorPathEvent {
@@ -442,7 +924,7 @@ pub fn vector_path_bounds(path: &[VectorPathEvent]) -> Rect {
 }
 
 /// Stroke/outline appearance.
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub struct GraphicsDisplayStroke {
     /// The color of the stroke.
     pub color: StyleColor,
@@ -471,6 +953,58 @@ impl Default for GraphicsDisplayStroke {
     }
 }
 
+/// Features implemented by a [`GraphicsDisplay`](GraphicsDisplay), as returned by [`capabilities`](GraphicsDisplay::capabilities).
+///
+/// The default value supports nothing beyond plain items, rectangular clips and saved states.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DisplayCapabilities {
+    /// [`FilterOp`](FilterOp)s of items are applied.
+    pub filters: bool,
+    /// [`BackdropFilter`](DisplayCommand::BackdropFilter) commands are applied.
+    pub backdrop_filter: bool,
+    /// Blend modes other than [`Normal`](BlendMode::Normal) are applied, both to paints and layers.
+    pub blend_modes: bool,
+    /// [`SaveLayer`](DisplayCommand::SaveLayer) draws into an offscreen render target.
+    pub layers: bool,
+    /// Rounded rectangle and elliptical clips.
+    pub rounded_clips: bool,
+    /// Vector path clips.
+    pub path_clips: bool,
+    /// Clips with [`ClipOp::Difference`](ClipOp::Difference).
+    pub difference_clips: bool,
+    /// [`Mesh`](GraphicsDisplayItem::Mesh) items are shaded smoothly.
+    pub meshes: bool,
+    /// [Shaped](DisplayText::Shaped) text is drawn.
+    pub text_shaping: bool,
+    /// [`Hole`](DisplayCommand::Hole)s are drawn by their callbacks, rather than only cleared.
+    pub holes: bool,
+    /// [`Extension`](DisplayCommand::Extension)s are drawn by registered interpreters where available, rather than
+    /// always by their fallback.
+    pub extensions: bool,
+    /// See [`max_texture_size`](GraphicsDisplay::max_texture_size).
+    pub max_texture_size: Option<u32>,
+}
+
+impl DisplayCapabilities {
+    /// Returns capabilities supporting every feature, without a texture size limit.
+    pub fn all() -> Self {
+        DisplayCapabilities {
+            filters: true,
+            backdrop_filter: true,
+            blend_modes: true,
+            layers: true,
+            rounded_clips: true,
+            path_clips: true,
+            difference_clips: true,
+            meshes: true,
+            text_shaping: true,
+            holes: true,
+            extensions: true,
+            max_texture_size: None,
+        }
+    }
+}
+
 /// Appearance of a display item.
 #[derive(Clone)]
 pub enum GraphicsDisplayPaint {
@@ -478,6 +1012,64 @@ pub enum GraphicsDisplayPaint {
     Fill(StyleColor),
     /// The item will be stroked/outlined.
     Stroke(GraphicsDisplayStroke),
+    /// The item will be painted as per the inner paint, blended with what's behind it using a blend mode.
+    ///
+    /// Prefer constructing this through [`with_blend_mode`](GraphicsDisplayPaint::with_blend_mode).
+    Blend(Box<GraphicsDisplayPaint>, BlendMode),
+}
+
+impl GraphicsDisplayPaint {
+    /// Blends the paint with what's behind it using `mode`, replacing any previous blend mode.
+    pub fn with_blend_mode(self, mode: BlendMode) -> Self {
+        match self {
+            GraphicsDisplayPaint::Blend(paint, _) => GraphicsDisplayPaint::Blend(paint, mode),
+            paint => GraphicsDisplayPaint::Blend(Box::new(paint), mode),
+        }
+    }
+
+    /// Returns the mode the paint is blended with.
+    pub fn blend_mode(&self) -> BlendMode {
+        match self {
+            GraphicsDisplayPaint::Blend(_, mode) => *mode,
+            _ => BlendMode::Normal,
+        }
+    }
+
+    /// Returns the stroke of the paint, if it's stroked.
+    pub fn stroke(&self) -> Option<&GraphicsDisplayStroke> {
+        match self {
+            GraphicsDisplayPaint::Fill(_) => None,
+            GraphicsDisplayPaint::Stroke(stroke) => Some(stroke),
+            GraphicsDisplayPaint::Blend(paint, _) => paint.stroke(),
+        }
+    }
+}
+
+/// How a source (such as an item or a layer) is combined with the destination behind it.
+///
+/// Besides [`Plus`](BlendMode::Plus), these are the separable and non-separable blend modes of CSS/PDF compositing.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    /// Source-over alpha blending.
+    #[default]
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
+    /// Adds the source and destination colors.
+    Plus,
 }
 
 /// Describes all the possible graphical items (excluding text, see [`TextDisplayItem`](TextDisplayItem)).
@@ -529,10 +1121,20 @@ pub enum GraphicsDisplayItem {
         /// Paint style of the vector path.
         paint: GraphicsDisplayPaint,
     },
+    /// Triangles which are shaded smoothly between the colors of their vertices, such as heatmaps.
+    Mesh {
+        /// Positions and colors of the vertices.
+        vertices: Vec<(Point, Color)>,
+        /// Indices into [`vertices`](GraphicsDisplayItem::Mesh::vertices), where every three form a triangle.
+        indices: Vec<u32>,
+    },
 }
 
 impl GraphicsDisplayItem {
     /// Returns the exact maximum boundaries for the item.
+    ///
+    /// Unlike text, paths aren't memoized; their bounds are a single pass over the points of the path, without
+    /// any font lookups, and caching them would require the path to be stored behind accessors.
     pub fn bounds(&self) -> Rect {
         match self {
             GraphicsDisplayItem::Line { a, b, stroke } => {
@@ -547,44 +1149,30 @@ impl GraphicsDisplayItem {
                     Angle::radians(2.0 * ((*a - axis_rect_xy).length() / size.height).asin()),
                 )
             }
-            GraphicsDisplayItem::Rectangle { rect, paint } => match paint {
-                GraphicsDisplayPaint::Fill(_) => *rect,
-                GraphicsDisplayPaint::Stroke(stroke) => {
-                    rect.inflate(stroke.thickness / 2.0, stroke.thickness / 2.0)
-                }
-            },
-            GraphicsDisplayItem::RoundRectangle { rect, paint, .. } => match paint {
-                GraphicsDisplayPaint::Fill(_) => *rect,
-                GraphicsDisplayPaint::Stroke(stroke) => {
-                    rect.inflate(stroke.thickness / 2.0, stroke.thickness / 2.0)
-                }
+            GraphicsDisplayItem::Rectangle { rect, paint }
+            | GraphicsDisplayItem::RoundRectangle { rect, paint, .. } => match paint.stroke() {
+                None => *rect,
+                Some(stroke) => rect.inflate(stroke.thickness / 2.0, stroke.thickness / 2.0),
             },
             GraphicsDisplayItem::Ellipse { center, radii, paint } => {
                 let rect = Rect::new(
                     (center.x - radii.x, center.y - radii.y).into(),
                     (radii.x * 2.0, radii.y * 2.0).into(),
                 );
-                match paint {
-                    GraphicsDisplayPaint::Fill(_) => rect,
-                    GraphicsDisplayPaint::Stroke(stroke) => {
-                        rect.inflate(stroke.thickness / 2.0, stroke.thickness / 2.0)
-                    }
+                match paint.stroke() {
+                    None => rect,
+                    Some(stroke) => rect.inflate(stroke.thickness / 2.0, stroke.thickness / 2.0),
                 }
             }
             GraphicsDisplayItem::Image { dst, .. } => *dst,
             GraphicsDisplayItem::Path { path, paint, .. } => {
-                let inflation = if let GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
-                    thickness,
-                    ..
-                }) = paint
-                {
-                    thickness * 2.0
-                } else {
-                    0.0
-                };
+                let inflation = paint.stroke().map_or(0.0, |stroke| stroke.thickness * 2.0);
 
                 vector_path_bounds(path).inflate(inflation, inflation)
             }
+            GraphicsDisplayItem::Mesh { vertices, .. } => {
+                Rect::from_points(vertices.iter().map(|(point, _)| *point))
+            }
         }
     }
 }
@@ -650,6 +1238,42 @@ impl DisplayText {
         }
     }
 
+    /// Returns the offset of the first caret position after `index`, or `len()` if there is none.
+    ///
+    /// Simple text is segmented into extended grapheme clusters, so that a character followed by combining
+    /// marks (or an emoji sequence) is stepped over as a whole. Shaped text carries no cluster information,
+    /// so every glyph is a caret position.
+    ///
+    /// # Panics
+    /// Panics if `index` doesn't lie on a character boundary of simple text.
+    pub fn next_boundary(&self, index: usize) -> usize {
+        match self {
+            DisplayText::Simple(text) => GraphemeCursor::new(index, text.len(), true)
+                .next_boundary(text, 0)
+                .ok()
+                .flatten()
+                .unwrap_or(text.len()),
+            DisplayText::Shaped(glyphs) => (index + 1).min(glyphs.len()),
+        }
+    }
+
+    /// Returns the offset of the last caret position before `index`, or `0` if there is none.
+    ///
+    /// This is the reverse of [`next_boundary`](DisplayText::next_boundary).
+    ///
+    /// # Panics
+    /// Panics if `index` doesn't lie on a character boundary of simple text.
+    pub fn prev_boundary(&self, index: usize) -> usize {
+        match self {
+            DisplayText::Simple(text) => GraphemeCursor::new(index, text.len(), true)
+                .prev_boundary(text, 0)
+                .ok()
+                .flatten()
+                .unwrap_or(0),
+            DisplayText::Shaped(_) => index.saturating_sub(1),
+        }
+    }
+
     /// Filters characters/glyphs based on a predicate.
     pub fn filter<F>(&mut self, mut f: F)
     where
@@ -689,6 +1313,8 @@ impl From<Vec<ShapedGlyph>> for DisplayText {
 }
 
 /// Describes a text render item.
+///
+/// Create one with [`new`](TextDisplayItem::new), then set the optional fields as needed.
 #[derive(Debug, Clone)]
 pub struct TextDisplayItem {
     pub text: DisplayText,
@@ -697,28 +1323,222 @@ pub struct TextDisplayItem {
     pub size: f32,
     pub bottom_left: Point,
     pub color: StyleColor,
+    /// Outline of the glyphs, drawn underneath the fill so that only the outer half of the stroke shows.
+    ///
+    /// Unlike drawing the text several times with offsets, the outline follows the glyph shapes exactly.
+    pub stroke: Option<GraphicsDisplayStroke>,
+    /// Shadow drawn behind the text (and its outline).
+    pub shadow: Option<TextShadow>,
+    /// Lines drawn along the text, placed as per the [font metrics](FontInfo::decoration_metrics).
+    pub decorations: TextDecorations,
+    bounds_cache: TextBoundsCache,
+}
+
+/// Memoized width of a [`TextDisplayItem`](TextDisplayItem), used by its [`bounds`](TextDisplayItem::bounds).
+///
+/// The width is stored along with the font, size and (a hash of the) text it was measured with, so changing
+/// any of the fields of the item simply measures it again, rather than returning stale bounds.
+#[derive(Debug, Default)]
+struct TextBoundsCache(Mutex<Option<(TextBoundsKey, f32)>>);
+
+/// Hash of everything the width of a text item depends on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TextBoundsKey(u64);
+
+impl TextBoundsKey {
+    fn of(item: &TextDisplayItem) -> Self {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        item.font_info.source.id.hash(&mut hasher);
+        item.size.to_bits().hash(&mut hasher);
+        match &item.text {
+            DisplayText::Simple(text) => text.hash(&mut hasher),
+            DisplayText::Shaped(glyphs) => {
+                for glyph in glyphs {
+                    glyph.codepoint.hash(&mut hasher);
+                    glyph.advance.x.to_bits().hash(&mut hasher);
+                }
+            }
+        }
+        TextBoundsKey(hasher.finish())
+    }
+}
+
+impl TextBoundsCache {
+    /// Returns the memoized width for `key`, or measures and stores it.
+    fn get_or_measure(
+        &self,
+        key: TextBoundsKey,
+        measure: impl FnOnce() -> Result<f32, error::FontError>,
+    ) -> Result<f32, error::FontError> {
+        let mut cached = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        match *cached {
+            Some((cached_key, width)) if cached_key == key => Ok(width),
+            _ => {
+                let width = measure()?;
+                *cached = Some((key, width));
+                Ok(width)
+            }
+        }
+    }
+}
+
+impl Clone for TextBoundsCache {
+    fn clone(&self) -> Self {
+        TextBoundsCache(Mutex::new(*self.0.lock().unwrap_or_else(|err| err.into_inner())))
+    }
+}
+
+/// Lines drawn along text, see [`TextDisplayItem::decorations`](TextDisplayItem::decorations).
+///
+/// Decorations are drawn with the same paints as the glyphs, including the outline and shadow.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextDecorations {
+    pub underline: bool,
+    pub strikethrough: bool,
+    pub overline: bool,
+}
+
+impl TextDecorations {
+    /// Returns `true` if no line is drawn.
+    pub fn is_empty(self) -> bool {
+        !(self.underline || self.strikethrough || self.overline)
+    }
+}
+
+/// Placement of text decoration lines, in pixels for a given font size.
+///
+/// Offsets are of the top edge of each line, relative to the baseline; positive values are below the baseline.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DecorationMetrics {
+    pub underline_offset: f32,
+    pub strikethrough_offset: f32,
+    pub overline_offset: f32,
+    /// Thickness of every line.
+    pub thickness: f32,
+}
+
+/// Vertical metrics of a font at a given size and scale factor, see [`FontInfo::scaled_metrics`](FontInfo::scaled_metrics).
+///
+/// Every value is in logical pixels, snapped so that it spans a whole number of physical pixels.
+/// Distances are positive; `ascent` is above the baseline and `descent` below it.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ScaledFontMetrics {
+    pub ascent: f32,
+    pub descent: f32,
+    pub line_gap: f32,
+    pub x_height: f32,
+    pub cap_height: f32,
+    /// Scale factor the metrics were snapped for.
+    pub scale: f32,
+}
+
+impl ScaledFontMetrics {
+    /// Returns the distance between consecutive baselines.
+    #[inline]
+    pub fn line_height(&self) -> f32 {
+        self.ascent + self.descent + self.line_gap
+    }
+}
+
+/// A blurred, offset copy of text drawn behind it, see [`TextDisplayItem::shadow`](TextDisplayItem::shadow).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextShadow {
+    pub offset: Vector,
+    /// Standard deviation of the blur; `0.0` gives a hard-edged shadow.
+    pub blur: f32,
+    pub color: Color,
+}
+
+/// How text wider than the space it's given is cut off, see [`TextDisplayItem::truncated`](TextDisplayItem::truncated).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextOverflow {
+    /// The text is cut off at the edge, possibly in the middle of a glyph.
+    #[default]
+    Clip,
+    /// The text is shortened by whole graphemes and followed by an ellipsis ("…"), so that it fits entirely.
+    Ellipsis,
+    /// The text is cut off at the edge, fading out over the last two ems before it.
+    ///
+    /// Text which isn't painted with a solid color is clipped instead.
+    Fade,
 }
 
 impl TextDisplayItem {
-    /// Returns the maximum boundaries for the text.
+    /// Creates a text item without an outline, shadow or decorations.
+    ///
+    /// The width of the text is measured lazily and memoized by [`bounds`](TextDisplayItem::bounds); the
+    /// measurement is keyed by the font, size and text, so the public fields can be changed freely afterwards.
+    pub fn new(
+        text: impl Into<DisplayText>,
+        font: ResourceReference,
+        font_info: FontInfo,
+        size: f32,
+        bottom_left: Point,
+        color: impl Into<StyleColor>,
+    ) -> Self {
+        TextDisplayItem {
+            text: text.into(),
+            font,
+            font_info,
+            size,
+            bottom_left,
+            color: color.into(),
+            stroke: None,
+            shadow: None,
+            decorations: Default::default(),
+            bounds_cache: Default::default(),
+        }
+    }
+
+    /// Returns an item with the same font and paint as this one, showing `text` at `bottom_left`.
+    pub(crate) fn with_text(&self, text: DisplayText, bottom_left: Point) -> Self {
+        TextDisplayItem {
+            stroke: self.stroke.clone(),
+            shadow: self.shadow,
+            decorations: self.decorations,
+            ..TextDisplayItem::new(
+                text,
+                self.font,
+                self.font_info.clone(),
+                self.size,
+                bottom_left,
+                self.color.clone(),
+            )
+        }
+    }
+
+    /// Returns the maximum boundaries for the text, including its outline, shadow and decorations.
     ///
     /// The height of the bounding box is conservative; it doesn't change based
     /// on the contents of [`text`](TextDisplayItem::text), is defined on a per-font basis,
     /// and is "worst-case" (as in it represents the largest height value in the font).
     ///
-    /// The bounding box is identical to that of a browser's.
+    /// Without an outline, shadow or decorations, the bounding box is identical to that of a browser's.
     pub fn bounds(&self) -> Result<Rect, error::FontError> {
-        self.limited_bounds(match &self.text {
+        let bounds = self.limited_bounds(match &self.text {
             DisplayText::Simple(text) => text.len(),
             DisplayText::Shaped(glyphs) => glyphs.len(),
+        })?;
+        let bounds =
+            self.decoration_rects()?.iter().fold(bounds, |bounds, line| bounds.union(line));
+        let bounds = match &self.stroke {
+            Some(stroke) => bounds.inflate(stroke.thickness / 2.0, stroke.thickness / 2.0),
+            None => bounds,
+        };
+        Ok(match &self.shadow {
+            // a gaussian blur is practically invisible beyond 3 standard deviations.
+            Some(shadow) => bounds.union(
+                &bounds.translate(shadow.offset).inflate(shadow.blur * 3.0, shadow.blur * 3.0),
+            ),
+            None => bounds,
         })
     }
 
-    /// Returns the boundaries of the text, up to the n-th character (`limit`).
+    /// Returns the boundaries of the text, up to the offset `limit` (in bytes for simple text, or glyphs for shaped text).
     ///
     /// For more information, see [`bounds`](TextDisplayItem::bounds).
     pub fn limited_bounds(&self, limit: usize) -> Result<Rect, error::FontError> {
-        let metrics = self.font_info.font.metrics();
+        let metrics = self.font_info.metrics();
         let units_per_em = metrics.units_per_em as f32;
 
         let font_height = metrics.ascent - metrics.descent;
@@ -728,42 +1548,172 @@ impl TextDisplayItem {
 
         let y = self.bottom_left.y - metrics.ascent / units_per_em * self.size;
 
-        let width = match self.text {
+        let width = if limit == self.text.len() {
+            self.bounds_cache
+                .get_or_measure(TextBoundsKey::of(self), || self.advance_of(0..limit))?
+        } else {
+            self.advance_of(0..limit)?
+        };
+
+        Ok(Rect::new(Point::new(self.bottom_left.x, y), Size::new(width, height)))
+    }
+
+    /// Returns the caret position closest to the x-coordinate `x`, as an offset into the text.
+    ///
+    /// Offsets are in bytes for simple text and in glyphs for shaped text (as with
+    /// [`subtext`](DisplayText::subtext)), and always lie on a [boundary](DisplayText::next_boundary).
+    /// Simple text is measured with the advances of its characters, so basic text fields work without
+    /// shaping. Positions left of the text resolve to `0`, and positions right of it to `len()`.
+    pub fn char_index_at_x(&self, x: f32) -> Result<usize, error::FontError> {
+        let x = x - self.bottom_left.x;
+        let (mut index, mut start) = (0, 0.0);
+        while index < self.text.len() {
+            let next = self.text.next_boundary(index);
+            let end = start + self.advance_of(index..next)?;
+            if x < (start + end) / 2.0 {
+                return Ok(index);
+            }
+            index = next;
+            start = end;
+        }
+        Ok(index)
+    }
+
+    /// Returns the x-coordinate of the caret at `index`; the reverse of
+    /// [`char_index_at_x`](TextDisplayItem::char_index_at_x).
+    ///
+    /// # Panics
+    /// Panics if `index` is out-of-bounds, or doesn't lie on a character boundary of simple text.
+    pub fn x_at_char_index(&self, index: usize) -> Result<f32, error::FontError> {
+        Ok(self.bottom_left.x + self.advance_of(0..index)?)
+    }
+
+    /// Returns the text cut off to fit within `max_width` (measured from `bottom_left`), as per `overflow`.
+    ///
+    /// Text which already fits is returned as is. Otherwise, the text is measured a single time, grapheme by grapheme
+    /// as with [`char_index_at_x`](TextDisplayItem::char_index_at_x). [`Clip`](TextOverflow::Clip) and
+    /// [`Fade`](TextOverflow::Fade) only keep the graphemes which start within the width, but still need to be clipped
+    /// to it, which [`push_truncated_text`](DisplayListBuilder::push_truncated_text) takes care of.
+    pub fn truncated(
+        &self,
+        max_width: f32,
+        overflow: TextOverflow,
+    ) -> Result<TextDisplayItem, error::FontError> {
+        let units_per_em = self.font_info.metrics().units_per_em as f32;
+        let ellipsis_width = match overflow {
+            TextOverflow::Ellipsis => self.font_info.advance('…')? / units_per_em * self.size,
+            _ => 0.0,
+        };
+
+        // `fit` is the end of the text which still fits with the ellipsis after it.
+        let (mut index, mut x, mut fit) = (0, 0.0, 0);
+        while index < self.text.len() && x < max_width {
+            let next = self.text.next_boundary(index);
+            x += self.advance_of(index..next)?;
+            index = next;
+            if x + ellipsis_width <= max_width {
+                fit = index;
+            }
+        }
+        if index == self.text.len() && x <= max_width {
+            return Ok(self.clone());
+        }
+
+        let mut item = self.clone();
+        match overflow {
+            TextOverflow::Ellipsis if ellipsis_width > max_width => {
+                item.text = self.text.subtext(0..0)
+            }
+            TextOverflow::Ellipsis => {
+                item.text = match self.text.subtext(0..fit) {
+                    DisplayText::Simple(text) => {
+                        DisplayText::Simple(format!("{}…", text.trim_end()))
+                    }
+                    DisplayText::Shaped(mut glyphs) => {
+                        glyphs.push(ShapedGlyph {
+                            codepoint: self
+                                .font_info
+                                .font()?
+                                .glyph_for_char('…')
+                                .ok_or(error::FontError::CodepointError)?,
+                            advance: Vector::new(ellipsis_width, 0.0),
+                            offset: Vector::zero(),
+                        });
+                        DisplayText::Shaped(glyphs)
+                    }
+                };
+            }
+            TextOverflow::Clip | TextOverflow::Fade => {
+                item.text = self.text.subtext(0..index);
+                if overflow == TextOverflow::Fade {
+                    let (color, color_space) = match self.color {
+                        StyleColor::Color(color) => (color, ColorSpace::Srgb),
+                        StyleColor::TaggedColor(color, color_space) => (color, color_space),
+                        _ => return Ok(item),
+                    };
+                    let end = self.bottom_left + Size::new(max_width, 0.0);
+                    item.color = StyleColor::LinearGradient(Gradient {
+                        start: end - Size::new((self.size * 2.0).min(max_width), 0.0),
+                        end,
+                        stops: vec![(0.0, color), (1.0, Color { alpha: 0.0, ..color })],
+                        color_space,
+                    });
+                }
+            }
+        }
+        Ok(item)
+    }
+
+    /// Returns the horizontal advance of a range of the text.
+    fn advance_of(&self, range: std::ops::Range<usize>) -> Result<f32, error::FontError> {
+        Ok(match self.text {
             DisplayText::Simple(ref text) => {
-                text.as_bytes()[0..limit].iter().try_fold(
+                let units_per_em = self.font_info.metrics().units_per_em as f32;
+                text[range].chars().try_fold(
                     0.0,
-                    |width, &character| -> Result<f32, error::FontError> {
-                        Ok(width
-                            + self
-                                .font_info
-                                .font
-                                .advance(
-                                    self.font_info
-                                        .font
-                                        .glyph_for_char(character as char)
-                                        .ok_or(error::FontError::CodepointError)?,
-                                )?
-                                .x())
+                    |width, character| -> Result<f32, error::FontError> {
+                        Ok(width + self.font_info.advance(character)?)
                     },
                 )? / units_per_em
                     * self.size
             }
             DisplayText::Shaped(ref glyphs) => {
-                glyphs[0..limit].iter().fold(0.0, |width, glyph| width + glyph.advance.x)
+                glyphs[range].iter().fold(0.0, |width, glyph| width + glyph.advance.x)
             }
+        })
+    }
+
+    /// Returns the rectangles of the [decoration](TextDisplayItem::decorations) lines, spanning the width of the text.
+    pub fn decoration_rects(&self) -> Result<Vec<Rect>, error::FontError> {
+        if self.decorations.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let metrics = self.font_info.decoration_metrics(self.size);
+        let width = self.limited_bounds(self.text.len())?.size.width;
+        let line = |offset: f32| {
+            Rect::new(
+                Point::new(self.bottom_left.x, self.bottom_left.y + offset),
+                Size::new(width, metrics.thickness),
+            )
         };
 
-        Ok(Rect::new(Point::new(self.bottom_left.x, y), Size::new(width, height)))
+        let mut rects = Vec::new();
+        if self.decorations.underline {
+            rects.push(line(metrics.underline_offset));
+        }
+        if self.decorations.strikethrough {
+            rects.push(line(metrics.strikethrough_offset));
+        }
+        if self.decorations.overline {
+            rects.push(line(metrics.overline_offset));
+        }
+        Ok(rects)
     }
 
-    /// Breaks the text based on a maximum width using the standard Unicode line
-    /// breaking algorithm.
-    pub fn linebreak(
-        mut self,
-        max_width: f32,
-        line_height: f32,
-        remove_newlines: bool,
-    ) -> Result<Vec<TextDisplayItem>, error::FontError> {
+    /// Returns the offset at which the first line of the text ends, as per [`linebreak`](TextDisplayItem::linebreak),
+    /// or `None` if the text fits within a single line.
+    pub fn next_line_break(&self, max_width: f32) -> Result<Option<usize>, error::FontError> {
         let text = match &self.text {
             DisplayText::Simple(text) => text.clone(),
             DisplayText::Shaped(glyphs) => glyphs.iter().fold(String::new(), |mut text, glyph| {
@@ -773,29 +1723,33 @@ impl TextDisplayItem {
             }),
         };
 
-        let mut next = None;
-
         for (offset, hard) in xi_unicode::LineBreakIterator::new(&text) {
-            if hard || self.limited_bounds(offset)?.size.width > max_width {
-                let next_text = TextDisplayItem {
-                    text: self.text.subtext(offset..self.text.len()),
-                    font: self.font,
-                    font_info: self.font_info.clone(),
-                    size: self.size,
-                    bottom_left: self.bottom_left + Size::new(0.0, line_height),
-                    color: self.color.clone(),
-                };
-
-                if next_text.text.is_empty() {
-                    continue;
-                }
-
-                next = Some((next_text, offset));
-
-                break;
+            if (hard || self.limited_bounds(offset)?.size.width > max_width)
+                && offset < self.text.len()
+            {
+                return Ok(Some(offset));
             }
         }
 
+        Ok(None)
+    }
+
+    /// Breaks the text based on a maximum width using the standard Unicode line
+    /// breaking algorithm.
+    pub fn linebreak(
+        mut self,
+        max_width: f32,
+        line_height: f32,
+        remove_newlines: bool,
+    ) -> Result<Vec<TextDisplayItem>, error::FontError> {
+        let next = self.next_line_break(max_width)?.map(|offset| {
+            let next_text = self.with_text(
+                self.text.subtext(offset..self.text.len()),
+                self.bottom_left + Size::new(0.0, line_height),
+            );
+            (next_text, offset)
+        });
+
         let mut out = Vec::new();
 
         if let Some((next, offset)) = next {
@@ -812,7 +1766,7 @@ impl TextDisplayItem {
                 out.push(self);
             }
 
-            out.extend(next.linebreak(max_width, line_height, remove_newlines)?.into_iter());
+            out.extend(next.linebreak(max_width, line_height, remove_newlines)?);
         } else {
             out.push(self);
         }
@@ -822,7 +1776,7 @@ impl TextDisplayItem {
 
     /// Sets the top-left position of this text item, using the font baseline as an anchor.
     pub fn set_top_left(&mut self, top_left: Point) {
-        let metrics = self.font_info.font.metrics();
+        let metrics = self.font_info.metrics();
 
         self.bottom_left.x = top_left.x;
         self.bottom_left.y =
@@ -856,16 +1810,143 @@ pub type FontStyle = font_kit::properties::Style;
 pub type FontWeight = font_kit::properties::Weight;
 // Stretching of the font; condensed, extra-condensed etc.
 pub type FontStretch = font_kit::properties::Stretch;
+/// Metrics of a font, in font units; ascent, descent, units per em, etc.
+pub type FontMetrics = font_kit::metrics::Metrics;
+
+/// Source of the font IDs which key the per-thread [loaded fonts](LOADED_FONTS).
+static NEXT_FONT_ID: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug)]
+struct FontSource {
+    id: usize,
+    name: String,
+    /// Shared with every loaded instance of the font, rather than copied.
+    data: Arc<Vec<u8>>,
+    font_index: u32,
+    metrics: FontMetrics,
+}
+
+/// A font loaded on the current thread, along with its memoized measurements.
+struct LoadedFont {
+    source: Weak<FontSource>,
+    font: Rc<font_kit::font::Font>,
+    /// Horizontal glyph advances by character, in font units.
+    advances: HashMap<char, f32>,
+    /// Glyph outlines by character.
+    outlines: HashMap<char, Rc<GlyphOutline>>,
+    /// Characters by glyph ID, built from the character map on first use.
+    characters: Option<HashMap<u32, char>>,
+}
+
+impl LoadedFont {
+    fn new(source: &Arc<FontSource>, font: Rc<font_kit::font::Font>) -> Self {
+        LoadedFont {
+            source: Arc::downgrade(source),
+            font,
+            advances: HashMap::new(),
+            outlines: HashMap::new(),
+            characters: None,
+        }
+    }
+}
+
+/// The vector outline of a single glyph, in font units.
+///
+/// Unlike in the font itself, the Y axis points downwards, with the baseline at zero.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlyphOutline {
+    pub path: VectorPath,
+    /// Boundaries of the glyph, relative to its origin on the baseline.
+    pub bounds: Rect,
+}
+
+/// Collects a `font_kit` outline into a [`VectorPath`](VectorPath).
+#[derive(Default)]
+struct OutlinePathSink {
+    path: VectorPath,
+}
+
+impl OutlinePathSink {
+    fn point(v: pathfinder_geometry::vector::Vector2F) -> Point {
+        Point::new(v.x(), -v.y())
+    }
+}
+
+impl font_kit::outline::OutlineSink for OutlinePathSink {
+    fn move_to(&mut self, to: pathfinder_geometry::vector::Vector2F) {
+        self.path.push(VectorPathEvent::MoveTo { to: Self::point(to) });
+    }
+
+    fn line_to(&mut self, to: pathfinder_geometry::vector::Vector2F) {
+        self.path.push(VectorPathEvent::LineTo { to: Self::point(to) });
+    }
+
+    fn quadratic_curve_to(
+        &mut self,
+        control: pathfinder_geometry::vector::Vector2F,
+        to: pathfinder_geometry::vector::Vector2F,
+    ) {
+        self.path
+            .push(VectorPathEvent::QuadTo { control: Self::point(control), to: Self::point(to) });
+    }
+
+    fn cubic_curve_to(
+        &mut self,
+        control: pathfinder_geometry::line_segment::LineSegment2F,
+        to: pathfinder_geometry::vector::Vector2F,
+    ) {
+        self.path.push(VectorPathEvent::CubicTo {
+            c1: Self::point(control.from()),
+            c2: Self::point(control.to()),
+            to: Self::point(to),
+        });
+    }
+
+    // contours are implicitly closed when the path is filled.
+    fn close(&mut self) {}
+}
+
+thread_local! {
+    /// Fonts loaded on the current thread by [ID](FontSource::id), since `font_kit` fonts can't be shared across
+    /// threads.
+    static LOADED_FONTS: RefCell<HashMap<usize, LoadedFont>> = RefCell::new(HashMap::new());
+}
 
 /// Represents a single font.
+///
+/// This only holds the font data, so it can be freely sent across threads (e.g. along with display commands).
+/// `font_kit` fonts can't be shared across threads, so the font is no longer a public `font: Arc<Font>` field;
+/// instead, it's loaded on demand for every thread it's used on and returned by [`font`](FontInfo::font).
+///
+/// The first use of a font on a thread parses the font data again (the data itself is shared, not copied), which
+/// costs about as much as loading the font in the first place; later uses on that thread are served from a cache.
+/// [`name`](FontInfo::name), [`metrics`](FontInfo::metrics) and [`data`](FontInfo::data) never load the font.
 #[derive(Debug, Clone)]
 pub struct FontInfo {
-    name: String,
-    /// Underlying font reference.
-    pub font: Arc<font_kit::font::Font>,
+    source: Arc<FontSource>,
 }
 
 impl FontInfo {
+    /// Loads a font from `data`, which is then shared by the font on every thread.
+    fn load(data: Arc<Vec<u8>>, font_index: u32) -> Result<Self, error::FontError> {
+        let font = font_kit::font::Font::from_bytes(data.clone(), font_index)?;
+        let info = FontInfo {
+            source: Arc::new(FontSource {
+                id: NEXT_FONT_ID.fetch_add(1, Ordering::Relaxed),
+                name: font.full_name(),
+                data,
+                font_index,
+                metrics: font.metrics(),
+            }),
+        };
+        LOADED_FONTS.with(|fonts| {
+            let mut fonts = fonts.borrow_mut();
+            fonts.retain(|_, loaded| loaded.source.strong_count() > 0);
+            fonts.insert(info.source.id, LoadedFont::new(&info.source, Rc::new(font)));
+        });
+        Ok(info)
+    }
+
     /// Creates a new font reference, matched to the font `name`, with optional `fallbacks` and `properties`.
     ///
     /// See [`from_postscript_name`](FontInfo::from_postscript_name).
@@ -882,11 +1963,10 @@ impl FontInfo {
                 .collect::<Vec<_>>(),
         );
 
-        let font = font_kit::source::SystemSource::new()
-            .select_best_match(&names, &properties.unwrap_or_default())?
-            .load()?;
+        let handle = font_kit::source::SystemSource::new()
+            .select_best_match(&names, &properties.unwrap_or_default())?;
 
-        Ok(FontInfo { name: font.full_name(), font: Arc::new(font) })
+        Self::from_handle(handle)
     }
 
     /// Creates a new font reference, matched to the PostScript `name`, with optional `fallbacks`.
@@ -896,19 +1976,28 @@ impl FontInfo {
         let mut names = vec![name.to_string()];
         names.append(&mut fallbacks.iter().map(|&name| name.to_string()).collect());
 
-        let mut font = None;
+        let mut handle = None;
 
         for name in names {
-            font = font_kit::source::SystemSource::new().select_by_postscript_name(&name).ok();
+            handle = font_kit::source::SystemSource::new().select_by_postscript_name(&name).ok();
         }
 
-        let font = font
-            .ok_or_else(|| {
-                error::FontError::MatchingError(font_kit::error::SelectionError::NotFound)
-            })?
-            .load()?;
+        Self::from_handle(
+            handle.ok_or(error::FontError::MatchingError(
+                font_kit::error::SelectionError::NotFound,
+            ))?,
+        )
+    }
 
-        Ok(FontInfo { name: font.full_name(), font: Arc::new(font) })
+    fn from_handle(handle: font_kit::handle::Handle) -> Result<Self, error::FontError> {
+        match handle {
+            font_kit::handle::Handle::Path { path, font_index } => {
+                Self::from_path(path, font_index)
+            }
+            font_kit::handle::Handle::Memory { bytes, font_index } => {
+                Self::from_data(bytes, font_index)
+            }
+        }
     }
 
     /// Creates a new font reference from a font file located at `path`.
@@ -918,27 +2007,210 @@ impl FontInfo {
         path: P,
         font_index: u32,
     ) -> Result<Self, error::FontError> {
-        let font = font_kit::font::Font::from_path(path, font_index)?;
-
-        Ok(FontInfo { name: font.full_name(), font: Arc::new(font) })
+        let data = std::fs::read(path).map_err(font_kit::error::FontLoadingError::Io)?;
+        Self::load(Arc::new(data), font_index)
     }
 
     /// Creates a new font reference from font data.
     /// Similar to [`from_path`](FontInfo::from_path), however as bytes rather than a path to a file.
     pub fn from_data(data: Arc<Vec<u8>>, font_index: u32) -> Result<Self, error::FontError> {
-        let font = font_kit::font::Font::from_bytes(data, font_index)?;
+        Self::load(data, font_index)
+    }
+
+    /// Returns the underlying font, loaded for the current thread; this replaces the former public `font` field.
+    ///
+    /// The font is loaded once per thread (sharing the font data) and cached for as long as this font reference
+    /// (or any clone of it) lives, so the first call on each thread pays for parsing the font. Loading can fail,
+    /// unlike reading the former field, if the font data turns out to be unusable on this platform.
+    pub fn font(&self) -> Result<Rc<font_kit::font::Font>, error::FontError> {
+        self.with_loaded(|loaded| Ok(loaded.font.clone()))
+    }
+
+    /// Returns the metrics of the font, in font units.
+    ///
+    /// Unlike [`font`](FontInfo::font), this never loads the font.
+    #[inline]
+    pub fn metrics(&self) -> FontMetrics {
+        self.source.metrics
+    }
+
+    /// Returns the placement of underlines, strikethroughs and overlines for text of a given size.
+    ///
+    /// The underline is as suggested by the font. Fonts don't describe strikethroughs (as far as `font-kit` exposes),
+    /// so the strikethrough is centered on half the x-height, and the overline sits on the ascent.
+    pub fn decoration_metrics(&self, size: f32) -> DecorationMetrics {
+        let metrics = self.metrics();
+        let scale = size / metrics.units_per_em as f32;
+
+        let thickness = if metrics.underline_thickness > 0.0 {
+            metrics.underline_thickness * scale
+        } else {
+            size / 16.0
+        };
+        let x_height = if metrics.x_height > 0.0 { metrics.x_height } else { metrics.ascent / 2.0 };
+
+        DecorationMetrics {
+            underline_offset: -metrics.underline_position * scale,
+            strikethrough_offset: -x_height / 2.0 * scale - thickness / 2.0,
+            overline_offset: -metrics.ascent * scale,
+            thickness,
+        }
+    }
+
+    /// Returns the vertical metrics at `size` (in logical pixels), snapped to the physical pixels of a display with
+    /// the scale factor `scale`.
+    ///
+    /// Metrics computed for one scale factor shouldn't be reused for another, as the rounding differs; recompute them
+    /// when the window reports [`ScaleChanged`](crate::input::WindowEvent::ScaleChanged).
+    pub fn scaled_metrics(&self, size: f32, scale: f32) -> ScaledFontMetrics {
+        let metrics = self.metrics();
+        let scale = if scale > 0.0 { scale } else { 1.0 };
+        let units = size * scale / metrics.units_per_em as f32;
+        let snap = |value: f32| (value * units).abs().round() / scale;
+
+        let x_height = if metrics.x_height > 0.0 { metrics.x_height } else { metrics.ascent / 2.0 };
+        let cap_height = if metrics.cap_height > 0.0 { metrics.cap_height } else { metrics.ascent };
+
+        ScaledFontMetrics {
+            ascent: snap(metrics.ascent),
+            descent: snap(metrics.descent),
+            line_gap: snap(metrics.line_gap),
+            x_height: snap(x_height),
+            cap_height: snap(cap_height),
+            scale,
+        }
+    }
+
+    /// Returns the horizontal advance of a character, in font units.
+    ///
+    /// Advances are memoized per thread, so repeatedly measuring the same text is cheap.
+    pub fn advance(&self, character: char) -> Result<f32, error::FontError> {
+        self.with_loaded(|loaded| {
+            if let Some(&advance) = loaded.advances.get(&character) {
+                return Ok(advance);
+            }
+
+            let font = &loaded.font;
+            let advance = font
+                .advance(font.glyph_for_char(character).ok_or(error::FontError::CodepointError)?)?
+                .x();
+            loaded.advances.insert(character, advance);
+            Ok(advance)
+        })
+    }
+
+    /// Returns the vector outline of the glyph of a character.
+    ///
+    /// Like advances, outlines are memoized per thread.
+    pub fn outline(&self, character: char) -> Result<Rc<GlyphOutline>, error::FontError> {
+        self.with_loaded(|loaded| {
+            if let Some(outline) = loaded.outlines.get(&character) {
+                return Ok(outline.clone());
+            }
+
+            let font = &loaded.font;
+            let glyph = font.glyph_for_char(character).ok_or(error::FontError::CodepointError)?;
+            let mut sink = OutlinePathSink::default();
+            font.outline(glyph, font_kit::hinting::HintingOptions::None, &mut sink)?;
+            let bounds = font.typographic_bounds(glyph)?;
+            let outline = Rc::new(GlyphOutline {
+                path: sink.path,
+                bounds: Rect::new(
+                    Point::new(bounds.origin_x(), -bounds.max_y()),
+                    Size::new(bounds.width(), bounds.height()),
+                ),
+            });
+            loaded.outlines.insert(character, outline.clone());
+            Ok(outline)
+        })
+    }
 
-        Ok(FontInfo { name: font.full_name(), font: Arc::new(font) })
+    /// Returns the character which maps to a glyph ID (as in [`ShapedGlyph::codepoint`](ShapedGlyph::codepoint)),
+    /// or `None` if no character (within the first two Unicode planes) maps to it.
+    ///
+    /// If several characters map to the glyph, the lowest is returned. The first lookup on each thread scans the
+    /// character map of the font, so this is meant for occasional conversions rather than drawing.
+    pub fn character(&self, glyph: u32) -> Result<Option<char>, error::FontError> {
+        self.with_loaded(|loaded| {
+            let font = &loaded.font;
+            let characters = loaded.characters.get_or_insert_with(|| {
+                let mut characters = HashMap::new();
+                for character in (0..=0x1FFFF).filter_map(std::char::from_u32) {
+                    if let Some(glyph) = font.glyph_for_char(character) {
+                        characters.entry(glyph).or_insert(character);
+                    }
+                }
+                characters
+            });
+            Ok(characters.get(&glyph).copied())
+        })
+    }
+
+    fn with_loaded<R>(
+        &self,
+        f: impl FnOnce(&mut LoadedFont) -> Result<R, error::FontError>,
+    ) -> Result<R, error::FontError> {
+        LOADED_FONTS.with(|fonts| {
+            let mut fonts = fonts.borrow_mut();
+            if !fonts.contains_key(&self.source.id) {
+                // dropped fonts are only pruned when a font is loaded, keeping lookups cheap.
+                fonts.retain(|_, loaded| loaded.source.strong_count() > 0);
+            }
+            let loaded = match fonts.entry(self.source.id) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let font = font_kit::font::Font::from_bytes(
+                        self.source.data.clone(),
+                        self.source.font_index,
+                    )?;
+                    entry.insert(LoadedFont::new(&self.source, Rc::new(font)))
+                }
+            };
+            f(loaded)
+        })
     }
 
     /// Returns the final unique name of the loaded font.
+    ///
+    /// The name is read once, when the font is created, so this never loads the font.
     pub fn name(&self) -> String {
-        self.name.clone()
+        self.source.name.clone()
     }
 
     /// Returns the font data as bytes.
     pub fn data(&self) -> Option<Vec<u8>> {
-        Some((*self.font.copy_font_data()?).clone())
+        Some((*self.source.data).clone())
+    }
+}
+
+/// Draws a text blob resource (see [`ResourceDescriptor::TextBlob`](ResourceDescriptor::TextBlob)).
+///
+/// Unlike [`TextDisplayItem`](TextDisplayItem), the glyph runs aren't rebuilt every time the item is drawn,
+/// which makes this preferable for static text such as labels.
+#[derive(Debug, Clone)]
+pub struct TextBlobDisplayItem {
+    pub blob: ResourceReference,
+    pub bottom_left: Point,
+    pub color: StyleColor,
+    /// Boundaries of the text, relative to [`bottom_left`](TextBlobDisplayItem::bottom_left).
+    pub extents: Rect,
+}
+
+impl TextBlobDisplayItem {
+    /// Creates an item drawing `blob`, which was created from `text`, at the position and in the color of `text`.
+    pub fn new(blob: ResourceReference, text: &TextDisplayItem) -> Result<Self, error::FontError> {
+        Ok(TextBlobDisplayItem {
+            blob,
+            bottom_left: text.bottom_left,
+            color: text.color.clone(),
+            extents: text.bounds()?.translate(-text.bottom_left.to_vector()),
+        })
+    }
+
+    /// Returns the boundaries of the text.
+    #[inline]
+    pub fn bounds(&self) -> Rect {
+        self.extents.translate(self.bottom_left.to_vector())
     }
 }
 
@@ -949,6 +2221,8 @@ pub enum DisplayItem {
     Graphics(GraphicsDisplayItem),
     /// Render-able text item.
     Text(TextDisplayItem),
+    /// Retained text, see [`TextBlobDisplayItem`](TextBlobDisplayItem).
+    TextBlob(TextBlobDisplayItem),
 }
 
 impl DisplayItem {
@@ -957,6 +2231,7 @@ impl DisplayItem {
         match self {
             DisplayItem::Graphics(item) => Ok(item.bounds()),
             DisplayItem::Text(text) => Ok(text.bounds()?),
+            DisplayItem::TextBlob(blob) => Ok(blob.bounds()),
         }
     }
 }
@@ -973,20 +2248,31 @@ pub enum DisplayClip {
     /// Rectangle clip with rounded corners.
     RoundRectangle {
         rect: Rect,
-        /// Corner radii.
+        /// Corner radii (from top-left, top-right, bottom-left, bottom-right).
         radii: [f32; 4],
+        antialias: bool,
     },
     /// Elliptical clip.
-    Ellipse { center: Point, radii: Vector },
+    Ellipse { center: Point, radii: Vector, antialias: bool },
     /// Vector path clip.
-    Path { path: VectorPath, is_closed: bool },
+    Path { path: VectorPath, is_closed: bool, antialias: bool },
 }
 
 impl DisplayClip {
+    /// Returns `true` if the edges of the clip are antialiased.
+    pub fn antialias(&self) -> bool {
+        match self {
+            DisplayClip::Rectangle { antialias, .. }
+            | DisplayClip::RoundRectangle { antialias, .. }
+            | DisplayClip::Ellipse { antialias, .. }
+            | DisplayClip::Path { antialias, .. } => *antialias,
+        }
+    }
+
     pub fn bounds(&self) -> Rect {
         match self {
             DisplayClip::Rectangle { rect, .. } | DisplayClip::RoundRectangle { rect, .. } => *rect,
-            DisplayClip::Ellipse { center, radii } => Rect::new(
+            DisplayClip::Ellipse { center, radii, .. } => Rect::new(
                 (center.x - radii.x, center.y - radii.y).into(),
                 (radii.x * 2.0, radii.y * 2.0).into(),
             ),
@@ -995,21 +2281,36 @@ impl DisplayClip {
     }
 }
 
+/// How a clip is combined with the current clip of the draw state.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ClipOp {
+    /// Drawing is limited to the inside of the clip.
+    #[default]
+    Intersect,
+    /// Drawing is limited to the outside of the clip, i.e. the clip cuts a hole.
+    ///
+    /// Not every display supports this (see [`DisplayCapabilities`](DisplayCapabilities));
+    /// [`degrade`](degrade::degrade) emulates it with a path clip.
+    Difference,
+}
+
 /// Describes all possible display commands.
+// text items are among the most common commands; boxing them would cost an allocation per item.
+#[allow(clippy::large_enum_variant)]
 #[derive(Clone)]
 pub enum DisplayCommand {
-    /// Display an item with an optional filter.
-    Item(DisplayItem, Option<Filter>),
-    /// Applies a filter onto the frame with a mask.
-    BackdropFilter(DisplayClip, Filter),
+    /// Display an item through a chain of filters (which may be empty).
+    Item(DisplayItem, Vec<FilterOp>),
+    /// Applies a chain of filters onto the frame with a mask.
+    BackdropFilter(DisplayClip, Vec<FilterOp>),
     /// Pushes a clip onto the draw state.
     /// To remove the clip, call this after a [`save`](DisplayCommand::Save) command, which once [`restored`](DisplayCommand::Restore), the clip will be removed.
-    Clip(DisplayClip),
+    Clip(DisplayClip, ClipOp),
     /// Saves the draw state (clip and transformations).
     Save,
     /// Saves the draw state (clip and transformations) and begins drawing into a new layer.
-    /// The float value is the layer opacity.
-    SaveLayer(f32),
+    /// The layer is composited with the given opacity and blend mode once [restored](DisplayCommand::Restore).
+    SaveLayer(f32, BlendMode),
     /// Restores a last saved draw state.
     Restore,
     /// Adds translation to the transformation matrix.
@@ -1020,6 +2321,12 @@ pub enum DisplayCommand {
     Rotate(Angle),
     /// Fills the clipped region with a solid color.
     Clear(Color),
+    /// Cuts a hole into `rect` (clearing it to transparent, within the current clip) for external content, such as a
+    /// 3D viewport, and invokes the [hole callback](GraphicsDisplay::set_hole_callback) of the ID at this point
+    /// in the draw order, so that the content is composited between the layers of UI drawn before and after.
+    Hole(u64, Rect),
+    /// A command defined outside of Reclutch, see [`CustomCommand`](CustomCommand).
+    Extension(Box<dyn CustomCommand>),
 }
 
 impl DisplayCommand {
@@ -1029,22 +2336,37 @@ impl DisplayCommand {
         Ok(match self {
             DisplayCommand::Item(item, _) => Some(item.bounds()?),
             DisplayCommand::BackdropFilter(item, _) => Some(item.bounds()),
-            DisplayCommand::Clip(clip) => Some(clip.bounds()),
+            DisplayCommand::Clip(clip, _) => Some(clip.bounds()),
+            DisplayCommand::Hole(_, rect) => Some(*rect),
+            DisplayCommand::Extension(command) => command.bounds(),
             _ => None,
         })
     }
 }
 
+/// Converts a rectangle in logical pixels to physical pixels.
+pub fn to_physical(rect: Rect, scale: ScaleFactor) -> PhysicalRect {
+    rect * scale
+}
+
+/// Converts a rectangle in physical pixels to logical pixels.
+pub fn to_logical(rect: PhysicalRect, scale: ScaleFactor) -> Rect {
+    rect / scale
+}
+
 /// Returns the total maximum for a list of display commands.
+///
+/// Display commands are `Send + Sync`, so the bounds of independent display lists (e.g. one per command group) can
+/// be computed in parallel, such as from a rayon `par_iter`. Text measurement loads the font once per thread.
 pub fn display_list_bounds(display_list: &[DisplayCommand]) -> Result<Rect, error::FontError> {
     Ok(display_list
         .iter()
-        .filter_map(|disp| {
-            if let DisplayCommand::Item(item, _) = disp {
-                Some(item.bounds())
-            } else {
-                None
-            }
+        .filter_map(|disp| match disp {
+            DisplayCommand::Item(item, _) => Some(item.bounds()),
+            // holes are drawn by their callbacks, which must run whenever the hole is visible.
+            DisplayCommand::Hole(_, rect) => Some(Ok(*rect)),
+            DisplayCommand::Extension(command) => command.bounds().map(Ok),
+            _ => None,
         })
         .try_fold::<Option<Rect>, _, Result<_, error::FontError>>(None, |rect, bounds| {
             let bounds = bounds?;
@@ -1059,15 +2381,101 @@ pub struct Gradient {
     pub start: Point,
     pub end: Point,
     pub stops: Vec<(f32, Color)>,
+    /// Color space the stop colors are specified in.
+    pub color_space: ColorSpace,
 }
 
+/// RGBA color. Unless tagged otherwise (e.g. with [`StyleColor::TaggedColor`](StyleColor::TaggedColor)), this is in sRGB.
 pub type Color = Srgba;
 
+/// Color space in which the components of a [`Color`](Color) are interpreted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    /// Standard sRGB (gamma-encoded).
+    #[default]
+    Srgb,
+    /// Display P3; sRGB transfer function with the wider DCI-P3 primaries.
+    DisplayP3,
+    /// Linear sRGB.
+    LinearSrgb,
+}
+
+const SRGB_TO_P3: [[f32; 3]; 3] = [
+    [0.822_462_1, 0.177_538, 0.0],
+    [0.033_194_1, 0.966_805_8, 0.0],
+    [0.017_082_7, 0.072_397_4, 0.910_519_9],
+];
+
+const P3_TO_SRGB: [[f32; 3]; 3] = [
+    [1.224_940_1, -0.224_940_4, 0.0],
+    [-0.042_056_9, 1.042_057_1, 0.0],
+    [-0.019_637_6, -0.078_636_1, 1.098_273_5],
+];
+
+fn srgb_decode(c: f32) -> f32 {
+    let a = c.abs();
+    let l = if a <= 0.040_45 { a / 12.92 } else { ((a + 0.055) / 1.055).powf(2.4) };
+    l.copysign(c)
+}
+
+fn srgb_encode(l: f32) -> f32 {
+    let a = l.abs();
+    let c = if a <= 0.003_130_8 { a * 12.92 } else { 1.055 * a.powf(1.0 / 2.4) - 0.055 };
+    c.copysign(l)
+}
+
+fn mul_matrix(m: &[[f32; 3]; 3], v: [f32; 3]) -> [f32; 3] {
+    let row = |r: &[f32; 3]| r[0] * v[0] + r[1] * v[1] + r[2] * v[2];
+    [row(&m[0]), row(&m[1]), row(&m[2])]
+}
+
+impl ColorSpace {
+    /// Converts a color from this color space to another.
+    ///
+    /// Colors outside the destination gamut are not clipped (components may exceed `0..=1`).
+    pub fn convert(self, color: Color, to: ColorSpace) -> Color {
+        if self == to {
+            return color;
+        }
+
+        let rgb = [color.red, color.green, color.blue];
+        let linear_srgb = match self {
+            ColorSpace::Srgb => [srgb_decode(rgb[0]), srgb_decode(rgb[1]), srgb_decode(rgb[2])],
+            ColorSpace::LinearSrgb => rgb,
+            ColorSpace::DisplayP3 => mul_matrix(
+                &P3_TO_SRGB,
+                [srgb_decode(rgb[0]), srgb_decode(rgb[1]), srgb_decode(rgb[2])],
+            ),
+        };
+        let out = match to {
+            ColorSpace::Srgb => linear_srgb,
+            ColorSpace::LinearSrgb => linear_srgb,
+            ColorSpace::DisplayP3 => mul_matrix(&SRGB_TO_P3, linear_srgb),
+        };
+        let out = match to {
+            ColorSpace::LinearSrgb => out,
+            ColorSpace::Srgb | ColorSpace::DisplayP3 => {
+                [srgb_encode(out[0]), srgb_encode(out[1]), srgb_encode(out[2])]
+            }
+        };
+
+        Color::new(out[0], out[1], out[2], color.alpha)
+    }
+
+    /// Converts a color in this color space to sRGB.
+    #[inline]
+    pub fn to_srgb(self, color: Color) -> Color {
+        self.convert(color, ColorSpace::Srgb)
+    }
+}
+
 /// Possible ways to paint a stroke/fill.
 #[derive(Debug, Clone)]
 pub enum StyleColor {
     /// Solid color.
     Color(Color),
+    /// Solid color in a specific color space.
+    TaggedColor(Color, ColorSpace),
     /// Linear gradient (simply from point A to B).
     LinearGradient(Gradient),
     /// Radial gradient (center being point A and point B being the edge of the circle).
@@ -1079,6 +2487,7 @@ impl StyleColor {
     pub fn color_or_black(&self) -> Color {
         match self {
             StyleColor::Color(color) => *color,
+            StyleColor::TaggedColor(color, space) => space.to_srgb(*color),
             _ => Color::new(0.0, 0.0, 0.0, 1.0),
         }
     }
@@ -1090,11 +2499,262 @@ impl From<Color> for StyleColor {
     }
 }
 
-/// Graphical filter.
+/// A single graphical filter.
+///
+/// Filters are given as a chain (`Vec<FilterOp>`), where each filter is applied onto the output of the previous one;
+/// for instance, frosted glass is a [`Blur`](FilterOp::Blur) followed by a [`Brightness`](FilterOp::Brightness).
 #[derive(Debug, Clone, Copy, PartialEq)]
-pub enum Filter {
+pub enum FilterOp {
+    /// Gaussian blur, given as the standard deviation on the X and Y axes.
     Blur(f32, f32),
+    /// Inverts the colors.
     Invert,
+    /// Desaturates the colors, from `0.0` (unchanged) to `1.0` (completely gray).
+    Grayscale(f32),
+    /// Scales the colors; `1.0` leaves them unchanged.
+    Brightness(f32),
+    /// Scales the contrast around mid-gray; `1.0` leaves it unchanged.
+    Contrast(f32),
+    /// Rotates the hue of the colors.
+    HueRotate(Angle),
+    /// Draws a blurred, tinted copy of the input offset behind it.
+    DropShadow {
+        offset: Vector,
+        /// Standard deviation of the blur on the X and Y axes.
+        sigma: (f32, f32),
+        color: Color,
+    },
+    /// Transforms colors through a row-major 4x5 matrix. The fifth column is an offset, where `1.0` is full intensity.
+    ColorMatrix([f32; 20]),
+}
+
+impl FilterOp {
+    /// Returns the color matrix equivalent to the filter (in the layout of [`ColorMatrix`](FilterOp::ColorMatrix)),
+    /// or `None` for filters which aren't per-pixel color transformations (i.e. blurs and shadows).
+    ///
+    /// The matrices are those of the CSS filter effects.
+    pub fn color_matrix(&self) -> Option<[f32; 20]> {
+        Some(match *self {
+            FilterOp::Blur(..) | FilterOp::DropShadow { .. } => return None,
+            FilterOp::Invert => [
+                -1.0, 0.0, 0.0, 0.0, 1.0, //
+                0.0, -1.0, 0.0, 0.0, 1.0, //
+                0.0, 0.0, -1.0, 0.0, 1.0, //
+                0.0, 0.0, 0.0, 1.0, 0.0,
+            ],
+            FilterOp::Grayscale(amount) => {
+                let s = 1.0 - amount.clamp(0.0, 1.0);
+                [
+                    0.2126 + 0.7874 * s,
+                    0.7152 - 0.7152 * s,
+                    0.0722 - 0.0722 * s,
+                    0.0,
+                    0.0,
+                    0.2126 - 0.2126 * s,
+                    0.7152 + 0.2848 * s,
+                    0.0722 - 0.0722 * s,
+                    0.0,
+                    0.0,
+                    0.2126 - 0.2126 * s,
+                    0.7152 - 0.7152 * s,
+                    0.0722 + 0.9278 * s,
+                    0.0,
+                    0.0,
+                    0.0,
+                    0.0,
+                    0.0,
+                    1.0,
+                    0.0,
+                ]
+            }
+            FilterOp::Brightness(b) => [
+                b, 0.0, 0.0, 0.0, 0.0, //
+                0.0, b, 0.0, 0.0, 0.0, //
+                0.0, 0.0, b, 0.0, 0.0, //
+                0.0, 0.0, 0.0, 1.0, 0.0,
+            ],
+            FilterOp::Contrast(c) => {
+                let t = (1.0 - c) / 2.0;
+                [
+                    c, 0.0, 0.0, 0.0, t, //
+                    0.0, c, 0.0, 0.0, t, //
+                    0.0, 0.0, c, 0.0, t, //
+                    0.0, 0.0, 0.0, 1.0, 0.0,
+                ]
+            }
+            FilterOp::HueRotate(angle) => {
+                let (sin, cos) = angle.sin_cos();
+                [
+                    0.213 + cos * 0.787 - sin * 0.213,
+                    0.715 - cos * 0.715 - sin * 0.715,
+                    0.072 - cos * 0.072 + sin * 0.928,
+                    0.0,
+                    0.0,
+                    0.213 - cos * 0.213 + sin * 0.143,
+                    0.715 + cos * 0.285 + sin * 0.140,
+                    0.072 - cos * 0.072 - sin * 0.283,
+                    0.0,
+                    0.0,
+                    0.213 - cos * 0.213 - sin * 0.787,
+                    0.715 - cos * 0.715 + sin * 0.715,
+                    0.072 + cos * 0.928 + sin * 0.072,
+                    0.0,
+                    0.0,
+                    0.0,
+                    0.0,
+                    0.0,
+                    1.0,
+                    0.0,
+                ]
+            }
+            FilterOp::ColorMatrix(matrix) => matrix,
+        })
+    }
+}
+
+/// How the frame is sampled when scaled from a [virtual resolution](VirtualResolution) to the surface.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScaleFilter {
+    /// Nearest-neighbor sampling, which keeps pixel art crisp.
+    Nearest,
+    /// Bilinear sampling.
+    #[default]
+    Linear,
+}
+
+/// Fixed resolution which the UI is drawn at, regardless of the size of the surface, see
+/// [`set_virtual_resolution`](GraphicsDisplay::set_virtual_resolution).
+///
+/// On present, the frame is scaled uniformly to fit the surface, and centered with the remaining area (the letterbox
+/// or pillarbox bars) filled with [`letterbox`](VirtualResolution::letterbox).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VirtualResolution {
+    /// Size of the frame, in pixels.
+    pub size: (u32, u32),
+    pub filter: ScaleFilter,
+    pub letterbox: Color,
+    /// Scales only by whole numbers (if the surface is large enough), so that every pixel is scaled evenly.
+    pub integer_scaling: bool,
+}
+
+impl VirtualResolution {
+    /// Creates a virtual resolution with bilinear sampling and black bars.
+    pub fn new(size: (u32, u32)) -> Self {
+        VirtualResolution {
+            size,
+            filter: ScaleFilter::Linear,
+            letterbox: Color::new(0.0, 0.0, 0.0, 1.0),
+            integer_scaling: false,
+        }
+    }
+
+    /// Returns the scale of the frame on a surface of size `surface`.
+    pub fn scale(&self, surface: (u32, u32)) -> f32 {
+        let scale = (surface.0 as f32 / self.size.0.max(1) as f32)
+            .min(surface.1 as f32 / self.size.1.max(1) as f32);
+        if self.integer_scaling && scale >= 1.0 {
+            scale.floor()
+        } else {
+            scale
+        }
+    }
+
+    /// Returns where the frame is placed on a surface of size `surface`, in surface pixels.
+    pub fn placement(&self, surface: (u32, u32)) -> Rect {
+        let scale = self.scale(surface);
+        let size = Size::new(self.size.0 as f32 * scale, self.size.1 as f32 * scale);
+        let origin = Point::new(
+            (surface.0 as f32 - size.width) / 2.0,
+            (surface.1 as f32 - size.height) / 2.0,
+        );
+        Rect::new(origin.round(), size)
+    }
+
+    /// Maps a rectangle in the frame onto a surface of size `surface`, e.g. to find a region of the frame for
+    /// [`read_pixels`](GraphicsDisplay::read_pixels).
+    pub fn to_surface(&self, rect: Rect, surface: (u32, u32)) -> Rect {
+        let placement = self.placement(surface);
+        let scale = self.scale(surface);
+        Rect::new(placement.origin + rect.origin.to_vector() * scale, rect.size * scale)
+    }
+
+    /// Maps a point on the surface (e.g. the cursor position) into the frame, or returns `None` if it's on the bars.
+    pub fn to_virtual(&self, point: Point, surface: (u32, u32)) -> Option<Point> {
+        let placement = self.placement(surface);
+        if !placement.contains(point) {
+            return None;
+        }
+        let scale = self.scale(surface);
+        Some(((point - placement.origin) / scale).to_point())
+    }
+}
+
+/// Color transformation applied to every presented frame as a whole, see
+/// [`set_color_correction`](GraphicsDisplay::set_color_correction).
+///
+/// This is for display-wide adjustments such as accessibility settings or a night light, which would otherwise
+/// require changing the colors of every widget. Gamma correction is applied first, then brightness, then the tint.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorCorrection {
+    /// Gamma of the correction; each color channel `c` becomes `c^(1 / gamma)`, so values above `1.0` brighten mid-tones.
+    pub gamma: f32,
+    /// Scales the colors; `1.0` leaves them unchanged.
+    pub brightness: f32,
+    /// Multiplies the colors, ignoring alpha; white leaves them unchanged.
+    pub tint: Color,
+}
+
+impl Default for ColorCorrection {
+    fn default() -> Self {
+        ColorCorrection { gamma: 1.0, brightness: 1.0, tint: Color::new(1.0, 1.0, 1.0, 1.0) }
+    }
+}
+
+impl ColorCorrection {
+    /// Returns a correction which warms the colors by reducing blue (and some green) light, from `0.0` (unchanged)
+    /// to `1.0` (strongest).
+    pub fn night_light(strength: f32) -> Self {
+        let strength = strength.clamp(0.0, 1.0);
+        ColorCorrection {
+            tint: Color::new(1.0, 1.0 - 0.25 * strength, 1.0 - 0.6 * strength, 1.0),
+            ..Default::default()
+        }
+    }
+
+    /// Returns `true` if the correction leaves colors unchanged.
+    pub fn is_identity(&self) -> bool {
+        *self == Default::default()
+    }
+
+    /// Returns the brightness and tint as a color matrix (in the layout of [`ColorMatrix`](FilterOp::ColorMatrix)),
+    /// which is applied after gamma correction.
+    pub fn color_matrix(&self) -> [f32; 20] {
+        let tint = self.tint;
+        let (r, g, b) = (tint.red, tint.green, tint.blue);
+        let (r, g, b) = (r * self.brightness, g * self.brightness, b * self.brightness);
+        [
+            r, 0.0, 0.0, 0.0, 0.0, //
+            0.0, g, 0.0, 0.0, 0.0, //
+            0.0, 0.0, b, 0.0, 0.0, //
+            0.0, 0.0, 0.0, 1.0, 0.0,
+        ]
+    }
+
+    /// Returns the gamma correction of a single color channel, from `0.0` to `1.0`.
+    pub fn apply_gamma(&self, channel: f32) -> f32 {
+        channel.clamp(0.0, 1.0).powf(1.0 / self.gamma)
+    }
+
+    /// Applies the correction to a single color, as the display does to every pixel.
+    pub fn apply(&self, color: Color) -> Color {
+        let m = self.color_matrix();
+        Color::new(
+            (self.apply_gamma(color.red) * m[0]).clamp(0.0, 1.0),
+            (self.apply_gamma(color.green) * m[6]).clamp(0.0, 1.0),
+            (self.apply_gamma(color.blue) * m[12]).clamp(0.0, 1.0),
+            color.alpha,
+        )
+    }
 }
 
 /// Interface to simplify creating a list of display commands.
@@ -1120,11 +2780,11 @@ impl DisplayListBuilder {
         a: Point,
         b: Point,
         stroke: GraphicsDisplayStroke,
-        filter: Option<Filter>,
+        filters: Vec<FilterOp>,
     ) {
         self.display_list.push(DisplayCommand::Item(
             DisplayItem::Graphics(GraphicsDisplayItem::Line { a, b, stroke }),
-            filter,
+            filters,
         ));
     }
 
@@ -1133,11 +2793,11 @@ impl DisplayListBuilder {
         &mut self,
         rect: Rect,
         paint: GraphicsDisplayPaint,
-        filter: Option<Filter>,
+        filters: Vec<FilterOp>,
     ) {
         self.display_list.push(DisplayCommand::Item(
             DisplayItem::Graphics(GraphicsDisplayItem::Rectangle { rect, paint }),
-            filter,
+            filters,
         ));
     }
 
@@ -1147,11 +2807,11 @@ impl DisplayListBuilder {
         rect: Rect,
         radii: [f32; 4],
         paint: GraphicsDisplayPaint,
-        filter: Option<Filter>,
+        filters: Vec<FilterOp>,
     ) {
         self.display_list.push(DisplayCommand::Item(
             DisplayItem::Graphics(GraphicsDisplayItem::RoundRectangle { rect, radii, paint }),
-            filter,
+            filters,
         ));
     }
 
@@ -1161,11 +2821,11 @@ impl DisplayListBuilder {
         center: Point,
         radii: Vector,
         paint: GraphicsDisplayPaint,
-        filter: Option<Filter>,
+        filters: Vec<FilterOp>,
     ) {
         self.display_list.push(DisplayCommand::Item(
             DisplayItem::Graphics(GraphicsDisplayItem::Ellipse { center, radii, paint }),
-            filter,
+            filters,
         ));
     }
 
@@ -1175,7 +2835,7 @@ impl DisplayListBuilder {
         src: impl Into<Option<Rect>>,
         dst: Rect,
         image: ResourceReference,
-        filter: Option<Filter>,
+        filters: Vec<FilterOp>,
     ) {
         self.display_list.push(DisplayCommand::Item(
             DisplayItem::Graphics(GraphicsDisplayItem::Image {
@@ -1183,7 +2843,7 @@ impl DisplayListBuilder {
                 dst,
                 resource: image,
             }),
-            filter,
+            filters,
         ));
     }
 
@@ -1193,54 +2853,133 @@ impl DisplayListBuilder {
         path: VectorPath,
         is_closed: bool,
         paint: GraphicsDisplayPaint,
-        filter: Option<Filter>,
+        filters: Vec<FilterOp>,
     ) {
         self.display_list.push(DisplayCommand::Item(
             DisplayItem::Graphics(GraphicsDisplayItem::Path { path, is_closed, paint }),
-            filter,
+            filters,
+        ));
+    }
+
+    /// Pushes a mesh of smoothly shaded triangles; every three `indices` into `vertices` form a triangle.
+    pub fn push_mesh(
+        &mut self,
+        vertices: Vec<(Point, Color)>,
+        indices: Vec<u32>,
+        filters: Vec<FilterOp>,
+    ) {
+        self.display_list.push(DisplayCommand::Item(
+            DisplayItem::Graphics(GraphicsDisplayItem::Mesh { vertices, indices }),
+            filters,
         ));
     }
 
     /// Pushes a line of text.
-    pub fn push_text(&mut self, text: TextDisplayItem, filter: Option<Filter>) {
-        self.display_list.push(DisplayCommand::Item(DisplayItem::Text(text), filter));
+    pub fn push_text(&mut self, text: TextDisplayItem, filters: Vec<FilterOp>) {
+        self.display_list.push(DisplayCommand::Item(DisplayItem::Text(text), filters));
     }
 
-    /// Pushes a rectangle which applies a filter on everything behind it.
-    pub fn push_rectangle_backdrop(&mut self, rect: Rect, antialias: bool, filter: Filter) {
+    /// Pushes a line of text cut off at `max_width`, as per [`TextDisplayItem::truncated`](TextDisplayItem::truncated).
+    ///
+    /// Clipped and faded text is pushed within its own save/restore.
+    pub fn push_truncated_text(
+        &mut self,
+        text: TextDisplayItem,
+        max_width: f32,
+        overflow: TextOverflow,
+        filters: Vec<FilterOp>,
+    ) -> Result<(), error::FontError> {
+        let truncated = text.truncated(max_width, overflow)?;
+        if overflow == TextOverflow::Ellipsis {
+            self.push_text(truncated, filters);
+        } else {
+            let bounds = truncated.bounds()?;
+            self.save();
+            self.push_rectangle_clip(
+                Rect::new(
+                    bounds.origin,
+                    Size::new(text.bottom_left.x + max_width - bounds.origin.x, bounds.size.height),
+                ),
+                true,
+            );
+            self.push_text(truncated, filters);
+            self.restore();
+        }
+        Ok(())
+    }
+
+    /// Pushes a text blob resource.
+    pub fn push_text_blob(&mut self, blob: TextBlobDisplayItem, filters: Vec<FilterOp>) {
+        self.display_list.push(DisplayCommand::Item(DisplayItem::TextBlob(blob), filters));
+    }
+
+    /// Pushes a hole for external content, see [`DisplayCommand::Hole`](DisplayCommand::Hole).
+    pub fn push_hole(&mut self, id: u64, rect: Rect) {
+        self.display_list.push(DisplayCommand::Hole(id, rect));
+    }
+
+    /// Pushes a custom command, see [`DisplayCommand::Extension`](DisplayCommand::Extension).
+    pub fn push_extension(&mut self, command: impl CustomCommand) {
+        self.display_list.push(DisplayCommand::Extension(Box::new(command)));
+    }
+
+    /// Pushes a rectangle which applies filters on everything behind it.
+    pub fn push_rectangle_backdrop(&mut self, rect: Rect, antialias: bool, filters: Vec<FilterOp>) {
         self.display_list.push(DisplayCommand::BackdropFilter(
             DisplayClip::Rectangle { rect, antialias },
-            filter,
+            filters,
         ));
     }
 
-    /// Pushes a rectangle with rounded corners which applies a filter on everything behind it.
-    pub fn push_round_rectangle_backdrop(&mut self, rect: Rect, radii: [f32; 4], filter: Filter) {
+    /// Pushes a rectangle with rounded corners which applies filters on everything behind it.
+    pub fn push_round_rectangle_backdrop(
+        &mut self,
+        rect: Rect,
+        radii: [f32; 4],
+        filters: Vec<FilterOp>,
+    ) {
         self.display_list.push(DisplayCommand::BackdropFilter(
-            DisplayClip::RoundRectangle { rect, radii },
-            filter,
+            DisplayClip::RoundRectangle { rect, radii, antialias: true },
+            filters,
         ));
     }
 
-    /// Pushes an ellipse which applies a filter on everything behind it.
-    pub fn push_ellipse_backdrop(&mut self, center: Point, radii: Vector, filter: Filter) {
-        self.display_list
-            .push(DisplayCommand::BackdropFilter(DisplayClip::Ellipse { center, radii }, filter));
+    /// Pushes an ellipse which applies filters on everything behind it.
+    pub fn push_ellipse_backdrop(&mut self, center: Point, radii: Vector, filters: Vec<FilterOp>) {
+        self.display_list.push(DisplayCommand::BackdropFilter(
+            DisplayClip::Ellipse { center, radii, antialias: true },
+            filters,
+        ));
     }
 
     /// Pushes a rectangle which clips proceeding display commands.
     pub fn push_rectangle_clip(&mut self, rect: Rect, antialias: bool) {
-        self.display_list.push(DisplayCommand::Clip(DisplayClip::Rectangle { rect, antialias }));
+        self.push_clip(DisplayClip::Rectangle { rect, antialias }, ClipOp::Intersect);
     }
 
-    /// Pushes a rectangle with rounded corners which clips proceeding display commands.
+    /// Pushes an antialiased rectangle with rounded corners which clips proceeding display commands.
     pub fn push_round_rectangle_clip(&mut self, rect: Rect, radii: [f32; 4]) {
-        self.display_list.push(DisplayCommand::Clip(DisplayClip::RoundRectangle { rect, radii }));
+        self.push_clip(
+            DisplayClip::RoundRectangle { rect, radii, antialias: true },
+            ClipOp::Intersect,
+        );
     }
 
-    /// Pushes an ellipse which clips proceeding display commands.
+    /// Pushes an antialiased ellipse which clips proceeding display commands.
     pub fn push_ellipse_clip(&mut self, center: Point, radii: Vector) {
-        self.display_list.push(DisplayCommand::Clip(DisplayClip::Ellipse { center, radii }));
+        self.push_clip(DisplayClip::Ellipse { center, radii, antialias: true }, ClipOp::Intersect);
+    }
+
+    /// Pushes an antialiased vector path which clips proceeding display commands.
+    pub fn push_path_clip(&mut self, path: VectorPath, is_closed: bool) {
+        self.push_clip(DisplayClip::Path { path, is_closed, antialias: true }, ClipOp::Intersect);
+    }
+
+    /// Pushes a clip which is combined with the current clip as per `op`.
+    ///
+    /// For example, [`ClipOp::Difference`](ClipOp::Difference) cuts a hole into everything drawn afterwards.
+    pub fn push_clip(&mut self, clip: DisplayClip, op: ClipOp) {
+        self.display_list.push(DisplayCommand::Clip(clip, op));
     }
 
     /// Saves the current draw state (clip, transformation, layers).
@@ -1250,7 +2989,13 @@ impl DisplayListBuilder {
 
     /// Saves the current draw state (clip, transformation, layers) and begins drawing to a new layer, with a specified opacity.
     pub fn save_layer(&mut self, opacity: f32) {
-        self.display_list.push(DisplayCommand::SaveLayer(opacity));
+        self.save_blend_layer(opacity, BlendMode::Normal);
+    }
+
+    /// Saves the current draw state (clip, transformation, layers) and begins drawing to a new layer,
+    /// which is blended with what's behind it using `mode`.
+    pub fn save_blend_layer(&mut self, opacity: f32, mode: BlendMode) {
+        self.display_list.push(DisplayCommand::SaveLayer(opacity, mode));
     }
 
     /// Restores previously saved states.
@@ -1305,6 +3050,30 @@ fn rotated_rectangle_bounds(rect: &Rect, angle: Angle) -> Rect {
     )
 }
 
+/// Data of the font which tests lay out and measure text with.
+#[cfg(test)]
+pub(crate) fn test_font_data() -> Arc<Vec<u8>> {
+    Arc::new(include_bytes!("../../../reclutch/examples/shaping/NotoSans.ttf").to_vec())
+}
+
+#[cfg(test)]
+pub(crate) fn test_font() -> FontInfo {
+    FontInfo::from_data(test_font_data(), 0).unwrap()
+}
+
+/// Black text in the test font, 16 pixels in size, starting at (5, 20).
+#[cfg(test)]
+pub(crate) fn test_text(text: &str) -> TextDisplayItem {
+    TextDisplayItem::new(
+        text,
+        ResourceReference::Font(0),
+        test_font(),
+        16.0,
+        Point::new(5.0, 20.0),
+        Color::new(0.0, 0.0, 0.0, 1.0),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1420,4 +3189,556 @@ mod tests {
             &Rect::new(Point::new(-34.0, -72.0), Size::new(94.0, 32.0)),
         );
     }
+
+    #[test]
+    fn test_pixel_conversion() {
+        let scale = ScaleFactor::new(2.0);
+        let rect = Rect::new(Point::new(10.0, 5.0), Size::new(20.0, 40.0));
+        let physical = to_physical(rect, scale);
+        assert_eq!(
+            physical,
+            PhysicalRect::new(PhysicalPoint::new(20.0, 10.0), PhysicalSize::new(40.0, 80.0))
+        );
+        assert_eq!(to_logical(physical, scale), rect);
+        assert_eq!(Point::new(3.0, 4.0) * scale, PhysicalPoint::new(6.0, 8.0));
+        assert_eq!(PhysicalSize::new(6.0, 8.0) / scale, Size::new(3.0, 4.0));
+    }
+
+    #[test]
+    fn test_color_space_conversion() {
+        let eq = |a: Color, b: Color| {
+            assert!(approx_eq!(f32, a.red, b.red, epsilon = 1e-3));
+            assert!(approx_eq!(f32, a.green, b.green, epsilon = 1e-3));
+            assert!(approx_eq!(f32, a.blue, b.blue, epsilon = 1e-3));
+            assert!(approx_eq!(f32, a.alpha, b.alpha, epsilon = 1e-6));
+        };
+
+        let gray = Color::new(0.5, 0.5, 0.5, 0.8);
+        eq(ColorSpace::Srgb.convert(gray, ColorSpace::DisplayP3), gray);
+        eq(
+            ColorSpace::Srgb.convert(gray, ColorSpace::LinearSrgb),
+            Color::new(0.214, 0.214, 0.214, 0.8),
+        );
+
+        // pure P3 red lies outside of the sRGB gamut.
+        let p3_red = ColorSpace::DisplayP3.to_srgb(Color::new(1.0, 0.0, 0.0, 1.0));
+        assert!(p3_red.red > 1.0 && p3_red.green < 0.0);
+
+        let color = Color::new(0.2, 0.7, 0.4, 1.0);
+        for space in &[ColorSpace::DisplayP3, ColorSpace::LinearSrgb] {
+            eq(space.to_srgb(ColorSpace::Srgb.convert(color, *space)), color);
+        }
+    }
+
+    #[test]
+    fn test_command_group_push() {
+        let mut display = recording::RecordingDisplay::<u32>::default();
+        let mut group = CommandGroup::new();
+
+        assert!(group.push(&mut display, &[1], ZOrder::default(), None, None).unwrap());
+        assert!(!group.push(&mut display, &[2], ZOrder::default(), None, None).unwrap());
+
+        let mut builds = 0;
+        let mut push = |group: &mut CommandGroup, display: &mut dyn GraphicsDisplay<u32>, hash| {
+            group
+                .push_if_changed(
+                    display,
+                    hash,
+                    || {
+                        builds += 1;
+                        vec![hash as u32]
+                    },
+                    ZOrder::default(),
+                    None,
+                    None,
+                )
+                .unwrap()
+        };
+
+        assert!(push(&mut group, &mut display, 3));
+        assert!(!push(&mut group, &mut display, 3));
+        assert!(push(&mut group, &mut display, 4));
+        group.repaint();
+        assert!(push(&mut group, &mut display, 4));
+        assert_eq!(builds, 3);
+
+        display.present(None).unwrap();
+        assert_eq!(display.presented(), &[4]);
+
+        group.remove(&mut display);
+        assert!(!group.will_repaint());
+        assert_eq!(display.command_group_count(), 0);
+    }
+
+    #[test]
+    fn test_command_group_remove() {
+        let mut display = recording::RecordingDisplay::<u32>::default();
+        let mut group = CommandGroup::new();
+        assert!(group.push(&mut display, &[1], ZOrder::default(), None, None).unwrap());
+
+        // without a repaint, pushing after removal leaves the command group out.
+        group.remove(&mut display);
+        assert!(!group.push(&mut display, &[1], ZOrder::default(), None, None).unwrap());
+        assert_eq!(display.command_group_count(), 0);
+        group.repaint();
+        assert!(group.push(&mut display, &[1], ZOrder::default(), None, None).unwrap());
+        assert_eq!(display.command_group_count(), 1);
+
+        // the same hash recreates it though, since the commands it describes were removed.
+        let push = |group: &mut CommandGroup, display: &mut dyn GraphicsDisplay<u32>| {
+            group.push_if_changed(display, 7, || vec![7], ZOrder::default(), None, None).unwrap()
+        };
+        assert!(push(&mut group, &mut display));
+        group.remove(&mut display);
+        assert!(push(&mut group, &mut display));
+        assert_eq!(display.command_group_count(), 1);
+    }
+
+    #[test]
+    fn test_repaint_all() {
+        let mut display = recording::RecordingDisplay::<u32>::default();
+        let (mut a, mut b) = (CommandGroup::new(), CommandGroup::new());
+        assert!(a.push(&mut display, &[1], ZOrder::default(), None, None).unwrap());
+        assert!(b.push(&mut display, &[2], ZOrder::default(), None, None).unwrap());
+        assert!(!a.will_repaint() && !b.will_repaint());
+
+        CommandGroup::repaint_all(vec![&mut a, &mut b]);
+        assert!(a.will_repaint() && b.will_repaint());
+        assert!(a.push(&mut display, &[3], ZOrder::default(), None, None).unwrap());
+        assert!(b.push(&mut display, &[4], ZOrder::default(), None, None).unwrap());
+
+        display.present(None).unwrap();
+        assert_eq!(display.presented(), &[3, 4]);
+    }
+
+    #[test]
+    fn test_custom_command() {
+        #[derive(Debug, Clone)]
+        struct Candle {
+            x: f32,
+            open: f32,
+            close: f32,
+        }
+
+        impl CustomCommand for Candle {
+            fn name(&self) -> &'static str {
+                "candle"
+            }
+
+            fn bounds(&self) -> Option<Rect> {
+                let top = self.open.min(self.close);
+                Some(Rect::new(
+                    Point::new(self.x, top),
+                    Size::new(4.0, (self.open - self.close).abs()),
+                ))
+            }
+
+            fn fallback(&self) -> Vec<DisplayCommand> {
+                let mut builder = DisplayListBuilder::new();
+                builder.push_rectangle(
+                    self.bounds().unwrap(),
+                    GraphicsDisplayPaint::Fill(Color::new(0.0, 1.0, 0.0, 1.0).into()),
+                    vec![],
+                );
+                builder.build()
+            }
+        }
+
+        let mut builder = DisplayListBuilder::new();
+        builder.push_extension(Candle { x: 10.0, open: 30.0, close: 20.0 });
+        let commands = builder.build();
+        let cloned = commands.clone();
+
+        match &cloned[0] {
+            DisplayCommand::Extension(command) => {
+                assert_eq!(command.name(), "candle");
+                assert_eq!(command.downcast_ref::<Candle>().unwrap().close, 20.0);
+            }
+            _ => panic!("expected an extension"),
+        }
+        let bounds = Rect::new(Point::new(10.0, 20.0), Size::new(4.0, 10.0));
+        assert_eq!(display_list_bounds(&commands).unwrap(), bounds);
+        validate::validate_display_list(&commands).unwrap();
+        validate::validate_display_list(&[DisplayCommand::Extension(Box::new(Candle {
+            x: f32::NAN,
+            open: 0.0,
+            close: 1.0,
+        }))])
+        .unwrap_err();
+
+        // displays which don't interpret extensions get the fallback.
+        assert!(matches!(
+            degrade::degrade(&commands, &DisplayCapabilities::all())[..],
+            [DisplayCommand::Extension(_)]
+        ));
+        assert!(matches!(
+            degrade::degrade(&commands, &Default::default())[..],
+            [DisplayCommand::Item(DisplayItem::Graphics(GraphicsDisplayItem::Rectangle { .. }), _)]
+        ));
+    }
+
+    #[test]
+    fn test_memoized_advances() {
+        let font_data = test_font_data();
+        let font_info = FontInfo::from_data(font_data.clone(), 0).unwrap();
+        // the font data is shared by the font reference and the loaded font, rather than copied.
+        assert_eq!(Arc::strong_count(&font_data), 3);
+        let font = font_info.font().unwrap();
+        assert_eq!(font_info.metrics().units_per_em, font.metrics().units_per_em);
+        assert_eq!(font_info.name(), font.full_name());
+        let expected = font.advance(font.glyph_for_char('W').unwrap()).unwrap().x();
+
+        assert_eq!(font_info.advance('W').unwrap(), expected);
+        // the second lookup is served from the cache.
+        assert_eq!(font_info.advance('W').unwrap(), expected);
+        assert!(matches!(font_info.advance('\u{10FFFF}'), Err(error::FontError::CodepointError)));
+    }
+
+    #[test]
+    fn test_memoized_bounds() {
+        let mut text = test_text("Hello");
+        let bounds = text.bounds().unwrap();
+        let width = text.bounds_cache.0.lock().unwrap().unwrap().1;
+        assert_eq!(width, bounds.size.width);
+
+        // clones keep the measurement, and moving the text doesn't measure it again.
+        let mut moved = text.clone();
+        moved.bottom_left.x += 10.0;
+        *moved.bounds_cache.0.lock().unwrap() = Some((TextBoundsKey::of(&moved), width + 1.0));
+        assert_eq!(moved.bounds().unwrap().size.width, width + 1.0);
+
+        // changing the text or size measures it again.
+        text.text = "Hello, world".into();
+        assert!(text.bounds().unwrap().size.width > width);
+        text.text = "Hello".into();
+        text.size = 32.0;
+        assert!((text.bounds().unwrap().size.width - width * 2.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_parallel_bounds() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<DisplayCommand>();
+        assert_send_sync::<DisplayItem>();
+        assert_send_sync::<TextDisplayItem>();
+        assert_send_sync::<FontInfo>();
+        assert_send_sync::<error::FontError>();
+
+        let list = vec![DisplayCommand::Item(DisplayItem::Text(test_text("Hello, world")), vec![])];
+        let bounds = display_list_bounds(&list).unwrap();
+
+        // the font is loaded again on each thread it's measured on.
+        let lists: Vec<_> = (0..4).map(|_| list.clone()).collect();
+        let handles: Vec<_> = lists
+            .into_iter()
+            .map(|list| std::thread::spawn(move || display_list_bounds(&list).unwrap()))
+            .collect();
+        for handle in handles {
+            epsilon_rect(&handle.join().unwrap(), &bounds);
+        }
+    }
+
+    #[test]
+    fn test_text_blob() {
+        let font_data = test_font_data();
+        let mut display = recording::RecordingDisplay::<DisplayCommand>::default();
+        let font = display
+            .new_resource(ResourceDescriptor::Font(ResourceData::Data(SharedData::RefCount(
+                font_data.clone(),
+            ))))
+            .unwrap();
+        let text = TextDisplayItem {
+            font,
+            font_info: FontInfo::from_data(font_data, 0).unwrap(),
+            ..test_text("Label")
+        };
+        let blob = display.new_resource(ResourceDescriptor::TextBlob(text.clone())).unwrap();
+        assert!(matches!(blob, ResourceReference::TextBlob(_)));
+
+        let mut item = TextBlobDisplayItem::new(blob, &text).unwrap();
+        epsilon_rect(
+            &DisplayItem::TextBlob(item.clone()).bounds().unwrap(),
+            &text.bounds().unwrap(),
+        );
+
+        // moving the item doesn't require a new blob.
+        item.bottom_left = Point::new(45.0, 60.0);
+        epsilon_rect(&item.bounds(), &text.bounds().unwrap().translate(Vector::new(40.0, 40.0)));
+    }
+
+    #[test]
+    fn test_text_decoration_bounds() {
+        let mut text = TextDisplayItem {
+            size: 32.0,
+            bottom_left: Point::new(10.0, 40.0),
+            color: Color::new(1.0, 1.0, 1.0, 1.0).into(),
+            ..test_text("Outlined headline")
+        };
+        let plain = text.bounds().unwrap();
+
+        text.stroke = Some(GraphicsDisplayStroke { thickness: 4.0, ..Default::default() });
+        epsilon_rect(&text.bounds().unwrap(), &plain.inflate(2.0, 2.0));
+
+        text.shadow = Some(TextShadow {
+            offset: Vector::new(10.0, 5.0),
+            blur: 1.0,
+            color: Color::new(0.0, 0.0, 0.0, 0.5),
+        });
+        let bounds = text.bounds().unwrap();
+        epsilon_rect(
+            &bounds,
+            &Rect::new(
+                plain.origin - Vector::new(2.0, 2.0),
+                plain.size + Size::new(4.0 + 10.0 + 3.0, 4.0 + 5.0 + 3.0),
+            ),
+        );
+
+        let metrics = text.font_info.decoration_metrics(32.0);
+        assert!(metrics.thickness > 0.0);
+        assert!(metrics.underline_offset > 0.0);
+        assert!(metrics.overline_offset < metrics.strikethrough_offset);
+        assert!(metrics.strikethrough_offset < 0.0);
+
+        let scaled = text.font_info.scaled_metrics(32.0, 1.5);
+        assert_eq!(scaled.scale, 1.5);
+        for value in &[scaled.ascent, scaled.descent, scaled.x_height, scaled.cap_height] {
+            assert!(*value >= 0.0);
+            assert_eq!((value * 1.5).fract(), 0.0);
+        }
+        assert!(scaled.ascent > scaled.x_height);
+        assert!(scaled.line_height() >= scaled.ascent + scaled.descent);
+        let unscaled = text.font_info.scaled_metrics(32.0, 1.0);
+        assert!((unscaled.ascent - scaled.ascent).abs() <= 1.0);
+
+        assert!(text.decoration_rects().unwrap().is_empty());
+        text.decorations =
+            TextDecorations { underline: true, strikethrough: true, overline: false };
+        let lines = text.decoration_rects().unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].origin, Point::new(10.0, 40.0 + metrics.underline_offset));
+        assert_eq!(lines[0].size.width, plain.size.width);
+        assert_eq!(lines[1].origin.y, 40.0 + metrics.strikethrough_offset);
+        assert!(lines.iter().all(|line| bounds.contains_rect(line)));
+
+        // line breaking keeps the decorations.
+        let lines = text.linebreak(1.0, 40.0, false).unwrap();
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|line| line.stroke.is_some()
+            && line.shadow.is_some()
+            && line.decorations.underline));
+    }
+
+    #[test]
+    fn test_text_truncation() {
+        let item = TextDisplayItem {
+            bottom_left: Point::new(10.0, 30.0),
+            ..test_text("A label which is too long")
+        };
+        let width = |item: &TextDisplayItem| item.bounds().unwrap().size.width;
+
+        assert_eq!(item.truncated(1000.0, TextOverflow::Ellipsis).unwrap().text, item.text);
+
+        let ellipsis = item.truncated(80.0, TextOverflow::Ellipsis).unwrap();
+        let text = match &ellipsis.text {
+            DisplayText::Simple(text) => text.clone(),
+            DisplayText::Shaped(_) => unreachable!(),
+        };
+        assert!(text.ends_with('…') && !text.ends_with(" …"));
+        assert!(width(&ellipsis) <= 80.0);
+        assert!(width(&ellipsis) > 50.0);
+        assert!(item.truncated(1.0, TextOverflow::Ellipsis).unwrap().text.is_empty());
+
+        // clipped text keeps the grapheme crossing the edge, and drops the rest.
+        let clipped = item.truncated(80.0, TextOverflow::Clip).unwrap();
+        assert!(width(&clipped) > 80.0);
+        let last = clipped.text.prev_boundary(clipped.text.len());
+        assert!(clipped.x_at_char_index(last).unwrap() < 90.0);
+
+        let faded = item.truncated(80.0, TextOverflow::Fade).unwrap();
+        assert_eq!(faded.text, clipped.text);
+        match &faded.color {
+            StyleColor::LinearGradient(gradient) => {
+                assert_eq!(gradient.start, Point::new(58.0, 30.0));
+                assert_eq!(gradient.end, Point::new(90.0, 30.0));
+                assert_eq!(gradient.stops[1].1.alpha, 0.0);
+            }
+            _ => panic!("faded text should be painted with a gradient"),
+        }
+
+        let mut builder = DisplayListBuilder::new();
+        builder.push_truncated_text(item.clone(), 80.0, TextOverflow::Clip, vec![]).unwrap();
+        builder.push_truncated_text(item, 80.0, TextOverflow::Ellipsis, vec![]).unwrap();
+        let commands = builder.build();
+        assert_eq!(commands.len(), 5);
+        match &commands[1] {
+            DisplayCommand::Clip(DisplayClip::Rectangle { rect, .. }, _) => {
+                assert!((rect.max_x() - 90.0).abs() < 0.001)
+            }
+            _ => panic!("clipped text should be pushed with a clip"),
+        }
+    }
+
+    #[test]
+    fn test_text_hit_testing() {
+        // "e" followed by a combining acute accent is a single grapheme spanning bytes 1..4.
+        let simple = TextDisplayItem {
+            size: 20.0,
+            bottom_left: Point::new(10.0, 30.0),
+            ..test_text("ae\u{301}b")
+        };
+        let font_info = &simple.font_info;
+
+        assert_eq!(simple.text.next_boundary(1), 4);
+        assert_eq!(simple.text.prev_boundary(4), 1);
+        assert_eq!(simple.text.next_boundary(4), 5);
+        assert_eq!(simple.text.next_boundary(5), 5);
+        assert_eq!(simple.text.prev_boundary(0), 0);
+
+        let end = simple.x_at_char_index(5).unwrap();
+        assert!((end - simple.bounds().unwrap().max_x()).abs() < 0.001);
+        assert_eq!(simple.char_index_at_x(0.0).unwrap(), 0);
+        assert_eq!(simple.char_index_at_x(end + 10.0).unwrap(), 5);
+        let mut x = 0.0;
+        while x < end + 10.0 {
+            assert!([0, 1, 4, 5].contains(&simple.char_index_at_x(x).unwrap()));
+            x += 0.5;
+        }
+
+        // shaped glyphs with the same advances place the caret at the same positions.
+        let units_per_em = font_info.metrics().units_per_em as f32;
+        let shaped = TextDisplayItem {
+            text: DisplayText::Shaped(
+                "ae\u{301}b"
+                    .chars()
+                    .map(|c| ShapedGlyph {
+                        codepoint: c as u32,
+                        advance: Vector::new(
+                            font_info.advance(c).unwrap() / units_per_em * 20.0,
+                            0.0,
+                        ),
+                        offset: Vector::zero(),
+                    })
+                    .collect(),
+            ),
+            ..simple.clone()
+        };
+        for &(char_index, glyph_index) in &[(0, 0), (1, 1), (4, 3), (5, 4)] {
+            let x = simple.x_at_char_index(char_index).unwrap();
+            assert!((x - shaped.x_at_char_index(glyph_index).unwrap()).abs() < 0.001);
+        }
+        let (a, b) = (simple.x_at_char_index(1).unwrap(), simple.x_at_char_index(4).unwrap());
+        assert_eq!(simple.char_index_at_x(a + 1.0).unwrap(), 1);
+        assert_eq!(shaped.char_index_at_x(a + 1.0).unwrap(), 1);
+        assert_eq!(simple.char_index_at_x(b - 1.0).unwrap(), 4);
+    }
+
+    #[test]
+    fn test_filter_color_matrix() {
+        fn apply(filter: FilterOp, rgb: [f32; 3]) -> [f32; 3] {
+            let m = filter.color_matrix().unwrap();
+            let mut out = [0.0; 3];
+            for (row, out) in out.iter_mut().enumerate() {
+                let m = &m[row * 5..row * 5 + 5];
+                *out = m[0] * rgb[0] + m[1] * rgb[1] + m[2] * rgb[2] + m[3] + m[4];
+            }
+            out
+        }
+        fn approx(a: [f32; 3], b: [f32; 3]) {
+            for (a, b) in a.iter().zip(b.iter()) {
+                assert!(approx_eq!(f32, *a, *b, epsilon = 0.001), "{:?} != {:?}", a, b);
+            }
+        }
+
+        let color = [0.8, 0.4, 0.2];
+        approx(apply(FilterOp::Invert, color), [0.2, 0.6, 0.8]);
+        approx(apply(FilterOp::Brightness(0.5), color), [0.4, 0.2, 0.1]);
+        approx(apply(FilterOp::Contrast(0.0), color), [0.5, 0.5, 0.5]);
+        approx(apply(FilterOp::Grayscale(0.0), color), color);
+        approx(apply(FilterOp::HueRotate(Angle::zero()), color), color);
+
+        let gray = apply(FilterOp::Grayscale(1.0), color);
+        approx(gray, [gray[0]; 3]);
+
+        assert!(FilterOp::Blur(2.0, 2.0).color_matrix().is_none());
+    }
+
+    #[test]
+    fn test_virtual_resolution() {
+        let mut resolution = VirtualResolution::new((320, 180));
+        // pillarboxed on a 4:3 surface.
+        assert_eq!(
+            resolution.placement((800, 450)),
+            Rect::new(Point::new(0.0, 0.0), Size::new(800.0, 450.0))
+        );
+        let placement = resolution.placement((800, 600));
+        assert_eq!(placement, Rect::new(Point::new(0.0, 75.0), Size::new(800.0, 450.0)));
+        assert_eq!(
+            resolution.to_virtual(Point::new(400.0, 300.0), (800, 600)),
+            Some(Point::new(160.0, 90.0))
+        );
+        assert_eq!(resolution.to_virtual(Point::new(400.0, 10.0), (800, 600)), None);
+        // holes and read-back regions map the other way.
+        assert_eq!(
+            resolution
+                .to_surface(Rect::new(Point::new(160.0, 90.0), Size::new(10.0, 5.0)), (800, 600)),
+            Rect::new(Point::new(400.0, 300.0), Size::new(25.0, 12.5))
+        );
+
+        resolution.integer_scaling = true;
+        assert_eq!(resolution.scale((800, 600)), 2.0);
+        assert_eq!(
+            resolution.placement((800, 600)),
+            Rect::new(Point::new(80.0, 120.0), Size::new(640.0, 360.0))
+        );
+        assert_eq!(resolution.scale((160, 90)), 0.5);
+    }
+
+    #[test]
+    fn test_color_correction() {
+        let color = Color::new(0.25, 0.5, 1.0, 0.5);
+        assert!(ColorCorrection::default().is_identity());
+        assert_eq!(ColorCorrection::default().apply(color), color);
+
+        let gamma = ColorCorrection { gamma: 2.0, ..Default::default() };
+        assert!(!gamma.is_identity());
+        assert_eq!(gamma.apply(color), Color::new(0.5, 0.5f32.sqrt(), 1.0, 0.5));
+
+        let dimmed = ColorCorrection { brightness: 0.5, ..ColorCorrection::night_light(1.0) };
+        let corrected = dimmed.apply(color);
+        assert!(approx_eq!(f32, corrected.red, 0.125, epsilon = 0.001));
+        assert!(approx_eq!(f32, corrected.green, 0.1875, epsilon = 0.001));
+        assert!(approx_eq!(f32, corrected.blue, 0.2, epsilon = 0.001));
+        assert_eq!(corrected.alpha, 0.5);
+        assert!(ColorCorrection::night_light(0.0).is_identity());
+    }
+
+    #[test]
+    fn test_blend_mode() {
+        let stroke = GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+            thickness: 4.0,
+            ..Default::default()
+        });
+        assert_eq!(stroke.blend_mode(), BlendMode::Normal);
+
+        let paint = stroke.with_blend_mode(BlendMode::Screen).with_blend_mode(BlendMode::Multiply);
+        assert_eq!(paint.blend_mode(), BlendMode::Multiply);
+        assert!(
+            matches!(&paint, GraphicsDisplayPaint::Blend(inner, _) if inner.stroke().is_some())
+        );
+
+        // blending doesn't affect the bounds of the stroke.
+        let rect = Rect::new(Point::new(10.0, 10.0), Size::new(20.0, 20.0));
+        epsilon_rect(
+            &GraphicsDisplayItem::Rectangle { rect, paint }.bounds(),
+            &rect.inflate(2.0, 2.0),
+        );
+
+        let mut builder = DisplayListBuilder::new();
+        builder.save_blend_layer(0.5, BlendMode::Overlay);
+        builder.restore();
+        assert!(matches!(
+            builder.build()[0],
+            DisplayCommand::SaveLayer(opacity, BlendMode::Overlay) if opacity == 0.5
+        ));
+    }
 }
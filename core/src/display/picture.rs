@@ -0,0 +1,280 @@
+//! Pre-recorded drawings which can be replayed into any display.
+//!
+//! A [`Picture`](Picture) is an immutable, cheaply cloneable list of display commands, which may contain other
+//! pictures. It is built once (e.g. an expensive icon or chart) and then shared between widgets, each replaying
+//! it with its own transform. Since resources belong to a display, resource references within a picture are
+//! mapped through a [`ResourceResolver`](ResourceResolver) when it is replayed.
+
+use {super::*, crate::widget::WidgetTransform, std::collections::HashMap};
+
+/// Maps the resource references recorded in a [`Picture`](Picture) to the resources of the display it is replayed into.
+pub trait ResourceResolver {
+    fn resolve(&mut self, reference: ResourceReference) -> ResourceReference;
+}
+
+impl<F: FnMut(ResourceReference) -> ResourceReference> ResourceResolver for F {
+    #[inline]
+    fn resolve(&mut self, reference: ResourceReference) -> ResourceReference {
+        self(reference)
+    }
+}
+
+/// Resolver which keeps resource references as they are, for pictures recorded against the same display.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IdentityResolver;
+
+impl ResourceResolver for IdentityResolver {
+    #[inline]
+    fn resolve(&mut self, reference: ResourceReference) -> ResourceReference {
+        reference
+    }
+}
+
+impl ResourceResolver for HashMap<ResourceReference, ResourceReference> {
+    /// References without an entry are kept as they are.
+    fn resolve(&mut self, reference: ResourceReference) -> ResourceReference {
+        self.get(&reference).copied().unwrap_or(reference)
+    }
+}
+
+#[derive(Clone)]
+enum PictureCommand {
+    Command(Box<DisplayCommand>),
+    Picture(Picture, Option<WidgetTransform>),
+}
+
+/// An immutable list of recorded display commands and nested pictures.
+///
+/// Cloning a picture is cheap; all clones share the same commands.
+#[derive(Clone, Default)]
+pub struct Picture {
+    commands: Arc<Vec<PictureCommand>>,
+}
+
+impl Picture {
+    /// Creates a picture from a list of display commands.
+    pub fn new(commands: Vec<DisplayCommand>) -> Self {
+        Picture {
+            commands: Arc::new(
+                commands
+                    .into_iter()
+                    .map(|command| PictureCommand::Command(Box::new(command)))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Returns `true` if the picture (including nested pictures) doesn't draw anything.
+    pub fn is_empty(&self) -> bool {
+        self.commands.iter().all(|command| match command {
+            PictureCommand::Command(_) => false,
+            PictureCommand::Picture(picture, _) => picture.is_empty(),
+        })
+    }
+
+    /// Converts the picture into a flat list of display commands.
+    ///
+    /// Nested pictures and the picture itself (if `transform` is given) are wrapped in a save/restore,
+    /// so that their transforms and clips don't leak.
+    pub fn flatten(
+        &self,
+        transform: Option<WidgetTransform>,
+        resolver: &mut dyn ResourceResolver,
+    ) -> Vec<DisplayCommand> {
+        let mut commands = Vec::new();
+        self.flatten_into(&mut commands, transform, resolver);
+        commands
+    }
+
+    fn flatten_into(
+        &self,
+        out: &mut Vec<DisplayCommand>,
+        transform: Option<WidgetTransform>,
+        resolver: &mut dyn ResourceResolver,
+    ) {
+        if let Some(transform) = transform {
+            out.push(DisplayCommand::Save);
+            out.push(DisplayCommand::Translate(transform.translation));
+            out.push(DisplayCommand::Scale(transform.scale));
+        }
+
+        for command in self.commands.iter() {
+            match command {
+                PictureCommand::Command(command) => out.push(resolve_command(command, resolver)),
+                PictureCommand::Picture(picture, transform) => {
+                    if transform.is_none() {
+                        out.push(DisplayCommand::Save);
+                    }
+                    picture.flatten_into(out, *transform, resolver);
+                    if transform.is_none() {
+                        out.push(DisplayCommand::Restore);
+                    }
+                }
+            }
+        }
+
+        if transform.is_some() {
+            out.push(DisplayCommand::Restore);
+        }
+    }
+
+    /// Replays the picture into `display` as a new command group.
+    ///
+    /// See [`push_command_group`](GraphicsDisplay::push_command_group) for the remaining parameters.
+    pub fn replay(
+        &self,
+        display: &mut dyn GraphicsDisplay,
+        transform: Option<WidgetTransform>,
+        resolver: &mut dyn ResourceResolver,
+        z_order: ZOrder,
+        protected: Option<bool>,
+        needs_maintain: Option<bool>,
+    ) -> Result<CommandGroupHandle, error::DisplayError> {
+        display.push_command_group(
+            &self.flatten(transform, resolver),
+            z_order,
+            protected,
+            needs_maintain,
+        )
+    }
+
+    /// Appends the picture to a display list builder.
+    pub fn replay_into(
+        &self,
+        builder: &mut DisplayListBuilder,
+        transform: Option<WidgetTransform>,
+        resolver: &mut dyn ResourceResolver,
+    ) {
+        builder.display_list.extend(self.flatten(transform, resolver));
+    }
+}
+
+/// Records display commands and nested pictures into a [`Picture`](Picture).
+#[derive(Clone, Default)]
+pub struct PictureRecorder {
+    commands: Vec<PictureCommand>,
+}
+
+impl PictureRecorder {
+    /// Creates a new, empty recorder.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Records display commands (e.g. as built by a [`DisplayListBuilder`](DisplayListBuilder)).
+    pub fn push_commands(&mut self, commands: impl IntoIterator<Item = DisplayCommand>) {
+        self.commands
+            .extend(commands.into_iter().map(|command| PictureCommand::Command(Box::new(command))));
+    }
+
+    /// Records a nested picture, drawn with an optional transform.
+    ///
+    /// The nested picture is shared, not copied.
+    pub fn push_picture(&mut self, picture: &Picture, transform: Option<WidgetTransform>) {
+        self.commands.push(PictureCommand::Picture(picture.clone(), transform));
+    }
+
+    /// Finishes recording.
+    pub fn finish(self) -> Picture {
+        Picture { commands: Arc::new(self.commands) }
+    }
+}
+
+fn resolve_command(
+    command: &DisplayCommand,
+    resolver: &mut dyn ResourceResolver,
+) -> DisplayCommand {
+    match command {
+        DisplayCommand::Item(
+            DisplayItem::Graphics(GraphicsDisplayItem::Image { src, dst, resource }),
+            filters,
+        ) => DisplayCommand::Item(
+            DisplayItem::Graphics(GraphicsDisplayItem::Image {
+                src: *src,
+                dst: *dst,
+                resource: resolver.resolve(*resource),
+            }),
+            filters.clone(),
+        ),
+        DisplayCommand::Item(DisplayItem::Text(item), filters) => DisplayCommand::Item(
+            DisplayItem::Text(TextDisplayItem {
+                font: resolver.resolve(item.font),
+                ..item.clone()
+            }),
+            filters.clone(),
+        ),
+        DisplayCommand::Item(DisplayItem::TextBlob(item), filters) => DisplayCommand::Item(
+            DisplayItem::TextBlob(TextBlobDisplayItem {
+                blob: resolver.resolve(item.blob),
+                ..item.clone()
+            }),
+            filters.clone(),
+        ),
+        command => command.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, recording::RecordingDisplay};
+
+    fn image(resource: ResourceReference) -> DisplayCommand {
+        DisplayCommand::Item(
+            DisplayItem::Graphics(GraphicsDisplayItem::Image {
+                src: None,
+                dst: Rect::new(Point::zero(), Size::new(16.0, 16.0)),
+                resource,
+            }),
+            vec![],
+        )
+    }
+
+    #[test]
+    fn test_picture() {
+        let icon = Picture::new(vec![image(ResourceReference::Image(0))]);
+
+        let mut recorder = PictureRecorder::new();
+        recorder.push_commands(vec![DisplayCommand::Clear(Color::new(1.0, 1.0, 1.0, 1.0))]);
+        recorder.push_picture(&icon, None);
+        recorder
+            .push_picture(&icon, Some(WidgetTransform::from_translation(Vector::new(20.0, 0.0))));
+        let toolbar = recorder.finish();
+        assert!(!toolbar.is_empty());
+        assert!(Picture::default().is_empty());
+
+        let mut display = RecordingDisplay::default();
+        let mut resolver: HashMap<_, _> =
+            vec![(ResourceReference::Image(0), ResourceReference::Image(7))].into_iter().collect();
+        let transform = WidgetTransform::from_scale(Vector::new(2.0, 2.0));
+        let handle = toolbar
+            .replay(&mut display, Some(transform), &mut resolver, ZOrder(0), None, None)
+            .unwrap();
+
+        let commands = display.get_command_group(handle).unwrap();
+        validate::validate_display_list(commands).unwrap();
+        assert_eq!(commands.len(), 13);
+        assert!(
+            matches!(commands[2], DisplayCommand::Scale(scale) if scale == Vector::new(2.0, 2.0))
+        );
+        let images = commands
+            .iter()
+            .filter(|command| {
+                matches!(
+                    command,
+                    DisplayCommand::Item(
+                        DisplayItem::Graphics(GraphicsDisplayItem::Image {
+                            resource: ResourceReference::Image(7),
+                            ..
+                        }),
+                        _
+                    )
+                )
+            })
+            .count();
+        assert_eq!(images, 2);
+
+        let mut builder = DisplayListBuilder::new();
+        icon.replay_into(&mut builder, None, &mut IdentityResolver);
+        assert_eq!(builder.build().len(), 1);
+    }
+}
@@ -0,0 +1,223 @@
+//! Submission of command groups from worker threads.
+//!
+//! Display lists can be built on any thread (both [`DisplayCommand`](DisplayCommand) and [`DisplayListBuilder`](DisplayListBuilder)
+//! are `Send + Sync`), however a [`GraphicsDisplay`](GraphicsDisplay) is bound to the render thread.
+//! A [`ThreadSafeDisplayProxy`](ThreadSafeDisplayProxy) bridges the two by queuing operations, which are then
+//! applied to the display on the render thread.
+//!
+//! ```ignore
+//! let proxy = ThreadSafeDisplayProxy::new();
+//!
+//! let worker_proxy = proxy.clone();
+//! std::thread::spawn(move || {
+//!     let handle = worker_proxy.push(build_chart(), ZOrder(0), None, Some(false));
+//!     // ...
+//! });
+//!
+//! // on the render thread, every frame:
+//! proxy.apply(&mut display)?;
+//! display.present(None)?;
+//! ```
+
+use {
+    super::*,
+    std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Mutex, PoisonError,
+        },
+    },
+};
+
+/// Handle to a command group submitted through a [`ThreadSafeDisplayProxy`](ThreadSafeDisplayProxy).
+///
+/// This is available immediately, before the command group actually exists in the display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ProxyHandle(u64);
+
+enum ProxyOperation {
+    Push {
+        handle: ProxyHandle,
+        commands: Vec<DisplayCommand>,
+        z_order: ZOrder,
+        protected: Option<bool>,
+        needs_maintain: Option<bool>,
+    },
+    Maintain(ProxyHandle),
+    Remove(ProxyHandle),
+}
+
+#[derive(Default)]
+struct ProxyState {
+    queue: Vec<ProxyOperation>,
+    handles: HashMap<ProxyHandle, CommandGroupHandle>,
+}
+
+/// Queues command group operations from any thread, to be applied to a display on the render thread.
+///
+/// Cloning the proxy is cheap; all clones share the same queue.
+#[derive(Clone, Default)]
+pub struct ThreadSafeDisplayProxy {
+    state: Arc<Mutex<ProxyState>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl ThreadSafeDisplayProxy {
+    /// Creates a new proxy with an empty queue.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    fn enqueue(&self, operation: ProxyOperation) {
+        // the state stays consistent even if another thread panicked while holding the lock.
+        self.state.lock().unwrap_or_else(PoisonError::into_inner).queue.push(operation);
+    }
+
+    /// Queues a new command group.
+    ///
+    /// See [`push_command_group`](GraphicsDisplay::push_command_group).
+    pub fn push(
+        &self,
+        commands: Vec<DisplayCommand>,
+        z_order: ZOrder,
+        protected: impl Into<Option<bool>>,
+        needs_maintain: impl Into<Option<bool>>,
+    ) -> ProxyHandle {
+        let handle = ProxyHandle(self.next_id.fetch_add(1, Ordering::Relaxed));
+        self.modify(handle, commands, z_order, protected, needs_maintain);
+        handle
+    }
+
+    /// Queues a replacement of the contents of a command group.
+    ///
+    /// If the command group doesn't exist in the display (anymore), it is pushed again instead.
+    pub fn modify(
+        &self,
+        handle: ProxyHandle,
+        commands: Vec<DisplayCommand>,
+        z_order: ZOrder,
+        protected: impl Into<Option<bool>>,
+        needs_maintain: impl Into<Option<bool>>,
+    ) {
+        self.enqueue(ProxyOperation::Push {
+            handle,
+            commands,
+            z_order,
+            protected: protected.into(),
+            needs_maintain: needs_maintain.into(),
+        });
+    }
+
+    /// Queues maintenance of a command group.
+    ///
+    /// See [`maintain_command_group`](GraphicsDisplay::maintain_command_group).
+    pub fn maintain(&self, handle: ProxyHandle) {
+        self.enqueue(ProxyOperation::Maintain(handle));
+    }
+
+    /// Queues removal of a command group.
+    pub fn remove(&self, handle: ProxyHandle) {
+        self.enqueue(ProxyOperation::Remove(handle));
+    }
+
+    /// Returns the number of queued operations.
+    pub fn pending(&self) -> usize {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner).queue.len()
+    }
+
+    /// Returns the display handle of an applied command group.
+    pub fn handle(&self, handle: ProxyHandle) -> Option<CommandGroupHandle> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner).handles.get(&handle).copied()
+    }
+
+    /// Applies all queued operations to `display`, in the order they were queued.
+    ///
+    /// This should be called on the render thread, before presenting.
+    /// All operations are applied even if some fail, in which case the first error is returned.
+    pub fn apply(&self, display: &mut dyn GraphicsDisplay) -> Result<(), error::DisplayError> {
+        // take the queue first, so that workers aren't blocked while the display is busy.
+        let queue =
+            std::mem::take(&mut self.state.lock().unwrap_or_else(PoisonError::into_inner).queue);
+
+        let mut result = Ok(());
+        for operation in queue {
+            let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+            match operation {
+                ProxyOperation::Push { handle, commands, z_order, protected, needs_maintain } => {
+                    let mut display_handle = state.handles.get(&handle).copied();
+                    let pushed = ok_or_push(
+                        &mut display_handle,
+                        display,
+                        &commands,
+                        z_order,
+                        protected,
+                        needs_maintain,
+                    );
+                    if let Some(display_handle) = display_handle {
+                        state.handles.insert(handle, display_handle);
+                    }
+                    if result.is_ok() {
+                        result = pushed;
+                    }
+                }
+                ProxyOperation::Maintain(handle) => {
+                    if let Some(&display_handle) = state.handles.get(&handle) {
+                        display.maintain_command_group(display_handle);
+                    }
+                }
+                ProxyOperation::Remove(handle) => {
+                    if let Some(display_handle) = state.handles.remove(&handle) {
+                        display.remove_command_group(display_handle);
+                    }
+                }
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, recording::RecordingDisplay};
+
+    #[test]
+    fn test_display_proxy() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<DisplayCommand>();
+        assert_send_sync::<DisplayListBuilder>();
+        assert_send_sync::<ThreadSafeDisplayProxy>();
+
+        let proxy = ThreadSafeDisplayProxy::new();
+
+        let workers: Vec<_> = (0..4)
+            .map(|i| {
+                let proxy = proxy.clone();
+                std::thread::spawn(move || {
+                    let mut builder = DisplayListBuilder::new();
+                    builder.push_rectangle(
+                        Rect::new(Point::new(i as f32, 0.0), Size::new(1.0, 1.0)),
+                        GraphicsDisplayPaint::Fill(Color::new(1.0, 1.0, 1.0, 1.0).into()),
+                        vec![],
+                    );
+                    proxy.push(builder.build(), ZOrder(i), None, Some(false))
+                })
+            })
+            .collect();
+        let handles: Vec<_> = workers.into_iter().map(|worker| worker.join().unwrap()).collect();
+
+        let mut display = RecordingDisplay::default();
+        assert_eq!(proxy.pending(), 4);
+        proxy.apply(&mut display).unwrap();
+        assert_eq!(proxy.pending(), 0);
+        assert_eq!(display.command_group_count(), 4);
+
+        proxy.modify(handles[0], Vec::new(), ZOrder(0), None, Some(false));
+        proxy.remove(handles[1]);
+        proxy.apply(&mut display).unwrap();
+        assert_eq!(display.command_group_count(), 3);
+        assert_eq!(display.get_command_group(proxy.handle(handles[0]).unwrap()).unwrap().len(), 0);
+        assert_eq!(proxy.handle(handles[1]), None);
+    }
+}
@@ -0,0 +1,504 @@
+//! In-memory implementation of `GraphicsDisplay` which records command groups instead of rendering them.
+//!
+//! This is mainly useful for testing, where there is no window to render into.
+
+use super::{transaction::PendingUpdate, *};
+use std::collections::{BTreeMap, HashMap};
+
+struct RecordedGroup<D> {
+    handle: CommandGroupHandle,
+    commands: Vec<D>,
+    protected: bool,
+    // number of presents since the group was last maintained, if it needs maintenance.
+    missed: Option<u32>,
+    label: Option<&'static str>,
+}
+
+/// Records command groups with the same retained semantics as a real display
+/// (z-ordering, maintenance and eviction), and keeps the flattened display list of the last [`present`](GraphicsDisplay::present).
+///
+/// Culling isn't supported, since the command type is opaque.
+pub struct RecordingDisplay<D = DisplayCommand> {
+    groups: BTreeMap<ZOrder, Vec<RecordedGroup<D>>>,
+    next_command_group_id: u64,
+    resources: HashMap<u64, ResourceDescriptor>,
+    next_resource_id: u64,
+    size: (u32, u32),
+    presented: Vec<D>,
+    frames: u64,
+    eviction_policy: EvictionPolicy,
+    eviction_callback: Option<EvictionCallback>,
+    pending: Option<Vec<PendingUpdate<Vec<D>>>>,
+    color_correction: ColorCorrection,
+    virtual_resolution: Option<VirtualResolution>,
+    hole_callbacks: HashMap<u64, HoleCallback>,
+}
+
+impl<D> Default for RecordingDisplay<D> {
+    fn default() -> Self {
+        Self::new((0, 0))
+    }
+}
+
+impl<D> RecordingDisplay<D> {
+    /// Creates a new, empty recording display.
+    pub fn new(size: (u32, u32)) -> Self {
+        RecordingDisplay {
+            groups: BTreeMap::new(),
+            next_command_group_id: 0,
+            resources: HashMap::new(),
+            next_resource_id: 0,
+            size,
+            presented: Vec::new(),
+            frames: 0,
+            eviction_policy: EvictionPolicy::default(),
+            eviction_callback: None,
+            pending: None,
+            color_correction: Default::default(),
+            virtual_resolution: None,
+            hole_callbacks: HashMap::new(),
+        }
+    }
+
+    /// Returns the size last given through [`resize`](GraphicsDisplay::resize).
+    pub fn size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    /// Returns the descriptor of an existing resource.
+    pub fn resource(&self, reference: ResourceReference) -> Option<&ResourceDescriptor> {
+        self.resources.get(&reference.id())
+    }
+
+    /// Returns the number of live command groups.
+    pub fn command_group_count(&self) -> usize {
+        self.groups.values().map(Vec::len).sum()
+    }
+
+    /// Returns the flattened display list of the last present, in drawing order.
+    pub fn presented(&self) -> &[D] {
+        &self.presented
+    }
+
+    /// Returns the color correction last given through [`set_color_correction`](GraphicsDisplay::set_color_correction).
+    pub fn color_correction(&self) -> ColorCorrection {
+        self.color_correction
+    }
+
+    /// Returns the virtual resolution last given through [`set_virtual_resolution`](GraphicsDisplay::set_virtual_resolution).
+    pub fn virtual_resolution(&self) -> Option<VirtualResolution> {
+        self.virtual_resolution
+    }
+
+    /// Returns `true` if a callback was set for holes with the ID `id` through
+    /// [`set_hole_callback`](GraphicsDisplay::set_hole_callback).
+    pub fn has_hole_callback(&self, id: u64) -> bool {
+        self.hole_callbacks.contains_key(&id)
+    }
+
+    /// Returns the number of times [`present`](GraphicsDisplay::present) was called.
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    fn find_mut(&mut self, handle: CommandGroupHandle) -> Option<&mut RecordedGroup<D>> {
+        self.groups.values_mut().flat_map(|groups| groups.iter_mut()).find(|g| g.handle == handle)
+    }
+
+    fn take(&mut self, handle: CommandGroupHandle) -> Option<RecordedGroup<D>> {
+        for groups in self.groups.values_mut() {
+            if let Some(idx) = groups.iter().position(|g| g.handle == handle) {
+                return Some(groups.remove(idx));
+            }
+        }
+        None
+    }
+
+    fn insert(
+        &mut self,
+        handle: CommandGroupHandle,
+        commands: Vec<D>,
+        z_order: ZOrder,
+        protected: Option<bool>,
+        needs_maintain: Option<bool>,
+        label: Option<&'static str>,
+    ) {
+        self.groups.entry(z_order).or_default().push(RecordedGroup {
+            handle,
+            commands,
+            protected: protected.unwrap_or(true),
+            missed: if needs_maintain.unwrap_or(true) { Some(0) } else { None },
+            label,
+        });
+    }
+}
+
+impl<D: Clone> GraphicsDisplay<D> for RecordingDisplay<D> {
+    fn resize(&mut self, size: (u32, u32)) -> Result<(), error::DisplayError> {
+        self.size = size;
+        Ok(())
+    }
+
+    fn new_resource(
+        &mut self,
+        descriptor: ResourceDescriptor,
+    ) -> Result<ResourceReference, error::ResourceError> {
+        let id = self.next_resource_id;
+        let reference = descriptor.reference(id);
+        self.resources.insert(id, descriptor);
+        self.next_resource_id += 1;
+        Ok(reference)
+    }
+
+    fn replace_resource(
+        &mut self,
+        reference: ResourceReference,
+        descriptor: ResourceDescriptor,
+    ) -> Result<(), error::DisplayError> {
+        let id = reference.id();
+        if !self.resources.contains_key(&id) {
+            return Err(error::DisplayError::InvalidResource(id));
+        }
+        if descriptor.reference(id) != reference {
+            return Err(error::DisplayError::MismatchedResource(id));
+        }
+        self.resources.insert(id, descriptor);
+        Ok(())
+    }
+
+    /// The descriptor is replaced as a whole, since there are no pixels to update in place.
+    fn update_resource(
+        &mut self,
+        reference: ResourceReference,
+        data: ImageData,
+    ) -> Result<(), error::DisplayError> {
+        self.replace_resource(reference, ResourceDescriptor::Image(data))
+    }
+
+    fn remove_resource(&mut self, reference: ResourceReference) {
+        self.resources.remove(&reference.id());
+    }
+
+    fn push_command_group(
+        &mut self,
+        commands: &[D],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        needs_maintain: Option<bool>,
+    ) -> Result<CommandGroupHandle, error::DisplayError> {
+        let handle = CommandGroupHandle::new(self.next_command_group_id);
+        self.next_command_group_id += 1;
+        if let Some(pending) = &mut self.pending {
+            pending.push(PendingUpdate::Push {
+                handle,
+                commands: commands.to_vec(),
+                z_order,
+                protected,
+                needs_maintain,
+            });
+        } else {
+            self.insert(handle, commands.to_vec(), z_order, protected, needs_maintain, None);
+        }
+        Ok(handle)
+    }
+
+    fn get_command_group(&self, handle: CommandGroupHandle) -> Option<&[D]> {
+        self.groups
+            .values()
+            .flat_map(|groups| groups.iter())
+            .find(|g| g.handle == handle)
+            .map(|g| &g.commands[..])
+    }
+
+    fn modify_command_group(
+        &mut self,
+        handle: CommandGroupHandle,
+        commands: &[D],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        needs_maintain: Option<bool>,
+    ) -> Result<(), error::DisplayError> {
+        if let Some(pending) = &mut self.pending {
+            pending.push(PendingUpdate::Push {
+                handle,
+                commands: commands.to_vec(),
+                z_order,
+                protected,
+                needs_maintain,
+            });
+        } else {
+            let label = self.take(handle).and_then(|g| g.label);
+            self.insert(handle, commands.to_vec(), z_order, protected, needs_maintain, label);
+        }
+        Ok(())
+    }
+
+    fn remove_command_group(&mut self, handle: CommandGroupHandle) -> Option<Vec<DisplayCommand>> {
+        // the commands can only be returned if they are `DisplayCommand`s, which isn't known here.
+        if let Some(pending) = &mut self.pending {
+            pending.push(PendingUpdate::Remove(handle));
+        } else {
+            self.take(handle);
+        }
+        None
+    }
+
+    fn label_command_group(&mut self, handle: CommandGroupHandle, label: Option<&'static str>) {
+        if let Some(pending) = &mut self.pending {
+            pending.push(PendingUpdate::Label(handle, label));
+        } else if let Some(group) = self.find_mut(handle) {
+            group.label = label;
+        }
+    }
+
+    fn begin_update(&mut self) {
+        self.pending.get_or_insert_with(Vec::new);
+    }
+
+    fn commit_update(&mut self) -> Result<(), error::DisplayError> {
+        for update in self.pending.take().unwrap_or_default() {
+            match update {
+                PendingUpdate::Push { handle, commands, z_order, protected, needs_maintain } => {
+                    self.modify_command_group(
+                        handle,
+                        &commands,
+                        z_order,
+                        protected,
+                        needs_maintain,
+                    )?
+                }
+                PendingUpdate::Remove(handle) => {
+                    self.remove_command_group(handle);
+                }
+                PendingUpdate::Label(handle, label) => self.label_command_group(handle, label),
+            }
+        }
+        Ok(())
+    }
+
+    fn debug_dump(&self) -> Vec<CommandGroupInfo> {
+        self.groups
+            .iter()
+            .flat_map(|(z_order, groups)| {
+                groups.iter().map(move |g| CommandGroupInfo {
+                    handle: g.handle,
+                    label: g.label,
+                    z_order: *z_order,
+                    bounds: Rect::default(),
+                    protected: g.protected,
+                    maintained: g.missed.map(|missed| missed == 0),
+                })
+            })
+            .collect()
+    }
+
+    fn maintain_command_group(&mut self, handle: CommandGroupHandle) {
+        if let Some(group) = self.find_mut(handle) {
+            group.missed = group.missed.map(|_| 0);
+        }
+    }
+
+    /// Every command is recorded as-is, except that backdrop filters aren't reported as applied, since nothing is
+    /// drawn for them to filter.
+    fn capabilities(&self) -> DisplayCapabilities {
+        DisplayCapabilities { backdrop_filter: false, ..DisplayCapabilities::all() }
+    }
+
+    fn set_eviction_policy(&mut self, policy: EvictionPolicy) -> Result<(), error::DisplayError> {
+        self.eviction_policy = policy;
+        Ok(())
+    }
+
+    fn set_eviction_callback(
+        &mut self,
+        callback: Option<EvictionCallback>,
+    ) -> Result<(), error::DisplayError> {
+        self.eviction_callback = callback;
+        Ok(())
+    }
+
+    fn before_exit(&mut self) {}
+
+    /// The correction is only kept, since nothing is rasterized.
+    fn set_color_correction(
+        &mut self,
+        correction: ColorCorrection,
+    ) -> Result<(), error::DisplayError> {
+        self.color_correction = correction;
+        Ok(())
+    }
+
+    /// The virtual resolution is only kept, since nothing is rasterized.
+    fn set_virtual_resolution(
+        &mut self,
+        resolution: Option<VirtualResolution>,
+    ) -> Result<(), error::DisplayError> {
+        self.virtual_resolution = resolution;
+        Ok(())
+    }
+
+    /// Callbacks are only kept, since holes aren't drawn.
+    fn set_hole_callback(
+        &mut self,
+        id: u64,
+        callback: Option<HoleCallback>,
+    ) -> Result<(), error::DisplayError> {
+        match callback {
+            Some(callback) => self.hole_callbacks.insert(id, callback),
+            None => self.hole_callbacks.remove(&id),
+        };
+        Ok(())
+    }
+
+    /// Nothing is rasterized, so there are no pixels to read.
+    fn read_pixels(&mut self, _rect: PixelRect) -> Result<RasterImage, error::DisplayError> {
+        Err(error::DisplayError::Unsupported("read_pixels"))
+    }
+
+    /// The callback is invoked as soon as the display list is recorded, since nothing is displayed.
+    fn present_with_callback(
+        &mut self,
+        cull: Option<Rect>,
+        callback: Box<dyn FnOnce(PresentFeedback)>,
+    ) -> Result<PresentInfo, error::DisplayError> {
+        let info = self.present(cull)?;
+        callback(PresentFeedback { info, submitted: Instant::now(), displayed: None });
+        Ok(info)
+    }
+
+    fn present(&mut self, _cull: Option<Rect>) -> Result<PresentInfo, error::DisplayError> {
+        self.presented.clear();
+        let mut info = PresentInfo::default();
+
+        let policy = self.eviction_policy;
+        for (z_order, groups) in self.groups.iter_mut() {
+            // as with other displays, evicted command groups are removed rather than drawn.
+            let mut idx = 0;
+            while idx < groups.len() {
+                if groups[idx].missed.map(|missed| policy.should_evict(missed)).unwrap_or(false) {
+                    let group = groups.remove(idx);
+                    info.evicted += 1;
+                    if let Some(callback) = &mut self.eviction_callback {
+                        callback(CommandGroupInfo {
+                            handle: group.handle,
+                            label: group.label,
+                            z_order: *z_order,
+                            bounds: Rect::default(),
+                            protected: group.protected,
+                            maintained: Some(false),
+                        });
+                    }
+                    continue;
+                }
+
+                let group = &mut groups[idx];
+                self.presented.extend(group.commands.iter().cloned());
+                group.missed = group.missed.map(|missed| missed.saturating_add(1));
+                info.drawn += 1;
+                idx += 1;
+            }
+        }
+
+        self.frames += 1;
+        Ok(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recording_display() {
+        let mut display = RecordingDisplay::<i32>::default();
+
+        let a = display.push_command_group(&[1, 2], ZOrder(1), None, None).unwrap();
+        let b = display.push_command_group(&[3], ZOrder(0), None, Some(false)).unwrap();
+        display.label_command_group(a, Some("a"));
+
+        assert_eq!(display.present(None).unwrap().drawn, 2);
+        assert_eq!(display.presented(), &[3, 1, 2]);
+
+        // `a` isn't maintained, so it's removed; `b` doesn't need maintenance.
+        display.present(None).unwrap();
+        assert_eq!(display.presented(), &[3]);
+        assert_eq!(display.get_command_group(a), None);
+
+        display.modify_command_group(b, &[4, 5], ZOrder(2), None, None).unwrap();
+        display.present(None).unwrap();
+        assert_eq!(display.presented(), &[4, 5]);
+        assert_eq!(display.debug_dump()[0].z_order, ZOrder(2));
+        assert_eq!(display.frames(), 3);
+    }
+
+    #[test]
+    fn test_update_resource() {
+        let mut display = RecordingDisplay::<i32>::default();
+        let frame = |size: u32| {
+            ImageData::Raw(
+                ResourceData::Data(SharedData::RefCount(Arc::new(vec![0; (size * size * 4) as _]))),
+                RasterImageInfo {
+                    size: (size, size),
+                    format: RasterImageFormat::Rgba8,
+                    color_space: ColorSpace::Srgb,
+                },
+            )
+        };
+        let image = display.new_resource(ResourceDescriptor::Image(frame(1))).unwrap();
+        let group = display.push_command_group(&[1], ZOrder(0), None, Some(false)).unwrap();
+
+        // the next frame replaces the pixels, without touching the command groups drawing them.
+        display.update_resource(image, frame(2)).unwrap();
+        match display.resource(image) {
+            Some(ResourceDescriptor::Image(ImageData::Raw(_, info))) => {
+                assert_eq!(info.size, (2, 2))
+            }
+            _ => panic!("expected the updated image"),
+        }
+        assert_eq!(display.get_command_group(group), Some(&[1][..]));
+
+        let font = ResourceReference::Font(image.id());
+        assert!(matches!(
+            display.update_resource(font, frame(3)),
+            Err(error::DisplayError::MismatchedResource(_))
+        ));
+        assert!(matches!(
+            display.update_resource(ResourceReference::Image(100), frame(3)),
+            Err(error::DisplayError::InvalidResource(100))
+        ));
+    }
+
+    #[test]
+    fn test_eviction_policy() {
+        use std::{cell::RefCell, rc::Rc};
+
+        let mut display = RecordingDisplay::<i32>::default();
+        let evicted = Rc::new(RefCell::new(Vec::new()));
+        display
+            .set_eviction_callback(Some(Box::new({
+                let evicted = evicted.clone();
+                move |info| evicted.borrow_mut().push((info.handle, info.label))
+            })))
+            .unwrap();
+        display.set_eviction_policy(EvictionPolicy::KeepForNFrames(2)).unwrap();
+
+        let a = display.push_command_group(&[1], ZOrder(0), None, None).unwrap();
+        display.label_command_group(a, Some("a"));
+        let b = display.push_command_group(&[2], ZOrder(0), None, None).unwrap();
+
+        // `b` is maintained every frame, `a` skips three draws and is dropped on the third present without it.
+        for frame in 0..4 {
+            display.maintain_command_group(b);
+            let info = display.present(None).unwrap();
+            assert_eq!(info.evicted, if frame == 3 { 1 } else { 0 });
+        }
+        assert_eq!(display.presented(), &[2]);
+        assert_eq!(*evicted.borrow(), vec![(a, Some("a"))]);
+
+        display.set_eviction_policy(EvictionPolicy::Never).unwrap();
+        for _ in 0..5 {
+            display.present(None).unwrap();
+        }
+        assert_eq!(display.presented(), &[2]);
+    }
+}
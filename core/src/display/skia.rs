@@ -1,35 +1,117 @@
 //! Robust implementation of `GraphicsDisplay` using Google's Skia.
+//!
+//! The display draws into OpenGL framebuffers and textures, or (with the `metal` feature, on macOS and iOS) into the
+//! drawables of a `CAMetalLayer`. Direct3D isn't supported yet, since the pinned version of `skia-safe` has no D3D
+//! backend.
 
-use super::*;
+use super::{transaction::PendingUpdate, *};
 use {
     crate::error,
     skia_safe as sk,
     std::collections::{BTreeMap, HashMap},
 };
 
+/// `GL_TEXTURE_2D`; the constants are spelled out so that OpenGL ES contexts don't rely on desktop OpenGL bindings.
+const GL_TEXTURE_2D: u32 = 0x0DE1;
+
+/// Color format of an OpenGL framebuffer or texture.
+///
+/// Desktop OpenGL and most OpenGL ES 3 contexts use [`Rgba8`](SkiaOpenGlFormat::Rgba8), whereas OpenGL ES 2 contexts
+/// (e.g. on older Android devices or the Raspberry Pi) may only offer 16-bit formats, and ANGLE on Windows commonly
+/// uses [`Bgra8`](SkiaOpenGlFormat::Bgra8).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SkiaOpenGlFormat {
+    /// `GL_RGBA8`.
+    #[default]
+    Rgba8,
+    /// `GL_BGRA8_EXT`.
+    Bgra8,
+    /// `GL_RGB8`, without alpha.
+    Rgb8,
+    /// `GL_RGB565`.
+    Rgb565,
+    /// `GL_RGBA4`.
+    Rgba4,
+}
+
+impl SkiaOpenGlFormat {
+    /// Returns the sized internal format enum of OpenGL.
+    pub fn gl_format(self) -> u32 {
+        match self {
+            SkiaOpenGlFormat::Rgba8 => 0x8058,
+            SkiaOpenGlFormat::Bgra8 => 0x93A1,
+            SkiaOpenGlFormat::Rgb8 => 0x8051,
+            SkiaOpenGlFormat::Rgb565 => 0x8D62,
+            SkiaOpenGlFormat::Rgba4 => 0x8056,
+        }
+    }
+
+    fn color_type(self) -> sk::ColorType {
+        match self {
+            SkiaOpenGlFormat::Rgba8 => sk::ColorType::RGBA8888,
+            SkiaOpenGlFormat::Bgra8 => sk::ColorType::BGRA8888,
+            SkiaOpenGlFormat::Rgb8 => sk::ColorType::RGB888x,
+            SkiaOpenGlFormat::Rgb565 => sk::ColorType::RGB565,
+            SkiaOpenGlFormat::Rgba4 => sk::ColorType::ARGB4444,
+        }
+    }
+}
+
 /// Contains information about an existing OpenGL framebuffer.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy)]
 pub struct SkiaOpenGlFramebuffer {
     pub size: (i32, i32),
     pub framebuffer_id: u32,
+    pub format: SkiaOpenGlFormat,
+    /// Number of multisampling samples of the framebuffer; `0` if it isn't multisampled.
+    pub sample_count: usize,
+    /// Number of bits of the stencil buffer attached to the framebuffer; `0` if there is none.
+    pub stencil_bits: usize,
 }
 
 /// Contains information about an existing OpenGL texture.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Default, Clone, Copy)]
 pub struct SkiaOpenGlTexture {
     pub size: (i32, i32),
     pub mip_mapped: bool,
     pub texture_id: u32,
+    pub format: SkiaOpenGlFormat,
+}
+
+/// Contains information about an existing `CAMetalLayer`.
+#[cfg(feature = "metal")]
+#[derive(Debug, Clone, Copy)]
+pub struct SkiaMetalLayer {
+    pub size: (i32, i32),
+    /// Pointer to the `CAMetalLayer`.
+    pub layer: *mut std::ffi::c_void,
 }
 
 enum SurfaceType {
     OpenGlFramebuffer(SkiaOpenGlFramebuffer),
     OpenGlTexture(SkiaOpenGlTexture),
+    /// The layer, along with the drawable which the surface draws into.
+    #[cfg(feature = "metal")]
+    MetalLayer(SkiaMetalLayer, sk::gpu::mtl::Handle),
 }
 
 enum Resource {
     Image(sk::Image),
     Font(sk::Typeface),
+    TextBlob(sk::TextBlob),
+}
+
+/// Draws [`CustomCommand`](crate::display::CustomCommand)s of a given name, see
+/// [`register_command_interpreter`](SkiaGraphicsDisplay::register_command_interpreter).
+pub type SkiaCommandInterpreter = Box<dyn FnMut(&dyn CustomCommand, &mut sk::Canvas, ResourceView)>;
+
+/// Callbacks invoked while drawing command groups.
+#[derive(Default)]
+struct DrawHooks {
+    holes: HashMap<u64, HoleCallback>,
+    interpreters: HashMap<&'static str, SkiaCommandInterpreter>,
+    /// Holes drawn into an offscreen surface, whose callbacks are invoked once the frame is on the surface.
+    deferred_holes: Option<Vec<HoleInfo>>,
 }
 
 /// Accessor view into the resources stored in a Skia display.
@@ -67,6 +149,21 @@ impl<'a> ResourceView<'a> {
             None
         }
     }
+
+    /// Returns a given text blob resource.
+    pub fn text_blob(&self, reference: ResourceReference) -> Option<&sk::TextBlob> {
+        if let ResourceReference::TextBlob(id) = reference {
+            self.resources.get(&id).and_then(|res| {
+                if let Resource::TextBlob(blob) = res {
+                    Some(blob)
+                } else {
+                    None
+                }
+            })
+        } else {
+            None
+        }
+    }
 }
 
 enum Commands {
@@ -91,11 +188,22 @@ enum CommandsRef<'a> {
 #[derive(Default)]
 struct CommandList {
     command_groups:
-        BTreeMap<ZOrder, linked_hash_map::LinkedHashMap<u64, (Commands, Rect, bool, Option<bool>)>>,
+        BTreeMap<ZOrder, linked_hash_map::LinkedHashMap<u64, (Commands, Rect, bool, Option<u32>)>>,
     z_lookup: HashMap<CommandGroupHandle, ZOrder>,
+    labels: HashMap<CommandGroupHandle, &'static str>,
+    // updates deferred until `commit`, within `begin_update`.
+    pending: Option<Vec<PendingUpdate<Commands>>>,
 }
 
 impl CommandList {
+    fn bounds(commands: &Commands) -> Result<Rect, error::DisplayError> {
+        Ok(if let Commands::Display(cmds) = commands {
+            display_list_bounds(cmds)?
+        } else {
+            Rect::new(Point::new(0., 0.), Size::new(f32::MAX, f32::MAX))
+        })
+    }
+
     fn push(
         &mut self,
         commands: Commands,
@@ -103,24 +211,42 @@ impl CommandList {
         protected: Option<bool>,
         needs_maintain: Option<bool>,
         handle: CommandGroupHandle,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let bounds = if let Commands::Display(cmds) = &commands {
-            display_list_bounds(cmds)?
-        } else {
-            Rect::new(Point::new(0., 0.), Size::new(f32::MAX, f32::MAX))
-        };
+    ) -> Result<(), error::DisplayError> {
+        if let Some(pending) = &mut self.pending {
+            pending.push(PendingUpdate::Push {
+                handle,
+                commands,
+                z_order,
+                protected,
+                needs_maintain,
+            });
+            return Ok(());
+        }
+
+        let bounds = Self::bounds(&commands)?;
+        self.insert(commands, bounds, z_order, protected, needs_maintain, handle);
+        Ok(())
+    }
 
+    fn insert(
+        &mut self,
+        commands: Commands,
+        bounds: Rect,
+        z_order: ZOrder,
+        protected: Option<bool>,
+        needs_maintain: Option<bool>,
+        handle: CommandGroupHandle,
+    ) {
         self.command_groups.entry(z_order).or_default().insert(
             handle.id(),
             (
                 commands,
                 bounds,
                 protected.unwrap_or(true),
-                if needs_maintain.unwrap_or(true) { Some(true) } else { None },
+                if needs_maintain.unwrap_or(true) { Some(0) } else { None },
             ),
         );
         self.z_lookup.insert(handle, z_order);
-        Ok(())
     }
 
     fn get(&self, handle: CommandGroupHandle) -> Option<CommandsRef<'_>> {
@@ -137,31 +263,129 @@ impl CommandList {
         z_order: ZOrder,
         protected: Option<bool>,
         needs_maintain: Option<bool>,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        self.remove(handle);
-        self.push(commands, z_order, protected, needs_maintain, handle)
+    ) -> Result<(), error::DisplayError> {
+        if self.pending.is_some() {
+            return self.push(commands, z_order, protected, needs_maintain, handle);
+        }
+
+        // compute the bounds before removing, so that the existing group is kept if this fails.
+        let bounds = Self::bounds(&commands)?;
+        self.replace(handle, commands, bounds, z_order, protected, needs_maintain);
+        Ok(())
+    }
+
+    fn replace(
+        &mut self,
+        handle: CommandGroupHandle,
+        commands: Commands,
+        bounds: Rect,
+        z_order: ZOrder,
+        protected: Option<bool>,
+        needs_maintain: Option<bool>,
+    ) {
+        let label = self.labels.get(&handle).copied();
+        self.remove_now(handle);
+        self.insert(commands, bounds, z_order, protected, needs_maintain, handle);
+        if let Some(label) = label {
+            self.labels.insert(handle, label);
+        }
+    }
+
+    fn label(&mut self, handle: CommandGroupHandle, label: Option<&'static str>) {
+        if let Some(pending) = &mut self.pending {
+            pending.push(PendingUpdate::Label(handle, label));
+            return;
+        }
+
+        match label {
+            Some(label) if self.z_lookup.contains_key(&handle) => {
+                self.labels.insert(handle, label);
+            }
+            _ => {
+                self.labels.remove(&handle);
+            }
+        }
+    }
+
+    fn begin(&mut self) {
+        self.pending.get_or_insert_with(Vec::new);
+    }
+
+    /// Applies the deferred updates, computing all the bounds up-front so that nothing is applied if any fails.
+    fn commit(&mut self) -> Result<(), error::DisplayError> {
+        let pending = self.pending.take().unwrap_or_default();
+        let bounds = pending
+            .iter()
+            .filter_map(|update| match update {
+                PendingUpdate::Push { commands, .. } => Some(Self::bounds(commands)),
+                _ => None,
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut bounds = bounds.into_iter();
+        for update in pending {
+            match update {
+                PendingUpdate::Push { handle, commands, z_order, protected, needs_maintain } => {
+                    let bounds = bounds.next().unwrap();
+                    self.replace(handle, commands, bounds, z_order, protected, needs_maintain);
+                }
+                PendingUpdate::Remove(handle) => {
+                    self.remove_now(handle);
+                }
+                PendingUpdate::Label(handle, label) => self.label(handle, label),
+            }
+        }
+        Ok(())
     }
 
     fn maintain(&mut self, handle: CommandGroupHandle) {
         if let Some(z) = self.z_lookup.get(&handle) {
             if let Some(z_list) = self.command_groups.get_mut(z) {
                 if let Some(cmd_group) = z_list.get_refresh(&handle.id()) {
-                    cmd_group.3 = cmd_group.3.map(|_| true);
+                    cmd_group.3 = cmd_group.3.map(|_| 0);
                 }
             }
         }
     }
 
     fn remove(&mut self, handle: CommandGroupHandle) -> Option<Commands> {
+        if let Some(pending) = &mut self.pending {
+            pending.push(PendingUpdate::Remove(handle));
+            return None;
+        }
+        self.remove_now(handle)
+    }
+
+    fn remove_now(&mut self, handle: CommandGroupHandle) -> Option<Commands> {
         if let Some(&z) = self.z_lookup.get(&handle) {
             self.z_lookup.remove(&handle);
+            self.labels.remove(&handle);
             Some(self.command_groups.get_mut(&z)?.remove(&handle.id())?.0)
         } else {
             None
         }
     }
 
-    fn flattened(&self) -> Vec<(u64, &(Commands, Rect, bool, Option<bool>))> {
+    fn debug_dump(&self) -> Vec<CommandGroupInfo> {
+        self.command_groups
+            .iter()
+            .flat_map(|(z_order, z_list)| {
+                z_list.iter().map(move |(id, cmds)| {
+                    let handle = CommandGroupHandle::new(*id);
+                    CommandGroupInfo {
+                        handle,
+                        label: self.labels.get(&handle).copied(),
+                        z_order: *z_order,
+                        bounds: cmds.1,
+                        protected: cmds.2,
+                        maintained: cmds.3.map(|missed| missed == 0),
+                    }
+                })
+            })
+            .collect()
+    }
+
+    fn flattened(&self) -> Vec<(u64, &(Commands, Rect, bool, Option<u32>))> {
         self.command_groups
             .iter()
             .fold(Vec::new(), |mut list, (_, z_list)| {
@@ -182,46 +406,146 @@ pub struct SkiaGraphicsDisplay {
     list: CommandList,
     next_command_group_id: u64,
     resources: HashMap<u64, Resource>,
+    /// Surfaces holding the pixels of raw images updated through `update_resource`, which are written in place.
+    image_surfaces: HashMap<u64, (RasterImageInfo, sk::Surface)>,
     next_resource_id: u64,
+    color_space: ColorSpace,
+    eviction_policy: EvictionPolicy,
+    eviction_callback: Option<EvictionCallback>,
+    hooks: DrawHooks,
+    color_correction: ColorCorrection,
+    /// Offscreen surface of the virtual resolution, which command groups are drawn into instead, if any.
+    virtual_surface: Option<(VirtualResolution, sk::Surface)>,
+    /// Offscreen surface which command groups are drawn into while a color correction is set without a virtual
+    /// resolution, so that the correction is applied once as the frame is copied onto the surface, rather than
+    /// accumulating on pixels which aren't redrawn.
+    corrected_surface: Option<sk::Surface>,
+    /// Draws every command group on the next present, since an offscreen surface was (re)created empty.
+    needs_full_redraw: bool,
 }
 
 impl SkiaGraphicsDisplay {
     /// Creates a new [`SkiaGraphicsDisplay`](SkiaGraphicsDisplay) with the Skia OpenGL backend, drawing into an existing framebuffer.
-    /// This assumes that an OpenGL context has already been set up.
-    /// This also assumes that the color format is RGBA with 8-bit components.
+    /// This assumes that an OpenGL (or OpenGL ES) context has already been set up.
+    ///
+    /// `loader` resolves functions of the context, e.g. `eglGetProcAddress` for EGL and ANGLE.
     pub fn new_gl_framebuffer(
         loader: impl FnMut(&str) -> *const std::ffi::c_void,
         target: &SkiaOpenGlFramebuffer,
     ) -> Result<Self, error::SkiaError> {
-        let (surface, context) = Self::new_gl_framebuffer_surface(loader, target)?;
-        Ok(Self {
-            surface,
-            surface_type: SurfaceType::OpenGlFramebuffer(*target),
-            context,
-            list: Default::default(),
-            next_command_group_id: 0,
-            resources: HashMap::new(),
-            next_resource_id: 0,
-        })
+        Self::new_gl_framebuffer_with_context(Self::new_gl_context(loader)?, target)
     }
 
     /// Creates a new [`SkiaGraphicsDisplay`](SkiaGraphicsDisplay) with the Skia OpenGL backend, drawing into an existing texture.
-    /// This assumes that an OpenGL context has already been set up.
-    /// This also assumes that the color format is RGBA with 8-bit components
+    /// This assumes that an OpenGL (or OpenGL ES) context has already been set up.
+    ///
+    /// `loader` resolves functions of the context, e.g. `eglGetProcAddress` for EGL and ANGLE.
     pub fn new_gl_texture(
         loader: impl FnMut(&str) -> *const std::ffi::c_void,
         target: &SkiaOpenGlTexture,
     ) -> Result<Self, error::SkiaError> {
-        let (surface, context) = Self::new_gl_texture_surface(loader, target)?;
-        Ok(Self {
+        Self::new_gl_texture_with_context(Self::new_gl_context(loader)?, target)
+    }
+
+    /// Same as [`new_gl_framebuffer`](SkiaGraphicsDisplay::new_gl_framebuffer), but draws with an existing Skia context
+    /// rather than creating one.
+    ///
+    /// This is for applications which already render with Skia (e.g. a game or CAD viewport embedding a UI), so that
+    /// both share one context (which is reference counted) on the same OpenGL context. Skia caches the OpenGL state,
+    /// so whenever the application changes the state itself, it has to reset the context before the display presents.
+    pub fn new_gl_framebuffer_with_context(
+        mut context: sk::gpu::Context,
+        target: &SkiaOpenGlFramebuffer,
+    ) -> Result<Self, error::SkiaError> {
+        let surface =
+            Self::new_gl_framebuffer_from_context(target, &mut context, ColorSpace::Srgb)?;
+        Ok(Self::from_surface(surface, SurfaceType::OpenGlFramebuffer(*target), context))
+    }
+
+    /// Same as [`new_gl_texture`](SkiaGraphicsDisplay::new_gl_texture), but draws with an existing Skia context rather
+    /// than creating one.
+    ///
+    /// See [`new_gl_framebuffer_with_context`](SkiaGraphicsDisplay::new_gl_framebuffer_with_context).
+    pub fn new_gl_texture_with_context(
+        mut context: sk::gpu::Context,
+        target: &SkiaOpenGlTexture,
+    ) -> Result<Self, error::SkiaError> {
+        let surface = Self::new_gl_texture_from_context(target, &mut context, ColorSpace::Srgb)?;
+        Ok(Self::from_surface(surface, SurfaceType::OpenGlTexture(*target), context))
+    }
+
+    /// Creates a new [`SkiaGraphicsDisplay`](SkiaGraphicsDisplay) with the Skia Metal backend, drawing into the drawables
+    /// of a `CAMetalLayer`.
+    ///
+    /// Once a frame has been presented, the drawable has to be presented through
+    /// [`next_metal_drawable`](SkiaGraphicsDisplay::next_metal_drawable).
+    ///
+    /// # Safety
+    ///
+    /// `device` must point to a valid `MTLDevice`, `queue` to a valid `MTLCommandQueue` of that device,
+    /// and the layer of `target` must use the device.
+    #[cfg(feature = "metal")]
+    pub unsafe fn new_metal_layer(
+        device: *mut std::ffi::c_void,
+        queue: *mut std::ffi::c_void,
+        target: &SkiaMetalLayer,
+    ) -> Result<Self, error::SkiaError> {
+        let mut context =
+            sk::gpu::Context::new_metal(device, queue).ok_or(error::SkiaError::InvalidContext)?;
+        let (surface, drawable) =
+            Self::new_metal_surface_from_context(target, &mut context, ColorSpace::Srgb)?;
+        Ok(Self::from_surface(surface, SurfaceType::MetalLayer(*target, drawable), context))
+    }
+
+    /// Returns the `CAMetalDrawable` which the display has been drawing into, so that the application can present it
+    /// (e.g. with `presentDrawable` of a command buffer), and moves on to the next drawable of the layer.
+    ///
+    /// Returns `None` if the display doesn't draw into a `CAMetalLayer`.
+    #[cfg(feature = "metal")]
+    pub fn next_metal_drawable(
+        &mut self,
+    ) -> Result<Option<sk::gpu::mtl::Handle>, error::SkiaError> {
+        let target = match self.surface_type {
+            SurfaceType::MetalLayer(target, _) => target,
+            _ => return Ok(None),
+        };
+        let (surface, next) =
+            Self::new_metal_surface_from_context(&target, &mut self.context, self.color_space)?;
+        self.surface = surface;
+        Ok(Some(match self.surface_type {
+            SurfaceType::MetalLayer(_, ref mut drawable) => std::mem::replace(drawable, next),
+            _ => unreachable!(),
+        }))
+    }
+
+    /// Returns the Skia context the display draws with, e.g. to share it with other Skia rendering.
+    pub fn context_mut(&mut self) -> &mut sk::gpu::Context {
+        &mut self.context
+    }
+
+    fn from_surface(
+        surface: sk::Surface,
+        surface_type: SurfaceType,
+        context: sk::gpu::Context,
+    ) -> Self {
+        Self {
             surface,
-            surface_type: SurfaceType::OpenGlTexture(*target),
+            surface_type,
             context,
             list: Default::default(),
             next_command_group_id: 0,
             resources: HashMap::new(),
+            image_surfaces: HashMap::new(),
             next_resource_id: 0,
-        })
+            color_space: ColorSpace::Srgb,
+            eviction_policy: EvictionPolicy::default(),
+            eviction_callback: None,
+            hooks: Default::default(),
+            color_correction: Default::default(),
+            virtual_surface: None,
+            corrected_surface: None,
+            needs_full_redraw: false,
+        }
     }
 
     /// Returns the size of the underlying surface.
@@ -229,6 +553,83 @@ impl SkiaGraphicsDisplay {
         match self.surface_type {
             SurfaceType::OpenGlFramebuffer(SkiaOpenGlFramebuffer { size, .. })
             | SurfaceType::OpenGlTexture(SkiaOpenGlTexture { size, .. }) => size,
+            #[cfg(feature = "metal")]
+            SurfaceType::MetalLayer(SkiaMetalLayer { size, .. }, _) => size,
+        }
+    }
+
+    /// Returns the color space of the underlying surface.
+    pub fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    /// Changes the color space of the underlying surface (sRGB by default), recreating it.
+    ///
+    /// This should match the color space of the monitor (e.g. Display P3 for wide-gamut displays),
+    /// so that sRGB (or otherwise tagged) colors are converted accordingly.
+    pub fn set_color_space(&mut self, color_space: ColorSpace) -> Result<(), error::SkiaError> {
+        self.surface = self.recreate_surface(color_space)?;
+        self.color_space = color_space;
+        if let Some((resolution, _)) = self.virtual_surface {
+            let surface = self.new_offscreen_surface(resolution.size, "virtual resolution")?;
+            self.virtual_surface = Some((resolution, surface));
+        }
+        self.update_corrected_surface(true)
+    }
+
+    fn new_offscreen_surface(
+        &mut self,
+        size: (u32, u32),
+        target: &str,
+    ) -> Result<sk::Surface, error::SkiaError> {
+        let info = sk::ImageInfo::new(
+            sk::ISize::new(size.0 as _, size.1 as _),
+            sk::ColorType::RGBA8888,
+            sk::AlphaType::Premul,
+            convert_color_space(self.color_space),
+        );
+        self.needs_full_redraw = true;
+        self.surface
+            .new_surface(&info)
+            .ok_or_else(|| error::SkiaError::InvalidTarget(String::from(target)))
+    }
+
+    /// Creates or removes the offscreen surface of the color correction as needed, recreating an existing one if
+    /// `recreate` (e.g. after a resize).
+    fn update_corrected_surface(&mut self, recreate: bool) -> Result<(), error::SkiaError> {
+        let needed = !self.color_correction.is_identity() && self.virtual_surface.is_none();
+        if !needed {
+            // frames are drawn straight into the surface again, which still holds the last corrected frame.
+            if self.corrected_surface.take().is_some() {
+                self.needs_full_redraw = true;
+            }
+        } else if recreate || self.corrected_surface.is_none() {
+            let (width, height) = self.size();
+            self.corrected_surface =
+                Some(self.new_offscreen_surface((width as _, height as _), "color correction")?);
+        }
+        Ok(())
+    }
+
+    fn recreate_surface(
+        &mut self,
+        color_space: ColorSpace,
+    ) -> Result<sk::Surface, error::SkiaError> {
+        match self.surface_type {
+            SurfaceType::OpenGlFramebuffer(ref target) => {
+                Self::new_gl_framebuffer_from_context(target, &mut self.context, color_space)
+            }
+            SurfaceType::OpenGlTexture(ref target) => {
+                Self::new_gl_texture_from_context(target, &mut self.context, color_space)
+            }
+            #[cfg(feature = "metal")]
+            SurfaceType::MetalLayer(ref target, ref mut drawable) => {
+                // the surface draws into a new drawable, so the current one is released unpresented.
+                let (surface, next) =
+                    Self::new_metal_surface_from_context(target, &mut self.context, color_space)?;
+                *drawable = next;
+                Ok(surface)
+            }
         }
     }
 
@@ -239,7 +640,7 @@ impl SkiaGraphicsDisplay {
         z_order: ZOrder,
         protected: Option<bool>,
         needs_maintain: Option<bool>,
-    ) -> Result<CommandGroupHandle, Box<dyn std::error::Error>> {
+    ) -> Result<CommandGroupHandle, error::DisplayError> {
         let handle = CommandGroupHandle::new(self.next_command_group_id);
         self.list.push(
             Commands::Custom(Box::new(closure)),
@@ -252,62 +653,66 @@ impl SkiaGraphicsDisplay {
         Ok(handle)
     }
 
-    /// Immediately executes a closure which has direct access to the Skia canvas and stored resources.
-    pub fn perform_draw_closure(&mut self, closure: impl FnOnce(&mut sk::Canvas, ResourceView)) {
-        closure(self.surface.canvas(), ResourceView { resources: &self.resources })
+    /// Registers an interpreter drawing every [`CustomCommand`](crate::display::CustomCommand) named `name`,
+    /// returning the interpreter it replaces, if any.
+    ///
+    /// Custom commands without an interpreter are drawn as their [`fallback`](crate::display::CustomCommand::fallback).
+    pub fn register_command_interpreter(
+        &mut self,
+        name: &'static str,
+        interpreter: SkiaCommandInterpreter,
+    ) -> Option<SkiaCommandInterpreter> {
+        self.hooks.interpreters.insert(name, interpreter)
     }
 
-    fn new_gl_framebuffer_surface(
-        loader: impl FnMut(&str) -> *const std::ffi::c_void,
-        target: &SkiaOpenGlFramebuffer,
-    ) -> Result<(sk::Surface, sk::gpu::Context), error::SkiaError> {
-        let mut context = Self::new_gl_context(loader)?;
+    /// Removes the interpreter of custom commands named `name`, returning it if there was one.
+    pub fn unregister_command_interpreter(&mut self, name: &str) -> Option<SkiaCommandInterpreter> {
+        self.hooks.interpreters.remove(name)
+    }
 
-        Ok((SkiaGraphicsDisplay::new_gl_framebuffer_from_context(target, &mut context)?, context))
+    /// Immediately executes a closure which has direct access to the Skia canvas and stored resources.
+    pub fn perform_draw_closure(&mut self, closure: impl FnOnce(&mut sk::Canvas, ResourceView)) {
+        closure(self.surface.canvas(), ResourceView { resources: &self.resources })
     }
 
     fn new_gl_framebuffer_from_context(
         target: &SkiaOpenGlFramebuffer,
         context: &mut sk::gpu::Context,
+        color_space: ColorSpace,
     ) -> Result<sk::Surface, error::SkiaError> {
         let info = sk::gpu::BackendRenderTarget::new_gl(
             target.size,
-            None,
-            8,
-            sk::gpu::gl::FramebufferInfo { fboid: target.framebuffer_id, format: gl::RGBA8 },
+            if target.sample_count > 0 { Some(target.sample_count) } else { None },
+            target.stencil_bits,
+            sk::gpu::gl::FramebufferInfo {
+                fboid: target.framebuffer_id,
+                format: target.format.gl_format(),
+            },
         );
 
         Ok(sk::Surface::from_backend_render_target(
             context,
             &info,
             sk::gpu::SurfaceOrigin::BottomLeft,
-            sk::ColorType::RGBA8888,
-            sk::ColorSpace::new_srgb(),
+            target.format.color_type(),
+            convert_color_space(color_space),
             None,
         )
         .ok_or_else(|| error::SkiaError::InvalidTarget(String::from("framebuffer")))?)
     }
 
-    fn new_gl_texture_surface(
-        loader: impl FnMut(&str) -> *const std::ffi::c_void,
-        target: &SkiaOpenGlTexture,
-    ) -> Result<(sk::Surface, sk::gpu::Context), error::SkiaError> {
-        let mut context = Self::new_gl_context(loader)?;
-
-        Ok((SkiaGraphicsDisplay::new_gl_texture_from_context(target, &mut context)?, context))
-    }
-
     fn new_gl_texture_from_context(
         target: &SkiaOpenGlTexture,
         context: &mut sk::gpu::Context,
+        color_space: ColorSpace,
     ) -> Result<sk::Surface, error::SkiaError> {
         let info = unsafe {
             sk::gpu::BackendTexture::new_gl(
                 target.size,
                 if target.mip_mapped { sk::gpu::MipMapped::Yes } else { sk::gpu::MipMapped::No },
                 sk::gpu::gl::TextureInfo {
-                    format: gl::RGBA8,
-                    target: gl::TEXTURE_2D,
+                    format: target.format.gl_format(),
+                    target: GL_TEXTURE_2D,
                     id: target.texture_id,
                 },
             )
@@ -318,41 +723,55 @@ impl SkiaGraphicsDisplay {
             &info,
             sk::gpu::SurfaceOrigin::BottomLeft,
             None,
-            sk::ColorType::RGBA8888,
-            sk::ColorSpace::new_srgb(),
+            target.format.color_type(),
+            convert_color_space(color_space),
             None,
         )
         .ok_or_else(|| error::SkiaError::InvalidTarget(String::from("texture")))?)
     }
 
+    #[cfg(feature = "metal")]
+    fn new_metal_surface_from_context(
+        target: &SkiaMetalLayer,
+        context: &mut sk::gpu::Context,
+        color_space: ColorSpace,
+    ) -> Result<(sk::Surface, sk::gpu::mtl::Handle), error::SkiaError> {
+        unsafe {
+            sk::Surface::from_ca_metal_layer(
+                context,
+                target.layer as _,
+                sk::gpu::SurfaceOrigin::TopLeft,
+                None,
+                sk::ColorType::BGRA8888,
+                convert_color_space(color_space),
+                None,
+            )
+        }
+        .ok_or_else(|| error::SkiaError::InvalidTarget(String::from("metal layer")))
+    }
+
     fn new_gl_context(
         loader: impl FnMut(&str) -> *const std::ffi::c_void,
     ) -> Result<sk::gpu::Context, error::SkiaError> {
         sk::gpu::Context::new_gl(sk::gpu::gl::Interface::new_load_with(loader))
             .ok_or(error::SkiaError::InvalidContext)
     }
-}
-
-impl GraphicsDisplay for SkiaGraphicsDisplay {
-    fn resize(&mut self, size: (u32, u32)) -> Result<(), Box<dyn std::error::Error>> {
-        self.surface = match self.surface_type {
-            SurfaceType::OpenGlFramebuffer(ref mut target) => {
-                target.size = (size.0 as i32, size.1 as i32);
-                Self::new_gl_framebuffer_from_context(target, &mut self.context)
-            }
-            SurfaceType::OpenGlTexture(ref mut target) => {
-                target.size = (size.0 as i32, size.1 as i32);
-                Self::new_gl_texture_from_context(target, &mut self.context)
-            }
-        }?;
 
-        Ok(())
+    /// Creates a Skia context for the OpenGL (or OpenGL ES) context current on this thread, resolving functions through
+    /// the platform's native mechanism (e.g. EGL on Android) rather than a loader.
+    ///
+    /// The returned context is for [`new_gl_framebuffer_with_context`](SkiaGraphicsDisplay::new_gl_framebuffer_with_context)
+    /// and [`new_gl_texture_with_context`](SkiaGraphicsDisplay::new_gl_texture_with_context).
+    pub fn new_native_gl_context() -> Result<sk::gpu::Context, error::SkiaError> {
+        sk::gpu::gl::Interface::new_native()
+            .and_then(sk::gpu::Context::new_gl)
+            .ok_or(error::SkiaError::InvalidContext)
     }
 
-    fn new_resource(
-        &mut self,
-        descriptor: ResourceDescriptor,
-    ) -> Result<ResourceReference, error::ResourceError> {
+    fn create_resource(
+        &self,
+        descriptor: &ResourceDescriptor,
+    ) -> Result<Resource, error::ResourceError> {
         let load_data = |data: ResourceData| -> Result<sk::Data, error::ResourceError> {
             Ok(match data {
                 ResourceData::File(path) => {
@@ -371,49 +790,150 @@ impl GraphicsDisplay for SkiaGraphicsDisplay {
             })
         };
 
-        let id = self.next_resource_id;
-        let (rid, res) = match &descriptor {
-            ResourceDescriptor::Image(data) => (
-                ResourceReference::Image(id),
-                Resource::Image(match data {
-                    ImageData::Encoded(data) => {
-                        sk::Image::from_encoded(load_data(data.clone())?, None)
-                            .ok_or(error::ResourceError::InvalidData)?
-                    }
-                    ImageData::Raw(data, info) => sk::Image::from_raster_data(
-                        &sk::ImageInfo::new(
-                            sk::ISize::new(info.size.0 as _, info.size.1 as _),
-                            match info.format {
-                                RasterImageFormat::Rgba8 => sk::ColorType::RGBA8888,
-                                RasterImageFormat::Bgra8 => sk::ColorType::BGRA8888,
-                            },
-                            sk::AlphaType::Unpremul,
-                            None,
-                        ),
-                        load_data(data.clone())?,
-                        info.size.0 as usize * 4, // width * 4 bytes -> 4 x 8-bit components
-                    )
+        Ok(match descriptor {
+            ResourceDescriptor::Image(data) => Resource::Image(match data {
+                ImageData::Encoded(data) => sk::Image::from_encoded(load_data(data.clone())?, None)
+                    .ok_or(error::ResourceError::InvalidData)?,
+                ImageData::Raw(data, info) => sk::Image::from_raster_data(
+                    &convert_raster_image_info(info),
+                    load_data(data.clone())?,
+                    info.size.0 as usize * 4, // width * 4 bytes -> 4 x 8-bit components
+                )
+                .ok_or(error::ResourceError::InvalidData)?,
+            }),
+            ResourceDescriptor::Font(data) => Resource::Font(
+                sk::Typeface::from_data(load_data(data.clone())?, None)
                     .ok_or(error::ResourceError::InvalidData)?,
-                }),
-            ),
-            ResourceDescriptor::Font(data) => (
-                ResourceReference::Font(id),
-                Resource::Font(
-                    sk::Typeface::from_data(load_data(data.clone())?, None)
-                        .ok_or(error::ResourceError::InvalidData)?,
-                ),
             ),
-        };
+            ResourceDescriptor::TextBlob(item) => {
+                let typeface = match (item.font, self.resources.get(&item.font.id())) {
+                    (ResourceReference::Font(_), Some(Resource::Font(typeface))) => {
+                        typeface.clone()
+                    }
+                    _ => return Err(error::ResourceError::InvalidData),
+                };
+                Resource::TextBlob(
+                    convert_display_text(&item.text, sk::Font::new(typeface, item.size))
+                        .map_err(|e| error::ResourceError::InternalError(e.into()))?,
+                )
+            }
+        })
+    }
+}
+
+impl GraphicsDisplay for SkiaGraphicsDisplay {
+    fn resize(&mut self, size: (u32, u32)) -> Result<(), error::DisplayError> {
+        let size = (size.0 as i32, size.1 as i32);
+        match self.surface_type {
+            SurfaceType::OpenGlFramebuffer(ref mut target) => target.size = size,
+            SurfaceType::OpenGlTexture(ref mut target) => target.size = size,
+            // the layer itself is resized (through its `drawableSize`) by the application.
+            #[cfg(feature = "metal")]
+            SurfaceType::MetalLayer(ref mut target, _) => target.size = size,
+        }
+        self.surface = self.recreate_surface(self.color_space)?;
+        self.update_corrected_surface(true)?;
 
-        self.resources.insert(id, res);
+        Ok(())
+    }
+
+    fn new_resource(
+        &mut self,
+        descriptor: ResourceDescriptor,
+    ) -> Result<ResourceReference, error::ResourceError> {
+        let id = self.next_resource_id;
+        let resource = self.create_resource(&descriptor)?;
+        self.resources.insert(id, resource);
         self.next_resource_id += 1;
 
-        Ok(rid)
+        Ok(descriptor.reference(id))
+    }
+
+    fn replace_resource(
+        &mut self,
+        reference: ResourceReference,
+        descriptor: ResourceDescriptor,
+    ) -> Result<(), error::DisplayError> {
+        let id = reference.id();
+        if !self.resources.contains_key(&id) {
+            return Err(error::DisplayError::InvalidResource(id));
+        }
+        if descriptor.reference(id) != reference {
+            return Err(error::DisplayError::MismatchedResource(id));
+        }
+        // command groups look resources up by ID every time they're drawn, so they pick up the new resource as is.
+        let resource = self.create_resource(&descriptor)?;
+        self.resources.insert(id, resource);
+        self.image_surfaces.remove(&id);
+        Ok(())
+    }
+
+    fn update_resource(
+        &mut self,
+        reference: ResourceReference,
+        data: ImageData,
+    ) -> Result<(), error::DisplayError> {
+        let id = reference.id();
+        let size = match self.resources.get(&id) {
+            Some(Resource::Image(image)) => (image.width() as u32, image.height() as u32),
+            Some(_) => return Err(error::DisplayError::MismatchedResource(id)),
+            None => return Err(error::DisplayError::InvalidResource(id)),
+        };
+        // only raw pixels of the same size can be written into the existing image.
+        let (pixels, info) = match data {
+            ImageData::Raw(ref pixels, info) if info.size == size => (pixels, info),
+            data => return self.replace_resource(reference, ResourceDescriptor::Image(data)),
+        };
+        let pixels = match pixels {
+            ResourceData::File(path) => {
+                std::borrow::Cow::Owned(std::fs::read(path).map_err(error::ResourceError::from)?)
+            }
+            ResourceData::Data(SharedData::RefCount(data)) => std::borrow::Cow::Borrowed(&data[..]),
+            ResourceData::Data(SharedData::Static(data)) => std::borrow::Cow::Borrowed(*data),
+        };
+        let row_bytes = info.size.0 as usize * 4;
+        if pixels.len() < row_bytes * info.size.1 as usize {
+            return Err(error::ResourceError::InvalidData.into());
+        }
+
+        let mut surface = match self.image_surfaces.remove(&id) {
+            Some((surface_info, surface)) if surface_info == info => surface,
+            _ => {
+                let surface_info = sk::ImageInfo::new(
+                    sk::ISize::new(info.size.0 as _, info.size.1 as _),
+                    sk::ColorType::RGBA8888,
+                    sk::AlphaType::Premul,
+                    convert_color_space(info.color_space),
+                );
+                self.surface.new_surface(&surface_info).ok_or_else(|| {
+                    error::SkiaError::InvalidTarget(String::from("image resource"))
+                })?
+            }
+        };
+
+        // the current image is released first, so that Skia doesn't copy the pixels before they're overwritten.
+        let previous = self.resources.remove(&id);
+        if !surface.canvas().write_pixels(
+            &convert_raster_image_info(&info),
+            &pixels,
+            row_bytes,
+            sk::IPoint::new(0, 0),
+        ) {
+            if let Some(previous) = previous {
+                self.resources.insert(id, previous);
+            }
+            return Err(error::SkiaError::UnknownError.into());
+        }
+        drop(previous);
+        self.resources.insert(id, Resource::Image(surface.image_snapshot()));
+        self.image_surfaces.insert(id, (info, surface));
+        Ok(())
     }
 
     #[inline]
     fn remove_resource(&mut self, reference: ResourceReference) {
         self.resources.remove(&reference.id());
+        self.image_surfaces.remove(&reference.id());
     }
 
     fn push_command_group(
@@ -422,7 +942,7 @@ impl GraphicsDisplay for SkiaGraphicsDisplay {
         z_order: ZOrder,
         protected: Option<bool>,
         needs_maintain: Option<bool>,
-    ) -> Result<CommandGroupHandle, Box<dyn std::error::Error>> {
+    ) -> Result<CommandGroupHandle, error::DisplayError> {
         let handle = CommandGroupHandle::new(self.next_command_group_id);
         self.list.push(
             Commands::Display(commands.to_owned()),
@@ -454,7 +974,7 @@ impl GraphicsDisplay for SkiaGraphicsDisplay {
         z_order: ZOrder,
         protected: Option<bool>,
         needs_maintain: Option<bool>,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    ) -> Result<(), error::DisplayError> {
         self.list.modify(
             handle,
             Commands::Display(commands.to_owned()),
@@ -464,11 +984,44 @@ impl GraphicsDisplay for SkiaGraphicsDisplay {
         )
     }
 
+    #[inline]
+    fn label_command_group(&mut self, handle: CommandGroupHandle, label: Option<&'static str>) {
+        self.list.label(handle, label);
+    }
+
+    #[inline]
+    fn begin_update(&mut self) {
+        self.list.begin();
+    }
+
+    #[inline]
+    fn commit_update(&mut self) -> Result<(), error::DisplayError> {
+        self.list.commit()
+    }
+
+    #[inline]
+    fn debug_dump(&self) -> Vec<CommandGroupInfo> {
+        self.list.debug_dump()
+    }
+
     #[inline]
     fn maintain_command_group(&mut self, handle: CommandGroupHandle) {
         self.list.maintain(handle);
     }
 
+    fn set_eviction_policy(&mut self, policy: EvictionPolicy) -> Result<(), error::DisplayError> {
+        self.eviction_policy = policy;
+        Ok(())
+    }
+
+    fn set_eviction_callback(
+        &mut self,
+        callback: Option<EvictionCallback>,
+    ) -> Result<(), error::DisplayError> {
+        self.eviction_callback = callback;
+        Ok(())
+    }
+
     #[inline]
     fn remove_command_group(&mut self, handle: CommandGroupHandle) -> Option<Vec<DisplayCommand>> {
         self.list.remove(handle).and_then(|cmds| {
@@ -480,65 +1033,246 @@ impl GraphicsDisplay for SkiaGraphicsDisplay {
         })
     }
 
+    fn supports_backdrop_filter(&self) -> bool {
+        true
+    }
+
+    fn max_texture_size(&self) -> Option<u32> {
+        Some(self.context.max_texture_size() as _)
+    }
+
+    fn capabilities(&self) -> DisplayCapabilities {
+        DisplayCapabilities {
+            max_texture_size: self.max_texture_size(),
+            ..DisplayCapabilities::all()
+        }
+    }
+
     #[inline]
     fn before_exit(&mut self) {
         self.surface.flush()
     }
 
-    fn present(&mut self, cull: Option<Rect>) -> Result<(), error::DisplayError> {
+    fn set_color_correction(
+        &mut self,
+        correction: ColorCorrection,
+    ) -> Result<(), error::DisplayError> {
+        self.color_correction = correction;
+        Ok(self.update_corrected_surface(false)?)
+    }
+
+    fn set_virtual_resolution(
+        &mut self,
+        resolution: Option<VirtualResolution>,
+    ) -> Result<(), error::DisplayError> {
+        self.virtual_surface = match resolution {
+            Some(resolution) => Some((
+                resolution,
+                self.new_offscreen_surface(resolution.size, "virtual resolution")?,
+            )),
+            None => {
+                self.needs_full_redraw = true;
+                None
+            }
+        };
+        Ok(self.update_corrected_surface(false)?)
+    }
+
+    fn set_hole_callback(
+        &mut self,
+        id: u64,
+        callback: Option<HoleCallback>,
+    ) -> Result<(), error::DisplayError> {
+        match callback {
+            Some(callback) => self.hooks.holes.insert(id, callback),
+            None => self.hooks.holes.remove(&id),
+        };
+        Ok(())
+    }
+
+    fn read_pixels(&mut self, rect: PixelRect) -> Result<RasterImage, error::DisplayError> {
+        let (width, height) = self.size();
+        let rect =
+            rect.intersection(&PixelRect::from_size(euclid::Size2D::new(width as _, height as _)));
+        let rect = match rect {
+            Some(rect) if !rect.is_empty() => rect,
+            _ => return Err(error::DisplayError::InvalidRegion),
+        };
+
+        let size = (rect.size.width, rect.size.height);
+        let info = sk::ImageInfo::new(
+            sk::ISize::new(size.0 as _, size.1 as _),
+            sk::ColorType::RGBA8888,
+            sk::AlphaType::Unpremul,
+            convert_color_space(self.color_space),
+        );
+        let row_bytes = size.0 as usize * 4;
+        let mut data = vec![0; row_bytes * size.1 as usize];
+
+        if !self.surface.read_pixels(
+            &info,
+            &mut data,
+            row_bytes,
+            sk::IPoint::new(rect.origin.x as _, rect.origin.y as _),
+        ) {
+            return Err(error::SkiaError::UnknownError.into());
+        }
+
+        Ok(RasterImage {
+            data,
+            info: RasterImageInfo {
+                size,
+                format: RasterImageFormat::Rgba8,
+                color_space: self.color_space,
+            },
+        })
+    }
+
+    fn present(&mut self, cull: Option<Rect>) -> Result<PresentInfo, error::DisplayError> {
         let mut processed = Vec::new();
+        let mut info = PresentInfo::default();
+        let cull = if std::mem::take(&mut self.needs_full_redraw) { None } else { cull };
 
         {
+            let policy = self.eviction_policy;
             let cmds = self
                 .list
                 .flattened()
                 .into_iter()
                 .map(|(id, cmds)| (&cmds.0, &cmds.1, &cmds.2, &cmds.3, id))
-                .filter_map(|(cmd_group, bounds, protected, maintained, id)| {
+                .filter_map(|(cmd_group, bounds, protected, missed, id)| {
                     if cull.map(|cull| cull.intersects(bounds)).unwrap_or(true) {
-                        if let Some(maintained) = *maintained {
-                            if maintained {
-                                processed.push((true, id));
-                            } else {
-                                processed.push((false, id));
+                        if let Some(missed) = *missed {
+                            let evict = policy.should_evict(missed);
+                            processed.push((!evict, id));
+                            if evict {
                                 return None;
                             }
                         }
 
                         Some((cmd_group, protected))
                     } else {
+                        info.culled += 1;
                         None
                     }
                 });
             let resources = &self.resources;
-            let size = self.size();
-            let surface = &mut self.surface;
+            let surface_size = self.size();
+            let (surface, size) = match (&mut self.virtual_surface, &mut self.corrected_surface) {
+                (Some((resolution, surface)), _) => {
+                    (surface, (resolution.size.0 as i32, resolution.size.1 as i32))
+                }
+                (None, Some(surface)) => (surface, surface_size),
+                (None, None) => (&mut self.surface, surface_size),
+            };
+            let context = &mut self.context;
+            let hooks = &mut self.hooks;
+            // callbacks would draw into the surface, only for the offscreen frame to be copied over it.
+            hooks.deferred_holes =
+                if self.virtual_surface.is_some() || self.corrected_surface.is_some() {
+                    Some(Vec::new())
+                } else {
+                    None
+                };
+            let mut drawn = 0;
             for cmd_group in cmds {
                 let count = if *cmd_group.1 { Some(surface.canvas().save()) } else { None };
 
-                draw_command_group(cmd_group.0, surface, resources, size)?;
+                draw_command_group(cmd_group.0, surface, context, hooks, resources, size)?;
 
                 if let Some(count) = count {
                     surface.canvas().restore_to_count(count);
                 }
+
+                drawn += 1;
+            }
+            info.drawn = drawn;
+
+            // the offscreen frame replaces the surface entirely, so the correction is applied exactly once per pixel.
+            let correction = &self.color_correction;
+            let mut paint = sk::Paint::default();
+            paint.set_blend_mode(sk::BlendMode::Src);
+            if !correction.is_identity() {
+                if let Some(correction) = convert_color_correction(correction) {
+                    paint.set_image_filter(correction);
+                }
+            }
+
+            let surface = &mut self.surface;
+            if let Some((resolution, ref mut virtual_surface)) = self.virtual_surface {
+                let frame = virtual_surface.image_snapshot();
+                paint.set_filter_quality(match resolution.filter {
+                    ScaleFilter::Nearest => sk::FilterQuality::None,
+                    ScaleFilter::Linear => sk::FilterQuality::Low,
+                });
+                let canvas = surface.canvas();
+                canvas.clear(convert_color(correction.apply(resolution.letterbox)).to_color());
+                canvas.draw_image_rect(
+                    frame,
+                    None,
+                    &convert_rect(
+                        &resolution.placement((surface_size.0 as u32, surface_size.1 as u32)),
+                    ),
+                    &paint,
+                );
+            } else if let Some(ref mut corrected_surface) = self.corrected_surface {
+                let frame = corrected_surface.image_snapshot();
+                surface.canvas().draw_image(frame, sk::Point::new(0.0, 0.0), Some(&paint));
+            }
+
+            let deferred_holes = self.hooks.deferred_holes.take().unwrap_or_default();
+            for mut hole in deferred_holes {
+                if let Some((resolution, _)) = self.virtual_surface {
+                    hole.rect = resolution
+                        .to_surface(hole.rect, (surface_size.0 as u32, surface_size.1 as u32))
+                        .round_out();
+                }
+                if let Some(callback) = self.hooks.holes.get_mut(&hole.id) {
+                    surface.flush();
+                    callback(hole);
+                    self.context.reset(None);
+                }
             }
 
+            let flush_start = std::time::Instant::now();
             surface.flush();
+            info.gpu_wait = flush_start.elapsed();
         }
 
         for (ok, id) in processed {
-            if let Some(z) = self.list.z_lookup.get(&CommandGroupHandle(id)) {
-                if let Some(z_list) = self.list.command_groups.get_mut(z) {
-                    if ok {
-                        z_list.get_mut(&id).unwrap().3 = Some(false);
-                    } else {
-                        z_list.remove(&id);
+            let handle = CommandGroupHandle(id);
+            if ok {
+                if let Some(z) = self.list.z_lookup.get(&handle) {
+                    if let Some(z_list) = self.list.command_groups.get_mut(z) {
+                        let missed = &mut z_list.get_mut(&id).unwrap().3;
+                        *missed = missed.map(|missed| missed.saturating_add(1));
+                    }
+                }
+            } else {
+                let z_order = self.list.z_lookup.get(&handle).copied();
+                let label = self.list.labels.get(&handle).copied();
+                if let Some((_, bounds, protected, _)) = z_order
+                    .and_then(|z| self.list.command_groups.get(&z))
+                    .and_then(|z_list| z_list.get(&id))
+                {
+                    let evicted = CommandGroupInfo {
+                        handle,
+                        label,
+                        z_order: z_order.unwrap(),
+                        bounds: *bounds,
+                        protected: *protected,
+                        maintained: Some(false),
+                    };
+                    self.list.remove_now(handle);
+                    info.evicted += 1;
+                    if let Some(callback) = &mut self.eviction_callback {
+                        callback(evicted);
                     }
                 }
             }
         }
 
-        Ok(())
+        Ok(info)
     }
 }
 
@@ -546,6 +1280,20 @@ fn convert_color(color: Color) -> sk::Color4f {
     sk::Color4f::new(color.red, color.green, color.blue, color.alpha)
 }
 
+/// Skia color space of surfaces and raw images.
+///
+/// Since every surface is tagged, colors are always passed to Skia in (extended) sRGB and converted by Skia into the
+/// color space of the surface.
+fn convert_color_space(color_space: ColorSpace) -> Option<sk::ColorSpace> {
+    match color_space {
+        ColorSpace::Srgb => Some(sk::ColorSpace::new_srgb()),
+        ColorSpace::LinearSrgb => Some(sk::ColorSpace::new_srgb_linear()),
+        ColorSpace::DisplayP3 => {
+            sk::ColorSpace::new_rgb(&sk::named_transfer_fn::SRGB, &sk::named_gamut::DISPLAY_P3)
+        }
+    }
+}
+
 fn convert_point(point: Point) -> sk::Point {
     sk::Point::new(point.x, point.y)
 }
@@ -556,17 +1304,25 @@ fn apply_color(color: &StyleColor, paint: &mut sk::Paint) -> Result<(), error::S
             // we can afford to "make" the SRGB color space every time; it's actually a singleton in the C++ Skia code.
             paint.set_color4f(convert_color(*color), &sk::ColorSpace::new_srgb());
         }
+        StyleColor::TaggedColor(ref color, tagged) => {
+            paint.set_color4f(convert_color(tagged.to_srgb(*color)), &sk::ColorSpace::new_srgb());
+        }
         StyleColor::LinearGradient(ref gradient) => {
             let (colors, stops): (Vec<_>, Vec<_>) = gradient
                 .stops
                 .iter()
-                .map(|stop| (convert_color(stop.1).to_color(), stop.0 as sk::scalar))
+                .map(|stop| {
+                    (convert_color(gradient.color_space.to_srgb(stop.1)), stop.0 as sk::scalar)
+                })
                 .unzip();
 
             paint.set_shader(
                 sk::gradient_shader::linear(
                     (convert_point(gradient.start), convert_point(gradient.end)),
-                    sk::gradient_shader::GradientShaderColors::Colors(&colors[..]),
+                    sk::gradient_shader::GradientShaderColors::ColorsInSpace(
+                        &colors[..],
+                        Some(sk::ColorSpace::new_srgb()),
+                    ),
                     &stops[..],
                     sk::TileMode::default(),
                     None,
@@ -579,13 +1335,18 @@ fn apply_color(color: &StyleColor, paint: &mut sk::Paint) -> Result<(), error::S
             let (colors, stops): (Vec<_>, Vec<_>) = gradient
                 .stops
                 .iter()
-                .map(|stop| (convert_color(stop.1).to_color(), stop.0 as sk::scalar))
+                .map(|stop| {
+                    (convert_color(gradient.color_space.to_srgb(stop.1)), stop.0 as sk::scalar)
+                })
                 .unzip();
 
             paint.set_shader(sk::gradient_shader::radial(
                 convert_point(gradient.start),
                 (gradient.end - gradient.start).length(),
-                sk::gradient_shader::GradientShaderColors::Colors(&colors[..]),
+                sk::gradient_shader::GradientShaderColors::ColorsInSpace(
+                    &colors[..],
+                    Some(sk::ColorSpace::new_srgb()),
+                ),
                 &stops[..],
                 sk::TileMode::default(),
                 None,
@@ -613,36 +1374,80 @@ fn convert_line_join(join: LineJoin) -> sk::PaintJoin {
     }
 }
 
-fn apply_filter_to_paint(paint: &mut sk::Paint, filter: Option<Filter>) {
-    if let Some(filter) = filter {
-        match filter {
-            Filter::Blur(sigma_x, sigma_y) => {
-                paint.set_image_filter(sk::image_filters::blur(
-                    (sigma_x, sigma_y),
-                    sk::TileMode::Decal,
-                    None,
-                    None,
-                ));
+/// Chains filters into a single image filter, each taking the output of the previous one as input.
+fn convert_filters(
+    filters: &[FilterOp],
+    tile_mode: sk::TileMode,
+    crop_rect: Option<&sk::IRect>,
+) -> Option<sk::ImageFilter> {
+    filters.iter().try_fold(None, |input: Option<sk::ImageFilter>, filter| {
+        Some(Some(match filter {
+            FilterOp::Blur(sigma_x, sigma_y) => {
+                sk::image_filters::blur((*sigma_x, *sigma_y), tile_mode, input, crop_rect)?
             }
-            Filter::Invert => {
-                let color_matrix = sk::ColorMatrix::new(
-                    -1.0, 0.0, 0.0, 1.0, 0.0, 0.0, -1.0, 0.0, 1.0, 0.0, 0.0, 0.0, -1.0, 1.0, 0.0,
-                    1.0, 1.0, 1.0, 1.0, 0.0,
-                );
+            FilterOp::DropShadow { offset, sigma, color } => sk::image_filters::drop_shadow(
+                sk::Vector::new(offset.x, offset.y),
+                *sigma,
+                convert_color(*color).to_color(),
+                input,
+                crop_rect,
+            )?,
+            filter => sk::image_filters::color_filter(
+                sk::ColorFilters::matrix_row_major(&filter.color_matrix()?),
+                input,
+                crop_rect,
+            )?,
+        }))
+    })?
+}
 
-                paint.set_color_filter(sk::ColorFilters::matrix(&color_matrix));
-            }
+fn convert_raster_image_info(info: &RasterImageInfo) -> sk::ImageInfo {
+    sk::ImageInfo::new(
+        sk::ISize::new(info.size.0 as _, info.size.1 as _),
+        match info.format {
+            RasterImageFormat::Rgba8 => sk::ColorType::RGBA8888,
+            RasterImageFormat::Bgra8 => sk::ColorType::BGRA8888,
+        },
+        sk::AlphaType::Unpremul,
+        convert_color_space(info.color_space),
+    )
+}
+
+fn convert_color_correction(correction: &ColorCorrection) -> Option<sk::ImageFilter> {
+    let gamma = if correction.gamma != 1.0 {
+        let mut table = [0; 256];
+        for (i, value) in table.iter_mut().enumerate() {
+            *value = (correction.apply_gamma(i as f32 / 255.0) * 255.0).round() as u8;
         }
+        Some(sk::image_filters::color_filter(sk::ColorFilters::table(&table)?, None, None)?)
+    } else {
+        None
+    };
+    sk::image_filters::color_filter(
+        sk::ColorFilters::matrix_row_major(&correction.color_matrix()),
+        gamma,
+        None,
+    )
+}
+
+fn apply_filter_to_paint(paint: &mut sk::Paint, filters: &[FilterOp]) {
+    if let Some(filter) = convert_filters(filters, sk::TileMode::Decal, None) {
+        paint.set_image_filter(filter);
     }
 }
 
 fn convert_paint(
     gdpaint: &GraphicsDisplayPaint,
-    filter: Option<Filter>,
+    filters: &[FilterOp],
 ) -> Result<sk::Paint, error::SkiaError> {
     let mut paint = sk::Paint::default();
 
     match gdpaint {
+        GraphicsDisplayPaint::Blend(ref inner, mode) => {
+            let mut paint = convert_paint(inner, filters)?;
+            paint.set_blend_mode(convert_blend_mode(*mode));
+            return Ok(paint);
+        }
         GraphicsDisplayPaint::Fill(ref color) => {
             paint.set_anti_alias(true);
 
@@ -661,11 +1466,40 @@ fn convert_paint(
         }
     }
 
-    apply_filter_to_paint(&mut paint, filter);
+    apply_filter_to_paint(&mut paint, filters);
 
     Ok(paint)
 }
 
+fn convert_blend_mode(mode: BlendMode) -> sk::BlendMode {
+    match mode {
+        BlendMode::Normal => sk::BlendMode::SrcOver,
+        BlendMode::Multiply => sk::BlendMode::Multiply,
+        BlendMode::Screen => sk::BlendMode::Screen,
+        BlendMode::Overlay => sk::BlendMode::Overlay,
+        BlendMode::Darken => sk::BlendMode::Darken,
+        BlendMode::Lighten => sk::BlendMode::Lighten,
+        BlendMode::ColorDodge => sk::BlendMode::ColorDodge,
+        BlendMode::ColorBurn => sk::BlendMode::ColorBurn,
+        BlendMode::HardLight => sk::BlendMode::HardLight,
+        BlendMode::SoftLight => sk::BlendMode::SoftLight,
+        BlendMode::Difference => sk::BlendMode::Difference,
+        BlendMode::Exclusion => sk::BlendMode::Exclusion,
+        BlendMode::Hue => sk::BlendMode::Hue,
+        BlendMode::Saturation => sk::BlendMode::Saturation,
+        BlendMode::Color => sk::BlendMode::Color,
+        BlendMode::Luminosity => sk::BlendMode::Luminosity,
+        BlendMode::Plus => sk::BlendMode::Plus,
+    }
+}
+
+fn convert_clip_op(op: ClipOp) -> sk::ClipOp {
+    match op {
+        ClipOp::Intersect => sk::ClipOp::Intersect,
+        ClipOp::Difference => sk::ClipOp::Difference,
+    }
+}
+
 fn convert_rect(rect: &Rect) -> sk::Rect {
     sk::Rect::from_xywh(rect.origin.x, rect.origin.y, rect.size.width, rect.size.height)
 }
@@ -729,12 +1563,13 @@ fn convert_display_text(
     }
 }
 
-fn apply_clip(canvas: &mut sk::Canvas, clip: &DisplayClip) {
+fn apply_clip(canvas: &mut sk::Canvas, clip: &DisplayClip, op: ClipOp) {
+    let op = convert_clip_op(op);
     match clip {
         DisplayClip::Rectangle { ref rect, antialias } => {
-            canvas.clip_rect(convert_rect(rect), None, *antialias);
+            canvas.clip_rect(convert_rect(rect), op, *antialias);
         }
-        DisplayClip::RoundRectangle { ref rect, radii } => {
+        DisplayClip::RoundRectangle { ref rect, radii, antialias } => {
             canvas.clip_rrect(
                 &sk::RRect::new_rect_radii(
                     convert_rect(rect),
@@ -745,11 +1580,11 @@ fn apply_clip(canvas: &mut sk::Canvas, clip: &DisplayClip) {
                         sk::Vector::new(radii[3], radii[3]),
                     ],
                 ),
-                None,
-                true,
+                op,
+                *antialias,
             );
         }
-        DisplayClip::Ellipse { ref center, radii } => {
+        DisplayClip::Ellipse { ref center, radii, antialias } => {
             let mut path = sk::Path::new();
             path.add_oval(
                 convert_rect(&Rect::new(
@@ -759,20 +1594,37 @@ fn apply_clip(canvas: &mut sk::Canvas, clip: &DisplayClip) {
                 None,
             );
 
-            canvas.clip_path(&path, None, true);
+            canvas.clip_path(&path, op, *antialias);
         }
-        DisplayClip::Path { path, is_closed } => {
+        DisplayClip::Path { path, is_closed, antialias } => {
             let path = convert_path(path, *is_closed);
-            canvas.clip_path(&path, None, true);
+            canvas.clip_path(&path, op, *antialias);
         }
     };
 }
 
+/// Draws text and its decoration lines with a paint, offset from the position of the text.
+fn draw_decorated_text(
+    canvas: &mut sk::Canvas,
+    blob: &sk::TextBlob,
+    item: &TextDisplayItem,
+    decorations: &[Rect],
+    offset: Vector,
+    paint: &sk::Paint,
+) {
+    canvas.draw_text_blob(blob, convert_point(item.bottom_left + offset), paint);
+    for line in decorations {
+        canvas.draw_rect(convert_rect(&line.translate(offset)), paint);
+    }
+}
+
 // The meat of this module.
 // If there are any drawing bugs, they probably happen here.
 fn draw_command_group(
     cmds: &Commands,
     surface: &mut sk::Surface,
+    context: &mut sk::gpu::Context,
+    hooks: &mut DrawHooks,
     resources: &HashMap<u64, Resource>,
     size: (i32, i32),
 ) -> Result<(), error::DisplayError> {
@@ -780,12 +1632,12 @@ fn draw_command_group(
         Commands::Display(cmds) => {
             for cmd in cmds {
                 match cmd {
-                    DisplayCommand::Item(item, filter) => match item {
+                    DisplayCommand::Item(item, filters) => match item {
                         DisplayItem::Graphics(ref item) => match item {
                             GraphicsDisplayItem::Line { a, b, stroke } => {
                                 let paint = convert_paint(
                                     &GraphicsDisplayPaint::Stroke((*stroke).clone()),
-                                    *filter,
+                                    filters,
                                 )
                                 .map_err(|e| error::DisplayError::InternalError(e.into()))?;
                                 surface.canvas().draw_line(
@@ -795,12 +1647,12 @@ fn draw_command_group(
                                 );
                             }
                             GraphicsDisplayItem::Rectangle { rect, paint } => {
-                                let paint = convert_paint(paint, *filter)
+                                let paint = convert_paint(paint, filters)
                                     .map_err(|e| error::DisplayError::InternalError(e.into()))?;
                                 surface.canvas().draw_rect(&convert_rect(rect), &paint);
                             }
                             GraphicsDisplayItem::RoundRectangle { rect, radii, paint } => {
-                                let paint = convert_paint(paint, *filter)
+                                let paint = convert_paint(paint, filters)
                                     .map_err(|e| error::DisplayError::InternalError(e.into()))?;
                                 surface.canvas().draw_rrect(
                                     sk::RRect::new_rect_radii(
@@ -818,7 +1670,7 @@ fn draw_command_group(
                             GraphicsDisplayItem::Ellipse { paint, .. } => {
                                 surface.canvas().draw_oval(
                                     convert_rect(&item.bounds()),
-                                    &convert_paint(paint, *filter).map_err(|e| {
+                                    &convert_paint(paint, filters).map_err(|e| {
                                         error::DisplayError::InternalError(e.into())
                                     })?,
                                 );
@@ -834,11 +1686,12 @@ fn draw_command_group(
                                         let mut paint = sk::Paint::default();
                                         paint.set_filter_quality(sk::FilterQuality::Medium); // TODO(jazzfool): perhaps we can expose the image filter quality?
 
-                                        apply_filter_to_paint(&mut paint, *filter);
+                                        apply_filter_to_paint(&mut paint, filters);
 
                                         apply_clip(
                                             surface.canvas(),
                                             &DisplayClip::Rectangle { rect: *dst, antialias: true },
+                                            ClipOp::Intersect,
                                         );
 
                                         let o_src = src.map(|src_rect| convert_rect(&src_rect));
@@ -862,11 +1715,56 @@ fn draw_command_group(
                             GraphicsDisplayItem::Path { path, is_closed, paint } => {
                                 surface.canvas().draw_path(
                                     &convert_path(path, *is_closed),
-                                    &convert_paint(paint, *filter).map_err(|e| {
+                                    &convert_paint(paint, filters).map_err(|e| {
                                         error::DisplayError::InternalError(e.into())
                                     })?,
                                 );
                             }
+                            GraphicsDisplayItem::Mesh { vertices, indices } => {
+                                if indices.iter().any(|&i| i as usize >= vertices.len()) {
+                                    continue;
+                                }
+
+                                let mut paint = sk::Paint::default();
+                                paint.set_anti_alias(true);
+                                apply_filter_to_paint(&mut paint, filters);
+
+                                let convert_vertex = |(point, color): &(Point, Color)| {
+                                    (convert_point(*point), convert_color(*color).to_color())
+                                };
+                                let indices = &indices[..indices.len() / 3 * 3];
+                                // Skia indexes vertices with 16 bits, so larger meshes are drawn unindexed.
+                                let vertices = if vertices.len() <= u16::MAX as usize + 1 {
+                                    let (positions, colors): (Vec<_>, Vec<_>) =
+                                        vertices.iter().map(convert_vertex).unzip();
+                                    let indices: Vec<_> =
+                                        indices.iter().map(|&i| i as u16).collect();
+                                    sk::Vertices::new_copy(
+                                        sk::VertexMode::Triangles,
+                                        &positions,
+                                        &[],
+                                        &colors,
+                                        Some(&indices[..]),
+                                    )
+                                } else {
+                                    let (positions, colors): (Vec<_>, Vec<_>) = indices
+                                        .iter()
+                                        .map(|&i| convert_vertex(&vertices[i as usize]))
+                                        .unzip();
+                                    sk::Vertices::new_copy(
+                                        sk::VertexMode::Triangles,
+                                        &positions,
+                                        &[],
+                                        &colors,
+                                        None,
+                                    )
+                                };
+                                surface.canvas().draw_vertices(
+                                    &vertices,
+                                    sk::BlendMode::Modulate,
+                                    &paint,
+                                );
+                            }
                         },
                         DisplayItem::Text(ref item) => {
                             if item.text.len() == 0 {
@@ -879,21 +1777,68 @@ fn draw_command_group(
                                     .get(id)
                                     .ok_or(error::DisplayError::InvalidResource(*id))?
                                 {
-                                    let paint = convert_paint(
-                                        &GraphicsDisplayPaint::Fill(item.color.clone()),
-                                        *filter,
+                                    let blob = convert_display_text(
+                                        &item.text,
+                                        sk::Font::new(typeface.clone(), item.size),
                                     )
                                     .map_err(|e| error::DisplayError::InternalError(e.into()))?;
+                                    let decorations = item.decoration_rects().map_err(|e| {
+                                        error::DisplayError::InternalError(e.into())
+                                    })?;
 
-                                    surface.canvas().draw_text_blob(
-                                        &convert_display_text(
-                                            &item.text,
-                                            sk::Font::new(typeface.clone(), item.size),
+                                    if let Some(ref shadow) = item.shadow {
+                                        let mut shadow_filters = filters.clone();
+                                        if shadow.blur > 0.0 {
+                                            shadow_filters
+                                                .push(FilterOp::Blur(shadow.blur, shadow.blur));
+                                        }
+                                        let paint = convert_paint(
+                                            &GraphicsDisplayPaint::Fill(shadow.color.into()),
+                                            &shadow_filters,
                                         )
                                         .map_err(|e| {
                                             error::DisplayError::InternalError(e.into())
-                                        })?,
-                                        convert_point(item.bottom_left),
+                                        })?;
+                                        draw_decorated_text(
+                                            surface.canvas(),
+                                            &blob,
+                                            item,
+                                            &decorations,
+                                            shadow.offset,
+                                            &paint,
+                                        );
+                                    }
+
+                                    if let Some(ref stroke) = item.stroke {
+                                        let paint = convert_paint(
+                                            &GraphicsDisplayPaint::Stroke(stroke.clone()),
+                                            filters,
+                                        )
+                                        .map_err(|e| {
+                                            error::DisplayError::InternalError(e.into())
+                                        })?;
+                                        draw_decorated_text(
+                                            surface.canvas(),
+                                            &blob,
+                                            item,
+                                            &decorations,
+                                            Vector::zero(),
+                                            &paint,
+                                        );
+                                    }
+
+                                    let paint = convert_paint(
+                                        &GraphicsDisplayPaint::Fill(item.color.clone()),
+                                        filters,
+                                    )
+                                    .map_err(|e| error::DisplayError::InternalError(e.into()))?;
+
+                                    draw_decorated_text(
+                                        surface.canvas(),
+                                        &blob,
+                                        item,
+                                        &decorations,
+                                        Vector::zero(),
                                         &paint,
                                     );
                                 }
@@ -903,67 +1848,66 @@ fn draw_command_group(
                                 ));
                             }
                         }
-                    },
-                    DisplayCommand::BackdropFilter(ref clip, ref filter) => {
-                        let count = surface.canvas().save();
-
-                        apply_clip(surface.canvas(), clip);
-
-                        let bounds = clip.bounds();
-
-                        match filter {
-                            Filter::Blur(sigma_x, sigma_y) => {
-                                // TODO(jazzfool): cache blur filter (figure out a way to cache by floats)
-                                if let Some(ref _snapshot_rect) =
-                                    bounds.round_out().intersection(&Rect::new(
-                                        Point::default(),
-                                        Size::new(size.0 as _, size.1 as _),
-                                    ))
+                        DisplayItem::TextBlob(ref item) => {
+                            if let ResourceReference::TextBlob(ref id) = item.blob {
+                                if let Resource::TextBlob(ref blob) = resources
+                                    .get(id)
+                                    .ok_or(error::DisplayError::InvalidResource(*id))?
                                 {
-                                    let blur = sk::image_filters::blur(
-                                        (*sigma_x, *sigma_y),
-                                        sk::TileMode::Clamp,
-                                        None,
-                                        &convert_rect(&bounds).round(),
+                                    let paint = convert_paint(
+                                        &GraphicsDisplayPaint::Fill(item.color.clone()),
+                                        filters,
                                     )
-                                    .ok_or_else(|| {
-                                        error::DisplayError::InternalError(Box::new(
-                                            error::SkiaError::UnknownError,
-                                        ))
-                                    })?;
+                                    .map_err(|e| error::DisplayError::InternalError(e.into()))?;
 
-                                    surface
-                                        .canvas()
-                                        .save_layer(&sk::SaveLayerRec::default().backdrop(&blur));
+                                    surface.canvas().draw_text_blob(
+                                        blob,
+                                        convert_point(item.bottom_left),
+                                        &paint,
+                                    );
                                 }
+                            } else {
+                                return Err(error::DisplayError::MismatchedResource(
+                                    item.blob.id(),
+                                ));
                             }
-                            Filter::Invert => {
-                                let mut paint = sk::Paint::default();
+                        }
+                    },
+                    DisplayCommand::BackdropFilter(ref clip, ref filters) => {
+                        let count = surface.canvas().save();
 
-                                let color_matrix = sk::ColorMatrix::new(
-                                    -1.0, 0.0, 0.0, 1.0, 0.0, 0.0, -1.0, 0.0, 1.0, 0.0, 0.0, 0.0,
-                                    -1.0, 1.0, 0.0, 1.0, 1.0, 1.0, 1.0, 0.0,
-                                );
+                        apply_clip(surface.canvas(), clip, ClipOp::Intersect);
 
-                                paint.set_color_filter(sk::ColorFilters::matrix(&color_matrix));
+                        let bounds = clip.bounds();
 
+                        // TODO(jazzfool): cache filters (figure out a way to cache by floats)
+                        if bounds.round_out().intersects(&Rect::new(
+                            Point::default(),
+                            Size::new(size.0 as _, size.1 as _),
+                        )) {
+                            if let Some(backdrop) = convert_filters(
+                                filters,
+                                sk::TileMode::Clamp,
+                                Some(&convert_rect(&bounds).round()),
+                            ) {
                                 surface
                                     .canvas()
-                                    .save_layer(&sk::SaveLayerRec::default().paint(&paint));
+                                    .save_layer(&sk::SaveLayerRec::default().backdrop(&backdrop));
                             }
                         }
 
                         surface.canvas().restore_to_count(count);
                     }
-                    DisplayCommand::Clip(ref clip) => {
-                        apply_clip(surface.canvas(), clip);
+                    DisplayCommand::Clip(ref clip, op) => {
+                        apply_clip(surface.canvas(), clip, *op);
                     }
                     DisplayCommand::Save => {
                         surface.canvas().save();
                     }
-                    DisplayCommand::SaveLayer(opacity) => {
+                    DisplayCommand::SaveLayer(opacity, mode) => {
                         let mut paint = sk::Paint::default();
                         paint.set_alpha_f(*opacity);
+                        paint.set_blend_mode(convert_blend_mode(*mode));
 
                         surface.canvas().save_layer(&sk::SaveLayerRec::default().paint(&paint));
                     }
@@ -982,6 +1926,59 @@ fn draw_command_group(
                     DisplayCommand::Clear(ref color) => {
                         surface.canvas().clear(convert_color(*color).to_color());
                     }
+                    DisplayCommand::Hole(id, ref rect) => {
+                        let canvas = surface.canvas();
+                        canvas.save();
+                        canvas.clip_rect(convert_rect(rect), sk::ClipOp::Intersect, false);
+                        canvas.clear(sk::Color::TRANSPARENT);
+                        let device_rect = canvas.device_clip_bounds();
+                        canvas.restore();
+
+                        let hole = device_rect.map(|device_rect| HoleInfo {
+                            id: *id,
+                            rect: Rect::new(
+                                Point::new(device_rect.left as _, device_rect.top as _),
+                                Size::new(device_rect.width() as _, device_rect.height() as _),
+                            ),
+                        });
+                        match (hole, &mut hooks.deferred_holes) {
+                            (Some(hole), Some(deferred)) if hooks.holes.contains_key(id) => {
+                                deferred.push(hole)
+                            }
+                            (Some(hole), None) => {
+                                if let Some(callback) = hooks.holes.get_mut(id) {
+                                    // everything beneath the hole has to reach the surface before the callback
+                                    // draws into it.
+                                    surface.flush();
+                                    callback(hole);
+                                    // the callback has most likely changed the GPU state cached by Skia.
+                                    context.reset(None);
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                    DisplayCommand::Extension(ref command) => {
+                        match hooks.interpreters.get_mut(command.name()) {
+                            Some(interpreter) => interpreter(
+                                command.as_ref(),
+                                surface.canvas(),
+                                ResourceView { resources },
+                            ),
+                            None => {
+                                let count = surface.canvas().save();
+                                draw_command_group(
+                                    &Commands::Display(command.fallback()),
+                                    surface,
+                                    context,
+                                    hooks,
+                                    resources,
+                                    size,
+                                )?;
+                                surface.canvas().restore_to_count(count);
+                            }
+                        }
+                    }
                 }
             }
         }
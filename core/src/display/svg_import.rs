@@ -0,0 +1,395 @@
+//! Loading of SVG files into display commands, so that vector assets (e.g. icons) don't have to be rasterized
+//! up front.
+//!
+//! The SVG is parsed and simplified with [`usvg`](usvg), which resolves styles, `use` elements, units and
+//! shapes into paths. Paths, solid and gradient fills and strokes, group opacity, blend modes and clip paths
+//! are converted; images, text, patterns, masks, filters and dashes are ignored.
+//!
+//! ```ignore
+//! let icon = SvgImage::from_file("assets/close.svg")?;
+//!
+//! // in draw:
+//! let rect = Rect::new(Point::new(4.0, 4.0), Size::new(16.0, 16.0));
+//! self.command_group.push(display, &icon.commands_in(rect), Default::default(), None, None)?;
+//! ```
+
+use {
+    super::{picture::Picture, *},
+    crate::error::SvgError,
+    std::path::Path,
+};
+
+/// A vector image loaded from an SVG.
+#[derive(Clone)]
+pub struct SvgImage {
+    size: Size,
+    commands: Vec<DisplayCommand>,
+}
+
+impl SvgImage {
+    /// Parses an SVG (or gzip-compressed SVGZ) document.
+    pub fn from_data(data: &[u8]) -> Result<Self, SvgError> {
+        Ok(Self::from_tree(&usvg::Tree::from_data(data, &Default::default())?))
+    }
+
+    /// Loads and parses an SVG file.
+    ///
+    /// Relative references (e.g. to images) are ignored, so the directory of the file doesn't matter.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, SvgError> {
+        Self::from_data(&std::fs::read(path)?)
+    }
+
+    /// Converts an already parsed SVG.
+    pub fn from_tree(tree: &usvg::Tree) -> Self {
+        let mut commands = Vec::new();
+        convert_group(tree.root(), &mut commands);
+        SvgImage { size: Size::new(tree.size().width(), tree.size().height()), commands }
+    }
+
+    /// Returns the size of the image, as given by the `width`/`height` (or `viewBox`) of the SVG.
+    #[inline]
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// Returns the display commands, which draw the image at its own size at the origin.
+    #[inline]
+    pub fn commands(&self) -> &[DisplayCommand] {
+        &self.commands
+    }
+
+    /// Returns the display commands drawing the image stretched into `rect`.
+    ///
+    /// The commands leave the draw state untouched.
+    pub fn commands_in(&self, rect: Rect) -> Vec<DisplayCommand> {
+        let mut commands = Vec::with_capacity(self.commands.len() + 4);
+        commands.push(DisplayCommand::Save);
+        commands.push(DisplayCommand::Translate(rect.origin.to_vector()));
+        commands.push(DisplayCommand::Scale(Vector::new(
+            rect.size.width / self.size.width,
+            rect.size.height / self.size.height,
+        )));
+        commands.extend(self.commands.iter().cloned());
+        commands.push(DisplayCommand::Restore);
+        commands
+    }
+
+    /// Returns a picture of the image at its own size, for cheap reuse.
+    pub fn to_picture(&self) -> Picture {
+        Picture::new(self.commands.clone())
+    }
+}
+
+fn convert_group(group: &usvg::Group, commands: &mut Vec<DisplayCommand>) {
+    let blend_mode = convert_blend_mode(group.blend_mode());
+    let opacity = group.opacity().get();
+    let clip = group.clip_path().map(|clip| clip_path(clip, group.abs_transform()));
+    let needs_save = opacity < 1.0 || blend_mode != BlendMode::Normal || clip.is_some();
+
+    if opacity < 1.0 || blend_mode != BlendMode::Normal {
+        commands.push(DisplayCommand::SaveLayer(opacity, blend_mode));
+    } else if needs_save {
+        commands.push(DisplayCommand::Save);
+    }
+    if let Some(path) = clip {
+        commands.push(DisplayCommand::Clip(
+            DisplayClip::Path { path, is_closed: true, antialias: true },
+            ClipOp::Intersect,
+        ));
+    }
+
+    for node in group.children() {
+        match node {
+            usvg::Node::Group(group) => convert_group(group, commands),
+            usvg::Node::Path(path) => convert_path(path, commands),
+            usvg::Node::Image(_) | usvg::Node::Text(_) => {}
+        }
+    }
+
+    if needs_save {
+        commands.push(DisplayCommand::Restore);
+    }
+}
+
+/// Combines the paths of a clip path into a single path (in absolute coordinates).
+///
+/// Nested clip paths and clip paths on the children are ignored.
+fn clip_path(clip: &usvg::ClipPath, transform: usvg::Transform) -> VectorPath {
+    fn collect(group: &usvg::Group, transform: usvg::Transform, path: &mut VectorPath) {
+        for node in group.children() {
+            match node {
+                usvg::Node::Group(group) => collect(group, transform, path),
+                usvg::Node::Path(node) => {
+                    let transform = transform.pre_concat(node.abs_transform());
+                    for (sub_path, _) in convert_segments(node.data(), transform) {
+                        path.extend(sub_path);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut path = Vec::new();
+    collect(clip.root(), transform.pre_concat(clip.transform()), &mut path);
+    path
+}
+
+fn convert_path(path: &usvg::Path, commands: &mut Vec<DisplayCommand>) {
+    if !path.is_visible() {
+        return;
+    }
+
+    let transform = path.abs_transform();
+    let sub_paths = convert_segments(path.data(), transform);
+
+    let fill = path.fill().map(|fill| {
+        GraphicsDisplayPaint::Fill(convert_paint(fill.paint(), fill.opacity().get(), transform))
+    });
+    let stroke = path.stroke().map(|stroke| {
+        GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+            color: convert_paint(stroke.paint(), stroke.opacity().get(), transform),
+            // strokes are scaled along with the path.
+            thickness: stroke.width().get()
+                * (transform.sx * transform.sy - transform.kx * transform.ky).abs().sqrt(),
+            cap: match stroke.linecap() {
+                usvg::LineCap::Butt => LineCap::Flat,
+                usvg::LineCap::Round => LineCap::Round,
+                usvg::LineCap::Square => LineCap::Square,
+            },
+            join: match stroke.linejoin() {
+                usvg::LineJoin::Miter | usvg::LineJoin::MiterClip => LineJoin::Miter,
+                usvg::LineJoin::Round => LineJoin::Round,
+                usvg::LineJoin::Bevel => LineJoin::Bevel,
+            },
+            miter_limit: stroke.miterlimit().get(),
+            antialias: true,
+        })
+    });
+
+    let paints = match path.paint_order() {
+        usvg::PaintOrder::FillAndStroke => [fill, stroke],
+        usvg::PaintOrder::StrokeAndFill => [stroke, fill],
+    };
+    for paint in paints.iter().flatten() {
+        let push = |commands: &mut Vec<DisplayCommand>, path: VectorPath, is_closed: bool| {
+            commands.push(DisplayCommand::Item(
+                DisplayItem::Graphics(GraphicsDisplayItem::Path {
+                    path,
+                    is_closed,
+                    paint: paint.clone(),
+                }),
+                vec![],
+            ))
+        };
+        match paint {
+            // fills need every sub-path at once, for holes.
+            GraphicsDisplayPaint::Fill(_) => {
+                push(commands, sub_paths.iter().flat_map(|(path, _)| path.clone()).collect(), true)
+            }
+            _ => {
+                for (path, is_closed) in &sub_paths {
+                    push(commands, path.clone(), *is_closed);
+                }
+            }
+        }
+    }
+}
+
+/// Splits a path into sub-paths (along with whether each one is closed), in absolute coordinates.
+fn convert_segments(
+    path: &usvg::tiny_skia_path::Path,
+    transform: usvg::Transform,
+) -> Vec<(VectorPath, bool)> {
+    use usvg::tiny_skia_path::PathSegment;
+
+    let point = |mut p: usvg::tiny_skia_path::Point| {
+        transform.map_point(&mut p);
+        Point::new(p.x, p.y)
+    };
+
+    let mut sub_paths: Vec<(VectorPath, bool)> = Vec::new();
+    for segment in path.segments() {
+        let event = match segment {
+            PathSegment::MoveTo(to) => {
+                sub_paths.push((Vec::new(), false));
+                VectorPathEvent::MoveTo { to: point(to) }
+            }
+            PathSegment::LineTo(to) => VectorPathEvent::LineTo { to: point(to) },
+            PathSegment::QuadTo(control, to) => {
+                VectorPathEvent::QuadTo { control: point(control), to: point(to) }
+            }
+            PathSegment::CubicTo(c1, c2, to) => {
+                VectorPathEvent::CubicTo { c1: point(c1), c2: point(c2), to: point(to) }
+            }
+            PathSegment::Close => {
+                if let Some((_, is_closed)) = sub_paths.last_mut() {
+                    *is_closed = true;
+                }
+                continue;
+            }
+        };
+        if let Some((sub_path, _)) = sub_paths.last_mut() {
+            sub_path.push(event);
+        }
+    }
+    sub_paths
+}
+
+fn convert_paint(paint: &usvg::Paint, opacity: f32, transform: usvg::Transform) -> StyleColor {
+    let point = |x: f32, y: f32, gradient_transform: usvg::Transform| {
+        let mut p = usvg::tiny_skia_path::Point::from_xy(x, y);
+        transform.pre_concat(gradient_transform).map_point(&mut p);
+        Point::new(p.x, p.y)
+    };
+    let stops = |stops: &[usvg::Stop]| {
+        stops
+            .iter()
+            .map(|stop| {
+                (stop.offset().get(), convert_color(stop.color(), stop.opacity().get() * opacity))
+            })
+            .collect()
+    };
+
+    match paint {
+        usvg::Paint::Color(color) => StyleColor::Color(convert_color(*color, opacity)),
+        usvg::Paint::LinearGradient(gradient) => StyleColor::LinearGradient(Gradient {
+            start: point(gradient.x1(), gradient.y1(), gradient.transform()),
+            end: point(gradient.x2(), gradient.y2(), gradient.transform()),
+            stops: stops(gradient.stops()),
+            color_space: ColorSpace::default(),
+        }),
+        // the focal point isn't supported.
+        usvg::Paint::RadialGradient(gradient) => StyleColor::RadialGradient(Gradient {
+            start: point(gradient.cx(), gradient.cy(), gradient.transform()),
+            end: point(gradient.cx() + gradient.r().get(), gradient.cy(), gradient.transform()),
+            stops: stops(gradient.stops()),
+            color_space: ColorSpace::default(),
+        }),
+        // patterns fall back to their first solid color, if any.
+        usvg::Paint::Pattern(pattern) => StyleColor::Color(
+            first_color(pattern.root())
+                .map(|color| convert_color(color, opacity))
+                .unwrap_or_else(|| Color::new(0.0, 0.0, 0.0, 0.0)),
+        ),
+    }
+}
+
+fn first_color(group: &usvg::Group) -> Option<usvg::Color> {
+    group.children().iter().find_map(|node| match node {
+        usvg::Node::Group(group) => first_color(group),
+        usvg::Node::Path(path) => path.fill().and_then(|fill| match fill.paint() {
+            usvg::Paint::Color(color) => Some(*color),
+            _ => None,
+        }),
+        _ => None,
+    })
+}
+
+fn convert_color(color: usvg::Color, alpha: f32) -> Color {
+    Color::new(
+        color.red as f32 / 255.0,
+        color.green as f32 / 255.0,
+        color.blue as f32 / 255.0,
+        alpha,
+    )
+}
+
+fn convert_blend_mode(mode: usvg::BlendMode) -> BlendMode {
+    match mode {
+        usvg::BlendMode::Normal => BlendMode::Normal,
+        usvg::BlendMode::Multiply => BlendMode::Multiply,
+        usvg::BlendMode::Screen => BlendMode::Screen,
+        usvg::BlendMode::Overlay => BlendMode::Overlay,
+        usvg::BlendMode::Darken => BlendMode::Darken,
+        usvg::BlendMode::Lighten => BlendMode::Lighten,
+        usvg::BlendMode::ColorDodge => BlendMode::ColorDodge,
+        usvg::BlendMode::ColorBurn => BlendMode::ColorBurn,
+        usvg::BlendMode::HardLight => BlendMode::HardLight,
+        usvg::BlendMode::SoftLight => BlendMode::SoftLight,
+        usvg::BlendMode::Difference => BlendMode::Difference,
+        usvg::BlendMode::Exclusion => BlendMode::Exclusion,
+        usvg::BlendMode::Hue => BlendMode::Hue,
+        usvg::BlendMode::Saturation => BlendMode::Saturation,
+        usvg::BlendMode::Color => BlendMode::Color,
+        usvg::BlendMode::Luminosity => BlendMode::Luminosity,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_svg_import() {
+        let svg = br##"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="10">
+            <defs>
+                <linearGradient id="g" x1="0" y1="0" x2="10" y2="0" gradientUnits="userSpaceOnUse">
+                    <stop offset="0" stop-color="#ff0000"/>
+                    <stop offset="1" stop-color="#0000ff" stop-opacity="0.5"/>
+                </linearGradient>
+            </defs>
+            <rect x="0" y="0" width="10" height="10" fill="url(#g)"/>
+            <clipPath id="c"><rect x="1" y="0" width="5" height="5"/></clipPath>
+            <g transform="translate(10 0)" opacity="0.5" clip-path="url(#c)">
+                <path d="M 0 0 L 5 5 L 10 0" fill="none" stroke="#00ff00" stroke-width="2" stroke-linecap="round"/>
+            </g>
+        </svg>"##;
+        let image = SvgImage::from_data(svg).unwrap();
+        assert_eq!(image.size(), Size::new(20.0, 10.0));
+        assert!(SvgImage::from_data(b"<svg").is_err());
+
+        let commands = image.commands();
+        assert_eq!(commands.len(), 5);
+        match &commands[0] {
+            DisplayCommand::Item(
+                DisplayItem::Graphics(GraphicsDisplayItem::Path {
+                    paint: GraphicsDisplayPaint::Fill(StyleColor::LinearGradient(gradient)),
+                    is_closed: true,
+                    ..
+                }),
+                _,
+            ) => {
+                assert_eq!(gradient.end, Point::new(10.0, 0.0));
+                assert_eq!(gradient.stops[1], (1.0, Color::new(0.0, 0.0, 1.0, 0.5)));
+            }
+            _ => panic!("expected a gradient fill"),
+        }
+        assert!(
+            matches!(commands[1], DisplayCommand::SaveLayer(opacity, BlendMode::Normal) if opacity == 0.5)
+        );
+        match &commands[2] {
+            DisplayCommand::Clip(DisplayClip::Path { path, .. }, ClipOp::Intersect) => {
+                assert!(
+                    matches!(path[0], VectorPathEvent::MoveTo { to } if to == Point::new(11.0, 0.0))
+                )
+            }
+            _ => panic!("expected a clip"),
+        }
+        match &commands[3] {
+            DisplayCommand::Item(
+                DisplayItem::Graphics(GraphicsDisplayItem::Path {
+                    path,
+                    is_closed: false,
+                    paint: GraphicsDisplayPaint::Stroke(stroke),
+                }),
+                _,
+            ) => {
+                // the group transform is applied to the path itself.
+                assert!(
+                    matches!(path[0], VectorPathEvent::MoveTo { to } if to == Point::new(10.0, 0.0))
+                );
+                assert_eq!(stroke.cap, LineCap::Round);
+                assert_eq!(stroke.thickness, 2.0);
+            }
+            _ => panic!("expected a stroked path"),
+        }
+        assert!(matches!(commands[4], DisplayCommand::Restore));
+
+        let fitted = image.commands_in(Rect::new(Point::new(5.0, 5.0), Size::new(40.0, 40.0)));
+        assert!(
+            matches!(fitted[2], DisplayCommand::Scale(scale) if scale == Vector::new(2.0, 4.0))
+        );
+        assert_eq!(fitted.len(), commands.len() + 4);
+    }
+}
@@ -0,0 +1,629 @@
+//! Incremental multi-line text layout.
+//!
+//! [`TextDisplayItem::linebreak`](TextDisplayItem::linebreak) lays out the entire text every time.
+//! For large, frequently edited text (e.g. an editor) a [`TextLayout`](TextLayout) instead keeps the lines
+//! from the last layout, and when the text changes only re-breaks the lines from the edit point onwards,
+//! reusing the lines after the edit as soon as the line breaks line up again.
+//!
+//! Longer rich text (e.g. a chat log or a document) is made of many differently styled paragraphs; a
+//! [`DocumentLayout`](DocumentLayout) stacks a `TextLayout` per paragraph, aligns the lines, and when
+//! resized only re-wraps the paragraphs which don't fit on their lines anyway.
+
+use {super::*, std::ops::Range};
+
+/// Identifies a line of a [`TextLayout`](TextLayout).
+///
+/// The ID of a line is kept for as long as the line isn't re-broken, even if text is inserted or
+/// removed before it (which shifts its range and position).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LineId(u64);
+
+/// A single laid out line.
+#[derive(Debug, Clone)]
+pub struct LineLayout {
+    id: LineId,
+    range: Range<usize>,
+    item: TextDisplayItem,
+}
+
+impl LineLayout {
+    /// Returns the stable ID of the line.
+    #[inline]
+    pub fn id(&self) -> LineId {
+        self.id
+    }
+
+    /// Returns the range of the line within the text of the layout.
+    #[inline]
+    pub fn range(&self) -> Range<usize> {
+        self.range.clone()
+    }
+
+    /// Returns the text item of this line, positioned within the layout.
+    #[inline]
+    pub fn item(&self) -> &TextDisplayItem {
+        &self.item
+    }
+}
+
+/// Multi-line text, broken the same way as [`linebreak`](TextDisplayItem::linebreak), which can be updated incrementally.
+#[derive(Debug, Clone)]
+pub struct TextLayout {
+    item: TextDisplayItem,
+    max_width: f32,
+    line_height: f32,
+    remove_newlines: bool,
+    lines: Vec<LineLayout>,
+    next_id: u64,
+}
+
+impl TextLayout {
+    /// Lays out the text of `item`, which is positioned at the first line.
+    ///
+    /// See [`linebreak`](TextDisplayItem::linebreak) for the meaning of the parameters.
+    pub fn new(
+        item: TextDisplayItem,
+        max_width: f32,
+        line_height: f32,
+        remove_newlines: bool,
+    ) -> Result<Self, error::FontError> {
+        let mut layout = TextLayout {
+            item,
+            max_width,
+            line_height,
+            remove_newlines,
+            lines: Vec::new(),
+            next_id: 0,
+        };
+        layout.relayout()?;
+        Ok(layout)
+    }
+
+    /// Returns the full text.
+    #[inline]
+    pub fn text(&self) -> &DisplayText {
+        &self.item.text
+    }
+
+    /// Returns all the lines, from top to bottom.
+    #[inline]
+    pub fn lines(&self) -> &[LineLayout] {
+        &self.lines
+    }
+
+    /// Returns the text items of all the lines which aren't empty, ready to be displayed.
+    pub fn items(&self) -> impl Iterator<Item = &TextDisplayItem> + '_ {
+        self.lines.iter().map(LineLayout::item).filter(|item| !item.text.is_empty())
+    }
+
+    /// Returns the total height of the laid out text.
+    pub fn height(&self) -> f32 {
+        self.lines.len() as f32 * self.line_height
+    }
+
+    /// Changes the maximum line width, re-breaking all the lines.
+    pub fn set_max_width(&mut self, max_width: f32) -> Result<(), error::FontError> {
+        self.max_width = max_width;
+        self.relayout()
+    }
+
+    /// Lays out all the lines again, assigning new IDs.
+    pub fn relayout(&mut self) -> Result<(), error::FontError> {
+        self.lines = self.break_lines(0, 0, None)?.0;
+        Ok(())
+    }
+
+    /// Replaces the text, only re-breaking the lines which are affected by the change.
+    ///
+    /// Returns the indices of the lines which were re-broken (these lines have new IDs).
+    /// The lines after them were reused and keep their IDs, but may have been moved.
+    ///
+    /// If this fails, the layout is left unchanged.
+    pub fn set_text(
+        &mut self,
+        text: impl Into<DisplayText>,
+    ) -> Result<Range<usize>, error::FontError> {
+        let old = std::mem::replace(&mut self.item.text, text.into());
+        let (prefix, suffix) = common_affixes(&old, &self.item.text);
+
+        // the line before the edited line is re-broken too, since its break may depend on the edited text.
+        let first = self
+            .lines
+            .iter()
+            .position(|line| line.range.end > prefix)
+            .unwrap_or(self.lines.len())
+            .saturating_sub(1);
+        let start = self.lines.get(first).map(|line| line.range.start).unwrap_or(0);
+
+        match self.break_lines(first, start, Some((old.len(), suffix))) {
+            Ok((lines, rebroken)) => {
+                self.lines.truncate(first);
+                self.lines.extend(lines);
+                Ok(first..first + rebroken)
+            }
+            Err(err) => {
+                self.item.text = old;
+                Err(err)
+            }
+        }
+    }
+
+    /// Breaks the text into lines, starting at `start` as line number `index`.
+    ///
+    /// If `reuse` is given (as the length of the previous text and the length of the unchanged suffix),
+    /// previous lines are reused as soon as a line starts at the same point in the unchanged suffix.
+    /// Returns the lines, along with the number of lines which were actually broken.
+    fn break_lines(
+        &mut self,
+        mut index: usize,
+        mut start: usize,
+        reuse: Option<(usize, usize)>,
+    ) -> Result<(Vec<LineLayout>, usize), error::FontError> {
+        let len = self.item.text.len();
+        let mut lines = Vec::new();
+
+        loop {
+            if let Some((old_len, suffix)) = reuse {
+                // breaking only depends on the text that follows, so from here on the lines are the same as before.
+                if start >= len - suffix {
+                    let old_start = start + old_len - len;
+                    if let Some(reused) =
+                        self.lines.iter().position(|line| line.range.start == old_start)
+                    {
+                        let rebroken = lines.len();
+                        for line in &self.lines[reused..] {
+                            let mut item = line.item.clone();
+                            item.bottom_left = self.line_origin(index);
+                            lines.push(LineLayout {
+                                id: line.id,
+                                range: line.range.start + len - old_len
+                                    ..line.range.end + len - old_len,
+                                item,
+                            });
+                            index += 1;
+                        }
+                        return Ok((lines, rebroken));
+                    }
+                }
+            }
+
+            let remaining =
+                self.item.with_text(self.item.text.subtext(start..len), self.item.bottom_left);
+            let end =
+                remaining.next_line_break(self.max_width)?.map_or(len, |offset| start + offset);
+
+            let id = LineId(self.next_id);
+            self.next_id += 1;
+            lines.push(LineLayout {
+                id,
+                range: start..end,
+                item: self.line_item(start..end, index),
+            });
+
+            if end >= len {
+                let rebroken = lines.len();
+                return Ok((lines, rebroken));
+            }
+
+            index += 1;
+            start = end;
+        }
+    }
+
+    fn line_origin(&self, index: usize) -> Point {
+        self.item.bottom_left + Size::new(0.0, index as f32 * self.line_height)
+    }
+
+    fn line_item(&self, range: Range<usize>, index: usize) -> TextDisplayItem {
+        let mut item = self.item.with_text(self.item.text.subtext(range), self.line_origin(index));
+
+        if self.remove_newlines {
+            item.text.filter(|character| match character {
+                DisplayCharacter::Character(c) => c != '\n',
+                DisplayCharacter::Glyph(glyph) => glyph.codepoint as u8 as char != '\n',
+            });
+        }
+
+        item
+    }
+}
+
+/// Horizontal alignment of the lines of a [`DocumentLayout`](DocumentLayout).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// How the paragraphs of a [`DocumentLayout`](DocumentLayout) are broken and arranged.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DocumentConstraints {
+    /// Lines are broken to fit within this width, and aligned within it.
+    ///
+    /// Lines are always left-aligned if this isn't finite.
+    pub max_width: f32,
+    pub align: TextAlign,
+    /// Multiple of the natural line height of each paragraph's font, which lines are spaced by.
+    pub line_spacing: f32,
+    /// Vertical space between consecutive paragraphs.
+    pub paragraph_spacing: f32,
+}
+
+impl DocumentConstraints {
+    /// Left-aligned, evenly spaced lines no wider than `max_width`.
+    pub fn new(max_width: f32) -> Self {
+        DocumentConstraints {
+            max_width,
+            align: TextAlign::Left,
+            line_spacing: 1.0,
+            paragraph_spacing: 0.0,
+        }
+    }
+}
+
+/// A laid out paragraph, along with the measurements needed to arrange it.
+#[derive(Debug, Clone)]
+struct ParagraphLayout {
+    layout: TextLayout,
+    /// Width of the whole text on a single line; if this fits, only mandatory breaks break the text.
+    natural_width: f32,
+    /// Natural line height of the font.
+    line_height: f32,
+    /// Width of each line, without trailing whitespace.
+    line_widths: Vec<f32>,
+}
+
+impl ParagraphLayout {
+    fn new(mut item: TextDisplayItem, max_width: f32) -> Result<Self, error::FontError> {
+        item.set_top_left(Point::zero());
+        let natural_width = item.bounds()?.size.width;
+        let line_height = item.limited_bounds(0)?.size.height;
+        let mut paragraph = ParagraphLayout {
+            layout: TextLayout::new(item, max_width, line_height, true)?,
+            natural_width,
+            line_height,
+            line_widths: Vec::new(),
+        };
+        paragraph.measure()?;
+        Ok(paragraph)
+    }
+
+    fn measure(&mut self) -> Result<(), error::FontError> {
+        self.natural_width = self.layout.item.bounds()?.size.width;
+        self.line_widths = self
+            .layout
+            .lines()
+            .iter()
+            .map(|line| {
+                let len = match &line.item.text {
+                    DisplayText::Simple(text) => text.trim_end().len(),
+                    DisplayText::Shaped(glyphs) => glyphs.len(),
+                };
+                line.item.limited_bounds(len).map(|bounds| bounds.size.width)
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(())
+    }
+}
+
+/// Multiple styled paragraphs, broken into lines and stacked from top to bottom.
+///
+/// Each paragraph is given as a text item, whose text and style (font, size, color, etc.) is used for all of
+/// its lines; the position of the item is ignored. Paragraphs are broken separately and the results are kept,
+/// so that editing a paragraph or changing the [constraints](DocumentConstraints) only re-breaks what changed.
+#[derive(Debug, Clone)]
+pub struct DocumentLayout {
+    origin: Point,
+    constraints: DocumentConstraints,
+    paragraphs: Vec<ParagraphLayout>,
+}
+
+impl DocumentLayout {
+    /// Lays out `paragraphs`, with the top-left corner of the first at `origin`.
+    pub fn new(
+        paragraphs: impl IntoIterator<Item = TextDisplayItem>,
+        origin: Point,
+        constraints: DocumentConstraints,
+    ) -> Result<Self, error::FontError> {
+        Ok(DocumentLayout {
+            origin,
+            constraints,
+            paragraphs: paragraphs
+                .into_iter()
+                .map(|item| ParagraphLayout::new(item, constraints.max_width))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Returns the number of paragraphs.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.paragraphs.len()
+    }
+
+    /// Returns `true` if there are no paragraphs.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.paragraphs.is_empty()
+    }
+
+    /// Returns the layout of the paragraph at `index`, positioned at the origin.
+    ///
+    /// # Panics
+    /// Panics if `index` is out-of-bounds.
+    #[inline]
+    pub fn paragraph(&self, index: usize) -> &TextLayout {
+        &self.paragraphs[index].layout
+    }
+
+    /// Appends a paragraph to the bottom; this doesn't affect the other paragraphs.
+    pub fn push_paragraph(&mut self, item: TextDisplayItem) -> Result<(), error::FontError> {
+        self.paragraphs.push(ParagraphLayout::new(item, self.constraints.max_width)?);
+        Ok(())
+    }
+
+    /// Removes the paragraph at `index`, moving the paragraphs below it up.
+    ///
+    /// # Panics
+    /// Panics if `index` is out-of-bounds.
+    pub fn remove_paragraph(&mut self, index: usize) -> TextDisplayItem {
+        self.paragraphs.remove(index).layout.item
+    }
+
+    /// Replaces the text of the paragraph at `index`, keeping its style.
+    ///
+    /// As with [`TextLayout::set_text`](TextLayout::set_text), only the affected lines are re-broken, and their
+    /// indices are returned.
+    ///
+    /// # Panics
+    /// Panics if `index` is out-of-bounds.
+    pub fn set_paragraph_text(
+        &mut self,
+        index: usize,
+        text: impl Into<DisplayText>,
+    ) -> Result<Range<usize>, error::FontError> {
+        let paragraph = &mut self.paragraphs[index];
+        let rebroken = paragraph.layout.set_text(text)?;
+        paragraph.measure()?;
+        Ok(rebroken)
+    }
+
+    /// Returns the origin (the top-left corner of the first paragraph).
+    #[inline]
+    pub fn origin(&self) -> Point {
+        self.origin
+    }
+
+    /// Moves the layout; this doesn't re-break any lines.
+    #[inline]
+    pub fn set_origin(&mut self, origin: Point) {
+        self.origin = origin;
+    }
+
+    /// Returns the constraints the paragraphs are laid out with.
+    #[inline]
+    pub fn constraints(&self) -> DocumentConstraints {
+        self.constraints
+    }
+
+    /// Changes the constraints, re-breaking the paragraphs which may break differently at the new width.
+    ///
+    /// Paragraphs which fit within both the old and the new width aren't re-broken, since they only break
+    /// at newlines. Returns the indices of the paragraphs which were re-broken.
+    pub fn set_constraints(
+        &mut self,
+        constraints: DocumentConstraints,
+    ) -> Result<Vec<usize>, error::FontError> {
+        let narrowest = self.constraints.max_width.min(constraints.max_width);
+        let changed = self.constraints.max_width != constraints.max_width;
+        self.constraints = constraints;
+
+        let mut rebroken = Vec::new();
+        for (index, paragraph) in self.paragraphs.iter_mut().enumerate() {
+            if changed && paragraph.natural_width > narrowest {
+                paragraph.layout.set_max_width(constraints.max_width)?;
+                paragraph.measure()?;
+                rebroken.push(index);
+            }
+        }
+        Ok(rebroken)
+    }
+
+    /// Returns the total height of all the paragraphs, including the spacing between them.
+    pub fn height(&self) -> f32 {
+        let spacing =
+            self.paragraphs.len().saturating_sub(1) as f32 * self.constraints.paragraph_spacing;
+        self.paragraphs.iter().map(|paragraph| self.paragraph_height(paragraph)).sum::<f32>()
+            + spacing
+    }
+
+    /// Returns the text items of all the lines which aren't empty, positioned and aligned, from top to bottom.
+    pub fn items(&self) -> impl Iterator<Item = TextDisplayItem> + '_ {
+        let mut top = self.origin.y;
+        self.paragraphs.iter().flat_map(move |paragraph| {
+            let paragraph_top = top;
+            top += self.paragraph_height(paragraph) + self.constraints.paragraph_spacing;
+            let line_height = paragraph.line_height * self.constraints.line_spacing;
+
+            paragraph.layout.lines().iter().zip(&paragraph.line_widths).enumerate().filter_map(
+                move |(index, (line, &width))| {
+                    if line.item.text.is_empty() {
+                        return None;
+                    }
+                    let mut item = line.item.clone();
+                    item.set_top_left(Point::new(
+                        self.origin.x + self.align_offset(width),
+                        paragraph_top + index as f32 * line_height,
+                    ));
+                    Some(item)
+                },
+            )
+        })
+    }
+
+    /// Pushes all the lines which aren't empty as text items.
+    pub fn push(&self, builder: &mut DisplayListBuilder, filters: Vec<FilterOp>) {
+        for item in self.items() {
+            builder.push_text(item, filters.clone());
+        }
+    }
+
+    fn paragraph_height(&self, paragraph: &ParagraphLayout) -> f32 {
+        paragraph.layout.lines().len() as f32
+            * paragraph.line_height
+            * self.constraints.line_spacing
+    }
+
+    fn align_offset(&self, width: f32) -> f32 {
+        let max_width = self.constraints.max_width;
+        if !max_width.is_finite() {
+            return 0.0;
+        }
+        match self.constraints.align {
+            TextAlign::Left => 0.0,
+            TextAlign::Center => (max_width - width) / 2.0,
+            TextAlign::Right => max_width - width,
+        }
+    }
+}
+
+/// Returns the length of the common prefix and suffix of two texts, which don't overlap.
+fn common_affixes(a: &DisplayText, b: &DisplayText) -> (usize, usize) {
+    match (a, b) {
+        (DisplayText::Simple(a), DisplayText::Simple(b)) => {
+            let prefix = a
+                .char_indices()
+                .zip(b.chars())
+                .find(|((_, x), y)| x != y)
+                .map_or(a.len().min(b.len()), |((i, _), _)| i);
+            let max_suffix = a.len().min(b.len()) - prefix;
+            let suffix = a[prefix..]
+                .chars()
+                .rev()
+                .zip(b[prefix..].chars().rev())
+                .take_while(|(x, y)| x == y)
+                .map(|(x, _)| x.len_utf8())
+                .scan(0, |len, c| {
+                    *len += c;
+                    Some(*len)
+                })
+                .take_while(|len| *len <= max_suffix)
+                .last()
+                .unwrap_or(0);
+            (prefix, suffix)
+        }
+        (DisplayText::Shaped(a), DisplayText::Shaped(b)) => {
+            let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+            let suffix = a[prefix..]
+                .iter()
+                .rev()
+                .zip(b[prefix..].iter().rev())
+                .take_while(|(x, y)| x == y)
+                .count();
+            (prefix, suffix)
+        }
+        _ => (0, 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_matches_linebreak(layout: &TextLayout) {
+        let expected = TextDisplayItem { text: layout.text().clone(), ..test_text("") }
+            .linebreak(100.0, 20.0, true)
+            .unwrap();
+        let actual: Vec<_> = layout.items().collect();
+        assert_eq!(actual.len(), expected.len());
+        for (actual, expected) in actual.iter().zip(&expected) {
+            assert_eq!(actual.text, expected.text);
+            assert_eq!(actual.bottom_left, expected.bottom_left);
+        }
+    }
+
+    #[test]
+    fn test_incremental_text_layout() {
+        let text = "The quick brown fox jumps over the lazy dog. Pack my box with five dozen liquor jugs.\nSphinx of black quartz, judge my vow.";
+        let mut layout = TextLayout::new(test_text(text), 100.0, 20.0, true).unwrap();
+        assert!(layout.lines().len() > 5);
+        assert_matches_linebreak(&layout);
+
+        let ids: Vec<_> = layout.lines().iter().map(LineLayout::id).collect();
+        let last_range = layout.lines().last().unwrap().range();
+
+        // editing a word in the middle only re-breaks the lines around it.
+        let edited = text.replacen("box", "crate", 1);
+        let rebroken = layout.set_text(edited.as_str()).unwrap();
+        assert_matches_linebreak(&layout);
+        assert!(rebroken.start > 0 && rebroken.end < layout.lines().len());
+        assert_eq!(
+            layout.lines()[..rebroken.start].iter().map(LineLayout::id).collect::<Vec<_>>(),
+            ids[..rebroken.start]
+        );
+        assert_eq!(layout.lines().last().unwrap().id(), *ids.last().unwrap());
+        assert_eq!(
+            layout.lines().last().unwrap().range(),
+            last_range.start + 2..last_range.end + 2
+        );
+
+        // typing at the end only re-breaks the last line(s).
+        let mut typed = edited.clone();
+        typed.push_str(" Again");
+        let rebroken = layout.set_text(typed.as_str()).unwrap();
+        assert_matches_linebreak(&layout);
+        assert_eq!(rebroken.end, layout.lines().len());
+        assert!(rebroken.len() <= 2);
+
+        layout.set_text("").unwrap();
+        assert_eq!(layout.lines().len(), 1);
+        assert_eq!(layout.items().count(), 0);
+    }
+
+    #[test]
+    fn test_document_layout() {
+        let long =
+            "The quick brown fox jumps over the lazy dog. Pack my box with five dozen liquor jugs.";
+        let heading = TextDisplayItem { size: 24.0, ..test_text("Log") };
+        let mut constraints = DocumentConstraints::new(150.0);
+        constraints.align = TextAlign::Right;
+        constraints.paragraph_spacing = 10.0;
+        let mut document = DocumentLayout::new(
+            vec![heading, test_text(long), test_text("ok")],
+            Point::new(10.0, 10.0),
+            constraints,
+        )
+        .unwrap();
+        assert_eq!(document.len(), 3);
+
+        let items: Vec<_> = document.items().collect();
+        assert_eq!(items.len(), document.paragraph(1).lines().len() + 2);
+        // right-aligned lines end at the right edge, and the paragraphs are stacked in order.
+        assert!((items[0].bounds().unwrap().max_x() - 160.0).abs() < 0.01);
+        assert!((items.last().unwrap().bounds().unwrap().max_x() - 160.0).abs() < 0.01);
+        assert!(items.windows(2).all(|pair| pair[0].bottom_left.y < pair[1].bottom_left.y));
+        let last = items.last().unwrap().bounds().unwrap();
+        assert!((last.max_y() - (10.0 + document.height())).abs() < 0.01);
+
+        let mut builder = DisplayListBuilder::new();
+        document.push(&mut builder, vec![]);
+        assert_eq!(builder.build().len(), items.len());
+
+        // widening only re-wraps the paragraph which didn't fit.
+        let ids: Vec<_> = document.paragraph(2).lines().iter().map(LineLayout::id).collect();
+        let lines = document.paragraph(1).lines().len();
+        assert_eq!(document.set_constraints(DocumentConstraints::new(300.0)).unwrap(), vec![1]);
+        assert!(document.paragraph(1).lines().len() < lines);
+        assert_eq!(
+            document.paragraph(2).lines().iter().map(LineLayout::id).collect::<Vec<_>>(),
+            ids
+        );
+
+        document.push_paragraph(test_text("appended")).unwrap();
+        document.set_paragraph_text(3, "edited").unwrap();
+        assert_eq!(document.remove_paragraph(0).size, 24.0);
+        assert_eq!(document.items().last().unwrap().text, DisplayText::Simple("edited".into()));
+    }
+}
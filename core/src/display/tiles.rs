@@ -0,0 +1,541 @@
+//! Surfaces assembled from image tiles fetched on demand, as used by maps and viewers of very large images.
+//!
+//! The surface is a pyramid of zoom levels, where zoom level `z` is `2^z` tiles wide and high, each tile
+//! being [`tile_size`](TiledSurface::tile_size) pixels square. A [`TiledSurface`](TiledSurface) fetches the tiles
+//! within a [`TileViewport`](TileViewport) from a [`TileProvider`](TileProvider) on a few background threads, keeps the
+//! most recently drawn ones as image resources, and draws a scaled-up portion of a loaded ancestor while a tile is
+//! still loading:
+//!
+//! ```ignore
+//! // in draw:
+//! surface.update(display);
+//! surface.request(&viewport);
+//! let mut builder = DisplayListBuilder::new();
+//! surface.push(&mut builder, &viewport);
+//!
+//! // in update, for each event of `surface.event`:
+//! TileEvent::Loaded(_) => command_group.repaint(),
+//! ```
+
+use {
+    super::{
+        image_loader::{image_data, DecodeError},
+        workers::WorkerPool,
+        *,
+    },
+    crate::{event::RcEventQueue, prelude::*},
+    std::{
+        collections::{HashMap, HashSet},
+        sync::mpsc::{self, Receiver},
+    },
+};
+
+/// Position of a tile within the pyramid; `x` and `y` range from `0` to `2^zoom`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TileId {
+    pub zoom: u8,
+    pub x: u32,
+    pub y: u32,
+}
+
+impl TileId {
+    pub fn new(zoom: u8, x: u32, y: u32) -> Self {
+        TileId { zoom, x, y }
+    }
+
+    /// Returns the tile of the previous zoom level covering this one, or `None` at zoom level `0`.
+    pub fn parent(self) -> Option<TileId> {
+        if self.zoom == 0 {
+            None
+        } else {
+            Some(TileId::new(self.zoom - 1, self.x / 2, self.y / 2))
+        }
+    }
+}
+
+/// Fetches the pixels of tiles, on the threads of a [`TiledSurface`](TiledSurface).
+///
+/// Implemented for closures with the same signature as [`fetch`](TileProvider::fetch).
+pub trait TileProvider: Send + Sync + 'static {
+    /// Downloads, reads or renders a tile; blocking is fine.
+    fn fetch(&self, tile: TileId) -> Result<RasterImage, DecodeError>;
+}
+
+impl<F> TileProvider for F
+where
+    F: Fn(TileId) -> Result<RasterImage, DecodeError> + Send + Sync + 'static,
+{
+    #[inline]
+    fn fetch(&self, tile: TileId) -> Result<RasterImage, DecodeError> {
+        self(tile)
+    }
+}
+
+/// The part of a [`TiledSurface`](TiledSurface) to show.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileViewport {
+    /// Where the surface is drawn on the display.
+    pub bounds: Rect,
+    /// Position of the top-left of `bounds` within the surface, in pixels at `zoom`.
+    pub offset: Vector,
+    pub zoom: u8,
+}
+
+/// How far a tile of a [`TiledSurface`](TiledSurface) has loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TileStage {
+    Pending,
+    Loaded,
+    /// Fetching failed; the tile is requested again once it has been evicted.
+    Failed,
+}
+
+/// Events emitted by a [`TiledSurface`](TiledSurface) as tiles arrive, signalling that it needs repainting.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TileEvent {
+    Loaded(TileId),
+    Failed(TileId, String),
+}
+
+#[derive(Debug)]
+struct Tile {
+    stage: TileStage,
+    resource: Option<ResourceReference>,
+    /// Size of the fetched image, which isn't necessarily `tile_size` (e.g. for high-DPI tiles).
+    size: (u32, u32),
+    last_used: u64,
+}
+
+/// Fetches, caches and draws the tiles of a surface, see the [module documentation](self).
+pub struct TiledSurface {
+    pub event: RcEventQueue<TileEvent>,
+    workers: WorkerPool<TileId>,
+    tile_size: u32,
+    max_zoom: u8,
+    capacity: usize,
+    tiles: HashMap<TileId, Tile>,
+    frame: u64,
+    receiver: Receiver<(TileId, Result<RasterImage, DecodeError>)>,
+}
+
+impl TiledSurface {
+    /// Creates a surface of tiles `tile_size` pixels square, fetched from `provider`.
+    ///
+    /// By default, zoom levels up to 18 are fetched, on up to 4 threads, and up to 256 tiles are kept.
+    pub fn new(provider: impl TileProvider, tile_size: u32) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        TiledSurface {
+            event: RcEventQueue::new(),
+            workers: WorkerPool::new(4, move |id| {
+                // the surface may have been dropped, in which case nobody is interested anymore.
+                let _ = sender.send((id, provider.fetch(id)));
+            }),
+            tile_size: tile_size.max(1),
+            max_zoom: 18,
+            capacity: 256,
+            tiles: HashMap::new(),
+            frame: 0,
+            receiver,
+        }
+    }
+
+    /// Returns the width and height of the tiles, in pixels.
+    pub fn tile_size(&self) -> u32 {
+        self.tile_size
+    }
+
+    /// Sets the deepest zoom level which is fetched; deeper viewports are drawn from scaled-up tiles of this level.
+    pub fn set_max_zoom(&mut self, max_zoom: u8) {
+        self.max_zoom = max_zoom;
+    }
+
+    /// Sets how many tiles are fetched at once; the others wait for their turn in the order they were requested.
+    pub fn set_max_workers(&mut self, max_workers: usize) {
+        self.workers.set_max_workers(max_workers);
+    }
+
+    /// Sets how many tiles are kept before the least recently drawn ones are evicted.
+    ///
+    /// Tiles drawn since the last [`update`](TiledSurface::update) are never evicted, so more tiles than this may be
+    /// kept if the viewport needs them.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+    }
+
+    /// Returns how far a tile has loaded, or `None` if it isn't cached nor being fetched.
+    pub fn stage(&self, tile: TileId) -> Option<TileStage> {
+        self.tiles.get(&tile).map(|tile| tile.stage)
+    }
+
+    /// Returns the number of tiles which are cached or being fetched.
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    /// Returns `true` if no tiles are cached nor being fetched.
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    /// Returns the tiles intersecting the viewport (at its zoom level, limited to the maximum zoom), along with
+    /// where each is drawn on the display.
+    pub fn visible_tiles(&self, viewport: &TileViewport) -> Vec<(TileId, Rect)> {
+        let zoom = viewport.zoom.min(self.max_zoom).min(31);
+        // tiles of a shallower zoom level are drawn larger.
+        let size = self.tile_size as f32 * 2f32.powi((viewport.zoom - zoom) as i32);
+        let count = 1i64 << zoom;
+
+        let range = |offset: f32, length: f32| {
+            let first = ((offset / size).floor() as i64).max(0);
+            let last = (((offset + length) / size).ceil() as i64).min(count);
+            first..last.max(first)
+        };
+        let (columns, rows) = (
+            range(viewport.offset.x, viewport.bounds.size.width),
+            range(viewport.offset.y, viewport.bounds.size.height),
+        );
+
+        let mut tiles = Vec::new();
+        for y in rows {
+            for x in columns.clone() {
+                let origin = viewport.bounds.origin + Vector::new(x as f32, y as f32) * size
+                    - viewport.offset;
+                tiles.push((
+                    TileId::new(zoom, x as u32, y as u32),
+                    Rect::new(origin, Size::new(size, size)),
+                ));
+            }
+        }
+        tiles
+    }
+
+    /// Starts fetching the visible tiles which aren't cached nor being fetched yet.
+    ///
+    /// Requested tiles which are still waiting for a thread and aren't visible anymore are cancelled, so that
+    /// scrolling or zooming quickly doesn't fetch every tile passed along the way.
+    pub fn request(&mut self, viewport: &TileViewport) {
+        let visible: Vec<_> = self.visible_tiles(viewport).into_iter().map(|(id, _)| id).collect();
+        let visible_set: HashSet<_> = visible.iter().copied().collect();
+        for id in self.workers.retain(|id| visible_set.contains(id)) {
+            self.tiles.remove(&id);
+        }
+
+        for id in visible {
+            if self.tiles.contains_key(&id) {
+                continue;
+            }
+            self.tiles.insert(
+                id,
+                Tile {
+                    stage: TileStage::Pending,
+                    resource: None,
+                    size: (0, 0),
+                    last_used: self.frame,
+                },
+            );
+            self.workers.push(id);
+        }
+    }
+
+    /// Returns the number of requested tiles waiting for a thread to fetch them.
+    pub fn queued(&self) -> usize {
+        self.workers.queued()
+    }
+
+    /// Creates image resources for the tiles fetched since the last update, emitting an event for each, then evicts
+    /// the least recently drawn tiles beyond the capacity.
+    ///
+    /// Returns `true` if any tiles arrived.
+    pub fn update(&mut self, display: &mut dyn GraphicsDisplay) -> bool {
+        let mut arrived = false;
+        while let Ok((id, result)) = self.receiver.try_recv() {
+            let tile = match self.tiles.get_mut(&id) {
+                Some(tile) if tile.stage == TileStage::Pending => tile,
+                _ => continue,
+            };
+            arrived = true;
+
+            let result = result.map_err(|err| err.to_string()).and_then(|image| {
+                let size = image.info.size;
+                display
+                    .new_resource(ResourceDescriptor::Image(image_data(image)))
+                    .map(|resource| (resource, size))
+                    .map_err(|err| err.to_string())
+            });
+            match result {
+                Ok((resource, size)) => {
+                    tile.stage = TileStage::Loaded;
+                    tile.resource = Some(resource);
+                    tile.size = size;
+                    self.event.emit_owned(TileEvent::Loaded(id));
+                }
+                Err(err) => {
+                    tile.stage = TileStage::Failed;
+                    self.event.emit_owned(TileEvent::Failed(id, err));
+                }
+            }
+        }
+
+        self.evict(display);
+        self.frame += 1;
+        arrived
+    }
+
+    fn evict(&mut self, display: &mut dyn GraphicsDisplay) {
+        if self.tiles.len() <= self.capacity {
+            return;
+        }
+
+        // pending tiles are kept so that their results aren't fetched twice.
+        let mut evictable: Vec<_> = self
+            .tiles
+            .iter()
+            .filter(|(_, tile)| tile.stage != TileStage::Pending && tile.last_used < self.frame)
+            .map(|(id, tile)| (tile.last_used, *id))
+            .collect();
+        evictable.sort_unstable();
+
+        let excess = self.tiles.len() - self.capacity;
+        for (_, id) in evictable.into_iter().take(excess) {
+            if let Some(resource) = self.tiles.remove(&id).and_then(|tile| tile.resource) {
+                display.remove_resource(resource);
+            }
+        }
+    }
+
+    /// Pushes the visible tiles, clipped to the viewport.
+    ///
+    /// Tiles which aren't loaded are drawn from the nearest loaded ancestor, if any.
+    pub fn push(&mut self, builder: &mut DisplayListBuilder, viewport: &TileViewport) {
+        builder.save();
+        builder.push_rectangle_clip(viewport.bounds, true);
+        for (id, dst) in self.visible_tiles(viewport) {
+            if let Some((resource, src)) = self.loaded_ancestor(id) {
+                builder.push_image(src, dst, resource, vec![]);
+            }
+        }
+        builder.restore();
+    }
+
+    /// Returns the nearest loaded tile covering `id` (including itself), along with the part of it covering `id`.
+    fn loaded_ancestor(&mut self, id: TileId) -> Option<(ResourceReference, Option<Rect>)> {
+        let mut ancestor = Some(id);
+        while let Some(current) = ancestor {
+            if let Some(tile) = self.tiles.get_mut(&current) {
+                tile.last_used = self.frame;
+                if let Some(resource) = tile.resource {
+                    let depth = id.zoom - current.zoom;
+                    if depth == 0 {
+                        return Some((resource, None));
+                    }
+                    // the source rectangle is in the pixels of the image, whatever its size.
+                    let scale = (1u64 << depth) as f32;
+                    let size = Size::new(tile.size.0 as f32 / scale, tile.size.1 as f32 / scale);
+                    let mask = (1u32 << depth) - 1;
+                    let origin = Point::new(
+                        (id.x & mask) as f32 * size.width,
+                        (id.y & mask) as f32 * size.height,
+                    );
+                    return Some((resource, Some(Rect::new(origin, size))));
+                }
+            }
+            ancestor = current.parent();
+        }
+        None
+    }
+
+    /// Removes every tile, along with its image resource.
+    pub fn clear(&mut self, display: &mut dyn GraphicsDisplay) {
+        for (_, tile) in self.tiles.drain() {
+            if let Some(resource) = tile.resource {
+                display.remove_resource(resource);
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for TiledSurface {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TiledSurface")
+            .field("tile_size", &self.tile_size)
+            .field("max_zoom", &self.max_zoom)
+            .field("capacity", &self.capacity)
+            .field("tiles", &self.tiles)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::display::recording::RecordingDisplay,
+        std::{
+            sync::atomic::{AtomicBool, Ordering},
+            time::{Duration, Instant},
+        },
+    };
+
+    fn tile(_: TileId) -> Result<RasterImage, DecodeError> {
+        Ok(RasterImage {
+            data: vec![0; 256 * 256 * 4],
+            info: RasterImageInfo {
+                size: (256, 256),
+                format: RasterImageFormat::Rgba8,
+                color_space: ColorSpace::Srgb,
+            },
+        })
+    }
+
+    fn update_until_loaded(surface: &mut TiledSurface, display: &mut RecordingDisplay) {
+        let start = Instant::now();
+        while surface.tiles.values().any(|tile| tile.stage == TileStage::Pending) {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            std::thread::sleep(Duration::from_millis(1));
+            surface.update(display);
+        }
+    }
+
+    fn images(builder: DisplayListBuilder) -> Vec<(Option<Rect>, Rect)> {
+        builder
+            .build()
+            .into_iter()
+            .filter_map(|command| match command {
+                DisplayCommand::Item(
+                    DisplayItem::Graphics(GraphicsDisplayItem::Image { src, dst, .. }),
+                    _,
+                ) => Some((src, dst)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_tiled_surface() {
+        let mut surface = TiledSurface::new(
+            |id: TileId| if id.x == 2 { Err("not found".into()) } else { tile(id) },
+            256,
+        );
+        let listener = surface.event.listen();
+        let mut display = RecordingDisplay::new((100, 100));
+
+        let root = TileViewport {
+            bounds: Rect::new(Point::new(10.0, 10.0), Size::new(300.0, 300.0)),
+            offset: Vector::zero(),
+            zoom: 0,
+        };
+        assert_eq!(
+            surface.visible_tiles(&root),
+            vec![(
+                TileId::new(0, 0, 0),
+                Rect::new(Point::new(10.0, 10.0), Size::new(256.0, 256.0))
+            )]
+        );
+        surface.request(&root);
+        update_until_loaded(&mut surface, &mut display);
+        assert_eq!(listener.peek(), &[TileEvent::Loaded(TileId::new(0, 0, 0))]);
+
+        // a 300x200 window straddling the tiles (1..3, 0..2) of zoom level 2.
+        let viewport = TileViewport {
+            bounds: Rect::new(Point::zero(), Size::new(300.0, 200.0)),
+            offset: Vector::new(400.0, 100.0),
+            zoom: 2,
+        };
+        let visible = surface.visible_tiles(&viewport);
+        assert_eq!(visible.len(), 4);
+        assert_eq!(
+            visible[0],
+            (TileId::new(2, 1, 0), Rect::new(Point::new(-144.0, -100.0), Size::new(256.0, 256.0)))
+        );
+
+        // before the tiles arrive, they're drawn from the quarter of the root tile covering them.
+        surface.request(&viewport);
+        let mut builder = DisplayListBuilder::new();
+        surface.push(&mut builder, &viewport);
+        let drawn = images(builder);
+        assert_eq!(drawn.len(), 4);
+        assert_eq!(drawn[0].0, Some(Rect::new(Point::new(64.0, 0.0), Size::new(64.0, 64.0))));
+
+        update_until_loaded(&mut surface, &mut display);
+        let events = listener.peek();
+        assert_eq!(events.iter().filter(|event| matches!(event, TileEvent::Loaded(_))).count(), 2);
+        assert_eq!(events.iter().filter(|event| matches!(event, TileEvent::Failed(..))).count(), 2);
+        assert_eq!(surface.stage(TileId::new(2, 2, 1)), Some(TileStage::Failed));
+
+        let mut builder = DisplayListBuilder::new();
+        surface.push(&mut builder, &viewport);
+        let drawn = images(builder);
+        assert_eq!(drawn[0].0, None);
+        assert!(drawn[1].0.is_some());
+
+        // tiles drawn since the last update are kept, even over capacity.
+        surface.set_capacity(1);
+        surface.update(&mut display);
+        assert_eq!(surface.len(), 5);
+        assert_eq!(surface.stage(TileId::new(0, 0, 0)), Some(TileStage::Loaded));
+        surface.update(&mut display);
+        assert_eq!(surface.len(), 1);
+
+        surface.clear(&mut display);
+        assert!(surface.is_empty());
+
+        // high-DPI tiles are drawn from their own pixels, rather than `tile_size` pixels.
+        let resource = ResourceReference::Image(0);
+        surface.tiles.insert(
+            TileId::new(0, 0, 0),
+            Tile {
+                stage: TileStage::Loaded,
+                resource: Some(resource),
+                size: (512, 512),
+                last_used: 0,
+            },
+        );
+        assert_eq!(
+            surface.loaded_ancestor(TileId::new(2, 1, 0)),
+            Some((resource, Some(Rect::new(Point::new(128.0, 0.0), Size::new(128.0, 128.0)))))
+        );
+    }
+
+    #[test]
+    fn test_cancel_requests() {
+        let (release, blocked) =
+            (Arc::new(std::sync::Mutex::new(())), Arc::new(AtomicBool::new(false)));
+        let guard = release.lock().unwrap();
+        let mut surface = TiledSurface::new(
+            {
+                let (release, blocked) = (release.clone(), blocked.clone());
+                move |id| {
+                    blocked.store(true, Ordering::SeqCst);
+                    drop(release.lock().unwrap());
+                    tile(id)
+                }
+            },
+            256,
+        );
+        surface.set_max_workers(1);
+        let mut display = RecordingDisplay::new((100, 100));
+
+        let viewport = |x: f32| TileViewport {
+            bounds: Rect::new(Point::zero(), Size::new(512.0, 256.0)),
+            offset: Vector::new(x, 0.0),
+            zoom: 3,
+        };
+        surface.request(&viewport(0.0));
+        let start = Instant::now();
+        while !blocked.load(Ordering::SeqCst) {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(surface.queued(), 1);
+
+        // the second tile scrolled out of view before its turn came, unlike the first one which is being fetched.
+        surface.request(&viewport(1024.0));
+        assert_eq!(surface.stage(TileId::new(3, 1, 0)), None);
+        assert_eq!(surface.stage(TileId::new(3, 0, 0)), Some(TileStage::Pending));
+        assert_eq!(surface.queued(), 2);
+
+        drop(guard);
+        update_until_loaded(&mut surface, &mut display);
+        assert_eq!(surface.len(), 3);
+    }
+}
@@ -0,0 +1,117 @@
+//! Batching of command group updates.
+//!
+//! Large interfaces may push, modify and remove hundreds of command groups per frame. Within a
+//! [`DisplayTransaction`](DisplayTransaction), a display can defer these updates and apply them all at
+//! once when the transaction is committed, doing bookkeeping such as z-order sorting and bounds computation
+//! a single time.
+//!
+//! ```ignore
+//! let mut transaction = DisplayTransaction::new(&mut display);
+//! for widget in &mut widgets {
+//!     widget.draw(&mut *transaction, &mut aux);
+//! }
+//! transaction.commit()?;
+//! ```
+
+use {
+    super::*,
+    std::ops::{Deref, DerefMut},
+};
+
+/// An update deferred by a display within [`begin_update`](GraphicsDisplay::begin_update) and
+/// [`commit_update`](GraphicsDisplay::commit_update).
+pub(crate) enum PendingUpdate<C> {
+    /// Pushes a new command group, or replaces an existing one.
+    Push {
+        handle: CommandGroupHandle,
+        commands: C,
+        z_order: ZOrder,
+        protected: Option<bool>,
+        needs_maintain: Option<bool>,
+    },
+    Remove(CommandGroupHandle),
+    Label(CommandGroupHandle, Option<&'static str>),
+}
+
+/// Guard which batches command group updates to a display (see [`begin_update`](GraphicsDisplay::begin_update)).
+///
+/// The display is accessed through the guard. The updates are committed by [`commit`](DisplayTransaction::commit),
+/// or when the guard is dropped, in which case any error is discarded.
+pub struct DisplayTransaction<'a, D: Sized = DisplayCommand> {
+    display: &'a mut dyn GraphicsDisplay<D>,
+    committed: bool,
+}
+
+impl<'a, D> DisplayTransaction<'a, D> {
+    /// Begins batching updates to `display`.
+    pub fn new(display: &'a mut dyn GraphicsDisplay<D>) -> Self {
+        display.begin_update();
+        DisplayTransaction { display, committed: false }
+    }
+
+    /// Applies all the updates made through the guard.
+    ///
+    /// If this fails, none of the updates are applied.
+    pub fn commit(mut self) -> Result<(), error::DisplayError> {
+        self.committed = true;
+        self.display.commit_update()
+    }
+}
+
+impl<'a, D> Deref for DisplayTransaction<'a, D> {
+    type Target = dyn GraphicsDisplay<D> + 'a;
+
+    fn deref(&self) -> &Self::Target {
+        self.display
+    }
+}
+
+impl<'a, D> DerefMut for DisplayTransaction<'a, D> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.display
+    }
+}
+
+impl<'a, D> Drop for DisplayTransaction<'a, D> {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = self.display.commit_update();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, recording::RecordingDisplay};
+
+    #[test]
+    fn test_display_transaction() {
+        let mut display = RecordingDisplay::<i32>::default();
+        let a = display.push_command_group(&[1], ZOrder(0), None, None).unwrap();
+
+        let mut transaction = DisplayTransaction::new(&mut display);
+        let b = transaction.push_command_group(&[2], ZOrder(1), None, None).unwrap();
+        transaction.label_command_group(b, Some("b"));
+        transaction.modify_command_group(a, &[3], ZOrder(2), None, None).unwrap();
+        let c = transaction.push_command_group(&[4], ZOrder(0), None, None).unwrap();
+        transaction.remove_command_group(c);
+
+        // nothing is applied until the commit.
+        assert_eq!(transaction.get_command_group(a), Some(&[1][..]));
+        assert_eq!(transaction.get_command_group(b), None);
+        transaction.commit().unwrap();
+
+        assert_eq!(display.get_command_group(a), Some(&[3][..]));
+        assert_eq!(display.get_command_group(c), None);
+        assert_eq!(display.debug_dump()[0].label, Some("b"));
+        display.present(None).unwrap();
+        assert_eq!(display.presented(), &[2, 3]);
+
+        // dropping the guard commits as well.
+        {
+            let mut transaction = DisplayTransaction::new(&mut display);
+            transaction.remove_command_group(a);
+        }
+        assert_eq!(display.get_command_group(a), None);
+    }
+}
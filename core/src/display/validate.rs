@@ -0,0 +1,429 @@
+//! Validation of display lists before they reach a graphics backend.
+//!
+//! Backends (Skia in particular) may assert, panic or silently corrupt their draw state when given
+//! NaN/infinite geometry, empty clips or unbalanced save/restore sequences. The functions here reject
+//! such display lists up-front with a [`ValidationError`](crate::error::ValidationError) instead.
+
+use {super::*, crate::error::ValidationError};
+
+fn finite(values: &[f32]) -> bool {
+    values.iter().all(|v| v.is_finite())
+}
+
+fn finite_point(p: &Point) -> bool {
+    finite(&[p.x, p.y])
+}
+
+fn finite_vector(v: &Vector) -> bool {
+    finite(&[v.x, v.y])
+}
+
+fn finite_rect(r: &Rect) -> bool {
+    finite(&[r.origin.x, r.origin.y, r.size.width, r.size.height])
+}
+
+fn finite_color(c: &Color) -> bool {
+    finite(&[c.red, c.green, c.blue, c.alpha])
+}
+
+fn finite_path(path: &[VectorPathEvent]) -> bool {
+    path.iter().all(|event| match event {
+        VectorPathEvent::MoveTo { to } | VectorPathEvent::LineTo { to } => finite_point(to),
+        VectorPathEvent::QuadTo { control, to } => finite_point(control) && finite_point(to),
+        VectorPathEvent::ConicTo { control, to, weight } => {
+            finite_point(control) && finite_point(to) && weight.is_finite()
+        }
+        VectorPathEvent::CubicTo { c1, c2, to } => {
+            finite_point(c1) && finite_point(c2) && finite_point(to)
+        }
+        VectorPathEvent::ArcTo { center, radii, start_angle, sweep_angle } => {
+            finite_point(center) && finite_vector(radii) && finite(&[*start_angle, *sweep_angle])
+        }
+    })
+}
+
+fn check(index: usize, is_finite: bool, non_negative: bool) -> Result<(), ValidationError> {
+    if !is_finite {
+        Err(ValidationError::NonFinite { index })
+    } else if !non_negative {
+        Err(ValidationError::Negative { index })
+    } else {
+        Ok(())
+    }
+}
+
+fn check_style_color(index: usize, color: &StyleColor) -> Result<(), ValidationError> {
+    let is_finite = match color {
+        StyleColor::Color(color) | StyleColor::TaggedColor(color, _) => finite_color(color),
+        StyleColor::LinearGradient(gradient) | StyleColor::RadialGradient(gradient) => {
+            finite_point(&gradient.start)
+                && finite_point(&gradient.end)
+                && gradient.stops.iter().all(|(pos, color)| pos.is_finite() && finite_color(color))
+        }
+    };
+    check(index, is_finite, true)
+}
+
+fn check_stroke(index: usize, stroke: &GraphicsDisplayStroke) -> Result<(), ValidationError> {
+    check_style_color(index, &stroke.color)?;
+    check(
+        index,
+        finite(&[stroke.thickness, stroke.miter_limit]),
+        stroke.thickness >= 0.0 && stroke.miter_limit >= 0.0,
+    )
+}
+
+fn check_paint(index: usize, paint: &GraphicsDisplayPaint) -> Result<(), ValidationError> {
+    match paint {
+        GraphicsDisplayPaint::Fill(color) => check_style_color(index, color),
+        GraphicsDisplayPaint::Stroke(stroke) => check_stroke(index, stroke),
+        GraphicsDisplayPaint::Blend(paint, _) => check_paint(index, paint),
+    }
+}
+
+fn check_item(index: usize, item: &DisplayItem) -> Result<(), ValidationError> {
+    match item {
+        DisplayItem::Graphics(item) => match item {
+            GraphicsDisplayItem::Line { a, b, stroke } => {
+                check(index, finite_point(a) && finite_point(b), true)?;
+                check_stroke(index, stroke)
+            }
+            GraphicsDisplayItem::Rectangle { rect, paint } => {
+                check(index, finite_rect(rect), true)?;
+                check_paint(index, paint)
+            }
+            GraphicsDisplayItem::RoundRectangle { rect, radii, paint } => {
+                check(index, finite_rect(rect) && finite(radii), radii.iter().all(|r| *r >= 0.0))?;
+                check_paint(index, paint)
+            }
+            GraphicsDisplayItem::Ellipse { center, radii, paint } => {
+                check(
+                    index,
+                    finite_point(center) && finite_vector(radii),
+                    radii.x >= 0.0 && radii.y >= 0.0,
+                )?;
+                check_paint(index, paint)
+            }
+            GraphicsDisplayItem::Image { src, dst, .. } => check(
+                index,
+                finite_rect(dst) && src.as_ref().map(finite_rect).unwrap_or(true),
+                true,
+            ),
+            GraphicsDisplayItem::Path { path, paint, .. } => {
+                check(index, finite_path(path), true)?;
+                check_paint(index, paint)
+            }
+            GraphicsDisplayItem::Mesh { vertices, indices } => {
+                check(
+                    index,
+                    vertices
+                        .iter()
+                        .all(|(point, color)| finite_point(point) && finite_color(color)),
+                    true,
+                )?;
+                if indices.len() % 3 != 0 || indices.iter().any(|&i| i as usize >= vertices.len()) {
+                    Err(ValidationError::InvalidMesh { index })
+                } else {
+                    Ok(())
+                }
+            }
+        },
+        DisplayItem::Text(text) => {
+            let glyphs_finite = match &text.text {
+                DisplayText::Simple(_) => true,
+                DisplayText::Shaped(glyphs) => glyphs
+                    .iter()
+                    .all(|glyph| finite_vector(&glyph.advance) && finite_vector(&glyph.offset)),
+            };
+            check(
+                index,
+                text.size.is_finite() && finite_point(&text.bottom_left) && glyphs_finite,
+                text.size >= 0.0,
+            )?;
+            if let Some(stroke) = &text.stroke {
+                check_stroke(index, stroke)?;
+            }
+            if let Some(shadow) = &text.shadow {
+                check(
+                    index,
+                    finite_vector(&shadow.offset)
+                        && shadow.blur.is_finite()
+                        && finite_color(&shadow.color),
+                    shadow.blur >= 0.0,
+                )?;
+            }
+            check_style_color(index, &text.color)
+        }
+        DisplayItem::TextBlob(blob) => {
+            check(
+                index,
+                finite_point(&blob.bottom_left) && finite_rect(&blob.extents),
+                blob.extents.size.width >= 0.0 && blob.extents.size.height >= 0.0,
+            )?;
+            check_style_color(index, &blob.color)
+        }
+    }
+}
+
+fn check_filters(index: usize, filters: &[FilterOp]) -> Result<(), ValidationError> {
+    filters.iter().try_for_each(|filter| match filter {
+        FilterOp::Blur(x, y) => check(index, finite(&[*x, *y]), *x >= 0.0 && *y >= 0.0),
+        FilterOp::Invert => Ok(()),
+        FilterOp::Grayscale(amount) => check(index, amount.is_finite(), true),
+        FilterOp::Brightness(amount) | FilterOp::Contrast(amount) => {
+            check(index, amount.is_finite(), *amount >= 0.0)
+        }
+        FilterOp::HueRotate(angle) => check(index, angle.radians.is_finite(), true),
+        FilterOp::DropShadow { offset, sigma, color } => check(
+            index,
+            finite_vector(offset) && finite(&[sigma.0, sigma.1]) && finite_color(color),
+            sigma.0 >= 0.0 && sigma.1 >= 0.0,
+        ),
+        FilterOp::ColorMatrix(matrix) => check(index, finite(matrix), true),
+    })
+}
+
+fn check_clip(index: usize, clip: &DisplayClip) -> Result<(), ValidationError> {
+    match clip {
+        DisplayClip::Rectangle { rect, .. } => check(index, finite_rect(rect), true)?,
+        DisplayClip::RoundRectangle { rect, radii, .. } => {
+            check(index, finite_rect(rect) && finite(radii), radii.iter().all(|r| *r >= 0.0))?
+        }
+        DisplayClip::Ellipse { center, radii, .. } => {
+            check(index, finite_point(center) && finite_vector(radii), true)?
+        }
+        DisplayClip::Path { path, .. } => check(index, finite_path(path), true)?,
+    }
+
+    if clip.bounds().is_empty_or_negative() {
+        Err(ValidationError::EmptyClip { index })
+    } else {
+        Ok(())
+    }
+}
+
+/// Checks that a display list can be safely executed by any backend.
+///
+/// This rejects:
+/// - Non-finite (NaN or infinite) geometry, colors, transformations and filter parameters.
+/// - Negative stroke thicknesses, corner/ellipse radii, font sizes and blur sigmas.
+/// - Clips which cover no area (zero-sized or negative-sized).
+/// - Meshes with indices out of range, or a number of indices which isn't a multiple of three.
+/// - [`Extension`](DisplayCommand::Extension)s with non-finite bounds or an invalid fallback.
+/// - [`Restore`](DisplayCommand::Restore)s without a matching [`Save`](DisplayCommand::Save)/[`SaveLayer`](DisplayCommand::SaveLayer),
+///   as well as saves which are never restored.
+pub fn validate_display_list(display_list: &[DisplayCommand]) -> Result<(), ValidationError> {
+    let mut depth = 0usize;
+
+    for (index, command) in display_list.iter().enumerate() {
+        match command {
+            DisplayCommand::Item(item, filters) => {
+                check_item(index, item)?;
+                check_filters(index, filters)?;
+            }
+            DisplayCommand::BackdropFilter(clip, filters) => {
+                check_clip(index, clip)?;
+                check_filters(index, filters)?;
+            }
+            DisplayCommand::Clip(clip, _) => check_clip(index, clip)?,
+            DisplayCommand::Save => depth += 1,
+            DisplayCommand::SaveLayer(opacity, _) => {
+                check(index, opacity.is_finite(), true)?;
+                depth += 1;
+            }
+            DisplayCommand::Restore => {
+                depth = depth.checked_sub(1).ok_or(ValidationError::UnmatchedRestore { index })?;
+            }
+            DisplayCommand::Translate(v) | DisplayCommand::Scale(v) => {
+                check(index, finite_vector(v), true)?
+            }
+            DisplayCommand::Rotate(angle) => check(index, angle.radians.is_finite(), true)?,
+            DisplayCommand::Clear(color) => check(index, finite_color(color), true)?,
+            DisplayCommand::Hole(_, rect) => check(index, finite_rect(rect), true)?,
+            DisplayCommand::Extension(command) => {
+                check(index, command.bounds().as_ref().is_none_or(finite_rect), true)?;
+                if validate_display_list(&command.fallback()).is_err() {
+                    return Err(ValidationError::InvalidFallback { index });
+                }
+            }
+        }
+    }
+
+    if depth > 0 {
+        Err(ValidationError::UnclosedSave { count: depth })
+    } else {
+        Ok(())
+    }
+}
+
+/// Validated submission of display lists, implemented for every [`GraphicsDisplay`](GraphicsDisplay) of [`DisplayCommand`](DisplayCommand)s.
+pub trait ValidatedDisplay: GraphicsDisplay {
+    /// Validates `commands` (see [`validate_display_list`](validate_display_list)), then pushes them as a new command group.
+    ///
+    /// Nothing is pushed if validation fails, in which case the error is [`InvalidCommand`](crate::error::DisplayError::InvalidCommand).
+    fn validate_and_execute(
+        &mut self,
+        commands: &[DisplayCommand],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        needs_maintain: Option<bool>,
+    ) -> Result<CommandGroupHandle, error::DisplayError> {
+        validate_display_list(commands)?;
+        self.push_command_group(commands, z_order, protected, needs_maintain)
+    }
+
+    /// Validates `commands`, then replaces the contents of an existing command group with them.
+    ///
+    /// The command group is left untouched if validation fails.
+    fn validate_and_modify(
+        &mut self,
+        handle: CommandGroupHandle,
+        commands: &[DisplayCommand],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        needs_maintain: Option<bool>,
+    ) -> Result<(), error::DisplayError> {
+        validate_display_list(commands)?;
+        self.modify_command_group(handle, commands, z_order, protected, needs_maintain)
+    }
+}
+
+impl<T: GraphicsDisplay + ?Sized> ValidatedDisplay for T {}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, recording::RecordingDisplay};
+
+    fn rect(x: f32, y: f32, w: f32, h: f32) -> Rect {
+        Rect::new(Point::new(x, y), Size::new(w, h))
+    }
+
+    fn fill(rect: Rect) -> DisplayCommand {
+        DisplayCommand::Item(
+            DisplayItem::Graphics(GraphicsDisplayItem::Rectangle {
+                rect,
+                paint: GraphicsDisplayPaint::Fill(Color::new(1.0, 0.0, 0.0, 1.0).into()),
+            }),
+            vec![],
+        )
+    }
+
+    fn clip(rect: Rect) -> DisplayCommand {
+        DisplayCommand::Clip(DisplayClip::Rectangle { rect, antialias: false }, ClipOp::Intersect)
+    }
+
+    #[test]
+    fn test_validate_display_list() {
+        let valid = [
+            DisplayCommand::Save,
+            clip(rect(0.0, 0.0, 10.0, 10.0)),
+            fill(rect(0.0, 0.0, 5.0, 5.0)),
+            DisplayCommand::Restore,
+        ];
+        assert_eq!(validate_display_list(&valid), Ok(()));
+
+        assert_eq!(
+            validate_display_list(&[fill(rect(0.0, f32::NAN, 5.0, 5.0))]),
+            Err(ValidationError::NonFinite { index: 0 })
+        );
+        assert_eq!(
+            validate_display_list(&[
+                DisplayCommand::Save,
+                DisplayCommand::Translate(Vector::new(f32::INFINITY, 0.0)),
+                DisplayCommand::Restore
+            ]),
+            Err(ValidationError::NonFinite { index: 1 })
+        );
+        assert_eq!(
+            validate_display_list(&[DisplayCommand::Item(
+                DisplayItem::Graphics(GraphicsDisplayItem::Line {
+                    a: Point::new(0.0, 0.0),
+                    b: Point::new(1.0, 1.0),
+                    stroke: GraphicsDisplayStroke { thickness: -1.0, ..Default::default() },
+                }),
+                vec![]
+            )]),
+            Err(ValidationError::Negative { index: 0 })
+        );
+        let mesh = |indices| {
+            let white = Color::new(1.0, 1.0, 1.0, 1.0);
+            DisplayCommand::Item(
+                DisplayItem::Graphics(GraphicsDisplayItem::Mesh {
+                    vertices: vec![
+                        (Point::new(0.0, 0.0), white),
+                        (Point::new(1.0, 0.0), white),
+                        (Point::new(0.0, 1.0), white),
+                    ],
+                    indices,
+                }),
+                vec![],
+            )
+        };
+        assert_eq!(validate_display_list(&[mesh(vec![0, 1, 2])]), Ok(()));
+        assert_eq!(
+            validate_display_list(&[mesh(vec![0, 1, 3])]),
+            Err(ValidationError::InvalidMesh { index: 0 })
+        );
+        assert_eq!(
+            validate_display_list(&[mesh(vec![0, 1])]),
+            Err(ValidationError::InvalidMesh { index: 0 })
+        );
+        let shadowed = DisplayCommand::Item(
+            DisplayItem::Text(TextDisplayItem {
+                bottom_left: Point::new(0.0, 16.0),
+                shadow: Some(TextShadow {
+                    offset: Vector::new(1.0, 1.0),
+                    blur: -1.0,
+                    color: Color::new(0.0, 0.0, 0.0, 0.5),
+                }),
+                ..test_text("Text")
+            }),
+            vec![],
+        );
+        assert_eq!(validate_display_list(&[shadowed]), Err(ValidationError::Negative { index: 0 }));
+        assert_eq!(
+            validate_display_list(&[DisplayCommand::Save, clip(rect(0.0, 0.0, 0.0, 10.0))]),
+            Err(ValidationError::EmptyClip { index: 1 })
+        );
+        assert_eq!(
+            validate_display_list(&[DisplayCommand::Restore]),
+            Err(ValidationError::UnmatchedRestore { index: 0 })
+        );
+        assert_eq!(
+            validate_display_list(&[
+                DisplayCommand::Save,
+                DisplayCommand::SaveLayer(0.5, BlendMode::Normal)
+            ]),
+            Err(ValidationError::UnclosedSave { count: 2 })
+        );
+    }
+
+    #[test]
+    fn test_validate_and_execute() {
+        let mut display = RecordingDisplay::default();
+
+        let handle = display
+            .validate_and_execute(&[fill(rect(0.0, 0.0, 5.0, 5.0))], Default::default(), None, None)
+            .unwrap();
+
+        let err = display
+            .validate_and_execute(&[DisplayCommand::Restore], Default::default(), None, None)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            error::DisplayError::InvalidCommand(ValidationError::UnmatchedRestore { index: 0 })
+        ));
+        assert!(display
+            .validate_and_modify(
+                handle,
+                &[fill(rect(f32::NAN, 0.0, 5.0, 5.0))],
+                Default::default(),
+                None,
+                None
+            )
+            .is_err());
+
+        assert_eq!(display.command_group_count(), 1);
+        assert_eq!(display.get_command_group(handle).map(<[_]>::len), Some(1));
+    }
+}
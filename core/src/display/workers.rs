@@ -0,0 +1,186 @@
+//! Bounded pool of background threads, shared by the loaders of this module.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Condvar, Mutex},
+};
+
+struct Queue<J> {
+    jobs: VecDeque<J>,
+    workers: usize,
+    max_workers: usize,
+    idle: usize,
+    closed: bool,
+}
+
+struct Shared<J> {
+    queue: Mutex<Queue<J>>,
+    available: Condvar,
+    run: Box<dyn Fn(J) + Send + Sync>,
+}
+
+/// Runs jobs on at most [`max_workers`](WorkerPool::set_max_workers) threads, which are spawned as jobs are queued
+/// and exit once the pool is dropped.
+///
+/// Jobs which haven't started yet can be cancelled through [`retain`](WorkerPool::retain).
+pub(crate) struct WorkerPool<J: Send + 'static> {
+    shared: Arc<Shared<J>>,
+}
+
+impl<J: Send + 'static> WorkerPool<J> {
+    pub fn new(max_workers: usize, run: impl Fn(J) + Send + Sync + 'static) -> Self {
+        WorkerPool {
+            shared: Arc::new(Shared {
+                queue: Mutex::new(Queue {
+                    jobs: VecDeque::new(),
+                    workers: 0,
+                    max_workers: max_workers.max(1),
+                    idle: 0,
+                    closed: false,
+                }),
+                available: Condvar::new(),
+                run: Box::new(run),
+            }),
+        }
+    }
+
+    /// Sets how many threads may run jobs at once; threads beyond it exit as they finish their current job.
+    pub fn set_max_workers(&self, max_workers: usize) {
+        self.lock().max_workers = max_workers.max(1);
+        self.shared.available.notify_all();
+    }
+
+    /// Queues a job, spawning a thread for it if every thread is busy and there are fewer than the maximum.
+    pub fn push(&self, job: J) {
+        let mut queue = self.lock();
+        queue.jobs.push_back(job);
+        if queue.idle == 0 && queue.workers < queue.max_workers {
+            queue.workers += 1;
+            let shared = self.shared.clone();
+            std::thread::spawn(move || work(shared));
+        } else {
+            self.shared.available.notify_one();
+        }
+    }
+
+    /// Cancels the queued jobs for which `keep` returns `false`, returning them.
+    ///
+    /// Jobs already running aren't affected.
+    pub fn retain(&self, mut keep: impl FnMut(&J) -> bool) -> Vec<J> {
+        let mut queue = self.lock();
+        let mut cancelled = Vec::new();
+        for job in std::mem::take(&mut queue.jobs) {
+            if keep(&job) {
+                queue.jobs.push_back(job);
+            } else {
+                cancelled.push(job);
+            }
+        }
+        cancelled
+    }
+
+    /// Returns the number of jobs which haven't started yet.
+    pub fn queued(&self) -> usize {
+        self.lock().jobs.len()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Queue<J>> {
+        // jobs run outside of the lock, so it can't be poisoned by a panicking job.
+        self.shared.queue.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl<J: Send + 'static> Drop for WorkerPool<J> {
+    fn drop(&mut self) {
+        let mut queue = self.lock();
+        queue.closed = true;
+        queue.jobs.clear();
+        self.shared.available.notify_all();
+    }
+}
+
+fn work<J>(shared: Arc<Shared<J>>) {
+    loop {
+        let job = {
+            let mut queue = shared.queue.lock().unwrap_or_else(|err| err.into_inner());
+            loop {
+                if queue.closed || queue.workers > queue.max_workers {
+                    queue.workers -= 1;
+                    return;
+                }
+                if let Some(job) = queue.jobs.pop_front() {
+                    break job;
+                }
+                queue.idle += 1;
+                queue = shared.available.wait(queue).unwrap_or_else(|err| err.into_inner());
+                queue.idle -= 1;
+            }
+        };
+        // a panicking job only loses its own result, rather than a thread of the pool.
+        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| (shared.run)(job)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::{
+            sync::{atomic::*, mpsc},
+            time::Duration,
+        },
+    };
+
+    #[test]
+    fn test_worker_pool() {
+        let (running, peak) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let (sender, receiver) = mpsc::channel();
+        let pool = WorkerPool::new(2, {
+            let (running, peak) = (running.clone(), peak.clone());
+            move |job: u32| {
+                let count = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(count, Ordering::SeqCst);
+                std::thread::sleep(Duration::from_millis(5));
+                running.fetch_sub(1, Ordering::SeqCst);
+                sender.send(job).unwrap();
+            }
+        });
+        for job in 0..6 {
+            pool.push(job);
+        }
+        let mut done: Vec<_> = (0..6).map(|_| receiver.recv().unwrap()).collect();
+        done.sort_unstable();
+        assert_eq!(done, [0, 1, 2, 3, 4, 5]);
+        assert!(peak.load(Ordering::SeqCst) <= 2);
+    }
+
+    #[test]
+    fn test_cancel_queued_jobs() {
+        let (release, blocked) = (Arc::new(Mutex::new(())), mpsc::channel());
+        let (sender, receiver) = mpsc::channel();
+        let guard = release.lock().unwrap();
+        let pool = WorkerPool::new(1, {
+            let release = release.clone();
+            let blocked = Mutex::new(blocked.0);
+            move |job: u32| {
+                if job == 0 {
+                    blocked.lock().unwrap().send(()).unwrap();
+                    drop(release.lock().unwrap());
+                }
+                sender.send(job).unwrap();
+            }
+        });
+        for job in 0..6 {
+            pool.push(job);
+        }
+
+        // the only thread is stuck on the first job, so the others are still queued.
+        blocked.1.recv().unwrap();
+        assert_eq!(pool.retain(|job| job % 2 == 0), [1, 3, 5]);
+        assert_eq!(pool.queued(), 2);
+        drop(guard);
+        let done: Vec<_> = (0..3).map(|_| receiver.recv().unwrap()).collect();
+        assert_eq!(done, [0, 2, 4]);
+        assert!(receiver.recv_timeout(Duration::from_millis(20)).is_err());
+    }
+}
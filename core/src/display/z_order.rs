@@ -0,0 +1,219 @@
+//! Allocation of relative stacking orders.
+//!
+//! Rather than hard-coding [`ZOrder`](ZOrder) constants, widgets can allocate layers from a
+//! [`ZOrderAllocator`](ZOrderAllocator) relative to each other (e.g. a popup [`above`](ZOrderAllocator::above) its owner).
+//! Layers are spaced apart, so that inserting between two layers (fractional indexing) takes their midpoint
+//! and doesn't affect any other layer. Only when there's no room left are all layers renumbered,
+//! which is reported through [`take_changed`](ZOrderAllocator::take_changed).
+
+use super::ZOrder;
+
+/// Handle to a layer allocated by a [`ZOrderAllocator`](ZOrderAllocator).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ZLayer(u64);
+
+/// Default distance between layers appended at the top or bottom.
+const SPACING: i64 = 1 << 16;
+
+/// Hands out [`ZOrder`](ZOrder)s which are ordered relative to each other.
+#[derive(Debug, Clone, Default)]
+pub struct ZOrderAllocator {
+    // sorted by z-order, from bottom to top.
+    layers: Vec<(ZLayer, i32)>,
+    next_id: u64,
+    changed: Vec<ZLayer>,
+}
+
+impl ZOrderAllocator {
+    /// Creates a new allocator without any layers.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Allocates a layer above all other layers.
+    pub fn top(&mut self) -> ZLayer {
+        self.insert_at(self.layers.len())
+    }
+
+    /// Allocates a layer below all other layers.
+    pub fn bottom(&mut self) -> ZLayer {
+        self.insert_at(0)
+    }
+
+    /// Allocates a layer directly above `layer` (i.e. below the layer which was above `layer`).
+    ///
+    /// Returns `None` if `layer` doesn't exist.
+    pub fn above(&mut self, layer: ZLayer) -> Option<ZLayer> {
+        let index = self.index_of(layer)?;
+        Some(self.insert_at(index + 1))
+    }
+
+    /// Allocates a layer directly below `layer`.
+    ///
+    /// Returns `None` if `layer` doesn't exist.
+    pub fn below(&mut self, layer: ZLayer) -> Option<ZLayer> {
+        let index = self.index_of(layer)?;
+        Some(self.insert_at(index))
+    }
+
+    /// Allocates a layer between `a` and `b`, in either order.
+    ///
+    /// If other layers are between `a` and `b`, the new layer is placed directly above the lower one.
+    /// Returns `None` if either layer doesn't exist.
+    pub fn between(&mut self, a: ZLayer, b: ZLayer) -> Option<ZLayer> {
+        let lower = self.index_of(a)?.min(self.index_of(b)?);
+        Some(self.insert_at(lower + 1))
+    }
+
+    /// Moves an existing layer directly above `target`, keeping its handle.
+    ///
+    /// Returns `false` if either layer doesn't exist.
+    pub fn move_above(&mut self, layer: ZLayer, target: ZLayer) -> bool {
+        if layer == target {
+            return self.index_of(layer).is_some();
+        }
+        if self.index_of(target).is_none() || !self.remove(layer) {
+            return false;
+        }
+        let index = self.index_of(target).unwrap() + 1;
+        self.place(index, layer);
+        self.changed.push(layer);
+        true
+    }
+
+    /// Moves an existing layer directly below `target`, keeping its handle.
+    ///
+    /// Returns `false` if either layer doesn't exist.
+    pub fn move_below(&mut self, layer: ZLayer, target: ZLayer) -> bool {
+        if layer == target {
+            return self.index_of(layer).is_some();
+        }
+        if self.index_of(target).is_none() || !self.remove(layer) {
+            return false;
+        }
+        let index = self.index_of(target).unwrap();
+        self.place(index, layer);
+        self.changed.push(layer);
+        true
+    }
+
+    /// Frees a layer. Returns `false` if it didn't exist.
+    pub fn remove(&mut self, layer: ZLayer) -> bool {
+        match self.index_of(layer) {
+            Some(index) => {
+                self.layers.remove(index);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the z-order of a layer, to be used when pushing command groups.
+    pub fn z_order(&self, layer: ZLayer) -> Option<ZOrder> {
+        self.layers.iter().find(|(id, _)| *id == layer).map(|(_, z)| ZOrder(*z))
+    }
+
+    /// Returns all the layers from bottom to top.
+    pub fn layers(&self) -> impl Iterator<Item = ZLayer> + '_ {
+        self.layers.iter().map(|(id, _)| *id)
+    }
+
+    /// Returns (and clears) the existing layers whose z-order changed since the last call,
+    /// either because they were moved or because the layers had to be renumbered.
+    ///
+    /// Command groups of these layers need to be pushed again with their new z-order.
+    pub fn take_changed(&mut self) -> Vec<ZLayer> {
+        let mut changed = std::mem::take(&mut self.changed);
+        changed.sort();
+        changed.dedup();
+        changed.retain(|layer| self.index_of(*layer).is_some());
+        changed
+    }
+
+    fn index_of(&self, layer: ZLayer) -> Option<usize> {
+        self.layers.iter().position(|(id, _)| *id == layer)
+    }
+
+    fn insert_at(&mut self, index: usize) -> ZLayer {
+        let layer = ZLayer(self.next_id);
+        self.next_id += 1;
+        self.place(index, layer);
+        layer
+    }
+
+    fn place(&mut self, index: usize, layer: ZLayer) {
+        let lower = index.checked_sub(1).map(|i| self.layers[i].1 as i64);
+        let upper = self.layers.get(index).map(|(_, z)| *z as i64);
+        let z = match (lower, upper) {
+            (None, None) => Some(0),
+            (Some(lower), None) => Some(lower + SPACING),
+            (None, Some(upper)) => Some(upper - SPACING),
+            (Some(lower), Some(upper)) if upper - lower > 1 => Some(lower + (upper - lower) / 2),
+            _ => None,
+        }
+        .filter(|z| *z >= i32::MIN as i64 && *z <= i32::MAX as i64);
+
+        self.layers.insert(index, (layer, z.unwrap_or_default() as i32));
+        if z.is_none() {
+            self.renumber();
+        }
+    }
+
+    /// Spaces all the layers out evenly around zero.
+    fn renumber(&mut self) {
+        let count = self.layers.len() as i64;
+        let spacing = SPACING.min((u32::MAX as i64) / (count + 1)).max(1);
+        for (index, (layer, z)) in self.layers.iter_mut().enumerate() {
+            let new_z = ((index as i64 - count / 2) * spacing) as i32;
+            if *z != new_z {
+                *z = new_z;
+                self.changed.push(*layer);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_ordered(allocator: &ZOrderAllocator, expected: &[ZLayer]) {
+        assert_eq!(allocator.layers().collect::<Vec<_>>(), expected);
+        let orders: Vec<_> = expected.iter().map(|l| allocator.z_order(*l).unwrap()).collect();
+        assert!(orders.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_z_order_allocator() {
+        let mut allocator = ZOrderAllocator::new();
+        let panel = allocator.top();
+        let background = allocator.bottom();
+        let popup = allocator.above(panel).unwrap();
+        let shadow = allocator.between(panel, popup).unwrap();
+        let overlay = allocator.below(background).unwrap();
+        assert_ordered(&allocator, &[overlay, background, panel, shadow, popup]);
+        assert!(allocator.take_changed().is_empty());
+
+        // reordering only changes the moved layer.
+        let before = allocator.z_order(panel);
+        assert!(allocator.move_above(overlay, popup));
+        assert_ordered(&allocator, &[background, panel, shadow, popup, overlay]);
+        assert_eq!(allocator.z_order(panel), before);
+        assert_eq!(allocator.take_changed(), vec![overlay]);
+
+        // repeatedly inserting at the same spot eventually renumbers everything.
+        let mut last = panel;
+        for _ in 0..40 {
+            last = allocator.below(last).unwrap();
+        }
+        assert!(!allocator.take_changed().is_empty());
+        let layers: Vec<_> = allocator.layers().collect();
+        assert_eq!(layers.len(), 45);
+        assert_ordered(&allocator, &layers);
+
+        assert!(allocator.remove(shadow));
+        assert!(!allocator.remove(shadow));
+        assert_eq!(allocator.z_order(shadow), None);
+        assert_eq!(allocator.above(shadow), None);
+    }
+}
@@ -11,6 +11,8 @@ pub enum FontError {
     MatchingError(#[from] font_kit::error::SelectionError),
     #[error("failed to look up matching codepoint for character")]
     CodepointError,
+    #[error("the font data is unavailable")]
+    DataUnavailable,
 }
 
 /// An error within Skia and its interactions with OpenGL.
@@ -25,6 +27,49 @@ pub enum SkiaError {
     UnknownError,
 }
 
+/// An error in parsing a Lottie animation.
+#[derive(Error, Debug)]
+#[cfg(feature = "lottie")]
+pub enum LottieError {
+    #[error("{0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("the animation has no frames or a non-positive frame rate")]
+    InvalidTiming,
+}
+
+/// An error in loading an SVG.
+#[derive(Error, Debug)]
+#[cfg(feature = "svg")]
+pub enum SvgError {
+    #[error("{0}")]
+    IoError(#[from] std::io::Error),
+    #[error("{0}")]
+    ParseError(#[from] usvg::Error),
+}
+
+/// An error in compiling or running a script.
+#[derive(Error, Debug)]
+#[cfg(feature = "scripting")]
+pub enum ScriptError {
+    #[error("{0}")]
+    ParseError(#[from] rhai::ParseError),
+    #[error("{0}")]
+    EvalError(#[from] Box<rhai::EvalAltResult>),
+}
+
+/// An error in accessing a clipboard.
+#[derive(Error, Debug)]
+pub enum ClipboardError {
+    #[error("the clipboard doesn't contain the requested format")]
+    Empty,
+    #[error("the clipboard doesn't support the requested format")]
+    Unsupported,
+    #[error("the image data doesn't match its size")]
+    InvalidImage,
+    #[error("{0}")]
+    System(String),
+}
+
 /// An error associated with loading graphical resources.
 #[derive(Error, Debug)]
 pub enum ResourceError {
@@ -47,6 +92,84 @@ pub enum DisplayError {
     InvalidResource(u64),
     #[error("mismatched resource reference type (id: {0})")]
     MismatchedResource(u64),
+    #[error("the requested region is outside of the display")]
+    InvalidRegion,
+    #[error("{0} is not supported by this display")]
+    Unsupported(&'static str),
+    #[error("{0}")]
+    InvalidCommand(#[from] ValidationError),
+    #[error("{0}")]
+    FontError(#[from] FontError),
+    #[cfg(feature = "skia")]
+    #[error("{0}")]
+    SkiaError(#[from] SkiaError),
+    /// Any other backend-specific error.
     #[error("{0}")]
     InternalError(#[from] Box<dyn std::error::Error>),
 }
+
+/// A display list which would be rejected (or mishandled) by a graphics backend.
+///
+/// `index` is the position of the offending command within the display list.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationError {
+    #[error("command {index} contains non-finite (NaN or infinite) values")]
+    NonFinite { index: usize },
+    #[error("command {index} contains a negative size, radius or thickness")]
+    Negative { index: usize },
+    #[error(
+        "command {index} contains a mesh with an index out of range or an incomplete triangle"
+    )]
+    InvalidMesh { index: usize },
+    #[error("command {index} clips to an empty region")]
+    EmptyClip { index: usize },
+    #[error("the fallback of custom command {index} is not a valid display list")]
+    InvalidFallback { index: usize },
+    #[error("command {index} restores without a matching save")]
+    UnmatchedRestore { index: usize },
+    #[error("{count} save(s) are never restored")]
+    UnclosedSave { count: usize },
+}
+
+/// An error in the dependencies between updates of an update scheduler.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleError {
+    #[error("no update named \"{0}\"")]
+    UnknownUpdate(&'static str),
+    #[error("updates {0:?} depend on each other")]
+    Cycle(Vec<&'static str>),
+}
+
+/// An error in registering keyboard accelerators.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AcceleratorError {
+    #[error("\"{0}\" is not a valid key chord")]
+    InvalidChord(String),
+    #[error("{chord} is already bound to \"{existing}\" in context \"{context}\"")]
+    Conflict { chord: crate::accelerator::KeyChord, context: &'static str, existing: &'static str },
+}
+
+/// An error in triggering or managing commands of a [`CommandRegistry`](crate::command::CommandRegistry).
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CommandError {
+    #[error("no command with the id \"{0}\"")]
+    Unknown(String),
+    #[error("a command with the id \"{0}\" already exists")]
+    Duplicate(&'static str),
+    #[error("the command \"{0}\" is disabled")]
+    Disabled(&'static str),
+}
+
+/// An error in playing feedback sounds.
+#[cfg(feature = "audio")]
+#[derive(Error, Debug)]
+pub enum FeedbackError {
+    #[error("{0}")]
+    Stream(#[from] rodio::StreamError),
+    #[error("{0}")]
+    Play(#[from] rodio::PlayError),
+    #[error("{0}")]
+    Decode(#[from] rodio::decoder::DecoderError),
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+}
@@ -0,0 +1,297 @@
+//! Feedback (typically sounds) for named UI events, kept out of widget code.
+//!
+//! Widgets and the application emit event names (e.g. [`CLICK`](CLICK)) into [`Feedback::event`](Feedback::event),
+//! and the application decides what each name does by mapping it to a callback or, with the `audio` feature,
+//! a [`Sound`](Sound). Triggered commands can produce feedback too, by listening to
+//! [`CommandRegistry::triggered`](crate::command::CommandRegistry::triggered) and mapping command IDs.
+//!
+//! ```ignore
+//! let mut feedback = Feedback::new();
+//! feedback.set_player(AudioPlayer::new()?);
+//! feedback.map_sound(feedback::CLICK, Sound::load("click.wav")?);
+//! feedback.map_sound("delete", Sound::load("trash.wav")?);
+//! feedback.listen_to(&commands.triggered);
+//!
+//! // in a button:
+//! feedback_q.emit_owned(feedback::CLICK);
+//!
+//! // in update:
+//! commands.update(&mut app);
+//! feedback.update();
+//! ```
+
+use {
+    crate::{
+        event::{RcEventListener, RcEventQueue},
+        prelude::*,
+    },
+    std::{collections::HashMap, fmt},
+};
+
+#[cfg(feature = "audio")]
+use {
+    crate::error::FeedbackError,
+    rodio::{Decoder, OutputStream, OutputStreamHandle, Source},
+    std::{io::Cursor, path::Path, sync::Arc},
+};
+
+/// A button or similar control was activated.
+pub const CLICK: &str = "click";
+/// An action failed, or input was rejected.
+pub const ERROR: &str = "error";
+/// Something needs the attention of the user.
+pub const NOTIFICATION: &str = "notification";
+
+type FeedbackCallback = Box<dyn FnMut(&str)>;
+
+enum Response {
+    Callback(FeedbackCallback),
+    #[cfg(feature = "audio")]
+    Sound(Sound),
+}
+
+/// Maps named UI events to their feedback.
+///
+/// Names are played at most once per [`update`](Feedback::update), so that, for instance, a burst of errors
+/// doesn't stack the same sound several times.
+pub struct Feedback {
+    pub event: RcEventQueue<&'static str>,
+
+    responses: HashMap<String, Response>,
+    listeners: Vec<RcEventListener<&'static str>>,
+    enabled: bool,
+    #[cfg(feature = "audio")]
+    player: Option<AudioPlayer>,
+}
+
+impl Default for Feedback {
+    fn default() -> Self {
+        let event = RcEventQueue::new();
+        let listeners = vec![event.listen()];
+        Feedback {
+            event,
+            responses: HashMap::new(),
+            listeners,
+            enabled: true,
+            #[cfg(feature = "audio")]
+            player: None,
+        }
+    }
+}
+
+impl Feedback {
+    /// Creates an enabled mapping without any responses.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Calls `callback` with the name whenever `name` is played, replacing any previous response.
+    pub fn map(&mut self, name: impl Into<String>, callback: impl FnMut(&str) + 'static) {
+        self.responses.insert(name.into(), Response::Callback(Box::new(callback)));
+    }
+
+    /// Plays `sound` whenever `name` is played, replacing any previous response.
+    ///
+    /// Sounds are silent until a player is set through [`set_player`](Feedback::set_player).
+    #[cfg(feature = "audio")]
+    pub fn map_sound(&mut self, name: impl Into<String>, sound: Sound) {
+        self.responses.insert(name.into(), Response::Sound(sound));
+    }
+
+    /// Removes the response to `name`, returning `true` if there was one.
+    pub fn unmap(&mut self, name: &str) -> bool {
+        self.responses.remove(name).is_some()
+    }
+
+    /// Returns `true` if `name` has a response.
+    pub fn is_mapped(&self, name: &str) -> bool {
+        self.responses.contains_key(name)
+    }
+
+    /// Sets the player of mapped sounds.
+    #[cfg(feature = "audio")]
+    pub fn set_player(&mut self, player: AudioPlayer) {
+        self.player = Some(player);
+    }
+
+    /// Returns the player of mapped sounds, if any.
+    #[cfg(feature = "audio")]
+    pub fn player_mut(&mut self) -> Option<&mut AudioPlayer> {
+        self.player.as_mut()
+    }
+
+    /// Enables or disables (i.e. mutes) all feedback. Names emitted while disabled are dropped.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Plays the names emitted from now on into `queue` as well, e.g.
+    /// [`CommandRegistry::triggered`](crate::command::CommandRegistry::triggered).
+    pub fn listen_to(&mut self, queue: &RcEventQueue<&'static str>) {
+        self.listeners.push(queue.listen());
+    }
+
+    /// Plays the response to `name` immediately, returning `true` if there is one and feedback is enabled.
+    ///
+    /// Sounds which fail to play are ignored; feedback is never essential.
+    pub fn play(&mut self, name: &str) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        match self.responses.get_mut(name) {
+            Some(Response::Callback(callback)) => callback(name),
+            #[cfg(feature = "audio")]
+            Some(Response::Sound(sound)) => {
+                if let Some(player) = &self.player {
+                    let _ = player.play(sound);
+                }
+            }
+            None => return false,
+        }
+        true
+    }
+
+    /// Plays the names emitted since the last update, each at most once, in the order they were first emitted.
+    pub fn update(&mut self) {
+        let mut names: Vec<&'static str> = Vec::new();
+        for listener in &self.listeners {
+            for name in listener.peek() {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+        }
+        for name in names {
+            self.play(name);
+        }
+    }
+}
+
+impl fmt::Debug for Feedback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names: Vec<_> = self.responses.keys().collect();
+        names.sort();
+        f.debug_struct("Feedback")
+            .field("names", &names)
+            .field("listeners", &self.listeners.len())
+            .field("enabled", &self.enabled)
+            .finish()
+    }
+}
+
+/// Encoded audio (WAV), shared cheaply between clones.
+#[cfg(feature = "audio")]
+#[derive(Debug, Clone)]
+pub struct Sound(Arc<[u8]>);
+
+#[cfg(feature = "audio")]
+impl Sound {
+    /// Wraps encoded audio, failing if it can't be decoded.
+    pub fn from_data(data: impl Into<Arc<[u8]>>) -> Result<Self, FeedbackError> {
+        let sound = Sound(data.into());
+        sound.decode()?;
+        Ok(sound)
+    }
+
+    /// Reads and validates an audio file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, FeedbackError> {
+        Sound::from_data(std::fs::read(path)?)
+    }
+
+    fn decode(&self) -> Result<Decoder<Cursor<Arc<[u8]>>>, FeedbackError> {
+        Ok(Decoder::new(Cursor::new(self.0.clone()))?)
+    }
+}
+
+/// Plays sounds on the default output device, through [`rodio`](https://docs.rs/rodio).
+#[cfg(feature = "audio")]
+pub struct AudioPlayer {
+    _stream: OutputStream,
+    handle: OutputStreamHandle,
+    volume: f32,
+}
+
+#[cfg(feature = "audio")]
+impl AudioPlayer {
+    /// Opens the default output device, at full volume.
+    pub fn new() -> Result<Self, FeedbackError> {
+        let (stream, handle) = OutputStream::try_default()?;
+        Ok(AudioPlayer { _stream: stream, handle, volume: 1.0 })
+    }
+
+    /// Sets the volume of sounds played from now on, where `1.0` is the original volume.
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume.max(0.0);
+    }
+
+    #[inline]
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    /// Starts playing a sound, mixed with any sounds still playing.
+    pub fn play(&self, sound: &Sound) -> Result<(), FeedbackError> {
+        let source = sound.decode()?.convert_samples::<f32>().amplify(self.volume);
+        Ok(self.handle.play_raw(source)?)
+    }
+}
+
+#[cfg(feature = "audio")]
+impl fmt::Debug for AudioPlayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AudioPlayer").field("volume", &self.volume).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::command::{Command, CommandRegistry},
+        std::{cell::RefCell, rc::Rc},
+    };
+
+    #[test]
+    fn test_feedback() {
+        let played = Rc::new(RefCell::new(Vec::new()));
+        let mut feedback = Feedback::new();
+        for name in [CLICK, ERROR, "save"] {
+            let played = played.clone();
+            feedback.map(name, move |name| played.borrow_mut().push(name.to_string()));
+        }
+
+        let mut commands = CommandRegistry::new();
+        commands.add(Command::new("save", "Save").with_run(|_: &mut ()| {})).unwrap();
+        commands.add(Command::new("quit", "Quit")).unwrap();
+        feedback.listen_to(&commands.triggered);
+
+        // repeated names play once per update; unmapped names are ignored.
+        feedback.event.emit_owned(ERROR);
+        feedback.event.emit_owned(CLICK);
+        feedback.event.emit_owned(ERROR);
+        feedback.event.emit_owned(NOTIFICATION);
+        commands.trigger("save", &mut ()).unwrap();
+        commands.trigger("quit", &mut ()).unwrap();
+        feedback.update();
+        assert_eq!(*played.borrow(), ["error", "click", "save"]);
+
+        played.borrow_mut().clear();
+        feedback.set_enabled(false);
+        feedback.event.emit_owned(CLICK);
+        feedback.update();
+        assert!(!feedback.play(CLICK));
+        feedback.set_enabled(true);
+        feedback.update();
+        assert!(played.borrow().is_empty());
+
+        assert!(feedback.unmap(CLICK));
+        assert!(!feedback.play(CLICK));
+        assert!(feedback.play("save"));
+        assert_eq!(*played.borrow(), ["save"]);
+    }
+}
@@ -0,0 +1,151 @@
+//! String catalogs, for translating UI text and switching the language at runtime.
+//!
+//! Widgets look their text up by key, and listen to [`Catalog::event`](Catalog::event) to look it up again
+//! (and repaint) when the language is switched, rather than the application tracking every translated label:
+//!
+//! ```ignore
+//! let mut catalog = Catalog::new("en-US");
+//! catalog.insert("en-US", "greeting", "Hello, {name}!");
+//! catalog.insert("de-DE", "greeting", "Hallo, {name}!");
+//! let locale_changes = catalog.event.listen();
+//!
+//! // in update:
+//! if !locale_changes.peek().is_empty() {
+//!     self.label.set_text(catalog.format("greeting", &[("name", &self.name)]));
+//! }
+//! ```
+
+use {
+    crate::{event::RcEventQueue, intl::Locale, prelude::*},
+    std::collections::HashMap,
+};
+
+/// Events emitted by a [`Catalog`](Catalog).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CatalogEvent {
+    /// The language was switched; contains the new language tag.
+    LocaleChanged(String),
+}
+
+/// Translated strings of any number of languages, keyed by ID, along with the current language.
+#[derive(Debug)]
+pub struct Catalog {
+    pub event: RcEventQueue<CatalogEvent>,
+    strings: HashMap<String, HashMap<String, String>>,
+    language: String,
+    fallback: Option<String>,
+}
+
+impl Catalog {
+    /// Creates an empty catalog with `language` as the current language.
+    pub fn new(language: impl Into<String>) -> Self {
+        Catalog {
+            event: RcEventQueue::new(),
+            strings: HashMap::new(),
+            language: language.into(),
+            fallback: None,
+        }
+    }
+
+    /// Looks up strings missing from the current language in `language` instead.
+    pub fn with_fallback(mut self, language: impl Into<String>) -> Self {
+        self.fallback = Some(language.into());
+        self
+    }
+
+    /// Adds (or replaces) the translation of `key` into `language`.
+    pub fn insert(
+        &mut self,
+        language: impl Into<String>,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) {
+        self.strings.entry(language.into()).or_default().insert(key.into(), value.into());
+    }
+
+    /// Adds (or replaces) many translations into `language` at once.
+    pub fn extend<K: Into<String>, V: Into<String>>(
+        &mut self,
+        language: impl Into<String>,
+        strings: impl IntoIterator<Item = (K, V)>,
+    ) {
+        self.strings
+            .entry(language.into())
+            .or_default()
+            .extend(strings.into_iter().map(|(key, value)| (key.into(), value.into())));
+    }
+
+    /// Returns the tags of every language with at least one string, in no particular order.
+    pub fn languages(&self) -> impl Iterator<Item = &str> + '_ {
+        self.strings.keys().map(String::as_str)
+    }
+
+    /// Returns the tag of the current language.
+    #[inline]
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Returns the built-in formatting conventions of the current language, if there are any.
+    pub fn locale(&self) -> Option<Locale> {
+        Locale::from_tag(&self.language)
+    }
+
+    /// Switches the current language, emitting [`LocaleChanged`](CatalogEvent::LocaleChanged) if it changed.
+    pub fn set_language(&mut self, language: impl Into<String>) {
+        let language = language.into();
+        if self.language != language {
+            self.language = language;
+            self.event.emit_owned(CatalogEvent::LocaleChanged(self.language.clone()));
+        }
+    }
+
+    /// Returns the string for `key` in the current language, or in the fallback language, if there is any.
+    pub fn try_get(&self, key: &str) -> Option<&str> {
+        let lookup = |language: &str| self.strings.get(language)?.get(key).map(String::as_str);
+        lookup(&self.language).or_else(|| lookup(self.fallback.as_ref()?))
+    }
+
+    /// Returns the string for `key` as per [`try_get`](Catalog::try_get), or the key itself if it has no translation,
+    /// so that missing translations stand out without breaking the UI.
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.try_get(key).unwrap_or(key)
+    }
+
+    /// Returns the string for `key` as per [`get`](Catalog::get), with every `{name}` placeholder replaced by the
+    /// value paired with `name` in `args`.
+    pub fn format(&self, key: &str, args: &[(&str, &str)]) -> String {
+        args.iter().fold(self.get(key).to_owned(), |text, (name, value)| {
+            text.replace(&format!("{{{}}}", name), value)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalog() {
+        let mut catalog = Catalog::new("en-US").with_fallback("en-US");
+        catalog.extend("en-US", vec![("greeting", "Hello, {name}!"), ("quit", "Quit")]);
+        catalog.insert("de-DE", "greeting", "Hallo, {name}!");
+        let listener = catalog.event.listen();
+
+        assert_eq!(catalog.format("greeting", &[("name", "Ada")]), "Hello, Ada!");
+        catalog.set_language("de-DE");
+        catalog.set_language("de-DE");
+        assert_eq!(listener.peek(), &[CatalogEvent::LocaleChanged("de-DE".into())]);
+        assert_eq!(catalog.format("greeting", &[("name", "Ada")]), "Hallo, Ada!");
+        assert_eq!(catalog.locale().unwrap().decimal_separator, ',');
+
+        // missing strings fall back to the fallback language, and then to the key.
+        assert_eq!(catalog.get("quit"), "Quit");
+        assert_eq!(catalog.try_get("missing"), None);
+        assert_eq!(catalog.get("missing"), "missing");
+
+        let mut languages: Vec<_> = catalog.languages().collect();
+        languages.sort_unstable();
+        assert_eq!(languages, ["de-DE", "en-US"]);
+    }
+}
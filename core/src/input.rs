@@ -0,0 +1,602 @@
+//! Standard window input events, as emitted into the global event queue by the application runner
+//! and the testing [`Harness`](crate::testing::Harness).
+
+use {
+    crate::{
+        clipboard::ClipboardAction,
+        display::{Point, Size, Vector},
+    },
+    std::{collections::HashMap, fmt, ops::BitOr, path::PathBuf, str::FromStr},
+};
+
+/// Mouse buttons.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    Other(u16),
+}
+
+/// Physical keys, identified by their meaning on a US layout.
+///
+/// Text input should use [`Character`](WindowEvent::Character) instead; keys are meant for shortcuts and navigation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    Key0,
+    Key1,
+    Key2,
+    Key3,
+    Key4,
+    Key5,
+    Key6,
+    Key7,
+    Key8,
+    Key9,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    Escape,
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Insert,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Left,
+    Right,
+    Up,
+    Down,
+    Space,
+    /// Any other key, by platform scancode.
+    Other(u32),
+}
+
+const NAMED_KEYS: &[(Key, &str)] = &[
+    (Key::F1, "F1"),
+    (Key::F2, "F2"),
+    (Key::F3, "F3"),
+    (Key::F4, "F4"),
+    (Key::F5, "F5"),
+    (Key::F6, "F6"),
+    (Key::F7, "F7"),
+    (Key::F8, "F8"),
+    (Key::F9, "F9"),
+    (Key::F10, "F10"),
+    (Key::F11, "F11"),
+    (Key::F12, "F12"),
+    (Key::Escape, "Escape"),
+    (Key::Enter, "Enter"),
+    (Key::Tab, "Tab"),
+    (Key::Backspace, "Backspace"),
+    (Key::Delete, "Delete"),
+    (Key::Insert, "Insert"),
+    (Key::Home, "Home"),
+    (Key::End, "End"),
+    (Key::PageUp, "PageUp"),
+    (Key::PageDown, "PageDown"),
+    (Key::Left, "Left"),
+    (Key::Right, "Right"),
+    (Key::Up, "Up"),
+    (Key::Down, "Down"),
+    (Key::Space, "Space"),
+];
+
+const LETTER_KEYS: [Key; 26] = [
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+];
+
+const DIGIT_KEYS: [Key; 10] = [
+    Key::Key0,
+    Key::Key1,
+    Key::Key2,
+    Key::Key3,
+    Key::Key4,
+    Key::Key5,
+    Key::Key6,
+    Key::Key7,
+    Key::Key8,
+    Key::Key9,
+];
+
+impl fmt::Display for Key {
+    /// Formats the key as in a shortcut (e.g. `S`, `5` or `PageUp`).
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(index) = LETTER_KEYS.iter().position(|key| key == self) {
+            write!(f, "{}", (b'A' + index as u8) as char)
+        } else if let Some(index) = DIGIT_KEYS.iter().position(|key| key == self) {
+            write!(f, "{}", index)
+        } else if let Some((_, name)) = NAMED_KEYS.iter().find(|(key, _)| key == self) {
+            f.write_str(name)
+        } else if let Key::Other(scancode) = self {
+            write!(f, "#{}", scancode)
+        } else {
+            unreachable!()
+        }
+    }
+}
+
+impl FromStr for Key {
+    type Err = ();
+
+    /// Parses a key as formatted through `Display`, ignoring case. `Esc`, `Return` and `Del` are accepted as well.
+    fn from_str(s: &str) -> Result<Self, ()> {
+        let bytes = s.as_bytes();
+        if bytes.len() == 1 && bytes[0].is_ascii_alphabetic() {
+            return Ok(LETTER_KEYS[(bytes[0].to_ascii_uppercase() - b'A') as usize]);
+        }
+        if bytes.len() == 1 && bytes[0].is_ascii_digit() {
+            return Ok(DIGIT_KEYS[(bytes[0] - b'0') as usize]);
+        }
+        if let Some(scancode) = s.strip_prefix('#') {
+            return scancode.parse().map(Key::Other).map_err(|_| ());
+        }
+        match s.to_ascii_lowercase().as_str() {
+            "esc" => Ok(Key::Escape),
+            "return" => Ok(Key::Enter),
+            "del" => Ok(Key::Delete),
+            name => NAMED_KEYS
+                .iter()
+                .find(|(_, key_name)| key_name.eq_ignore_ascii_case(name))
+                .map(|(key, _)| *key)
+                .ok_or(()),
+        }
+    }
+}
+
+/// Modifier keys held down along with a key.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    /// The Windows key, or the Command key on macOS.
+    pub logo: bool,
+}
+
+impl Modifiers {
+    pub const NONE: Modifiers = Modifiers { ctrl: false, shift: false, alt: false, logo: false };
+    pub const CTRL: Modifiers = Modifiers { ctrl: true, ..Modifiers::NONE };
+    pub const SHIFT: Modifiers = Modifiers { shift: true, ..Modifiers::NONE };
+    pub const ALT: Modifiers = Modifiers { alt: true, ..Modifiers::NONE };
+    pub const LOGO: Modifiers = Modifiers { logo: true, ..Modifiers::NONE };
+}
+
+impl BitOr for Modifiers {
+    type Output = Modifiers;
+
+    fn bitor(self, rhs: Modifiers) -> Modifiers {
+        Modifiers {
+            ctrl: self.ctrl || rhs.ctrl,
+            shift: self.shift || rhs.shift,
+            alt: self.alt || rhs.alt,
+            logo: self.logo || rhs.logo,
+        }
+    }
+}
+
+/// Stage in the lifetime of a touch point.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TouchPhase {
+    /// A finger started touching the screen.
+    Start,
+    /// A finger moved while touching the screen.
+    Move,
+    /// A finger was lifted from the screen.
+    End,
+    /// The touch was aborted by the system (e.g. because the window lost focus); no action should be taken.
+    Cancel,
+}
+
+/// A change of a single touch point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TouchEvent {
+    /// Identifies the touch point from its [`Start`](TouchPhase::Start) until its [`End`](TouchPhase::End)
+    /// or [`Cancel`](TouchPhase::Cancel). Ids may be reused afterwards.
+    pub id: u64,
+    pub phase: TouchPhase,
+    pub position: Point,
+    /// Force of the touch, from 0 to 1, if the device reports it.
+    pub force: Option<f32>,
+}
+
+/// Stage in the interaction of a pen with the screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PenPhase {
+    /// The pen came within range of the screen, without touching it.
+    Enter,
+    /// The pen touched the screen.
+    Down,
+    /// The pen moved, either touching the screen or hovering over it.
+    Move,
+    /// The pen was lifted from the screen, but is still within range.
+    Up,
+    /// The pen went out of range of the screen.
+    Leave,
+}
+
+/// Buttons on the barrel of a pen.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PenButtons {
+    pub primary: bool,
+    pub secondary: bool,
+}
+
+/// A change of the state of a pen (stylus).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PenEvent {
+    pub phase: PenPhase,
+    pub position: Point,
+    /// Pressure of the tip, from 0 (not touching) to 1.
+    pub pressure: f32,
+    /// Tilt of the pen away from perpendicular to the screen along the x and y axes, in degrees (-90 to 90).
+    /// Positive values tilt towards the right and the bottom of the screen respectively.
+    pub tilt: Vector,
+    /// Clockwise rotation of the pen around its own axis, in degrees (0 to 360).
+    pub twist: f32,
+    pub buttons: PenButtons,
+    /// Whether the pen is inverted, i.e. the eraser end is used.
+    pub eraser: bool,
+}
+
+/// Window input.
+///
+/// Positions and sizes are in logical pixels, i.e. the window coordinates divided by its scale factor.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowEvent {
+    /// A mouse button was pressed at the cursor position.
+    MousePress(Point, MouseButton),
+    /// A mouse button was released at the cursor position.
+    MouseRelease(Point, MouseButton),
+    /// The cursor moved.
+    MouseMove(Point),
+    /// A unicode character was typed.
+    Character(char),
+    /// A key was pressed (or repeated while held down), with the modifiers held at the time.
+    KeyPress(Key, Modifiers),
+    /// A key was released.
+    KeyRelease(Key, Modifiers),
+    /// A clipboard shortcut was pressed, to be handled by the focused widget.
+    ///
+    /// This is emitted right after the [`KeyPress`](WindowEvent::KeyPress) of the shortcut, see
+    /// [`ClipboardAction::from_chord`](crate::clipboard::ClipboardAction::from_chord).
+    Clipboard(ClipboardAction),
+    /// The window gained (`true`) or lost (`false`) focus.
+    Focus(bool),
+    /// The window was resized. The display has already been resized when this is emitted.
+    ///
+    /// This is also emitted right before [`ScaleChanged`](WindowEvent::ScaleChanged), as the logical size changes
+    /// along with the scale factor.
+    Resize(Size),
+    /// The scale factor of the window changed, typically because it moved to a monitor with a different DPI.
+    ///
+    /// The display has already been resized to the new physical size when this is emitted.
+    /// Anything rendered at a size derived from the old scale factor (most notably text, see
+    /// [`FontInfo::scaled_metrics`](crate::display::FontInfo::scaled_metrics)) should be repainted, see
+    /// [`CommandGroup::repaint_all`](crate::display::CommandGroup::repaint_all).
+    ScaleChanged(f32),
+    /// A touch point changed. Touches are not converted to mouse events.
+    Touch(TouchEvent),
+    /// A pen changed its position, pressure, tilt or buttons.
+    Pen(PenEvent),
+}
+
+/// Generates [`WindowEvent::KEYS`](WindowEvent::KEYS) and [`WindowEvent::key`](WindowEvent::key) from a single list,
+/// so that they can't diverge; the match is exhaustive, so a new variant doesn't compile until it's given a key.
+macro_rules! window_event_keys {
+    ($($variant:ident => $key:literal),* $(,)?) => {
+        impl WindowEvent {
+            /// Key of every variant, in declaration order, as used by verb graph handlers (e.g. `"mouse_press"`).
+            pub const KEYS: &'static [&'static str] = &[$($key),*];
+
+            /// Returns the key of the variant, see [`KEYS`](WindowEvent::KEYS).
+            pub fn key(&self) -> &'static str {
+                match self {
+                    $(WindowEvent::$variant(..) => $key),*
+                }
+            }
+        }
+    };
+}
+
+window_event_keys! {
+    MousePress => "mouse_press",
+    MouseRelease => "mouse_release",
+    MouseMove => "mouse_move",
+    Character => "character",
+    KeyPress => "key_press",
+    KeyRelease => "key_release",
+    Clipboard => "clipboard",
+    Focus => "focus",
+    Resize => "resize",
+    ScaleChanged => "scale_changed",
+    Touch => "touch",
+    Pen => "pen",
+}
+
+/// Files dragged into the window from another application, e.g. a file manager.
+///
+/// These aren't emitted into the global [`WindowEvent`](WindowEvent) queue, but delivered to the widget under
+/// the cursor through [`Widget::file_drop`](crate::widget::Widget::file_drop), see
+/// [`FileDropRouter`](crate::widget::FileDropRouter). Positions are in the coordinate space of the parent of the
+/// receiving widget, like its [`bounds`](crate::widget::Widget::bounds).
+///
+/// Windowing libraries don't necessarily report the cursor while files are dragged (e.g. winit only reports the
+/// paths), in which case the position is only the last one reported before the drag, typically where the cursor
+/// entered the window; `reclutch::app::App` is subject to this.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileDropEvent {
+    /// Files are dragged over the window.
+    Hovered(Vec<PathBuf>, Point),
+    /// Files were dropped onto the window.
+    Dropped(Vec<PathBuf>, Point),
+    /// The files were dragged out of the window, or the drag was aborted.
+    Cancelled,
+}
+
+impl FileDropEvent {
+    /// Returns the paths of the hovered or dropped files.
+    pub fn paths(&self) -> &[PathBuf] {
+        match self {
+            FileDropEvent::Hovered(paths, _) | FileDropEvent::Dropped(paths, _) => paths,
+            FileDropEvent::Cancelled => &[],
+        }
+    }
+
+    /// Returns the position of the cursor, or `None` if the drag was cancelled.
+    pub fn position(&self) -> Option<Point> {
+        match self {
+            FileDropEvent::Hovered(_, position) | FileDropEvent::Dropped(_, position) => {
+                Some(*position)
+            }
+            FileDropEvent::Cancelled => None,
+        }
+    }
+
+    /// Returns the event with its position replaced.
+    pub fn with_position(&self, position: Point) -> Self {
+        match self {
+            FileDropEvent::Hovered(paths, _) => FileDropEvent::Hovered(paths.clone(), position),
+            FileDropEvent::Dropped(paths, _) => FileDropEvent::Dropped(paths.clone(), position),
+            FileDropEvent::Cancelled => FileDropEvent::Cancelled,
+        }
+    }
+}
+
+/// A touch point tracked by a [`TouchTracker`](TouchTracker).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Touch {
+    /// Position at which the touch started.
+    pub start: Point,
+    pub position: Point,
+    /// Position before the last move.
+    pub previous: Point,
+    pub force: Option<f32>,
+}
+
+impl Touch {
+    /// Returns the distance moved by the last move.
+    pub fn delta(&self) -> Vector {
+        self.position - self.previous
+    }
+}
+
+/// Tracks the touch points currently on the screen from [`TouchEvent`](TouchEvent)s,
+/// to implement multi-touch gestures such as panning and pinching.
+///
+/// ```ignore
+/// let spread = tracker.spread();
+/// tracker.handle(&touch_event);
+/// if tracker.len() == 2 && spread > 0.0 {
+///     zoom *= tracker.spread() / spread;
+/// }
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct TouchTracker {
+    touches: HashMap<u64, Touch>,
+}
+
+impl TouchTracker {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Updates the touch points. Returns the touch point the event applies to, unless it ended
+    /// (or its start was never seen).
+    pub fn handle(&mut self, event: &TouchEvent) -> Option<&Touch> {
+        match event.phase {
+            TouchPhase::Start => {
+                let touch = Touch {
+                    start: event.position,
+                    position: event.position,
+                    previous: event.position,
+                    force: event.force,
+                };
+                self.touches.insert(event.id, touch);
+                self.touches.get(&event.id)
+            }
+            TouchPhase::Move => self.touches.get_mut(&event.id).map(|touch| {
+                touch.previous = touch.position;
+                touch.position = event.position;
+                touch.force = event.force;
+                &*touch
+            }),
+            TouchPhase::End | TouchPhase::Cancel => {
+                self.touches.remove(&event.id);
+                None
+            }
+        }
+    }
+
+    /// Updates the touch points from a window event, ignoring other events.
+    ///
+    /// Every touch point is removed once the window loses focus, since the end of the touches won't be reported.
+    pub fn handle_window_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::Touch(touch) => {
+                self.handle(touch);
+            }
+            WindowEvent::Focus(false) => self.clear(),
+            _ => {}
+        }
+    }
+
+    /// Returns the touch point with a given id.
+    pub fn get(&self, id: u64) -> Option<&Touch> {
+        self.touches.get(&id)
+    }
+
+    /// Returns the touch points currently on the screen, along with their ids, in no particular order.
+    pub fn touches(&self) -> impl Iterator<Item = (u64, &Touch)> {
+        self.touches.iter().map(|(id, touch)| (*id, touch))
+    }
+
+    /// Returns the number of touch points currently on the screen.
+    pub fn len(&self) -> usize {
+        self.touches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.touches.is_empty()
+    }
+
+    /// Forgets every touch point.
+    pub fn clear(&mut self) {
+        self.touches.clear();
+    }
+
+    /// Returns the average position of the touch points, if there are any.
+    pub fn centroid(&self) -> Option<Point> {
+        if self.touches.is_empty() {
+            return None;
+        }
+        let sum = self
+            .touches
+            .values()
+            .fold(Vector::zero(), |sum, touch| sum + touch.position.to_vector());
+        Some((sum / self.touches.len() as f32).to_point())
+    }
+
+    /// Returns the average distance of the touch points from their centroid, or 0 without touch points.
+    ///
+    /// The ratio between the spread after and before a move is the scale factor of a pinch gesture.
+    pub fn spread(&self) -> f32 {
+        self.centroid().map_or(0.0, |centroid| {
+            let sum: f32 =
+                self.touches.values().map(|touch| (touch.position - centroid).length()).sum();
+            sum / self.touches.len() as f32
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touch(id: u64, phase: TouchPhase, x: f32, y: f32) -> TouchEvent {
+        TouchEvent { id, phase, position: Point::new(x, y), force: None }
+    }
+
+    #[test]
+    fn test_window_event_keys() {
+        assert_eq!(WindowEvent::Focus(true).key(), "focus");
+        assert!(WindowEvent::KEYS.contains(&WindowEvent::Character('a').key()));
+
+        let mut keys = WindowEvent::KEYS.to_vec();
+        keys.sort_unstable();
+        keys.dedup();
+        assert_eq!(keys.len(), WindowEvent::KEYS.len());
+    }
+
+    #[test]
+    fn test_touch_tracker() {
+        let mut tracker = TouchTracker::new();
+        assert_eq!(tracker.centroid(), None);
+        assert_eq!(tracker.spread(), 0.0);
+
+        tracker.handle(&touch(1, TouchPhase::Start, 0.0, 0.0));
+        tracker.handle(&touch(2, TouchPhase::Start, 10.0, 0.0));
+        assert_eq!(tracker.len(), 2);
+        assert_eq!(tracker.centroid(), Some(Point::new(5.0, 0.0)));
+        assert_eq!(tracker.spread(), 5.0);
+
+        // pinching out.
+        let moved = *tracker.handle(&touch(2, TouchPhase::Move, 20.0, 0.0)).unwrap();
+        assert_eq!(moved.start, Point::new(10.0, 0.0));
+        assert_eq!(moved.delta(), Vector::new(10.0, 0.0));
+        assert_eq!(tracker.spread(), 10.0);
+
+        // moves of unknown touches are ignored.
+        assert!(tracker.handle(&touch(3, TouchPhase::Move, 0.0, 0.0)).is_none());
+        assert!(tracker.handle(&touch(1, TouchPhase::End, 0.0, 0.0)).is_none());
+        assert_eq!(tracker.touches().map(|(id, _)| id).collect::<Vec<_>>(), &[2]);
+
+        tracker.handle_window_event(&WindowEvent::Touch(touch(1, TouchPhase::Start, 0.0, 0.0)));
+        assert_eq!(tracker.len(), 2);
+        tracker.handle_window_event(&WindowEvent::Focus(false));
+        assert!(tracker.is_empty());
+    }
+}
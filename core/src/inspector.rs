@@ -0,0 +1,318 @@
+//! Live widget tree inspection for debugging.
+//!
+//! An [`Inspector`](Inspector) serializes the widget tree to JSON every frame and serves the latest
+//! snapshot over a local TCP socket as plain HTTP, so it can be viewed from a browser, `curl` or
+//! an external tool while the application is running.
+//!
+//! ```ignore
+//! let mut inspector = Inspector::bind("127.0.0.1:9229")?;
+//!
+//! // every frame:
+//! inspector.update(&root);
+//! ```
+
+use {
+    crate::widget::WidgetChildren,
+    serde::Serialize,
+    std::{
+        io::{self, Read, Write},
+        net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs},
+        time::{Duration, Instant},
+    },
+};
+
+/// How long to wait for a client to send its request before answering anyway.
+const REQUEST_TIMEOUT: Duration = Duration::from_millis(50);
+/// How long a connection may take overall before it's dropped, e.g. if the client doesn't read the response.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(5);
+/// Most connections in progress at once; further ones wait in the backlog of the socket.
+const MAX_CONNECTIONS: usize = 16;
+/// Longest request which is read; the request is ignored anyway.
+const MAX_REQUEST_LENGTH: usize = 16 * 1024;
+
+/// A serialized widget within a snapshot.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InspectorNode {
+    /// Child indices from the root to this widget, which identify it within the snapshot.
+    pub path: Vec<usize>,
+    /// See [`Widget::debug_name`](crate::widget::Widget::debug_name).
+    pub name: &'static str,
+    /// Bounds as `[x, y, width, height]`.
+    pub bounds: [f32; 4],
+    /// See [`Widget::repaint_pending`](crate::widget::Widget::repaint_pending).
+    pub repaint_pending: Option<bool>,
+    pub children: Vec<InspectorNode>,
+}
+
+/// A serialized widget tree.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Snapshot {
+    /// Number of times the inspector was updated before this snapshot.
+    pub frame: u64,
+    pub root: InspectorNode,
+}
+
+/// Walks the widget tree, starting at `root`.
+pub fn inspect<U, G, D>(
+    root: &dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = D>,
+) -> InspectorNode {
+    inspect_at(root, Vec::new())
+}
+
+fn inspect_at<U, G, D>(
+    widget: &dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = D>,
+    path: Vec<usize>,
+) -> InspectorNode {
+    let bounds = widget.bounds();
+    let children = widget
+        .children()
+        .into_iter()
+        .enumerate()
+        .map(|(i, child)| {
+            let mut path = path.clone();
+            path.push(i);
+            inspect_at(child, path)
+        })
+        .collect();
+
+    InspectorNode {
+        path,
+        name: widget.debug_name(),
+        bounds: [bounds.origin.x, bounds.origin.y, bounds.size.width, bounds.size.height],
+        repaint_pending: widget.repaint_pending(),
+        children,
+    }
+}
+
+/// Serves JSON snapshots of the widget tree over a local TCP socket.
+///
+/// This never blocks the application; every socket is non-blocking, and connections only make progress within
+/// [`update`](Inspector::update) and [`serve`](Inspector::serve), at most 16 at a time.
+#[derive(Debug)]
+pub struct Inspector {
+    listener: TcpListener,
+    connections: Vec<Connection>,
+    frame: u64,
+    snapshot: String,
+}
+
+/// A connection being answered, over a non-blocking stream.
+#[derive(Debug)]
+struct Connection {
+    stream: TcpStream,
+    accepted: Instant,
+    request: Vec<u8>,
+    /// The response and how much of it was written, once the request was read.
+    response: Option<(Vec<u8>, usize)>,
+}
+
+impl Connection {
+    /// Reads the request and writes the response as far as possible without blocking,
+    /// returning `false` once the connection is done with (or failed).
+    fn poll(&mut self, snapshot: &str) -> bool {
+        if self.accepted.elapsed() > CONNECTION_TIMEOUT {
+            return false;
+        }
+
+        if self.response.is_none() {
+            // consume (and ignore) the request; closing with unread data may reset the connection.
+            let mut buffer = [0; 4096];
+            let complete = loop {
+                match self.stream.read(&mut buffer) {
+                    Ok(0) => break true,
+                    Ok(n) => {
+                        self.request.extend_from_slice(&buffer[..n]);
+                        if self.request.windows(4).any(|window| window == b"\r\n\r\n")
+                            || self.request.len() >= MAX_REQUEST_LENGTH
+                        {
+                            break true;
+                        }
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                        break self.accepted.elapsed() > REQUEST_TIMEOUT
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                    Err(_) => return false,
+                }
+            };
+            if !complete {
+                return true;
+            }
+            let response = format!(
+                "HTTP/1.0 200 OK\r\nContent-Type: application/json\r\nAccess-Control-Allow-Origin: *\r\nContent-Length: {}\r\n\r\n{}",
+                snapshot.len(),
+                snapshot
+            );
+            self.response = Some((response.into_bytes(), 0));
+        }
+
+        let (response, written) = self.response.as_mut().unwrap();
+        while *written < response.len() {
+            match self.stream.write(&response[*written..]) {
+                Ok(0) => return false,
+                Ok(n) => *written += n,
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => return true,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(_) => return false,
+            }
+        }
+        false
+    }
+}
+
+impl Inspector {
+    /// Starts listening on `addr` (e.g. `"127.0.0.1:9229"`, or port `0` for any free port).
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        Ok(Inspector { listener, connections: Vec::new(), frame: 0, snapshot: "null".into() })
+    }
+
+    /// Returns the address the inspector is listening on.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Returns the latest snapshot as JSON.
+    pub fn snapshot(&self) -> &str {
+        &self.snapshot
+    }
+
+    /// Takes a new snapshot of the tree and answers pending connections.
+    ///
+    /// This should be called once per frame, after updating the widget tree.
+    pub fn update<U, G, D>(
+        &mut self,
+        root: &dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = D>,
+    ) {
+        let snapshot = Snapshot { frame: self.frame, root: inspect(root) };
+        if let Ok(json) = serde_json::to_string(&snapshot) {
+            self.snapshot = json;
+        }
+        self.frame += 1;
+        self.serve();
+    }
+
+    /// Accepts pending connections and answers them with the latest snapshot as far as possible without blocking,
+    /// without taking a new snapshot.
+    pub fn serve(&mut self) {
+        while self.connections.len() < MAX_CONNECTIONS {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    if stream.set_nonblocking(true).is_ok() {
+                        self.connections.push(Connection {
+                            stream,
+                            accepted: Instant::now(),
+                            request: Vec::new(),
+                            response: None,
+                        });
+                    }
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(_) => break,
+            }
+        }
+
+        let snapshot = &self.snapshot;
+        self.connections.retain_mut(|connection| connection.poll(snapshot));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            display::{Point, Rect, Size},
+            widget::{ChildrenList, Widget},
+        },
+        smallvec::smallvec,
+        std::sync::mpsc,
+    };
+
+    struct Leaf;
+
+    impl Widget for Leaf {
+        type UpdateAux = ();
+        type GraphicalAux = ();
+        type DisplayObject = ();
+
+        fn bounds(&self) -> Rect {
+            Rect::new(Point::new(1.0, 2.0), Size::new(3.0, 4.0))
+        }
+
+        fn debug_name(&self) -> &'static str {
+            "Leaf"
+        }
+
+        fn repaint_pending(&self) -> Option<bool> {
+            Some(true)
+        }
+    }
+
+    impl WidgetChildren for Leaf {}
+
+    struct Parent(Leaf, Leaf);
+
+    impl Widget for Parent {
+        type UpdateAux = ();
+        type GraphicalAux = ();
+        type DisplayObject = ();
+    }
+
+    impl WidgetChildren for Parent {
+        fn children(&self) -> ChildrenList<'_, (), (), ()> {
+            smallvec![&self.0 as _, &self.1 as _]
+        }
+    }
+
+    #[test]
+    fn test_inspector() {
+        let root = Parent(Leaf, Leaf);
+
+        let node = inspect(&root);
+        assert!(node.name.ends_with("Parent"));
+        assert_eq!(node.repaint_pending, None);
+        assert_eq!(node.children.len(), 2);
+        assert_eq!(node.children[1].path, vec![1]);
+        assert_eq!(node.children[1].bounds, [1.0, 2.0, 3.0, 4.0]);
+
+        let mut inspector = Inspector::bind("127.0.0.1:0").unwrap();
+        let addr = inspector.local_addr().unwrap();
+
+        // idle clients neither block the inspector nor take up more than the connection limit.
+        let idle: Vec<_> =
+            (0..MAX_CONNECTIONS + 4).map(|_| TcpStream::connect(addr).unwrap()).collect();
+        let start = Instant::now();
+        while inspector.connections.len() < MAX_CONNECTIONS {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            inspector.serve();
+        }
+        assert_eq!(inspector.connections.len(), MAX_CONNECTIONS);
+        drop(idle);
+
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut client = TcpStream::connect(addr).unwrap();
+            client.write_all(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+            let mut response = String::new();
+            client.read_to_string(&mut response).unwrap();
+            sender.send(response).unwrap();
+        });
+
+        inspector.update(&root);
+        let start = Instant::now();
+        let response = loop {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            if let Ok(response) = receiver.recv_timeout(Duration::from_millis(1)) {
+                break response;
+            }
+            inspector.serve();
+        };
+        let body = response.split("\r\n\r\n").nth(1).unwrap();
+        let json: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(json["frame"], 0);
+        assert_eq!(json["root"]["children"][0]["name"], "Leaf");
+        assert_eq!(json["root"]["children"][0]["repaint_pending"], true);
+    }
+}
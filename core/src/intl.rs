@@ -0,0 +1,374 @@
+//! Locale-aware formatting of numbers, dates and plurals for UI strings.
+//!
+//! Widgets which produce text format values through the [`Formatter`](Formatter) traits, which they reach
+//! from their `UpdateAux` through [`IntlAux`](IntlAux), rather than with `format!` (which always uses the
+//! conventions of the C locale). An application can implement the traits on top of a full localization
+//! library, or use the built-in [`Locale`](Locale) tables for the common cases.
+
+use {crate::pacer::FrameTime, std::time::SystemTime};
+
+/// Formats numbers with locale-specific separators.
+pub trait NumberFormatter {
+    /// Formats `value`, rounded to `decimals` digits after the decimal separator.
+    fn format_number(&self, value: f64, decimals: usize) -> String;
+
+    /// Formats an integer.
+    #[inline]
+    fn format_integer(&self, value: i64) -> String {
+        self.format_number(value as f64, 0)
+    }
+}
+
+/// Formats calendar dates.
+pub trait DateFormatter {
+    fn format_date(&self, date: Date, style: DateStyle) -> String;
+}
+
+/// Category of a count, which selects the grammatical form of the words that go with it.
+///
+/// These are the categories of the Unicode CLDR plural rules; most languages only use a few of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PluralCategory {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+/// Selects the plural category of counts.
+pub trait PluralRules {
+    fn plural_category(&self, count: u64) -> PluralCategory;
+
+    /// Returns the form in `forms` matching the category of `count`, or `other` if there's none.
+    fn select_plural<'a>(
+        &self,
+        count: u64,
+        forms: &[(PluralCategory, &'a str)],
+        other: &'a str,
+    ) -> &'a str {
+        let category = self.plural_category(count);
+        forms.iter().find(|(form, _)| *form == category).map_or(other, |(_, text)| text)
+    }
+}
+
+/// All the formatting a localized UI needs; implemented for every type implementing the individual traits.
+pub trait Formatter: NumberFormatter + DateFormatter + PluralRules {}
+
+impl<T: NumberFormatter + DateFormatter + PluralRules + ?Sized> Formatter for T {}
+
+/// An `UpdateAux` through which widgets format their text.
+///
+/// Implemented for `()` and [`FrameTime`](FrameTime) (which format as [`Locale::EN_US`](Locale::EN_US)),
+/// and for [`Locale`](Locale) itself.
+/// An `UpdateAux` which needs more should forward to a `Locale` (or custom [`Formatter`](Formatter)) field.
+pub trait IntlAux {
+    fn formatter(&self) -> &dyn Formatter;
+}
+
+impl IntlAux for () {
+    #[inline]
+    fn formatter(&self) -> &dyn Formatter {
+        &Locale::EN_US
+    }
+}
+
+impl IntlAux for FrameTime {
+    #[inline]
+    fn formatter(&self) -> &dyn Formatter {
+        &Locale::EN_US
+    }
+}
+
+impl IntlAux for Locale {
+    #[inline]
+    fn formatter(&self) -> &dyn Formatter {
+        self
+    }
+}
+
+/// A day of the proleptic Gregorian calendar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    pub year: i32,
+    /// Month of the year, from 1 to 12.
+    pub month: u8,
+    /// Day of the month, from 1 to 31.
+    pub day: u8,
+}
+
+impl Date {
+    /// Returns the day `time` falls on in UTC.
+    pub fn from_system_time(time: SystemTime) -> Self {
+        let secs = match time.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(since) => since.as_secs() as i64,
+            Err(err) => -(err.duration().as_secs_f64().ceil() as i64),
+        };
+        Date::from_days_since_epoch(secs.div_euclid(86400))
+    }
+
+    /// Converts a number of days since 1970-01-01.
+    pub fn from_days_since_epoch(days: i64) -> Self {
+        // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+        let z = days + 719_468;
+        let era = z.div_euclid(146_097);
+        let day_of_era = z.rem_euclid(146_097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_index = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+        let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+
+        Date {
+            year: (year_of_era + era * 400 + (month <= 2) as i64) as i32,
+            month: month as u8,
+            day: day as u8,
+        }
+    }
+}
+
+/// How much of a date is spelled out.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DateStyle {
+    /// All numeric, e.g. "3/5/2024".
+    #[default]
+    Short,
+    /// With the name of the month, e.g. "March 5, 2024".
+    Long,
+}
+
+/// Formatting conventions of a locale, implementing all the [`Formatter`](Formatter) traits.
+///
+/// Date patterns are made of the placeholders `{d}` (day), `{dd}` (two-digit day), `{M}` (month number), `{MM}`
+/// (two-digit month number), `{MMMM}` (month name) and `{y}` (year), and literal text.
+#[derive(Debug, Clone, Copy)]
+pub struct Locale {
+    /// BCP 47 language tag, e.g. `"en-US"`.
+    pub tag: &'static str,
+    pub decimal_separator: char,
+    /// Separator between groups of three integer digits, if they're grouped at all.
+    pub grouping_separator: Option<char>,
+    pub short_date_pattern: &'static str,
+    pub long_date_pattern: &'static str,
+    pub month_names: [&'static str; 12],
+    pub plural_rule: fn(u64) -> PluralCategory,
+}
+
+const ENGLISH_MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// "One" for exactly one, as in English and German.
+fn one_other(count: u64) -> PluralCategory {
+    if count == 1 {
+        PluralCategory::One
+    } else {
+        PluralCategory::Other
+    }
+}
+
+/// "One" for zero and one, as in French.
+fn zero_one_other(count: u64) -> PluralCategory {
+    if count <= 1 {
+        PluralCategory::One
+    } else {
+        PluralCategory::Other
+    }
+}
+
+impl Locale {
+    pub const EN_US: Locale = Locale {
+        tag: "en-US",
+        decimal_separator: '.',
+        grouping_separator: Some(','),
+        short_date_pattern: "{M}/{d}/{y}",
+        long_date_pattern: "{MMMM} {d}, {y}",
+        month_names: ENGLISH_MONTHS,
+        plural_rule: one_other,
+    };
+
+    pub const EN_GB: Locale = Locale {
+        tag: "en-GB",
+        short_date_pattern: "{dd}/{MM}/{y}",
+        long_date_pattern: "{d} {MMMM} {y}",
+        ..Locale::EN_US
+    };
+
+    pub const DE_DE: Locale = Locale {
+        tag: "de-DE",
+        decimal_separator: ',',
+        grouping_separator: Some('.'),
+        short_date_pattern: "{dd}.{MM}.{y}",
+        long_date_pattern: "{d}. {MMMM} {y}",
+        month_names: [
+            "Januar",
+            "Februar",
+            "März",
+            "April",
+            "Mai",
+            "Juni",
+            "Juli",
+            "August",
+            "September",
+            "Oktober",
+            "November",
+            "Dezember",
+        ],
+        plural_rule: one_other,
+    };
+
+    pub const FR_FR: Locale = Locale {
+        tag: "fr-FR",
+        decimal_separator: ',',
+        // narrow no-break space.
+        grouping_separator: Some('\u{202f}'),
+        short_date_pattern: "{dd}/{MM}/{y}",
+        long_date_pattern: "{d} {MMMM} {y}",
+        month_names: [
+            "janvier",
+            "février",
+            "mars",
+            "avril",
+            "mai",
+            "juin",
+            "juillet",
+            "août",
+            "septembre",
+            "octobre",
+            "novembre",
+            "décembre",
+        ],
+        plural_rule: zero_one_other,
+    };
+
+    /// Returns the built-in locale with the language tag `tag` (compared case-insensitively), if any.
+    pub fn from_tag(tag: &str) -> Option<Locale> {
+        [Locale::EN_US, Locale::EN_GB, Locale::DE_DE, Locale::FR_FR]
+            .iter()
+            .find(|locale| locale.tag.eq_ignore_ascii_case(tag))
+            .copied()
+    }
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale::EN_US
+    }
+}
+
+impl NumberFormatter for Locale {
+    fn format_number(&self, value: f64, decimals: usize) -> String {
+        if !value.is_finite() {
+            return value.to_string();
+        }
+
+        let digits = format!("{:.*}", decimals, value.abs());
+        let (integer, fraction) = match digits.find('.') {
+            Some(point) => (&digits[..point], Some(&digits[point + 1..])),
+            None => (&digits[..], None),
+        };
+
+        let mut out = String::with_capacity(digits.len() + integer.len() / 3 + 1);
+        // "-0" isn't worth showing once rounded.
+        if value < 0.0 && digits.bytes().any(|digit| digit.is_ascii_digit() && digit != b'0') {
+            out.push('-');
+        }
+        for (index, digit) in integer.chars().enumerate() {
+            if index > 0 && (integer.len() - index) % 3 == 0 {
+                out.extend(self.grouping_separator);
+            }
+            out.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            out.push(self.decimal_separator);
+            out.push_str(fraction);
+        }
+        out
+    }
+}
+
+impl DateFormatter for Locale {
+    fn format_date(&self, date: Date, style: DateStyle) -> String {
+        let pattern = match style {
+            DateStyle::Short => self.short_date_pattern,
+            DateStyle::Long => self.long_date_pattern,
+        };
+        let month_name = self.month_names[(date.month.clamp(1, 12) - 1) as usize];
+
+        pattern
+            .replace("{dd}", &format!("{:02}", date.day))
+            .replace("{d}", &date.day.to_string())
+            .replace("{MMMM}", month_name)
+            .replace("{MM}", &format!("{:02}", date.month))
+            .replace("{M}", &date.month.to_string())
+            .replace("{y}", &date.year.to_string())
+    }
+}
+
+impl PluralRules for Locale {
+    #[inline]
+    fn plural_category(&self, count: u64) -> PluralCategory {
+        (self.plural_rule)(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::time::Duration};
+
+    #[test]
+    fn test_format_numbers() {
+        let en = Locale::EN_US;
+        assert_eq!(en.format_number(1234567.891, 2), "1,234,567.89");
+        assert_eq!(en.format_number(-999.5, 0), "-1,000");
+        assert_eq!(en.format_number(-0.001, 2), "0.00");
+        assert_eq!(en.format_integer(100), "100");
+        assert_eq!(Locale::DE_DE.format_number(1234.5, 1), "1.234,5");
+        assert_eq!(Locale::FR_FR.format_integer(-1234567), "-1\u{202f}234\u{202f}567");
+        assert_eq!(en.format_number(f64::INFINITY, 2), "inf");
+    }
+
+    #[test]
+    fn test_format_dates() {
+        // 2024-03-05T12:00:00Z
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_709_640_000);
+        let date = Date::from_system_time(time);
+        assert_eq!(date, Date { year: 2024, month: 3, day: 5 });
+        assert_eq!(
+            Date::from_system_time(SystemTime::UNIX_EPOCH - Duration::from_secs(1)),
+            Date { year: 1969, month: 12, day: 31 }
+        );
+        assert_eq!(Date::from_days_since_epoch(11016), Date { year: 2000, month: 2, day: 29 });
+
+        assert_eq!(Locale::EN_US.format_date(date, DateStyle::Short), "3/5/2024");
+        assert_eq!(Locale::EN_US.format_date(date, DateStyle::Long), "March 5, 2024");
+        assert_eq!(Locale::EN_GB.format_date(date, DateStyle::Short), "05/03/2024");
+        assert_eq!(Locale::DE_DE.format_date(date, DateStyle::Long), "5. März 2024");
+        assert_eq!(
+            Locale::from_tag("fr-fr").unwrap().format_date(date, DateStyle::Long),
+            "5 mars 2024"
+        );
+    }
+
+    #[test]
+    fn test_plurals() {
+        let forms = [(PluralCategory::One, "file")];
+        let formatter = ().formatter();
+        assert_eq!(formatter.select_plural(1, &forms, "files"), "file");
+        assert_eq!(formatter.select_plural(0, &forms, "files"), "files");
+        assert_eq!(Locale::FR_FR.formatter().select_plural(0, &forms, "files"), "file");
+    }
+}
@@ -0,0 +1,310 @@
+//! Input latency instrumentation, measuring how long input events take to be reflected on screen.
+//!
+//! A [`LatencyTracker`](LatencyTracker) is given the emission times of input events (as recorded by event queues with
+//! timestamps enabled, see `RcEventQueue::set_timestamps`), then the points at which the frame handling them was
+//! updated, pushed to the display and presented. Every frame with input contributes to a histogram per stage:
+//!
+//! ```ignore
+//! window_events.set_timestamps(true);
+//! let mut latency = LatencyTracker::new();
+//!
+//! // every frame:
+//! listener.with_timestamps(|events| latency.inputs(events));
+//! root.update(&mut aux);
+//! latency.update_finished();
+//! root.draw(&mut display, &mut ());
+//! latency.push_finished();
+//! display.present(None)?;
+//! latency.presented();
+//!
+//! println!("p95: {:?}", latency.histogram(LatencyStage::Present).percentile(0.95));
+//! ```
+//!
+//! Tracking is entirely opt-in; nothing is measured unless a tracker is driven as above.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Upper bounds of the histogram buckets, in milliseconds; the last bucket holds everything above.
+pub const BUCKET_BOUNDS_MS: [u64; 10] = [1, 2, 4, 8, 16, 33, 50, 100, 200, 500];
+
+/// Number of frames kept by [`LatencyTracker::frames`](LatencyTracker::frames).
+const FRAME_HISTORY: usize = 120;
+
+/// Point of the frame which latency is measured up to, from the emission of the input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LatencyStage {
+    /// The widget tree was updated, handling the input.
+    Update,
+    /// The command groups reflecting the input were pushed to the display.
+    Push,
+    /// The frame was presented.
+    Present,
+}
+
+/// Distribution of latencies, bucketed as per [`BUCKET_BOUNDS_MS`](BUCKET_BOUNDS_MS).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    buckets: [u64; BUCKET_BOUNDS_MS.len() + 1],
+    total: Duration,
+    max: Duration,
+}
+
+impl LatencyHistogram {
+    /// Adds a sample.
+    pub fn record(&mut self, latency: Duration) {
+        let bucket = BUCKET_BOUNDS_MS
+            .iter()
+            .position(|bound| latency <= Duration::from_millis(*bound))
+            .unwrap_or(BUCKET_BOUNDS_MS.len());
+        self.buckets[bucket] += 1;
+        self.total += latency;
+        self.max = self.max.max(latency);
+    }
+
+    /// Returns the number of samples.
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+
+    /// Returns the number of samples in each bucket, along with the upper bound of the bucket (`None` for the last).
+    pub fn buckets(&self) -> impl Iterator<Item = (Option<Duration>, u64)> + '_ {
+        BUCKET_BOUNDS_MS
+            .iter()
+            .map(|bound| Some(Duration::from_millis(*bound)))
+            .chain(std::iter::once(None))
+            .zip(self.buckets.iter().copied())
+    }
+
+    /// Returns the average latency, or `None` if there are no samples.
+    pub fn mean(&self) -> Option<Duration> {
+        match self.count() {
+            0 => None,
+            count => Some(self.total / count as u32),
+        }
+    }
+
+    /// Returns the highest latency, or `None` if there are no samples.
+    pub fn max(&self) -> Option<Duration> {
+        if self.count() == 0 {
+            None
+        } else {
+            Some(self.max)
+        }
+    }
+
+    /// Returns the upper bound of the bucket containing the `p`-th percentile (`p` from `0.0` to `1.0`),
+    /// or `None` if there are no samples.
+    ///
+    /// For the last bucket, which has no upper bound, this is the highest latency.
+    pub fn percentile(&self, p: f64) -> Option<Duration> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        let rank = ((p.clamp(0.0, 1.0) * count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        self.buckets().find_map(|(bound, samples)| {
+            seen += samples;
+            if seen >= rank {
+                Some(bound.unwrap_or(self.max).min(self.max))
+            } else {
+                None
+            }
+        })
+    }
+}
+
+/// Latencies of a single frame, measured from the earliest input it handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameLatency {
+    /// Number of input events handled in the frame.
+    pub inputs: usize,
+    pub update: Duration,
+    pub push: Duration,
+    pub present: Duration,
+}
+
+impl FrameLatency {
+    /// Returns the latency up to `stage`.
+    pub fn stage(&self, stage: LatencyStage) -> Duration {
+        match stage {
+            LatencyStage::Update => self.update,
+            LatencyStage::Push => self.push,
+            LatencyStage::Present => self.present,
+        }
+    }
+}
+
+/// Correlates input events with the frames which handle them, see the [module documentation](self).
+#[derive(Debug, Clone, Default)]
+pub struct LatencyTracker {
+    earliest_input: Option<Instant>,
+    inputs: usize,
+    update: Option<Instant>,
+    push: Option<Instant>,
+    frames: VecDeque<FrameLatency>,
+    update_histogram: LatencyHistogram,
+    push_histogram: LatencyHistogram,
+    present_histogram: LatencyHistogram,
+}
+
+impl LatencyTracker {
+    /// Creates a tracker without any samples.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Records an input event emitted at `emitted`, to be handled in the current frame.
+    pub fn input(&mut self, emitted: Instant) {
+        self.earliest_input = Some(self.earliest_input.map_or(emitted, |input| input.min(emitted)));
+        self.inputs += 1;
+    }
+
+    /// Records every input event of a listener, as given by `with_timestamps`.
+    pub fn inputs<T>(&mut self, events: &[(Instant, T)]) {
+        for (emitted, _) in events {
+            self.input(*emitted);
+        }
+    }
+
+    /// Records that the widget tree was updated at `now`.
+    pub fn update_finished_at(&mut self, now: Instant) {
+        self.update.get_or_insert(now);
+    }
+
+    /// Records that the widget tree was just updated.
+    #[inline]
+    pub fn update_finished(&mut self) {
+        self.update_finished_at(Instant::now())
+    }
+
+    /// Records that the command groups of the frame were pushed to the display at `now`.
+    pub fn push_finished_at(&mut self, now: Instant) {
+        self.push.get_or_insert(now);
+    }
+
+    /// Records that the command groups of the frame were just pushed to the display.
+    #[inline]
+    pub fn push_finished(&mut self) {
+        self.push_finished_at(Instant::now())
+    }
+
+    /// Records that the frame was presented at `now`, completing its measurement, which is returned if it handled
+    /// any input.
+    ///
+    /// Stages which weren't recorded for the frame are taken to have finished at `now`.
+    pub fn presented_at(&mut self, now: Instant) -> Option<FrameLatency> {
+        let update = self.update.take().unwrap_or(now);
+        let push = self.push.take().unwrap_or(now);
+        let inputs = std::mem::take(&mut self.inputs);
+        let earliest = self.earliest_input.take()?;
+
+        let frame = FrameLatency {
+            inputs,
+            update: update.saturating_duration_since(earliest),
+            push: push.saturating_duration_since(earliest),
+            present: now.saturating_duration_since(earliest),
+        };
+        self.update_histogram.record(frame.update);
+        self.push_histogram.record(frame.push);
+        self.present_histogram.record(frame.present);
+        if self.frames.len() == FRAME_HISTORY {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame);
+        Some(frame)
+    }
+
+    /// Records that the frame was just presented, see [`presented_at`](LatencyTracker::presented_at).
+    #[inline]
+    pub fn presented(&mut self) -> Option<FrameLatency> {
+        self.presented_at(Instant::now())
+    }
+
+    /// Returns the histogram of every frame with input, up to `stage`.
+    pub fn histogram(&self, stage: LatencyStage) -> &LatencyHistogram {
+        match stage {
+            LatencyStage::Update => &self.update_histogram,
+            LatencyStage::Push => &self.push_histogram,
+            LatencyStage::Present => &self.present_histogram,
+        }
+    }
+
+    /// Returns the latencies of the last 120 frames with input, oldest first.
+    pub fn frames(&self) -> impl Iterator<Item = &FrameLatency> + '_ {
+        self.frames.iter()
+    }
+
+    /// Clears every sample, keeping any input of the current frame.
+    pub fn reset(&mut self) {
+        self.frames.clear();
+        self.update_histogram = Default::default();
+        self.push_histogram = Default::default();
+        self.present_histogram = Default::default();
+    }
+}
+
+/// An `UpdateAux` through which widgets report the emission time of input events they handle, for
+/// events which don't pass through a listener the application reads timestamps from.
+///
+/// Implemented for `()` and [`FrameTime`](crate::pacer::FrameTime) (which ignore them), and for
+/// [`LatencyTracker`](LatencyTracker) itself.
+pub trait LatencyAux {
+    fn record_input(&mut self, emitted: Instant);
+}
+
+impl LatencyAux for () {
+    #[inline]
+    fn record_input(&mut self, _emitted: Instant) {}
+}
+
+impl LatencyAux for crate::pacer::FrameTime {
+    #[inline]
+    fn record_input(&mut self, _emitted: Instant) {}
+}
+
+impl LatencyAux for LatencyTracker {
+    #[inline]
+    fn record_input(&mut self, emitted: Instant) {
+        self.input(emitted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_tracker() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut tracker = LatencyTracker::new();
+
+        // frames without input aren't measured.
+        assert_eq!(tracker.presented_at(start), None);
+
+        tracker.inputs(&[(start + ms(2), 'b'), (start, 'a')]);
+        tracker.update_finished_at(start + ms(3));
+        tracker.push_finished_at(start + ms(5));
+        let frame = tracker.presented_at(start + ms(12)).unwrap();
+        assert_eq!(frame, FrameLatency { inputs: 2, update: ms(3), push: ms(5), present: ms(12) });
+
+        tracker.record_input(start + ms(20));
+        assert_eq!(tracker.presented_at(start + ms(120)).unwrap().update, ms(100));
+        assert_eq!(tracker.frames().count(), 2);
+
+        let present = tracker.histogram(LatencyStage::Present);
+        assert_eq!(present.count(), 2);
+        assert_eq!(present.mean(), Some(ms(56)));
+        assert_eq!(present.max(), Some(ms(100)));
+        assert_eq!(present.percentile(0.5), Some(ms(16)));
+        assert_eq!(present.percentile(1.0), Some(ms(100)));
+        assert_eq!(present.buckets().filter(|(_, count)| *count > 0).count(), 2);
+
+        tracker.reset();
+        assert_eq!(tracker.histogram(LatencyStage::Update).percentile(0.5), None);
+    }
+}
@@ -0,0 +1,183 @@
+//! Relative sizing units for layouts.
+//!
+//! Instead of hard-coding pixel rectangles, widgets can describe their geometry with [`Length`](Length)s
+//! (e.g. "half of the parent width, 2em tall"), which are resolved into concrete [`Rect`](Rect)s
+//! once the parent size and font size are known (typically whenever the window is resized).
+
+use crate::display::{Point, Rect, Size};
+
+/// A length along a single axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Length {
+    /// Absolute length in pixels.
+    Px(f32),
+    /// Percentage of the parent's length along the same axis (`100.0` is the full length).
+    Percent(f32),
+    /// Multiple of the font size.
+    Em(f32),
+}
+
+impl Default for Length {
+    fn default() -> Self {
+        Length::Px(0.0)
+    }
+}
+
+impl From<f32> for Length {
+    #[inline]
+    fn from(px: f32) -> Self {
+        Length::Px(px)
+    }
+}
+
+impl Length {
+    /// Converts the length into pixels, given the parent's length along the same axis and the font size.
+    pub fn resolve(self, parent: f32, font_size: f32) -> f32 {
+        match self {
+            Length::Px(px) => px,
+            Length::Percent(percent) => parent * percent / 100.0,
+            Length::Em(em) => font_size * em,
+        }
+    }
+}
+
+/// A rectangle described by relative lengths, positioned relative to the origin of its parent.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LengthRect {
+    pub x: Length,
+    pub y: Length,
+    pub width: Length,
+    pub height: Length,
+}
+
+impl LengthRect {
+    /// Creates a new relative rectangle.
+    pub fn new(
+        x: impl Into<Length>,
+        y: impl Into<Length>,
+        width: impl Into<Length>,
+        height: impl Into<Length>,
+    ) -> Self {
+        LengthRect { x: x.into(), y: y.into(), width: width.into(), height: height.into() }
+    }
+
+    /// Creates a relative rectangle which fills its parent.
+    pub fn fill() -> Self {
+        LengthRect::new(0.0, 0.0, Length::Percent(100.0), Length::Percent(100.0))
+    }
+
+    /// Converts the rectangle into an absolute rectangle within `parent`.
+    pub fn resolve(&self, parent: Rect, font_size: f32) -> Rect {
+        Rect::new(
+            parent.origin
+                + Size::new(
+                    self.x.resolve(parent.size.width, font_size),
+                    self.y.resolve(parent.size.height, font_size),
+                ),
+            Size::new(
+                self.width.resolve(parent.size.width, font_size),
+                self.height.resolve(parent.size.height, font_size),
+            ),
+        )
+    }
+}
+
+/// A node of a tree of relative rectangles, to be resolved in a single pass by [`resolve`](resolve).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LayoutNode {
+    /// Geometry of the node, relative to its parent.
+    pub rect: LengthRect,
+    /// Font size used to resolve `Em` lengths of this node and its children, or `None` to inherit it.
+    pub font_size: Option<f32>,
+    pub children: Vec<LayoutNode>,
+    resolved: Rect,
+}
+
+impl LayoutNode {
+    /// Creates a new node without any children.
+    pub fn new(rect: LengthRect) -> Self {
+        LayoutNode { rect, ..Default::default() }
+    }
+
+    /// Adds a child node, returning `self`.
+    pub fn with_child(mut self, child: LayoutNode) -> Self {
+        self.children.push(child);
+        self
+    }
+
+    /// Sets the font size of the node, returning `self`.
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = Some(font_size);
+        self
+    }
+
+    /// Returns the absolute rectangle computed by the last [`resolve`](resolve).
+    #[inline]
+    pub fn resolved(&self) -> Rect {
+        self.resolved
+    }
+}
+
+/// Resolves the relative rectangles of `node` and all its descendants into absolute rectangles, top-down.
+///
+/// `parent` is the absolute rectangle the root is laid out in (e.g. the window) and `font_size` is the
+/// root font size.
+pub fn resolve(node: &mut LayoutNode, parent: Rect, font_size: f32) {
+    let font_size = node.font_size.unwrap_or(font_size);
+    node.resolved = node.rect.resolve(parent, font_size);
+    let resolved = node.resolved;
+    for child in &mut node.children {
+        resolve(child, resolved, font_size);
+    }
+}
+
+/// Resolves the relative rectangles of `node` and all its descendants within a window of a given size.
+#[inline]
+pub fn resolve_in(node: &mut LayoutNode, window: Size, font_size: f32) {
+    resolve(node, Rect::new(Point::zero(), window), font_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_layout() {
+        let mut root = LayoutNode::new(LengthRect::fill())
+            .with_child(LayoutNode::new(LengthRect::new(
+                Length::Percent(25.0),
+                Length::Em(1.0),
+                Length::Percent(50.0),
+                Length::Em(2.0),
+            )))
+            .with_child(
+                LayoutNode::new(LengthRect::new(10.0, 10.0, 100.0, Length::Percent(50.0)))
+                    .with_font_size(20.0)
+                    .with_child(LayoutNode::new(LengthRect::new(
+                        Length::Em(1.0),
+                        0.0,
+                        Length::Percent(100.0),
+                        Length::Em(0.5),
+                    ))),
+            );
+
+        resolve_in(&mut root, Size::new(400.0, 300.0), 16.0);
+        assert_eq!(root.resolved(), Rect::new(Point::zero(), Size::new(400.0, 300.0)));
+        assert_eq!(
+            root.children[0].resolved(),
+            Rect::new(Point::new(100.0, 16.0), Size::new(200.0, 32.0))
+        );
+        assert_eq!(
+            root.children[1].resolved(),
+            Rect::new(Point::new(10.0, 10.0), Size::new(100.0, 150.0))
+        );
+        assert_eq!(
+            root.children[1].children[0].resolved(),
+            Rect::new(Point::new(30.0, 10.0), Size::new(100.0, 10.0))
+        );
+
+        // resizing the window only requires another pass.
+        resolve_in(&mut root, Size::new(800.0, 600.0), 16.0);
+        assert_eq!(root.children[0].resolved().size, Size::new(400.0, 32.0));
+    }
+}
@@ -1,11 +1,34 @@
 //! Core components of Reclutch, such as the Widget types and the display module.
 
+pub mod accelerator;
+pub mod clipboard;
+pub mod command;
+pub mod cursor;
 pub mod display;
 pub mod error;
+pub mod feedback;
+pub mod i18n;
+pub mod input;
+#[cfg(feature = "inspector")]
+pub mod inspector;
+pub mod intl;
+pub mod latency;
+pub mod layout;
+pub mod pacer;
+#[cfg(feature = "scripting")]
+pub mod scripting;
+pub mod scroll;
+#[cfg(feature = "state")]
+pub mod state;
+pub mod style;
+pub mod testing;
+pub mod tooltip;
+pub mod window_chrome;
 
 pub use euclid;
 pub use font_kit;
 pub use palette;
+pub use smallvec;
 
 #[cfg(feature = "skia")]
 pub use skia_safe as skia;
@@ -13,20 +36,47 @@ pub use skia_safe as skia;
 #[cfg(feature = "skia")]
 pub use gl;
 
+#[cfg(feature = "state")]
+pub use serde_json;
+
+#[cfg(feature = "scripting")]
+pub use rhai;
+
 /// Intricate event queues.
 pub use reclutch_event as event;
 
 pub mod prelude {
     pub use crate::{
-        display::GraphicsDisplay,
-        widget::{Widget, WidgetChildren},
+        display::{validate::ValidatedDisplay, GraphicsDisplay},
+        style::Styled,
+        widget::{DynamicWidgetChildren, HasWidgetCommon, Widget, WidgetChildren, WidgetTransform},
     };
     pub use reclutch_event::prelude::*;
 }
 
 /// Widget systems in which Reclutch is built around.
 pub mod widget {
-    use crate::display::{GraphicsDisplay, Rect};
+    use crate::{
+        display::{GraphicsDisplay, Point, Rect, Vector},
+        input::FileDropEvent,
+    };
+    use reclutch_event::{prelude::*, RcEventQueue};
+    use smallvec::SmallVec;
+
+    /// Number of children which can be returned from [`WidgetChildren`] without a heap allocation.
+    pub const CHILDREN_INLINE_CAPACITY: usize = 8;
+
+    /// Immutable dynamic references to children, as returned from [`children`](WidgetChildren::children).
+    pub type ChildrenList<'a, U, G, D> = SmallVec<
+        [&'a dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = D>;
+            CHILDREN_INLINE_CAPACITY],
+    >;
+
+    /// Mutable dynamic references to children, as returned from [`children_mut`](WidgetChildren::children_mut).
+    pub type ChildrenListMut<'a, U, G, D> = SmallVec<
+        [&'a mut dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = D>;
+            CHILDREN_INLINE_CAPACITY],
+    >;
 
     /// Simple widget trait with a render boundary, event updating and rendering.
     pub trait Widget {
@@ -37,10 +87,73 @@ pub mod widget {
         /// The bounds method doesn't necessarily have an internal need within Reclutch,
         /// however widget boundaries is crucial data in every GUI, for things such as
         /// layout, partial redraw, and input.
+        ///
+        /// The bounds are in the coordinate space of the parent, which is window space for a root widget.
         fn bounds(&self) -> Rect {
             Rect::default()
         }
 
+        /// Transform from the local coordinate space of the widget into the space of its parent.
+        ///
+        /// Widgets which draw and handle input relative to their own origin return a translation to
+        /// their position here, so that they can be repositioned without changing any of their logic.
+        fn transform(&self) -> WidgetTransform {
+            WidgetTransform::identity()
+        }
+
+        /// The bounds of the widget within its own local coordinate space.
+        ///
+        /// Defaults to [`bounds`](Widget::bounds) mapped through the inverse of [`transform`](Widget::transform).
+        fn local_bounds(&self) -> Rect {
+            self.transform().inverse_transform_rect(self.bounds()).unwrap_or_default()
+        }
+
+        /// Name of the widget, as shown by debugging tools such as the inspector.
+        ///
+        /// Defaults to the type name.
+        fn debug_name(&self) -> &'static str {
+            std::any::type_name::<Self>()
+        }
+
+        /// Whether the widget will repaint on the next draw, or `None` if unknown.
+        ///
+        /// This is purely informational (e.g. for the inspector); typically it returns
+        /// [`will_repaint`](crate::display::CommandGroup::will_repaint) of the widget's command group.
+        fn repaint_pending(&self) -> Option<bool> {
+            None
+        }
+
+        /// Returns the widget as [`PersistentState`](crate::state::PersistentState) if it has state to persist
+        /// across sessions; typically `Some(self)`.
+        ///
+        /// This is how the [`state`](crate::state) module finds the persistent widgets of a tree.
+        #[cfg(feature = "state")]
+        fn persistent_state(&self) -> Option<&dyn crate::state::PersistentState> {
+            None
+        }
+
+        /// Mutable counterpart of [`persistent_state`](Widget::persistent_state).
+        #[cfg(feature = "state")]
+        fn persistent_state_mut(&mut self) -> Option<&mut dyn crate::state::PersistentState> {
+            None
+        }
+
+        /// Whether the widget (along with its children) is shown.
+        ///
+        /// Hidden widgets are skipped by [`propagate_update`], [`propagate_draw`] and [`hit_test`].
+        /// Widgets with their own visibility typically store it in a [`WidgetCommon`].
+        fn visible(&self) -> bool {
+            true
+        }
+
+        /// Whether the widget (along with its children) accepts input.
+        ///
+        /// Disabled widgets are still updated and drawn (e.g. greyed out), but [`hit_test`] doesn't
+        /// find them, and they should ignore input events in [`update`](Widget::update).
+        fn enabled(&self) -> bool {
+            true
+        }
+
         /// Perhaps the most important method, this method gives every widget an opportunity
         /// to process events, emit events and execute all the side effects attached to such.
         /// Event handling is performed through a focused event system (see the event module).
@@ -80,6 +193,29 @@ pub mod widget {
         /// [`UpdateAux`]: Widget::UpdateAux
         fn update(&mut self, _aux: &mut Self::UpdateAux) {}
 
+        /// Handles files dragged over or dropped onto the widget, returning `true` to accept the event.
+        ///
+        /// Events are delivered to the widget under the cursor, and passed on to its ancestors until one accepts it;
+        /// see [`FileDropRouter`]. A widget which accepted [`Hovered`](FileDropEvent::Hovered) (e.g. to highlight
+        /// itself) also receives [`Cancelled`](FileDropEvent::Cancelled) if the files aren't dropped onto it.
+        fn file_drop(&mut self, _event: &FileDropEvent, _aux: &mut Self::UpdateAux) -> bool {
+            false
+        }
+
+        /// Name and dependencies under which [`scheduled_update`](Widget::scheduled_update) is run by an update
+        /// scheduler, such as `reclutch_verbgraph::schedule::UpdateScheduler`.
+        ///
+        /// Defaults to `None`, leaving the widget out of the schedule.
+        fn update_schedule(&self) -> Option<UpdateSchedule> {
+            None
+        }
+
+        /// Update run by an update scheduler once the updates named in [`update_schedule`](Widget::update_schedule)
+        /// have run; typically this updates the verb graph of the widget.
+        ///
+        /// Unlike [`update`](Widget::update), this shouldn't propagate to children, which are scheduled separately.
+        fn scheduled_update(&mut self, _aux: &mut Self::UpdateAux) {}
+
         /// Drawing is renderer-agnostic, however this doesn't mean the API is restrictive.
         /// Generally, drawing is performed through [`CommandGroup`].
         /// This is also where [`GraphicalAux`] and [`DisplayObject`] come in handy.
@@ -136,34 +272,756 @@ pub mod widget {
         }
     }
 
+    /// Where a widget is placed within an update schedule, as returned from [`update_schedule`](Widget::update_schedule).
+    ///
+    /// ```ignore
+    /// fn update_schedule(&self) -> Option<UpdateSchedule> {
+    ///     Some(UpdateSchedule { name: "layout", after: &["data"] })
+    /// }
+    /// ```
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct UpdateSchedule {
+        /// Unique name of the update.
+        pub name: &'static str,
+        /// Names of the updates which must run before this one.
+        pub after: &'static [&'static str],
+    }
+
+    /// Visibility and enabled state, for widgets to embed and return from [`visible`](Widget::visible)
+    /// and [`enabled`](Widget::enabled).
+    ///
+    /// ```ignore
+    /// impl Widget for Panel {
+    ///     fn visible(&self) -> bool {
+    ///         self.common.is_visible()
+    ///     }
+    ///
+    ///     fn enabled(&self) -> bool {
+    ///         self.common.is_enabled()
+    ///     }
+    ///
+    ///     // --snip--
+    /// }
+    /// ```
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct WidgetCommon {
+        visible: bool,
+        enabled: bool,
+    }
+
+    impl Default for WidgetCommon {
+        fn default() -> Self {
+            WidgetCommon { visible: true, enabled: true }
+        }
+    }
+
+    impl WidgetCommon {
+        /// Creates a visible and enabled state.
+        #[inline]
+        pub fn new() -> Self {
+            Default::default()
+        }
+
+        #[inline]
+        pub fn is_visible(&self) -> bool {
+            self.visible
+        }
+
+        #[inline]
+        pub fn is_enabled(&self) -> bool {
+            self.enabled
+        }
+
+        /// Shows or hides the widget, returning `true` if this changed the visibility.
+        pub fn set_visible(&mut self, visible: bool) -> bool {
+            std::mem::replace(&mut self.visible, visible) != visible
+        }
+
+        /// Enables or disables the widget, returning `true` if this changed the enabled state.
+        pub fn set_enabled(&mut self, enabled: bool) -> bool {
+            std::mem::replace(&mut self.enabled, enabled) != enabled
+        }
+    }
+
+    /// Interface for widgets which embed a [`WidgetCommon`], giving them setters for visibility and enabled state.
+    pub trait HasWidgetCommon: Widget {
+        fn common(&self) -> &WidgetCommon;
+
+        fn common_mut(&mut self) -> &mut WidgetCommon;
+
+        /// Shows or hides the widget, returning `true` if this changed the visibility.
+        #[inline]
+        fn set_visible(&mut self, visible: bool) -> bool {
+            self.common_mut().set_visible(visible)
+        }
+
+        /// Enables or disables the widget, returning `true` if this changed the enabled state.
+        #[inline]
+        fn set_enabled(&mut self, enabled: bool) -> bool {
+            self.common_mut().set_enabled(enabled)
+        }
+    }
+
+    /// Translation and scale mapping a widget's local coordinate space into the space of its parent.
+    ///
+    /// A point `p` is mapped to `p * scale + translation`.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct WidgetTransform {
+        pub translation: Vector,
+        pub scale: Vector,
+    }
+
+    impl Default for WidgetTransform {
+        fn default() -> Self {
+            WidgetTransform::identity()
+        }
+    }
+
+    impl WidgetTransform {
+        /// Creates a transform which leaves points unchanged.
+        #[inline]
+        pub fn identity() -> Self {
+            WidgetTransform { translation: Vector::zero(), scale: Vector::new(1.0, 1.0) }
+        }
+
+        /// Creates a transform which only translates.
+        #[inline]
+        pub fn from_translation(translation: Vector) -> Self {
+            WidgetTransform { translation, ..WidgetTransform::identity() }
+        }
+
+        /// Creates a transform which only scales (around the local origin).
+        #[inline]
+        pub fn from_scale(scale: Vector) -> Self {
+            WidgetTransform { scale, ..WidgetTransform::identity() }
+        }
+
+        /// Returns the transform which applies `self`, followed by `parent`.
+        pub fn then(&self, parent: &WidgetTransform) -> Self {
+            WidgetTransform {
+                translation: Vector::new(
+                    self.translation.x * parent.scale.x + parent.translation.x,
+                    self.translation.y * parent.scale.y + parent.translation.y,
+                ),
+                scale: Vector::new(self.scale.x * parent.scale.x, self.scale.y * parent.scale.y),
+            }
+        }
+
+        /// Returns the inverse transform, or `None` if the scale is zero on either axis.
+        pub fn inverse(&self) -> Option<Self> {
+            if self.scale.x == 0.0 || self.scale.y == 0.0 {
+                return None;
+            }
+            let scale = Vector::new(1.0 / self.scale.x, 1.0 / self.scale.y);
+            Some(WidgetTransform {
+                translation: Vector::new(
+                    -self.translation.x * scale.x,
+                    -self.translation.y * scale.y,
+                ),
+                scale,
+            })
+        }
+
+        /// Maps a point from local space into parent space.
+        #[inline]
+        pub fn transform_point(&self, point: Point) -> Point {
+            Point::new(
+                point.x * self.scale.x + self.translation.x,
+                point.y * self.scale.y + self.translation.y,
+            )
+        }
+
+        /// Maps a point from parent space into local space.
+        #[inline]
+        pub fn inverse_transform_point(&self, point: Point) -> Option<Point> {
+            Some(self.inverse()?.transform_point(point))
+        }
+
+        /// Maps a rectangle from local space into parent space.
+        ///
+        /// Negative scales flip the rectangle, which is normalized again.
+        pub fn transform_rect(&self, rect: Rect) -> Rect {
+            Rect::from_points([
+                self.transform_point(rect.origin),
+                self.transform_point(rect.origin + rect.size),
+            ])
+        }
+
+        /// Maps a rectangle from parent space into local space.
+        pub fn inverse_transform_rect(&self, rect: Rect) -> Option<Rect> {
+            Some(self.inverse()?.transform_rect(rect))
+        }
+    }
+
+    /// Returns the combined transform from the local space of the last widget in `path` into window space.
+    ///
+    /// `path` is the ancestor chain, starting at the root widget and ending at the widget itself.
+    pub fn window_transform<'a, W: Widget + ?Sized + 'a>(
+        path: impl IntoIterator<Item = &'a W>,
+    ) -> WidgetTransform {
+        let transforms: Vec<_> = path.into_iter().map(Widget::transform).collect();
+        transforms
+            .iter()
+            .rev()
+            .fold(WidgetTransform::identity(), |transform, parent| transform.then(parent))
+    }
+
+    /// Converts a point (e.g. a pointer position) from window space into the local space of the
+    /// last widget in `path` (see [`window_transform`]).
+    ///
+    /// Returns `None` if any widget along the path has a zero scale.
+    pub fn window_to_local<'a, W: Widget + ?Sized + 'a>(
+        path: impl IntoIterator<Item = &'a W>,
+        point: Point,
+    ) -> Option<Point> {
+        window_transform(path).inverse_transform_point(point)
+    }
+
+    /// Converts a point from the local space of the last widget in `path` into window space (see [`window_transform`]).
+    pub fn local_to_window<'a, W: Widget + ?Sized + 'a>(
+        path: impl IntoIterator<Item = &'a W>,
+        point: Point,
+    ) -> Point {
+        window_transform(path).transform_point(point)
+    }
+
+    /// Updates every visible child of `widget`, in order.
+    ///
+    /// Note that hidden widgets don't read their event listeners, so events accumulate until they're shown again.
+    pub fn propagate_update<W: WidgetChildren + ?Sized>(widget: &mut W, aux: &mut W::UpdateAux) {
+        for child in widget.children_mut() {
+            if child.visible() {
+                child.update(aux);
+            }
+        }
+    }
+
+    /// Draws every visible child of `widget`, in order.
+    pub fn propagate_draw<W: WidgetChildren + ?Sized>(
+        widget: &mut W,
+        display: &mut dyn GraphicsDisplay<W::DisplayObject>,
+        aux: &mut W::GraphicalAux,
+    ) {
+        for child in widget.children_mut() {
+            if child.visible() {
+                child.draw(display, aux);
+            }
+        }
+    }
+
+    /// Finds the top-most widget under `point` (in the parent space of `widget`, i.e. window space for a root widget),
+    /// returning the child indices leading to it from `widget` (which is empty for `widget` itself).
+    ///
+    /// Later children are considered on top of earlier ones, and hidden widgets are skipped.
+    /// Returns `None` if nothing was hit, or if the hit widget or any of its ancestors is disabled;
+    /// disabled widgets still occlude the widgets beneath them.
+    pub fn hit_test<W: WidgetChildren + ?Sized>(widget: &W, point: Point) -> Option<Vec<usize>> {
+        if !widget.visible() || !widget.bounds().contains(point) {
+            return None;
+        }
+        hit_test_visible(widget, point).filter(|_| widget.enabled()).map(|mut path| {
+            path.reverse();
+            path
+        })
+    }
+
+    /// Like [`hit_test`], for a visible widget containing `point`, except that the returned path is reversed.
+    ///
+    /// Disabled widgets (and those beneath them) return `None`.
+    fn hit_test_visible<W: WidgetChildren + ?Sized>(
+        widget: &W,
+        point: Point,
+    ) -> Option<Vec<usize>> {
+        let local = match widget.transform().inverse_transform_point(point) {
+            Some(local) => local,
+            None => return Some(Vec::new()),
+        };
+        let children = widget.children();
+        for (i, child) in children.iter().enumerate().rev() {
+            if child.visible() && child.bounds().contains(local) {
+                return if child.enabled() {
+                    hit_test_visible(*child, local).map(|mut path| {
+                        path.push(i);
+                        path
+                    })
+                } else {
+                    None
+                };
+            }
+        }
+        Some(Vec::new())
+    }
+
+    /// Delivers a file drop event to the widget at `path` (as returned by [`hit_test`]), then to each of its
+    /// ancestors in turn until one [accepts](Widget::file_drop) it, returning the path of that widget.
+    pub fn deliver_file_drop<W: WidgetChildren + ?Sized>(
+        widget: &mut W,
+        path: &[usize],
+        event: &FileDropEvent,
+        aux: &mut W::UpdateAux,
+    ) -> Option<Vec<usize>> {
+        if let Some((&index, rest)) = path.split_first() {
+            let local = match event.position() {
+                Some(position) => widget
+                    .transform()
+                    .inverse_transform_point(position)
+                    .map(|local| event.with_position(local)),
+                None => Some(event.clone()),
+            };
+            if let Some(local) = local {
+                let mut children = widget.children_mut();
+                if let Some(child) = children.get_mut(index) {
+                    if let Some(mut accepted) = deliver_file_drop(*child, rest, &local, aux) {
+                        accepted.insert(0, index);
+                        return Some(accepted);
+                    }
+                }
+            }
+        }
+
+        if widget.file_drop(event, aux) {
+            Some(Vec::new())
+        } else {
+            None
+        }
+    }
+
+    /// Routes [`FileDropEvent`]s from the window to the widgets under the cursor.
+    ///
+    /// Hovered and dropped files are delivered to the top-most widget under the cursor (see [`hit_test`]) and
+    /// passed up through its ancestors (see [`deliver_file_drop`]). The router remembers which widget accepted the
+    /// hover, so that it receives [`Cancelled`](FileDropEvent::Cancelled) once the drag leaves the window or the
+    /// files are dropped onto another widget.
+    #[derive(Debug, Default, Clone, PartialEq, Eq)]
+    pub struct FileDropRouter {
+        hovered: Option<Vec<usize>>,
+    }
+
+    impl FileDropRouter {
+        pub fn new() -> Self {
+            Default::default()
+        }
+
+        /// Returns the path of the widget which accepted the files being dragged, if any.
+        pub fn hovered(&self) -> Option<&[usize]> {
+            self.hovered.as_deref()
+        }
+
+        /// Delivers `event` (with a position in window space) into the widget tree of `root`, returning the path of
+        /// the widget which accepted it.
+        pub fn route<W: WidgetChildren + ?Sized>(
+            &mut self,
+            root: &mut W,
+            event: &FileDropEvent,
+            aux: &mut W::UpdateAux,
+        ) -> Option<Vec<usize>> {
+            let hovered = self.hovered.take();
+            let accepted = match event.position() {
+                Some(position) => hit_test(root, position)
+                    .and_then(|path| deliver_file_drop(root, &path, event, aux)),
+                None => None,
+            };
+
+            let cancel = match event {
+                FileDropEvent::Hovered(..) => {
+                    self.hovered = accepted.clone();
+                    hovered.filter(|hovered| Some(hovered) != accepted.as_ref())
+                }
+                FileDropEvent::Dropped(..) => {
+                    hovered.filter(|hovered| Some(hovered) != accepted.as_ref())
+                }
+                FileDropEvent::Cancelled => {
+                    return hovered.and_then(|hovered| {
+                        deliver_file_drop(root, &hovered, &FileDropEvent::Cancelled, aux)
+                    });
+                }
+            };
+            if let Some(cancel) = cancel {
+                deliver_file_drop(root, &cancel, &FileDropEvent::Cancelled, aux);
+            }
+            accepted
+        }
+    }
+
     /// Interface to get children of a widget as an array of dynamic widgets.
     ///
+    /// Up to [`CHILDREN_INLINE_CAPACITY`] children are returned without any heap allocation.
+    ///
     /// Ideally, this wouldn't be implemented directly, but rather with `derive(WidgetChildren)`.
     pub trait WidgetChildren: Widget {
         /// Returns all the children as immutable dynamic references.
         fn children(
             &self,
-        ) -> Vec<
-            &dyn WidgetChildren<
-                UpdateAux = Self::UpdateAux,
-                GraphicalAux = Self::GraphicalAux,
-                DisplayObject = Self::DisplayObject,
-            >,
-        > {
-            Vec::new()
+        ) -> ChildrenList<'_, Self::UpdateAux, Self::GraphicalAux, Self::DisplayObject> {
+            SmallVec::new()
         }
 
         /// Returns all the children as mutable dynamic references.
         fn children_mut(
             &mut self,
-        ) -> Vec<
-            &mut dyn WidgetChildren<
-                UpdateAux = Self::UpdateAux,
-                GraphicalAux = Self::GraphicalAux,
-                DisplayObject = Self::DisplayObject,
-            >,
+        ) -> ChildrenListMut<'_, Self::UpdateAux, Self::GraphicalAux, Self::DisplayObject> {
+            SmallVec::new()
+        }
+
+        /// Returns the number of children.
+        ///
+        /// The default implementation collects [`children`](WidgetChildren::children),
+        /// however `derive(WidgetChildren)` computes this without collecting.
+        fn child_count(&self) -> usize {
+            self.children().len()
+        }
+    }
+
+    /// Unique identifier of a child added through [`DynamicWidgetChildren::add_child`].
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    #[repr(transparent)]
+    pub struct WidgetId(u64);
+
+    impl WidgetId {
+        /// Returns the inner ID.
+        #[inline]
+        pub fn id(self) -> u64 {
+            self.0
+        }
+    }
+
+    /// Emitted by [`DynamicChildren`] whenever the list of children changes.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub enum ChildrenEvent {
+        /// A child was added.
+        Added(WidgetId),
+        /// A child was removed.
+        Removed(WidgetId),
+    }
+
+    /// Boxed dynamic widget, as stored in [`DynamicChildren`].
+    pub type BoxedWidget<U, G, D> =
+        Box<dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = D>>;
+
+    /// Container of children which are added and removed at runtime.
+    ///
+    /// Children are kept in insertion order.
+    /// Use `#[dynamic_widget_children]` on a field of this type in `derive(WidgetChildren)`
+    /// to include the children in [`WidgetChildren`] and implement [`DynamicWidgetChildren`].
+    pub struct DynamicChildren<U, G, D> {
+        children: Vec<(WidgetId, BoxedWidget<U, G, D>)>,
+        next_id: u64,
+        /// Emits [`ChildrenEvent`]s when children are added or removed.
+        pub event: RcEventQueue<ChildrenEvent>,
+    }
+
+    impl<U, G, D> Default for DynamicChildren<U, G, D> {
+        fn default() -> Self {
+            DynamicChildren { children: Vec::new(), next_id: 0, event: Default::default() }
+        }
+    }
+
+    impl<U, G, D> DynamicChildren<U, G, D> {
+        /// Creates a new, empty container.
+        #[inline]
+        pub fn new() -> Self {
+            Default::default()
+        }
+
+        /// Adds a child, returning its unique ID.
+        pub fn add(&mut self, child: BoxedWidget<U, G, D>) -> WidgetId {
+            let id = WidgetId(self.next_id);
+            self.next_id += 1;
+            self.children.push((id, child));
+            self.event.emit_owned(ChildrenEvent::Added(id));
+            id
+        }
+
+        /// Removes a child, returning it if it existed.
+        pub fn remove(&mut self, id: WidgetId) -> Option<BoxedWidget<U, G, D>> {
+            let idx = self.children.iter().position(|(child_id, _)| *child_id == id)?;
+            let (_, child) = self.children.remove(idx);
+            self.event.emit_owned(ChildrenEvent::Removed(id));
+            Some(child)
+        }
+
+        /// Returns a child by ID.
+        pub fn get(
+            &self,
+            id: WidgetId,
+        ) -> Option<&dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = D>>
+        {
+            self.children.iter().find(|(child_id, _)| *child_id == id).map(|(_, child)| &**child)
+        }
+
+        /// Returns a child by ID, mutably.
+        pub fn get_mut(
+            &mut self,
+            id: WidgetId,
+        ) -> Option<
+            &mut (dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = D> + 'static),
+        > {
+            self.children
+                .iter_mut()
+                .find(|(child_id, _)| *child_id == id)
+                .map(|(_, child)| &mut **child)
+        }
+
+        /// Returns the IDs of all the children, in order.
+        pub fn ids(&self) -> Vec<WidgetId> {
+            self.children.iter().map(|(id, _)| *id).collect()
+        }
+
+        /// Returns an iterator over the children.
+        pub fn iter(
+            &self,
+        ) -> impl Iterator<Item = &dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = D>>
+        {
+            self.children.iter().map(|(_, child)| &**child as _)
+        }
+
+        /// Returns a mutable iterator over the children.
+        pub fn iter_mut(
+            &mut self,
+        ) -> impl Iterator<
+            Item = &mut (dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = D>
+                             + 'static),
         > {
-            Vec::new()
+            self.children.iter_mut().map(|(_, child)| &mut **child)
+        }
+
+        /// Returns the number of children.
+        #[inline]
+        pub fn len(&self) -> usize {
+            self.children.len()
+        }
+
+        /// Returns `true` if there are no children.
+        #[inline]
+        pub fn is_empty(&self) -> bool {
+            self.children.is_empty()
+        }
+    }
+
+    /// Interface for widgets whose children can be added and removed at runtime.
+    ///
+    /// Ideally, this wouldn't be implemented directly, but rather with `derive(WidgetChildren)`
+    /// and the `#[dynamic_widget_children]` attribute on a [`DynamicChildren`] field.
+    pub trait DynamicWidgetChildren: WidgetChildren {
+        /// Returns the container of dynamic children.
+        fn dynamic_children(
+            &self,
+        ) -> &DynamicChildren<Self::UpdateAux, Self::GraphicalAux, Self::DisplayObject>;
+
+        /// Returns the container of dynamic children, mutably.
+        fn dynamic_children_mut(
+            &mut self,
+        ) -> &mut DynamicChildren<Self::UpdateAux, Self::GraphicalAux, Self::DisplayObject>;
+
+        /// Adds a child, returning its unique ID.
+        #[inline]
+        fn add_child(
+            &mut self,
+            child: BoxedWidget<Self::UpdateAux, Self::GraphicalAux, Self::DisplayObject>,
+        ) -> WidgetId {
+            self.dynamic_children_mut().add(child)
+        }
+
+        /// Removes a child, returning it if it existed.
+        #[inline]
+        fn remove_child(
+            &mut self,
+            id: WidgetId,
+        ) -> Option<BoxedWidget<Self::UpdateAux, Self::GraphicalAux, Self::DisplayObject>> {
+            self.dynamic_children_mut().remove(id)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::display::Size;
+
+        struct Panel {
+            bounds: Rect,
+            transform: WidgetTransform,
+        }
+
+        impl Widget for Panel {
+            type UpdateAux = ();
+            type GraphicalAux = ();
+            type DisplayObject = ();
+
+            fn bounds(&self) -> Rect {
+                self.bounds
+            }
+
+            fn transform(&self) -> WidgetTransform {
+                self.transform
+            }
+        }
+
+        #[test]
+        fn test_widget_transform() {
+            let root = Panel {
+                bounds: Rect::new(Point::new(10.0, 20.0), Size::new(200.0, 100.0)),
+                transform: WidgetTransform::from_translation(Vector::new(10.0, 20.0)),
+            };
+            let child = Panel {
+                bounds: Rect::new(Point::new(50.0, 0.0), Size::new(20.0, 20.0)),
+                transform: WidgetTransform {
+                    translation: Vector::new(50.0, 0.0),
+                    scale: Vector::new(2.0, 2.0),
+                },
+            };
+
+            assert_eq!(root.local_bounds(), Rect::new(Point::zero(), Size::new(200.0, 100.0)));
+            assert_eq!(child.local_bounds(), Rect::new(Point::zero(), Size::new(10.0, 10.0)));
+
+            let path = [&root, &child];
+            let local = window_to_local(path.iter().copied(), Point::new(70.0, 30.0)).unwrap();
+            assert_eq!(local, Point::new(5.0, 5.0));
+            assert!(child.local_bounds().contains(local));
+            assert_eq!(local_to_window(path.iter().copied(), local), Point::new(70.0, 30.0));
+
+            let flipped = WidgetTransform::from_scale(Vector::new(-1.0, 1.0));
+            assert_eq!(
+                flipped.transform_rect(Rect::new(Point::zero(), Size::new(10.0, 10.0))),
+                Rect::new(Point::new(-10.0, 0.0), Size::new(10.0, 10.0))
+            );
+            assert_eq!(WidgetTransform::from_scale(Vector::zero()).inverse(), None);
+        }
+
+        #[derive(Default)]
+        struct Node {
+            bounds: Rect,
+            common: WidgetCommon,
+            updates: u32,
+            accepts_files: bool,
+            file_drops: Vec<FileDropEvent>,
+            children: Vec<Node>,
+        }
+
+        impl Node {
+            fn new(x: f32, width: f32, children: Vec<Node>) -> Self {
+                Node {
+                    bounds: Rect::new(Point::new(x, 0.0), Size::new(width, 10.0)),
+                    children,
+                    ..Default::default()
+                }
+            }
+        }
+
+        impl Widget for Node {
+            type UpdateAux = ();
+            type GraphicalAux = ();
+            type DisplayObject = ();
+
+            fn bounds(&self) -> Rect {
+                self.bounds
+            }
+
+            fn visible(&self) -> bool {
+                self.common.is_visible()
+            }
+
+            fn enabled(&self) -> bool {
+                self.common.is_enabled()
+            }
+
+            fn update(&mut self, aux: &mut ()) {
+                self.updates += 1;
+                propagate_update(self, aux);
+            }
+
+            fn file_drop(&mut self, event: &FileDropEvent, _aux: &mut ()) -> bool {
+                self.file_drops.push(event.clone());
+                self.accepts_files
+            }
+        }
+
+        impl HasWidgetCommon for Node {
+            fn common(&self) -> &WidgetCommon {
+                &self.common
+            }
+
+            fn common_mut(&mut self) -> &mut WidgetCommon {
+                &mut self.common
+            }
+        }
+
+        impl WidgetChildren for Node {
+            fn children(&self) -> ChildrenList<'_, (), (), ()> {
+                self.children.iter().map(|child| child as _).collect()
+            }
+
+            fn children_mut(&mut self) -> ChildrenListMut<'_, (), (), ()> {
+                self.children.iter_mut().map(|child| child as _).collect()
+            }
+        }
+
+        #[test]
+        fn test_widget_common() {
+            // the second child overlaps the first.
+            let mut root = Node::new(
+                0.0,
+                100.0,
+                vec![
+                    Node::new(0.0, 50.0, vec![Node::new(10.0, 10.0, vec![])]),
+                    Node::new(40.0, 20.0, vec![]),
+                ],
+            );
+
+            assert_eq!(hit_test(&root, Point::new(15.0, 5.0)), Some(vec![0, 0]));
+            assert_eq!(hit_test(&root, Point::new(45.0, 5.0)), Some(vec![1]));
+            assert_eq!(hit_test(&root, Point::new(80.0, 5.0)), Some(vec![]));
+            assert_eq!(hit_test(&root, Point::new(150.0, 5.0)), None);
+
+            // hidden widgets are skipped entirely.
+            assert!(root.children[1].set_visible(false));
+            assert!(!root.children[1].set_visible(false));
+            assert_eq!(hit_test(&root, Point::new(45.0, 5.0)), Some(vec![0]));
+            root.update(&mut ());
+            assert_eq!((root.children[0].children[0].updates, root.children[1].updates), (1, 0));
+
+            // disabled widgets block input to themselves, their children and whatever is beneath them.
+            root.children[0].set_enabled(false);
+            assert_eq!(hit_test(&root, Point::new(15.0, 5.0)), None);
+            assert_eq!(hit_test(&root, Point::new(45.0, 5.0)), None);
+            root.update(&mut ());
+            assert_eq!(root.children[0].updates, 2);
+        }
+
+        #[test]
+        fn test_file_drop() {
+            let mut root = Node::new(
+                0.0,
+                100.0,
+                vec![
+                    Node::new(0.0, 50.0, vec![Node::new(10.0, 10.0, vec![])]),
+                    Node::new(60.0, 20.0, vec![]),
+                ],
+            );
+            root.children[0].accepts_files = true;
+            root.children[1].accepts_files = true;
+            let mut router = FileDropRouter::new();
+            let files = vec![std::path::PathBuf::from("image.png")];
+
+            // the innermost widget declines, so the event bubbles up to its parent.
+            let hovered = FileDropEvent::Hovered(files.clone(), Point::new(15.0, 5.0));
+            assert_eq!(router.route(&mut root, &hovered, &mut ()), Some(vec![0]));
+            assert_eq!(router.hovered(), Some(&[0][..]));
+            assert_eq!(root.children[0].children[0].file_drops, vec![hovered.clone()]);
+            assert_eq!(root.children[0].file_drops, vec![hovered]);
+
+            // dropping onto another widget cancels the hover.
+            let dropped = FileDropEvent::Dropped(files.clone(), Point::new(65.0, 5.0));
+            assert_eq!(router.route(&mut root, &dropped, &mut ()), Some(vec![1]));
+            assert_eq!(root.children[1].file_drops, vec![dropped]);
+            assert_eq!(root.children[0].file_drops.last(), Some(&FileDropEvent::Cancelled));
+            assert_eq!(router.hovered(), None);
+
+            let hovered = FileDropEvent::Hovered(files, Point::new(65.0, 5.0));
+            router.route(&mut root, &hovered, &mut ());
+            assert_eq!(router.route(&mut root, &FileDropEvent::Cancelled, &mut ()), Some(vec![1]));
+            assert_eq!(root.children[1].file_drops.last(), Some(&FileDropEvent::Cancelled));
+            assert_eq!(router.route(&mut root, &FileDropEvent::Cancelled, &mut ()), None);
+            assert!(root.file_drops.is_empty());
         }
     }
 }
@@ -0,0 +1,196 @@
+//! Frame pacing, to keep time-based updates (e.g. animations) independent of the redraw frequency.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+/// Number of frames the average frame rate is computed over.
+const FPS_SAMPLES: usize = 60;
+
+/// Timing information of the current frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FrameTime {
+    /// Time since the previous frame was presented (zero for the first frame).
+    pub delta_time: Duration,
+    /// Time since the pacer was created.
+    pub elapsed: Duration,
+    /// Number of frames presented so far.
+    pub frame: u64,
+}
+
+impl FrameTime {
+    /// Returns the delta time in seconds, which is the typical multiplier for animation speeds.
+    #[inline]
+    pub fn delta_secs(&self) -> f32 {
+        self.delta_time.as_secs_f32()
+    }
+}
+
+/// An `UpdateAux` which receives the [`FrameTime`](FrameTime) before every update.
+///
+/// Implemented for `()` (which ignores it) and for [`FrameTime`](FrameTime) itself.
+pub trait FrameAux {
+    fn set_frame_time(&mut self, frame_time: FrameTime);
+}
+
+impl FrameAux for () {
+    #[inline]
+    fn set_frame_time(&mut self, _frame_time: FrameTime) {}
+}
+
+impl FrameAux for FrameTime {
+    #[inline]
+    fn set_frame_time(&mut self, frame_time: FrameTime) {
+        *self = frame_time;
+    }
+}
+
+/// Tracks present timestamps and optionally throttles presentation to a target frame rate.
+///
+/// The throttling is only meant for when vsync is disabled; with vsync the swap itself blocks.
+#[derive(Debug, Clone)]
+pub struct FramePacer {
+    start: Instant,
+    last_present: Option<Instant>,
+    delta_time: Duration,
+    frame: u64,
+    samples: VecDeque<Duration>,
+    frame_interval: Option<Duration>,
+}
+
+impl Default for FramePacer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FramePacer {
+    /// Creates a new pacer without a target frame rate.
+    pub fn new() -> Self {
+        Self::starting_at(Instant::now())
+    }
+
+    /// Creates a new pacer without a target frame rate, starting at a specific time.
+    pub fn starting_at(start: Instant) -> Self {
+        FramePacer {
+            start,
+            last_present: None,
+            delta_time: Duration::default(),
+            frame: 0,
+            samples: VecDeque::with_capacity(FPS_SAMPLES),
+            frame_interval: None,
+        }
+    }
+
+    /// Sets the target frame rate, or removes it if `None` (or not positive).
+    pub fn set_target_fps(&mut self, fps: impl Into<Option<f64>>) {
+        self.frame_interval =
+            fps.into().filter(|fps| *fps > 0.0).map(|fps| Duration::from_secs_f64(1.0 / fps));
+    }
+
+    /// Builder-style variant of [`set_target_fps`](FramePacer::set_target_fps).
+    pub fn with_target_fps(mut self, fps: impl Into<Option<f64>>) -> Self {
+        self.set_target_fps(fps);
+        self
+    }
+
+    /// Returns the target frame rate, if any.
+    pub fn target_fps(&self) -> Option<f64> {
+        self.frame_interval.map(|interval| 1.0 / interval.as_secs_f64())
+    }
+
+    /// Records that a frame was presented at `now`.
+    pub fn frame_presented_at(&mut self, now: Instant) {
+        if let Some(last) = self.last_present {
+            self.delta_time = now.saturating_duration_since(last);
+            if self.samples.len() == FPS_SAMPLES {
+                self.samples.pop_front();
+            }
+            self.samples.push_back(self.delta_time);
+        }
+        self.last_present = Some(now);
+        self.frame += 1;
+    }
+
+    /// Records that a frame was just presented.
+    #[inline]
+    pub fn frame_presented(&mut self) {
+        self.frame_presented_at(Instant::now())
+    }
+
+    /// Returns the timing information of the last presented frame.
+    pub fn frame_time(&self) -> FrameTime {
+        FrameTime {
+            delta_time: self.delta_time,
+            elapsed: self.last_present.unwrap_or(self.start).saturating_duration_since(self.start),
+            frame: self.frame,
+        }
+    }
+
+    /// Returns the average frame rate over the last 60 frames, or `None` if fewer than two frames were presented.
+    pub fn average_fps(&self) -> Option<f64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let total: Duration = self.samples.iter().sum();
+        if total == Duration::default() {
+            None
+        } else {
+            Some(self.samples.len() as f64 / total.as_secs_f64())
+        }
+    }
+
+    /// Returns the earliest time at which the next frame should be presented, given the target frame rate.
+    ///
+    /// Returns `None` if there is no target frame rate or no frame was presented yet.
+    pub fn next_frame_at(&self) -> Option<Instant> {
+        Some(self.last_present? + self.frame_interval?)
+    }
+
+    /// Returns `true` if a frame may be presented at `now` without exceeding the target frame rate.
+    pub fn should_present_at(&self, now: Instant) -> bool {
+        self.next_frame_at().map(|next| now >= next).unwrap_or(true)
+    }
+
+    /// Returns `true` if a frame may be presented now without exceeding the target frame rate.
+    #[inline]
+    pub fn should_present(&self) -> bool {
+        self.should_present_at(Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_pacer() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        let mut pacer = FramePacer::starting_at(start).with_target_fps(50.0);
+
+        assert!(pacer.should_present_at(start));
+        pacer.frame_presented_at(start);
+        assert_eq!(pacer.frame_time(), FrameTime { delta_time: ms(0), elapsed: ms(0), frame: 1 });
+        assert_eq!(pacer.average_fps(), None);
+
+        assert!(!pacer.should_present_at(start + ms(10)));
+        assert!(pacer.should_present_at(start + ms(20)));
+
+        pacer.frame_presented_at(start + ms(20));
+        pacer.frame_presented_at(start + ms(40));
+        let frame_time = pacer.frame_time();
+        assert_eq!(frame_time.delta_time, ms(20));
+        assert_eq!(frame_time.elapsed, ms(40));
+        assert_eq!(frame_time.frame, 3);
+        assert!((pacer.average_fps().unwrap() - 50.0).abs() < 1e-6);
+
+        pacer.set_target_fps(None);
+        assert!(pacer.should_present_at(start + ms(41)));
+
+        let mut aux = FrameTime::default();
+        aux.set_frame_time(frame_time);
+        assert_eq!(aux, frame_time);
+    }
+}
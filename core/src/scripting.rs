@@ -0,0 +1,289 @@
+//! Scripting hooks, exposing event queues, observed values and widget properties to an embedded
+//! [Rhai](https://rhai.rs) engine.
+//!
+//! Scripts interact with the application through three functions:
+//!
+//! - `emit(queue, value)` emits `value` into an event queue registered with
+//!   [`register_queue`](Scripting::register_queue).
+//! - `get(name)` returns an observed value (see [`register_observed`](Scripting::register_observed)) or a property
+//!   (see [`set_property`](Scripting::set_property)), or `()` if there is none.
+//! - `set(name, value)` changes an observed value, notifying its listeners, or a property.
+//!
+//! Widgets aren't referenced by scripts directly. Instead, the application publishes the properties scripts may read
+//! and applies the ones they changed, typically every update:
+//!
+//! ```ignore
+//! let mut scripting = Scripting::new();
+//! scripting.register_queue("log", &log_queue);
+//! scripting.register_observed("volume", &volume);
+//! scripting.run(r#"
+//!     emit("log", "muting");
+//!     set("volume", 0.0);
+//!     set("title.text", "Muted");
+//! "#)?;
+//!
+//! for (name, value) in scripting.take_property_changes() {
+//!     match name.as_str() {
+//!         "title.text" => title.set_text(value.into_string()?),
+//!         _ => {}
+//!     }
+//! }
+//! ```
+//!
+//! Values are converted with [`Dynamic::try_cast`](rhai::Dynamic::try_cast), so queues and observed values of
+//! Rhai's own types (`i64`, `f64`, `bool`, `String`, etc.) work as is; others can be converted with
+//! [`register_queue_with`](Scripting::register_queue_with).
+
+use {
+    crate::error::ScriptError,
+    reclutch_event::{observed::Observed, prelude::*, RcEventQueue},
+    rhai::{Dynamic, Engine, EvalAltResult, Scope, AST},
+    std::{cell::RefCell, collections::HashMap, rc::Rc},
+};
+
+type Emitter = Box<dyn Fn(Dynamic) -> bool>;
+
+struct ObservedValue {
+    get: Box<dyn Fn() -> Dynamic>,
+    set: Box<dyn Fn(Dynamic) -> bool>,
+}
+
+#[derive(Default)]
+struct Registry {
+    queues: HashMap<String, Emitter>,
+    observed: HashMap<String, ObservedValue>,
+    properties: HashMap<String, Dynamic>,
+    changes: Vec<(String, Dynamic)>,
+}
+
+/// A Rhai engine with access to the registered queues, observed values and properties,
+/// see the [module documentation](self).
+pub struct Scripting {
+    engine: Engine,
+    scope: Scope<'static>,
+    registry: Rc<RefCell<Registry>>,
+}
+
+impl Default for Scripting {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scripting {
+    /// Creates an engine without anything registered.
+    pub fn new() -> Self {
+        let registry: Rc<RefCell<Registry>> = Default::default();
+        let mut engine = Engine::new();
+
+        let emit_registry = registry.clone();
+        engine.register_fn(
+            "emit",
+            move |queue: &str, value: Dynamic| -> Result<(), Box<EvalAltResult>> {
+                let registry = emit_registry.borrow();
+                let emit = registry
+                    .queues
+                    .get(queue)
+                    .ok_or_else(|| format!("no event queue named \"{}\"", queue))?;
+                if emit(value) {
+                    Ok(())
+                } else {
+                    Err(format!("invalid event for the queue \"{}\"", queue).into())
+                }
+            },
+        );
+
+        let get_registry = registry.clone();
+        engine.register_fn("get", move |name: &str| -> Dynamic {
+            let registry = get_registry.borrow();
+            match registry.observed.get(name) {
+                Some(observed) => (observed.get)(),
+                None => registry.properties.get(name).cloned().unwrap_or(Dynamic::UNIT),
+            }
+        });
+
+        let set_registry = registry.clone();
+        engine.register_fn(
+            "set",
+            move |name: &str, value: Dynamic| -> Result<(), Box<EvalAltResult>> {
+                let mut registry = set_registry.borrow_mut();
+                if let Some(observed) = registry.observed.get(name) {
+                    return if (observed.set)(value) {
+                        Ok(())
+                    } else {
+                        Err(format!("invalid value for \"{}\"", name).into())
+                    };
+                }
+                registry.properties.insert(name.into(), value.clone());
+                registry.changes.push((name.into(), value));
+                Ok(())
+            },
+        );
+
+        Scripting { engine, scope: Scope::new(), registry }
+    }
+
+    /// Returns the underlying engine, e.g. to register additional functions or types.
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    /// Returns the underlying engine mutably.
+    pub fn engine_mut(&mut self) -> &mut Engine {
+        &mut self.engine
+    }
+
+    /// Returns the scope scripts are run in, which keeps variables between runs.
+    pub fn scope_mut(&mut self) -> &mut Scope<'static> {
+        &mut self.scope
+    }
+
+    /// Lets scripts emit into `queue` as `name`; emitted values must be of type `T`.
+    pub fn register_queue<T: Clone + 'static>(
+        &mut self,
+        name: impl Into<String>,
+        queue: &RcEventQueue<T>,
+    ) {
+        self.register_queue_with(name, queue, Dynamic::try_cast::<T>)
+    }
+
+    /// Lets scripts emit into `queue` as `name`, converting emitted values with `convert`.
+    ///
+    /// Emitting fails if `convert` returns `None`.
+    pub fn register_queue_with<T: Clone + 'static>(
+        &mut self,
+        name: impl Into<String>,
+        queue: &RcEventQueue<T>,
+        convert: impl Fn(Dynamic) -> Option<T> + 'static,
+    ) {
+        let queue = RcEventQueue(Rc::clone(&queue.0));
+        self.registry.borrow_mut().queues.insert(
+            name.into(),
+            Box::new(move |value| match convert(value) {
+                Some(event) => {
+                    queue.emit_owned(event);
+                    true
+                }
+                None => false,
+            }),
+        );
+    }
+
+    /// Lets scripts read and change `observed` as `name`.
+    pub fn register_observed<T: Clone + PartialEq + 'static>(
+        &mut self,
+        name: impl Into<String>,
+        observed: &Rc<RefCell<Observed<T>>>,
+    ) {
+        let (get, set) = (observed.clone(), observed.clone());
+        self.registry.borrow_mut().observed.insert(
+            name.into(),
+            ObservedValue {
+                get: Box::new(move || Dynamic::from(get.borrow().get().clone())),
+                set: Box::new(move |value| match value.try_cast::<T>() {
+                    Some(value) => {
+                        set.borrow_mut().set(value);
+                        true
+                    }
+                    None => false,
+                }),
+            },
+        );
+    }
+
+    /// Removes a queue or observed value registered as `name`, returning `true` if there was one.
+    pub fn unregister(&mut self, name: &str) -> bool {
+        let mut registry = self.registry.borrow_mut();
+        registry.queues.remove(name).is_some() | registry.observed.remove(name).is_some()
+    }
+
+    /// Publishes a property for scripts to read, without recording a change.
+    pub fn set_property(&mut self, name: impl Into<String>, value: impl Into<Dynamic>) {
+        self.registry.borrow_mut().properties.insert(name.into(), value.into());
+    }
+
+    /// Returns the current value of a property.
+    pub fn property(&self, name: &str) -> Option<Dynamic> {
+        self.registry.borrow().properties.get(name).cloned()
+    }
+
+    /// Returns the properties set by scripts since the last call, in order, to be applied to the widgets.
+    pub fn take_property_changes(&mut self) -> Vec<(String, Dynamic)> {
+        std::mem::take(&mut self.registry.borrow_mut().changes)
+    }
+
+    /// Compiles a script, to be run repeatedly with [`run_ast`](Scripting::run_ast).
+    pub fn compile(&self, script: &str) -> Result<AST, ScriptError> {
+        Ok(self.engine.compile_with_scope(&self.scope, script)?)
+    }
+
+    /// Runs a script, returning the value of its last expression.
+    pub fn run(&mut self, script: &str) -> Result<Dynamic, ScriptError> {
+        Ok(self.engine.eval_with_scope(&mut self.scope, script)?)
+    }
+
+    /// Runs a compiled script, returning the value of its last expression.
+    pub fn run_ast(&mut self, ast: &AST) -> Result<Dynamic, ScriptError> {
+        Ok(self.engine.eval_ast_with_scope(&mut self.scope, ast)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scripting() {
+        let mut scripting = Scripting::new();
+        let log: RcEventQueue<String> = RcEventQueue::new();
+        let clicks: RcEventQueue<u32> = RcEventQueue::new();
+        let volume = Rc::new(RefCell::new(Observed::new(0.5f64)));
+        let (log_listener, clicks_listener, volume_listener) =
+            (log.listen(), clicks.listen(), volume.borrow().listen());
+
+        scripting.register_queue("log", &log);
+        scripting.register_queue_with("clicks", &clicks, |value| {
+            value.as_int().ok().map(|clicks| clicks as u32)
+        });
+        scripting.register_observed("volume", &volume);
+        scripting.set_property("title.text", "Player");
+
+        let result = scripting
+            .run(
+                r#"
+                let previous = get("volume");
+                emit("log", "muting " + get("title.text"));
+                emit("clicks", 2);
+                set("volume", 0.0);
+                set("title.text", "Muted");
+                previous
+                "#,
+            )
+            .unwrap();
+        assert_eq!(result.as_float(), Ok(0.5));
+        assert_eq!(log_listener.peek(), &["muting Player".to_string()]);
+        assert_eq!(clicks_listener.peek(), &[2]);
+        assert_eq!(volume_listener.peek(), &[0.0]);
+        assert_eq!(**volume.borrow(), 0.0);
+
+        let changes = scripting.take_property_changes();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].0, "title.text");
+        assert_eq!(changes[0].1.clone().into_string().unwrap(), "Muted");
+        assert!(scripting.take_property_changes().is_empty());
+
+        // variables persist between runs.
+        assert_eq!(scripting.run("previous * 2.0").unwrap().as_float(), Ok(1.0));
+        let ast = scripting.compile(r#"get("missing")"#).unwrap();
+        assert!(scripting.run_ast(&ast).unwrap().is_unit());
+
+        assert!(matches!(scripting.run(r#"emit("log", 1)"#), Err(ScriptError::EvalError(_))));
+        assert!(matches!(
+            scripting.run(r#"set("volume", "loud")"#),
+            Err(ScriptError::EvalError(_))
+        ));
+        assert!(scripting.unregister("clicks"));
+        assert!(scripting.run(r#"emit("clicks", 1)"#).is_err());
+        assert!(matches!(scripting.compile("let"), Err(ScriptError::ParseError(_))));
+    }
+}
@@ -0,0 +1,374 @@
+//! Kinetic scrolling, with momentum after a drag is released and rubber-banding past the edges of the content.
+//!
+//! A [`ScrollPhysics`](ScrollPhysics) is fed the movement of the pointer (or a touch point) while dragging,
+//! and is stepped with the [`FrameTime`](crate::pacer::FrameTime) of every update, emitting the resulting
+//! offsets into its `event` queue:
+//!
+//! ```ignore
+//! // on press:
+//! self.scroll.begin_drag();
+//! // on move, while pressed:
+//! self.scroll.drag(point - previous);
+//! // on release:
+//! self.scroll.end_drag();
+//!
+//! // in update:
+//! self.scroll.update(aux.frame_time());
+//! for event in self.scroll_listener.peek() { /* offset the content */ }
+//! ```
+//!
+//! The offset is how far the viewport has moved into the content, so dragging towards the bottom-right
+//! decreases it. It stays within `0..=max_offset()`, except temporarily while rubber-banding.
+
+use {
+    crate::{
+        display::{Size, Vector},
+        event::RcEventQueue,
+        pacer::FrameTime,
+        prelude::*,
+    },
+    std::{
+        collections::VecDeque,
+        time::{Duration, Instant},
+    },
+};
+
+/// Drag movements within this long before the release contribute to the fling velocity.
+const VELOCITY_WINDOW: Duration = Duration::from_millis(100);
+/// Longest step of the simulation, in seconds, so that the spring stays stable on long frames.
+const MAX_STEP: f32 = 1.0 / 120.0;
+/// Speed (in pixels per second) and distance (in pixels) below which the motion stops.
+const REST_VELOCITY: f32 = 5.0;
+const REST_DISTANCE: f32 = 0.5;
+
+/// Events emitted by a [`ScrollPhysics`](ScrollPhysics).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScrollEvent {
+    /// The offset changed.
+    Offset(Vector),
+    /// The motion after a drag (or a fling) came to rest.
+    Settle,
+}
+
+/// Simulates scrolling within content larger than its viewport.
+#[derive(Debug)]
+pub struct ScrollPhysics {
+    pub event: RcEventQueue<ScrollEvent>,
+    /// Rate at which momentum decays, per second; the velocity is multiplied by `e^-friction` every second.
+    pub friction: f32,
+    /// Stiffness of the (critically damped) spring pulling the offset back within bounds after overscrolling.
+    pub stiffness: f32,
+    /// How strongly dragging past the edges is resisted, where 0 prevents overscrolling
+    /// and larger values resist less.
+    pub overscroll: f32,
+
+    viewport: Size,
+    content: Size,
+    offset: Vector,
+    velocity: Vector,
+    drag: Option<Drag>,
+}
+
+#[derive(Debug)]
+struct Drag {
+    /// Offset without rubber-banding.
+    unbounded: Vector,
+    samples: VecDeque<(Instant, Vector)>,
+}
+
+impl ScrollPhysics {
+    /// Creates a scroll model at offset zero, which can be scrolled by the difference in size
+    /// between the content and the viewport.
+    pub fn new(viewport: Size, content: Size) -> Self {
+        ScrollPhysics {
+            event: RcEventQueue::new(),
+            friction: 2.0,
+            stiffness: 150.0,
+            overscroll: 0.55,
+            viewport,
+            content,
+            offset: Vector::zero(),
+            velocity: Vector::zero(),
+            drag: None,
+        }
+    }
+
+    /// Returns the current offset.
+    pub fn offset(&self) -> Vector {
+        self.offset
+    }
+
+    /// Returns the current velocity, in pixels per second.
+    pub fn velocity(&self) -> Vector {
+        self.velocity
+    }
+
+    /// Returns the largest offset which is in bounds.
+    pub fn max_offset(&self) -> Vector {
+        Vector::new(
+            (self.content.width - self.viewport.width).max(0.0),
+            (self.content.height - self.viewport.height).max(0.0),
+        )
+    }
+
+    pub fn viewport(&self) -> Size {
+        self.viewport
+    }
+
+    pub fn content_size(&self) -> Size {
+        self.content
+    }
+
+    /// Changes the size of the viewport. An offset out of bounds is pulled back by the spring.
+    pub fn set_viewport(&mut self, viewport: Size) {
+        self.viewport = viewport;
+    }
+
+    /// Changes the size of the content. An offset out of bounds is pulled back by the spring.
+    pub fn set_content_size(&mut self, content: Size) {
+        self.content = content;
+    }
+
+    /// Returns `true` while the offset is changing without being dragged, i.e. [`update`](ScrollPhysics::update)
+    /// needs to be called every frame.
+    pub fn is_animating(&self) -> bool {
+        self.drag.is_none()
+            && (self.velocity != Vector::zero() || self.clamp(self.offset) != self.offset)
+    }
+
+    /// Returns `true` while dragging.
+    pub fn is_dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    /// Moves to `offset` (clamped to the bounds) immediately, stopping any motion.
+    pub fn set_offset(&mut self, offset: Vector) {
+        self.velocity = Vector::zero();
+        self.set_offset_internal(self.clamp(offset));
+    }
+
+    /// Scrolls by `delta` immediately (e.g. for mouse wheels), clamped to the bounds, stopping any motion.
+    pub fn scroll_by(&mut self, delta: Vector) {
+        self.set_offset(self.offset + delta);
+    }
+
+    /// Starts moving with a velocity in pixels per second, as if released after a drag.
+    pub fn fling(&mut self, velocity: Vector) {
+        self.drag = None;
+        self.velocity = velocity;
+    }
+
+    /// Starts a drag, stopping any motion.
+    pub fn begin_drag(&mut self) {
+        self.velocity = Vector::zero();
+        self.drag = Some(Drag { unbounded: self.offset, samples: VecDeque::new() });
+    }
+
+    /// Moves the content along with the pointer, which moved by `delta`. Ignored if not dragging.
+    ///
+    /// Past the edges, the content follows the pointer with increasing resistance.
+    #[inline]
+    pub fn drag(&mut self, delta: Vector) {
+        self.drag_at(delta, Instant::now())
+    }
+
+    /// Same as [`drag`](ScrollPhysics::drag), where `now` is the current time.
+    pub fn drag_at(&mut self, delta: Vector, now: Instant) {
+        let (max, viewport, overscroll) = (self.max_offset(), self.viewport, self.overscroll);
+        let offset = match &mut self.drag {
+            Some(drag) => {
+                drag.unbounded -= delta;
+                drag.samples.push_back((now, delta));
+                while drag.samples.front().is_some_and(|(time, _)| now - *time > VELOCITY_WINDOW) {
+                    drag.samples.pop_front();
+                }
+                Vector::new(
+                    rubber_band(drag.unbounded.x, max.x, viewport.width, overscroll),
+                    rubber_band(drag.unbounded.y, max.y, viewport.height, overscroll),
+                )
+            }
+            None => return,
+        };
+        self.set_offset_internal(offset);
+    }
+
+    /// Releases the drag, continuing with the velocity of the last movements.
+    #[inline]
+    pub fn end_drag(&mut self) {
+        self.end_drag_at(Instant::now())
+    }
+
+    /// Same as [`end_drag`](ScrollPhysics::end_drag), where `now` is the current time.
+    pub fn end_drag_at(&mut self, now: Instant) {
+        let drag = match self.drag.take() {
+            Some(drag) => drag,
+            None => return,
+        };
+
+        let recent = drag.samples.iter().filter(|(time, _)| now - *time <= VELOCITY_WINDOW);
+        let (first, distance) = recent
+            .fold((None, Vector::zero()), |(first, distance), (time, delta)| {
+                (first.or(Some(*time)), distance + *delta)
+            });
+        self.velocity = match first {
+            // the first movement happened over about a frame before it was reported.
+            Some(first) => -distance / ((now - first).as_secs_f32() + 1.0 / 60.0),
+            None => Vector::zero(),
+        };
+        if self.velocity == Vector::zero() && self.clamp(self.offset) == self.offset {
+            self.event.emit_owned(ScrollEvent::Settle);
+        }
+    }
+
+    /// Advances the motion by the delta time of a frame.
+    #[inline]
+    pub fn update(&mut self, frame_time: FrameTime) {
+        self.step(frame_time.delta_secs())
+    }
+
+    /// Advances the motion by `dt` seconds.
+    pub fn step(&mut self, dt: f32) {
+        if !self.is_animating() || dt <= 0.0 {
+            return;
+        }
+
+        let (mut offset, mut velocity) = (self.offset, self.velocity);
+        let steps = (dt / MAX_STEP).ceil();
+        let dt = dt / steps;
+        for _ in 0..steps as u32 {
+            let (x, vx) = self.step_axis(offset.x, velocity.x, self.max_offset().x, dt);
+            let (y, vy) = self.step_axis(offset.y, velocity.y, self.max_offset().y, dt);
+            offset = Vector::new(x, y);
+            velocity = Vector::new(vx, vy);
+        }
+
+        let target = self.clamp(offset);
+        if velocity.length() < REST_VELOCITY && (offset - target).length() < REST_DISTANCE {
+            self.velocity = Vector::zero();
+            self.set_offset_internal(target);
+            self.event.emit_owned(ScrollEvent::Settle);
+        } else {
+            self.velocity = velocity;
+            self.set_offset_internal(offset);
+        }
+    }
+
+    fn step_axis(&self, offset: f32, velocity: f32, max: f32, dt: f32) -> (f32, f32) {
+        let target = offset.clamp(0.0, max);
+        let velocity = if offset == target {
+            velocity * (-self.friction * dt).exp()
+        } else {
+            let displacement = offset - target;
+            let damping = 2.0 * self.stiffness.sqrt();
+            velocity + (-self.stiffness * displacement - damping * velocity) * dt
+        };
+        let next = offset + velocity * dt;
+        if offset != target && (next - target).signum() != (offset - target).signum() {
+            // the spring doesn't overshoot; settle at the edge.
+            (target, 0.0)
+        } else {
+            (next, velocity)
+        }
+    }
+
+    fn clamp(&self, offset: Vector) -> Vector {
+        let max = self.max_offset();
+        Vector::new(offset.x.clamp(0.0, max.x), offset.y.clamp(0.0, max.y))
+    }
+
+    fn set_offset_internal(&mut self, offset: Vector) {
+        if self.offset != offset {
+            self.offset = offset;
+            self.event.emit_owned(ScrollEvent::Offset(offset));
+        }
+    }
+}
+
+/// Applies resistance to the part of `offset` outside of `0..=max`, such that it never exceeds `extent`.
+fn rubber_band(offset: f32, max: f32, extent: f32, resistance: f32) -> f32 {
+    let band = |x: f32| {
+        if extent <= 0.0 {
+            0.0
+        } else {
+            (1.0 - 1.0 / (x * resistance / extent + 1.0)) * extent
+        }
+    };
+    if offset < 0.0 {
+        -band(-offset)
+    } else if offset > max {
+        max + band(offset - max)
+    } else {
+        offset
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settle(scroll: &mut ScrollPhysics) -> usize {
+        let mut frames = 0;
+        while scroll.is_animating() {
+            scroll.step(1.0 / 60.0);
+            frames += 1;
+            assert!(frames < 600, "never settled");
+        }
+        frames
+    }
+
+    #[test]
+    fn test_scroll_physics() {
+        let mut scroll = ScrollPhysics::new(Size::new(100.0, 100.0), Size::new(100.0, 1000.0));
+        let listener = scroll.event.listen();
+        assert_eq!(scroll.max_offset(), Vector::new(0.0, 900.0));
+
+        // a quick flick upwards keeps scrolling after the release.
+        let start = Instant::now();
+        scroll.begin_drag();
+        for i in 1..=5 {
+            scroll.drag_at(Vector::new(0.0, -10.0), start + Duration::from_millis(i * 10));
+        }
+        assert_eq!(scroll.offset(), Vector::new(0.0, 50.0));
+        scroll.end_drag_at(start + Duration::from_millis(50));
+        assert!(scroll.velocity().y > 800.0);
+        assert!(scroll.is_animating());
+
+        settle(&mut scroll);
+        let offset = scroll.offset().y;
+        assert!(offset > 300.0 && offset <= 900.0);
+        let events = listener.peek();
+        assert_eq!(events.last(), Some(&ScrollEvent::Settle));
+        assert_eq!(
+            events.len() - 1,
+            events.iter().filter(|e| matches!(e, ScrollEvent::Offset(_))).count()
+        );
+
+        // dragging past the top edge is resisted, and springs back once released.
+        scroll.set_offset(Vector::zero());
+        scroll.begin_drag();
+        scroll.drag_at(Vector::new(0.0, 200.0), start + Duration::from_secs(1));
+        let overscroll = -scroll.offset().y;
+        assert!(overscroll > 0.0 && overscroll < 100.0);
+        // held still before releasing, so there's no fling.
+        scroll.end_drag_at(start + Duration::from_secs(2));
+        assert_eq!(scroll.velocity(), Vector::zero());
+        assert!(scroll.is_animating());
+        settle(&mut scroll);
+        assert_eq!(scroll.offset(), Vector::zero());
+
+        // flinging past the end comes to rest at the end.
+        scroll.fling(Vector::new(0.0, 100_000.0));
+        settle(&mut scroll);
+        assert_eq!(scroll.offset(), Vector::new(0.0, 900.0));
+
+        // shrinking the content pulls the offset back within bounds.
+        scroll.set_content_size(Size::new(100.0, 500.0));
+        assert!(scroll.is_animating());
+        settle(&mut scroll);
+        assert_eq!(scroll.offset(), Vector::new(0.0, 400.0));
+
+        scroll.scroll_by(Vector::new(50.0, -1000.0));
+        assert_eq!(scroll.offset(), Vector::zero());
+        assert!(!scroll.is_animating());
+    }
+}
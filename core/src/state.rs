@@ -0,0 +1,268 @@
+//! Persistence of UI state (scroll positions, expanded nodes, text contents, etc.) across sessions.
+//!
+//! Widgets opt in by implementing [`PersistentState`](PersistentState), usually through [`SaveState`](SaveState)
+//! (which can be derived for a subset of fields), and returning themselves from
+//! [`Widget::persistent_state`](crate::widget::Widget::persistent_state) and
+//! [`Widget::persistent_state_mut`](crate::widget::Widget::persistent_state_mut). [`save`](save) then walks the
+//! widget tree into a
+//! [`StateTree`](StateTree), which serializes with `serde`, and [`restore`](restore) applies one back:
+//!
+//! ```ignore
+//! // on exit:
+//! std::fs::write("ui.json", state::save_json(&root)?)?;
+//!
+//! // once the tree is built again:
+//! if let Ok(json) = std::fs::read_to_string("ui.json") {
+//!     state::restore_json(&mut root, &json)?;
+//! }
+//! ```
+//!
+//! Widgets are matched by their position within the tree and their [`debug_name`](crate::widget::Widget::debug_name),
+//! so state saved from a tree with a different structure is only restored where it still lines up.
+
+use {
+    crate::widget::WidgetChildren,
+    serde::{Deserialize, Serialize},
+    serde_json::Value,
+};
+
+/// Saved state of a widget and its children.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateTree {
+    /// See [`Widget::debug_name`](crate::widget::Widget::debug_name).
+    pub name: String,
+    /// State returned by [`save_state`](PersistentState::save_state), if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<StateTree>,
+}
+
+impl StateTree {
+    /// Returns `true` if neither this widget nor any of its descendants saved state.
+    pub fn is_empty(&self) -> bool {
+        self.state.is_none() && self.children.iter().all(StateTree::is_empty)
+    }
+}
+
+/// State of a widget which persists across sessions (e.g. a scroll position or text contents).
+///
+/// The widget must also return itself from [`Widget::persistent_state`](crate::widget::Widget::persistent_state) and
+/// [`Widget::persistent_state_mut`](crate::widget::Widget::persistent_state_mut) to be found in the widget tree.
+pub trait PersistentState {
+    /// State to persist, or `None` if there is none at the moment.
+    fn save_state(&self) -> Option<Value>;
+
+    /// Restores state previously returned from [`save_state`](PersistentState::save_state).
+    ///
+    /// State may come from an older version of the application, so invalid state should be ignored.
+    fn restore_state(&mut self, state: &Value);
+}
+
+/// Saves and restores a subset of fields, for implementing [`PersistentState`](PersistentState).
+///
+/// This is typically derived, with the persisted fields marked as `#[save_state]`. Fields are saved as a JSON object
+/// keyed by field name, and restoring skips fields which are missing or fail to deserialize, so that state saved by
+/// an older version of a widget can still be loaded. The derive also repaints any fields marked as `#[command_group]`
+/// once restored.
+///
+/// ```ignore
+/// #[derive(SaveState)]
+/// struct Tree {
+///     #[save_state]
+///     expanded: Vec<bool>,
+///     #[command_group]
+///     command_group: CommandGroup,
+/// }
+///
+/// impl PersistentState for Tree {
+///     fn save_state(&self) -> Option<Value> {
+///         Some(self.save_fields())
+///     }
+///
+///     fn restore_state(&mut self, state: &Value) {
+///         self.restore_fields(state);
+///     }
+/// }
+///
+/// impl Widget for Tree {
+///     fn persistent_state(&self) -> Option<&dyn PersistentState> {
+///         Some(self)
+///     }
+///
+///     fn persistent_state_mut(&mut self) -> Option<&mut dyn PersistentState> {
+///         Some(self)
+///     }
+///
+///     // --snip--
+/// }
+/// ```
+pub trait SaveState {
+    fn save_fields(&self) -> Value;
+    fn restore_fields(&mut self, state: &Value);
+}
+
+/// Walks the widget tree, starting at `root`, collecting the saved state of every widget.
+pub fn save<U, G, D>(
+    root: &dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = D>,
+) -> StateTree {
+    StateTree {
+        name: root.debug_name().into(),
+        state: root.persistent_state().and_then(PersistentState::save_state),
+        children: root.children().into_iter().map(save).collect(),
+    }
+}
+
+/// Applies `tree` to the widget tree, starting at `root`, returning the number of widgets which were restored.
+///
+/// Widgets (along with their children) are skipped if their name doesn't match the saved name at the same position.
+pub fn restore<U, G, D>(
+    root: &mut dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = D>,
+    tree: &StateTree,
+) -> usize {
+    if root.debug_name() != tree.name {
+        return 0;
+    }
+
+    let mut restored = 0;
+    if let (Some(state), Some(persistent)) = (&tree.state, root.persistent_state_mut()) {
+        persistent.restore_state(state);
+        restored += 1;
+    }
+    for (child, tree) in root.children_mut().into_iter().zip(&tree.children) {
+        restored += restore(child, tree);
+    }
+    restored
+}
+
+/// [`save`](save)s the widget tree as a JSON string.
+pub fn save_json<U, G, D>(
+    root: &dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = D>,
+) -> Result<String, serde_json::Error> {
+    serde_json::to_string(&save(root))
+}
+
+/// [`restore`](restore)s the widget tree from a JSON string, as returned by [`save_json`](save_json).
+pub fn restore_json<U, G, D>(
+    root: &mut dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = D>,
+    json: &str,
+) -> Result<usize, serde_json::Error> {
+    Ok(restore(root, &serde_json::from_str(json)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::widget::{ChildrenList, ChildrenListMut, Widget},
+        smallvec::smallvec,
+    };
+
+    #[derive(Default)]
+    struct Scroll {
+        offset: f32,
+        restored: usize,
+    }
+
+    impl SaveState for Scroll {
+        fn save_fields(&self) -> Value {
+            serde_json::json!({ "offset": self.offset })
+        }
+
+        fn restore_fields(&mut self, state: &Value) {
+            if let Some(offset) = state.get("offset").and_then(Value::as_f64) {
+                self.offset = offset as _;
+            }
+            self.restored += 1;
+        }
+    }
+
+    impl Widget for Scroll {
+        type UpdateAux = ();
+        type GraphicalAux = ();
+        type DisplayObject = ();
+
+        fn debug_name(&self) -> &'static str {
+            "Scroll"
+        }
+
+        fn persistent_state(&self) -> Option<&dyn PersistentState> {
+            Some(self)
+        }
+
+        fn persistent_state_mut(&mut self) -> Option<&mut dyn PersistentState> {
+            Some(self)
+        }
+    }
+
+    impl PersistentState for Scroll {
+        fn save_state(&self) -> Option<Value> {
+            Some(self.save_fields())
+        }
+
+        fn restore_state(&mut self, state: &Value) {
+            self.restore_fields(state);
+        }
+    }
+
+    impl WidgetChildren for Scroll {}
+
+    struct Label;
+
+    impl Widget for Label {
+        type UpdateAux = ();
+        type GraphicalAux = ();
+        type DisplayObject = ();
+
+        fn debug_name(&self) -> &'static str {
+            "Label"
+        }
+    }
+
+    impl WidgetChildren for Label {}
+
+    struct Window(Label, Scroll);
+
+    impl Widget for Window {
+        type UpdateAux = ();
+        type GraphicalAux = ();
+        type DisplayObject = ();
+
+        fn debug_name(&self) -> &'static str {
+            "Window"
+        }
+    }
+
+    impl WidgetChildren for Window {
+        fn children(&self) -> ChildrenList<'_, (), (), ()> {
+            smallvec![&self.0 as _, &self.1 as _]
+        }
+
+        fn children_mut(&mut self) -> ChildrenListMut<'_, (), (), ()> {
+            smallvec![&mut self.0 as _, &mut self.1 as _]
+        }
+    }
+
+    #[test]
+    fn test_save_restore() {
+        let window = Window(Label, Scroll { offset: 120.0, restored: 0 });
+        let json = save_json(&window).unwrap();
+
+        let tree = save(&window);
+        assert!(!tree.is_empty());
+        assert!(tree.children[0].is_empty());
+
+        let mut restored = Window(Label, Scroll::default());
+        assert_eq!(restore_json(&mut restored, &json).unwrap(), 1);
+        assert_eq!(restored.1.offset, 120.0);
+
+        // mismatched structure isn't restored.
+        let mut tree: StateTree = serde_json::from_str(&json).unwrap();
+        tree.children.swap(0, 1);
+        let mut mismatched = Window(Label, Scroll::default());
+        assert_eq!(restore(&mut mismatched, &tree), 0);
+        assert_eq!(mismatched.1.restored, 0);
+
+        assert!(restore_json(&mut mismatched, "{").is_err());
+    }
+}
@@ -0,0 +1,275 @@
+//! Style classes and selector-based styling, so that a set of widgets can be themed consistently.
+//!
+//! A widget exposes a style class (e.g. `"button"`) and its interaction state through [`Styled`](Styled).
+//! A [`StyleSheet`](StyleSheet) resolves these to the paint parameters used while drawing,
+//! by applying every rule whose [`Selector`](Selector) matches on top of a base style:
+//!
+//! ```ignore
+//! let styles = StyleSheet::new(Style::default())
+//!     .and_rule(Selector::any().hovered(), vec![StyleProperty::Background(hover_color)])
+//!     .and_rule(Selector::class("button"), vec![StyleProperty::CornerRadius(3.0)]);
+//!
+//! // in draw:
+//! let style = styles.resolve_for(self);
+//! builder.push_round_rectangle(bounds, [style.corner_radius; 4], style.background_paint(), vec![]);
+//! ```
+//!
+//! As in CSS, more specific rules take precedence; a class is more specific than any combination of state flags,
+//! and more state flags are more specific than fewer. Rules of the same specificity apply in the order they were added.
+
+use crate::display::{Color, GraphicsDisplayPaint, GraphicsDisplayStroke};
+
+/// Interaction state of a widget, matched by [`Selector`](Selector)s.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StyleState {
+    pub hovered: bool,
+    pub pressed: bool,
+    pub focused: bool,
+    pub disabled: bool,
+}
+
+impl StyleState {
+    /// Returns `true` if every flag set in `other` is also set in `self`.
+    pub fn contains(self, other: StyleState) -> bool {
+        (self.hovered || !other.hovered)
+            && (self.pressed || !other.pressed)
+            && (self.focused || !other.focused)
+            && (self.disabled || !other.disabled)
+    }
+
+    /// Returns the number of flags which are set.
+    pub fn count(self) -> u32 {
+        self.hovered as u32 + self.pressed as u32 + self.focused as u32 + self.disabled as u32
+    }
+}
+
+/// Matches widgets by style class and state.
+///
+/// A selector without a class matches every class, and it matches a state if the state has (at least)
+/// all of the flags of the selector set.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Selector {
+    pub class: Option<String>,
+    pub state: StyleState,
+}
+
+impl Selector {
+    /// Creates a selector matching every widget.
+    pub fn any() -> Self {
+        Default::default()
+    }
+
+    /// Creates a selector matching widgets of a style class.
+    pub fn class(class: impl Into<String>) -> Self {
+        Selector { class: Some(class.into()), state: Default::default() }
+    }
+
+    /// Only matches hovered widgets.
+    pub fn hovered(mut self) -> Self {
+        self.state.hovered = true;
+        self
+    }
+
+    /// Only matches pressed widgets.
+    pub fn pressed(mut self) -> Self {
+        self.state.pressed = true;
+        self
+    }
+
+    /// Only matches focused widgets.
+    pub fn focused(mut self) -> Self {
+        self.state.focused = true;
+        self
+    }
+
+    /// Only matches disabled widgets.
+    pub fn disabled(mut self) -> Self {
+        self.state.disabled = true;
+        self
+    }
+
+    /// Returns `true` if a widget of a given class and state is matched.
+    pub fn matches(&self, class: &str, state: StyleState) -> bool {
+        self.class.as_ref().is_none_or(|c| c == class) && state.contains(self.state)
+    }
+
+    /// Returns the specificity of the selector; rules with greater specificity take precedence.
+    pub fn specificity(&self) -> (bool, u32) {
+        (self.class.is_some(), self.state.count())
+    }
+}
+
+/// A single paint parameter set by a style rule.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StyleProperty {
+    Background(Color),
+    Foreground(Color),
+    Border(Color),
+    Accent(Color),
+    BorderWidth(f32),
+    CornerRadius(f32),
+}
+
+/// Resolved paint parameters of a widget.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Style {
+    pub background: Color,
+    /// Color of text and glyphs.
+    pub foreground: Color,
+    pub border: Color,
+    /// Color of highlights, such as checkmarks and text cursors.
+    pub accent: Color,
+    pub border_width: f32,
+    pub corner_radius: f32,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            background: Color::new(1.0, 1.0, 1.0, 1.0),
+            foreground: Color::new(0.0, 0.0, 0.0, 1.0),
+            border: Color::new(0.0, 0.0, 0.0, 1.0),
+            accent: Color::new(0.0, 0.0, 1.0, 1.0),
+            border_width: 1.0,
+            corner_radius: 0.0,
+        }
+    }
+}
+
+impl Style {
+    /// Overrides the parameter set by `property`.
+    pub fn apply(&mut self, property: StyleProperty) {
+        match property {
+            StyleProperty::Background(color) => self.background = color,
+            StyleProperty::Foreground(color) => self.foreground = color,
+            StyleProperty::Border(color) => self.border = color,
+            StyleProperty::Accent(color) => self.accent = color,
+            StyleProperty::BorderWidth(width) => self.border_width = width,
+            StyleProperty::CornerRadius(radius) => self.corner_radius = radius,
+        }
+    }
+
+    /// Returns a fill of the background color.
+    pub fn background_paint(&self) -> GraphicsDisplayPaint {
+        GraphicsDisplayPaint::Fill(self.background.into())
+    }
+
+    /// Returns a stroke of the border color and width.
+    pub fn border_paint(&self) -> GraphicsDisplayPaint {
+        GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+            color: self.border.into(),
+            thickness: self.border_width,
+            ..Default::default()
+        })
+    }
+}
+
+/// A widget which can be styled by a [`StyleSheet`](StyleSheet).
+pub trait Styled {
+    /// Returns the style class of the widget, matched by [`Selector::class`](Selector::class).
+    fn style_class(&self) -> &str;
+
+    /// Returns the current interaction state of the widget.
+    fn style_state(&self) -> StyleState {
+        Default::default()
+    }
+}
+
+/// A base style and a list of rules overriding it for matching widgets.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct StyleSheet {
+    pub base: Style,
+    rules: Vec<(Selector, Vec<StyleProperty>)>,
+}
+
+impl StyleSheet {
+    /// Creates a style sheet without rules.
+    pub fn new(base: Style) -> Self {
+        StyleSheet { base, rules: Vec::new() }
+    }
+
+    /// Adds a rule, which sets `properties` for the widgets matched by `selector`.
+    pub fn add_rule(&mut self, selector: Selector, properties: Vec<StyleProperty>) {
+        self.rules.push((selector, properties));
+    }
+
+    /// Adds a rule and returns `self`, for builder-style construction.
+    pub fn and_rule(mut self, selector: Selector, properties: Vec<StyleProperty>) -> Self {
+        self.add_rule(selector, properties);
+        self
+    }
+
+    /// Returns the style of a widget of a given class and state.
+    pub fn resolve(&self, class: &str, state: StyleState) -> Style {
+        let mut rules: Vec<_> =
+            self.rules.iter().filter(|(s, _)| s.matches(class, state)).collect();
+        // stable; rules of the same specificity keep their order.
+        rules.sort_by_key(|(selector, _)| selector.specificity());
+
+        let mut style = self.base;
+        for property in rules.into_iter().flat_map(|(_, properties)| properties) {
+            style.apply(*property);
+        }
+        style
+    }
+
+    /// Returns the style of a widget.
+    pub fn resolve_for(&self, widget: &(impl Styled + ?Sized)) -> Style {
+        self.resolve(widget.style_class(), widget.style_state())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_style_sheet() {
+        let red = Color::new(1.0, 0.0, 0.0, 1.0);
+        let green = Color::new(0.0, 1.0, 0.0, 1.0);
+        let blue = Color::new(0.0, 0.0, 1.0, 1.0);
+
+        let styles = StyleSheet::new(Style::default())
+            .and_rule(Selector::class("button").hovered(), vec![StyleProperty::Background(blue)])
+            .and_rule(
+                Selector::any().hovered().pressed(),
+                vec![StyleProperty::Background(green), StyleProperty::BorderWidth(2.0)],
+            )
+            .and_rule(Selector::any().hovered(), vec![StyleProperty::Background(red)])
+            .and_rule(Selector::class("button"), vec![StyleProperty::CornerRadius(3.0)])
+            .and_rule(Selector::class("button"), vec![StyleProperty::CornerRadius(4.0)]);
+
+        let hovered = StyleState { hovered: true, ..Default::default() };
+        let pressed = StyleState { pressed: true, ..hovered };
+
+        assert_eq!(styles.resolve("label", Default::default()), Style::default());
+        assert_eq!(styles.resolve("label", hovered).background, red);
+        assert_eq!(styles.resolve("label", pressed).background, green);
+        assert_eq!(styles.resolve("label", pressed).border_width, 2.0);
+
+        // the class is more specific than any state.
+        let button = styles.resolve("button", pressed);
+        assert_eq!(button.background, blue);
+        assert_eq!(button.border_width, 2.0);
+        // later rules of the same specificity win.
+        assert_eq!(button.corner_radius, 4.0);
+
+        struct Widget(StyleState);
+
+        impl Styled for Widget {
+            fn style_class(&self) -> &str {
+                "button"
+            }
+
+            fn style_state(&self) -> StyleState {
+                self.0
+            }
+        }
+
+        assert_eq!(styles.resolve_for(&Widget(hovered)).background, blue);
+        assert_eq!(
+            styles.resolve_for(&Widget(Default::default())).background,
+            Color::new(1.0, 1.0, 1.0, 1.0)
+        );
+    }
+}
@@ -0,0 +1,255 @@
+//! Headless test harness, to drive a widget tree with synthetic input without opening a window.
+
+use crate::{
+    clipboard::ClipboardAction,
+    display::{recording::RecordingDisplay, GraphicsDisplay, Point},
+    event::RcEventQueue,
+    input::{FileDropEvent, Key, Modifiers, MouseButton, TouchEvent, TouchPhase, WindowEvent},
+    pacer::{FrameAux, FrameTime},
+    prelude::*,
+    widget::FileDropRouter,
+};
+use std::time::Duration;
+
+/// Owns a root widget, a [`RecordingDisplay`](crate::display::recording::RecordingDisplay) and the global
+/// [`WindowEvent`](crate::input::WindowEvent) queue, mirroring what the application runner does.
+///
+/// Every input method emits into the global queue, then updates the root widget.
+///
+/// ```ignore
+/// let mut harness = Harness::new(|window_q, _| Counter::new(window_q), (), ());
+/// harness.click(Point::new(10.0, 10.0));
+/// assert_eq!(harness.root().count, 1);
+/// ```
+pub struct Harness<W: WidgetChildren> {
+    root: W,
+    queue: RcEventQueue<WindowEvent>,
+    display: RecordingDisplay<W::DisplayObject>,
+    update_aux: W::UpdateAux,
+    graphical_aux: W::GraphicalAux,
+    frame_time: FrameTime,
+    cursor: Point,
+    file_drops: FileDropRouter,
+}
+
+impl<W> Harness<W>
+where
+    W: WidgetChildren,
+    W::UpdateAux: FrameAux,
+    W::DisplayObject: Clone,
+{
+    /// Creates the harness, where `factory` creates the root widget from the global event queue
+    /// and the display (which can be used to load resources up-front).
+    pub fn new<F>(factory: F, update_aux: W::UpdateAux, graphical_aux: W::GraphicalAux) -> Self
+    where
+        F: FnOnce(&RcEventQueue<WindowEvent>, &mut dyn GraphicsDisplay<W::DisplayObject>) -> W,
+    {
+        let queue = RcEventQueue::default();
+        let mut display = RecordingDisplay::default();
+        let root = factory(&queue, &mut display);
+        Harness {
+            root,
+            queue,
+            display,
+            update_aux,
+            graphical_aux,
+            frame_time: FrameTime::default(),
+            cursor: Point::default(),
+            file_drops: FileDropRouter::new(),
+        }
+    }
+
+    /// Returns the root widget.
+    pub fn root(&self) -> &W {
+        &self.root
+    }
+
+    /// Returns the root widget mutably.
+    pub fn root_mut(&mut self) -> &mut W {
+        &mut self.root
+    }
+
+    /// Returns the global event queue.
+    pub fn queue(&self) -> &RcEventQueue<WindowEvent> {
+        &self.queue
+    }
+
+    /// Returns the display the widgets are drawn into.
+    pub fn display(&self) -> &RecordingDisplay<W::DisplayObject> {
+        &self.display
+    }
+
+    /// Returns the update auxiliary.
+    pub fn update_aux(&mut self) -> &mut W::UpdateAux {
+        &mut self.update_aux
+    }
+
+    /// Returns the current (synthetic) frame time.
+    pub fn frame_time(&self) -> FrameTime {
+        self.frame_time
+    }
+
+    /// Updates the root widget.
+    pub fn update(&mut self) {
+        self.update_aux.set_frame_time(self.frame_time);
+        self.root.update(&mut self.update_aux);
+    }
+
+    /// Emits an event into the global queue, then updates.
+    pub fn emit(&mut self, event: WindowEvent) {
+        if let WindowEvent::MouseMove(point) = event {
+            self.cursor = point;
+        }
+        self.queue.emit_owned(event);
+        self.update();
+    }
+
+    /// Moves the cursor to `point`.
+    pub fn move_mouse(&mut self, point: Point) {
+        self.emit(WindowEvent::MouseMove(point));
+    }
+
+    /// Moves the cursor to `point` (if not already there), then presses and releases the left mouse button.
+    pub fn click(&mut self, point: Point) {
+        if self.cursor != point {
+            self.move_mouse(point);
+        }
+        self.emit(WindowEvent::MousePress(point, MouseButton::Left));
+        self.emit(WindowEvent::MouseRelease(point, MouseButton::Left));
+    }
+
+    /// Taps the screen at `point` with a single finger, i.e. starts and ends a touch with the given id.
+    pub fn tap(&mut self, id: u64, point: Point) {
+        for phase in [TouchPhase::Start, TouchPhase::End] {
+            self.emit(WindowEvent::Touch(TouchEvent { id, phase, position: point, force: None }));
+        }
+    }
+
+    /// Types a character.
+    pub fn key(&mut self, c: char) {
+        self.emit(WindowEvent::Character(c));
+    }
+
+    /// Presses and releases a key, e.g. to trigger a shortcut.
+    ///
+    /// As with the application runner, clipboard shortcuts also emit [`Clipboard`](WindowEvent::Clipboard).
+    pub fn press_key(&mut self, key: Key, modifiers: Modifiers) {
+        self.emit(WindowEvent::KeyPress(key, modifiers));
+        if let Some(action) = ClipboardAction::from_chord(key, modifiers) {
+            self.emit(WindowEvent::Clipboard(action));
+        }
+        self.emit(WindowEvent::KeyRelease(key, modifiers));
+    }
+
+    /// Types every character in `text`.
+    pub fn type_text(&mut self, text: &str) {
+        for c in text.chars() {
+            self.key(c);
+        }
+    }
+
+    /// Delivers a file drop event to the widget under its position, then updates.
+    ///
+    /// Returns the path of the widget which accepted the event, see [`FileDropRouter`](FileDropRouter).
+    pub fn file_drop(&mut self, event: FileDropEvent) -> Option<Vec<usize>> {
+        self.update_aux.set_frame_time(self.frame_time);
+        let accepted = self.file_drops.route(&mut self.root, &event, &mut self.update_aux);
+        self.update();
+        accepted
+    }
+
+    /// Advances the synthetic clock by `ms` milliseconds as a single frame, then updates.
+    pub fn advance_time(&mut self, ms: u64) {
+        let delta_time = Duration::from_millis(ms);
+        self.frame_time = FrameTime {
+            delta_time,
+            elapsed: self.frame_time.elapsed + delta_time,
+            frame: self.frame_time.frame + 1,
+        };
+        self.update();
+    }
+
+    /// Draws the root widget and presents the display, returning the presented display list.
+    pub fn render_to_buffer(&mut self) -> &[W::DisplayObject] {
+        self.root.draw(&mut self.display, &mut self.graphical_aux);
+        // presenting a recording display can't fail.
+        let _ = self.display.present(None);
+        self.display.presented()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            display::CommandGroup,
+            event::{RcEventListener, RcEventQueue},
+        },
+    };
+
+    struct Clicker {
+        clicks: u32,
+        text: String,
+        elapsed: Duration,
+        listener: RcEventListener<WindowEvent>,
+        command_group: CommandGroup,
+    }
+
+    impl Widget for Clicker {
+        type UpdateAux = FrameTime;
+        type GraphicalAux = ();
+        type DisplayObject = u32;
+
+        fn update(&mut self, aux: &mut FrameTime) {
+            self.elapsed = aux.elapsed;
+            for event in self.listener.peek() {
+                match event {
+                    WindowEvent::MouseRelease(..) => {
+                        self.clicks += 1;
+                        self.command_group.repaint();
+                    }
+                    WindowEvent::Character(c) => self.text.push(c),
+                    _ => (),
+                }
+            }
+        }
+
+        fn draw(&mut self, display: &mut dyn GraphicsDisplay<u32>, _aux: &mut ()) {
+            self.command_group
+                .push(display, &[self.clicks], Default::default(), None, None)
+                .unwrap();
+        }
+    }
+
+    impl WidgetChildren for Clicker {}
+
+    #[test]
+    fn test_harness() {
+        let mut harness = Harness::new(
+            |queue: &RcEventQueue<WindowEvent>, _: &mut dyn GraphicsDisplay<u32>| Clicker {
+                clicks: 0,
+                text: String::new(),
+                elapsed: Duration::default(),
+                listener: queue.listen(),
+                command_group: CommandGroup::new(),
+            },
+            FrameTime::default(),
+            (),
+        );
+
+        assert_eq!(harness.render_to_buffer(), &[0]);
+
+        harness.click(Point::new(4.0, 2.0));
+        harness.click(Point::new(4.0, 2.0));
+        harness.type_text("hi");
+        harness.advance_time(16);
+        harness.advance_time(16);
+
+        assert_eq!(harness.root().clicks, 2);
+        assert_eq!(harness.root().text, "hi");
+        assert_eq!(harness.root().elapsed, Duration::from_millis(32));
+        assert_eq!(harness.frame_time().frame, 2);
+        assert_eq!(harness.render_to_buffer(), &[2]);
+    }
+}
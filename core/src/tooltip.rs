@@ -0,0 +1,359 @@
+//! Tooltips, shown after hovering an area for a while and dismissed once the cursor leaves it.
+//!
+//! A [`TooltipManager`](TooltipManager) is typically owned by the root widget. Widgets register the area a tooltip
+//! belongs to along with its content, and the root updates and draws the manager after its children:
+//!
+//! ```ignore
+//! let mut tooltips = TooltipManager::new(window_q, window_size);
+//! let id = tooltips.register(button.bounds(), tooltip_commands);
+//!
+//! // in update:
+//! tooltips.update();
+//! // in draw, after the children:
+//! tooltips.draw(display)?;
+//! ```
+//!
+//! Tooltip content is drawn relative to the origin; the manager places it next to the cursor,
+//! moving it so that it stays within the window.
+
+use {
+    crate::{
+        display::{
+            display_list_bounds, CommandGroup, DisplayCommand, GraphicsDisplay, Point, Rect, Size,
+            Vector, ZOrder,
+        },
+        error,
+        event::{RcEventListener, RcEventQueue},
+        input::WindowEvent,
+        prelude::*,
+    },
+    std::time::{Duration, Instant},
+};
+
+/// Gap between the cursor and a tooltip which is shown above the cursor.
+const ABOVE_GAP: f32 = 4.0;
+
+/// Identifies a tooltip registered with a [`TooltipManager`](TooltipManager).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TooltipId(u64);
+
+type TooltipFactory = Box<dyn FnMut() -> Vec<DisplayCommand>>;
+
+struct Tooltip {
+    id: TooltipId,
+    area: Rect,
+    content: TooltipFactory,
+}
+
+struct ShownTooltip {
+    id: TooltipId,
+    commands: Vec<DisplayCommand>,
+    bounds: Rect,
+    position: Point,
+}
+
+/// Shows the tooltip of the hovered area after a delay, in its own command group above everything else.
+///
+/// A tooltip is hidden as soon as the cursor leaves its area, a mouse button is pressed or the window loses focus.
+/// After a press, the tooltip isn't shown again until the cursor re-enters the area.
+pub struct TooltipManager {
+    listener: RcEventListener<WindowEvent>,
+    tooltips: Vec<Tooltip>,
+    next_id: u64,
+    delay: Duration,
+    offset: Vector,
+    z_order: ZOrder,
+    window_size: Size,
+    cursor: Point,
+    hovered: Option<(TooltipId, Instant)>,
+    dismissed: bool,
+    shown: Option<ShownTooltip>,
+    command_group: CommandGroup,
+}
+
+impl TooltipManager {
+    /// Creates a tooltip manager, handling input from the global window event queue.
+    ///
+    /// `window_size` is updated from [`Resize`](WindowEvent::Resize) events from then on.
+    pub fn new(window_q: &RcEventQueue<WindowEvent>, window_size: Size) -> Self {
+        TooltipManager {
+            listener: window_q.listen(),
+            tooltips: Vec::new(),
+            next_id: 0,
+            delay: Duration::from_millis(500),
+            offset: Vector::new(12.0, 16.0),
+            z_order: ZOrder(i32::MAX),
+            window_size,
+            cursor: Point::default(),
+            hovered: None,
+            dismissed: false,
+            shown: None,
+            command_group: CommandGroup::with_label("tooltip"),
+        }
+    }
+
+    /// Registers a tooltip for `area`, showing a fixed list of display commands.
+    ///
+    /// Where areas overlap, the tooltip registered last is shown.
+    pub fn register(&mut self, area: Rect, commands: Vec<DisplayCommand>) -> TooltipId {
+        self.register_with(area, move || commands.clone())
+    }
+
+    /// Registers a tooltip for `area`, whose display commands are built by `factory` every time it's shown.
+    pub fn register_with(
+        &mut self,
+        area: Rect,
+        factory: impl FnMut() -> Vec<DisplayCommand> + 'static,
+    ) -> TooltipId {
+        let id = TooltipId(self.next_id);
+        self.next_id += 1;
+        self.tooltips.push(Tooltip { id, area, content: Box::new(factory) });
+        id
+    }
+
+    /// Moves the area of a tooltip, e.g. after the widget it belongs to was laid out again.
+    pub fn set_area(&mut self, id: TooltipId, area: Rect) {
+        if let Some(tooltip) = self.tooltips.iter_mut().find(|tooltip| tooltip.id == id) {
+            tooltip.area = area;
+        }
+    }
+
+    /// Removes a tooltip, hiding it if it's shown.
+    pub fn unregister(&mut self, id: TooltipId) {
+        self.tooltips.retain(|tooltip| tooltip.id != id);
+        if self.hovered.map(|(hovered, _)| hovered) == Some(id) {
+            self.hovered = None;
+            self.hide();
+        }
+    }
+
+    /// Sets how long the cursor has to stay within an area before its tooltip is shown (500ms by default).
+    pub fn set_delay(&mut self, delay: Duration) {
+        self.delay = delay;
+    }
+
+    /// Builder-style variant of [`set_delay`](TooltipManager::set_delay).
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.set_delay(delay);
+        self
+    }
+
+    /// Returns the hover delay.
+    pub fn delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// Sets the offset of tooltips from the cursor, leaving room for the cursor icon.
+    pub fn set_offset(&mut self, offset: Vector) {
+        self.offset = offset;
+    }
+
+    /// Sets the stacking order of the tooltip command group (`ZOrder(i32::MAX)` by default).
+    pub fn set_z_order(&mut self, z_order: ZOrder) {
+        self.z_order = z_order;
+        self.command_group.repaint();
+    }
+
+    /// Returns the tooltip currently shown, if any.
+    pub fn shown(&self) -> Option<TooltipId> {
+        self.shown.as_ref().map(|shown| shown.id)
+    }
+
+    /// Returns the window-space bounds of the tooltip currently shown, if any.
+    pub fn shown_bounds(&self) -> Option<Rect> {
+        self.shown.as_ref().map(|shown| shown.bounds.translate(shown.position.to_vector()))
+    }
+
+    /// Returns when the tooltip of the hovered area is due to be shown, if it isn't yet.
+    ///
+    /// The application should update the manager again at that time (e.g. by waking up the event loop).
+    pub fn show_at(&self) -> Option<Instant> {
+        match self.hovered {
+            Some((_, since)) if self.shown.is_none() && !self.dismissed => Some(since + self.delay),
+            _ => None,
+        }
+    }
+
+    /// Handles the pending window events and shows the tooltip of the hovered area if the delay elapsed.
+    #[inline]
+    pub fn update(&mut self) {
+        self.update_at(Instant::now())
+    }
+
+    /// Same as [`update`](TooltipManager::update), where `now` is the current time.
+    pub fn update_at(&mut self, now: Instant) {
+        for event in self.listener.peek() {
+            match event {
+                WindowEvent::MouseMove(point) => {
+                    self.cursor = point;
+                    let hit = self.hit(point);
+                    if hit != self.hovered.map(|(id, _)| id) {
+                        self.hovered = hit.map(|id| (id, now));
+                        self.dismissed = false;
+                        self.hide();
+                    }
+                }
+                WindowEvent::MousePress(..) => {
+                    self.dismissed = true;
+                    self.hide();
+                }
+                WindowEvent::Focus(false) => {
+                    self.hovered = None;
+                    self.hide();
+                }
+                WindowEvent::Resize(size) => {
+                    self.window_size = size;
+                    if let Some(shown) = &mut self.shown {
+                        shown.position = place(self.cursor, self.offset, shown.bounds, size);
+                        self.command_group.repaint();
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(show_at) = self.show_at() {
+            if now >= show_at {
+                self.show();
+            }
+        }
+    }
+
+    /// Pushes the shown tooltip into its command group, or removes the command group if none is shown.
+    pub fn draw(&mut self, display: &mut dyn GraphicsDisplay) -> Result<(), error::DisplayError> {
+        match &self.shown {
+            Some(shown) => {
+                self.command_group.push_with(
+                    display,
+                    || {
+                        let mut commands = Vec::with_capacity(shown.commands.len() + 3);
+                        commands.push(DisplayCommand::Save);
+                        commands.push(DisplayCommand::Translate(shown.position.to_vector()));
+                        commands.extend(shown.commands.iter().cloned());
+                        commands.push(DisplayCommand::Restore);
+                        commands
+                    },
+                    self.z_order,
+                    None,
+                    None,
+                )?;
+            }
+            None => self.command_group.remove(display),
+        }
+        Ok(())
+    }
+
+    fn hit(&self, point: Point) -> Option<TooltipId> {
+        self.tooltips
+            .iter()
+            .rev()
+            .find(|tooltip| tooltip.area.contains(point))
+            .map(|tooltip| tooltip.id)
+    }
+
+    fn show(&mut self) {
+        let id = match self.hovered {
+            Some((id, _)) => id,
+            None => return,
+        };
+        if let Some(tooltip) = self.tooltips.iter_mut().find(|tooltip| tooltip.id == id) {
+            let commands = (tooltip.content)();
+            // content which can't be measured is still shown, just without being kept on-screen.
+            let bounds = display_list_bounds(&commands).unwrap_or_default();
+            let position = place(self.cursor, self.offset, bounds, self.window_size);
+            self.shown = Some(ShownTooltip { id, commands, bounds, position });
+            self.command_group.repaint();
+        }
+    }
+
+    fn hide(&mut self) {
+        if self.shown.take().is_some() {
+            self.command_group.repaint();
+        }
+    }
+}
+
+/// Returns where content with `bounds` is drawn so that it's below and to the right of the cursor,
+/// or above the cursor if it doesn't fit below, while staying within the window.
+fn place(cursor: Point, offset: Vector, bounds: Rect, window_size: Size) -> Point {
+    let mut position = cursor + offset;
+    if position.y + bounds.max_y() > window_size.height {
+        position.y = cursor.y - ABOVE_GAP - bounds.max_y();
+    }
+    // overflowing the right/bottom edges is preferred over the left/top edges if the content doesn't fit at all.
+    position.x = position.x.min(window_size.width - bounds.max_x()).max(-bounds.min_x());
+    position.y = position.y.max(-bounds.min_y());
+    position
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            display::{
+                recording::RecordingDisplay, Color, DisplayListBuilder, GraphicsDisplayPaint,
+            },
+            input::MouseButton,
+        },
+    };
+
+    #[test]
+    fn test_tooltip_manager() {
+        let window_q = RcEventQueue::new();
+        let mut tooltips = TooltipManager::new(&window_q, Size::new(200.0, 100.0));
+        let mut builder = DisplayListBuilder::new();
+        builder.push_rectangle(
+            Rect::new(Point::zero(), Size::new(50.0, 20.0)),
+            GraphicsDisplayPaint::Fill(Color::new(0.0, 0.0, 0.0, 1.0).into()),
+            vec![],
+        );
+        let id =
+            tooltips.register(Rect::new(Point::zero(), Size::new(200.0, 100.0)), builder.build());
+        let mut display = RecordingDisplay::default();
+
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+        window_q.emit_owned(WindowEvent::MouseMove(Point::new(10.0, 10.0)));
+        tooltips.update_at(start);
+        assert_eq!(tooltips.show_at(), Some(start + ms(500)));
+        tooltips.update_at(start + ms(499));
+        assert_eq!(tooltips.shown(), None);
+
+        tooltips.update_at(start + ms(500));
+        assert_eq!(tooltips.shown(), Some(id));
+        assert_eq!(
+            tooltips.shown_bounds(),
+            Some(Rect::new(Point::new(22.0, 26.0), Size::new(50.0, 20.0)))
+        );
+        tooltips.draw(&mut display).unwrap();
+        assert_eq!(display.command_group_count(), 1);
+
+        // moving within the area doesn't hide the tooltip.
+        window_q.emit_owned(WindowEvent::MouseMove(Point::new(15.0, 10.0)));
+        tooltips.update_at(start + ms(600));
+        assert_eq!(tooltips.shown(), Some(id));
+
+        // pressing dismisses the tooltip until the area is entered again.
+        window_q.emit_owned(WindowEvent::MousePress(Point::new(15.0, 10.0), MouseButton::Left));
+        tooltips.update_at(start + ms(700));
+        assert_eq!(tooltips.shown(), None);
+        tooltips.update_at(start + ms(2000));
+        assert_eq!(tooltips.shown(), None);
+        tooltips.draw(&mut display).unwrap();
+        assert_eq!(display.command_group_count(), 0);
+
+        // near the bottom-right corner, the tooltip moves above the cursor and back into the window.
+        window_q.emit_owned(WindowEvent::MouseMove(Point::new(300.0, 10.0)));
+        window_q.emit_owned(WindowEvent::MouseMove(Point::new(190.0, 90.0)));
+        tooltips.update_at(start + ms(3000));
+        tooltips.update_at(start + ms(3500));
+        assert_eq!(
+            tooltips.shown_bounds(),
+            Some(Rect::new(Point::new(150.0, 66.0), Size::new(50.0, 20.0)))
+        );
+
+        tooltips.unregister(id);
+        assert_eq!(tooltips.shown(), None);
+    }
+}
@@ -0,0 +1,332 @@
+//! Helpers for custom window decorations (or in-window panels): dragging by a title bar and resizing by the edges.
+//!
+//! Both helpers are fed the global window events from a widget's update, and emit the resulting moves and resizes
+//! into their `event` queue:
+//!
+//! ```ignore
+//! let mut titlebar = DragRegion::new(Rect::new(position, Size::new(width, 30.0)));
+//! let moves = titlebar.event.listen();
+//!
+//! // in update:
+//! for event in window_listener.peek() {
+//!     titlebar.handle(&event);
+//! }
+//! for event in moves.peek() {
+//!     if let DragEvent::Move(delta) = event {
+//!         self.position = self.position_anchor + delta;
+//!     }
+//! }
+//! ```
+
+use crate::{
+    cursor::CursorIcon,
+    display::{Point, Rect, Size, Vector},
+    event::RcEventQueue,
+    input::{MouseButton, WindowEvent},
+    prelude::*,
+};
+
+/// Events emitted by a [`DragRegion`](DragRegion).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DragEvent {
+    /// The left mouse button was pressed within the region.
+    Begin(Point),
+    /// The cursor moved while dragging, by the given offset from where the drag began.
+    Move(Vector),
+    /// The left mouse button was released.
+    End,
+}
+
+/// An area (e.g. a title bar) which can be dragged with the left mouse button.
+#[derive(Debug)]
+pub struct DragRegion {
+    pub event: RcEventQueue<DragEvent>,
+    area: Rect,
+    anchor: Option<Point>,
+}
+
+impl DragRegion {
+    /// Creates a drag region covering `area`.
+    pub fn new(area: Rect) -> Self {
+        DragRegion { event: RcEventQueue::new(), area, anchor: None }
+    }
+
+    /// Returns the area which begins a drag when pressed.
+    pub fn area(&self) -> Rect {
+        self.area
+    }
+
+    /// Moves the area, typically along with whatever is being dragged.
+    ///
+    /// This doesn't affect an ongoing drag, since offsets are relative to where the drag began.
+    pub fn set_area(&mut self, area: Rect) {
+        self.area = area;
+    }
+
+    /// Returns `true` while dragging.
+    pub fn is_dragging(&self) -> bool {
+        self.anchor.is_some()
+    }
+
+    /// Handles a window event, emitting the resulting drag events.
+    pub fn handle(&mut self, event: &WindowEvent) {
+        match *event {
+            WindowEvent::MousePress(point, MouseButton::Left) if self.area.contains(point) => {
+                self.anchor = Some(point);
+                self.event.emit_owned(DragEvent::Begin(point));
+            }
+            WindowEvent::MouseMove(point) => {
+                if let Some(anchor) = self.anchor {
+                    self.event.emit_owned(DragEvent::Move(point - anchor));
+                }
+            }
+            WindowEvent::MouseRelease(_, MouseButton::Left) | WindowEvent::Focus(false)
+                if self.anchor.is_some() =>
+            {
+                self.anchor = None;
+                self.event.emit_owned(DragEvent::End);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// An edge or corner of a [`ResizeBorder`](ResizeBorder).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResizeEdge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl ResizeEdge {
+    /// Returns the cursor icon which indicates resizing by this edge.
+    pub fn cursor_icon(self) -> CursorIcon {
+        match self {
+            ResizeEdge::Top | ResizeEdge::Bottom => CursorIcon::ResizeVertical,
+            ResizeEdge::Left | ResizeEdge::Right => CursorIcon::ResizeHorizontal,
+            ResizeEdge::TopRight | ResizeEdge::BottomLeft => CursorIcon::ResizeNeSw,
+            ResizeEdge::TopLeft | ResizeEdge::BottomRight => CursorIcon::ResizeNwSe,
+        }
+    }
+
+    fn left(self) -> bool {
+        matches!(self, ResizeEdge::Left | ResizeEdge::TopLeft | ResizeEdge::BottomLeft)
+    }
+
+    fn right(self) -> bool {
+        matches!(self, ResizeEdge::Right | ResizeEdge::TopRight | ResizeEdge::BottomRight)
+    }
+
+    fn top(self) -> bool {
+        matches!(self, ResizeEdge::Top | ResizeEdge::TopLeft | ResizeEdge::TopRight)
+    }
+
+    fn bottom(self) -> bool {
+        matches!(self, ResizeEdge::Bottom | ResizeEdge::BottomLeft | ResizeEdge::BottomRight)
+    }
+}
+
+/// Events emitted by a [`ResizeBorder`](ResizeBorder).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResizeEvent {
+    /// The left mouse button was pressed on an edge.
+    Begin(ResizeEdge),
+    /// The cursor moved while resizing; the bounds are the new bounds, which are already applied to the border.
+    Resize(ResizeEdge, Rect),
+    /// The left mouse button was released.
+    End(ResizeEdge),
+}
+
+/// Resize handles along the inside edges of some bounds (e.g. an undecorated window).
+///
+/// Corners are as large as the border is thick, and take precedence over the edges.
+#[derive(Debug)]
+pub struct ResizeBorder {
+    pub event: RcEventQueue<ResizeEvent>,
+    bounds: Rect,
+    thickness: f32,
+    min_size: Size,
+    resizing: Option<(ResizeEdge, Point, Rect)>,
+}
+
+impl ResizeBorder {
+    /// Creates resize handles `thickness` wide along the edges of `bounds`.
+    pub fn new(bounds: Rect, thickness: f32) -> Self {
+        ResizeBorder {
+            event: RcEventQueue::new(),
+            bounds,
+            thickness,
+            min_size: Size::new(thickness * 2.0, thickness * 2.0),
+            resizing: None,
+        }
+    }
+
+    /// Sets the smallest size the bounds can be resized to (twice the thickness by default).
+    pub fn set_min_size(&mut self, min_size: Size) {
+        self.min_size = min_size;
+    }
+
+    /// Builder-style variant of [`set_min_size`](ResizeBorder::set_min_size).
+    pub fn with_min_size(mut self, min_size: Size) -> Self {
+        self.set_min_size(min_size);
+        self
+    }
+
+    /// Returns the current bounds.
+    pub fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    /// Sets the bounds, e.g. after the window was resized by other means.
+    pub fn set_bounds(&mut self, bounds: Rect) {
+        self.bounds = bounds;
+    }
+
+    /// Returns the edge being resized, if any.
+    pub fn resizing(&self) -> Option<ResizeEdge> {
+        self.resizing.map(|(edge, _, _)| edge)
+    }
+
+    /// Returns the edge (or corner) at `point`, if any.
+    pub fn hit_test(&self, point: Point) -> Option<ResizeEdge> {
+        if !self.bounds.contains(point) {
+            return None;
+        }
+
+        let left = point.x < self.bounds.min_x() + self.thickness;
+        let right = point.x >= self.bounds.max_x() - self.thickness;
+        let top = point.y < self.bounds.min_y() + self.thickness;
+        let bottom = point.y >= self.bounds.max_y() - self.thickness;
+
+        Some(match (left, right, top, bottom) {
+            (true, _, true, _) => ResizeEdge::TopLeft,
+            (_, true, true, _) => ResizeEdge::TopRight,
+            (true, _, _, true) => ResizeEdge::BottomLeft,
+            (_, true, _, true) => ResizeEdge::BottomRight,
+            (true, ..) => ResizeEdge::Left,
+            (_, true, ..) => ResizeEdge::Right,
+            (_, _, true, _) => ResizeEdge::Top,
+            (_, _, _, true) => ResizeEdge::Bottom,
+            _ => return None,
+        })
+    }
+
+    /// Returns the cursor icon to show at `point`; the icon of the edge being resized, or of the hovered edge.
+    pub fn cursor_icon(&self, point: Point) -> Option<CursorIcon> {
+        self.resizing().or_else(|| self.hit_test(point)).map(ResizeEdge::cursor_icon)
+    }
+
+    /// Handles a window event, emitting the resulting resize events.
+    pub fn handle(&mut self, event: &WindowEvent) {
+        match *event {
+            WindowEvent::MousePress(point, MouseButton::Left) => {
+                if let Some(edge) = self.hit_test(point) {
+                    self.resizing = Some((edge, point, self.bounds));
+                    self.event.emit_owned(ResizeEvent::Begin(edge));
+                }
+            }
+            WindowEvent::MouseMove(point) => {
+                if let Some((edge, anchor, start)) = self.resizing {
+                    let bounds = self.resized(edge, start, point - anchor);
+                    if bounds != self.bounds {
+                        self.bounds = bounds;
+                        self.event.emit_owned(ResizeEvent::Resize(edge, bounds));
+                    }
+                }
+            }
+            WindowEvent::MouseRelease(_, MouseButton::Left) | WindowEvent::Focus(false) => {
+                if let Some((edge, _, _)) = self.resizing.take() {
+                    self.event.emit_owned(ResizeEvent::End(edge));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Returns `start` with `edge` moved by `delta`, keeping the opposite edge in place.
+    fn resized(&self, edge: ResizeEdge, start: Rect, delta: Vector) -> Rect {
+        let (mut min, mut max) = (start.min(), start.max());
+        if edge.left() {
+            min.x = (min.x + delta.x).min(max.x - self.min_size.width);
+        }
+        if edge.right() {
+            max.x = (max.x + delta.x).max(min.x + self.min_size.width);
+        }
+        if edge.top() {
+            min.y = (min.y + delta.y).min(max.y - self.min_size.height);
+        }
+        if edge.bottom() {
+            max.y = (max.y + delta.y).max(min.y + self.min_size.height);
+        }
+        Rect::new(min, (max - min).to_size())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drag_region() {
+        let mut region = DragRegion::new(Rect::new(Point::zero(), Size::new(100.0, 30.0)));
+        let listener = region.event.listen();
+
+        region.handle(&WindowEvent::MousePress(Point::new(50.0, 50.0), MouseButton::Left));
+        assert!(!region.is_dragging());
+        region.handle(&WindowEvent::MousePress(Point::new(10.0, 10.0), MouseButton::Left));
+        region.handle(&WindowEvent::MouseMove(Point::new(30.0, 5.0)));
+        region.handle(&WindowEvent::MouseRelease(Point::new(30.0, 5.0), MouseButton::Left));
+        region.handle(&WindowEvent::MouseMove(Point::new(40.0, 5.0)));
+        assert_eq!(
+            listener.peek(),
+            &[
+                DragEvent::Begin(Point::new(10.0, 10.0)),
+                DragEvent::Move(Vector::new(20.0, -5.0)),
+                DragEvent::End,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resize_border() {
+        let mut border = ResizeBorder::new(Rect::new(Point::zero(), Size::new(100.0, 100.0)), 5.0)
+            .with_min_size(Size::new(50.0, 50.0));
+        let listener = border.event.listen();
+
+        assert_eq!(border.hit_test(Point::new(2.0, 2.0)), Some(ResizeEdge::TopLeft));
+        assert_eq!(border.hit_test(Point::new(98.0, 50.0)), Some(ResizeEdge::Right));
+        assert_eq!(border.hit_test(Point::new(50.0, 99.0)), Some(ResizeEdge::Bottom));
+        assert_eq!(border.hit_test(Point::new(50.0, 50.0)), None);
+        assert_eq!(border.hit_test(Point::new(150.0, 50.0)), None);
+        assert_eq!(border.cursor_icon(Point::new(98.0, 2.0)), Some(CursorIcon::ResizeNeSw));
+
+        // resizing by the top-left corner moves the origin, down to the minimum size.
+        border.handle(&WindowEvent::MousePress(Point::new(2.0, 2.0), MouseButton::Left));
+        border.handle(&WindowEvent::MouseMove(Point::new(12.0, -8.0)));
+        border.handle(&WindowEvent::MouseMove(Point::new(80.0, -8.0)));
+        assert_eq!(border.cursor_icon(Point::new(50.0, 50.0)), Some(CursorIcon::ResizeNwSe));
+        border.handle(&WindowEvent::MouseRelease(Point::new(80.0, -8.0), MouseButton::Left));
+        assert_eq!(
+            listener.peek(),
+            &[
+                ResizeEvent::Begin(ResizeEdge::TopLeft),
+                ResizeEvent::Resize(
+                    ResizeEdge::TopLeft,
+                    Rect::new(Point::new(10.0, -10.0), Size::new(90.0, 110.0))
+                ),
+                ResizeEvent::Resize(
+                    ResizeEdge::TopLeft,
+                    Rect::new(Point::new(50.0, -10.0), Size::new(50.0, 110.0))
+                ),
+                ResizeEvent::End(ResizeEdge::TopLeft),
+            ]
+        );
+        assert_eq!(border.bounds(), Rect::new(Point::new(50.0, -10.0), Size::new(50.0, 110.0)));
+    }
+}
@@ -7,6 +7,7 @@ pub fn impl_event_macro(ast: syn::DeriveInput) -> TokenStream {
             let name = ast.ident;
 
             let mut key_pats: Vec<proc_macro2::TokenStream> = Vec::new();
+            let mut keys: Vec<String> = Vec::new();
             let mut cast_fns: Vec<proc_macro2::TokenStream> = Vec::new();
 
             for variant in enum_data.variants {
@@ -25,6 +26,11 @@ pub fn impl_event_macro(ast: syn::DeriveInput) -> TokenStream {
                     quote! { #name::#um => std::stringify!(#key) }
                 });
 
+                let key = key.to_string();
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+
                 cast_fns.push({
                     quote! {
                         pub fn #func(self) -> Option<#ty> {
@@ -41,6 +47,8 @@ pub fn impl_event_macro(ast: syn::DeriveInput) -> TokenStream {
             {
                 quote! {
                     impl #impl_generics reclutch::verbgraph::Event for #name #ty_generics #where_clause {
+                        const KEYS: &'static [&'static str] = &[#(#keys),*];
+
                         fn get_key(&self) -> &'static str {
                             match self {
                                 #(#key_pats),*
@@ -64,6 +72,8 @@ pub fn impl_event_macro(ast: syn::DeriveInput) -> TokenStream {
             {
                 quote! {
                     impl #impl_generics reclutch::verbgraph::Event for #name #ty_generics #where_clause {
+                        const KEYS: &'static [&'static str] = &[std::stringify!(#key)];
+
                         fn get_key(&self) -> &'static str {
                             std::stringify!(#key)
                         }
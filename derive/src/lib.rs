@@ -1,23 +1,35 @@
 extern crate proc_macro;
 
 mod event;
+mod state;
 
 use {proc_macro::TokenStream, quote::quote};
 
+/// Implements `WidgetChildren` for the fields marked `#[widget_child]`, `#[vec_widget_child]` and
+/// `#[dynamic_widget_children]`.
+///
+/// Child fields may be widgets, generic parameters bounded by `WidgetChildren`, or any number of `Box` layers
+/// around either (including `Box<dyn WidgetChildren<...>>`), also as the elements of a `Vec`.
+///
+/// Shared children (`Rc`, `Arc`, `RefCell`, `Mutex` and `RwLock`, such as `Rc<RefCell<...>>`) aren't supported
+/// and are rejected with a compile error: `children` returns plain references, which can't outlive the borrow
+/// guard of a `RefCell` or lock, and `Rc` only hands out shared references for `children_mut`.
+/// Store such children in a `Box` or a `DynamicChildren` field instead.
 #[proc_macro_derive(
     WidgetChildren,
-    attributes(widget_child, vec_widget_child, widget_children_trait)
+    attributes(widget_child, vec_widget_child, dynamic_widget_children, widget_children_trait)
 )]
 pub fn widget_macro_derive(input: TokenStream) -> TokenStream {
     let ast = syn::parse(input).unwrap();
 
-    impl_widget_macro(&ast)
+    impl_widget_macro(&ast).unwrap_or_else(|err| err.to_compile_error()).into()
 }
 
 enum ChildAttr {
     None,
     WidgetChild,
     VecWidgetChild,
+    DynamicWidgetChildren,
 }
 
 enum StringOrInt {
@@ -25,9 +37,26 @@ enum StringOrInt {
     Int(usize),
 }
 
+impl StringOrInt {
+    fn to_tokens(&self) -> proc_macro2::TokenStream {
+        match self {
+            StringOrInt::String(child) => {
+                let ident = quote::format_ident!("{}", child);
+                quote! { #ident }
+            }
+            StringOrInt::Int(child) => {
+                let ident = syn::Index::from(*child);
+                quote! { #ident }
+            }
+        }
+    }
+}
+
+/// A child field, along with the number of `Box` dereferences needed to reach the widget.
 enum ChildReference {
-    Single(StringOrInt),
-    Vec(StringOrInt),
+    Single(StringOrInt, usize),
+    Vec(StringOrInt, usize),
+    Dynamic(StringOrInt),
 }
 
 fn chk_attrs_is_child(attrs: &[syn::Attribute]) -> ChildAttr {
@@ -37,15 +66,73 @@ fn chk_attrs_is_child(attrs: &[syn::Attribute]) -> ChildAttr {
         } else if attr.path.segments.first().map(|i| i.ident == "vec_widget_child").unwrap_or(false)
         {
             return ChildAttr::VecWidgetChild;
+        } else if attr
+            .path
+            .segments
+            .first()
+            .map(|i| i.ident == "dynamic_widget_children")
+            .unwrap_or(false)
+        {
+            return ChildAttr::DynamicWidgetChildren;
         }
     }
     ChildAttr::None
 }
 
-fn impl_widget_macro(ast: &syn::DeriveInput) -> TokenStream {
-    let trait_type = if let Some(attr) = ast.attrs.iter().find(|attr| {
+/// Returns the generic type arguments of the last path segment of `ty` if it is named `name`.
+fn type_args<'a>(ty: &'a syn::Type, name: &str) -> Option<Vec<&'a syn::Type>> {
+    if let syn::Type::Path(path) = ty {
+        let segment = path.path.segments.last()?;
+        if segment.ident == name {
+            if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
+                return Some(
+                    args.args
+                        .iter()
+                        .filter_map(|arg| match arg {
+                            syn::GenericArgument::Type(ty) => Some(ty),
+                            _ => None,
+                        })
+                        .collect(),
+                );
+            }
+        }
+    }
+    None
+}
+
+/// Counts the `Box` layers around a child widget type, or returns an error spanning a shared child type.
+fn child_derefs(ty: &syn::Type) -> syn::Result<usize> {
+    for shared in &["Rc", "Arc", "RefCell", "Mutex", "RwLock"] {
+        if type_args(ty, shared).is_some() {
+            return Err(syn::Error::new_spanned(
+                ty,
+                format!(
+                    "{} children aren't supported by derive(WidgetChildren), since they can't be borrowed for the lifetime of the widget; use Box or DynamicChildren instead.",
+                    shared
+                ),
+            ));
+        }
+    }
+
+    match type_args(ty, "Box").as_ref().and_then(|args| args.first()) {
+        Some(inner) => Ok(1 + child_derefs(inner)?),
+        None => Ok(0),
+    }
+}
+
+/// Same as `child_derefs`, but for the element type of a `Vec` child field.
+fn vec_child_derefs(ty: &syn::Type) -> syn::Result<usize> {
+    match type_args(ty, "Vec").as_ref().and_then(|args| args.first()) {
+        Some(ty) => child_derefs(ty),
+        None => Ok(0),
+    }
+}
+
+fn impl_widget_macro(ast: &syn::DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let custom_trait = ast.attrs.iter().find(|attr| {
         attr.path.segments.first().map(|i| i.ident == "widget_children_trait").unwrap_or(false)
-    }) {
+    });
+    let trait_type = if let Some(attr) = custom_trait {
         let mut out = None;
         for token in attr.tokens.clone().into_iter() {
             if let proc_macro2::TokenTree::Group(grp) = token {
@@ -74,12 +161,19 @@ fn impl_widget_macro(ast: &syn::DeriveInput) -> TokenStream {
                             ChildAttr::None => continue,
                             ChildAttr::WidgetChild => {
                                 capacity += 1;
-                                children.push(ChildReference::Single(StringOrInt::String(
-                                    ident.to_string(),
-                                )));
+                                children.push(ChildReference::Single(
+                                    StringOrInt::String(ident.to_string()),
+                                    child_derefs(&field.ty)?,
+                                ));
                             }
                             ChildAttr::VecWidgetChild => {
-                                children.push(ChildReference::Vec(StringOrInt::String(
+                                children.push(ChildReference::Vec(
+                                    StringOrInt::String(ident.to_string()),
+                                    vec_child_derefs(&field.ty)?,
+                                ));
+                            }
+                            ChildAttr::DynamicWidgetChildren => {
+                                children.push(ChildReference::Dynamic(StringOrInt::String(
                                     ident.to_string(),
                                 )));
                             }
@@ -93,10 +187,19 @@ fn impl_widget_macro(ast: &syn::DeriveInput) -> TokenStream {
                         ChildAttr::None => continue,
                         ChildAttr::WidgetChild => {
                             capacity += 1;
-                            children.push(ChildReference::Single(StringOrInt::Int(i)));
+                            children.push(ChildReference::Single(
+                                StringOrInt::Int(i),
+                                child_derefs(&field.ty)?,
+                            ));
                         }
                         ChildAttr::VecWidgetChild => {
-                            children.push(ChildReference::Vec(StringOrInt::Int(i)));
+                            children.push(ChildReference::Vec(
+                                StringOrInt::Int(i),
+                                vec_child_derefs(&field.ty)?,
+                            ));
+                        }
+                        ChildAttr::DynamicWidgetChildren => {
+                            children.push(ChildReference::Dynamic(StringOrInt::Int(i)));
                         }
                     }
                 }
@@ -108,77 +211,102 @@ fn impl_widget_macro(ast: &syn::DeriveInput) -> TokenStream {
     let mut push_children = Vec::new();
     let mut push_children_mut = Vec::new();
     let mut capacities = Vec::new();
+    let mut dynamic_children = None;
 
     for child in children {
         match child {
-            ChildReference::Single(ident) => match ident {
-                StringOrInt::String(child) => {
-                    let ident = quote::format_ident!("{}", child);
-                    push_children.push(quote! { children.push(&self.#ident as _); });
-                    push_children_mut.push(quote! { children.push(&mut self.#ident as _); });
-                }
-                StringOrInt::Int(child) => {
-                    let ident = syn::Index::from(child);
-                    push_children.push(quote! { children.push(&self.#ident as _); });
-                    push_children_mut.push(quote! { children.push(&mut self.#ident as _); });
-                }
-            },
-            ChildReference::Vec(ident) => match ident {
-                StringOrInt::String(child) => {
-                    let ident = quote::format_ident!("{}", child);
-                    push_children
-                        .push(quote! { for child in &self.#ident { children.push(child as _); } });
-                    push_children_mut.push(
-                        quote! { for child in &mut self.#ident { children.push(child as _); } },
-                    );
-                    capacities.push(quote! { + self.#ident.len() });
-                }
-                StringOrInt::Int(child) => {
-                    let ident = syn::Index::from(child);
-                    push_children
-                        .push(quote! { for child in &self.#ident { children.push(child as _); } });
-                    push_children_mut.push(
-                        quote! { for child in &mut self.#ident { children.push(child as _); } },
-                    );
-                    capacities.push(quote! { + self.#ident.len() });
+            ChildReference::Single(ident, derefs) => {
+                let ident = ident.to_tokens();
+                let derefs = vec![quote! { * }; derefs];
+                let derefs_mut = derefs.clone();
+                push_children.push(quote! { children.push(&#(#derefs)*self.#ident as _); });
+                push_children_mut
+                    .push(quote! { children.push(&mut #(#derefs_mut)*self.#ident as _); });
+            }
+            ChildReference::Vec(ident, derefs) => {
+                let ident = ident.to_tokens();
+                let derefs = vec![quote! { * }; derefs + 1];
+                let derefs_mut = derefs.clone();
+                push_children.push(
+                    quote! { for child in &self.#ident { children.push(&#(#derefs)*child as _); } },
+                );
+                push_children_mut.push(
+                    quote! { for child in &mut self.#ident { children.push(&mut #(#derefs_mut)*child as _); } },
+                );
+                capacities.push(quote! { + self.#ident.len() });
+            }
+            ChildReference::Dynamic(ident) => {
+                let ident = ident.to_tokens();
+                push_children.push(
+                    quote! { for child in self.#ident.iter() { children.push(child as _); } },
+                );
+                push_children_mut.push(
+                    quote! { for child in self.#ident.iter_mut() { children.push(child as _); } },
+                );
+                capacities.push(quote! { + self.#ident.len() });
+                if dynamic_children.is_none() {
+                    dynamic_children = Some(ident);
                 }
-            },
+            }
         }
     }
 
-    {
-        quote! {
-            impl #impl_generics #trait_type for #name #ty_generics #where_clause {
-                fn children(
+    // `DynamicWidgetChildren` extends the standard `WidgetChildren` only.
+    let dynamic_impl = match dynamic_children {
+        Some(ident) if custom_trait.is_none() => quote! {
+            impl #impl_generics reclutch::widget::DynamicWidgetChildren for #name #ty_generics #where_clause {
+                fn dynamic_children(
                     &self
-                ) -> Vec<
-                    &dyn #trait_type<
-                        UpdateAux = Self::UpdateAux,
-                        GraphicalAux = Self::GraphicalAux,
-                        DisplayObject = Self::DisplayObject,
-                    >
-                > {
-                    let mut children = Vec::with_capacity(#capacity as usize #(#capacities)*);
-                    #(#push_children)*
-                    children
+                ) -> &reclutch::widget::DynamicChildren<Self::UpdateAux, Self::GraphicalAux, Self::DisplayObject> {
+                    &self.#ident
                 }
-                fn children_mut(
+                fn dynamic_children_mut(
                     &mut self
-                ) -> Vec<
-                    &mut dyn #trait_type<
-                        UpdateAux = Self::UpdateAux,
-                        GraphicalAux = Self::GraphicalAux,
-                        DisplayObject = Self::DisplayObject,
-                    >
-                > {
-                    let mut children = Vec::with_capacity(#capacity as usize #(#capacities)*);
-                    #(#push_children_mut)*
-                    children
+                ) -> &mut reclutch::widget::DynamicChildren<Self::UpdateAux, Self::GraphicalAux, Self::DisplayObject> {
+                    &mut self.#ident
                 }
             }
+        },
+        _ => quote! {},
+    };
+
+    Ok(quote! {
+        impl #impl_generics #trait_type for #name #ty_generics #where_clause {
+            fn children(
+                &self
+            ) -> reclutch::smallvec::SmallVec<[
+                &dyn #trait_type<
+                    UpdateAux = Self::UpdateAux,
+                    GraphicalAux = Self::GraphicalAux,
+                    DisplayObject = Self::DisplayObject,
+                >;
+                reclutch::widget::CHILDREN_INLINE_CAPACITY
+            ]> {
+                let mut children = reclutch::smallvec::SmallVec::with_capacity(#capacity as usize #(#capacities)*);
+                #(#push_children)*
+                children
+            }
+            fn children_mut(
+                &mut self
+            ) -> reclutch::smallvec::SmallVec<[
+                &mut dyn #trait_type<
+                    UpdateAux = Self::UpdateAux,
+                    GraphicalAux = Self::GraphicalAux,
+                    DisplayObject = Self::DisplayObject,
+                >;
+                reclutch::widget::CHILDREN_INLINE_CAPACITY
+            ]> {
+                let mut children = reclutch::smallvec::SmallVec::with_capacity(#capacity as usize #(#capacities)*);
+                #(#push_children_mut)*
+                children
+            }
+            fn child_count(&self) -> usize {
+                #capacity as usize #(#capacities)*
+            }
         }
-    }
-    .into()
+
+        #dynamic_impl
+    })
 }
 
 #[proc_macro_derive(OperatesVerbGraph)]
@@ -208,6 +336,27 @@ fn impl_operates_verb_graph_macro(ast: syn::DeriveInput) -> TokenStream {
     .into()
 }
 
+#[proc_macro_derive(HasVerbGraph, attributes(verb_graph))]
+pub fn has_verb_graph_macro_derive(input: TokenStream) -> TokenStream {
+    let ast: syn::DeriveInput = syn::parse(input).unwrap();
+
+    state::impl_has_verb_graph_macro(ast)
+}
+
+#[proc_macro_derive(WidgetState, attributes(widget_state, command_group))]
+pub fn widget_state_macro_derive(input: TokenStream) -> TokenStream {
+    let ast: syn::DeriveInput = syn::parse(input).unwrap();
+
+    state::impl_widget_state_macro(ast)
+}
+
+#[proc_macro_derive(SaveState, attributes(save_state, command_group))]
+pub fn save_state_macro_derive(input: TokenStream) -> TokenStream {
+    let ast: syn::DeriveInput = syn::parse(input).unwrap();
+
+    state::impl_save_state_macro(ast)
+}
+
 #[proc_macro_derive(Event, attributes(event_key))]
 pub fn event_macro_derive(input: TokenStream) -> TokenStream {
     let ast: syn::DeriveInput = syn::parse(input).unwrap();
@@ -0,0 +1,141 @@
+use {proc_macro::TokenStream, quote::quote};
+
+fn has_attr(attrs: &[syn::Attribute], name: &str) -> bool {
+    attrs.iter().any(|attr| attr.path.segments.first().map(|i| i.ident == name).unwrap_or(false))
+}
+
+/// Returns the (possibly unnamed) members and types of all the fields marked with `#[name]`.
+fn find_marked_members(data: &syn::Data, name: &str) -> Vec<(syn::Member, syn::Type)> {
+    let mut members = Vec::new();
+    if let syn::Data::Struct(data) = data {
+        for (i, field) in data.fields.iter().enumerate() {
+            if has_attr(&field.attrs, name) {
+                let member = match &field.ident {
+                    Some(ident) => syn::Member::Named(ident.clone()),
+                    None => syn::Member::Unnamed(syn::Index::from(i)),
+                };
+                members.push((member, field.ty.clone()));
+            }
+        }
+    } else {
+        panic!("derive only supports structs.");
+    }
+    members
+}
+
+pub fn impl_has_verb_graph_macro(ast: syn::DeriveInput) -> TokenStream {
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let graph = match find_marked_members(&ast.data, "verb_graph").into_iter().next() {
+        Some((member, _)) => member,
+        None => panic!("derive(HasVerbGraph) requires a field marked with #[verb_graph]."),
+    };
+
+    {
+        quote! {
+            impl #impl_generics reclutch::verbgraph::HasVerbGraph for #name #ty_generics #where_clause {
+                fn verb_graph(&mut self) -> &mut reclutch::verbgraph::OptionVerbGraph<Self, <Self as reclutch::widget::Widget>::UpdateAux> {
+                    &mut self.#graph
+                }
+            }
+        }
+    }
+    .into()
+}
+
+pub fn impl_widget_state_macro(ast: syn::DeriveInput) -> TokenStream {
+    let name = &ast.ident;
+    let vis = &ast.vis;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let groups: Vec<_> = find_marked_members(&ast.data, "command_group")
+        .into_iter()
+        .map(|(member, _)| member)
+        .collect();
+    if groups.is_empty() {
+        panic!("derive(WidgetState) requires a CommandGroup field marked with #[command_group].");
+    }
+
+    let mut accessors = Vec::new();
+    for (member, ty) in find_marked_members(&ast.data, "widget_state") {
+        let ident = match &member {
+            syn::Member::Named(ident) => ident.clone(),
+            syn::Member::Unnamed(_) => panic!("#[widget_state] requires named fields."),
+        };
+        let setter = quote::format_ident!("set_{}", ident);
+        let groups = &groups;
+
+        accessors.push(quote! {
+            #vis fn #ident(&self) -> &#ty {
+                &self.#member
+            }
+
+            #vis fn #setter(&mut self, value: #ty) {
+                if self.#member != value {
+                    self.#member = value;
+                    #(self.#groups.repaint();)*
+                }
+            }
+        });
+    }
+
+    {
+        quote! {
+            impl #impl_generics #name #ty_generics #where_clause {
+                #(#accessors)*
+            }
+        }
+    }
+    .into()
+}
+
+pub fn impl_save_state_macro(ast: syn::DeriveInput) -> TokenStream {
+    let name = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let groups: Vec<_> = find_marked_members(&ast.data, "command_group")
+        .into_iter()
+        .map(|(member, _)| member)
+        .collect();
+
+    let mut save = Vec::new();
+    let mut restore = Vec::new();
+    for (member, ty) in find_marked_members(&ast.data, "save_state") {
+        let key = match &member {
+            syn::Member::Named(ident) => ident.to_string(),
+            syn::Member::Unnamed(index) => index.index.to_string(),
+        };
+
+        save.push(quote! {
+            if let Ok(value) = reclutch::serde_json::to_value(&self.#member) {
+                fields.insert(#key.to_string(), value);
+            }
+        });
+        restore.push(quote! {
+            if let Some(Ok(value)) =
+                state.get(#key).map(|value| reclutch::serde_json::from_value::<#ty>(value.clone()))
+            {
+                self.#member = value;
+            }
+        });
+    }
+
+    {
+        quote! {
+            impl #impl_generics reclutch::state::SaveState for #name #ty_generics #where_clause {
+                fn save_fields(&self) -> reclutch::serde_json::Value {
+                    let mut fields = reclutch::serde_json::Map::new();
+                    #(#save)*
+                    reclutch::serde_json::Value::Object(fields)
+                }
+
+                fn restore_fields(&mut self, state: &reclutch::serde_json::Value) {
+                    #(#restore)*
+                    #(self.#groups.repaint();)*
+                }
+            }
+        }
+    }
+    .into()
+}
@@ -1,9 +1,35 @@
 use crate::traits::{self, EmitResult};
 use std::{borrow::Cow, cell::RefCell, collections::VecDeque, rc::Rc};
 
+/// One direction of a bidirectional queue.
+#[derive(Debug)]
+pub(crate) struct Half<T> {
+    events: VecDeque<T>,
+    capacity: Option<usize>,
+}
+
+impl<T> Half<T> {
+    fn new(capacity: Option<usize>) -> Self {
+        Half { events: capacity.map(VecDeque::with_capacity).unwrap_or_default(), capacity }
+    }
+
+    #[inline]
+    fn is_full(&self) -> bool {
+        self.capacity.map(|cap| self.events.len() >= cap).unwrap_or(false)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Inner<Tp, Ts> {
+    primary: Half<Tp>,
+    secondary: Half<Ts>,
+    closed: bool,
+}
+
 struct InnerRef<'parent, Tin, Tout> {
-    inq: &'parent mut VecDeque<Tin>,
-    outq: &'parent mut VecDeque<Tout>,
+    inq: &'parent mut Half<Tin>,
+    outq: &'parent mut Half<Tout>,
+    closed: &'parent mut bool,
 }
 
 /// Non-thread-safe, reference-counted,
@@ -15,8 +41,15 @@ struct InnerRef<'parent, Tin, Tout> {
 /// events which the primary peer receives,
 /// the second type parameter describes the
 /// events which the secondary peer receives.
+///
+/// Each direction can optionally be bounded (see [`with_capacity`](crate::bidir::Queue::with_capacity)),
+/// in which case emitting into a full direction returns [`EmitResult::Undelivered`].
+///
+/// Either peer can [`close`](crate::bidir::Queue::close) the queue,
+/// after which no new events are accepted in either direction.
+/// Events which were buffered before closing can still be received.
 #[derive(Clone, Debug)]
-pub struct Queue<Tp, Ts>(pub(crate) Rc<RefCell<(VecDeque<Tp>, VecDeque<Ts>)>>);
+pub struct Queue<Tp, Ts>(pub(crate) Rc<RefCell<Inner<Tp, Ts>>>);
 
 /// The "other" end of the bidirectional [`Queue`](crate::bidir::Queue)
 #[derive(Clone, Debug)]
@@ -24,7 +57,7 @@ pub struct Secondary<Tp, Ts>(Queue<Tp, Ts>);
 
 impl<Tp, Ts> Default for Queue<Tp, Ts> {
     fn default() -> Self {
-        Queue(Rc::new(RefCell::new((VecDeque::new(), VecDeque::new()))))
+        Self::with_capacities(None, None)
     }
 }
 
@@ -34,6 +67,25 @@ impl<Tp, Ts> Queue<Tp, Ts> {
         Default::default()
     }
 
+    /// Creates a new queue in which each direction buffers at most `capacity` events.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacities(Some(capacity), Some(capacity))
+    }
+
+    /// Creates a new queue with separate (optional) capacities per direction.
+    ///
+    /// `primary` bounds the events received by the primary peer,
+    /// `secondary` bounds the events received by the secondary peer.
+    /// `None` means unbounded.
+    pub fn with_capacities(primary: Option<usize>, secondary: Option<usize>) -> Self {
+        Queue(Rc::new(RefCell::new(Inner {
+            primary: Half::new(primary),
+            secondary: Half::new(secondary),
+            closed: false,
+        })))
+    }
+
     /// This function returns the "other" end of the bidirectional `Queue`
     ///
     /// NOTE: multiple calls to this method on the same queue
@@ -48,7 +100,11 @@ impl<Tp, Ts> Queue<Tp, Ts> {
         F: FnOnce(InnerRef<Tp, Ts>) -> R,
     {
         let inner = &mut *self.0.borrow_mut();
-        f(InnerRef { inq: &mut inner.0, outq: &mut inner.1 })
+        f(InnerRef {
+            inq: &mut inner.primary,
+            outq: &mut inner.secondary,
+            closed: &mut inner.closed,
+        })
     }
 }
 
@@ -58,7 +114,11 @@ impl<Tp, Ts> Secondary<Tp, Ts> {
         F: FnOnce(InnerRef<Ts, Tp>) -> R,
     {
         let inner = &mut *(self.0).0.borrow_mut();
-        f(InnerRef { inq: &mut inner.1, outq: &mut inner.0 })
+        f(InnerRef {
+            inq: &mut inner.secondary,
+            outq: &mut inner.primary,
+            closed: &mut inner.closed,
+        })
     }
 }
 
@@ -68,19 +128,90 @@ macro_rules! impl_queue_part {
             /// Function which iterates over the input event queue
             /// and optionally schedules items to be put into the
             /// outgoing event queue
-            pub fn bounce<F>(&self, f: F)
+            ///
+            /// Bouncing stops once the outgoing event queue can't take
+            /// another reply (because it is full or the queue was closed);
+            /// the remaining events are kept in the input event queue,
+            /// and their number is returned.
+            pub fn bounce<F>(&self, mut f: F) -> usize
             where
                 F: FnMut($tin) -> Option<$tout>,
             {
                 self.on_queues_mut(|x| {
-                    x.outq.extend(std::mem::replace(x.inq, VecDeque::new()).into_iter().flat_map(f))
+                    while !*x.closed && !x.outq.is_full() {
+                        let event = match x.inq.events.pop_front() {
+                            Some(event) => event,
+                            None => break,
+                        };
+                        if let Some(reply) = f(event) {
+                            x.outq.events.push_back(reply);
+                        }
+                    }
+                    x.inq.events.len()
                 })
             }
 
             /// This function retrieves the newest event from
             /// the event queue and drops the rest.
             pub fn retrieve_newest(&self) -> Option<$tin> {
-                self.on_queues_mut(|x| x.inq.drain(..).last())
+                self.on_queues_mut(|x| x.inq.events.drain(..).last())
+            }
+
+            /// Returns the number of events currently buffered for this peer.
+            #[inline]
+            pub fn len(&self) -> usize {
+                self.on_queues_mut(|x| x.inq.events.len())
+            }
+
+            /// Returns `true` if no events are currently buffered for this peer.
+            #[inline]
+            pub fn is_empty(&self) -> bool {
+                self.len() == 0
+            }
+
+            /// Returns the maximum number of events which can be buffered for this peer,
+            /// or `None` if unbounded.
+            #[inline]
+            pub fn capacity(&self) -> Option<usize> {
+                self.on_queues_mut(|x| x.inq.capacity)
+            }
+
+            /// Closes the queue in both directions.
+            ///
+            /// Any further emits (by either peer) are undelivered,
+            /// however already buffered events can still be received.
+            #[inline]
+            pub fn close(&self) {
+                self.on_queues_mut(|x| *x.closed = true)
+            }
+
+            /// Delivers a terminal event to the other peer and closes the queue.
+            ///
+            /// The terminal event is delivered even if the outgoing direction is full.
+            /// If the queue was already closed, the event is returned.
+            pub fn close_with(&self, event: $tout) -> Result<(), $tout> {
+                self.on_queues_mut(|x| {
+                    if *x.closed {
+                        Err(event)
+                    } else {
+                        x.outq.events.push_back(event);
+                        *x.closed = true;
+                        Ok(())
+                    }
+                })
+            }
+
+            /// Returns `true` if either peer has closed the queue.
+            #[inline]
+            pub fn is_closed(&self) -> bool {
+                self.on_queues_mut(|x| *x.closed)
+            }
+
+            /// Returns `true` if the queue was closed and all the events buffered
+            /// for this peer were received, i.e. no more events will ever arrive.
+            #[inline]
+            pub fn is_finished(&self) -> bool {
+                self.on_queues_mut(|x| *x.closed && x.inq.events.is_empty())
             }
         }
 
@@ -89,15 +220,21 @@ macro_rules! impl_queue_part {
 
             #[inline]
             fn buffer_is_empty(&self) -> bool {
-                self.on_queues_mut(|x| x.outq.is_empty())
+                self.on_queues_mut(|x| x.outq.events.is_empty())
             }
         }
 
         impl<$tin, $tout: Clone> traits::Emitter for $strucn<$tp1, $tp2> {
             #[inline]
             fn emit<'a>(&self, event: Cow<'a, $tout>) -> EmitResult<'a, $tout> {
-                self.on_queues_mut(|x| x.outq.push_back(event.into_owned()));
-                EmitResult::Delivered
+                self.on_queues_mut(|x| {
+                    if *x.closed || x.outq.is_full() {
+                        EmitResult::Undelivered(event)
+                    } else {
+                        x.outq.events.push_back(event.into_owned());
+                        EmitResult::Delivered
+                    }
+                })
             }
         }
 
@@ -117,16 +254,12 @@ macro_rules! impl_queue_part {
             where
                 F: FnMut(&Self::Item) -> R,
             {
-                self.on_queues_mut(|x| {
-                    std::mem::replace(x.inq, VecDeque::new()).iter().map(f).collect()
-                })
+                self.on_queues_mut(|x| std::mem::take(&mut x.inq.events).iter().map(f).collect())
             }
 
             #[inline]
             fn peek(&self) -> Vec<Self::Item> {
-                self.on_queues_mut(|x| {
-                    std::mem::replace(x.inq, VecDeque::new()).into_iter().collect()
-                })
+                self.on_queues_mut(|x| std::mem::take(&mut x.inq.events).into_iter().collect())
             }
 
             #[inline]
@@ -143,16 +276,16 @@ macro_rules! impl_queue_part {
                 F: FnMut(&Self::Item) -> R,
             {
                 self.on_queues_mut(|x| {
-                    let n = n.min(x.inq.len());
-                    x.inq.drain(0..n).collect::<Vec<_>>().iter().map(f).collect()
+                    let n = n.min(x.inq.events.len());
+                    x.inq.events.drain(0..n).collect::<Vec<_>>().iter().map(f).collect()
                 })
             }
 
             #[inline]
             fn peek_n(&self, n: usize) -> Vec<Self::Item> {
                 self.on_queues_mut(|x| {
-                    let n = n.min(x.inq.len());
-                    x.inq.drain(0..n).collect()
+                    let n = n.min(x.inq.events.len());
+                    x.inq.events.drain(0..n).collect()
                 })
             }
         }
@@ -204,4 +337,63 @@ mod tests {
         assert_eq!(secondary.peek_n(2), &[3, 5]);
         assert_eq!(secondary.peek_n(2), &[6, 7]);
     }
+
+    #[test]
+    fn test_capacity_bidir_evq() {
+        let primary = super::Queue::<(), i32>::with_capacity(2);
+        let secondary = primary.secondary();
+
+        assert_eq!(secondary.capacity(), Some(2));
+
+        primary.emit_owned(1).into_result().unwrap();
+        primary.emit_owned(2).into_result().unwrap();
+        primary.emit_owned(3).into_result().unwrap_err();
+        assert_eq!(secondary.len(), 2);
+        assert_eq!(secondary.peek(), &[1, 2]);
+
+        primary.emit_owned(3).into_result().unwrap();
+        assert_eq!(secondary.peek(), &[3]);
+    }
+
+    #[test]
+    fn test_bounce_into_full_queue() {
+        let primary = super::Queue::<i32, i32>::with_capacities(None, Some(2));
+        let secondary = primary.secondary();
+
+        for event in 0..5 {
+            secondary.emit_owned(event).into_result().unwrap();
+        }
+        // events without a reply don't take up room.
+        assert_eq!(primary.bounce(|x| if x == 0 { None } else { Some(x * 10) }), 2);
+        assert_eq!(secondary.peek(), &[10, 20]);
+
+        assert_eq!(primary.bounce(|x| Some(x * 10)), 0);
+        assert_eq!(secondary.peek(), &[30, 40]);
+
+        secondary.emit_owned(5).into_result().unwrap();
+        primary.close();
+        assert_eq!(primary.bounce(|x| Some(x * 10)), 1);
+        assert_eq!(primary.peek(), &[5]);
+    }
+
+    #[test]
+    fn test_close_bidir_evq() {
+        let primary = super::Queue::with_capacity(1);
+        let secondary = primary.secondary();
+
+        primary.emit_owned(1).into_result().unwrap();
+        // the terminal event bypasses the capacity
+        primary.close_with(0).unwrap();
+
+        assert!(secondary.is_closed());
+        assert!(!secondary.is_finished());
+        assert_eq!(primary.close_with(5), Err(5));
+
+        primary.emit_owned(2).into_result().unwrap_err();
+        secondary.emit_owned(3).into_result().unwrap_err();
+
+        assert_eq!(secondary.peek(), &[1, 0]);
+        assert!(secondary.is_finished());
+        assert!(primary.is_finished());
+    }
 }
@@ -122,7 +122,7 @@ impl<T: Clone> crate::traits::Emitter for Queue<T> {
             if inner.ev.listeners.is_empty() {
                 Err(event)
             } else {
-                inner.ev.events.push(event.into_owned());
+                inner.ev.push_event(event.into_owned());
                 inner.notify();
                 Ok(())
             }
@@ -0,0 +1,197 @@
+use crate::traits::Listen;
+use std::cell::RefCell;
+
+/// Combinators which wrap a listener into a new listener, transforming the events lazily
+/// whenever they're read (through [`with`](Listen::with) and friends).
+///
+/// The names differ from the iterator combinators, since [`Listen::map`](Listen::map) is already taken.
+pub trait ListenExt: Listen + Sized {
+    /// Transforms every event with `f`.
+    #[inline]
+    fn mapped<F, R>(self, f: F) -> Mapped<Self, F>
+    where
+        F: Fn(&Self::Item) -> R,
+    {
+        Mapped { listener: self, f }
+    }
+
+    /// Only keeps the events accepted by `pred`.
+    #[inline]
+    fn filtered<P>(self, pred: P) -> Filtered<Self, P>
+    where
+        P: Fn(&Self::Item) -> bool,
+    {
+        Filtered { listener: self, pred }
+    }
+
+    /// Transforms every event with `f`, which also has access to some state carried across all the events
+    /// (e.g. to compute running totals, or deltas from the previous event).
+    #[inline]
+    fn scanned<S, F, R>(self, state: S, f: F) -> Scanned<Self, S, F>
+    where
+        F: FnMut(&mut S, &Self::Item) -> R,
+    {
+        Scanned { listener: self, state: RefCell::new((state, f)) }
+    }
+}
+
+impl<L: Listen> ListenExt for L {}
+
+/// A listener which transforms every event; see [`ListenExt::mapped`](ListenExt::mapped).
+#[derive(Debug)]
+pub struct Mapped<L, F> {
+    listener: L,
+    f: F,
+}
+
+impl<L, F> Mapped<L, F> {
+    /// Returns the wrapped listener.
+    #[inline]
+    pub fn into_inner(self) -> L {
+        self.listener
+    }
+}
+
+impl<L, F, R> Listen for Mapped<L, F>
+where
+    L: Listen,
+    F: Fn(&L::Item) -> R,
+{
+    type Item = R;
+
+    fn with<G, T>(&self, g: G) -> T
+    where
+        G: FnOnce(&[R]) -> T,
+    {
+        let events: Vec<_> = self.listener.map(&self.f);
+        g(&events)
+    }
+
+    fn with_n<G, T>(&self, n: usize, g: G) -> T
+    where
+        G: FnOnce(&[R]) -> T,
+    {
+        let events: Vec<_> = self.listener.map_n(n, &self.f);
+        g(&events)
+    }
+}
+
+/// A listener which only keeps some events; see [`ListenExt::filtered`](ListenExt::filtered).
+#[derive(Debug)]
+pub struct Filtered<L, P> {
+    listener: L,
+    pred: P,
+}
+
+impl<L, P> Filtered<L, P> {
+    /// Returns the wrapped listener.
+    #[inline]
+    pub fn into_inner(self) -> L {
+        self.listener
+    }
+}
+
+impl<L, P> Listen for Filtered<L, P>
+where
+    L: Listen,
+    L::Item: Clone,
+    P: Fn(&L::Item) -> bool,
+{
+    type Item = L::Item;
+
+    fn with<G, T>(&self, g: G) -> T
+    where
+        G: FnOnce(&[L::Item]) -> T,
+    {
+        let pred = &self.pred;
+        self.listener.with(|events| {
+            g(&events.iter().filter(|event| pred(event)).cloned().collect::<Vec<_>>())
+        })
+    }
+
+    /// The first `n` events of the wrapped listener are read, of which the accepted ones are given.
+    fn with_n<G, T>(&self, n: usize, g: G) -> T
+    where
+        G: FnOnce(&[L::Item]) -> T,
+    {
+        let pred = &self.pred;
+        self.listener.with_n(n, |events| {
+            g(&events.iter().filter(|event| pred(event)).cloned().collect::<Vec<_>>())
+        })
+    }
+}
+
+/// A listener which transforms every event along with some state; see [`ListenExt::scanned`](ListenExt::scanned).
+#[derive(Debug)]
+pub struct Scanned<L, S, F> {
+    listener: L,
+    state: RefCell<(S, F)>,
+}
+
+impl<L, S, F> Scanned<L, S, F> {
+    /// Returns the wrapped listener and the current state.
+    #[inline]
+    pub fn into_inner(self) -> (L, S) {
+        (self.listener, self.state.into_inner().0)
+    }
+}
+
+impl<L, S, F, R> Listen for Scanned<L, S, F>
+where
+    L: Listen,
+    F: FnMut(&mut S, &L::Item) -> R,
+{
+    type Item = R;
+
+    fn with<G, T>(&self, g: G) -> T
+    where
+        G: FnOnce(&[R]) -> T,
+    {
+        let events = {
+            let (state, f) = &mut *self.state.borrow_mut();
+            self.listener.map(|event| f(state, event))
+        };
+        g(&events)
+    }
+
+    fn with_n<G, T>(&self, n: usize, g: G) -> T
+    where
+        G: FnOnce(&[R]) -> T,
+    {
+        let events = {
+            let (state, f) = &mut *self.state.borrow_mut();
+            self.listener.map_n(n, |event| f(state, event))
+        };
+        g(&events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{nonts::Queue, traits::EmitterExt, traits::QueueInterfaceListable};
+
+    #[test]
+    fn test_listener_combinators() {
+        let queue = Queue::new();
+        let doubled = queue.listen().mapped(|x: &i32| x * 2);
+        let even = queue.listen().filtered(|x: &i32| x % 2 == 0);
+        let totals = queue.listen().filtered(|x: &i32| *x > 1).scanned(0, |total, x: &i32| {
+            *total += x;
+            *total
+        });
+
+        for i in 1..=4 {
+            queue.emit_owned(i);
+        }
+
+        assert_eq!(doubled.peek(), &[2, 4, 6, 8]);
+        assert_eq!(even.peek_n(3), &[2]);
+        assert_eq!(even.peek(), &[4]);
+        assert_eq!(totals.peek(), &[2, 5, 9]);
+
+        queue.emit_owned(5);
+        assert_eq!(totals.peek(), &[14]);
+        assert_eq!(totals.into_inner().1, 14);
+    }
+}
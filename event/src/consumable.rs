@@ -0,0 +1,93 @@
+use std::{cell::RefCell, fmt, rc::Rc};
+
+/// An event payload which can be consumed by at most one receiver.
+///
+/// Clones share the payload, so when the event is broadcast to several listeners
+/// (or several clones end up in different queues), the first receiver to consume it
+/// takes the payload and every later receiver sees it as consumed.
+///
+/// This is typically used for input events (e.g. a mouse click) which should only be
+/// handled by the topmost widget accepting them.
+pub struct ConsumableEvent<T>(Rc<RefCell<Option<T>>>);
+
+impl<T> ConsumableEvent<T> {
+    /// Creates a new, unconsumed event.
+    pub fn new(value: T) -> Self {
+        ConsumableEvent(Rc::new(RefCell::new(Some(value))))
+    }
+
+    /// Consumes the payload if `pred` returns `true` for it.
+    ///
+    /// Returns `None` if the predicate failed or if the event was already consumed,
+    /// in which case `pred` isn't called.
+    pub fn consume_if(&self, pred: impl FnOnce(&T) -> bool) -> Option<T> {
+        let mut value = self.0.borrow_mut();
+        if pred(value.as_ref()?) {
+            value.take()
+        } else {
+            None
+        }
+    }
+
+    /// Unconditionally consumes the payload, returning `None` if it was already consumed.
+    #[inline]
+    pub fn consume(&self) -> Option<T> {
+        self.0.borrow_mut().take()
+    }
+
+    /// Returns `true` if the payload has been consumed.
+    #[inline]
+    pub fn is_consumed(&self) -> bool {
+        self.0.borrow().is_none()
+    }
+
+    /// Invokes `f` with the payload without consuming it, returning `None` if it was already consumed.
+    pub fn peek<R>(&self, f: impl FnOnce(&T) -> R) -> Option<R> {
+        self.0.borrow().as_ref().map(f)
+    }
+}
+
+impl<T> Clone for ConsumableEvent<T> {
+    fn clone(&self) -> Self {
+        ConsumableEvent(Rc::clone(&self.0))
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ConsumableEvent<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &*self.0.borrow() {
+            Some(value) => f.debug_tuple("ConsumableEvent").field(value).finish(),
+            None => f.write_str("ConsumableEvent(<consumed>)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{nonts::Queue, prelude::*};
+
+    #[test]
+    fn test_consumable_event() {
+        let queue = Queue::new();
+        let first = queue.listen();
+        let second = queue.listen();
+
+        queue.emit_owned(ConsumableEvent::new(5));
+        queue.emit_owned(ConsumableEvent::new(10));
+
+        // the first listener only accepts large values.
+        let consumed: Vec<_> =
+            first.peek().into_iter().filter_map(|ev| ev.consume_if(|x| *x > 7)).collect();
+        assert_eq!(consumed, vec![10]);
+
+        let events = second.peek();
+        assert!(!events[0].is_consumed());
+        assert!(events[1].is_consumed());
+        assert_eq!(events[0].peek(|x| *x), Some(5));
+        assert_eq!(events[1].consume_if(|_| true), None);
+        assert_eq!(events[0].consume(), Some(5));
+        assert_eq!(events[0].consume(), None);
+        assert_eq!(format!("{:?}", events[0]), "ConsumableEvent(<consumed>)");
+    }
+}
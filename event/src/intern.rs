@@ -1,3 +1,5 @@
+use std::{collections::HashMap, time::Instant};
+
 pub(crate) type ListenerKey = slotmap::DefaultKey;
 
 /// Non-thread-safe, non-reference-counted API
@@ -5,11 +7,28 @@ pub(crate) type ListenerKey = slotmap::DefaultKey;
 pub struct Queue<T> {
     pub(crate) listeners: slotmap::SlotMap<ListenerKey, usize>,
     pub(crate) events: Vec<T>,
+    /// Emission time of each buffered event, if timestamps are enabled.
+    timestamps: Option<Vec<Instant>>,
+    /// Events to be delivered in place of the events dropped by [`drop_lagging`](Queue::drop_lagging),
+    /// stamped with the time they were dropped.
+    overflows: HashMap<ListenerKey, (T, Instant)>,
+    high_water_mark: usize,
+    soft_cap: Option<usize>,
+    /// Number of most recent events kept in the buffer for listeners created later.
+    retention: usize,
 }
 
 impl<T> Default for Queue<T> {
     fn default() -> Self {
-        Self { listeners: Default::default(), events: Vec::new() }
+        Self {
+            listeners: Default::default(),
+            events: Vec::new(),
+            timestamps: None,
+            overflows: HashMap::new(),
+            high_water_mark: 0,
+            soft_cap: None,
+            retention: 0,
+        }
     }
 }
 
@@ -19,9 +38,10 @@ impl<T> Queue<T> {
         Default::default()
     }
 
-    /// Removes all events that have been already seen by all listeners
+    /// Removes all events that have been already seen by all listeners, except for the retained events
     fn cleanup(&mut self) {
-        let min_idx = *self.listeners.values().min().unwrap_or(&0);
+        let min_idx = self.listeners.values().min().copied().unwrap_or(self.events.len());
+        let min_idx = min_idx.min(self.events.len().saturating_sub(self.retention));
         if min_idx == 0 {
             return;
         }
@@ -31,20 +51,185 @@ impl<T> Queue<T> {
         }
 
         self.events.drain(0..min_idx);
+        if let Some(timestamps) = &mut self.timestamps {
+            timestamps.drain(0..min_idx);
+        }
+    }
+
+    /// Returns `true` if a listener which was at `idx` before it advanced might have held back the cleanup
+    #[inline]
+    fn was_blocker(&self, idx: usize) -> bool {
+        // after a cleanup, the slowest listener is at most `retention` events in
+        idx <= self.retention
+    }
+
+    /// Pushes an event into the buffer, regardless of listeners, updating the high-water mark
+    pub(crate) fn push_event(&mut self, event: T) {
+        self.events.push(event);
+        self.buffer_grown();
+    }
+
+    /// Enables or disables stamping every emitted event with the time it was emitted,
+    /// which can then be read through [`pull_with_timestamps`](Queue::pull_with_timestamps).
+    ///
+    /// Events which are already buffered when timestamps are enabled are stamped with the current time.
+    pub fn set_timestamps(&mut self, enabled: bool) {
+        self.timestamps = if enabled {
+            let now = Instant::now();
+            self.timestamps.take().or_else(|| Some(vec![now; self.events.len()]))
+        } else {
+            None
+        };
+    }
+
+    /// Returns `true` if events are stamped with the time they were emitted.
+    #[inline]
+    pub fn has_timestamps(&self) -> bool {
+        self.timestamps.is_some()
+    }
+
+    /// Stamps new events and updates the high-water mark after events were buffered
+    fn buffer_grown(&mut self) {
+        let len = self.events.len();
+        if let Some(timestamps) = &mut self.timestamps {
+            timestamps.resize(len, Instant::now());
+        }
+        self.high_water_mark = self.high_water_mark.max(len);
     }
 
-    /// Creates a subscription
+    /// Returns the number of buffered events, i.e. the events which haven't been seen by every listener yet.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Returns `true` if no events are buffered.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Returns the number of events the buffer can hold without reallocating.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.events.capacity()
+    }
+
+    /// Returns a copy of the buffered events, oldest first, without affecting any listener.
+    ///
+    /// Pending overflow events (see [`drop_lagging`](Queue::drop_lagging)) aren't included.
+    pub fn snapshot(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.events.clone()
+    }
+
+    /// Sets the number of buffered events above which the queue is considered backed up (or removes the limit with `None`).
+    ///
+    /// Events are still buffered beyond the cap; check [`exceeds_soft_cap`](Queue::exceeds_soft_cap) (or compare the
+    /// [`high_water_mark`](Queue::high_water_mark) with the cap, to catch spikes in between checks) to find out,
+    /// since this usually means a listener is stuck (see [`drop_lagging`](Queue::drop_lagging) to recover from that).
+    #[inline]
+    pub fn set_soft_cap(&mut self, cap: Option<usize>) {
+        self.soft_cap = cap;
+    }
+
+    /// Returns `true` if more events than the [soft cap](Queue::set_soft_cap) are buffered.
+    #[inline]
+    pub fn exceeds_soft_cap(&self) -> bool {
+        matches!(self.soft_cap, Some(cap) if self.events.len() > cap)
+    }
+
+    /// Returns the soft cap, if any.
+    #[inline]
+    pub fn soft_cap(&self) -> Option<usize> {
+        self.soft_cap
+    }
+
+    /// Sets the number of most recent events which are kept in the buffer even after every listener has seen them,
+    /// so that listeners created later receive them immediately.
+    ///
+    /// This is useful for state-like events (e.g. the window size or the theme), where a late listener
+    /// is interested in the current state rather than only in future changes.
+    /// With a non-zero retention, events are buffered even if there are no listeners.
+    pub fn set_retention(&mut self, n: usize) {
+        self.retention = n;
+        self.cleanup();
+    }
+
+    /// Returns the number of most recent events kept for listeners created later.
+    #[inline]
+    pub fn retention(&self) -> usize {
+        self.retention
+    }
+
+    /// Creates a subscription, which starts with the [retained](Queue::set_retention) events
     pub fn create_listener(&mut self) -> ListenerKey {
-        let maxidx = self.events.len();
-        self.listeners.insert(maxidx)
+        let idx = self.events.len().saturating_sub(self.retention);
+        self.listeners.insert(idx)
     }
 
     /// Removes a subscription
     pub fn remove_listener(&mut self, key: ListenerKey) {
-        // oldidx != 0 --> this is not a blocker
-        if self.listeners.remove(key) == Some(0) {
+        self.overflows.remove(&key);
+        match self.listeners.remove(key) {
+            Some(idx) if self.was_blocker(idx) => self.cleanup(),
+            _ => {}
+        }
+    }
+
+    /// Returns the number of events the listener hasn't seen yet (i.e. how far it lags behind).
+    ///
+    /// A pending overflow event (see [`drop_lagging`](Queue::drop_lagging)) counts as one event.
+    pub fn events_pending(&self, key: ListenerKey) -> usize {
+        self.listeners
+            .get(key)
+            .map(|idx| self.events.len() - idx + self.overflows.contains_key(&key) as usize)
+            .unwrap_or(0)
+    }
+
+    /// Returns the number of events the slowest listener hasn't seen yet.
+    pub fn max_lag(&self) -> usize {
+        self.listeners.keys().map(|key| self.events_pending(key)).max().unwrap_or(0)
+    }
+
+    /// Returns the largest number of events which were buffered at once
+    /// since creation or the last [`reset_high_water_mark`](Queue::reset_high_water_mark).
+    #[inline]
+    pub fn high_water_mark(&self) -> usize {
+        self.high_water_mark
+    }
+
+    /// Resets the high-water mark to the current buffer length.
+    #[inline]
+    pub fn reset_high_water_mark(&mut self) {
+        self.high_water_mark = self.events.len();
+    }
+
+    /// Forcefully skips the backlog of every listener which lags behind by more than `max_lag` events.
+    ///
+    /// Instead of the dropped events, the listener will receive a single overflow event,
+    /// created by `overflow` from the number of dropped events, on its next pull.
+    /// Returns the keys of the affected listeners.
+    pub fn drop_lagging<F>(&mut self, max_lag: usize, mut overflow: F) -> Vec<ListenerKey>
+    where
+        F: FnMut(usize) -> T,
+    {
+        let maxidx = self.events.len();
+        let mut dropped = Vec::new();
+        for (key, idx) in self.listeners.iter_mut() {
+            let lag = maxidx - *idx;
+            if lag > max_lag {
+                *idx = maxidx;
+                self.overflows.insert(key, (overflow(lag), Instant::now()));
+                dropped.push(key);
+            }
+        }
+        if !dropped.is_empty() {
             self.cleanup();
         }
+        dropped
     }
 
     /// Get the start index of new events since last `pull`
@@ -61,30 +246,72 @@ impl<T> Queue<T> {
         (*idx - n, n)
     }
 
+    /// Takes the pending overflow event of a listener, if any
+    #[inline]
+    fn take_overflow(&mut self, key: ListenerKey) -> Option<(T, Instant)> {
+        self.overflows.remove(&key)
+    }
+
     /// Applies a function to the list of new events since last `pull`
     #[inline]
     pub fn pull_with<F, R>(&mut self, key: ListenerKey, f: F) -> R
     where
         F: FnOnce(&[T]) -> R,
     {
+        if let Some((overflow, _)) = self.take_overflow(key) {
+            return f(std::slice::from_ref(&overflow));
+        }
+
         let idx = self.pull(key);
         let ret = f(&self.events[idx..]);
-        if idx == 0 {
-            // this was a blocker
+        if self.was_blocker(idx) {
             self.cleanup();
         }
         ret
     }
 
+    /// Like [`pull_with`](Queue::pull_with), but with every event paired with the time it was emitted.
+    ///
+    /// Without [timestamps](Queue::set_timestamps) enabled, every event is stamped with the current time.
+    pub fn pull_with_timestamps<F, R>(&mut self, key: ListenerKey, f: F) -> R
+    where
+        T: Clone,
+        F: FnOnce(&[(Instant, T)]) -> R,
+    {
+        if let Some((overflow, time)) = self.take_overflow(key) {
+            return f(&[(time, overflow)]);
+        }
+
+        let idx = self.pull(key);
+        let now = Instant::now();
+        let events: Vec<_> = self.events[idx..]
+            .iter()
+            .enumerate()
+            .map(|(i, event)| {
+                let time = self.timestamps.as_ref().map_or(now, |timestamps| timestamps[idx + i]);
+                (time, event.clone())
+            })
+            .collect();
+        if self.was_blocker(idx) {
+            self.cleanup();
+        }
+        f(&events)
+    }
+
     #[inline]
     pub fn pull_n_with<F, R>(&mut self, n: usize, key: ListenerKey, f: F) -> R
     where
         F: FnOnce(&[T]) -> R,
     {
+        if n != 0 {
+            if let Some((overflow, _)) = self.take_overflow(key) {
+                return f(std::slice::from_ref(&overflow));
+            }
+        }
+
         let (idx, n) = self.pull_n(n, key);
         let ret = f(&self.events[idx..idx + n]);
-        if idx == 0 {
-            // this was a blocker
+        if self.was_blocker(idx) {
             self.cleanup();
         }
         ret
@@ -93,28 +320,28 @@ impl<T> Queue<T> {
     /// Get the next event since last `pull`
     #[inline]
     pub fn peek_get(&self, key: ListenerKey) -> Option<&T> {
-        self.events.get(*self.listeners.get(key)?)
+        let idx = *self.listeners.get(key)?;
+        self.overflows.get(&key).map(|(overflow, _)| overflow).or_else(|| self.events.get(idx))
     }
 
     /// Finish with this peek, go to next event
     #[inline]
     pub fn peek_finish(&mut self, key: ListenerKey) {
+        if self.take_overflow(key).is_some() {
+            return;
+        }
+
         let maxidx = self.events.len();
-        let was_blocker = self
-            .listeners
-            .get_mut(key)
-            .map(|idx| {
-                if *idx < maxidx {
-                    // only increment the idx if it is in bounds
-                    *idx += 1;
-                    *idx == 1
-                } else {
-                    false
-                }
-            })
-            .unwrap_or(false);
-        if was_blocker {
-            // this was a blocker
+        let old_idx = self.listeners.get_mut(key).and_then(|idx| {
+            if *idx < maxidx {
+                // only increment the idx if it is in bounds
+                *idx += 1;
+                Some(*idx - 1)
+            } else {
+                None
+            }
+        });
+        if old_idx.is_some_and(|idx| self.was_blocker(idx)) {
             self.cleanup();
         }
     }
@@ -139,9 +366,14 @@ impl<T: Clone> crate::traits::EmitterMut for Queue<T> {
     #[inline]
     fn emit<'a>(&mut self, event: std::borrow::Cow<'a, T>) -> crate::traits::EmitResult<'a, T> {
         if !self.listeners.is_empty() {
-            self.events.push(event.into_owned());
+            self.push_event(event.into_owned());
             crate::traits::EmitResult::Delivered
         } else {
+            if self.retention != 0 {
+                // keep the event for listeners created later
+                self.push_event(event.clone().into_owned());
+                self.cleanup();
+            }
             crate::traits::EmitResult::Undelivered(event)
         }
     }
@@ -153,8 +385,12 @@ impl<A> std::iter::Extend<A> for Queue<A> {
     where
         T: IntoIterator<Item = A>,
     {
-        if !self.listeners.is_empty() {
-            self.events.extend(iter)
+        if !self.listeners.is_empty() || self.retention != 0 {
+            self.events.extend(iter);
+            self.buffer_grown();
+            if self.listeners.is_empty() {
+                self.cleanup();
+            }
         }
     }
 }
@@ -230,4 +466,139 @@ mod tests {
 
         assert_eq!(event.events_len(), 0);
     }
+
+    #[test]
+    fn test_event_lag() {
+        let mut event = Queue::new();
+
+        let fast = event.create_listener();
+        let slow = event.create_listener();
+
+        for i in 0..5 {
+            event.emit_owned(i).into_result().unwrap();
+        }
+
+        assert_eq!(event.events_pending(fast), 5);
+        event.pull_with(fast, |x| assert_eq!(x, &[0, 1, 2, 3, 4]));
+        assert_eq!(event.events_pending(fast), 0);
+        assert_eq!(event.events_pending(slow), 5);
+        assert_eq!(event.max_lag(), 5);
+        assert_eq!(event.high_water_mark(), 5);
+
+        assert_eq!(event.drop_lagging(3, |n| -(n as i32)), &[slow]);
+        assert_eq!(event.events_len(), 0);
+        assert_eq!(event.high_water_mark(), 5);
+        event.reset_high_water_mark();
+        assert_eq!(event.high_water_mark(), 0);
+
+        event.emit_owned(5).into_result().unwrap();
+
+        assert_eq!(event.events_pending(slow), 2);
+        event.pull_with(slow, |x| assert_eq!(x, &[-5]));
+        event.pull_with(slow, |x| assert_eq!(x, &[5]));
+        event.pull_with(fast, |x| assert_eq!(x, &[5]));
+
+        event.remove_listener(fast);
+        event.remove_listener(slow);
+    }
+
+    #[test]
+    fn test_event_snapshot() {
+        let mut event = Queue::new();
+        let listener = event.create_listener();
+        event.set_soft_cap(Some(2));
+        assert_eq!(event.soft_cap(), Some(2));
+
+        for i in 0..3 {
+            event.emit_owned(i).into_result().unwrap();
+        }
+        assert!(event.exceeds_soft_cap());
+        assert_eq!(event.len(), 3);
+        assert!(event.capacity() >= 3);
+        assert_eq!(event.snapshot(), vec![0, 1, 2]);
+        // taking a snapshot doesn't consume anything.
+        assert_eq!(event.events_pending(listener), 3);
+
+        event.pull_with(listener, |x| assert_eq!(x, &[0, 1, 2]));
+        assert!(event.is_empty());
+        assert!(!event.exceeds_soft_cap());
+        event.emit_owned(3).into_result().unwrap();
+        assert!(!event.exceeds_soft_cap());
+        assert!(event.high_water_mark() > event.soft_cap().unwrap());
+
+        event.remove_listener(listener);
+    }
+
+    #[test]
+    fn test_event_timestamps() {
+        let mut event = Queue::new();
+        let fast = event.create_listener();
+        let slow = event.create_listener();
+
+        event.emit_owned(0).into_result().unwrap();
+        let enabled = std::time::Instant::now();
+        event.set_timestamps(true);
+        assert!(event.has_timestamps());
+        event.emit_owned(1).into_result().unwrap();
+        event.emit_owned(2).into_result().unwrap();
+
+        event.pull_with_timestamps(fast, |x| {
+            assert_eq!(x.iter().map(|(_, e)| *e).collect::<Vec<_>>(), &[0, 1, 2]);
+            // events buffered before enabling are stamped on enabling.
+            assert!(x[0].0 >= enabled);
+            assert!(x[0].0 <= x[1].0 && x[1].0 <= x[2].0);
+        });
+
+        // timestamps are dropped along with the events seen by every listener.
+        event.pull_with(slow, |x| assert_eq!(x, &[0, 1, 2]));
+        event.emit_owned(3).into_result().unwrap();
+        assert_eq!(event.timestamps.as_ref().map(Vec::len), Some(1));
+
+        event.drop_lagging(0, |n| -(n as i32));
+        event.pull_with_timestamps(slow, |x| assert_eq!(x[0].1, -1));
+
+        event.set_timestamps(false);
+        event.remove_listener(fast);
+        event.remove_listener(slow);
+    }
+
+    #[test]
+    fn test_event_retention() {
+        let mut event = Queue::new();
+        event.set_retention(2);
+
+        // retained events are buffered even without listeners.
+        event.emit_owned(0).into_result().unwrap_err();
+        event.extend(vec![1, 2]);
+        assert_eq!(event.snapshot(), &[1, 2]);
+
+        let early = event.create_listener();
+        event.emit_owned(3).into_result().unwrap();
+        event.pull_with(early, |x| assert_eq!(x, &[1, 2, 3]));
+
+        // a late listener receives the retained events, but the buffer doesn't grow beyond them.
+        let late = event.create_listener();
+        event.pull_with(late, |x| assert_eq!(x, &[2, 3]));
+        for i in 4..10 {
+            event.emit_owned(i).into_result().unwrap();
+            event.pull_with(early, |x| assert_eq!(x, &[i]));
+            event.pull_with(late, |x| assert_eq!(x, &[i]));
+        }
+        assert_eq!(event.len(), 2);
+
+        event.remove_listener(early);
+        event.remove_listener(late);
+        assert_eq!(event.snapshot(), &[8, 9]);
+
+        event.set_retention(1);
+        let sticky = event.create_listener();
+        assert_eq!(event.peek_get(sticky), Some(&9));
+        event.peek_finish(sticky);
+        assert_eq!(event.peek_get(sticky), None);
+        assert_eq!(event.len(), 1);
+
+        event.set_retention(0);
+        assert!(event.is_empty());
+        event.remove_listener(sticky);
+    }
 }
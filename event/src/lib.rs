@@ -60,6 +60,7 @@ mod macros;
 mod traits;
 
 /// Contains an bidirectional `1:1`, non-thread-safe, reference-counted API
+/// with optionally bounded directions and close notification
 pub mod bidir;
 
 /// Like `bidir`, but each direction can only save one event at a time
@@ -92,9 +93,22 @@ channels_api! {
 #[cfg_attr(feature = "docs", doc(cfg(futures)))]
 pub mod streaming;
 
-/// Contains an Event queue merger
+/// Contains combinators wrapping listeners into new, transforming listeners
+pub mod combinators;
+
+/// Contains an event payload wrapper which can be consumed by a single receiver
+pub mod consumable;
+
+/// Contains an Event queue merger and a fan-out forwarder (`Multicaster`)
 pub mod merge;
 
+/// Contains a value wrapper which notifies listeners of every change
+pub mod observed;
+
+/// Contains a request/response helper over bidirectional queues,
+/// correlating each response with its request
+pub mod rpc;
+
 /// Contains the non-thread-safe, non-reference-counted API
 pub mod nonrc;
 
@@ -116,6 +130,7 @@ use intern::ListenerKey;
 
 /// Exports the most important traits
 pub mod prelude {
+    pub use crate::combinators::ListenExt as EventListenExt;
     pub use crate::traits::{
         Emitter as EventEmitter, EmitterExt as EventEmitterExt, EmitterMut as EventEmitterMut,
         EmitterMutExt as EventEmitterMutExt, Listen as EventListen, QueueInterfaceCommon,
@@ -123,10 +138,15 @@ pub mod prelude {
     };
 }
 
+#[cfg(feature = "tokio")]
+pub use thirdparty::{ChannelListener, TryRecv};
+
 pub use {
+    consumable::ConsumableEvent,
     intern::Queue as RawEventQueue,
     nonrc::{Listener as NonRcEventListener, Queue as NonRcEventQueue},
     nonts::{Listener as RcEventListener, Queue as RcEventQueue},
+    observed::Observed,
     prelude::*,
     traits::EmitResult,
 };
@@ -1,4 +1,9 @@
-use crate::traits::Listen;
+use crate::{
+    nonts::Queue,
+    traits::{EmitterExt, Listen},
+    RawEventQueue,
+};
+use std::{cell::RefCell, rc::Rc};
 
 pub type Listener<T> = Vec<Box<dyn Merge<T>>>;
 
@@ -72,3 +77,101 @@ impl<T> Listen for Listener<T> {
         ret
     }
 }
+
+type Downstream<T> = Rc<RefCell<RawEventQueue<T>>>;
+
+/// Identifies a downstream queue registered with a [`Multicaster`](Multicaster).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MulticastKey(u64);
+
+/// Forwards the events of a single listener to a changing set of downstream (non-thread-safe) queues.
+///
+/// Downstream queues can be registered and deregistered at any time; events are forwarded on
+/// [`update`](Multicaster::update), so a queue only receives events read after it was registered.
+pub struct Multicaster<L: Listen> {
+    listener: L,
+    downstream: Vec<(MulticastKey, Downstream<L::Item>)>,
+    next_key: u64,
+}
+
+impl<L: Listen> Multicaster<L>
+where
+    L::Item: Clone,
+{
+    /// Creates a multicaster forwarding the events of `listener`.
+    pub fn new(listener: L) -> Self {
+        Multicaster { listener, downstream: Vec::new(), next_key: 0 }
+    }
+
+    /// Registers a queue to forward events to.
+    pub fn register(&mut self, queue: &Queue<L::Item>) -> MulticastKey {
+        let key = MulticastKey(self.next_key);
+        self.next_key += 1;
+        self.downstream.push((key, queue.0.clone()));
+        key
+    }
+
+    /// Stops forwarding events to a queue. Returns `false` if the queue wasn't registered.
+    pub fn deregister(&mut self, key: MulticastKey) -> bool {
+        let len = self.downstream.len();
+        self.downstream.retain(|(k, _)| *k != key);
+        self.downstream.len() != len
+    }
+
+    /// Returns the number of registered queues.
+    pub fn len(&self) -> usize {
+        self.downstream.len()
+    }
+
+    /// Returns `true` if no queue is registered.
+    pub fn is_empty(&self) -> bool {
+        self.downstream.is_empty()
+    }
+
+    /// Forwards the new events of the listener to every registered queue, returning the number of events.
+    ///
+    /// If no queue is registered, the events are still read (and thereby dropped).
+    pub fn update(&mut self) -> usize {
+        let downstream = &self.downstream;
+        self.listener.with(|events| {
+            for (_, queue) in downstream {
+                for event in events {
+                    queue.emit_borrowed(event);
+                }
+            }
+            events.len()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::QueueInterfaceListable;
+
+    #[test]
+    fn test_multicaster() {
+        let source = Queue::new();
+        let mut multicaster = Multicaster::new(source.listen());
+        let (a, b) = (Queue::new(), Queue::new());
+        let (a_listener, b_listener) = (a.listen(), b.listen());
+
+        let a_key = multicaster.register(&a);
+        source.emit_owned(1);
+        assert_eq!(multicaster.update(), 1);
+
+        multicaster.register(&b);
+        source.emit_owned(2);
+        source.emit_owned(3);
+        assert_eq!(multicaster.update(), 2);
+
+        assert!(multicaster.deregister(a_key));
+        assert!(!multicaster.deregister(a_key));
+        source.emit_owned(4);
+        multicaster.update();
+
+        assert_eq!(a_listener.peek(), &[1, 2, 3]);
+        assert_eq!(b_listener.peek(), &[2, 3, 4]);
+        assert_eq!(multicaster.len(), 1);
+    }
+}
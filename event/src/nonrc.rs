@@ -11,6 +11,66 @@ impl<T> Queue<T> {
         Queue(Default::default())
     }
 
+    /// Returns the number of buffered events, i.e. the events which haven't been seen by every listener yet.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.borrow().len()
+    }
+
+    /// Returns `true` if no events are buffered.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.borrow().is_empty()
+    }
+
+    /// Returns the number of events the buffer can hold without reallocating.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.0.borrow().capacity()
+    }
+
+    /// Returns a copy of the buffered events, oldest first, without affecting any listener.
+    #[inline]
+    pub fn snapshot(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.0.borrow().snapshot()
+    }
+
+    /// Sets the number of buffered events above which the queue is considered backed up.
+    ///
+    /// See [`RawEventQueue::set_soft_cap`](crate::RawEventQueue::set_soft_cap).
+    #[inline]
+    pub fn set_soft_cap(&self, cap: Option<usize>) {
+        self.0.borrow_mut().set_soft_cap(cap)
+    }
+
+    /// Returns `true` if more events than the soft cap are buffered.
+    ///
+    /// See [`RawEventQueue::exceeds_soft_cap`](crate::RawEventQueue::exceeds_soft_cap).
+    #[inline]
+    pub fn exceeds_soft_cap(&self) -> bool {
+        self.0.borrow().exceeds_soft_cap()
+    }
+
+    /// Enables or disables stamping every emitted event with the time it was emitted,
+    /// which listeners read through `with_timestamps`.
+    ///
+    /// See [`RawEventQueue::set_timestamps`](crate::RawEventQueue::set_timestamps).
+    #[inline]
+    pub fn set_timestamps(&self, enabled: bool) {
+        self.0.borrow_mut().set_timestamps(enabled)
+    }
+
+    /// Sets the number of most recent events which listeners created later receive immediately.
+    ///
+    /// See [`RawEventQueue::set_retention`](crate::RawEventQueue::set_retention).
+    #[inline]
+    pub fn set_retention(&self, n: usize) {
+        self.0.borrow_mut().set_retention(n)
+    }
+
     #[inline]
     pub fn listen(&self) -> Listener<'_, T> {
         Listener::new(&self.0)
@@ -68,6 +128,24 @@ impl<'a, T> Listener<'a, T> {
     pub fn new(parent: &'a RefCell<RawEventQueue<T>>) -> Self {
         Listener(parent.borrow_mut().create_listener(), parent)
     }
+
+    /// Returns the number of events this listener hasn't seen yet.
+    #[inline]
+    pub fn events_pending(&self) -> usize {
+        self.1.borrow().events_pending(self.0)
+    }
+
+    /// Like [`with`](EventListen::with), but with every event paired with the time it was emitted.
+    ///
+    /// See [`RawEventQueue::pull_with_timestamps`](crate::RawEventQueue::pull_with_timestamps).
+    #[inline]
+    pub fn with_timestamps<F, R>(&self, f: F) -> R
+    where
+        T: Clone,
+        F: FnOnce(&[(std::time::Instant, T)]) -> R,
+    {
+        self.1.borrow_mut().pull_with_timestamps(self.0, f)
+    }
 }
 
 #[cfg(test)]
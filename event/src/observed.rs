@@ -0,0 +1,96 @@
+use crate::{
+    nonts::{Listener, Queue},
+    prelude::*,
+};
+use std::{fmt, ops::Deref};
+
+/// A value which emits a copy of itself to its listeners every time it's changed.
+///
+/// This replaces pairs of a field and a separate "changed" event queue; anything depending on the
+/// value listens to it, and picks up the latest value whenever it's notified.
+pub struct Observed<T> {
+    value: T,
+    queue: Queue<T>,
+}
+
+impl<T: Clone + 'static> Observed<T> {
+    /// Creates an observed value. No event is emitted for the initial value.
+    pub fn new(value: T) -> Self {
+        Observed { value, queue: Queue::new() }
+    }
+
+    /// Returns the current value.
+    #[inline]
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Replaces the value, notifying the listeners if it changed.
+    pub fn set(&mut self, value: T)
+    where
+        T: PartialEq,
+    {
+        if self.value != value {
+            self.value = value;
+            self.queue.emit_owned(self.value.clone());
+        }
+    }
+
+    /// Modifies the value in place, then unconditionally notifies the listeners.
+    pub fn modify(&mut self, f: impl FnOnce(&mut T)) {
+        f(&mut self.value);
+        self.queue.emit_owned(self.value.clone());
+    }
+
+    /// Returns a listener which receives every new value.
+    #[inline]
+    pub fn listen(&self) -> Listener<T> {
+        self.queue.listen()
+    }
+
+    /// Returns the value, dropping the queue.
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for Observed<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T: Clone + Default + 'static> Default for Observed<T> {
+    fn default() -> Self {
+        Observed::new(T::default())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Observed<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Observed").field(&self.value).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_observed() {
+        let mut count = Observed::new(0);
+        let listener = count.listen();
+
+        count.set(0);
+        assert!(listener.peek().is_empty());
+        count.set(1);
+        count.modify(|count| *count += 2);
+        assert_eq!(*count, 3);
+        assert_eq!(listener.peek(), vec![1, 3]);
+        assert_eq!(count.into_inner(), 3);
+    }
+}
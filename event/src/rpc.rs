@@ -0,0 +1,326 @@
+use crate::{
+    bidir,
+    traits::{Emitter, Listen},
+};
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    future::Future,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll, Waker},
+};
+
+/// Correlation id which pairs a request with its response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct RequestId(u64);
+
+impl RequestId {
+    /// Returns the inner ID.
+    #[inline]
+    pub fn id(self) -> u64 {
+        self.0
+    }
+}
+
+/// A request or response payload tagged with its correlation id.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Envelope<T> {
+    pub id: RequestId,
+    pub payload: T,
+}
+
+/// Error returned when a response will never arrive,
+/// because the queue was closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError;
+
+/// Error returned from [`ResponseHandle::try_recv`](crate::rpc::ResponseHandle::try_recv).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    /// The response hasn't arrived yet.
+    Empty,
+    /// The queue was closed before the response arrived.
+    Disconnected,
+}
+
+type Wakers = Rc<RefCell<HashMap<RequestId, Waker>>>;
+
+struct ClientInner<Req, Resp> {
+    queue: bidir::Queue<Envelope<Resp>, Envelope<Req>>,
+    next_id: Cell<u64>,
+    responses: RefCell<HashMap<RequestId, Resp>>,
+    /// Requests whose handles were dropped before their response arrived.
+    abandoned: RefCell<HashSet<RequestId>>,
+    wakers: Wakers,
+}
+
+impl<Req: Clone, Resp: Clone> ClientInner<Req, Resp> {
+    /// Moves all arrived responses into the response map, dropping those nobody is waiting for anymore.
+    fn dispatch(&self) {
+        let mut responses = self.responses.borrow_mut();
+        let mut abandoned = self.abandoned.borrow_mut();
+        for Envelope { id, payload } in self.queue.peek() {
+            if !abandoned.remove(&id) {
+                responses.insert(id, payload);
+            }
+        }
+    }
+
+    fn try_recv(&self, id: RequestId) -> Result<Resp, TryRecvError> {
+        self.dispatch();
+        match self.responses.borrow_mut().remove(&id) {
+            Some(resp) => Ok(resp),
+            None if self.queue.is_closed() => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+}
+
+/// The requesting end of an RPC channel, created with [`channel`](crate::rpc::channel).
+///
+/// Cloning a `Client` returns another reference to the same client.
+pub struct Client<Req, Resp>(Rc<ClientInner<Req, Resp>>);
+
+impl<Req, Resp> Clone for Client<Req, Resp> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Client(Rc::clone(&self.0))
+    }
+}
+
+/// The responding end of an RPC channel, created with [`channel`](crate::rpc::channel).
+pub struct Server<Req, Resp> {
+    queue: bidir::Secondary<Envelope<Resp>, Envelope<Req>>,
+    wakers: Wakers,
+}
+
+/// Creates a connected RPC client/server pair over an unbounded [`bidir`](crate::bidir) queue.
+pub fn channel<Req: Clone, Resp: Clone>() -> (Client<Req, Resp>, Server<Req, Resp>) {
+    from_queue(bidir::Queue::new())
+}
+
+/// Creates a connected RPC client/server pair over an existing [`bidir`](crate::bidir) queue,
+/// e.g. one created with [`with_capacity`](crate::bidir::Queue::with_capacity).
+///
+/// The queue shouldn't be used directly afterwards, otherwise correlation may break.
+pub fn from_queue<Req: Clone, Resp: Clone>(
+    queue: bidir::Queue<Envelope<Resp>, Envelope<Req>>,
+) -> (Client<Req, Resp>, Server<Req, Resp>) {
+    let wakers = Wakers::default();
+    let server = Server { queue: queue.secondary(), wakers: Rc::clone(&wakers) };
+    let client = Client(Rc::new(ClientInner {
+        queue,
+        next_id: Cell::new(0),
+        responses: Default::default(),
+        abandoned: Default::default(),
+        wakers,
+    }));
+    (client, server)
+}
+
+impl<Req: Clone, Resp: Clone> Client<Req, Resp> {
+    /// Sends a request, returning a handle to the eventual response.
+    ///
+    /// If the request couldn't be delivered (the queue is full or closed), the request is returned.
+    pub fn call(&self, request: Req) -> Result<ResponseHandle<Req, Resp>, Req> {
+        let id = RequestId(self.0.next_id.get());
+        match self.0.queue.emit(Cow::Owned(Envelope { id, payload: request })).into_result() {
+            Ok(()) => {
+                self.0.next_id.set(id.0 + 1);
+                Ok(ResponseHandle { client: self.clone(), id })
+            }
+            Err(envelope) => Err(envelope.into_owned().payload),
+        }
+    }
+
+    /// Closes the channel; pending and future responses will be disconnected.
+    pub fn close(&self) {
+        self.0.queue.close();
+        for (_, waker) in self.0.wakers.borrow_mut().drain() {
+            waker.wake();
+        }
+    }
+
+    /// Returns `true` if either end has closed the channel.
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.0.queue.is_closed()
+    }
+}
+
+impl<Req: Clone, Resp: Clone> Server<Req, Resp> {
+    /// Returns all requests received since the last call.
+    ///
+    /// Each request should eventually be answered with [`respond`](crate::rpc::Server::respond).
+    #[inline]
+    pub fn requests(&self) -> Vec<Envelope<Req>> {
+        self.queue.peek()
+    }
+
+    /// Sends the response to a specific request.
+    ///
+    /// If the response couldn't be delivered (the queue is full or closed), the response is returned.
+    pub fn respond(&self, id: RequestId, response: Resp) -> Result<(), Resp> {
+        self.queue
+            .emit(Cow::Owned(Envelope { id, payload: response }))
+            .into_result()
+            .map_err(|envelope| envelope.into_owned().payload)?;
+        if let Some(waker) = self.wakers.borrow_mut().remove(&id) {
+            waker.wake();
+        }
+        Ok(())
+    }
+
+    /// Answers all received requests immediately using `f`.
+    ///
+    /// Responses which couldn't be delivered are dropped.
+    pub fn serve<F>(&self, mut f: F)
+    where
+        F: FnMut(Req) -> Resp,
+    {
+        for Envelope { id, payload } in self.requests() {
+            let _ = self.respond(id, f(payload));
+        }
+    }
+
+    /// Closes the channel; pending and future responses will be disconnected.
+    pub fn close(&self) {
+        self.queue.close();
+        for (_, waker) in self.wakers.borrow_mut().drain() {
+            waker.wake();
+        }
+    }
+
+    /// Returns `true` if either end has closed the channel.
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.queue.is_closed()
+    }
+}
+
+/// Handle to a pending response, returned from [`Client::call`](crate::rpc::Client::call).
+///
+/// The response can either be polled with [`try_recv`](crate::rpc::ResponseHandle::try_recv),
+/// or awaited, since `ResponseHandle` implements [`Future`](std::future::Future).
+pub struct ResponseHandle<Req, Resp> {
+    client: Client<Req, Resp>,
+    id: RequestId,
+}
+
+impl<Req: Clone, Resp: Clone> ResponseHandle<Req, Resp> {
+    /// Returns the correlation id of the request.
+    #[inline]
+    pub fn id(&self) -> RequestId {
+        self.id
+    }
+
+    /// Attempts to retrieve the response without blocking.
+    ///
+    /// Once the response has been retrieved, subsequent calls return [`TryRecvError::Disconnected`]
+    /// or [`TryRecvError::Empty`].
+    #[inline]
+    pub fn try_recv(&self) -> Result<Resp, TryRecvError> {
+        (self.client.0).try_recv(self.id)
+    }
+}
+
+impl<Req, Resp> Drop for ResponseHandle<Req, Resp> {
+    /// Forgets the request, so that its response is dropped rather than kept forever.
+    fn drop(&mut self) {
+        let inner = &self.client.0;
+        inner.wakers.borrow_mut().remove(&self.id);
+        if inner.responses.borrow_mut().remove(&self.id).is_none() && !inner.queue.is_closed() {
+            inner.abandoned.borrow_mut().insert(self.id);
+        }
+    }
+}
+
+impl<Req: Clone, Resp: Clone> Future for ResponseHandle<Req, Resp> {
+    type Output = Result<Resp, RecvError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match self.try_recv() {
+            Ok(resp) => Poll::Ready(Ok(resp)),
+            Err(TryRecvError::Disconnected) => Poll::Ready(Err(RecvError)),
+            Err(TryRecvError::Empty) => {
+                (self.client.0).wakers.borrow_mut().insert(self.id, cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rpc_correlation() {
+        let (client, server) = channel::<i32, String>();
+
+        let a = client.call(1).unwrap();
+        let b = client.call(2).unwrap();
+        assert_eq!(a.try_recv(), Err(TryRecvError::Empty));
+
+        // answer out-of-order
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        for Envelope { id, payload } in requests.into_iter().rev() {
+            server.respond(id, payload.to_string()).unwrap();
+        }
+
+        assert_eq!(b.try_recv(), Ok("2".to_string()));
+        assert_eq!(a.try_recv(), Ok("1".to_string()));
+    }
+
+    #[test]
+    fn test_rpc_close() {
+        let (client, server) = channel::<i32, i32>();
+
+        let a = client.call(1).unwrap();
+        server.serve(|x| x * 2);
+        let b = client.call(2).unwrap();
+        server.close();
+
+        assert_eq!(client.call(3).err(), Some(3));
+        assert_eq!(a.try_recv(), Ok(2));
+        assert_eq!(b.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn test_rpc_await() {
+        let (client, server) = channel::<i32, i32>();
+
+        let a = client.call(21).unwrap();
+        server.serve(|x| x * 2);
+        assert_eq!(futures_executor::block_on(a), Ok(42));
+    }
+
+    #[test]
+    fn test_rpc_drop_handle() {
+        let (client, server) = channel::<i32, i32>();
+
+        // dropped after the response arrived, and before.
+        let a = client.call(1).unwrap();
+        let b = client.call(2).unwrap();
+        server.serve(|x| x * 2);
+        assert_eq!(b.try_recv(), Ok(4));
+        let c = client.call(3).unwrap();
+        client.0.dispatch();
+        drop(a);
+        drop(c);
+        assert!(client.0.responses.borrow().is_empty());
+        assert_eq!(client.0.abandoned.borrow().len(), 1);
+
+        server.serve(|x| x * 2);
+        let d = client.call(4).unwrap();
+        server.serve(|x| x * 2);
+        assert_eq!(d.try_recv(), Ok(8));
+        assert!(client.0.responses.borrow().is_empty());
+        assert!(client.0.abandoned.borrow().is_empty());
+        assert!(server.wakers.borrow().is_empty());
+    }
+}
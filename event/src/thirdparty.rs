@@ -2,11 +2,7 @@
 //! of `std::sync::mpsc`, because it's faster.
 //! (enable the support for `crossbeam-channel` via the feature flag)
 
-use crate::{
-    channels_api,
-    traits::{EmitResult, Emitter, EmitterMut, EmitterMutExt, QueueInterfaceCommon},
-};
-use retain_mut::RetainMut;
+use crate::traits::{EmitResult, Emitter, EmitterMut, EmitterMutExt, Listen, QueueInterfaceCommon};
 use std::{
     borrow::Cow,
     cell::RefCell,
@@ -207,6 +203,24 @@ impl<T: Clone> Emitter for mpsc::SyncSender<T> {
     }
 }
 
+impl<T> Listen for mpsc::Receiver<T> {
+    type Item = T;
+
+    fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&[Self::Item]) -> R,
+    {
+        f(&self.try_iter().collect::<Vec<_>>())
+    }
+
+    fn with_n<F, R>(&self, n: usize, f: F) -> R
+    where
+        F: FnOnce(&[Self::Item]) -> R,
+    {
+        f(&self.try_iter().take(n).collect::<Vec<_>>())
+    }
+}
+
 channels_api! {
     impl<T> QueueInterfaceCommon for crossbeam_channel::Sender<T> {
         type Item = T;
@@ -225,6 +239,144 @@ channels_api! {
     }
 }
 
+#[cfg(feature = "tokio")]
+impl<T> QueueInterfaceCommon for tokio::sync::mpsc::Sender<T> {
+    type Item = T;
+}
+
+/// Bounded tokio channels don't wait for capacity; an event which doesn't fit is undelivered.
+#[cfg(feature = "tokio")]
+impl<T: Clone> Emitter for tokio::sync::mpsc::Sender<T> {
+    #[inline]
+    fn emit<'a>(&self, event: Cow<'a, T>) -> EmitResult<'a, T> {
+        self.try_send(event.into_owned()).map_err(|err| Cow::Owned(err.into_inner())).into()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T> QueueInterfaceCommon for tokio::sync::mpsc::UnboundedSender<T> {
+    type Item = T;
+}
+
+#[cfg(feature = "tokio")]
+impl<T: Clone> Emitter for tokio::sync::mpsc::UnboundedSender<T> {
+    #[inline]
+    fn emit<'a>(&self, event: Cow<'a, T>) -> EmitResult<'a, T> {
+        self.send(event.into_owned())
+            .map_err(|tokio::sync::mpsc::error::SendError(x)| Cow::Owned(x))
+            .into()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T> QueueInterfaceCommon for tokio::sync::broadcast::Sender<T> {
+    type Item = T;
+}
+
+#[cfg(feature = "tokio")]
+impl<T: Clone> Emitter for tokio::sync::broadcast::Sender<T> {
+    #[inline]
+    fn emit<'a>(&self, event: Cow<'a, T>) -> EmitResult<'a, T> {
+        self.send(event.into_owned())
+            .map(|_| ())
+            .map_err(|tokio::sync::broadcast::error::SendError(x)| Cow::Owned(x))
+            .into()
+    }
+}
+
+/// The receiving half of a channel which can be polled without blocking.
+#[cfg(feature = "tokio")]
+#[cfg_attr(feature = "docs", doc(cfg(tokio)))]
+pub trait TryRecv {
+    type Item;
+
+    /// Returns the next buffered event, or `None` if there is none (or the channel is closed).
+    fn try_recv_event(&mut self) -> Option<Self::Item>;
+}
+
+#[cfg(feature = "tokio")]
+impl<T> TryRecv for tokio::sync::mpsc::Receiver<T> {
+    type Item = T;
+
+    #[inline]
+    fn try_recv_event(&mut self) -> Option<T> {
+        self.try_recv().ok()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<T> TryRecv for tokio::sync::mpsc::UnboundedReceiver<T> {
+    type Item = T;
+
+    #[inline]
+    fn try_recv_event(&mut self) -> Option<T> {
+        self.try_recv().ok()
+    }
+}
+
+/// A lagging broadcast receiver skips the events it missed.
+#[cfg(feature = "tokio")]
+impl<T: Clone> TryRecv for tokio::sync::broadcast::Receiver<T> {
+    type Item = T;
+
+    fn try_recv_event(&mut self) -> Option<T> {
+        use tokio::sync::broadcast::error::TryRecvError;
+        loop {
+            match self.try_recv() {
+                Ok(event) => return Some(event),
+                Err(TryRecvError::Lagged(_)) => continue,
+                Err(_) => return None,
+            }
+        }
+    }
+}
+
+/// Adapts the receiving half of a tokio channel to [`Listen`](crate::prelude::EventListen),
+/// so that it can be polled like any other listener from within an update.
+///
+/// ```ignore
+/// let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+/// let listener = ChannelListener::new(receiver);
+/// for event in listener.peek() { /* ... */ }
+/// ```
+#[cfg(feature = "tokio")]
+#[cfg_attr(feature = "docs", doc(cfg(tokio)))]
+#[derive(Debug)]
+pub struct ChannelListener<R>(RefCell<R>);
+
+#[cfg(feature = "tokio")]
+impl<R: TryRecv> ChannelListener<R> {
+    pub fn new(receiver: R) -> Self {
+        ChannelListener(RefCell::new(receiver))
+    }
+
+    /// Returns the wrapped receiver, e.g. to `.await` it outside of updates.
+    pub fn into_inner(self) -> R {
+        self.0.into_inner()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<R: TryRecv> Listen for ChannelListener<R> {
+    type Item = R::Item;
+
+    fn with<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce(&[Self::Item]) -> T,
+    {
+        self.with_n(usize::MAX, f)
+    }
+
+    fn with_n<F, T>(&self, n: usize, f: F) -> T
+    where
+        F: FnOnce(&[Self::Item]) -> T,
+    {
+        let mut receiver = self.0.borrow_mut();
+        let events: Vec<_> = std::iter::from_fn(|| receiver.try_recv_event()).take(n).collect();
+        f(&events)
+    }
+}
+
 #[cfg(feature = "winit")]
 impl<T> QueueInterfaceCommon for winit::event_loop::EventLoopProxy<T> {
     type Item = T;
@@ -304,6 +456,44 @@ mod tests {
         h2.join().unwrap();
     }
 
+    #[test]
+    fn test_receiver_listener() {
+        use crate::traits::Listen;
+
+        let (sender, receiver) = mpsc::channel();
+        let mut event = vec![sender];
+        for i in 0..4 {
+            event.emit_owned(i).into_result().unwrap();
+        }
+        assert_eq!(receiver.peek_n(1), &[0]);
+        assert_eq!(receiver.peek(), &[1, 2, 3]);
+        assert!(receiver.peek().is_empty());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn test_tokio_channels() {
+        use crate::traits::{EmitterExt, Listen};
+
+        let (sender, receiver) = tokio::sync::mpsc::channel(2);
+        let listener = super::ChannelListener::new(receiver);
+        sender.emit_owned(1).into_result().unwrap();
+        sender.emit_owned(2).into_result().unwrap();
+        // the channel is full.
+        assert!(sender.emit_owned(3).was_undelivered());
+        assert_eq!(listener.peek(), &[1, 2]);
+
+        let (sender, receiver) = tokio::sync::broadcast::channel(2);
+        let listener = super::ChannelListener::new(receiver);
+        for i in 0..4 {
+            sender.emit_owned(i).into_result().unwrap();
+        }
+        // lagged events are skipped.
+        assert_eq!(listener.peek(), &[2, 3]);
+        drop(listener);
+        assert!(sender.emit_owned(4).was_undelivered());
+    }
+
     #[cfg(all(feature = "crossbeam-channel", feature = "winit"))]
     #[allow(dead_code)]
     fn winit_cascade() {
@@ -181,7 +181,7 @@ pub trait Listen {
     where
         F: FnMut(&Self::Item) -> R,
     {
-        self.with(|slc| slc.iter().map(|i| f(i)).collect())
+        self.with(|slc| slc.iter().map(&mut f).collect())
     }
 
     /// Returns a list of new events since last `peek`
@@ -206,7 +206,7 @@ pub trait Listen {
     where
         F: FnMut(&Self::Item) -> R,
     {
-        self.with_n(n, |slc| slc.iter().map(|i| f(i)).collect())
+        self.with_n(n, |slc| slc.iter().map(&mut f).collect())
     }
 
     /// Almost identical to [`peek`](Listen::peek), however only the first `n` events
@@ -9,7 +9,7 @@ impl<T: Clone> QueueInterfaceListable for Queue<T> {
 
     #[inline]
     fn listen(&self) -> Listener<T> {
-        Listener::new(Arc::clone(&self))
+        Listener::new(Arc::clone(self))
     }
 }
 
@@ -52,4 +52,22 @@ impl<T> Listener<T> {
         let key = eq.write().unwrap().create_listener();
         Listener { key, eq }
     }
+
+    /// Returns the number of events this listener hasn't seen yet.
+    #[inline]
+    pub fn events_pending(&self) -> usize {
+        self.eq.read().map(|eq| eq.events_pending(self.key)).unwrap_or(0)
+    }
+
+    /// Like [`with`](EventListen::with), but with every event paired with the time it was emitted.
+    ///
+    /// See [`RawEventQueue::pull_with_timestamps`](crate::RawEventQueue::pull_with_timestamps).
+    #[inline]
+    pub fn with_timestamps<F, R>(&self, f: F) -> R
+    where
+        T: Clone,
+        F: FnOnce(&[(std::time::Instant, T)]) -> R,
+    {
+        self.eq.write().ok().unwrap().pull_with_timestamps(self.key, f)
+    }
 }
@@ -107,15 +107,15 @@ impl Widget for Counter {
         builder.push_clear(Color::new(1.0, 1.0, 1.0, 1.0));
 
         builder.push_text(
-            TextDisplayItem {
-                text: format!("Count: {}", self.count).into(),
-                font: self.font.as_ref().unwrap().clone(),
-                font_info: self.font_info.clone(),
-                size: 23.0,
-                bottom_left: bounds.origin.add_size(&Size::new(10.0, 22.0)),
-                color: Color::new(0.0, 0.0, 0.0, 1.0).into(),
-            },
-            None,
+            TextDisplayItem::new(
+                format!("Count: {}", self.count),
+                self.font.as_ref().unwrap().clone(),
+                self.font_info.clone(),
+                23.0,
+                bounds.origin.add_size(&Size::new(10.0, 22.0)),
+                Color::new(0.0, 0.0, 0.0, 1.0),
+            ),
+            vec![],
         );
 
         self.command_group
@@ -213,19 +213,19 @@ impl Widget for Button {
             bounds,
             [10.0; 4],
             GraphicsDisplayPaint::Fill(color.into()),
-            None,
+            vec![],
         );
 
         builder.push_text(
-            TextDisplayItem {
-                text: self.text.clone().into(),
-                font: self.font.as_ref().unwrap().clone(),
-                font_info: self.font_info.clone(),
-                size: 22.0,
-                bottom_left: bounds.origin.add_size(&Size::new(10.0, bounds.size.height / 2.0)),
-                color: Color::new(1.0, 1.0, 1.0, 1.0).into(),
-            },
-            None,
+            TextDisplayItem::new(
+                self.text.clone(),
+                self.font.as_ref().unwrap().clone(),
+                self.font_info.clone(),
+                22.0,
+                bounds.origin.add_size(&Size::new(10.0, bounds.size.height / 2.0)),
+                Color::new(1.0, 1.0, 1.0, 1.0),
+            ),
+            vec![],
         );
 
         self.command_group.push(display, &builder.build(), Default::default(), None, None).unwrap();
@@ -257,6 +257,8 @@ fn main() {
         &display::skia::SkiaOpenGlFramebuffer {
             framebuffer_id: fboid as _,
             size: (window_size.0 as _, window_size.1 as _),
+            stencil_bits: 8,
+            ..Default::default()
         },
     )
     .unwrap();
@@ -6,37 +6,18 @@ use {
     },
     reclutch::{
         display::{
-            self, Color, CommandGroup, DisplayCommand, DisplayListBuilder, Filter, FontInfo,
+            self, Color, CommandGroup, DisplayCommand, DisplayListBuilder, FilterOp, FontInfo,
             GraphicsDisplay, GraphicsDisplayPaint, GraphicsDisplayStroke, ImageData, Point, Rect,
             ResourceData, ResourceDescriptor, ResourceReference, SharedData, Size, TextDisplayItem,
             Vector,
         },
-        event::{merge::Merge, RcEventListener, RcEventQueue},
+        event::{merge::Merge, ConsumableEvent, RcEventListener, RcEventQueue},
         gl,
         prelude::*,
         WidgetChildren,
     },
 };
 
-#[derive(Clone)]
-struct ConsumableEvent<T>(std::rc::Rc<std::cell::RefCell<Option<T>>>);
-
-impl<T> ConsumableEvent<T> {
-    fn new(val: T) -> Self {
-        ConsumableEvent(std::rc::Rc::new(std::cell::RefCell::new(Some(val))))
-    }
-
-    fn with<P: FnMut(&T) -> bool>(&self, mut pred: P) -> Option<T> {
-        if self.0.borrow().is_some() {
-            if pred(self.0.borrow().as_ref().unwrap()) {
-                return self.0.replace(None);
-            }
-        }
-
-        None
-    }
-}
-
 #[derive(Clone)]
 enum GlobalEvent {
     MouseClick(ConsumableEvent<Point>),
@@ -110,7 +91,7 @@ impl Widget for Titlebar {
             match event {
                 GlobalEvent::MouseClick(click) => {
                     if let Some(ref position) =
-                        click.with(|pos| self.bounds().contains(pos.clone()))
+                        click.consume_if(|pos| self.bounds().contains(pos.clone()))
                     {
                         self.cursor_anchor = Some(position.clone());
                         self.move_event.emit_owned(TitlebarEvent::BeginClick(position.clone()));
@@ -145,31 +126,31 @@ impl Widget for Titlebar {
 
         let mut builder = DisplayListBuilder::new();
 
-        builder.push_rectangle_backdrop(bounds, true, Filter::Blur(10.0, 10.0));
+        builder.push_rectangle_backdrop(bounds, true, vec![FilterOp::Blur(10.0, 10.0)]);
 
         builder.push_rectangle(
             bounds,
             GraphicsDisplayPaint::Fill(Color::new(1.0, 1.0, 1.0, 0.6).into()),
-            None,
+            vec![],
         );
 
         builder.push_line(
             Point::new(bounds.origin.x, bounds.origin.y + bounds.size.height),
             Point::new(bounds.origin.x + bounds.size.width, bounds.origin.y + bounds.size.height),
             GraphicsDisplayStroke { thickness: 1.0, antialias: false, ..Default::default() },
-            None,
+            vec![],
         );
 
         builder.push_text(
-            TextDisplayItem {
-                text: self.text.clone().into(),
-                font: self.font_resource.as_ref().unwrap().clone(),
-                font_info: self.font.clone(),
-                size: 22.0,
-                bottom_left: bounds.origin + Size::new(5.0, 22.0),
-                color: Color::new(0.0, 0.0, 0.0, 1.0).into(),
-            },
-            None,
+            TextDisplayItem::new(
+                self.text.clone(),
+                self.font_resource.as_ref().unwrap().clone(),
+                self.font.clone(),
+                22.0,
+                bounds.origin + Size::new(5.0, 22.0),
+                Color::new(0.0, 0.0, 0.0, 1.0),
+            ),
+            vec![],
         );
 
         self.command_group.push(display, &builder.build(), Default::default(), None, None).unwrap();
@@ -279,7 +260,7 @@ impl Widget for Panel {
         for event in self.global_listener.peek() {
             match event {
                 GlobalEvent::MouseClick(click) => {
-                    if let Some(_) = click.with(|pos| self.bounds().contains(pos.clone())) {
+                    if let Some(_) = click.consume_if(|pos| self.bounds().contains(pos.clone())) {
                         self.on_click.emit_owned(self as _);
                         self.command_group.repaint();
                         self.titlebar.command_group.repaint();
@@ -309,15 +290,15 @@ impl Widget for Panel {
 
         let mut builder = DisplayListBuilder::new();
 
-        builder.push_rectangle_backdrop(bounds, true, Filter::Blur(5.0, 5.0));
+        builder.push_rectangle_backdrop(bounds, true, vec![FilterOp::Blur(5.0, 5.0)]);
 
         builder.push_rectangle(
             bounds,
             GraphicsDisplayPaint::Fill(Color::new(0.9, 0.9, 0.9, 0.5).into()),
-            None,
+            vec![],
         );
 
-        builder.push_image(None, bounds, self.image.clone().unwrap(), None);
+        builder.push_image(None, bounds, self.image.clone().unwrap(), vec![]);
 
         builder.push_rectangle(
             bounds.inflate(0.0, 0.5),
@@ -327,7 +308,7 @@ impl Widget for Panel {
                 antialias: false,
                 ..Default::default()
             }),
-            None,
+            vec![],
         );
 
         self.command_group.push(display, &builder.build(), Default::default(), None, None).unwrap();
@@ -419,6 +400,8 @@ fn main() {
         &display::skia::SkiaOpenGlFramebuffer {
             framebuffer_id: fboid as _,
             size: (window_size.0 as _, window_size.1 as _),
+            stencil_bits: 8,
+            ..Default::default()
         },
     )
     .unwrap();
@@ -11,7 +11,7 @@ use {
         GlObject, Surface,
     },
     reclutch::display::{
-        self, Color, DisplayListBuilder, Filter, GraphicsDisplay, GraphicsDisplayPaint, Point,
+        self, Color, DisplayListBuilder, FilterOp, GraphicsDisplay, GraphicsDisplayPaint, Point,
         Rect, Size,
     },
 };
@@ -219,6 +219,7 @@ fn main() {
             size: (window_size.0 as _, window_size.1 as _),
             texture_id: out_texture.get_id(),
             mip_mapped: false,
+            ..Default::default()
         },
     )
     .unwrap();
@@ -230,13 +231,13 @@ fn main() {
 
         let mut builder = DisplayListBuilder::new();
 
-        builder.push_round_rectangle_backdrop(rect, [20.0; 4], Filter::Blur(10.0, 10.0));
+        builder.push_round_rectangle_backdrop(rect, [20.0; 4], vec![FilterOp::Blur(10.0, 10.0)]);
 
         builder.push_round_rectangle(
             rect,
             [20.0; 4],
             GraphicsDisplayPaint::Fill(Color::new(0.0, 0.0, 0.0, 0.2).into()),
-            None,
+            vec![],
         );
 
         display.push_command_group(&builder.build(), Default::default(), None, Some(false)).unwrap()
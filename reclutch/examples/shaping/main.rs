@@ -83,6 +83,8 @@ fn main() {
         &display::skia::SkiaOpenGlFramebuffer {
             framebuffer_id: fboid as _,
             size: (window_size.0 as _, window_size.1 as _),
+            stencil_bits: 8,
+            ..Default::default()
         },
     )
     .unwrap();
@@ -97,38 +99,38 @@ fn main() {
         let font_info = FontInfo::from_data(font_data, 0).unwrap();
 
         let text_blobs = vec![
-            TextDisplayItem {
-                font: font_resource.clone(),
-                font_info: font_info.clone(),
-                size: 32.0,
-                text: String::from("HarfBuzz").into(),
-                color: Color::new(0.0, 0.0, 0.0, 1.0).into(),
-                bottom_left: Point::new(40.0, 42.0),
-            },
-            TextDisplayItem {
-                font: font_resource.clone(),
-                font_info: font_info.clone(),
-                size: FONT_SIZE as _,
-                text: DisplayText::Shaped(shape_with_harfbuzz("एकोऽयम्", FONT_SIZE)),
-                color: Color::new(0.0, 0.0, 0.0, 1.0).into(),
-                bottom_left: Point::new(40.0, FONT_SIZE as f32 + 60.0),
-            },
-            TextDisplayItem {
-                font: font_resource.clone(),
-                font_info: font_info.clone(),
-                size: 32.0,
-                text: String::from("RustType").into(),
-                color: Color::new(0.0, 0.0, 0.0, 1.0).into(),
-                bottom_left: Point::new(40.0, 190.0),
-            },
-            TextDisplayItem {
-                font: font_resource.clone(),
-                font_info: font_info.clone(),
-                size: FONT_SIZE as f32,
-                text: DisplayText::Shaped(shape_with_rusttype("एकोऽयम्", FONT_SIZE)),
-                color: Color::new(0.0, 0.0, 0.0, 1.0).into(),
-                bottom_left: Point::new(40.0, FONT_SIZE as f32 + 210.0),
-            },
+            TextDisplayItem::new(
+                String::from("HarfBuzz"),
+                font_resource.clone(),
+                font_info.clone(),
+                32.0,
+                Point::new(40.0, 42.0),
+                Color::new(0.0, 0.0, 0.0, 1.0),
+            ),
+            TextDisplayItem::new(
+                DisplayText::Shaped(shape_with_harfbuzz("एकोऽयम्", FONT_SIZE)),
+                font_resource.clone(),
+                font_info.clone(),
+                FONT_SIZE as _,
+                Point::new(40.0, FONT_SIZE as f32 + 60.0),
+                Color::new(0.0, 0.0, 0.0, 1.0),
+            ),
+            TextDisplayItem::new(
+                String::from("RustType"),
+                font_resource.clone(),
+                font_info.clone(),
+                32.0,
+                Point::new(40.0, 190.0),
+                Color::new(0.0, 0.0, 0.0, 1.0),
+            ),
+            TextDisplayItem::new(
+                DisplayText::Shaped(shape_with_rusttype("एकोऽयम्", FONT_SIZE)),
+                font_resource.clone(),
+                font_info.clone(),
+                FONT_SIZE as f32,
+                Point::new(40.0, FONT_SIZE as f32 + 210.0),
+                Color::new(0.0, 0.0, 0.0, 1.0),
+            ),
         ];
 
         let mut builder = DisplayListBuilder::new();
@@ -142,9 +144,9 @@ fn main() {
                 bbox,
                 [5.0; 4],
                 display::GraphicsDisplayPaint::Fill(Color::new(0.0, 0.4, 1.0, 0.25).into()),
-                None,
+                vec![],
             );
-            builder.push_text(text_blob, None);
+            builder.push_text(text_blob, vec![]);
         }
 
         display
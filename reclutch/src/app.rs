@@ -0,0 +1,503 @@
+//! Opt-in application runner, which owns the window, the Skia display and the event loop.
+//!
+//! ```ignore
+//! reclutch::app::App::new(|window_q, _display| Counter::new(window_q))
+//!     .with_title("Counter")
+//!     .with_error_handler(|err| eprintln!("{}", err))
+//!     .run()
+//!     .unwrap();
+//! ```
+
+use {
+    crate::{
+        clipboard::ClipboardAction,
+        cursor::{CursorAux, CursorIcon},
+        display::{
+            skia::{SkiaGraphicsDisplay, SkiaOpenGlFramebuffer},
+            DisplayCommand, GraphicsDisplay, PhysicalPoint, PhysicalSize, Point, ScaleFactor,
+            Vector,
+        },
+        error::DisplayError,
+        event::RcEventQueue,
+        input::FileDropEvent,
+        pacer::{FrameAux, FramePacer},
+        prelude::*,
+        widget::FileDropRouter,
+        widgets::adapters::WrappedDisplay,
+    },
+    glutin::{
+        event::{ElementState, Event as WinitEvent, StartCause, WindowEvent as WinitWindowEvent},
+        event_loop::{ControlFlow, EventLoop},
+    },
+};
+
+pub use {
+    crate::input::{Key, Modifiers, MouseButton, TouchEvent, TouchPhase, WindowEvent},
+    glutin,
+};
+
+fn convert_mouse_button(button: glutin::event::MouseButton) -> MouseButton {
+    match button {
+        glutin::event::MouseButton::Left => MouseButton::Left,
+        glutin::event::MouseButton::Right => MouseButton::Right,
+        glutin::event::MouseButton::Middle => MouseButton::Middle,
+        glutin::event::MouseButton::Other(button) => MouseButton::Other(button as _),
+    }
+}
+
+fn convert_touch(touch: &glutin::event::Touch, scale: ScaleFactor) -> TouchEvent {
+    use glutin::event::TouchPhase as P;
+
+    TouchEvent {
+        id: touch.id,
+        phase: match touch.phase {
+            P::Started => TouchPhase::Start,
+            P::Moved => TouchPhase::Move,
+            P::Ended => TouchPhase::End,
+            P::Cancelled => TouchPhase::Cancel,
+        },
+        position: PhysicalPoint::new(touch.location.x as _, touch.location.y as _) / scale,
+        force: touch.force.map(|force| force.normalized() as _),
+    }
+}
+
+fn convert_key(input: &glutin::event::KeyboardInput) -> Key {
+    use glutin::event::VirtualKeyCode as K;
+
+    let key = match input.virtual_keycode {
+        Some(key) => key,
+        None => return Key::Other(input.scancode),
+    };
+    match key {
+        K::A => Key::A,
+        K::B => Key::B,
+        K::C => Key::C,
+        K::D => Key::D,
+        K::E => Key::E,
+        K::F => Key::F,
+        K::G => Key::G,
+        K::H => Key::H,
+        K::I => Key::I,
+        K::J => Key::J,
+        K::K => Key::K,
+        K::L => Key::L,
+        K::M => Key::M,
+        K::N => Key::N,
+        K::O => Key::O,
+        K::P => Key::P,
+        K::Q => Key::Q,
+        K::R => Key::R,
+        K::S => Key::S,
+        K::T => Key::T,
+        K::U => Key::U,
+        K::V => Key::V,
+        K::W => Key::W,
+        K::X => Key::X,
+        K::Y => Key::Y,
+        K::Z => Key::Z,
+        K::Key0 | K::Numpad0 => Key::Key0,
+        K::Key1 | K::Numpad1 => Key::Key1,
+        K::Key2 | K::Numpad2 => Key::Key2,
+        K::Key3 | K::Numpad3 => Key::Key3,
+        K::Key4 | K::Numpad4 => Key::Key4,
+        K::Key5 | K::Numpad5 => Key::Key5,
+        K::Key6 | K::Numpad6 => Key::Key6,
+        K::Key7 | K::Numpad7 => Key::Key7,
+        K::Key8 | K::Numpad8 => Key::Key8,
+        K::Key9 | K::Numpad9 => Key::Key9,
+        K::F1 => Key::F1,
+        K::F2 => Key::F2,
+        K::F3 => Key::F3,
+        K::F4 => Key::F4,
+        K::F5 => Key::F5,
+        K::F6 => Key::F6,
+        K::F7 => Key::F7,
+        K::F8 => Key::F8,
+        K::F9 => Key::F9,
+        K::F10 => Key::F10,
+        K::F11 => Key::F11,
+        K::F12 => Key::F12,
+        K::Escape => Key::Escape,
+        K::Return | K::NumpadEnter => Key::Enter,
+        K::Tab => Key::Tab,
+        K::Back => Key::Backspace,
+        K::Delete => Key::Delete,
+        K::Insert => Key::Insert,
+        K::Home => Key::Home,
+        K::End => Key::End,
+        K::PageUp => Key::PageUp,
+        K::PageDown => Key::PageDown,
+        K::Left => Key::Left,
+        K::Right => Key::Right,
+        K::Up => Key::Up,
+        K::Down => Key::Down,
+        K::Space => Key::Space,
+        _ => Key::Other(input.scancode),
+    }
+}
+
+fn apply_cursor<A: CursorAux>(window: &glutin::window::Window, aux: &mut A) {
+    use glutin::window::CursorIcon as WinitCursorIcon;
+
+    let icon = match aux.cursor_resolver().and_then(|resolver| resolver.resolve()) {
+        Some(icon) => icon,
+        None => return,
+    };
+    window.set_cursor_visible(icon != CursorIcon::Hidden);
+    window.set_cursor_icon(match icon {
+        CursorIcon::Default | CursorIcon::Hidden => WinitCursorIcon::Default,
+        CursorIcon::Text => WinitCursorIcon::Text,
+        CursorIcon::Hand => WinitCursorIcon::Hand,
+        CursorIcon::Crosshair => WinitCursorIcon::Crosshair,
+        CursorIcon::Move => WinitCursorIcon::Move,
+        CursorIcon::Grab => WinitCursorIcon::Grab,
+        CursorIcon::Grabbing => WinitCursorIcon::Grabbing,
+        CursorIcon::NotAllowed => WinitCursorIcon::NotAllowed,
+        CursorIcon::Wait => WinitCursorIcon::Wait,
+        CursorIcon::Progress => WinitCursorIcon::Progress,
+        CursorIcon::Help => WinitCursorIcon::Help,
+        CursorIcon::ResizeHorizontal => WinitCursorIcon::EwResize,
+        CursorIcon::ResizeVertical => WinitCursorIcon::NsResize,
+        CursorIcon::ResizeNeSw => WinitCursorIcon::NeswResize,
+        CursorIcon::ResizeNwSe => WinitCursorIcon::NwseResize,
+    });
+}
+
+type Factory<W> = Box<dyn FnOnce(&RcEventQueue<WindowEvent>, &mut dyn GraphicsDisplay) -> W>;
+type ErrorHandler = Box<dyn FnMut(AppError)>;
+type CursorApplier<A> = fn(&glutin::window::Window, &mut A);
+
+/// An error which occurred while the event loop was running, see [`App::with_error_handler`](App::with_error_handler).
+///
+/// None of these stop the event loop; the next frame (or resize) tries again.
+#[derive(Debug)]
+pub enum AppError {
+    /// Presenting a frame to the display failed.
+    Present(DisplayError),
+    /// Swapping the buffers of the OpenGL context failed, so the frame wasn't shown.
+    SwapBuffers(glutin::ContextError),
+    /// Resizing the display to the new window size failed.
+    Resize(DisplayError),
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AppError::Present(err) => write!(f, "failed to present: {}", err),
+            AppError::SwapBuffers(err) => write!(f, "failed to swap buffers: {}", err),
+            AppError::Resize(err) => write!(f, "failed to resize display: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AppError::Present(err) | AppError::Resize(err) => Some(err),
+            AppError::SwapBuffers(err) => Some(err),
+        }
+    }
+}
+
+/// A window hosting a single root widget.
+///
+/// The root widget is created once the window and display are ready, and is then updated
+/// after every window event and drawn whenever the window is redrawn.
+///
+/// The `UpdateAux` of the root widget receives the current [`FrameTime`](crate::pacer::FrameTime)
+/// before every update, so using `FrameTime` as `UpdateAux` gives widgets access to `delta_time`.
+/// Cursor icons can be applied to the window as well, see [`with_cursor_icons`](App::with_cursor_icons).
+/// Files dragged into the window are delivered to the widget under the cursor, see
+/// [`FileDropRouter`](crate::widget::FileDropRouter).
+pub struct App<W: Widget> {
+    factory: Factory<W>,
+    title: String,
+    size: (u32, u32),
+    vsync: bool,
+    target_fps: Option<f64>,
+    error_handler: Option<ErrorHandler>,
+    cursor_applier: Option<CursorApplier<W::UpdateAux>>,
+}
+
+impl<W> App<W>
+where
+    W: WidgetChildren<GraphicalAux = (), DisplayObject = DisplayCommand> + 'static,
+    W::UpdateAux: FrameAux + Default,
+{
+    /// Creates a new application, where `factory` creates the root widget from the global event queue
+    /// and the display (which can be used to load resources up-front).
+    pub fn new<F>(factory: F) -> Self
+    where
+        F: FnOnce(&RcEventQueue<WindowEvent>, &mut dyn GraphicsDisplay) -> W + 'static,
+    {
+        App {
+            factory: Box::new(factory),
+            title: "Reclutch".into(),
+            size: (500, 500),
+            vsync: true,
+            target_fps: None,
+            error_handler: None,
+            cursor_applier: None,
+        }
+    }
+
+    /// Sets the window title.
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Sets the initial window size, in physical pixels.
+    pub fn with_size(mut self, width: u32, height: u32) -> Self {
+        self.size = (width, height);
+        self
+    }
+
+    /// Sets whether presentation is synchronized to the monitor refresh rate (enabled by default).
+    pub fn with_vsync(mut self, vsync: bool) -> Self {
+        self.vsync = vsync;
+        self
+    }
+
+    /// Limits the frame rate; mostly useful when vsync is disabled.
+    pub fn with_target_fps(mut self, fps: impl Into<Option<f64>>) -> Self {
+        self.target_fps = fps.into();
+        self
+    }
+
+    /// Sets the callback invoked with errors which occur while the event loop is running.
+    ///
+    /// Without one, such errors are ignored.
+    pub fn with_error_handler(mut self, handler: impl FnMut(AppError) + 'static) -> Self {
+        self.error_handler = Some(Box::new(handler));
+        self
+    }
+
+    /// Applies the cursor icons requested through [`CursorAux`](crate::cursor::CursorAux) to the window after
+    /// every update.
+    ///
+    /// Without this, the `UpdateAux` doesn't need to implement `CursorAux` and the cursor is left alone.
+    pub fn with_cursor_icons(mut self) -> Self
+    where
+        W::UpdateAux: CursorAux,
+    {
+        self.cursor_applier = Some(apply_cursor::<W::UpdateAux>);
+        self
+    }
+
+    /// Creates the window and display, then runs the event loop.
+    ///
+    /// This only returns if setting up the window or display failed; otherwise the process exits
+    /// once the window is closed.
+    pub fn run(self) -> Result<(), Box<dyn std::error::Error>> {
+        let App { factory, title, size, vsync, target_fps, error_handler, cursor_applier } = self;
+        let mut report = {
+            let mut error_handler = error_handler;
+            move |err: AppError| {
+                if let Some(handler) = &mut error_handler {
+                    handler(err);
+                }
+            }
+        };
+
+        let event_loop = EventLoop::new();
+
+        let wb = glutin::window::WindowBuilder::new()
+            .with_title(title)
+            .with_inner_size(glutin::dpi::PhysicalSize::new(size.0, size.1));
+
+        let context =
+            glutin::ContextBuilder::new().with_vsync(vsync).build_windowed(wb, &event_loop)?;
+        let context = unsafe { context.make_current().map_err(|(_, err)| err)? };
+
+        crate::gl::load_with(|s| context.get_proc_address(s));
+
+        let mut fboid = 0;
+        unsafe { crate::gl::GetIntegerv(crate::gl::FRAMEBUFFER_BINDING, &mut fboid) };
+
+        let mut display = SkiaGraphicsDisplay::new_gl_framebuffer(
+            |s| context.get_proc_address(s),
+            &SkiaOpenGlFramebuffer {
+                framebuffer_id: fboid as _,
+                size: (size.0 as _, size.1 as _),
+                stencil_bits: 8,
+                ..Default::default()
+            },
+        )?;
+
+        let window_q = RcEventQueue::default();
+        let mut root = factory(&window_q, &mut display);
+        let mut scale = ScaleFactor::new(context.window().scale_factor() as _);
+        let mut cursor = Point::default();
+        let mut modifiers = Modifiers::default();
+        let mut aux = W::UpdateAux::default();
+        let mut pacer = FramePacer::new().with_target_fps(target_fps);
+        let mut redraw_pending = false;
+        let mut file_drops = FileDropRouter::new();
+        // winit reports dragged files one at a time; they're delivered together once the events are cleared.
+        // it doesn't report the cursor during the drag, so they're delivered at the last position it reported.
+        let (mut hovered_files, mut dropped_files) = (Vec::new(), Vec::new());
+
+        event_loop.run(move |event, _, control_flow| {
+            *control_flow = match pacer.next_frame_at() {
+                Some(next_frame) if redraw_pending => ControlFlow::WaitUntil(next_frame),
+                _ => ControlFlow::Wait,
+            };
+
+            let window_event = match event {
+                WinitEvent::RedrawRequested { .. } => {
+                    // widgets draw in logical pixels, so their command groups are scaled onto the surface.
+                    root.draw(
+                        &mut WrappedDisplay {
+                            display: &mut display,
+                            prefix: vec![
+                                DisplayCommand::Save,
+                                DisplayCommand::Scale(Vector::new(scale.0, scale.0)),
+                            ],
+                            suffix: vec![DisplayCommand::Restore],
+                        },
+                        &mut (),
+                    );
+                    if let Err(err) = display.present(None) {
+                        report(AppError::Present(err));
+                    }
+                    if let Err(err) = context.swap_buffers() {
+                        report(AppError::SwapBuffers(err));
+                    }
+                    pacer.frame_presented();
+                    return;
+                }
+                WinitEvent::NewEvents(StartCause::ResumeTimeReached { .. }) if redraw_pending => {
+                    redraw_pending = false;
+                    aux.set_frame_time(pacer.frame_time());
+                    root.update(&mut aux);
+                    if let Some(apply_cursor) = cursor_applier {
+                        apply_cursor(context.window(), &mut aux);
+                    }
+                    context.window().request_redraw();
+                    return;
+                }
+                WinitEvent::MainEventsCleared
+                    if !hovered_files.is_empty() || !dropped_files.is_empty() =>
+                {
+                    aux.set_frame_time(pacer.frame_time());
+                    if !hovered_files.is_empty() {
+                        let event =
+                            FileDropEvent::Hovered(std::mem::take(&mut hovered_files), cursor);
+                        file_drops.route(&mut root, &event, &mut aux);
+                    }
+                    if !dropped_files.is_empty() {
+                        let event =
+                            FileDropEvent::Dropped(std::mem::take(&mut dropped_files), cursor);
+                        file_drops.route(&mut root, &event, &mut aux);
+                    }
+                    root.update(&mut aux);
+                    if let Some(apply_cursor) = cursor_applier {
+                        apply_cursor(context.window(), &mut aux);
+                    }
+                    context.window().request_redraw();
+                    return;
+                }
+                WinitEvent::WindowEvent { event, .. } => event,
+                _ => return,
+            };
+
+            match window_event {
+                WinitWindowEvent::CloseRequested => {
+                    *control_flow = ControlFlow::Exit;
+                    return;
+                }
+                WinitWindowEvent::CursorMoved { position, .. } => {
+                    cursor = PhysicalPoint::new(position.x as _, position.y as _) / scale;
+                    window_q.emit_owned(WindowEvent::MouseMove(cursor));
+                }
+                WinitWindowEvent::MouseInput { state, button, .. } => {
+                    let button = convert_mouse_button(button);
+                    window_q.emit_owned(match state {
+                        ElementState::Pressed => WindowEvent::MousePress(cursor, button),
+                        ElementState::Released => WindowEvent::MouseRelease(cursor, button),
+                    });
+                }
+                WinitWindowEvent::ModifiersChanged(state) => {
+                    modifiers = Modifiers {
+                        ctrl: state.ctrl(),
+                        shift: state.shift(),
+                        alt: state.alt(),
+                        logo: state.logo(),
+                    };
+                    return;
+                }
+                WinitWindowEvent::KeyboardInput { input, .. } => {
+                    let key = convert_key(&input);
+                    window_q.emit_owned(match input.state {
+                        ElementState::Pressed => WindowEvent::KeyPress(key, modifiers),
+                        ElementState::Released => WindowEvent::KeyRelease(key, modifiers),
+                    });
+                    if input.state == ElementState::Pressed {
+                        if let Some(action) = ClipboardAction::from_chord(key, modifiers) {
+                            window_q.emit_owned(WindowEvent::Clipboard(action));
+                        }
+                    }
+                }
+                WinitWindowEvent::Touch(touch) => {
+                    window_q.emit_owned(WindowEvent::Touch(convert_touch(&touch, scale)));
+                }
+                WinitWindowEvent::ReceivedCharacter(c) => {
+                    window_q.emit_owned(WindowEvent::Character(c));
+                }
+                WinitWindowEvent::HoveredFile(path) => {
+                    hovered_files.push(path);
+                    return;
+                }
+                WinitWindowEvent::DroppedFile(path) => {
+                    dropped_files.push(path);
+                    return;
+                }
+                WinitWindowEvent::HoveredFileCancelled => {
+                    hovered_files.clear();
+                    aux.set_frame_time(pacer.frame_time());
+                    file_drops.route(&mut root, &FileDropEvent::Cancelled, &mut aux);
+                }
+                WinitWindowEvent::Focused(focus) => {
+                    window_q.emit_owned(WindowEvent::Focus(focus));
+                }
+                WinitWindowEvent::Resized(size) => {
+                    if let Err(err) = display.resize((size.width, size.height)) {
+                        report(AppError::Resize(err));
+                    }
+                    context.resize(size);
+                    window_q.emit_owned(WindowEvent::Resize(
+                        PhysicalSize::new(size.width as _, size.height as _) / scale,
+                    ));
+                }
+                WinitWindowEvent::ScaleFactorChanged { scale_factor, new_inner_size } => {
+                    let size = *new_inner_size;
+                    if let Err(err) = display.resize((size.width, size.height)) {
+                        report(AppError::Resize(err));
+                    }
+                    context.resize(size);
+                    scale = ScaleFactor::new(scale_factor as _);
+                    window_q.emit_owned(WindowEvent::Resize(
+                        PhysicalSize::new(size.width as _, size.height as _) / scale,
+                    ));
+                    window_q.emit_owned(WindowEvent::ScaleChanged(scale_factor as _));
+                }
+                _ => return,
+            }
+
+            aux.set_frame_time(pacer.frame_time());
+            root.update(&mut aux);
+            if let Some(apply_cursor) = cursor_applier {
+                apply_cursor(context.window(), &mut aux);
+            }
+
+            if pacer.should_present() {
+                context.window().request_redraw();
+            } else if let Some(next_frame) = pacer.next_frame_at() {
+                // throttle to the target frame rate; the redraw is requested once the time is reached.
+                redraw_pending = true;
+                *control_flow = ControlFlow::WaitUntil(next_frame);
+            }
+        })
+    }
+}
@@ -17,12 +17,13 @@ for child in self.children_mut() {
     child.draw(display);
 }
 ```
-The above example involves the `WidgetChildren` trait.
+The above example involves the `WidgetChildren` trait. `widget::propagate_update` and
+`widget::propagate_draw` do the same, skipping children which aren't `visible`.
 
 # `WidgetChildren`
 
 [`WidgetChildren`] is a supertrait which defines an interface to collate all the
-child widgets from fields into a single [`Vec`].
+child widgets from fields into a single [`SmallVec`](smallvec::SmallVec).
 
 Most of the time you don't want to implement [`WidgetChildren`] manually, instead
 you can use the provided `derive` crate to reduce it to a couple extra lines;
@@ -44,29 +45,44 @@ This will resolve down to the following code:
 impl reclutch::widget::WidgetChildren for CounterWidget {
     fn children(
         &self
-    ) -> Vec<
+    ) -> reclutch::smallvec::SmallVec<[
         &dyn reclutch::widget::WidgetChildren<
             UpdateAux = Self::UpdateAux,
             GraphicalAux = Self::GraphicalAux,
             DisplayObject = Self::DisplayObject,
-        >
-    > {
-        vec![&self.count_label, &self.count_up, &self.count_down]
+        >;
+        reclutch::widget::CHILDREN_INLINE_CAPACITY
+    ]> {
+        let mut children = reclutch::smallvec::SmallVec::with_capacity(3);
+        children.push(&self.count_label as _);
+        children.push(&self.count_up as _);
+        children.push(&self.count_down as _);
+        children
     }
 
     fn children_mut(
         &mut self
-    ) -> Vec<
-        &dyn reclutch::widget::WidgetChildren<
+    ) -> reclutch::smallvec::SmallVec<[
+        &mut dyn reclutch::widget::WidgetChildren<
             UpdateAux = Self::UpdateAux,
             GraphicalAux = Self::GraphicalAux,
             DisplayObject = Self::DisplayObject,
-        >
-    > {
-        vec![&mut self.count_label, &mut self.count_up, &mut self.count_down]
+        >;
+        reclutch::widget::CHILDREN_INLINE_CAPACITY
+    ]> {
+        let mut children = reclutch::smallvec::SmallVec::with_capacity(3);
+        children.push(&mut self.count_label as _);
+        children.push(&mut self.count_up as _);
+        children.push(&mut self.count_down as _);
+        children
+    }
+
+    fn child_count(&self) -> usize {
+        3
     }
 }
 ```
+As long as there are no more than [`CHILDREN_INLINE_CAPACITY`] children, collecting them doesn't allocate.
 
 [`bounds`]: widget::Widget::bounds
 [`update`]: widget::Widget::update
@@ -75,6 +91,7 @@ impl reclutch::widget::WidgetChildren for CounterWidget {
 [`GraphicalAux`]: widget::Widget::GraphicalAux
 [`DisplayObject`]: widget::Widget::DisplayObject
 [`WidgetChildren`]: widget::WidgetChildren
+[`CHILDREN_INLINE_CAPACITY`]: widget::CHILDREN_INLINE_CAPACITY
 **/
 
 #[cfg(feature = "reclutch_derive")]
@@ -82,11 +99,18 @@ impl reclutch::widget::WidgetChildren for CounterWidget {
 #[macro_use]
 extern crate reclutch_derive;
 
+#[cfg(all(feature = "reclutch_derive", feature = "state"))]
+pub use reclutch_derive::SaveState;
 #[cfg(feature = "reclutch_derive")]
-pub use reclutch_derive::{Event, OperatesVerbGraph, WidgetChildren};
+pub use reclutch_derive::{Event, HasVerbGraph, OperatesVerbGraph, WidgetChildren, WidgetState};
 
 pub use reclutch_verbgraph as verbgraph;
 
+#[cfg(feature = "app")]
+pub mod app;
+
+pub mod widgets;
+
 pub use reclutch_core::*;
 
 #[cfg(test)]
@@ -134,7 +158,7 @@ mod tests {
             b: ExampleChild,
             #[vec_widget_child]
             c: Vec<ExampleChild>,
-        };
+        }
 
         impl Widget for Named {
             type UpdateAux = ();
@@ -152,5 +176,251 @@ mod tests {
         assert_eq!(named.children_mut()[0].bounds().origin.x, 2.0);
         assert_eq!(named.children()[1].bounds().origin.x, 3.0);
         assert_eq!(named.children_mut()[2].bounds().origin.x, 4.0);
+
+        assert_eq!(unnamed.child_count(), 3);
+        assert_eq!(named.child_count(), named.children().len());
+        assert!(!named.children().spilled());
+    }
+
+    #[cfg(feature = "reclutch_derive")]
+    #[test]
+    fn test_dynamic_widget_children() {
+        use crate as reclutch;
+        use reclutch::{
+            display::{Point, Rect},
+            event::RcEventListener,
+            prelude::*,
+            widget::{ChildrenEvent, DynamicChildren},
+        };
+
+        #[derive(WidgetChildren)]
+        struct ExampleChild(i8);
+
+        impl Widget for ExampleChild {
+            type UpdateAux = ();
+            type GraphicalAux = ();
+            type DisplayObject = ();
+
+            fn bounds(&self) -> Rect {
+                Rect::new(Point::new(self.0 as _, 0.0), Default::default())
+            }
+        }
+
+        #[derive(WidgetChildren)]
+        struct Container {
+            #[widget_child]
+            header: ExampleChild,
+            #[dynamic_widget_children]
+            items: DynamicChildren<(), (), ()>,
+        }
+
+        impl Widget for Container {
+            type UpdateAux = ();
+            type GraphicalAux = ();
+            type DisplayObject = ();
+        }
+
+        let mut container = Container { header: ExampleChild(0), items: DynamicChildren::new() };
+        let listener: RcEventListener<_> = container.items.event.listen();
+
+        let a = container.add_child(Box::new(ExampleChild(1)));
+        let b = container.add_child(Box::new(ExampleChild(2)));
+        assert_ne!(a, b);
+        assert_eq!(container.child_count(), 3);
+        assert_eq!(container.children_mut()[2].bounds().origin.x, 2.0);
+
+        assert!(container.remove_child(a).is_some());
+        assert!(container.remove_child(a).is_none());
+        assert_eq!(container.children().len(), 2);
+        assert_eq!(container.children()[1].bounds().origin.x, 2.0);
+
+        assert_eq!(
+            listener.peek(),
+            &[ChildrenEvent::Added(a), ChildrenEvent::Added(b), ChildrenEvent::Removed(a)]
+        );
+    }
+
+    #[cfg(feature = "reclutch_derive")]
+    #[test]
+    fn test_boxed_widget_derive() {
+        use crate as reclutch;
+        use reclutch::{
+            display::{Point, Rect},
+            prelude::*,
+        };
+
+        type DynChild = dyn WidgetChildren<UpdateAux = (), GraphicalAux = (), DisplayObject = ()>;
+
+        #[derive(WidgetChildren)]
+        struct ExampleChild(i8);
+
+        impl Widget for ExampleChild {
+            type UpdateAux = ();
+            type GraphicalAux = ();
+            type DisplayObject = ();
+
+            fn bounds(&self) -> Rect {
+                Rect::new(Point::new(self.0 as _, 0.0), Default::default())
+            }
+        }
+
+        #[derive(WidgetChildren)]
+        struct Boxed<T: WidgetChildren<UpdateAux = (), GraphicalAux = (), DisplayObject = ()>> {
+            #[widget_child]
+            a: Box<DynChild>,
+            #[widget_child]
+            b: Box<ExampleChild>,
+            #[widget_child]
+            c: T,
+            #[vec_widget_child]
+            d: Vec<Box<DynChild>>,
+        }
+
+        impl<T: WidgetChildren<UpdateAux = (), GraphicalAux = (), DisplayObject = ()>> Widget for Boxed<T> {
+            type UpdateAux = ();
+            type GraphicalAux = ();
+            type DisplayObject = ();
+        }
+
+        let mut boxed = Boxed {
+            a: Box::new(ExampleChild(0)),
+            b: Box::new(ExampleChild(1)),
+            c: ExampleChild(2),
+            d: vec![Box::new(ExampleChild(3)), Box::new(ExampleChild(4))],
+        };
+
+        assert_eq!(boxed.child_count(), 5);
+        for (i, child) in boxed.children().into_iter().enumerate() {
+            assert_eq!(child.bounds().origin.x, i as f32);
+        }
+        assert_eq!(boxed.children_mut()[4].bounds().origin.x, 4.0);
+    }
+
+    #[cfg(all(feature = "reclutch_derive", feature = "state"))]
+    #[test]
+    fn test_save_state_derive() {
+        use crate as reclutch;
+        use reclutch::{
+            display::{recording::RecordingDisplay, CommandGroup},
+            serde_json::json,
+            state::SaveState,
+        };
+
+        #[derive(SaveState)]
+        struct Tree {
+            #[save_state]
+            expanded: Vec<bool>,
+            #[save_state]
+            scroll: (f32, f32),
+            selected: Option<usize>,
+            #[command_group]
+            command_group: CommandGroup,
+        }
+
+        let mut tree = Tree {
+            expanded: vec![true, false],
+            scroll: (0.0, 40.0),
+            selected: Some(1),
+            command_group: CommandGroup::new(),
+        };
+        let state = tree.save_fields();
+        assert_eq!(state, json!({ "expanded": [true, false], "scroll": [0.0, 40.0] }));
+
+        let mut display = RecordingDisplay::<()>::default();
+        tree.command_group.push(&mut display, &[], Default::default(), None, None).unwrap();
+        assert!(!tree.command_group.will_repaint());
+        tree.restore_fields(&json!({ "expanded": [false], "scroll": "invalid" }));
+        assert_eq!(tree.expanded, vec![false]);
+        assert_eq!(tree.scroll, (0.0, 40.0));
+        assert_eq!(tree.selected, Some(1));
+        assert!(tree.command_group.will_repaint());
+    }
+
+    #[cfg(feature = "reclutch_derive")]
+    #[test]
+    fn test_widget_state_derive() {
+        use crate as reclutch;
+        use reclutch::{
+            display::CommandGroup,
+            prelude::*,
+            verbgraph::{HasVerbGraph, OptionVerbGraph},
+        };
+
+        #[derive(WidgetChildren, HasVerbGraph, WidgetState)]
+        struct Label {
+            #[widget_state]
+            text: String,
+            #[widget_state]
+            size: u32,
+            #[command_group]
+            command_group: CommandGroup,
+            #[verb_graph]
+            graph: OptionVerbGraph<Self, ()>,
+        }
+
+        impl Widget for Label {
+            type UpdateAux = ();
+            type GraphicalAux = ();
+            type DisplayObject = ();
+        }
+
+        let mut label = Label {
+            text: "hello".into(),
+            size: 12,
+            command_group: CommandGroup::new(),
+            graph: None,
+        };
+        label.set_size(12);
+        label.set_text("world".into());
+        assert_eq!(label.text(), "world");
+        assert_eq!(*label.size(), 12);
+        assert!(label.command_group.will_repaint());
+
+        assert!(label.verb_graph().is_none());
+    }
+
+    #[cfg(feature = "reclutch_derive")]
+    #[test]
+    fn test_event_keys_derive() {
+        use crate as reclutch;
+        use reclutch::{
+            event::RcEventQueue,
+            verbgraph::{Event, QueueHandler, VerbGraph},
+        };
+
+        #[derive(Event, Clone)]
+        enum ShapeEvent {
+            #[event_key(pop)]
+            Pop,
+            #[event_key(squeeze)]
+            Squeeze(f32),
+            #[event_key(smash)]
+            Smash { force: f64 },
+        }
+
+        #[derive(Event, Clone)]
+        #[event_key(tick)]
+        struct Tick;
+
+        assert_eq!(ShapeEvent::KEYS, &["pop", "squeeze", "smash"]);
+        let events = [ShapeEvent::Pop, ShapeEvent::Squeeze(0.5), ShapeEvent::Smash { force: 2.0 }];
+        assert_eq!(events.iter().map(Event::get_key).collect::<Vec<_>>(), ShapeEvent::KEYS);
+        assert_eq!(events[1].clone().unwrap_as_squeeze(), Some(0.5));
+        assert_eq!(events[2].clone().unwrap_as_smash(), Some(2.0));
+        assert_eq!(Tick::KEYS, &["tick"]);
+
+        let shapes: RcEventQueue<ShapeEvent> = RcEventQueue::new();
+        let ticks: RcEventQueue<Tick> = RcEventQueue::new();
+        let graph = VerbGraph::<(), ()>::new()
+            .and_add(
+                "shapes",
+                QueueHandler::new(&shapes)
+                    .and_on("pop", |_, _, _| {})
+                    .and_on("smash", |_, _, _| {}),
+            )
+            .and_add("ticks", QueueHandler::new(&ticks).and_on("tick", |_, _, _| {}));
+
+        assert_eq!(graph.check_exhaustive::<ShapeEvent>(), vec![("shapes", "squeeze")]);
+        assert!(graph.check_exhaustive::<Tick>().is_empty());
     }
 }
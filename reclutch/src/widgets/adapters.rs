@@ -0,0 +1,547 @@
+//! Generic wrapper widgets which apply a visual adjustment to any widget.
+//!
+//! Each adapter implements [`Widget`](Widget) and [`WidgetChildren`](WidgetChildren) by delegating to the
+//! inner widget, adjusting the bounds and, for the graphical adapters, injecting display commands around
+//! every command group the inner widget pushes. Since the commands are injected when the inner widget pushes
+//! or modifies its command groups, changing the parameters of an adapter only takes effect once the inner
+//! widget repaints. Adapters can be nested:
+//!
+//! ```ignore
+//! let button = Opacity::new(Clipped::new(Padded::new(button, Padding::new_all_same(4.0)), clip), 0.5);
+//! ```
+
+use crate::{
+    display::{
+        BlendMode, ClipOp, ColorCorrection, CommandGroupHandle, CommandGroupInfo,
+        DisplayCapabilities, DisplayClip, DisplayCommand, EvictionCallback, EvictionPolicy,
+        GraphicsDisplay, HoleCallback, ImageData, PixelRect, PresentFeedback, PresentInfo,
+        RasterImage, Rect, ResourceDescriptor, ResourceReference, VirtualResolution, ZOrder,
+    },
+    error,
+    input::FileDropEvent,
+    widget::{
+        ChildrenList, ChildrenListMut, UpdateSchedule, Widget, WidgetChildren, WidgetTransform,
+    },
+};
+
+/// Padding around each side of a widget.
+pub type Padding = crate::euclid::SideOffsets2D<f32, crate::display::LogicalPixel>;
+
+/// Display which wraps every command group pushed through it in a prefix and suffix.
+pub(crate) struct WrappedDisplay<'a> {
+    pub(crate) display: &'a mut dyn GraphicsDisplay,
+    pub(crate) prefix: Vec<DisplayCommand>,
+    pub(crate) suffix: Vec<DisplayCommand>,
+}
+
+impl<'a> WrappedDisplay<'a> {
+    fn wrap(&self, commands: &[DisplayCommand]) -> Vec<DisplayCommand> {
+        let mut wrapped =
+            Vec::with_capacity(self.prefix.len() + commands.len() + self.suffix.len());
+        wrapped.extend(self.prefix.iter().cloned());
+        wrapped.extend(commands.iter().cloned());
+        wrapped.extend(self.suffix.iter().cloned());
+        wrapped
+    }
+}
+
+impl<'a> GraphicsDisplay for WrappedDisplay<'a> {
+    fn resize(&mut self, size: (u32, u32)) -> Result<(), error::DisplayError> {
+        self.display.resize(size)
+    }
+
+    fn new_resource(
+        &mut self,
+        descriptor: ResourceDescriptor,
+    ) -> Result<ResourceReference, error::ResourceError> {
+        self.display.new_resource(descriptor)
+    }
+
+    fn replace_resource(
+        &mut self,
+        reference: ResourceReference,
+        descriptor: ResourceDescriptor,
+    ) -> Result<(), error::DisplayError> {
+        self.display.replace_resource(reference, descriptor)
+    }
+
+    fn update_resource(
+        &mut self,
+        reference: ResourceReference,
+        data: ImageData,
+    ) -> Result<(), error::DisplayError> {
+        self.display.update_resource(reference, data)
+    }
+
+    fn remove_resource(&mut self, reference: ResourceReference) {
+        self.display.remove_resource(reference)
+    }
+
+    fn push_command_group(
+        &mut self,
+        commands: &[DisplayCommand],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        needs_maintain: Option<bool>,
+    ) -> Result<CommandGroupHandle, error::DisplayError> {
+        let commands = self.wrap(commands);
+        self.display.push_command_group(&commands, z_order, protected, needs_maintain)
+    }
+
+    /// Returns the command group including the injected commands.
+    fn get_command_group(&self, handle: CommandGroupHandle) -> Option<&[DisplayCommand]> {
+        self.display.get_command_group(handle)
+    }
+
+    fn modify_command_group(
+        &mut self,
+        handle: CommandGroupHandle,
+        commands: &[DisplayCommand],
+        z_order: ZOrder,
+        protected: Option<bool>,
+        needs_maintain: Option<bool>,
+    ) -> Result<(), error::DisplayError> {
+        let commands = self.wrap(commands);
+        self.display.modify_command_group(handle, &commands, z_order, protected, needs_maintain)
+    }
+
+    fn remove_command_group(&mut self, handle: CommandGroupHandle) -> Option<Vec<DisplayCommand>> {
+        let (prefix, suffix) = (self.prefix.len(), self.suffix.len());
+        self.display.remove_command_group(handle).map(|mut commands| {
+            if commands.len() >= prefix + suffix {
+                commands.truncate(commands.len() - suffix);
+                commands.drain(..prefix);
+            }
+            commands
+        })
+    }
+
+    fn label_command_group(&mut self, handle: CommandGroupHandle, label: Option<&'static str>) {
+        self.display.label_command_group(handle, label)
+    }
+
+    fn debug_dump(&self) -> Vec<CommandGroupInfo> {
+        self.display.debug_dump()
+    }
+
+    fn maintain_command_group(&mut self, handle: CommandGroupHandle) {
+        self.display.maintain_command_group(handle)
+    }
+
+    fn supports_backdrop_filter(&self) -> bool {
+        self.display.supports_backdrop_filter()
+    }
+
+    fn max_texture_size(&self) -> Option<u32> {
+        self.display.max_texture_size()
+    }
+
+    fn capabilities(&self) -> DisplayCapabilities {
+        self.display.capabilities()
+    }
+
+    fn begin_update(&mut self) {
+        self.display.begin_update()
+    }
+
+    fn commit_update(&mut self) -> Result<(), error::DisplayError> {
+        self.display.commit_update()
+    }
+
+    fn set_eviction_policy(&mut self, policy: EvictionPolicy) -> Result<(), error::DisplayError> {
+        self.display.set_eviction_policy(policy)
+    }
+
+    fn set_eviction_callback(
+        &mut self,
+        callback: Option<EvictionCallback>,
+    ) -> Result<(), error::DisplayError> {
+        self.display.set_eviction_callback(callback)
+    }
+
+    fn before_exit(&mut self) {
+        self.display.before_exit()
+    }
+
+    fn present(&mut self, cull: Option<Rect>) -> Result<PresentInfo, error::DisplayError> {
+        self.display.present(cull)
+    }
+
+    fn present_with_callback(
+        &mut self,
+        cull: Option<Rect>,
+        callback: Box<dyn FnOnce(PresentFeedback)>,
+    ) -> Result<PresentInfo, error::DisplayError> {
+        self.display.present_with_callback(cull, callback)
+    }
+
+    fn read_pixels(&mut self, rect: PixelRect) -> Result<RasterImage, error::DisplayError> {
+        self.display.read_pixels(rect)
+    }
+
+    fn set_virtual_resolution(
+        &mut self,
+        resolution: Option<VirtualResolution>,
+    ) -> Result<(), error::DisplayError> {
+        self.display.set_virtual_resolution(resolution)
+    }
+
+    fn set_color_correction(
+        &mut self,
+        correction: ColorCorrection,
+    ) -> Result<(), error::DisplayError> {
+        self.display.set_color_correction(correction)
+    }
+
+    fn set_hole_callback(
+        &mut self,
+        id: u64,
+        callback: Option<HoleCallback>,
+    ) -> Result<(), error::DisplayError> {
+        self.display.set_hole_callback(id, callback)
+    }
+}
+
+/// Implements `WidgetChildren` for an adapter by delegating to the inner widget.
+macro_rules! delegate_children {
+    ($adapter:ident) => {
+        impl<W: WidgetChildren> WidgetChildren for $adapter<W>
+        where
+            $adapter<W>: Widget<
+                UpdateAux = W::UpdateAux,
+                GraphicalAux = W::GraphicalAux,
+                DisplayObject = W::DisplayObject,
+            >,
+        {
+            fn children(
+                &self,
+            ) -> ChildrenList<'_, Self::UpdateAux, Self::GraphicalAux, Self::DisplayObject> {
+                self.inner.children()
+            }
+
+            fn children_mut(
+                &mut self,
+            ) -> ChildrenListMut<'_, Self::UpdateAux, Self::GraphicalAux, Self::DisplayObject> {
+                self.inner.children_mut()
+            }
+
+            fn child_count(&self) -> usize {
+                self.inner.child_count()
+            }
+        }
+    };
+}
+
+/// Implements `Widget` for a graphical adapter, which draws the inner widget into a wrapped display.
+macro_rules! wrapping_widget {
+    ($adapter:ident, |$self:ident| $bounds:expr, $transform:expr) => {
+        impl<W: Widget<DisplayObject = DisplayCommand>> Widget for $adapter<W> {
+            type UpdateAux = W::UpdateAux;
+            type GraphicalAux = W::GraphicalAux;
+            type DisplayObject = DisplayCommand;
+
+            fn bounds(&$self) -> Rect {
+                $bounds
+            }
+
+            fn transform(&$self) -> WidgetTransform {
+                $transform
+            }
+
+            fn debug_name(&self) -> &'static str {
+                self.inner.debug_name()
+            }
+
+            fn repaint_pending(&self) -> Option<bool> {
+                self.inner.repaint_pending()
+            }
+
+            #[cfg(feature = "state")]
+            fn persistent_state(&self) -> Option<&dyn crate::state::PersistentState> {
+                self.inner.persistent_state()
+            }
+
+            #[cfg(feature = "state")]
+            fn persistent_state_mut(&mut self) -> Option<&mut dyn crate::state::PersistentState> {
+                self.inner.persistent_state_mut()
+            }
+
+            fn visible(&self) -> bool {
+                self.inner.visible()
+            }
+
+            fn enabled(&self) -> bool {
+                self.inner.enabled()
+            }
+
+            fn update(&mut self, aux: &mut Self::UpdateAux) {
+                self.inner.update(aux)
+            }
+
+            fn file_drop(&mut self, event: &FileDropEvent, aux: &mut Self::UpdateAux) -> bool {
+                self.inner.file_drop(event, aux)
+            }
+
+            fn update_schedule(&self) -> Option<UpdateSchedule> {
+                self.inner.update_schedule()
+            }
+
+            fn scheduled_update(&mut self, aux: &mut Self::UpdateAux) {
+                self.inner.scheduled_update(aux)
+            }
+
+            fn draw(&mut self, display: &mut dyn GraphicsDisplay, aux: &mut Self::GraphicalAux) {
+                let (prefix, suffix) = self.commands();
+                self.inner.draw(&mut WrappedDisplay { display, prefix, suffix }, aux)
+            }
+        }
+
+        delegate_children!($adapter);
+    };
+}
+
+/// Adds padding around the bounds of a widget.
+///
+/// Only the bounds are affected; the inner widget draws as usual.
+#[derive(Debug, Clone)]
+pub struct Padded<W> {
+    pub inner: W,
+    pub padding: Padding,
+}
+
+impl<W> Padded<W> {
+    /// Wraps a widget in padding.
+    pub fn new(inner: W, padding: Padding) -> Self {
+        Padded { inner, padding }
+    }
+}
+
+impl<W: Widget> Widget for Padded<W> {
+    type UpdateAux = W::UpdateAux;
+    type GraphicalAux = W::GraphicalAux;
+    type DisplayObject = W::DisplayObject;
+
+    fn bounds(&self) -> Rect {
+        self.inner.bounds().outer_rect(self.padding)
+    }
+
+    fn transform(&self) -> WidgetTransform {
+        self.inner.transform()
+    }
+
+    fn debug_name(&self) -> &'static str {
+        self.inner.debug_name()
+    }
+
+    fn repaint_pending(&self) -> Option<bool> {
+        self.inner.repaint_pending()
+    }
+
+    #[cfg(feature = "state")]
+    fn persistent_state(&self) -> Option<&dyn crate::state::PersistentState> {
+        self.inner.persistent_state()
+    }
+
+    #[cfg(feature = "state")]
+    fn persistent_state_mut(&mut self) -> Option<&mut dyn crate::state::PersistentState> {
+        self.inner.persistent_state_mut()
+    }
+
+    fn visible(&self) -> bool {
+        self.inner.visible()
+    }
+
+    fn enabled(&self) -> bool {
+        self.inner.enabled()
+    }
+
+    fn update(&mut self, aux: &mut Self::UpdateAux) {
+        self.inner.update(aux)
+    }
+
+    fn file_drop(&mut self, event: &FileDropEvent, aux: &mut Self::UpdateAux) -> bool {
+        self.inner.file_drop(event, aux)
+    }
+
+    fn update_schedule(&self) -> Option<UpdateSchedule> {
+        self.inner.update_schedule()
+    }
+
+    fn scheduled_update(&mut self, aux: &mut Self::UpdateAux) {
+        self.inner.scheduled_update(aux)
+    }
+
+    fn draw(
+        &mut self,
+        display: &mut dyn GraphicsDisplay<Self::DisplayObject>,
+        aux: &mut Self::GraphicalAux,
+    ) {
+        self.inner.draw(display, aux)
+    }
+}
+
+delegate_children!(Padded);
+
+/// Clips the drawing of a widget to a rectangle.
+#[derive(Debug, Clone)]
+pub struct Clipped<W> {
+    pub inner: W,
+    /// The clip, in the same space as the bounds of the inner widget.
+    pub clip: Rect,
+}
+
+impl<W> Clipped<W> {
+    /// Wraps a widget in a clip.
+    pub fn new(inner: W, clip: Rect) -> Self {
+        Clipped { inner, clip }
+    }
+
+    fn commands(&self) -> (Vec<DisplayCommand>, Vec<DisplayCommand>) {
+        (
+            vec![
+                DisplayCommand::Save,
+                DisplayCommand::Clip(
+                    DisplayClip::Rectangle { rect: self.clip, antialias: true },
+                    ClipOp::Intersect,
+                ),
+            ],
+            vec![DisplayCommand::Restore],
+        )
+    }
+}
+
+wrapping_widget!(
+    Clipped,
+    |self| self.inner.bounds().intersection(&self.clip).unwrap_or_default(),
+    self.inner.transform()
+);
+
+/// Draws a widget with an additional transform.
+#[derive(Debug, Clone)]
+pub struct Transformed<W> {
+    pub inner: W,
+    /// Transform applied on top of the transform of the inner widget.
+    pub transform: WidgetTransform,
+}
+
+impl<W> Transformed<W> {
+    /// Wraps a widget in a transform.
+    pub fn new(inner: W, transform: WidgetTransform) -> Self {
+        Transformed { inner, transform }
+    }
+
+    fn commands(&self) -> (Vec<DisplayCommand>, Vec<DisplayCommand>) {
+        (
+            vec![
+                DisplayCommand::Save,
+                DisplayCommand::Translate(self.transform.translation),
+                DisplayCommand::Scale(self.transform.scale),
+            ],
+            vec![DisplayCommand::Restore],
+        )
+    }
+}
+
+wrapping_widget!(
+    Transformed,
+    |self| self.transform.transform_rect(self.inner.bounds()),
+    self.inner.transform().then(&self.transform)
+);
+
+/// Draws a widget with reduced opacity.
+///
+/// Each command group of the inner widget is faded separately, so overlapping command groups
+/// show through each other.
+#[derive(Debug, Clone)]
+pub struct Opacity<W> {
+    pub inner: W,
+    /// Opacity from `0.0` (invisible) to `1.0` (opaque).
+    pub opacity: f32,
+}
+
+impl<W> Opacity<W> {
+    /// Wraps a widget in a fade.
+    pub fn new(inner: W, opacity: f32) -> Self {
+        Opacity { inner, opacity }
+    }
+
+    fn commands(&self) -> (Vec<DisplayCommand>, Vec<DisplayCommand>) {
+        (
+            vec![DisplayCommand::SaveLayer(self.opacity, BlendMode::Normal)],
+            vec![DisplayCommand::Restore],
+        )
+    }
+}
+
+wrapping_widget!(Opacity, |self| self.inner.bounds(), self.inner.transform());
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::display::{
+            recording::RecordingDisplay, validate::validate_display_list, Color, CommandGroup,
+            DisplayListBuilder, GraphicsDisplayPaint, Point, Size, Vector,
+        },
+    };
+
+    struct Square(CommandGroup);
+
+    impl Widget for Square {
+        type UpdateAux = ();
+        type GraphicalAux = ();
+        type DisplayObject = DisplayCommand;
+
+        fn bounds(&self) -> Rect {
+            Rect::new(Point::new(10.0, 10.0), Size::new(20.0, 20.0))
+        }
+
+        fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut ()) {
+            let mut builder = DisplayListBuilder::new();
+            builder.push_rectangle(
+                self.bounds(),
+                GraphicsDisplayPaint::Fill(Color::new(0.0, 0.0, 1.0, 1.0).into()),
+                vec![],
+            );
+            self.0.push(display, &builder.build(), ZOrder::default(), None, None).unwrap();
+        }
+    }
+
+    impl WidgetChildren for Square {}
+
+    #[test]
+    fn test_adapters() {
+        let padded = Padded::new(Square(CommandGroup::new()), Padding::new_all_same(5.0));
+        assert_eq!(padded.bounds(), Rect::new(Point::new(5.0, 5.0), Size::new(30.0, 30.0)));
+
+        let clipped = Clipped::new(padded, Rect::new(Point::zero(), Size::new(20.0, 20.0)));
+        assert_eq!(clipped.bounds(), Rect::new(Point::new(5.0, 5.0), Size::new(15.0, 15.0)));
+
+        let transformed =
+            Transformed::new(clipped, WidgetTransform::from_translation(Vector::new(100.0, 0.0)));
+        assert_eq!(transformed.bounds().origin, Point::new(105.0, 5.0));
+        assert_eq!(transformed.transform().translation, Vector::new(100.0, 0.0));
+
+        let mut widget = Opacity::new(transformed, 0.5);
+        assert_eq!(widget.child_count(), 0);
+        assert!(widget.debug_name().ends_with("Square"));
+
+        let mut display = RecordingDisplay::default();
+        widget.draw(&mut display, &mut ());
+        display.present(None).unwrap();
+
+        let presented = display.presented();
+        validate_display_list(presented).unwrap();
+        assert_eq!(presented.len(), 10);
+        assert!(matches!(presented[0], DisplayCommand::SaveLayer(opacity, _) if opacity == 0.5));
+        assert!(matches!(presented[2], DisplayCommand::Translate(_)));
+        assert!(matches!(presented[5], DisplayCommand::Clip(..)));
+        assert!(matches!(presented[6], DisplayCommand::Item(..)));
+
+        // repainting goes through the same wrapping.
+        widget.inner.inner.inner.inner.0.repaint();
+        widget.draw(&mut display, &mut ());
+        display.present(None).unwrap();
+        assert_eq!(display.presented().len(), 10);
+    }
+}
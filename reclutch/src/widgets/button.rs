@@ -0,0 +1,260 @@
+//! A push button with a text label.
+
+use {
+    super::Theme,
+    crate as reclutch,
+    crate::{
+        display::{
+            center, CommandGroup, DisplayCommand, DisplayListBuilder, GraphicsDisplay, Point, Rect,
+            ZOrder,
+        },
+        error::DisplayError,
+        event::RcEventQueue,
+        input::{MouseButton, WindowEvent},
+        prelude::*,
+        style::StyleState,
+        verbgraph::{self, OptionVerbGraph, QueueHandler, VerbGraph},
+        Event, HasVerbGraph, WidgetChildren, WidgetState,
+    },
+    std::marker::PhantomData,
+};
+
+/// Events emitted by a [`Button`](Button).
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum ButtonEvent {
+    /// The left mouse button was pressed within the button.
+    #[event_key(press)]
+    Press(Point),
+    /// The left mouse button was released after being pressed within the button, anywhere in the window.
+    #[event_key(release)]
+    Release(Point),
+    /// The left mouse button was pressed and released within the button.
+    #[event_key(click)]
+    Click(Point),
+}
+
+/// A push button.
+///
+/// The button is highlighted while hovered or pressed.
+#[derive(WidgetChildren, HasVerbGraph, WidgetState)]
+pub struct Button<U: 'static = (), G: 'static = ()> {
+    pub event: RcEventQueue<ButtonEvent>,
+
+    #[widget_state]
+    text: String,
+    #[widget_state]
+    bounds: Rect,
+    #[widget_state]
+    disabled: bool,
+
+    theme: Theme,
+    hovered: bool,
+    pressed: bool,
+    #[command_group]
+    command_group: CommandGroup,
+    #[verb_graph]
+    graph: OptionVerbGraph<Self, U>,
+    draw_error: Option<DisplayError>,
+    phantom: PhantomData<G>,
+}
+
+impl<U: 'static, G: 'static> Button<U, G> {
+    /// Creates a button, handling input from the global window event queue.
+    pub fn new(
+        text: impl Into<String>,
+        bounds: Rect,
+        theme: Theme,
+        window_q: &RcEventQueue<WindowEvent>,
+    ) -> Self {
+        let graph = VerbGraph::new().and_add(
+            "window",
+            QueueHandler::new(window_q)
+                .and_on("mouse_press", |button: &mut Self, _, event| {
+                    if let WindowEvent::MousePress(point, MouseButton::Left) = event {
+                        if !button.disabled && button.bounds.contains(point) {
+                            button.pressed = true;
+                            button.command_group.repaint();
+                            button.event.emit_owned(ButtonEvent::Press(point));
+                        }
+                    }
+                })
+                .and_on("mouse_release", |button: &mut Self, _, event| {
+                    if let WindowEvent::MouseRelease(point, MouseButton::Left) = event {
+                        if button.pressed {
+                            button.pressed = false;
+                            button.command_group.repaint();
+                            button.event.emit_owned(ButtonEvent::Release(point));
+                            if button.bounds.contains(point) {
+                                button.event.emit_owned(ButtonEvent::Click(point));
+                            }
+                        }
+                    }
+                })
+                .and_on("mouse_move", |button: &mut Self, _, event| {
+                    if let WindowEvent::MouseMove(point) = event {
+                        let hovered = button.bounds.contains(point);
+                        if button.hovered != hovered {
+                            button.hovered = hovered;
+                            button.command_group.repaint();
+                        }
+                    }
+                }),
+        );
+
+        Button {
+            event: RcEventQueue::new(),
+            text: text.into(),
+            bounds,
+            disabled: false,
+            theme,
+            hovered: false,
+            pressed: false,
+            command_group: CommandGroup::with_label("button"),
+            graph: Some(graph),
+            draw_error: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns `true` if the left mouse button was pressed within the button and not yet released.
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+
+    /// Returns `true` if the cursor is over the button.
+    pub fn is_hovered(&self) -> bool {
+        self.hovered
+    }
+
+    /// Takes the error which occurred the last time the button failed to push its commands to the display, if any.
+    ///
+    /// A failed push leaves the button marked for repaint, so it is drawn again on the next call to `draw`.
+    pub fn take_draw_error(&mut self) -> Option<DisplayError> {
+        self.draw_error.take()
+    }
+}
+
+impl<U: 'static, G: 'static> Styled for Button<U, G> {
+    fn style_class(&self) -> &str {
+        "button"
+    }
+
+    fn style_state(&self) -> StyleState {
+        StyleState {
+            hovered: self.hovered,
+            pressed: self.pressed,
+            disabled: self.disabled,
+            ..Default::default()
+        }
+    }
+}
+
+impl<U: 'static, G: 'static> Widget for Button<U, G> {
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    fn enabled(&self) -> bool {
+        !self.disabled
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        verbgraph::update_all(self, aux);
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let style = self.theme.styles.resolve_for(self);
+        let theme = &self.theme;
+        let (text, bounds) = (&self.text, self.bounds);
+
+        if let Err(err) = self.command_group.push_with(
+            display,
+            || {
+                let text_bounds = theme.text_bounds(text, Point::zero());
+                let mut builder = DisplayListBuilder::new();
+                let radii = [style.corner_radius; 4];
+                builder.push_round_rectangle(bounds, radii, style.background_paint(), vec![]);
+                builder.push_round_rectangle(bounds, radii, style.border_paint(), vec![]);
+                builder.push_text(
+                    theme.text_item(text, center(text_bounds.size, bounds), style.foreground),
+                    vec![],
+                );
+                builder.build()
+            },
+            ZOrder::default(),
+            None,
+            None,
+        ) {
+            self.draw_error = Some(err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            display::Size,
+            testing::Harness,
+            widgets::{test_theme as theme, FailingDisplay},
+        },
+    };
+
+    #[test]
+    fn test_button() {
+        let bounds = Rect::new(Point::new(10.0, 10.0), Size::new(100.0, 30.0));
+        let mut harness: Harness<Button> =
+            Harness::new(|window_q, _| Button::new("Count", bounds, theme(), window_q), (), ());
+        let listener = harness.root().event.listen();
+
+        harness.click(Point::new(20.0, 20.0));
+        assert!(harness.root().is_hovered());
+        assert_eq!(
+            listener.peek(),
+            &[
+                ButtonEvent::Press(Point::new(20.0, 20.0)),
+                ButtonEvent::Release(Point::new(20.0, 20.0)),
+                ButtonEvent::Click(Point::new(20.0, 20.0)),
+            ]
+        );
+
+        // dragging off the button before releasing doesn't click.
+        harness.emit(WindowEvent::MousePress(Point::new(20.0, 20.0), MouseButton::Left));
+        assert!(harness.root().is_pressed());
+        harness.emit(WindowEvent::MouseRelease(Point::new(200.0, 20.0), MouseButton::Left));
+        assert_eq!(
+            listener.peek(),
+            &[
+                ButtonEvent::Press(Point::new(20.0, 20.0)),
+                ButtonEvent::Release(Point::new(200.0, 20.0)),
+            ]
+        );
+
+        harness.root_mut().set_disabled(true);
+        assert!(!harness.root().enabled());
+        let style = harness.root().theme.styles.resolve_for(harness.root());
+        assert_eq!(style.foreground, theme().placeholder);
+        assert_eq!(style.corner_radius, 3.0);
+        harness.click(Point::new(20.0, 20.0));
+        assert!(listener.peek().is_empty());
+
+        assert_eq!(harness.render_to_buffer().len(), 3);
+        assert!(!harness.root().command_group.will_repaint());
+    }
+
+    #[test]
+    fn test_draw_error() {
+        let bounds = Rect::new(Point::new(10.0, 10.0), Size::new(100.0, 30.0));
+        let mut button: Button = Button::new("Count", bounds, theme(), &RcEventQueue::new());
+
+        button.draw(&mut FailingDisplay, &mut ());
+        assert!(button.take_draw_error().is_some());
+        assert!(button.take_draw_error().is_none());
+        assert!(button.command_group.will_repaint());
+    }
+}
@@ -0,0 +1,371 @@
+//! A surface for freehand drawing.
+
+use {
+    super::Theme,
+    crate as reclutch,
+    crate::{
+        display::{
+            CommandGroup, DisplayCommand, DisplayListBuilder, GraphicsDisplay,
+            GraphicsDisplayPaint, GraphicsDisplayStroke, LineCap, LineJoin, Point, Rect,
+            VectorPath, VectorPathEvent, ZOrder,
+        },
+        error::DisplayError,
+        event::RcEventQueue,
+        input::{Key, MouseButton, WindowEvent},
+        prelude::*,
+        verbgraph::{self, OptionVerbGraph, QueueHandler, VerbGraph},
+        Event, HasVerbGraph, WidgetChildren,
+    },
+    std::marker::PhantomData,
+};
+
+/// Smallest distance the cursor has to move for a point to be added to the active stroke.
+const MIN_POINT_DISTANCE: f32 = 1.0;
+
+/// A path drawn with a single drag.
+#[derive(Debug, Clone)]
+pub struct Stroke {
+    pub path: VectorPath,
+    pub style: GraphicsDisplayStroke,
+}
+
+impl Stroke {
+    fn start(point: Point, style: GraphicsDisplayStroke) -> Self {
+        // the initial zero-length line makes a single click leave a dot (given round caps).
+        Stroke {
+            path: vec![
+                VectorPathEvent::MoveTo { to: point },
+                VectorPathEvent::LineTo { to: point },
+            ],
+            style,
+        }
+    }
+
+    fn last_point(&self) -> Option<Point> {
+        match self.path.last()? {
+            VectorPathEvent::MoveTo { to } | VectorPathEvent::LineTo { to } => Some(*to),
+            _ => None,
+        }
+    }
+
+    fn push(&self, builder: &mut DisplayListBuilder) {
+        builder.push_path(
+            self.path.clone(),
+            false,
+            GraphicsDisplayPaint::Stroke(self.style.clone()),
+            vec![],
+        );
+    }
+}
+
+/// Events emitted by a [`CanvasWidget`](CanvasWidget).
+#[derive(Event, Debug, Clone, PartialEq)]
+pub enum CanvasEvent {
+    /// A stroke was finished by releasing the mouse; contains the new number of strokes.
+    #[event_key(stroke)]
+    Stroke(usize),
+    /// The last stroke was undone; contains the new number of strokes.
+    #[event_key(undo)]
+    Undo(usize),
+}
+
+/// A surface drawn on by dragging with the left mouse button, keeping every stroke until it's undone.
+///
+/// Finished strokes are drawn into one command group, which is only repainted when a stroke is finished or undone,
+/// while the stroke being drawn has a command group of its own. Adding a point therefore only re-pushes the
+/// active stroke, rather than the whole drawing.
+///
+/// Ctrl+Z undoes the last stroke.
+#[derive(WidgetChildren, HasVerbGraph)]
+pub struct CanvasWidget<U: 'static = (), G: 'static = ()> {
+    pub event: RcEventQueue<CanvasEvent>,
+
+    strokes: Vec<Stroke>,
+    active: Option<Stroke>,
+    style: GraphicsDisplayStroke,
+    bounds: Rect,
+
+    theme: Theme,
+    history_group: CommandGroup,
+    active_group: CommandGroup,
+    #[verb_graph]
+    graph: OptionVerbGraph<Self, U>,
+    draw_error: Option<DisplayError>,
+    phantom: PhantomData<G>,
+}
+
+impl<U: 'static, G: 'static> CanvasWidget<U, G> {
+    /// Creates an empty canvas, drawing in the text color of the theme, handling input from the global window
+    /// event queue.
+    pub fn new(bounds: Rect, theme: Theme, window_q: &RcEventQueue<WindowEvent>) -> Self {
+        let graph = VerbGraph::new().and_add(
+            "window",
+            QueueHandler::new(window_q)
+                .and_on("mouse_press", |canvas: &mut Self, _, event| {
+                    if let WindowEvent::MousePress(point, MouseButton::Left) = event {
+                        if canvas.bounds.contains(point) {
+                            canvas.active = Some(Stroke::start(point, canvas.style.clone()));
+                            canvas.active_group.repaint();
+                        }
+                    }
+                })
+                .and_on("mouse_move", |canvas: &mut Self, _, event| {
+                    if let WindowEvent::MouseMove(point) = event {
+                        canvas.extend_stroke(point);
+                    }
+                })
+                .and_on("mouse_release", |canvas: &mut Self, _, event| {
+                    if let WindowEvent::MouseRelease(point, MouseButton::Left) = event {
+                        canvas.extend_stroke(point);
+                        canvas.finish_stroke();
+                    }
+                })
+                .and_on("key_press", |canvas: &mut Self, _, event| {
+                    if let WindowEvent::KeyPress(Key::Z, modifiers) = event {
+                        if modifiers.ctrl || modifiers.logo {
+                            canvas.undo();
+                        }
+                    }
+                }),
+        );
+
+        let style = GraphicsDisplayStroke {
+            color: theme.text.into(),
+            thickness: 2.0,
+            cap: LineCap::Round,
+            join: LineJoin::Round,
+            ..Default::default()
+        };
+
+        CanvasWidget {
+            event: RcEventQueue::new(),
+            strokes: Vec::new(),
+            active: None,
+            style,
+            bounds,
+            theme,
+            history_group: CommandGroup::with_label("canvas"),
+            active_group: CommandGroup::with_label("canvas_stroke"),
+            graph: Some(graph),
+            draw_error: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the finished strokes, oldest first.
+    pub fn strokes(&self) -> &[Stroke] {
+        &self.strokes
+    }
+
+    /// Returns the stroke being drawn, if any.
+    pub fn active_stroke(&self) -> Option<&Stroke> {
+        self.active.as_ref()
+    }
+
+    /// Returns the style of new strokes.
+    pub fn stroke_style(&self) -> &GraphicsDisplayStroke {
+        &self.style
+    }
+
+    /// Changes the style of new strokes; existing strokes (including the active one) are left as they are.
+    pub fn set_stroke_style(&mut self, style: GraphicsDisplayStroke) {
+        self.style = style;
+    }
+
+    /// Adds a finished stroke, e.g. one loaded from a file. This doesn't emit an event.
+    pub fn push_stroke(&mut self, stroke: Stroke) {
+        self.strokes.push(stroke);
+        self.history_group.repaint();
+    }
+
+    /// Removes the stroke being drawn or, if there is none, the last finished stroke, returning it.
+    ///
+    /// Emits [`Undo`](CanvasEvent::Undo) if a finished stroke was removed.
+    pub fn undo(&mut self) -> Option<Stroke> {
+        if let Some(active) = self.active.take() {
+            self.active_group.repaint();
+            return Some(active);
+        }
+
+        let stroke = self.strokes.pop()?;
+        self.history_group.repaint();
+        self.event.emit_owned(CanvasEvent::Undo(self.strokes.len()));
+        Some(stroke)
+    }
+
+    /// Removes every stroke. This doesn't emit an event.
+    pub fn clear(&mut self) {
+        self.strokes.clear();
+        self.active = None;
+        CommandGroup::repaint_all([&mut self.history_group, &mut self.active_group]);
+    }
+
+    /// Moves the canvas. Strokes keep their window coordinates.
+    pub fn set_bounds(&mut self, bounds: Rect) {
+        self.bounds = bounds;
+        CommandGroup::repaint_all([&mut self.history_group, &mut self.active_group]);
+    }
+
+    /// Takes the error which occurred the last time the canvas failed to push its commands to the display, if any.
+    ///
+    /// A failed push leaves the canvas marked for repaint, so it is drawn again on the next call to `draw`.
+    pub fn take_draw_error(&mut self) -> Option<DisplayError> {
+        self.draw_error.take()
+    }
+
+    fn extend_stroke(&mut self, point: Point) {
+        if let Some(active) = &mut self.active {
+            if active.last_point().is_none_or(|last| (point - last).length() >= MIN_POINT_DISTANCE)
+            {
+                active.path.push(VectorPathEvent::LineTo { to: point });
+                self.active_group.repaint();
+            }
+        }
+    }
+
+    fn finish_stroke(&mut self) {
+        if let Some(active) = self.active.take() {
+            self.strokes.push(active);
+            CommandGroup::repaint_all([&mut self.history_group, &mut self.active_group]);
+            self.event.emit_owned(CanvasEvent::Stroke(self.strokes.len()));
+        }
+    }
+}
+
+impl<U: 'static, G: 'static> Styled for CanvasWidget<U, G> {
+    fn style_class(&self) -> &str {
+        "canvas"
+    }
+}
+
+impl<U: 'static, G: 'static> Widget for CanvasWidget<U, G> {
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        verbgraph::update_all(self, aux);
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let (bounds, strokes, active) = (self.bounds, &self.strokes, &self.active);
+        let style = self.theme.styles.resolve_for(self);
+
+        if let Err(err) = self.history_group.push_with(
+            display,
+            || {
+                let mut builder = DisplayListBuilder::new();
+                builder.push_rectangle(bounds, style.background_paint(), vec![]);
+                builder.push_rectangle_clip(bounds, true);
+                for stroke in strokes {
+                    stroke.push(&mut builder);
+                }
+                builder.build()
+            },
+            ZOrder::default(),
+            None,
+            None,
+        ) {
+            self.draw_error = Some(err);
+        }
+
+        if let Err(err) = self.active_group.push_with(
+            display,
+            || {
+                let mut builder = DisplayListBuilder::new();
+                if let Some(active) = active {
+                    builder.push_rectangle_clip(bounds, true);
+                    active.push(&mut builder);
+                }
+                builder.build()
+            },
+            ZOrder::default(),
+            None,
+            None,
+        ) {
+            self.draw_error = Some(err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            display::{DisplayItem, GraphicsDisplayItem, Size},
+            input::Modifiers,
+            testing::Harness,
+            widgets::{test_theme, FailingDisplay},
+        },
+    };
+
+    #[test]
+    fn test_canvas() {
+        let bounds = Rect::new(Point::new(10.0, 10.0), Size::new(200.0, 100.0));
+        let mut harness: Harness<CanvasWidget> =
+            Harness::new(|window_q, _| CanvasWidget::new(bounds, test_theme(), window_q), (), ());
+        let listener = harness.root().event.listen();
+
+        harness.move_mouse(Point::new(20.0, 20.0));
+        harness.emit(WindowEvent::MousePress(Point::new(20.0, 20.0), MouseButton::Left));
+        harness.render_to_buffer();
+
+        // adding points only repaints the active stroke.
+        harness.move_mouse(Point::new(30.0, 30.0));
+        harness.move_mouse(Point::new(30.2, 30.0));
+        assert!(!harness.root().history_group.will_repaint());
+        assert!(harness.root().active_group.will_repaint());
+        assert_eq!(harness.root().active_stroke().unwrap().path.len(), 3);
+        harness.render_to_buffer();
+
+        harness.emit(WindowEvent::MouseRelease(Point::new(40.0, 30.0), MouseButton::Left));
+        assert_eq!(harness.root().strokes().len(), 1);
+        assert_eq!(harness.root().strokes()[0].path.len(), 4);
+        assert!(harness.root().active_stroke().is_none());
+
+        // presses outside the canvas don't start a stroke.
+        harness.click(Point::new(300.0, 20.0));
+        harness.click(Point::new(50.0, 50.0));
+        assert_eq!(listener.peek(), &[CanvasEvent::Stroke(1), CanvasEvent::Stroke(2)]);
+        assert_eq!(harness.display().command_group_count(), 2);
+        let paths = harness
+            .render_to_buffer()
+            .iter()
+            .filter(|command| {
+                matches!(
+                    command,
+                    DisplayCommand::Item(
+                        DisplayItem::Graphics(GraphicsDisplayItem::Path { .. }),
+                        _
+                    )
+                )
+            })
+            .count();
+        assert_eq!(paths, 2);
+
+        harness.press_key(Key::Z, Modifiers { ctrl: true, ..Default::default() });
+        assert_eq!(harness.root().strokes().len(), 1);
+        assert_eq!(listener.peek(), &[CanvasEvent::Undo(1)]);
+
+        harness.root_mut().clear();
+        assert!(harness.root_mut().undo().is_none());
+        assert!(listener.peek().is_empty());
+    }
+
+    #[test]
+    fn test_draw_error() {
+        let bounds = Rect::new(Point::new(10.0, 10.0), Size::new(200.0, 100.0));
+        let mut canvas: CanvasWidget =
+            CanvasWidget::new(bounds, test_theme(), &RcEventQueue::new());
+
+        canvas.draw(&mut FailingDisplay, &mut ());
+        assert!(canvas.take_draw_error().is_some());
+        assert!(canvas.history_group.will_repaint());
+        assert!(canvas.active_group.will_repaint());
+    }
+}
@@ -0,0 +1,250 @@
+//! A checkbox with a text label.
+
+use {
+    super::Theme,
+    crate as reclutch,
+    crate::{
+        display::{
+            CommandGroup, DisplayCommand, DisplayListBuilder, GraphicsDisplay,
+            GraphicsDisplayPaint, GraphicsDisplayStroke, Point, Rect, Size, VectorPathBuilder,
+            ZOrder,
+        },
+        error::DisplayError,
+        event::RcEventQueue,
+        input::{MouseButton, WindowEvent},
+        prelude::*,
+        style::StyleState,
+        verbgraph::{self, OptionVerbGraph, QueueHandler, VerbGraph},
+        Event, HasVerbGraph, WidgetChildren, WidgetState,
+    },
+    std::marker::PhantomData,
+};
+
+/// Side length of the box.
+const BOX_SIZE: f32 = 16.0;
+/// Space between the box and the label.
+const LABEL_SPACING: f32 = 6.0;
+
+/// Events emitted by a [`Checkbox`](Checkbox).
+#[derive(Event, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckboxEvent {
+    /// The checkbox was clicked, changing its checked state to the given value.
+    #[event_key(toggle)]
+    Toggle(bool),
+}
+
+/// A box which is toggled between checked and unchecked by clicking it or its label.
+#[derive(WidgetChildren, HasVerbGraph, WidgetState)]
+pub struct Checkbox<U: 'static = (), G: 'static = ()> {
+    pub event: RcEventQueue<CheckboxEvent>,
+
+    #[widget_state]
+    text: String,
+    #[widget_state]
+    position: Point,
+    #[widget_state]
+    checked: bool,
+
+    theme: Theme,
+    hovered: bool,
+    pressed: bool,
+    #[command_group]
+    command_group: CommandGroup,
+    #[verb_graph]
+    graph: OptionVerbGraph<Self, U>,
+    draw_error: Option<DisplayError>,
+    phantom: PhantomData<G>,
+}
+
+impl<U: 'static, G: 'static> Checkbox<U, G> {
+    /// Creates an unchecked checkbox with its top-left corner at `position`,
+    /// handling input from the global window event queue.
+    pub fn new(
+        text: impl Into<String>,
+        position: Point,
+        theme: Theme,
+        window_q: &RcEventQueue<WindowEvent>,
+    ) -> Self {
+        let graph = VerbGraph::new().and_add(
+            "window",
+            QueueHandler::new(window_q)
+                .and_on("mouse_press", |checkbox: &mut Self, _, event| {
+                    if let WindowEvent::MousePress(point, MouseButton::Left) = event {
+                        checkbox.pressed = checkbox.bounds().contains(point);
+                    }
+                })
+                .and_on("mouse_release", |checkbox: &mut Self, _, event| {
+                    if let WindowEvent::MouseRelease(point, MouseButton::Left) = event {
+                        if std::mem::replace(&mut checkbox.pressed, false)
+                            && checkbox.bounds().contains(point)
+                        {
+                            let checked = !checkbox.checked;
+                            checkbox.set_checked(checked);
+                            checkbox.event.emit_owned(CheckboxEvent::Toggle(checked));
+                        }
+                    }
+                })
+                .and_on("mouse_move", |checkbox: &mut Self, _, event| {
+                    if let WindowEvent::MouseMove(point) = event {
+                        let hovered = checkbox.bounds().contains(point);
+                        if checkbox.hovered != hovered {
+                            checkbox.hovered = hovered;
+                            checkbox.command_group.repaint();
+                        }
+                    }
+                }),
+        );
+
+        Checkbox {
+            event: RcEventQueue::new(),
+            text: text.into(),
+            position,
+            checked: false,
+            theme,
+            hovered: false,
+            pressed: false,
+            command_group: CommandGroup::with_label("checkbox"),
+            graph: Some(graph),
+            draw_error: None,
+            phantom: PhantomData,
+        }
+    }
+
+    fn box_bounds(&self) -> Rect {
+        Rect::new(self.position, Size::new(BOX_SIZE, BOX_SIZE))
+    }
+
+    fn label_position(&self) -> Point {
+        let text_bounds = self.theme.text_bounds(&self.text, Point::zero());
+        Point::new(
+            self.position.x + BOX_SIZE + LABEL_SPACING,
+            self.position.y + (BOX_SIZE - text_bounds.size.height) / 2.0,
+        )
+    }
+
+    /// Takes the error which occurred the last time the checkbox failed to push its commands to the display, if any.
+    ///
+    /// A failed push leaves the checkbox marked for repaint, so it is drawn again on the next call to `draw`.
+    pub fn take_draw_error(&mut self) -> Option<DisplayError> {
+        self.draw_error.take()
+    }
+}
+
+impl<U: 'static, G: 'static> Styled for Checkbox<U, G> {
+    fn style_class(&self) -> &str {
+        "checkbox"
+    }
+
+    fn style_state(&self) -> StyleState {
+        StyleState { hovered: self.hovered, pressed: self.pressed, ..Default::default() }
+    }
+}
+
+#[cfg(feature = "state")]
+impl<U: 'static, G: 'static> crate::state::PersistentState for Checkbox<U, G> {
+    fn save_state(&self) -> Option<crate::serde_json::Value> {
+        Some(crate::serde_json::json!({ "checked": self.checked }))
+    }
+
+    fn restore_state(&mut self, state: &crate::serde_json::Value) {
+        if let Some(checked) = state.get("checked").and_then(|checked| checked.as_bool()) {
+            self.set_checked(checked);
+        }
+    }
+}
+
+impl<U: 'static, G: 'static> Widget for Checkbox<U, G> {
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    fn bounds(&self) -> Rect {
+        self.box_bounds().union(&self.theme.text_bounds(&self.text, self.label_position()))
+    }
+
+    #[cfg(feature = "state")]
+    fn persistent_state(&self) -> Option<&dyn crate::state::PersistentState> {
+        Some(self)
+    }
+
+    #[cfg(feature = "state")]
+    fn persistent_state_mut(&mut self) -> Option<&mut dyn crate::state::PersistentState> {
+        Some(self)
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        verbgraph::update_all(self, aux);
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let (box_bounds, label_position) = (self.box_bounds(), self.label_position());
+        let style = self.theme.styles.resolve_for(self);
+        let (theme, text, checked) = (&self.theme, &self.text, self.checked);
+
+        if let Err(err) = self.command_group.push_with(
+            display,
+            || {
+                let mut builder = DisplayListBuilder::new();
+                let radii = [style.corner_radius; 4];
+                builder.push_round_rectangle(box_bounds, radii, style.background_paint(), vec![]);
+                builder.push_round_rectangle(box_bounds, radii, style.border_paint(), vec![]);
+                if checked {
+                    let mut check = VectorPathBuilder::new();
+                    let (origin, size) = (box_bounds.origin, box_bounds.size.width);
+                    check.move_to(Point::new(origin.x + size * 0.2, origin.y + size * 0.5));
+                    check.line_to(Point::new(origin.x + size * 0.4, origin.y + size * 0.75));
+                    check.line_to(Point::new(origin.x + size * 0.8, origin.y + size * 0.25));
+                    builder.push_path(
+                        check.build(),
+                        false,
+                        GraphicsDisplayPaint::Stroke(GraphicsDisplayStroke {
+                            color: style.accent.into(),
+                            thickness: 2.0,
+                            ..Default::default()
+                        }),
+                        vec![],
+                    );
+                }
+                builder.push_text(theme.text_item(text, label_position, style.foreground), vec![]);
+                builder.build()
+            },
+            ZOrder::default(),
+            None,
+            None,
+        ) {
+            self.draw_error = Some(err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{display::Vector, testing::Harness, widgets::test_theme},
+    };
+
+    #[test]
+    fn test_checkbox() {
+        let mut harness: Harness<Checkbox> = Harness::new(
+            |window_q, _| Checkbox::new("Enabled", Point::new(10.0, 10.0), test_theme(), window_q),
+            (),
+            (),
+        );
+        let listener = harness.root().event.listen();
+        assert_eq!(harness.render_to_buffer().len(), 3);
+
+        // the label is part of the checkbox.
+        let label = harness.root().label_position() + Vector::new(2.0, 2.0);
+        harness.click(label);
+        assert!(*harness.root().checked());
+        harness.click(Point::new(15.0, 15.0));
+        harness.click(Point::new(500.0, 15.0));
+        assert!(!*harness.root().checked());
+        assert_eq!(listener.peek(), &[CheckboxEvent::Toggle(true), CheckboxEvent::Toggle(false)]);
+
+        harness.root_mut().set_checked(true);
+        assert!(listener.peek().is_empty());
+        assert_eq!(harness.render_to_buffer().len(), 4);
+    }
+}
@@ -0,0 +1,616 @@
+//! Widget trees built from a declarative JSON description, which can be reloaded while the application runs.
+//!
+//! A description lists the reference widgets along with their properties, and binds their events to named actions:
+//!
+//! ```json
+//! {
+//!     "widgets": [
+//!         { "type": "label", "text": "Volume", "position": [10, 10] },
+//!         { "type": "slider", "id": "volume", "range": [0, 100], "bounds": [10, 30, 200, 20],
+//!           "on": { "change": "set_volume" } },
+//!         { "type": "group", "position": [10, 60], "children": [
+//!             { "type": "checkbox", "id": "mute", "text": "Mute", "position": [0, 0], "on": { "toggle": "mute" } },
+//!             { "type": "button", "text": "Apply", "bounds": [0, 30, 80, 24], "on": { "click": "apply" } }
+//!         ] }
+//!     ]
+//! }
+//! ```
+//!
+//! A [`DescribedUi`](DescribedUi) constructs the widgets and emits an [`Action`](Action) into its `event` queue
+//! whenever a bound event occurs. Keys of `on` are the event keys of the widget (e.g. `"click"` for
+//! [`ButtonEvent::Click`](super::button::ButtonEvent::Click)). Groups offset their children by their `position`.
+//!
+//! When loaded with [`DescribedUi::load`](DescribedUi::load), the file is checked for changes while updating and
+//! the widgets are rebuilt from it, so that layouts can be tweaked without recompiling. Widgets with an `id`
+//! keep their text, checked state or value across reloads.
+
+use {
+    super::{
+        button::ButtonEvent, checkbox::CheckboxEvent, slider::SliderEvent, text_box::TextBoxEvent,
+        Button, Checkbox, Label, Slider, TextBox, Theme,
+    },
+    crate::{
+        display::{DisplayCommand, GraphicsDisplay, Point, Rect, Size, Vector},
+        event::{RcEventListener, RcEventQueue},
+        input::WindowEvent,
+        prelude::*,
+        verbgraph::Event,
+        widget::{self, ChildrenList, ChildrenListMut},
+    },
+    serde::{Deserialize, Serialize},
+    std::{
+        collections::{BTreeMap, HashMap},
+        path::{Path, PathBuf},
+        rc::Rc,
+        time::{Duration, Instant, SystemTime},
+    },
+};
+
+/// How often the description file is checked for changes by default.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Errors of loading a description.
+#[derive(Debug, thiserror::Error)]
+pub enum DescriptionError {
+    #[error("{0}")]
+    IoError(#[from] std::io::Error),
+    #[error("{0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("{widget} has no \"{event}\" event")]
+    UnknownEvent { widget: &'static str, event: String },
+}
+
+/// Root of a description.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UiDescription {
+    pub widgets: Vec<WidgetDescription>,
+}
+
+/// Maps event keys of a widget to action names.
+pub type Bindings = BTreeMap<String, String>;
+
+/// A described widget. Positions and bounds (`[x, y]` and `[x, y, width, height]`) are relative to the parent group.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WidgetDescription {
+    Label {
+        #[serde(default)]
+        id: Option<String>,
+        text: String,
+        position: [f32; 2],
+        #[serde(default)]
+        max_width: Option<f32>,
+    },
+    Button {
+        #[serde(default)]
+        id: Option<String>,
+        text: String,
+        bounds: [f32; 4],
+        #[serde(default)]
+        disabled: bool,
+        #[serde(default)]
+        on: Bindings,
+    },
+    Checkbox {
+        #[serde(default)]
+        id: Option<String>,
+        text: String,
+        position: [f32; 2],
+        #[serde(default)]
+        checked: bool,
+        #[serde(default)]
+        on: Bindings,
+    },
+    Slider {
+        #[serde(default)]
+        id: Option<String>,
+        range: [f32; 2],
+        bounds: [f32; 4],
+        /// Defaults to the start of the range.
+        #[serde(default)]
+        value: Option<f32>,
+        #[serde(default)]
+        on: Bindings,
+    },
+    TextBox {
+        #[serde(default)]
+        id: Option<String>,
+        bounds: [f32; 4],
+        #[serde(default)]
+        placeholder: String,
+        #[serde(default)]
+        text: String,
+        #[serde(default)]
+        on: Bindings,
+    },
+    /// Offsets its children by `position`.
+    Group {
+        #[serde(default)]
+        position: [f32; 2],
+        children: Vec<WidgetDescription>,
+    },
+}
+
+/// Payload of an [`Action`](Action), taken from the event which triggered it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActionValue {
+    None,
+    /// The new checked state, or whether a text box gained focus.
+    Bool(bool),
+    /// The new value of a slider.
+    Number(f32),
+    /// The text of a text box.
+    Text(String),
+}
+
+/// Emitted by a [`DescribedUi`](DescribedUi) when an event bound to an action occurs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Action {
+    /// Name of the action, as given in the description.
+    pub name: String,
+    /// `id` of the widget which emitted the event, if it has one.
+    pub id: Option<String>,
+    pub value: ActionValue,
+}
+
+enum NodeWidget<U: 'static, G: 'static> {
+    Label(Label<U, G>),
+    Button(Button<U, G>, RcEventListener<ButtonEvent>),
+    Checkbox(Checkbox<U, G>, RcEventListener<CheckboxEvent>),
+    Slider(Slider<U, G>, RcEventListener<SliderEvent>),
+    TextBox(TextBox<U, G>, RcEventListener<TextBoxEvent>),
+}
+
+struct Node<U: 'static, G: 'static> {
+    id: Option<String>,
+    on: Bindings,
+    widget: NodeWidget<U, G>,
+}
+
+impl<U: 'static, G: 'static> Node<U, G> {
+    fn widget(
+        &self,
+    ) -> &dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = DisplayCommand> {
+        match &self.widget {
+            NodeWidget::Label(label) => label,
+            NodeWidget::Button(button, _) => button,
+            NodeWidget::Checkbox(checkbox, _) => checkbox,
+            NodeWidget::Slider(slider, _) => slider,
+            NodeWidget::TextBox(text_box, _) => text_box,
+        }
+    }
+
+    fn widget_mut(
+        &mut self,
+    ) -> &mut dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = DisplayCommand>
+    {
+        match &mut self.widget {
+            NodeWidget::Label(label) => label,
+            NodeWidget::Button(button, _) => button,
+            NodeWidget::Checkbox(checkbox, _) => checkbox,
+            NodeWidget::Slider(slider, _) => slider,
+            NodeWidget::TextBox(text_box, _) => text_box,
+        }
+    }
+
+    /// Returns the actions bound to the events emitted since the last call.
+    fn actions(&self) -> Vec<Action> {
+        fn bound<E: Event>(
+            node_on: &Bindings,
+            id: &Option<String>,
+            events: Vec<E>,
+            value: impl Fn(E) -> ActionValue,
+        ) -> Vec<Action> {
+            events
+                .into_iter()
+                .filter_map(|event| {
+                    let name = node_on.get(event.get_key())?.clone();
+                    Some(Action { name, id: id.clone(), value: value(event) })
+                })
+                .collect()
+        }
+
+        match &self.widget {
+            NodeWidget::Label(_) => Vec::new(),
+            NodeWidget::Button(_, listener) => {
+                bound(&self.on, &self.id, listener.peek(), |_| ActionValue::None)
+            }
+            NodeWidget::Checkbox(_, listener) => {
+                bound(&self.on, &self.id, listener.peek(), |CheckboxEvent::Toggle(checked)| {
+                    ActionValue::Bool(checked)
+                })
+            }
+            NodeWidget::Slider(_, listener) => {
+                bound(&self.on, &self.id, listener.peek(), |SliderEvent::Change(value)| {
+                    ActionValue::Number(value)
+                })
+            }
+            NodeWidget::TextBox(_, listener) => {
+                bound(&self.on, &self.id, listener.peek(), |event| match event {
+                    TextBoxEvent::Change(text) | TextBoxEvent::Submit(text) => {
+                        ActionValue::Text(text)
+                    }
+                    TextBoxEvent::Focus(focused) => ActionValue::Bool(focused),
+                })
+            }
+        }
+    }
+}
+
+/// Live state of a widget kept across reloads.
+enum KeptState {
+    Checked(bool),
+    Value(f32),
+    Text(String),
+}
+
+fn check_bindings<E: Event>(widget: &'static str, on: &Bindings) -> Result<(), DescriptionError> {
+    match on.keys().find(|key| !E::KEYS.contains(&key.as_str())) {
+        Some(event) => Err(DescriptionError::UnknownEvent { widget, event: event.clone() }),
+        None => Ok(()),
+    }
+}
+
+fn point(offset: Vector, [x, y]: [f32; 2]) -> Point {
+    Point::new(x, y) + offset
+}
+
+fn rect(offset: Vector, [x, y, width, height]: [f32; 4]) -> Rect {
+    Rect::new(Point::new(x, y) + offset, Size::new(width, height))
+}
+
+/// A widget tree built from a [`UiDescription`](UiDescription), see the [module documentation](self).
+pub struct DescribedUi<U: 'static = (), G: 'static = ()> {
+    /// Actions triggered by the widgets.
+    pub event: RcEventQueue<Action>,
+
+    nodes: Vec<Node<U, G>>,
+    theme: Theme,
+    window_q: RcEventQueue<WindowEvent>,
+    path: Option<PathBuf>,
+    modified: Option<SystemTime>,
+    poll_interval: Option<Duration>,
+    last_poll: Option<Instant>,
+    reload_error: Option<DescriptionError>,
+}
+
+impl<U: 'static, G: 'static> DescribedUi<U, G> {
+    /// Builds the widgets of `description`, handling input from the global window event queue.
+    pub fn new(
+        description: &UiDescription,
+        theme: Theme,
+        window_q: &RcEventQueue<WindowEvent>,
+    ) -> Result<Self, DescriptionError> {
+        let mut ui = DescribedUi {
+            event: RcEventQueue::new(),
+            nodes: Vec::new(),
+            theme,
+            window_q: RcEventQueue(Rc::clone(&window_q.0)),
+            path: None,
+            modified: None,
+            poll_interval: None,
+            last_poll: None,
+            reload_error: None,
+        };
+        ui.set_description(description)?;
+        Ok(ui)
+    }
+
+    /// Builds the widgets of a JSON description.
+    pub fn from_json(
+        json: &str,
+        theme: Theme,
+        window_q: &RcEventQueue<WindowEvent>,
+    ) -> Result<Self, DescriptionError> {
+        Self::new(&serde_json::from_str(json)?, theme, window_q)
+    }
+
+    /// Builds the widgets of the JSON description in the file at `path`, which is reloaded whenever it changes.
+    pub fn load(
+        path: impl Into<PathBuf>,
+        theme: Theme,
+        window_q: &RcEventQueue<WindowEvent>,
+    ) -> Result<Self, DescriptionError> {
+        let path = path.into();
+        let modified = std::fs::metadata(&path)?.modified().ok();
+        let mut ui = Self::from_json(&std::fs::read_to_string(&path)?, theme, window_q)?;
+        ui.path = Some(path);
+        ui.modified = modified;
+        ui.poll_interval = Some(DEFAULT_POLL_INTERVAL);
+        Ok(ui)
+    }
+
+    /// Returns the file the description was loaded from, if any.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Sets how often [`update`](Widget::update) checks the file for changes (every 500ms by default),
+    /// or disables checking if `None`.
+    pub fn set_poll_interval(&mut self, interval: Option<Duration>) {
+        self.poll_interval = interval;
+    }
+
+    /// Returns the error of the last reload made while updating, if it failed.
+    ///
+    /// The previous widgets are kept until the description is fixed.
+    pub fn reload_error(&self) -> Option<&DescriptionError> {
+        self.reload_error.as_ref()
+    }
+
+    /// Rebuilds the widgets from the file if it changed since it was last read, returning `true` if so.
+    pub fn reload_if_changed(&mut self) -> Result<bool, DescriptionError> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(false),
+        };
+        let modified = std::fs::metadata(path)?.modified().ok();
+        if modified.is_some() && modified == self.modified {
+            return Ok(false);
+        }
+
+        let description: UiDescription = serde_json::from_str(&std::fs::read_to_string(path)?)?;
+        self.set_description(&description)?;
+        self.modified = modified;
+        Ok(true)
+    }
+
+    /// Rebuilds the widgets from `description`.
+    ///
+    /// Widgets with an `id` which was already present (for a widget of the same type) keep their current text,
+    /// checked state or value. If the description is invalid, the widgets are left untouched.
+    pub fn set_description(&mut self, description: &UiDescription) -> Result<(), DescriptionError> {
+        let kept: HashMap<String, KeptState> = self
+            .nodes
+            .iter()
+            .filter_map(|node| {
+                let state = match &node.widget {
+                    NodeWidget::Label(_) | NodeWidget::Button(..) => return None,
+                    NodeWidget::Checkbox(checkbox, _) => KeptState::Checked(*checkbox.checked()),
+                    NodeWidget::Slider(slider, _) => KeptState::Value(slider.value()),
+                    NodeWidget::TextBox(text_box, _) => KeptState::Text(text_box.text().clone()),
+                };
+                Some((node.id.clone()?, state))
+            })
+            .collect();
+
+        let mut nodes = Vec::new();
+        self.build(&description.widgets, Vector::zero(), &kept, &mut nodes)?;
+        self.nodes = nodes;
+        Ok(())
+    }
+
+    fn build(
+        &self,
+        descriptions: &[WidgetDescription],
+        offset: Vector,
+        kept: &HashMap<String, KeptState>,
+        nodes: &mut Vec<Node<U, G>>,
+    ) -> Result<(), DescriptionError> {
+        let theme = || self.theme.clone();
+        let window_q = &self.window_q;
+        let kept_state = |id: &Option<String>| id.as_ref().and_then(|id| kept.get(id));
+
+        for description in descriptions {
+            let (id, on, widget) = match description {
+                WidgetDescription::Label { id, text, position, max_width } => {
+                    let mut label = Label::new(text.clone(), point(offset, *position), theme());
+                    label.set_max_width(*max_width);
+                    (id, Bindings::new(), NodeWidget::Label(label))
+                }
+                WidgetDescription::Button { id, text, bounds, disabled, on } => {
+                    check_bindings::<ButtonEvent>("button", on)?;
+                    let mut button =
+                        Button::new(text.clone(), rect(offset, *bounds), theme(), window_q);
+                    button.set_disabled(*disabled);
+                    let listener = button.event.listen();
+                    (id, on.clone(), NodeWidget::Button(button, listener))
+                }
+                WidgetDescription::Checkbox { id, text, position, checked, on } => {
+                    check_bindings::<CheckboxEvent>("checkbox", on)?;
+                    let mut checkbox =
+                        Checkbox::new(text.clone(), point(offset, *position), theme(), window_q);
+                    checkbox.set_checked(match kept_state(id) {
+                        Some(KeptState::Checked(checked)) => *checked,
+                        _ => *checked,
+                    });
+                    let listener = checkbox.event.listen();
+                    (id, on.clone(), NodeWidget::Checkbox(checkbox, listener))
+                }
+                WidgetDescription::Slider { id, range, bounds, value, on } => {
+                    check_bindings::<SliderEvent>("slider", on)?;
+                    let mut slider =
+                        Slider::new(range[0]..=range[1], rect(offset, *bounds), theme(), window_q);
+                    match (kept_state(id), value) {
+                        (Some(KeptState::Value(value)), _) | (_, Some(value)) => {
+                            slider.set_value(*value)
+                        }
+                        _ => {}
+                    }
+                    let listener = slider.event.listen();
+                    (id, on.clone(), NodeWidget::Slider(slider, listener))
+                }
+                WidgetDescription::TextBox { id, bounds, placeholder, text, on } => {
+                    check_bindings::<TextBoxEvent>("text box", on)?;
+                    let mut text_box =
+                        TextBox::new(placeholder.clone(), rect(offset, *bounds), theme(), window_q);
+                    text_box.set_text(match kept_state(id) {
+                        Some(KeptState::Text(text)) => text.clone(),
+                        _ => text.clone(),
+                    });
+                    let listener = text_box.event.listen();
+                    (id, on.clone(), NodeWidget::TextBox(text_box, listener))
+                }
+                WidgetDescription::Group { position, children } => {
+                    self.build(
+                        children,
+                        offset + Vector::new(position[0], position[1]),
+                        kept,
+                        nodes,
+                    )?;
+                    continue;
+                }
+            };
+            nodes.push(Node { id: id.clone(), on, widget });
+        }
+        Ok(())
+    }
+
+    /// Returns the widget with a given `id`.
+    pub fn get(
+        &self,
+        id: &str,
+    ) -> Option<&dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = DisplayCommand>>
+    {
+        self.nodes.iter().find(|node| node.id.as_deref() == Some(id)).map(Node::widget)
+    }
+
+    /// Returns the number of widgets, excluding groups.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+impl<U: 'static, G: 'static> Widget for DescribedUi<U, G> {
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    fn bounds(&self) -> Rect {
+        self.nodes
+            .iter()
+            .map(|node| node.widget().bounds())
+            .fold(None, |bounds: Option<Rect>, rect| {
+                Some(bounds.map_or(rect, |bounds| bounds.union(&rect)))
+            })
+            .unwrap_or_default()
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        if let (Some(interval), Some(_)) = (self.poll_interval, &self.path) {
+            let now = Instant::now();
+            if self.last_poll.is_none_or(|last| now.duration_since(last) >= interval) {
+                self.last_poll = Some(now);
+                match self.reload_if_changed() {
+                    Ok(_) => self.reload_error = None,
+                    Err(err) => self.reload_error = Some(err),
+                }
+            }
+        }
+
+        widget::propagate_update(self, aux);
+
+        for node in &self.nodes {
+            for action in node.actions() {
+                self.event.emit_owned(action);
+            }
+        }
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, aux: &mut G) {
+        widget::propagate_draw(self, display, aux);
+    }
+}
+
+impl<U: 'static, G: 'static> WidgetChildren for DescribedUi<U, G> {
+    fn children(&self) -> ChildrenList<'_, U, G, DisplayCommand> {
+        self.nodes.iter().map(Node::widget).collect()
+    }
+
+    fn children_mut(&mut self) -> ChildrenListMut<'_, U, G, DisplayCommand> {
+        self.nodes.iter_mut().map(Node::widget_mut).collect()
+    }
+
+    fn child_count(&self) -> usize {
+        self.nodes.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{testing::Harness, widgets::test_theme},
+    };
+
+    const DESCRIPTION: &str = r#"{
+        "widgets": [
+            { "type": "label", "text": "Settings", "position": [10, 10] },
+            { "type": "group", "position": [10, 40], "children": [
+                { "type": "checkbox", "id": "mute", "text": "Mute", "position": [0, 0],
+                  "on": { "toggle": "mute" } },
+                { "type": "button", "id": "apply", "text": "Apply", "bounds": [0, 30, 80, 24],
+                  "on": { "click": "apply" } }
+            ] }
+        ]
+    }"#;
+
+    #[test]
+    fn test_described_ui() {
+        let mut harness: Harness<DescribedUi> = Harness::new(
+            |window_q, _| DescribedUi::from_json(DESCRIPTION, test_theme(), window_q).unwrap(),
+            (),
+            (),
+        );
+        let listener = harness.root().event.listen();
+        assert_eq!(harness.root().len(), 3);
+        assert_eq!(harness.root().child_count(), 3);
+        assert_eq!(harness.root().get("apply").unwrap().bounds().origin, Point::new(10.0, 70.0));
+
+        harness.click(Point::new(20.0, 80.0));
+        harness.click(Point::new(15.0, 45.0));
+        assert_eq!(
+            listener.peek(),
+            &[
+                Action { name: "apply".into(), id: Some("apply".into()), value: ActionValue::None },
+                Action {
+                    name: "mute".into(),
+                    id: Some("mute".into()),
+                    value: ActionValue::Bool(true)
+                },
+            ]
+        );
+        assert!(!harness.render_to_buffer().is_empty());
+
+        // moving the checkbox keeps its state.
+        let y = harness.root().get("mute").unwrap().bounds().origin.y;
+        let moved = DESCRIPTION.replace(r#""position": [10, 40]"#, r#""position": [10, 100]"#);
+        harness.root_mut().set_description(&serde_json::from_str(&moved).unwrap()).unwrap();
+        assert_eq!(harness.root().get("mute").unwrap().bounds().origin.y, y + 60.0);
+        harness.click(Point::new(15.0, 105.0));
+        assert_eq!(listener.peek()[0].value, ActionValue::Bool(false));
+
+        let invalid = DESCRIPTION.replace(r#""click""#, r#""toggle""#);
+        assert!(matches!(
+            harness.root_mut().set_description(&serde_json::from_str(&invalid).unwrap()),
+            Err(DescriptionError::UnknownEvent { widget: "button", .. })
+        ));
+        assert_eq!(harness.root().len(), 3);
+    }
+
+    #[test]
+    fn test_reload() {
+        let path =
+            std::env::temp_dir().join(format!("reclutch_description_{}.json", std::process::id()));
+        std::fs::write(&path, DESCRIPTION).unwrap();
+
+        let window_q = RcEventQueue::new();
+        let mut ui: DescribedUi = DescribedUi::load(&path, test_theme(), &window_q).unwrap();
+        assert_eq!(ui.path(), Some(path.as_path()));
+        assert!(!ui.reload_if_changed().unwrap());
+
+        let file = std::fs::File::create(&path).unwrap();
+        std::io::Write::write_all(&mut &file, br#"{ "widgets": [] }"#).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(1)).unwrap();
+        assert!(ui.reload_if_changed().unwrap());
+        assert!(ui.is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+        ui.update(&mut ());
+        assert!(matches!(ui.reload_error(), Some(DescriptionError::IoError(_))));
+    }
+}
@@ -0,0 +1,151 @@
+//! A single line of static text.
+
+use {
+    super::Theme,
+    crate as reclutch,
+    crate::{
+        display::{
+            CommandGroup, DisplayCommand, DisplayListBuilder, GraphicsDisplay, Point, Rect,
+            TextOverflow, ZOrder,
+        },
+        error::DisplayError,
+        prelude::*,
+        WidgetChildren, WidgetState,
+    },
+    std::marker::PhantomData,
+};
+
+/// A single line of text, which doesn't handle any input.
+#[derive(WidgetChildren, WidgetState)]
+pub struct Label<U: 'static = (), G: 'static = ()> {
+    #[widget_state]
+    text: String,
+    #[widget_state]
+    position: Point,
+    /// Width the text is cut off at (as per `overflow`), if any.
+    #[widget_state]
+    max_width: Option<f32>,
+    #[widget_state]
+    overflow: TextOverflow,
+
+    theme: Theme,
+    #[command_group]
+    command_group: CommandGroup,
+    draw_error: Option<DisplayError>,
+    phantom: PhantomData<(U, G)>,
+}
+
+impl<U: 'static, G: 'static> Label<U, G> {
+    /// Creates a label with its top-left corner at `position`.
+    ///
+    /// The label is as wide as its text, unless given a [`max_width`](Label::set_max_width), in which case the text
+    /// is shortened with an ellipsis by default.
+    pub fn new(text: impl Into<String>, position: Point, theme: Theme) -> Self {
+        Label {
+            text: text.into(),
+            position,
+            max_width: None,
+            overflow: TextOverflow::Ellipsis,
+            theme,
+            command_group: CommandGroup::with_label("label"),
+            draw_error: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Takes the error which occurred the last time the label failed to push its commands to the display, if any.
+    ///
+    /// A failed push leaves the label marked for repaint, so it is drawn again on the next call to `draw`.
+    pub fn take_draw_error(&mut self) -> Option<DisplayError> {
+        self.draw_error.take()
+    }
+}
+
+impl<U: 'static, G: 'static> Styled for Label<U, G> {
+    fn style_class(&self) -> &str {
+        "label"
+    }
+}
+
+impl<U: 'static, G: 'static> Widget for Label<U, G> {
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    fn bounds(&self) -> Rect {
+        let mut bounds = self.theme.text_bounds(&self.text, self.position);
+        if let Some(max_width) = self.max_width {
+            bounds.size.width = bounds.size.width.min(max_width);
+        }
+        bounds
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let style = self.theme.styles.resolve_for(self);
+        let (theme, text, position) = (&self.theme, &self.text, self.position);
+        let (max_width, overflow) = (self.max_width, self.overflow);
+        if let Err(err) = self.command_group.push_with(
+            display,
+            || {
+                let mut builder = DisplayListBuilder::new();
+                let item = theme.text_item(text, position, style.foreground);
+                // text which can't be truncated (e.g. without an ellipsis glyph) is shown in full.
+                let truncated = max_width.is_some_and(|max_width| {
+                    builder.push_truncated_text(item.clone(), max_width, overflow, vec![]).is_ok()
+                });
+                if !truncated {
+                    builder.push_text(item, vec![]);
+                }
+                builder.build()
+            },
+            ZOrder::default(),
+            None,
+            None,
+        ) {
+            self.draw_error = Some(err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::display::{recording::RecordingDisplay, DisplayItem, DisplayText},
+        crate::widgets::test_theme,
+    };
+
+    #[test]
+    fn test_label() {
+        let mut label: Label = Label::new("Hello", Point::new(10.0, 10.0), test_theme());
+        let width = label.bounds().size.width;
+        assert!(width > 0.0);
+        assert_eq!(label.bounds().origin, Point::new(10.0, 10.0));
+
+        let mut display = RecordingDisplay::default();
+        label.draw(&mut display, &mut ());
+        label.set_text("Hello, world".into());
+        assert!(label.bounds().size.width > width);
+        label.draw(&mut display, &mut ());
+        display.present(None).unwrap();
+
+        match display.presented() {
+            [DisplayCommand::Item(DisplayItem::Text(item), filters)] if filters.is_empty() => {
+                assert_eq!(item.text, "Hello, world".into());
+            }
+            _ => panic!("expected a single text item"),
+        }
+
+        label.set_max_width(Some(40.0));
+        assert_eq!(label.bounds().size.width, 40.0);
+        label.draw(&mut display, &mut ());
+        display.present(None).unwrap();
+        match display.presented() {
+            [DisplayCommand::Item(DisplayItem::Text(item), _)] => match &item.text {
+                DisplayText::Simple(text) => assert!(text.ends_with('…')),
+                DisplayText::Shaped(_) => panic!("expected simple text"),
+            },
+            _ => panic!("expected a single text item"),
+        }
+    }
+}
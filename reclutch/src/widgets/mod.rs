@@ -0,0 +1,215 @@
+//! Reusable widgets built on top of the core widget system.
+//!
+//! With the `widgets` feature, this also contains a handful of reference widgets ([`Button`](button::Button),
+//! [`Label`](label::Label), [`Checkbox`](checkbox::Checkbox), [`Slider`](slider::Slider),
+//! [`TextBox`](text_box::TextBox) and [`CanvasWidget`](canvas::CanvasWidget)). Each of them draws into a
+//! [`CommandGroup`](crate::display::CommandGroup) (the canvas keeps the stroke being drawn in a second one),
+//! handles the global [`WindowEvent`](crate::input::WindowEvent) queue through a verb graph and emits its own events
+//! into a public `event` queue. Bounds are in window coordinates. Errors from pushing to the display don't panic;
+//! the widget keeps the latest one until it's taken with `take_draw_error`.
+//!
+//! With the `description` feature, trees of the reference widgets can also be built from (and reloaded with) a
+//! JSON description, see [`description`](description).
+
+pub mod adapters;
+
+#[cfg(feature = "widgets")]
+pub mod button;
+#[cfg(feature = "widgets")]
+pub mod canvas;
+#[cfg(feature = "widgets")]
+pub mod checkbox;
+#[cfg(feature = "description")]
+pub mod description;
+#[cfg(feature = "widgets")]
+pub mod label;
+#[cfg(feature = "widgets")]
+pub mod slider;
+#[cfg(feature = "widgets")]
+pub mod text_box;
+
+#[cfg(feature = "widgets")]
+pub use self::{
+    button::Button, canvas::CanvasWidget, checkbox::Checkbox, label::Label, slider::Slider,
+    text_box::TextBox,
+};
+
+#[cfg(feature = "widgets")]
+use {
+    crate::{
+        display::{Color, FontInfo, Point, Rect, ResourceReference, TextDisplayItem},
+        style::{Selector, Style, StyleProperty, StyleSheet},
+    },
+    std::rc::Rc,
+};
+
+/// Fonts, colors and styles shared by the reference widgets.
+///
+/// The widgets resolve their paint parameters through [`styles`](Theme::styles), with the style classes
+/// `"button"`, `"canvas"`, `"checkbox"`, `"label"`, `"slider"` and `"text_box"`.
+#[cfg(feature = "widgets")]
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub font_info: FontInfo,
+    /// Font resource of `font_info`, created in the display the widgets are drawn into.
+    pub font: ResourceReference,
+    pub text_size: f32,
+    pub text: Color,
+    /// Color of the text of empty text boxes.
+    pub placeholder: Color,
+    pub background: Color,
+    /// Background of hovered and pressed controls.
+    pub hover: Color,
+    /// Color of checkmarks, slider tracks and text cursors.
+    pub accent: Color,
+    pub border: Color,
+    /// Style rules of the widgets, resolved while drawing.
+    pub styles: Rc<StyleSheet>,
+}
+
+#[cfg(feature = "widgets")]
+impl Theme {
+    /// Creates a light theme with a given font.
+    pub fn new(font_info: FontInfo, font: ResourceReference) -> Self {
+        let mut theme = Theme {
+            font_info,
+            font,
+            text_size: 14.0,
+            text: Color::new(0.0, 0.0, 0.0, 1.0),
+            placeholder: Color::new(0.5, 0.5, 0.5, 1.0),
+            background: Color::new(0.9, 0.9, 0.9, 1.0),
+            hover: Color::new(0.8, 0.8, 0.8, 1.0),
+            accent: Color::new(0.2, 0.4, 0.9, 1.0),
+            border: Color::new(0.4, 0.4, 0.4, 1.0),
+            styles: Default::default(),
+        };
+        theme.styles = Rc::new(theme.default_styles());
+        theme
+    }
+
+    /// Returns the default style rules of the widgets, in the colors of the theme.
+    ///
+    /// This can be used to rebuild [`styles`](Theme::styles) after changing the colors, or as a starting point
+    /// for additional rules.
+    pub fn default_styles(&self) -> StyleSheet {
+        let base = Style {
+            background: self.background,
+            foreground: self.text,
+            border: self.border,
+            accent: self.accent,
+            ..Default::default()
+        };
+
+        StyleSheet::new(base)
+            .and_rule(Selector::any().hovered(), vec![StyleProperty::Background(self.hover)])
+            .and_rule(Selector::any().pressed(), vec![StyleProperty::Background(self.hover)])
+            .and_rule(Selector::any().disabled(), vec![StyleProperty::Foreground(self.placeholder)])
+            .and_rule(Selector::class("button"), vec![StyleProperty::CornerRadius(3.0)])
+            .and_rule(Selector::class("checkbox"), vec![StyleProperty::CornerRadius(2.0)])
+            .and_rule(
+                Selector::class("text_box").focused(),
+                vec![StyleProperty::Border(self.accent)],
+            )
+    }
+
+    /// Returns a text item in the font of the theme, placed at `top_left`.
+    pub fn text_item(&self, text: &str, top_left: Point, color: Color) -> TextDisplayItem {
+        let mut item = TextDisplayItem::new(
+            text,
+            self.font,
+            self.font_info.clone(),
+            self.text_size,
+            top_left,
+            color,
+        );
+        item.set_top_left(top_left);
+        item
+    }
+
+    /// Returns the bounds of `text` placed at `top_left`.
+    pub fn text_bounds(&self, text: &str, top_left: Point) -> Rect {
+        self.text_item(text, top_left, self.text).bounds().unwrap_or_default()
+    }
+}
+
+/// Theme with the font shipped with the examples, which doesn't depend on the fonts installed on the system.
+#[cfg(all(test, feature = "widgets"))]
+pub(crate) fn test_theme() -> Theme {
+    let font_data =
+        std::sync::Arc::new(include_bytes!("../../examples/shaping/NotoSans.ttf").to_vec());
+    Theme::new(FontInfo::from_data(font_data, 0).unwrap(), ResourceReference::Font(0))
+}
+
+/// Display which refuses to push or modify command groups, for testing how widgets handle draw errors.
+#[cfg(all(test, feature = "widgets"))]
+pub(crate) struct FailingDisplay;
+
+#[cfg(all(test, feature = "widgets"))]
+impl crate::display::GraphicsDisplay for FailingDisplay {
+    fn resize(&mut self, _size: (u32, u32)) -> Result<(), crate::error::DisplayError> {
+        Ok(())
+    }
+
+    fn new_resource(
+        &mut self,
+        _descriptor: crate::display::ResourceDescriptor,
+    ) -> Result<ResourceReference, crate::error::ResourceError> {
+        Err(crate::error::ResourceError::InternalError("refused".into()))
+    }
+
+    fn replace_resource(
+        &mut self,
+        _reference: ResourceReference,
+        _descriptor: crate::display::ResourceDescriptor,
+    ) -> Result<(), crate::error::DisplayError> {
+        Err(crate::error::DisplayError::InternalError("refused".into()))
+    }
+
+    fn remove_resource(&mut self, _reference: ResourceReference) {}
+
+    fn push_command_group(
+        &mut self,
+        _commands: &[crate::display::DisplayCommand],
+        _z_order: crate::display::ZOrder,
+        _protected: Option<bool>,
+        _needs_maintain: Option<bool>,
+    ) -> Result<crate::display::CommandGroupHandle, crate::error::DisplayError> {
+        Err(crate::error::DisplayError::InternalError("refused".into()))
+    }
+
+    fn get_command_group(
+        &self,
+        _handle: crate::display::CommandGroupHandle,
+    ) -> Option<&[crate::display::DisplayCommand]> {
+        None
+    }
+
+    fn modify_command_group(
+        &mut self,
+        _handle: crate::display::CommandGroupHandle,
+        _commands: &[crate::display::DisplayCommand],
+        _z_order: crate::display::ZOrder,
+        _protected: Option<bool>,
+        _needs_maintain: Option<bool>,
+    ) -> Result<(), crate::error::DisplayError> {
+        Err(crate::error::DisplayError::InternalError("refused".into()))
+    }
+
+    fn remove_command_group(
+        &mut self,
+        _handle: crate::display::CommandGroupHandle,
+    ) -> Option<Vec<crate::display::DisplayCommand>> {
+        None
+    }
+
+    fn maintain_command_group(&mut self, _handle: crate::display::CommandGroupHandle) {}
+
+    fn before_exit(&mut self) {}
+
+    fn present(
+        &mut self,
+        _cull: Option<Rect>,
+    ) -> Result<crate::display::PresentInfo, crate::error::DisplayError> {
+        Err(crate::error::DisplayError::InternalError("refused".into()))
+    }
+}
@@ -0,0 +1,287 @@
+//! A horizontal slider to pick a number from a range.
+
+use {
+    super::Theme,
+    crate as reclutch,
+    crate::{
+        display::{
+            CommandGroup, DisplayCommand, DisplayListBuilder, GraphicsDisplay,
+            GraphicsDisplayPaint, Point, Rect, Size, Vector, ZOrder,
+        },
+        error::DisplayError,
+        event::RcEventQueue,
+        input::{MouseButton, WindowEvent},
+        prelude::*,
+        style::StyleState,
+        verbgraph::{self, OptionVerbGraph, QueueHandler, VerbGraph},
+        Event, HasVerbGraph, WidgetChildren,
+    },
+    std::{marker::PhantomData, ops::RangeInclusive},
+};
+
+/// Radius of the knob.
+const KNOB_RADIUS: f32 = 7.0;
+/// Height of the track.
+const TRACK_HEIGHT: f32 = 4.0;
+
+/// Events emitted by a [`Slider`](Slider).
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub enum SliderEvent {
+    /// The value was changed by dragging the slider.
+    #[event_key(change)]
+    Change(f32),
+}
+
+/// A horizontal track with a knob, which is dragged to change the value.
+///
+/// Pressing anywhere within the bounds jumps the knob to the cursor.
+#[derive(WidgetChildren, HasVerbGraph)]
+pub struct Slider<U: 'static = (), G: 'static = ()> {
+    pub event: RcEventQueue<SliderEvent>,
+
+    value: f32,
+    range: RangeInclusive<f32>,
+    bounds: Rect,
+
+    theme: Theme,
+    dragging: bool,
+    command_group: CommandGroup,
+    #[verb_graph]
+    graph: OptionVerbGraph<Self, U>,
+    draw_error: Option<DisplayError>,
+    phantom: PhantomData<G>,
+}
+
+impl<U: 'static, G: 'static> Slider<U, G> {
+    /// Creates a slider with a value within `range` (initially the start of the range),
+    /// handling input from the global window event queue.
+    pub fn new(
+        range: RangeInclusive<f32>,
+        bounds: Rect,
+        theme: Theme,
+        window_q: &RcEventQueue<WindowEvent>,
+    ) -> Self {
+        let graph = VerbGraph::new().and_add(
+            "window",
+            QueueHandler::new(window_q)
+                .and_on("mouse_press", |slider: &mut Self, _, event| {
+                    if let WindowEvent::MousePress(point, MouseButton::Left) = event {
+                        if slider.bounds.contains(point) {
+                            slider.dragging = true;
+                            slider.drag_to(point);
+                        }
+                    }
+                })
+                .and_on("mouse_release", |slider: &mut Self, _, event| {
+                    if let WindowEvent::MouseRelease(_, MouseButton::Left) = event {
+                        slider.dragging = false;
+                    }
+                })
+                .and_on("mouse_move", |slider: &mut Self, _, event| {
+                    if let WindowEvent::MouseMove(point) = event {
+                        if slider.dragging {
+                            slider.drag_to(point);
+                        }
+                    }
+                }),
+        );
+
+        Slider {
+            event: RcEventQueue::new(),
+            value: *range.start(),
+            range,
+            bounds,
+            theme,
+            dragging: false,
+            command_group: CommandGroup::with_label("slider"),
+            graph: Some(graph),
+            draw_error: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns the current value.
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// Changes the value, clamped to the range. This doesn't emit an event.
+    pub fn set_value(&mut self, value: f32) {
+        let value = value.max(*self.range.start()).min(*self.range.end());
+        if self.value != value {
+            self.value = value;
+            self.command_group.repaint();
+        }
+    }
+
+    /// Returns the range of the value.
+    pub fn range(&self) -> &RangeInclusive<f32> {
+        &self.range
+    }
+
+    /// Changes the range, clamping the value to it.
+    pub fn set_range(&mut self, range: RangeInclusive<f32>) {
+        self.range = range;
+        self.command_group.repaint();
+        self.set_value(self.value);
+    }
+
+    /// Moves the slider.
+    pub fn set_bounds(&mut self, bounds: Rect) {
+        self.bounds = bounds;
+        self.command_group.repaint();
+    }
+
+    /// Returns `true` while the knob is being dragged.
+    pub fn is_dragging(&self) -> bool {
+        self.dragging
+    }
+
+    /// Returns the horizontal extent of the center of the knob.
+    fn track(&self) -> (f32, f32) {
+        (self.bounds.min_x() + KNOB_RADIUS, self.bounds.max_x() - KNOB_RADIUS)
+    }
+
+    fn drag_to(&mut self, point: Point) {
+        let (start, end) = self.track();
+        let t = if end > start { ((point.x - start) / (end - start)).clamp(0.0, 1.0) } else { 0.0 };
+        let (min, max) = (*self.range.start(), *self.range.end());
+        let before = self.value;
+        self.set_value(min + (max - min) * t);
+        if self.value != before {
+            self.event.emit_owned(SliderEvent::Change(self.value));
+        }
+    }
+
+    /// Takes the error which occurred the last time the slider failed to push its commands to the display, if any.
+    ///
+    /// A failed push leaves the slider marked for repaint, so it is drawn again on the next call to `draw`.
+    pub fn take_draw_error(&mut self) -> Option<DisplayError> {
+        self.draw_error.take()
+    }
+}
+
+impl<U: 'static, G: 'static> Styled for Slider<U, G> {
+    fn style_class(&self) -> &str {
+        "slider"
+    }
+
+    fn style_state(&self) -> StyleState {
+        StyleState { pressed: self.dragging, ..Default::default() }
+    }
+}
+
+#[cfg(feature = "state")]
+impl<U: 'static, G: 'static> crate::state::PersistentState for Slider<U, G> {
+    fn save_state(&self) -> Option<crate::serde_json::Value> {
+        Some(crate::serde_json::json!({ "value": self.value }))
+    }
+
+    fn restore_state(&mut self, state: &crate::serde_json::Value) {
+        if let Some(value) = state.get("value").and_then(|value| value.as_f64()) {
+            self.set_value(value as _);
+        }
+    }
+}
+
+impl<U: 'static, G: 'static> Widget for Slider<U, G> {
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    #[cfg(feature = "state")]
+    fn persistent_state(&self) -> Option<&dyn crate::state::PersistentState> {
+        Some(self)
+    }
+
+    #[cfg(feature = "state")]
+    fn persistent_state_mut(&mut self) -> Option<&mut dyn crate::state::PersistentState> {
+        Some(self)
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        verbgraph::update_all(self, aux);
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let (start, end) = self.track();
+        let (min, max) = (*self.range.start(), *self.range.end());
+        let t = if max > min { (self.value - min) / (max - min) } else { 0.0 };
+        let center_y = self.bounds.center().y;
+        let knob = Point::new(start + (end - start) * t, center_y);
+        let style = self.theme.styles.resolve_for(self);
+
+        if let Err(err) = self.command_group.push_with(
+            display,
+            || {
+                let track = Rect::new(
+                    Point::new(start, center_y - TRACK_HEIGHT / 2.0),
+                    Size::new(end - start, TRACK_HEIGHT),
+                );
+                let filled = Rect::new(track.origin, Size::new(knob.x - start, TRACK_HEIGHT));
+
+                let mut builder = DisplayListBuilder::new();
+                let knob_radii = Vector::new(KNOB_RADIUS, KNOB_RADIUS);
+                builder.push_rectangle(track, style.background_paint(), vec![]);
+                builder.push_rectangle(
+                    filled,
+                    GraphicsDisplayPaint::Fill(style.accent.into()),
+                    vec![],
+                );
+                builder.push_ellipse(knob, knob_radii, style.background_paint(), vec![]);
+                builder.push_ellipse(knob, knob_radii, style.border_paint(), vec![]);
+                builder.build()
+            },
+            ZOrder::default(),
+            None,
+            None,
+        ) {
+            self.draw_error = Some(err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{testing::Harness, widgets::test_theme},
+    };
+
+    #[test]
+    fn test_slider() {
+        // the knob travels from x = 17 to x = 117.
+        let bounds = Rect::new(Point::new(10.0, 10.0), Size::new(114.0, 20.0));
+        let mut harness: Harness<Slider> = Harness::new(
+            |window_q, _| Slider::new(0.0..=10.0, bounds, test_theme(), window_q),
+            (),
+            (),
+        );
+        let listener = harness.root().event.listen();
+
+        harness.click(Point::new(67.0, 20.0));
+        assert_eq!(harness.root().value(), 5.0);
+
+        // dragging beyond the track clamps the value.
+        harness.emit(WindowEvent::MousePress(Point::new(67.0, 20.0), MouseButton::Left));
+        harness.move_mouse(Point::new(87.0, 100.0));
+        assert!(harness.root().is_dragging());
+        harness.move_mouse(Point::new(500.0, 100.0));
+        harness.emit(WindowEvent::MouseRelease(Point::new(500.0, 100.0), MouseButton::Left));
+        harness.move_mouse(Point::new(17.0, 20.0));
+        assert_eq!(harness.root().value(), 10.0);
+        assert_eq!(
+            listener.peek(),
+            &[SliderEvent::Change(5.0), SliderEvent::Change(7.0), SliderEvent::Change(10.0)]
+        );
+
+        harness.root_mut().set_range(0.0..=5.0);
+        assert_eq!(harness.root().value(), 5.0);
+        assert!(listener.peek().is_empty());
+        assert_eq!(harness.render_to_buffer().len(), 4);
+    }
+}
@@ -0,0 +1,435 @@
+//! A single-line text input.
+
+use {
+    super::Theme,
+    crate as reclutch,
+    crate::{
+        clipboard::{ClipboardAction, SharedClipboard},
+        display::{
+            center_vertically, Color, CommandGroup, DisplayCommand, DisplayListBuilder,
+            DisplayText, GraphicsDisplay, GraphicsDisplayPaint, Point, Rect, Size, TextDisplayItem,
+            ZOrder,
+        },
+        error::DisplayError,
+        event::RcEventQueue,
+        input::{MouseButton, WindowEvent},
+        prelude::*,
+        style::StyleState,
+        verbgraph::{self, OptionVerbGraph, QueueHandler, VerbGraph},
+        Event, HasVerbGraph, WidgetChildren, WidgetState,
+    },
+    std::marker::PhantomData,
+};
+
+/// Horizontal space between the border and the text.
+const TEXT_PADDING: f32 = 5.0;
+
+/// Events emitted by a [`TextBox`](TextBox).
+#[derive(Event, Debug, Clone, PartialEq)]
+pub enum TextBoxEvent {
+    /// The text was edited by typing; contains the new text.
+    #[event_key(change)]
+    Change(String),
+    /// Enter was pressed while focused; contains the text.
+    #[event_key(submit)]
+    Submit(String),
+    /// The text box gained (`true`) or lost (`false`) keyboard focus.
+    #[event_key(focus)]
+    Focus(bool),
+}
+
+/// A single line of editable text.
+///
+/// The text box is focused by clicking it, and unfocused by clicking anywhere else. Clicking also places the
+/// caret at the closest grapheme boundary. While focused, typed characters are inserted at the caret and
+/// backspace removes the grapheme before it.
+///
+/// Once given a [clipboard](TextBox::set_clipboard), the clipboard shortcuts copy or cut the whole text, and paste
+/// at the caret.
+#[derive(WidgetChildren, HasVerbGraph, WidgetState)]
+pub struct TextBox<U: 'static = (), G: 'static = ()> {
+    pub event: RcEventQueue<TextBoxEvent>,
+
+    #[widget_state]
+    text: String,
+    /// Text shown (in the placeholder color) while the text is empty.
+    #[widget_state]
+    placeholder: String,
+    #[widget_state]
+    bounds: Rect,
+
+    theme: Theme,
+    focused: bool,
+    /// Byte offset of the caret into the text.
+    caret: usize,
+    clipboard: Option<SharedClipboard>,
+    #[command_group]
+    command_group: CommandGroup,
+    #[verb_graph]
+    graph: OptionVerbGraph<Self, U>,
+    draw_error: Option<DisplayError>,
+    phantom: PhantomData<G>,
+}
+
+impl<U: 'static, G: 'static> TextBox<U, G> {
+    /// Creates an empty text box, handling input from the global window event queue.
+    pub fn new(
+        placeholder: impl Into<String>,
+        bounds: Rect,
+        theme: Theme,
+        window_q: &RcEventQueue<WindowEvent>,
+    ) -> Self {
+        let graph = VerbGraph::new().and_add(
+            "window",
+            QueueHandler::new(window_q)
+                .and_on("mouse_press", |text_box: &mut Self, _, event| {
+                    if let WindowEvent::MousePress(point, MouseButton::Left) = event {
+                        let inside = text_box.bounds.contains(point);
+                        text_box.set_focused(inside);
+                        if inside {
+                            text_box.place_caret(point.x);
+                        }
+                    }
+                })
+                .and_on("character", |text_box: &mut Self, _, event| {
+                    if let WindowEvent::Character(c) = event {
+                        if text_box.focused {
+                            text_box.type_char(c);
+                        }
+                    }
+                })
+                .and_on("clipboard", |text_box: &mut Self, _, event| {
+                    if let WindowEvent::Clipboard(action) = event {
+                        if text_box.focused {
+                            text_box.handle_clipboard(action);
+                        }
+                    }
+                }),
+        );
+
+        TextBox {
+            event: RcEventQueue::new(),
+            text: String::new(),
+            placeholder: placeholder.into(),
+            bounds,
+            theme,
+            focused: false,
+            caret: 0,
+            clipboard: None,
+            command_group: CommandGroup::with_label("text_box"),
+            graph: Some(graph),
+            draw_error: None,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Returns `true` if the text box receives typed characters.
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Focuses or unfocuses the text box, emitting [`Focus`](TextBoxEvent::Focus) if this changed.
+    pub fn set_focused(&mut self, focused: bool) {
+        if self.focused != focused {
+            self.focused = focused;
+            self.command_group.repaint();
+            self.event.emit_owned(TextBoxEvent::Focus(focused));
+        }
+    }
+
+    /// Sets the clipboard which is copied to and pasted from while focused.
+    pub fn set_clipboard(&mut self, clipboard: SharedClipboard) {
+        self.clipboard = Some(clipboard);
+    }
+
+    /// Returns the caret offset, moved to the end if the text was replaced from under it.
+    fn caret(&self) -> usize {
+        if self.caret <= self.text.len() && self.text.is_char_boundary(self.caret) {
+            self.caret
+        } else {
+            self.text.len()
+        }
+    }
+
+    /// Moves the caret to the grapheme boundary closest to the x-coordinate `x`.
+    fn place_caret(&mut self, x: f32) {
+        let caret =
+            self.layout(&self.text, Color::default()).char_index_at_x(x).unwrap_or(self.text.len());
+        if self.caret != caret {
+            self.caret = caret;
+            self.command_group.repaint();
+        }
+    }
+
+    /// Returns `text` as laid out within the text box.
+    fn layout(&self, text: &str, color: Color) -> TextDisplayItem {
+        let text_bounds = self.theme.text_bounds(
+            text,
+            Point::new(self.bounds.origin.x + TEXT_PADDING, self.bounds.origin.y),
+        );
+        self.theme.text_item(text, center_vertically(text_bounds, self.bounds), color)
+    }
+
+    fn type_char(&mut self, c: char) {
+        let caret = self.caret();
+        match c {
+            // backspace
+            '\u{8}' => {
+                if caret == 0 {
+                    return;
+                }
+                // deleting a whole grapheme keeps combining marks from being left dangling.
+                let prev = DisplayText::Simple(self.text.clone()).prev_boundary(caret);
+                self.text.replace_range(prev..caret, "");
+                self.caret = prev;
+            }
+            '\r' | '\n' => {
+                self.event.emit_owned(TextBoxEvent::Submit(self.text.clone()));
+                return;
+            }
+            c if c.is_control() => return,
+            c => {
+                self.text.insert(caret, c);
+                self.caret = caret + c.len_utf8();
+            }
+        }
+        self.command_group.repaint();
+        self.event.emit_owned(TextBoxEvent::Change(self.text.clone()));
+    }
+
+    fn handle_clipboard(&mut self, action: ClipboardAction) {
+        let clipboard = match &self.clipboard {
+            Some(clipboard) => clipboard,
+            None => return,
+        };
+        match action {
+            ClipboardAction::Copy => {
+                let _ = clipboard.set_text(self.text.clone());
+            }
+            ClipboardAction::Cut => {
+                if self.text.is_empty() || clipboard.set_text(self.text.clone()).is_err() {
+                    return;
+                }
+                self.text.clear();
+                self.caret = 0;
+                self.command_group.repaint();
+                self.event.emit_owned(TextBoxEvent::Change(self.text.clone()));
+            }
+            ClipboardAction::Paste => {
+                // the text box is a single line, so line breaks (and other control characters) are dropped.
+                let pasted: String = match clipboard.get_text() {
+                    Ok(text) => text.chars().filter(|c| !c.is_control()).collect(),
+                    Err(_) => return,
+                };
+                if pasted.is_empty() {
+                    return;
+                }
+                let caret = self.caret();
+                self.text.insert_str(caret, &pasted);
+                self.caret = caret + pasted.len();
+                self.command_group.repaint();
+                self.event.emit_owned(TextBoxEvent::Change(self.text.clone()));
+            }
+        }
+    }
+
+    /// Takes the error which occurred the last time the text box failed to push its commands to the display, if any.
+    ///
+    /// A failed push leaves the text box marked for repaint, so it is drawn again on the next call to `draw`.
+    pub fn take_draw_error(&mut self) -> Option<DisplayError> {
+        self.draw_error.take()
+    }
+}
+
+impl<U: 'static, G: 'static> Styled for TextBox<U, G> {
+    fn style_class(&self) -> &str {
+        "text_box"
+    }
+
+    fn style_state(&self) -> StyleState {
+        StyleState { focused: self.focused, ..Default::default() }
+    }
+}
+
+#[cfg(feature = "state")]
+impl<U: 'static, G: 'static> crate::state::PersistentState for TextBox<U, G> {
+    fn save_state(&self) -> Option<crate::serde_json::Value> {
+        Some(crate::serde_json::json!({ "text": self.text }))
+    }
+
+    /// Restores the text, moving the caret to the end. This doesn't emit an event.
+    fn restore_state(&mut self, state: &crate::serde_json::Value) {
+        if let Some(text) = state.get("text").and_then(|text| text.as_str()) {
+            self.set_text(text.into());
+        }
+    }
+}
+
+impl<U: 'static, G: 'static> Widget for TextBox<U, G> {
+    type UpdateAux = U;
+    type GraphicalAux = G;
+    type DisplayObject = DisplayCommand;
+
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    #[cfg(feature = "state")]
+    fn persistent_state(&self) -> Option<&dyn crate::state::PersistentState> {
+        Some(self)
+    }
+
+    #[cfg(feature = "state")]
+    fn persistent_state_mut(&mut self) -> Option<&mut dyn crate::state::PersistentState> {
+        Some(self)
+    }
+
+    fn update(&mut self, aux: &mut U) {
+        verbgraph::update_all(self, aux);
+    }
+
+    fn draw(&mut self, display: &mut dyn GraphicsDisplay, _aux: &mut G) {
+        let style = self.theme.styles.resolve_for(self);
+        let (theme, bounds, focused) = (&self.theme, self.bounds, self.focused);
+        let (text, color) = if self.text.is_empty() {
+            (&self.placeholder, theme.placeholder)
+        } else {
+            (&self.text, style.foreground)
+        };
+        let item = self.layout(text, color);
+        let text_bounds = item.bounds().unwrap_or_default();
+        let caret_x = if self.text.is_empty() {
+            text_bounds.origin.x
+        } else {
+            item.x_at_char_index(self.caret()).unwrap_or_else(|_| text_bounds.max_x())
+        };
+
+        if let Err(err) = self.command_group.push_with(
+            display,
+            || {
+                let mut builder = DisplayListBuilder::new();
+                builder.push_rectangle(bounds, style.background_paint(), vec![]);
+                builder.save();
+                builder.push_rectangle_clip(bounds, true);
+                builder.push_text(item, vec![]);
+                if focused {
+                    builder.push_rectangle(
+                        Rect::new(
+                            Point::new(caret_x, text_bounds.origin.y),
+                            Size::new(1.0, text_bounds.size.height),
+                        ),
+                        GraphicsDisplayPaint::Fill(style.accent.into()),
+                        vec![],
+                    );
+                }
+                builder.restore();
+                builder.push_rectangle(bounds, style.border_paint(), vec![]);
+                builder.build()
+            },
+            ZOrder::default(),
+            None,
+            None,
+        ) {
+            self.draw_error = Some(err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            display::validate::validate_display_list,
+            input::{Key, Modifiers},
+            testing::Harness,
+            widgets::test_theme,
+        },
+    };
+
+    #[test]
+    fn test_text_box() {
+        let bounds = Rect::new(Point::new(10.0, 10.0), Size::new(200.0, 24.0));
+        let mut harness: Harness<TextBox> = Harness::new(
+            |window_q, _| TextBox::new("Name", bounds, test_theme(), window_q),
+            (),
+            (),
+        );
+        let listener = harness.root().event.listen();
+
+        // typing while unfocused does nothing.
+        harness.type_text("abc");
+        assert_eq!(harness.root().text(), "");
+
+        harness.click(Point::new(20.0, 20.0));
+        harness.type_text("hi!\u{8}\u{1b}\r");
+        assert_eq!(harness.root().text(), "hi");
+        harness.click(Point::new(20.0, 100.0));
+        assert!(!harness.root().is_focused());
+        assert_eq!(
+            listener.peek(),
+            &[
+                TextBoxEvent::Focus(true),
+                TextBoxEvent::Change("h".into()),
+                TextBoxEvent::Change("hi".into()),
+                TextBoxEvent::Change("hi!".into()),
+                TextBoxEvent::Change("hi".into()),
+                TextBoxEvent::Submit("hi".into()),
+                TextBoxEvent::Focus(false),
+            ]
+        );
+
+        let commands = harness.render_to_buffer();
+        validate_display_list(commands).unwrap();
+        assert_eq!(commands.len(), 6);
+
+        // clicking left of the text places the caret at the start.
+        harness.click(Point::new(12.0, 20.0));
+        harness.type_text("oh");
+        assert_eq!(harness.root().text(), "ohhi");
+        harness.type_text("\u{8}");
+        assert_eq!(harness.root().text(), "ohi");
+
+        // backspace removes a combining mark along with its base character.
+        harness.click(Point::new(200.0, 20.0));
+        harness.type_text("e\u{301}\u{8}");
+        assert_eq!(harness.root().text(), "ohi");
+    }
+
+    #[test]
+    fn test_clipboard() {
+        let bounds = Rect::new(Point::new(10.0, 10.0), Size::new(200.0, 24.0));
+        let clipboard = SharedClipboard::default();
+        let mut harness: Harness<TextBox> = Harness::new(
+            |window_q, _| {
+                let mut text_box = TextBox::new("Name", bounds, test_theme(), window_q);
+                text_box.set_clipboard(clipboard.clone());
+                text_box
+            },
+            (),
+            (),
+        );
+        let ctrl = Modifiers { ctrl: true, ..Default::default() };
+
+        // shortcuts are ignored while unfocused.
+        clipboard.set_text("pasted\n").unwrap();
+        harness.press_key(Key::V, ctrl);
+        assert_eq!(harness.root().text(), "");
+
+        harness.click(Point::new(20.0, 20.0));
+        harness.type_text("ab");
+        harness.press_key(Key::V, ctrl);
+        assert_eq!(harness.root().text(), "abpasted");
+        harness.type_text("!");
+        assert_eq!(harness.root().text(), "abpasted!");
+
+        harness.press_key(Key::X, ctrl);
+        assert_eq!(harness.root().text(), "");
+        assert_eq!(clipboard.get_text().unwrap(), "abpasted!");
+
+        harness.type_text("copy");
+        harness.emit(WindowEvent::Clipboard(ClipboardAction::Copy));
+        assert_eq!(harness.root().text(), "copy");
+        assert_eq!(clipboard.get_text().unwrap(), "copy");
+    }
+}
@@ -0,0 +1,162 @@
+//! Graphviz export of verb graphs.
+//!
+//! [`VerbGraph::export_dot`](crate::VerbGraph::export_dot) describes the tags of a verb graph and the event
+//! queues they handle. Jumps into other verb graphs (through [`require_update`](crate::require_update)) can't be
+//! known up-front; they are recorded while [`set_jump_recording`](set_jump_recording) is enabled, and included
+//! in the export from then on.
+//!
+//! ```ignore
+//! reclutch::verbgraph::dot::set_jump_recording(true);
+//! root_graph.update_all(&mut root, &mut aux);
+//! std::fs::write("root.dot", root_graph.export_dot())?;
+//! ```
+
+use std::{
+    cell::{Cell, RefCell},
+    fmt::Write,
+};
+
+/// A jump from a tag of one verb graph into a tag of another, recorded from [`require_update`](crate::require_update).
+///
+/// Verb graphs are identified by the type name of the object they update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Jump {
+    pub from_graph: &'static str,
+    pub from_tag: &'static str,
+    pub to_graph: &'static str,
+    pub to_tag: &'static str,
+}
+
+thread_local! {
+    static RECORDING: Cell<bool> = const { Cell::new(false) };
+    /// Tags currently being updated, innermost last.
+    static UPDATING: RefCell<Vec<(&'static str, &'static str)>> = const { RefCell::new(Vec::new()) };
+    static JUMPS: RefCell<Vec<Jump>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Enables or disables recording jumps between verb graphs on the current thread.
+///
+/// Recording adds a small overhead to every tag update, so it's disabled by default.
+pub fn set_jump_recording(enabled: bool) {
+    RECORDING.with(|recording| recording.set(enabled));
+}
+
+/// Returns every distinct jump recorded on the current thread.
+pub fn recorded_jumps() -> Vec<Jump> {
+    JUMPS.with(|jumps| jumps.borrow().clone())
+}
+
+/// Forgets the jumps recorded on the current thread.
+pub fn clear_recorded_jumps() {
+    JUMPS.with(|jumps| jumps.borrow_mut().clear());
+}
+
+/// Marks a tag as being updated until the guard is dropped, if recording.
+pub(crate) struct UpdateGuard(bool);
+
+impl UpdateGuard {
+    pub(crate) fn enter(graph: &'static str, tag: &'static str) -> Self {
+        let recording = RECORDING.with(Cell::get);
+        if recording {
+            UPDATING.with(|updating| updating.borrow_mut().push((graph, tag)));
+        }
+        UpdateGuard(recording)
+    }
+}
+
+impl Drop for UpdateGuard {
+    fn drop(&mut self) {
+        if self.0 {
+            UPDATING.with(|updating| updating.borrow_mut().pop());
+        }
+    }
+}
+
+/// Records a jump from the tag currently being updated (if any) into `to_tag` of `to_graph`.
+pub(crate) fn record_jump(to_graph: &'static str, to_tag: &'static str) {
+    if !RECORDING.with(Cell::get) {
+        return;
+    }
+    if let Some((from_graph, from_tag)) =
+        UPDATING.with(|updating| updating.borrow().last().copied())
+    {
+        let jump = Jump { from_graph, from_tag, to_graph, to_tag };
+        JUMPS.with(|jumps| {
+            let mut jumps = jumps.borrow_mut();
+            if !jumps.contains(&jump) {
+                jumps.push(jump);
+            }
+        });
+    }
+}
+
+/// Quotes a string as a DOT identifier.
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn tag_node(graph: &str, tag: &str) -> String {
+    quote(&format!("{}::{}", graph, tag))
+}
+
+/// A tag of a verb graph, along with the event types (and handled keys) of its queue handlers.
+pub(crate) struct TagDescription {
+    pub(crate) tag: &'static str,
+    pub(crate) queues: Vec<(&'static str, Vec<&'static str>)>,
+}
+
+pub(crate) fn export(graph: &'static str, tags: &[TagDescription]) -> String {
+    let mut out = String::new();
+    // writing into a string can't fail.
+    let _ = writeln!(out, "digraph {} {{", quote(graph));
+    let _ = writeln!(out, "    rankdir=LR;");
+
+    let mut queues: Vec<_> =
+        tags.iter().flat_map(|tag| tag.queues.iter().map(|(queue, _)| *queue)).collect();
+    queues.sort_unstable();
+    queues.dedup();
+    for queue in queues {
+        let _ = writeln!(out, "    {} [shape=ellipse];", quote(queue));
+    }
+
+    for tag in tags {
+        let _ = writeln!(
+            out,
+            "    {} [shape=box, label={}];",
+            tag_node(graph, tag.tag),
+            quote(tag.tag)
+        );
+        for (queue, keys) in &tag.queues {
+            let _ = writeln!(
+                out,
+                "    {} -> {} [label={}];",
+                quote(queue),
+                tag_node(graph, tag.tag),
+                quote(&keys.join(", "))
+            );
+        }
+    }
+
+    let mut jumps: Vec<_> =
+        recorded_jumps().into_iter().filter(|jump| jump.from_graph == graph).collect();
+    jumps.sort_unstable();
+    for jump in jumps {
+        if jump.to_graph != graph {
+            let _ = writeln!(
+                out,
+                "    {} [shape=box, style=dashed, label={}];",
+                tag_node(jump.to_graph, jump.to_tag),
+                quote(&format!("{}: {}", jump.to_graph, jump.to_tag))
+            );
+        }
+        let _ = writeln!(
+            out,
+            "    {} -> {} [style=dashed, label=\"require_update\"];",
+            tag_node(jump.from_graph, jump.from_tag),
+            tag_node(jump.to_graph, jump.to_tag)
+        );
+    }
+
+    out.push_str("}\n");
+    out
+}
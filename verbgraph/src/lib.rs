@@ -1,11 +1,20 @@
 use {
     as_any::{AsAny, Downcast},
-    reclutch_core::event::prelude::*,
-    std::{collections::HashMap, ops::Deref},
+    reclutch_core::event::{prelude::*, ConsumableEvent},
+    std::{any::TypeId, collections::HashMap, ops::Deref, time::Instant},
 };
 
-pub use paste;
 pub use as_any;
+pub use paste;
+
+pub mod dot;
+pub mod profile;
+pub mod schedule;
+
+pub use {
+    profile::{TagProfile, TimingStats, VerbGraphProfile},
+    schedule::UpdateScheduler,
+};
 
 /// An object which contains an `OptionVerbGraph` that can be accessed mutably.
 pub trait HasVerbGraph: reclutch_core::widget::Widget + Sized + 'static {
@@ -24,12 +33,100 @@ pub type OptionVerbGraph<T, A> = Option<VerbGraph<T, A>>;
 
 /// Event which returns a string corresponding to the current event variant.
 pub trait Event: Clone {
+    /// Every key [`get_key`](Event::get_key) can return.
+    ///
+    /// This is generated by `#[derive(Event)]`; hand-written implementations which leave it empty
+    /// opt out of [`check_exhaustive`](VerbGraph::check_exhaustive).
+    const KEYS: &'static [&'static str] = &[];
+
     fn get_key(&self) -> &'static str;
 }
 
+impl Event for reclutch_core::input::WindowEvent {
+    const KEYS: &'static [&'static str] = reclutch_core::input::WindowEvent::KEYS;
+
+    #[inline]
+    fn get_key(&self) -> &'static str {
+        self.key()
+    }
+}
+
+/// Information about an event being handled, given to handlers added with `on_with_context`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventContext {
+    /// Index of the event within the events handled in this update.
+    pub index: usize,
+    /// Number of events after this one in this update (of any key).
+    pub remaining: usize,
+    /// Tag of the queue handler within the verb graph, if updated through one.
+    pub tag: Option<&'static str>,
+    stopped: bool,
+}
+
+impl EventContext {
+    /// Stops handling events for the rest of this update.
+    ///
+    /// The remaining events have already been read from the queue, so they are skipped rather than deferred.
+    #[inline]
+    pub fn stop(&mut self) {
+        self.stopped = true;
+    }
+
+    /// Returns `true` if [`stop`](EventContext::stop) was called.
+    #[inline]
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+}
+
+type Handler<T, A, E> = Box<dyn Fn(&mut T, &mut A, E, &mut EventContext)>;
+type HandlerMap<T, A, E> = HashMap<&'static str, Handler<T, A, E>>;
+
+fn handle_events<T, A, E: Event>(
+    handlers: &HandlerMap<T, A, E>,
+    events: &[E],
+    tag: Option<&'static str>,
+    obj: &mut T,
+    additional: &mut A,
+    mut profile: Option<&mut TagProfile>,
+) {
+    let mut ctx = EventContext { index: 0, remaining: 0, tag, stopped: false };
+    for (index, event) in events.iter().enumerate() {
+        if let Some(handler) = handlers.get(event.get_key()) {
+            ctx.index = index;
+            ctx.remaining = events.len() - index - 1;
+            match profile.as_mut() {
+                Some(profile) => {
+                    let start = Instant::now();
+                    (*handler)(obj, additional, event.clone(), &mut ctx);
+                    profile.handlers.entry(event.get_key()).or_default().record(start.elapsed(), 1);
+                }
+                None => (*handler)(obj, additional, event.clone(), &mut ctx),
+            }
+            if ctx.stopped {
+                break;
+            }
+        }
+    }
+}
+
+fn consume_handler<T, A, E, P>(
+    unwrap: impl Fn(E) -> Option<ConsumableEvent<P>> + 'static,
+    pred: impl Fn(&mut T, &mut A, &P) -> bool + 'static,
+    handler: impl Fn(&mut T, &mut A, P) + 'static,
+) -> Handler<T, A, E> {
+    Box::new(move |obj, additional, event, _| {
+        if let Some(value) =
+            unwrap(event).and_then(|event| event.consume_if(|value| pred(obj, additional, value)))
+        {
+            handler(obj, additional, value);
+        }
+    })
+}
+
 /// A queue handler not bound to any specific event queue.
 pub struct UnboundQueueHandler<T, A: 'static, E: Event> {
-    handlers: HashMap<&'static str, Box<dyn Fn(&mut T, &mut A, E)>>,
+    handlers: HandlerMap<T, A, E>,
 }
 
 impl<T, A, E: Event> Default for UnboundQueueHandler<T, A, E> {
@@ -52,7 +149,8 @@ impl<T, A, E: Event> UnboundQueueHandler<T, A, E> {
         ev: &'static str,
         handler: impl Fn(&mut T, &mut A, E) + 'static,
     ) -> &'a mut Self {
-        self.handlers.insert(ev, Box::new(handler));
+        self.handlers
+            .insert(ev, Box::new(move |obj, additional, event, _| handler(obj, additional, event)));
         self
     }
 
@@ -67,6 +165,57 @@ impl<T, A, E: Event> UnboundQueueHandler<T, A, E> {
         self
     }
 
+    /// Same as [`on`](UnboundQueueHandler::on), however the closure also receives an [`EventContext`](EventContext),
+    /// which allows coalescing events (through [`remaining`](EventContext::remaining)) and early termination.
+    pub fn on_with_context<'a>(
+        &'a mut self,
+        ev: &'static str,
+        handler: impl Fn(&mut T, &mut A, E, &mut EventContext) + 'static,
+    ) -> &'a mut Self {
+        self.handlers.insert(ev, Box::new(handler));
+        self
+    }
+
+    /// Same as [`on_with_context`](UnboundQueueHandler::on_with_context), however `self` is consumed and returned.
+    #[inline]
+    pub fn and_on_with_context(
+        mut self,
+        ev: &'static str,
+        handler: impl Fn(&mut T, &mut A, E, &mut EventContext) + 'static,
+    ) -> Self {
+        self.on_with_context(ev, handler);
+        self
+    }
+
+    /// Adds a closure to be executed when an event of a specific key carrying a [`ConsumableEvent`](ConsumableEvent) is matched,
+    /// the payload of which isn't consumed yet and is accepted by `pred`.
+    ///
+    /// `unwrap` extracts the consumable payload from the event (e.g. a derived `unwrap_as_*` function).
+    /// The payload is consumed before `handler` is invoked, so it isn't delivered to later handlers or graphs.
+    pub fn on_consume<'a, P>(
+        &'a mut self,
+        ev: &'static str,
+        unwrap: impl Fn(E) -> Option<ConsumableEvent<P>> + 'static,
+        pred: impl Fn(&mut T, &mut A, &P) -> bool + 'static,
+        handler: impl Fn(&mut T, &mut A, P) + 'static,
+    ) -> &'a mut Self {
+        self.handlers.insert(ev, consume_handler(unwrap, pred, handler));
+        self
+    }
+
+    /// Same as [`on_consume`](UnboundQueueHandler::on_consume), however `self` is consumed and returned.
+    #[inline]
+    pub fn and_on_consume<P>(
+        mut self,
+        ev: &'static str,
+        unwrap: impl Fn(E) -> Option<ConsumableEvent<P>> + 'static,
+        pred: impl Fn(&mut T, &mut A, &P) -> bool + 'static,
+        handler: impl Fn(&mut T, &mut A, P) + 'static,
+    ) -> Self {
+        self.on_consume(ev, unwrap, pred, handler);
+        self
+    }
+
     /// Binds the queue handler to a given event queue, thereby returning a regular, bound queue handler.
     pub fn bind<D: QueueInterfaceListable<Item = E, Listener = L>, L: EventListen<Item = E>>(
         self,
@@ -74,11 +223,16 @@ impl<T, A, E: Event> UnboundQueueHandler<T, A, E> {
     ) -> QueueHandler<T, A, E, L> {
         QueueHandler { handlers: self.handlers, listener: queue.listen() }
     }
+
+    /// Binds the queue handler to an existing listener; see [`QueueHandler::from_listener`](QueueHandler::from_listener).
+    pub fn bind_listener<L: EventListen<Item = E>>(self, listener: L) -> QueueHandler<T, A, E, L> {
+        QueueHandler { handlers: self.handlers, listener }
+    }
 }
 
 /// A queue handler containing a map of event keys to closures, bound to an event.
 pub struct QueueHandler<T, A: 'static, E: Event, L: EventListen<Item = E>> {
-    handlers: HashMap<&'static str, Box<dyn Fn(&mut T, &mut A, E)>>,
+    handlers: HandlerMap<T, A, E>,
     listener: L,
 }
 
@@ -90,6 +244,12 @@ impl<T, A, E: Event, L: EventListen<Item = E>> QueueHandler<T, A, E, L> {
         QueueHandler { handlers: HashMap::new(), listener: queue.listen() }
     }
 
+    /// Creates a new queue handler from an existing listener, such as one transformed through
+    /// [`EventListenExt`](reclutch_core::event::prelude::EventListenExt).
+    pub fn from_listener(listener: L) -> Self {
+        QueueHandler { handlers: HashMap::new(), listener }
+    }
+
     /// Adds a closure to be executed when an event of a specific key is matched.
     ///
     /// Also see [`event_key`](struct.Event.html#structmethod.get_key).
@@ -98,7 +258,8 @@ impl<T, A, E: Event, L: EventListen<Item = E>> QueueHandler<T, A, E, L> {
         ev: &'static str,
         handler: impl Fn(&mut T, &mut A, E) + 'static,
     ) -> &'a mut Self {
-        self.handlers.insert(ev, Box::new(handler));
+        self.handlers
+            .insert(ev, Box::new(move |obj, additional, event, _| handler(obj, additional, event)));
         self
     }
 
@@ -112,14 +273,114 @@ impl<T, A, E: Event, L: EventListen<Item = E>> QueueHandler<T, A, E, L> {
         self.on(ev, handler);
         self
     }
+
+    /// Same as [`on`](QueueHandler::on), however the closure also receives an [`EventContext`](EventContext),
+    /// which allows coalescing events (through [`remaining`](EventContext::remaining)) and early termination.
+    pub fn on_with_context<'a>(
+        &'a mut self,
+        ev: &'static str,
+        handler: impl Fn(&mut T, &mut A, E, &mut EventContext) + 'static,
+    ) -> &'a mut Self {
+        self.handlers.insert(ev, Box::new(handler));
+        self
+    }
+
+    /// Same as [`on_with_context`](QueueHandler::on_with_context), however `self` is consumed and returned.
+    #[inline]
+    pub fn and_on_with_context(
+        mut self,
+        ev: &'static str,
+        handler: impl Fn(&mut T, &mut A, E, &mut EventContext) + 'static,
+    ) -> Self {
+        self.on_with_context(ev, handler);
+        self
+    }
+
+    /// Adds a closure to be executed when an event of a specific key carrying a [`ConsumableEvent`](ConsumableEvent) is matched,
+    /// the payload of which isn't consumed yet and is accepted by `pred`.
+    ///
+    /// `unwrap` extracts the consumable payload from the event (e.g. a derived `unwrap_as_*` function).
+    /// The payload is consumed before `handler` is invoked, so it isn't delivered to later handlers or graphs.
+    pub fn on_consume<'a, P>(
+        &'a mut self,
+        ev: &'static str,
+        unwrap: impl Fn(E) -> Option<ConsumableEvent<P>> + 'static,
+        pred: impl Fn(&mut T, &mut A, &P) -> bool + 'static,
+        handler: impl Fn(&mut T, &mut A, P) + 'static,
+    ) -> &'a mut Self {
+        self.handlers.insert(ev, consume_handler(unwrap, pred, handler));
+        self
+    }
+
+    /// Same as [`on_consume`](QueueHandler::on_consume), however `self` is consumed and returned.
+    #[inline]
+    pub fn and_on_consume<P>(
+        mut self,
+        ev: &'static str,
+        unwrap: impl Fn(E) -> Option<ConsumableEvent<P>> + 'static,
+        pred: impl Fn(&mut T, &mut A, &P) -> bool + 'static,
+        handler: impl Fn(&mut T, &mut A, P) + 'static,
+    ) -> Self {
+        self.on_consume(ev, unwrap, pred, handler);
+        self
+    }
+
+    /// Returns the keys of the event type (see [`KEYS`](Event::KEYS)) which have no handler.
+    pub fn unhandled_keys(&self) -> Vec<&'static str> {
+        E::KEYS.iter().copied().filter(|key| !self.handlers.contains_key(key)).collect()
+    }
 }
 
 /// Implemented by queue handlers to execute the inner closures regardless of surrounding types.
 pub trait DynQueueHandler<T, A>: AsAny {
+    /// Invokes the queue handler to peek (at most `limit`) events and match them.
+    ///
+    /// `tag` is passed on to the handlers through [`EventContext`](EventContext::tag).
+    fn update_with(
+        &mut self,
+        limit: Option<usize>,
+        tag: Option<&'static str>,
+        obj: &mut T,
+        additional: &mut A,
+    );
     /// Invokes the queue handler to peek events and match them.
-    fn update(&mut self, obj: &mut T, additional: &mut A);
+    #[inline]
+    fn update(&mut self, obj: &mut T, additional: &mut A) {
+        self.update_with(None, None, obj, additional)
+    }
     /// Almost identical to `update`, however only the first `n` events are handled.
-    fn update_n(&mut self, n: usize, obj: &mut T, additional: &mut A);
+    #[inline]
+    fn update_n(&mut self, n: usize, obj: &mut T, additional: &mut A) {
+        self.update_with(Some(n), None, obj, additional)
+    }
+    /// Same as [`update_with`](DynQueueHandler::update_with) without a limit, while also recording the timings
+    /// of the individual event handlers into `profile`.
+    ///
+    /// Returns the number of events read from the queue. The default implementation doesn't record anything
+    /// and returns `0`.
+    fn update_profiled(
+        &mut self,
+        tag: &'static str,
+        obj: &mut T,
+        additional: &mut A,
+        profile: &mut TagProfile,
+    ) -> usize {
+        let _ = profile;
+        self.update_with(None, Some(tag), obj, additional);
+        0
+    }
+    /// Returns the type ID of the handled event type.
+    fn event_type(&self) -> TypeId;
+    /// Returns the type name of the handled event type, for diagnostics.
+    fn event_type_name(&self) -> &'static str {
+        "<unknown>"
+    }
+    /// Returns the event keys which have a handler, for diagnostics.
+    fn handled_keys(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+    /// See [`QueueHandler::unhandled_keys`](QueueHandler::unhandled_keys).
+    fn unhandled_keys(&self) -> Vec<&'static str>;
 }
 
 impl<T: 'static, A: 'static> Downcast for dyn DynQueueHandler<T, A> {}
@@ -131,26 +392,54 @@ where
     E: Event + 'static,
     L: EventListen<Item = E> + 'static,
 {
-    fn update(&mut self, obj: &mut T, additional: &mut A) {
-        let handlers = &mut self.handlers;
+    fn update_with(
+        &mut self,
+        limit: Option<usize>,
+        tag: Option<&'static str>,
+        obj: &mut T,
+        additional: &mut A,
+    ) {
+        let handlers = &self.handlers;
+        let handle = |events: &[E]| handle_events(handlers, events, tag, obj, additional, None);
+        match limit {
+            Some(n) => self.listener.with_n(n, handle),
+            None => self.listener.with(handle),
+        }
+    }
+
+    fn update_profiled(
+        &mut self,
+        tag: &'static str,
+        obj: &mut T,
+        additional: &mut A,
+        profile: &mut TagProfile,
+    ) -> usize {
+        let handlers = &self.handlers;
         self.listener.with(|events| {
-            for event in events {
-                if let Some(handler) = handlers.get_mut(event.get_key()) {
-                    (*handler)(obj, additional, event.clone());
-                }
-            }
-        });
+            handle_events(handlers, events, Some(tag), obj, additional, Some(profile));
+            events.len()
+        })
     }
 
-    fn update_n(&mut self, n: usize, obj: &mut T, additional: &mut A) {
-        let handlers = &mut self.handlers;
-        self.listener.with_n(n, |events| {
-            for event in events {
-                if let Some(handler) = handlers.get_mut(event.get_key()) {
-                    (*handler)(obj, additional, event.clone());
-                }
-            }
-        });
+    #[inline]
+    fn event_type(&self) -> TypeId {
+        TypeId::of::<E>()
+    }
+
+    #[inline]
+    fn event_type_name(&self) -> &'static str {
+        std::any::type_name::<E>()
+    }
+
+    fn handled_keys(&self) -> Vec<&'static str> {
+        let mut keys: Vec<_> = self.handlers.keys().copied().collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    #[inline]
+    fn unhandled_keys(&self) -> Vec<&'static str> {
+        QueueHandler::unhandled_keys(self)
     }
 }
 
@@ -158,11 +447,12 @@ where
 /// The tags facilitate jumping to specifc sections of other `VerbGraph`s, hence allowing for non-linear queue handling.
 pub struct VerbGraph<T: 'static, A: 'static> {
     handlers: HashMap<&'static str, Vec<Box<dyn DynQueueHandler<T, A>>>>,
+    profile: Option<VerbGraphProfile>,
 }
 
 impl<T: 'static, A: 'static> Default for VerbGraph<T, A> {
     fn default() -> Self {
-        VerbGraph { handlers: Default::default() }
+        VerbGraph { handlers: Default::default(), profile: None }
     }
 }
 
@@ -196,27 +486,117 @@ impl<T: 'static, A: 'static> VerbGraph<T, A> {
     }
 
     fn update_handlers(
+        tag: &'static str,
         handlers: &mut [Box<dyn DynQueueHandler<T, A>>],
         obj: &mut T,
         additional: &mut A,
+        profile: Option<&mut VerbGraphProfile>,
     ) {
-        for handler in handlers {
-            handler.update(obj, additional);
+        let _guard = dot::UpdateGuard::enter(std::any::type_name::<T>(), tag);
+        match profile {
+            Some(profile) => {
+                let profile = profile.tags.entry(tag).or_default();
+                let start = Instant::now();
+                let mut events = 0;
+                for handler in handlers {
+                    events += handler.update_profiled(tag, obj, additional, profile);
+                }
+                profile.stats.record(start.elapsed(), events as u64);
+            }
+            None => {
+                for handler in handlers {
+                    handler.update_with(None, Some(tag), obj, additional);
+                }
+            }
         }
     }
 
     /// Invokes all the queue handlers in a linear fashion, however non-linear jumping between verb graphs is still supported.
     pub fn update_all(&mut self, obj: &mut T, additional: &mut A) {
-        for handler_list in self.handlers.values_mut() {
-            VerbGraph::update_handlers(handler_list, obj, additional)
+        if let Some(profile) = &mut self.profile {
+            profile.updates += 1;
+        }
+        for (tag, handler_list) in self.handlers.iter_mut() {
+            VerbGraph::update_handlers(tag, handler_list, obj, additional, self.profile.as_mut())
         }
     }
 
+    /// Starts recording the timings of every update into a [`VerbGraphProfile`](VerbGraphProfile).
+    ///
+    /// This has no effect if profiling is already enabled.
+    pub fn enable_profiling(&mut self) {
+        if self.profile.is_none() {
+            self.profile = Some(Default::default());
+        }
+    }
+
+    /// Stops profiling, returning the timings recorded since profiling was enabled.
+    pub fn disable_profiling(&mut self) -> Option<VerbGraphProfile> {
+        self.profile.take()
+    }
+
+    /// Returns the timings recorded so far, if profiling is enabled.
+    ///
+    /// The profile implements `Display`, formatting it as a report.
+    pub fn profile(&self) -> Option<&VerbGraphProfile> {
+        self.profile.as_ref()
+    }
+
+    /// Clears the timings recorded so far (e.g. at the start of each frame), keeping profiling enabled.
+    pub fn reset_profile(&mut self) {
+        if let Some(profile) = &mut self.profile {
+            *profile = Default::default();
+        }
+    }
+
+    /// Returns the keys of `E` which are missed by queue handlers of `E`, as `(tag, key)` pairs.
+    ///
+    /// This is meant to be called once after building the verb graph (e.g. in a `debug_assert!`, or to report the
+    /// missing keys however the application sees fit), since handlers are keyed by strings and a missing variant is
+    /// otherwise silently ignored.
+    pub fn check_exhaustive<E: Event + 'static>(&self) -> Vec<(&'static str, &'static str)> {
+        let mut missing: Vec<_> = self
+            .handlers
+            .iter()
+            .flat_map(|(tag, handlers)| {
+                handlers
+                    .iter()
+                    .filter(|handler| handler.event_type() == TypeId::of::<E>())
+                    .flat_map(move |handler| {
+                        handler.unhandled_keys().into_iter().map(move |key| (*tag, key))
+                    })
+            })
+            .collect();
+        missing.sort_unstable();
+        missing.dedup();
+        missing
+    }
+
+    /// Returns a graphviz (DOT) description of the tags, the event queues they handle and the jumps into
+    /// other verb graphs recorded so far (see [`dot`](dot)).
+    ///
+    /// Queues are identified by their event type, so distinct queues of the same event type share a node.
+    pub fn export_dot(&self) -> String {
+        let mut tags: Vec<_> = self
+            .handlers
+            .iter()
+            .map(|(tag, handlers)| dot::TagDescription {
+                tag,
+                queues: handlers
+                    .iter()
+                    .map(|handler| (handler.event_type_name(), handler.handled_keys()))
+                    .collect(),
+            })
+            .collect();
+        tags.sort_by_key(|tag| tag.tag);
+        dot::export(std::any::type_name::<T>(), &tags)
+    }
+
     /// Invokes the queue handlers for a specific tag.
     #[inline]
     pub fn update_tag(&mut self, obj: &mut T, additional: &mut A, tag: &'static str) {
         if let Some(handlers) = self.handlers.get_mut(tag) {
-            VerbGraph::update_handlers(handlers, obj, additional)
+            VerbGraph::update_handlers(tag, handlers, obj, additional, self.profile.as_mut())
         }
     }
 }
@@ -240,6 +620,7 @@ where
     T: HasVerbGraph<UpdateAux = A>,
     A: 'static,
 {
+    dot::record_jump(std::any::type_name::<T>(), tag);
     update_obj_with(obj, additional, |graph, obj, additional| {
         graph.update_tag(obj, additional, tag)
     });
@@ -294,8 +675,8 @@ macro_rules! verbgraph {
                 qh.on(
                     std::stringify!($ev),
                     |$obj: &mut $ot, $add: &mut $at, #[allow(unused_variables)] $eo| {
-                        #[allow(unused_variables)]
                         $crate::paste::expr!{
+                            #[allow(unused_variables)]
                             let $eo = $eo.[<unwrap_as_ $ev>]().unwrap();
                             $body
                         }
@@ -340,8 +721,8 @@ macro_rules! unbound_queue_handler {
             qh.on(
                 std::stringify!($ev),
                 |$obj: &mut $ot, #[allow(unused_variables)] $add: &mut $at, $eo: $et| {
-                    #[allow(unused_variables)]
                     $crate::paste::expr!{
+                        #[allow(unused_variables)]
                         let $eo = $eo.[<unwrap_as_ $ev>]().unwrap();
                         $body
                     }
@@ -434,4 +815,284 @@ mod tests {
         assert_eq!(root.dep.a, root.dep.b);
         assert_eq!(root.dep.b, 7);
     }
+
+    #[test]
+    fn test_event_context() {
+        #[derive(Clone)]
+        struct Move(i32);
+
+        impl Event for Move {
+            fn get_key(&self) -> &'static str {
+                if self.0 < 0 {
+                    "stop"
+                } else {
+                    "move"
+                }
+            }
+        }
+
+        #[derive(Default)]
+        struct Cursor {
+            position: i32,
+            handled: usize,
+            contexts: Vec<EventContext>,
+        }
+
+        let queue = RcEventQueue::new();
+        let mut graph = VerbGraph::new().and_add(
+            "cursor",
+            QueueHandler::new(&queue)
+                .and_on_with_context("move", |obj: &mut Cursor, _: &mut (), event: Move, ctx| {
+                    obj.handled += 1;
+                    // coalesce bursts of moves into the last one.
+                    if ctx.remaining == 0 {
+                        obj.position = event.0;
+                    }
+                })
+                .and_on_with_context("stop", |obj, _, _, ctx| {
+                    obj.contexts.push(ctx.clone());
+                    ctx.stop();
+                }),
+        );
+
+        let mut cursor = Cursor::default();
+        for i in 1..=3 {
+            queue.emit_owned(Move(i));
+        }
+        graph.update_all(&mut cursor, &mut ());
+        assert_eq!(cursor.position, 3);
+        assert_eq!(cursor.handled, 3);
+
+        queue.emit_owned(Move(4));
+        queue.emit_owned(Move(-1));
+        queue.emit_owned(Move(5));
+        graph.update_tag(&mut cursor, &mut (), "cursor");
+        assert_eq!(cursor.handled, 4);
+        assert_eq!(cursor.position, 3);
+        assert_eq!(cursor.contexts.len(), 1);
+        assert_eq!((cursor.contexts[0].index, cursor.contexts[0].remaining), (1, 1));
+        assert_eq!(cursor.contexts[0].tag, Some("cursor"));
+        assert!(!cursor.contexts[0].is_stopped());
+
+        // the event after `stop` was skipped, not deferred.
+        graph.update_all(&mut cursor, &mut ());
+        assert_eq!(cursor.handled, 4);
+    }
+
+    #[test]
+    fn test_on_consume() {
+        #[derive(Clone)]
+        struct Click(ConsumableEvent<i32>);
+
+        impl Event for Click {
+            fn get_key(&self) -> &'static str {
+                "click"
+            }
+        }
+
+        // a widget occupying a range of positions, which accepts clicks within it.
+        fn graph(queue: &RcEventQueue<Click>) -> VerbGraph<(i32, Vec<i32>), ()> {
+            VerbGraph::new().and_add(
+                "widget",
+                QueueHandler::new(queue).and_on_consume(
+                    "click",
+                    |event: Click| Some(event.0),
+                    |obj: &mut (i32, Vec<i32>), _, pos| (obj.0..obj.0 + 10).contains(pos),
+                    |obj, _, pos| obj.1.push(pos),
+                ),
+            )
+        }
+
+        let queue = RcEventQueue::new();
+        let (mut top_graph, mut bottom_graph) = (graph(&queue), graph(&queue));
+        let (mut top, mut bottom) = ((5, Vec::new()), (0, Vec::new()));
+
+        for pos in &[2, 7, 12, 20] {
+            queue.emit_owned(Click(ConsumableEvent::new(*pos)));
+        }
+        top_graph.update_all(&mut top, &mut ());
+        bottom_graph.update_all(&mut bottom, &mut ());
+
+        assert_eq!(top.1, vec![7, 12]);
+        assert_eq!(bottom.1, vec![2]);
+    }
+
+    #[test]
+    fn test_from_listener() {
+        #[derive(Clone)]
+        struct Step(i32);
+
+        impl Event for Step {
+            fn get_key(&self) -> &'static str {
+                "step"
+            }
+        }
+
+        let queue = RcEventQueue::new();
+        let listener = queue.listen().filtered(|x: &i32| *x > 0).mapped(|x: &i32| Step(*x));
+        let mut graph = VerbGraph::new().and_add(
+            "steps",
+            QueueHandler::from_listener(listener)
+                .and_on("step", |total: &mut i32, _: &mut (), step: Step| *total += step.0),
+        );
+
+        for i in -2..=3 {
+            queue.emit_owned(i);
+        }
+        let mut total = 0;
+        graph.update_all(&mut total, &mut ());
+        assert_eq!(total, 6);
+    }
+
+    #[test]
+    fn test_profiling() {
+        #[derive(Clone)]
+        struct Move(i32);
+
+        impl Event for Move {
+            fn get_key(&self) -> &'static str {
+                if self.0 < 0 {
+                    "back"
+                } else {
+                    "forward"
+                }
+            }
+        }
+
+        let queue = RcEventQueue::new();
+        let mut graph = VerbGraph::new().and_add(
+            "cursor",
+            QueueHandler::new(&queue)
+                .and_on("forward", |obj: &mut i32, _: &mut (), event: Move| *obj += event.0)
+                .and_on("back", |obj, _, event| *obj += event.0),
+        );
+        let mut position = 0;
+
+        queue.emit_owned(Move(1));
+        graph.update_all(&mut position, &mut ());
+        assert!(graph.profile().is_none());
+
+        graph.enable_profiling();
+        for i in 0..4 {
+            queue.emit_owned(Move(2));
+            queue.emit_owned(Move(-1));
+            if i % 2 == 0 {
+                queue.emit_owned(Move(3));
+            }
+            graph.update_all(&mut position, &mut ());
+        }
+        graph.update_tag(&mut position, &mut (), "cursor");
+        assert_eq!(position, 11);
+
+        let profile = graph.profile().unwrap();
+        assert_eq!(profile.updates, 4);
+        let cursor = profile.tag("cursor").unwrap();
+        assert_eq!((cursor.stats.calls, cursor.stats.events), (5, 10));
+        assert_eq!(cursor.handlers["forward"].calls, 6);
+        assert_eq!(cursor.handlers["back"].calls, 4);
+        assert!(cursor.stats.max <= cursor.stats.total);
+        assert_eq!(profile.events_per_update("cursor"), 2.5);
+        assert_eq!(profile.events_per_update("missing"), 0.0);
+
+        let report = profile.to_string();
+        assert!(report.starts_with("4 updates\ntag \"cursor\": 5 calls, 10 events"));
+        assert!(report.contains("\"back\": 4 calls"));
+
+        graph.reset_profile();
+        assert_eq!(graph.profile().unwrap().updates, 0);
+        assert!(graph.disable_profiling().is_some());
+        assert!(graph.profile().is_none());
+    }
+
+    #[test]
+    fn test_export_dot() {
+        #[derive(Clone)]
+        struct Ping;
+
+        impl Event for Ping {
+            fn get_key(&self) -> &'static str {
+                "ping"
+            }
+        }
+
+        #[derive(Default)]
+        struct Child {
+            pings: u32,
+            q: RcEventQueue<Ping>,
+            g: OptionVerbGraph<Self, ()>,
+        }
+
+        impl reclutch_core::widget::Widget for Child {
+            type UpdateAux = ();
+            type GraphicalAux = ();
+            type DisplayObject = ();
+        }
+
+        impl HasVerbGraph for Child {
+            fn verb_graph(&mut self) -> &mut OptionVerbGraph<Self, ()> {
+                &mut self.g
+            }
+        }
+
+        #[derive(Default)]
+        struct Parent {
+            child: Child,
+            q: RcEventQueue<Ping>,
+        }
+
+        let mut parent = Parent::default();
+        let mut graph = VerbGraph::new().and_add(
+            "forward",
+            QueueHandler::new(&parent.q).and_on("ping", |parent: &mut Parent, aux: &mut (), _| {
+                parent.child.q.emit_owned(Ping);
+                require_update(&mut parent.child, aux, "count");
+            }),
+        );
+        parent.child.g = VerbGraph::new()
+            .and_add(
+                "count",
+                QueueHandler::new(&parent.child.q)
+                    .and_on("ping", |child: &mut Child, _, _| child.pings += 1),
+            )
+            .into();
+
+        // jumps aren't recorded by default.
+        parent.q.emit_owned(Ping);
+        graph.update_all(&mut parent, &mut ());
+        assert!(dot::recorded_jumps().is_empty());
+
+        dot::set_jump_recording(true);
+        parent.q.emit_owned(Ping);
+        graph.update_all(&mut parent, &mut ());
+        dot::set_jump_recording(false);
+        assert_eq!(parent.child.pings, 2);
+
+        let (parent_name, child_name) =
+            (std::any::type_name::<Parent>(), std::any::type_name::<Child>());
+        assert_eq!(
+            dot::recorded_jumps(),
+            vec![dot::Jump {
+                from_graph: parent_name,
+                from_tag: "forward",
+                to_graph: child_name,
+                to_tag: "count"
+            }]
+        );
+
+        let export = graph.export_dot();
+        assert!(export.starts_with(&format!("digraph \"{}\" {{", parent_name)));
+        assert!(export.contains(&format!(
+            "\"{}\" -> \"{}::forward\" [label=\"ping\"];",
+            std::any::type_name::<Ping>(),
+            parent_name
+        )));
+        assert!(export.contains(&format!(
+            "\"{}::forward\" -> \"{}::count\" [style=dashed",
+            parent_name, child_name
+        )));
+        assert!(export.ends_with("}\n"));
+
+        dot::clear_recorded_jumps();
+        assert!(!graph.export_dot().contains("require_update"));
+    }
 }
@@ -0,0 +1,117 @@
+//! Timing statistics of verb graph updates.
+//!
+//! Profiling is enabled per verb graph with [`enable_profiling`](crate::VerbGraph::enable_profiling). From then on,
+//! every update of a tag records how long its queue handlers took and how many events they handled, both per tag and
+//! per event key. The collected [`VerbGraphProfile`](VerbGraphProfile) is formatted as a report through `Display`:
+//!
+//! ```text
+//! 120 updates
+//! tag "window": 120 calls, 480 events, total 1.2ms, avg 10µs, max 40µs
+//!     "mouse_move": 400 calls, total 0.9ms, avg 2.25µs, max 20µs
+//!     "mouse_press": 80 calls, total 0.3ms, avg 3.75µs, max 30µs
+//! ```
+
+use std::{collections::HashMap, fmt, time::Duration};
+
+/// Accumulated timings of a tag or an event handler.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimingStats {
+    /// Number of times the tag was updated, or the handler was invoked.
+    pub calls: u64,
+    /// Number of events handled. For event handlers this is the same as `calls`.
+    pub events: u64,
+    pub total: Duration,
+    /// Longest single call.
+    pub max: Duration,
+}
+
+impl TimingStats {
+    pub(crate) fn record(&mut self, elapsed: Duration, events: u64) {
+        self.calls += 1;
+        self.events += events;
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+    }
+
+    /// Returns the average duration of a call.
+    pub fn average(&self) -> Duration {
+        if self.calls == 0 {
+            Duration::default()
+        } else {
+            self.total / self.calls as u32
+        }
+    }
+}
+
+/// Timings of a single tag of a verb graph.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TagProfile {
+    /// Timings of updating all the queue handlers of the tag, including reading the event queues.
+    pub stats: TimingStats,
+    /// Timings of the individual event handlers, by event key.
+    pub handlers: HashMap<&'static str, TimingStats>,
+}
+
+/// Timings collected by a profiled verb graph.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VerbGraphProfile {
+    /// Number of [`update_all`](crate::VerbGraph::update_all) calls.
+    pub updates: u64,
+    pub tags: HashMap<&'static str, TagProfile>,
+}
+
+impl VerbGraphProfile {
+    /// Returns the timings of a tag, if it was updated while profiling.
+    pub fn tag(&self, tag: &str) -> Option<&TagProfile> {
+        self.tags.get(tag)
+    }
+
+    /// Returns the average number of events a tag handled per [`update_all`](crate::VerbGraph::update_all).
+    pub fn events_per_update(&self, tag: &str) -> f64 {
+        match (self.tag(tag), self.updates) {
+            (Some(profile), updates) if updates > 0 => profile.stats.events as f64 / updates as f64,
+            _ => 0.0,
+        }
+    }
+}
+
+fn sorted_by_total<'a, V>(
+    map: &'a HashMap<&'static str, V>,
+    stats: impl Fn(&V) -> &TimingStats,
+) -> Vec<(&'static str, &'a V)> {
+    let mut entries: Vec<_> = map.iter().map(|(key, value)| (*key, value)).collect();
+    entries.sort_by(|a, b| stats(b.1).total.cmp(&stats(a.1).total).then(a.0.cmp(b.0)));
+    entries
+}
+
+impl fmt::Display for VerbGraphProfile {
+    /// Formats the report, with the slowest tags and handlers first.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} updates", self.updates)?;
+        for (tag, profile) in sorted_by_total(&self.tags, |profile| &profile.stats) {
+            let stats = &profile.stats;
+            writeln!(
+                f,
+                "tag {:?}: {} calls, {} events, total {:?}, avg {:?}, max {:?}",
+                tag,
+                stats.calls,
+                stats.events,
+                stats.total,
+                stats.average(),
+                stats.max
+            )?;
+            for (key, stats) in sorted_by_total(&profile.handlers, |stats| stats) {
+                writeln!(
+                    f,
+                    "    {:?}: {} calls, total {:?}, avg {:?}, max {:?}",
+                    key,
+                    stats.calls,
+                    stats.total,
+                    stats.average(),
+                    stats.max
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
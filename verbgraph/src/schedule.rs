@@ -0,0 +1,434 @@
+//! Running the verb graphs of a widget tree in dependency order.
+//!
+//! Normally, verb graphs are updated in whatever order `update` happens to be propagated through the widget
+//! tree. When a widget reacts to events emitted by a widget which is updated after it, it only sees them the
+//! next frame. An [`UpdateScheduler`](UpdateScheduler) instead updates a set of named widgets of a tree in an order
+//! which satisfies the declared dependencies:
+//!
+//! ```ignore
+//! let mut scheduler = UpdateScheduler::new();
+//! scheduler
+//!     .add("data", |app: &mut App| &mut app.model)
+//!     .add("layout", |app: &mut App| &mut app.panel.layout);
+//! scheduler.after("layout", "data")?;
+//!
+//! // every frame:
+//! scheduler.update(&mut app, &mut aux)?;
+//! ```
+//!
+//! Alternatively, widgets can name themselves and declare their own dependencies through
+//! [`Widget::update_schedule`](reclutch_core::widget::Widget::update_schedule), in which case
+//! [`collect`](UpdateScheduler::collect) finds them within the tree:
+//!
+//! ```ignore
+//! impl Widget for Layout {
+//!     fn update_schedule(&self) -> Option<UpdateSchedule> {
+//!         Some(UpdateSchedule { name: "layout", after: &["data"] })
+//!     }
+//!
+//!     fn scheduled_update(&mut self, aux: &mut Self::UpdateAux) {
+//!         self.update_all(aux)
+//!     }
+//!
+//!     // --snip--
+//! }
+//!
+//! scheduler.collect(&app)?;
+//! ```
+
+use {
+    crate::OperatesVerbGraph,
+    reclutch_core::{error::ScheduleError, widget::WidgetChildren},
+};
+
+type UpdateFn<R, A> = Box<dyn FnMut(&mut R, &mut A)>;
+
+struct ScheduledUpdate<R, A> {
+    name: &'static str,
+    update: UpdateFn<R, A>,
+    dependencies: Vec<usize>,
+}
+
+/// Updates the verb graphs of widgets within a tree rooted at `R`, in dependency order.
+///
+/// Updates without dependencies between them run in the order they were added.
+pub struct UpdateScheduler<R, A> {
+    updates: Vec<ScheduledUpdate<R, A>>,
+    order: Option<Vec<usize>>,
+    collected: Vec<&'static str>,
+}
+
+impl<R, A> Default for UpdateScheduler<R, A> {
+    fn default() -> Self {
+        UpdateScheduler { updates: Vec::new(), order: None, collected: Vec::new() }
+    }
+}
+
+impl<R, A> UpdateScheduler<R, A> {
+    /// Creates a scheduler without any updates.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a widget, selected from the root, whose verb graph is updated through
+    /// [`OperatesVerbGraph::update_all`](OperatesVerbGraph::update_all).
+    ///
+    /// If `name` was already added, its update is replaced and its dependencies are kept.
+    pub fn add<W, F>(&mut self, name: &'static str, select: F) -> &mut Self
+    where
+        W: OperatesVerbGraph<UpdateAux = A> + ?Sized,
+        F: for<'a> Fn(&'a mut R) -> &'a mut W + 'static,
+    {
+        self.add_with(name, move |root, aux| select(root).update_all(aux))
+    }
+
+    /// Adds an arbitrary update, e.g. one which only updates a specific tag.
+    ///
+    /// If `name` was already added, its update is replaced and its dependencies are kept.
+    pub fn add_with(
+        &mut self,
+        name: &'static str,
+        update: impl FnMut(&mut R, &mut A) + 'static,
+    ) -> &mut Self {
+        match self.index_of(name) {
+            Ok(index) => self.updates[index].update = Box::new(update),
+            Err(_) => {
+                self.updates.push(ScheduledUpdate {
+                    name,
+                    update: Box::new(update),
+                    dependencies: Vec::new(),
+                });
+                self.order = None;
+            }
+        }
+        self
+    }
+
+    /// Declares that `name` must be updated after `dependency`.
+    pub fn after(
+        &mut self,
+        name: &'static str,
+        dependency: &'static str,
+    ) -> Result<&mut Self, ScheduleError> {
+        let (index, dependency) = (self.index_of(name)?, self.index_of(dependency)?);
+        if !self.updates[index].dependencies.contains(&dependency) {
+            self.updates[index].dependencies.push(dependency);
+            self.order = None;
+        }
+        Ok(self)
+    }
+
+    /// Removes an update, along with the dependencies on it. Returns `false` if it didn't exist.
+    pub fn remove(&mut self, name: &'static str) -> bool {
+        let index = match self.index_of(name) {
+            Ok(index) => index,
+            Err(_) => return false,
+        };
+        self.updates.remove(index);
+        for update in &mut self.updates {
+            update.dependencies.retain(|dependency| *dependency != index);
+            for dependency in &mut update.dependencies {
+                if *dependency > index {
+                    *dependency -= 1;
+                }
+            }
+        }
+        self.order = None;
+        true
+    }
+
+    /// Adds every widget within the tree (including the root) which returns an
+    /// [`update_schedule`](reclutch_core::widget::Widget::update_schedule), running its
+    /// [`scheduled_update`](reclutch_core::widget::Widget::scheduled_update) after the updates it names.
+    ///
+    /// Widgets are found again through their position in the tree, so this should be called again whenever
+    /// children are added or removed; the updates of widgets which are no longer found are removed, and an update
+    /// whose position no longer holds the widget it was collected from is skipped until then.
+    ///
+    /// Dependencies can name updates added manually or collected from elsewhere in the tree; naming any other
+    /// update fails with [`UnknownUpdate`](ScheduleError::UnknownUpdate).
+    pub fn collect<G, D>(&mut self, root: &R) -> Result<&mut Self, ScheduleError>
+    where
+        R: WidgetChildren<UpdateAux = A, GraphicalAux = G, DisplayObject = D>,
+    {
+        let mut found = Vec::new();
+        find_scheduled(root, &mut Vec::new(), &mut found);
+
+        for name in std::mem::take(&mut self.collected) {
+            if !found.iter().any(|(schedule, _)| schedule.name == name) {
+                self.remove(name);
+            }
+        }
+        for (schedule, path) in &found {
+            let (name, path) = (schedule.name, path.clone());
+            self.add_with(name, move |root: &mut R, additional: &mut A| {
+                if let Some(widget) = widget_at(root, &path) {
+                    if widget.update_schedule().map(|schedule| schedule.name) == Some(name) {
+                        widget.scheduled_update(additional);
+                    }
+                }
+            });
+            self.collected.push(name);
+        }
+        for (schedule, _) in &found {
+            for dependency in schedule.after {
+                self.after(schedule.name, dependency)?;
+            }
+        }
+        Ok(self)
+    }
+
+    /// Returns the names of the updates in the order they're run.
+    pub fn order(&mut self) -> Result<Vec<&'static str>, ScheduleError> {
+        let order = self.sorted()?;
+        Ok(order.iter().map(|index| self.updates[*index].name).collect())
+    }
+
+    /// Runs every update in dependency order.
+    ///
+    /// Fails without running any update if the dependencies contain a cycle.
+    pub fn update(&mut self, root: &mut R, additional: &mut A) -> Result<(), ScheduleError> {
+        let order = self.sorted()?;
+        for index in order {
+            (self.updates[index].update)(root, additional);
+        }
+        Ok(())
+    }
+
+    fn index_of(&self, name: &'static str) -> Result<usize, ScheduleError> {
+        self.updates
+            .iter()
+            .position(|update| update.name == name)
+            .ok_or(ScheduleError::UnknownUpdate(name))
+    }
+
+    /// Topologically sorts the updates (Kahn's algorithm), preferring the order they were added in.
+    fn sorted(&mut self) -> Result<Vec<usize>, ScheduleError> {
+        if self.order.is_none() {
+            let mut remaining: Vec<usize> =
+                self.updates.iter().map(|update| update.dependencies.len()).collect();
+            let mut done = vec![false; self.updates.len()];
+            let mut order = Vec::with_capacity(self.updates.len());
+
+            while let Some(next) = (0..self.updates.len()).find(|i| !done[*i] && remaining[*i] == 0)
+            {
+                done[next] = true;
+                order.push(next);
+                for (index, update) in self.updates.iter().enumerate() {
+                    if update.dependencies.contains(&next) {
+                        remaining[index] -= 1;
+                    }
+                }
+            }
+
+            if order.len() < self.updates.len() {
+                return Err(ScheduleError::Cycle(
+                    (0..self.updates.len())
+                        .filter(|i| !done[*i])
+                        .map(|i| self.updates[i].name)
+                        .collect(),
+                ));
+            }
+            self.order = Some(order);
+        }
+        Ok(self.order.clone().unwrap_or_default())
+    }
+}
+
+type DynWidget<'a, U, G, D> =
+    dyn WidgetChildren<UpdateAux = U, GraphicalAux = G, DisplayObject = D> + 'a;
+
+/// Collects the schedules of a widget and its descendants, along with the child indices leading to them.
+fn find_scheduled<U, G, D>(
+    widget: &DynWidget<'_, U, G, D>,
+    path: &mut Vec<usize>,
+    found: &mut Vec<(reclutch_core::widget::UpdateSchedule, Vec<usize>)>,
+) {
+    if let Some(schedule) = widget.update_schedule() {
+        found.push((schedule, path.clone()));
+    }
+    for (index, child) in widget.children().into_iter().enumerate() {
+        path.push(index);
+        find_scheduled(child, path, found);
+        path.pop();
+    }
+}
+
+fn widget_at<'a, U, G, D>(
+    widget: &'a mut DynWidget<'a, U, G, D>,
+    path: &[usize],
+) -> Option<&'a mut DynWidget<'a, U, G, D>> {
+    match path.split_first() {
+        None => Some(widget),
+        Some((index, rest)) => widget_at(widget.children_mut().into_iter().nth(*index)?, rest),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{require_update, HasVerbGraph, OptionVerbGraph, QueueHandler, VerbGraph},
+        reclutch_core::{
+            event::{prelude::*, RcEventQueue},
+            widget::{ChildrenList, ChildrenListMut, UpdateSchedule, Widget},
+        },
+    };
+
+    #[derive(Clone)]
+    struct Changed(i32);
+
+    impl crate::Event for Changed {
+        fn get_key(&self) -> &'static str {
+            "changed"
+        }
+    }
+
+    /// Doubles every value it receives, and emits the result.
+    #[derive(Default)]
+    struct Doubler {
+        value: i32,
+        output: RcEventQueue<Changed>,
+        graph: OptionVerbGraph<Self, ()>,
+        schedule: Option<UpdateSchedule>,
+    }
+
+    impl Doubler {
+        fn new(input: &RcEventQueue<Changed>) -> Self {
+            let graph = VerbGraph::new().and_add(
+                "input",
+                QueueHandler::new(input).and_on("changed", |obj: &mut Doubler, _, event| {
+                    obj.value = event.0 * 2;
+                    obj.output.emit_owned(Changed(obj.value));
+                }),
+            );
+            Doubler { graph: Some(graph), ..Default::default() }
+        }
+    }
+
+    impl Widget for Doubler {
+        type UpdateAux = ();
+        type GraphicalAux = ();
+        type DisplayObject = ();
+
+        fn update_schedule(&self) -> Option<UpdateSchedule> {
+            self.schedule
+        }
+
+        fn scheduled_update(&mut self, aux: &mut ()) {
+            self.update_all(aux)
+        }
+    }
+
+    impl WidgetChildren for Doubler {}
+
+    impl HasVerbGraph for Doubler {
+        fn verb_graph(&mut self) -> &mut OptionVerbGraph<Self, ()> {
+            &mut self.graph
+        }
+    }
+
+    impl OperatesVerbGraph for Doubler {
+        fn update_all(&mut self, additional: &mut ()) {
+            crate::update_all(self, additional)
+        }
+
+        fn require_update(&mut self, additional: &mut (), tag: &'static str) {
+            require_update(self, additional, tag)
+        }
+    }
+
+    struct Root {
+        input: RcEventQueue<Changed>,
+        // `layout` depends on `data`, but is declared (and would be propagated) first.
+        layout: Doubler,
+        data: Doubler,
+    }
+
+    #[test]
+    fn test_update_scheduler() {
+        let input = RcEventQueue::new();
+        let data = Doubler::new(&input);
+        let layout = Doubler::new(&data.output);
+        let mut root = Root { input, layout, data };
+
+        let mut scheduler = UpdateScheduler::new();
+        scheduler
+            .add("layout", |root: &mut Root| &mut root.layout)
+            .add("data", |root: &mut Root| &mut root.data);
+        assert_eq!(scheduler.order().unwrap(), vec!["layout", "data"]);
+        scheduler.after("layout", "data").unwrap();
+        assert_eq!(scheduler.order().unwrap(), vec!["data", "layout"]);
+
+        // the layout reacts within the same frame.
+        root.input.emit_owned(Changed(3));
+        scheduler.update(&mut root, &mut ()).unwrap();
+        assert_eq!((root.data.value, root.layout.value), (6, 12));
+
+        assert_eq!(
+            scheduler.after("layout", "missing").err(),
+            Some(ScheduleError::UnknownUpdate("missing"))
+        );
+        scheduler.after("data", "layout").unwrap();
+        assert_eq!(
+            scheduler.update(&mut root, &mut ()),
+            Err(ScheduleError::Cycle(vec!["layout", "data"]))
+        );
+
+        assert!(scheduler.remove("layout"));
+        assert!(!scheduler.remove("layout"));
+        assert_eq!(scheduler.order().unwrap(), vec!["data"]);
+    }
+
+    struct Tree {
+        children: Vec<Doubler>,
+    }
+
+    impl Widget for Tree {
+        type UpdateAux = ();
+        type GraphicalAux = ();
+        type DisplayObject = ();
+    }
+
+    impl WidgetChildren for Tree {
+        fn children(&self) -> ChildrenList<'_, (), (), ()> {
+            self.children.iter().map(|child| child as _).collect()
+        }
+
+        fn children_mut(&mut self) -> ChildrenListMut<'_, (), (), ()> {
+            self.children.iter_mut().map(|child| child as _).collect()
+        }
+    }
+
+    #[test]
+    fn test_collect_update_scheduler() {
+        let input = RcEventQueue::new();
+        let mut data = Doubler::new(&input);
+        data.schedule = Some(UpdateSchedule { name: "data", after: &[] });
+        let mut layout = Doubler::new(&data.output);
+        layout.schedule = Some(UpdateSchedule { name: "layout", after: &["data"] });
+        let mut tree = Tree { children: vec![layout, data] };
+
+        let mut scheduler = UpdateScheduler::new();
+        scheduler.collect(&tree).unwrap();
+        assert_eq!(scheduler.order().unwrap(), vec!["data", "layout"]);
+
+        input.emit_owned(Changed(3));
+        scheduler.update(&mut tree, &mut ()).unwrap();
+        assert_eq!((tree.children[1].value, tree.children[0].value), (6, 12));
+
+        // until collected again, moved widgets aren't updated in place of others.
+        tree.children.remove(0);
+        input.emit_owned(Changed(4));
+        scheduler.update(&mut tree, &mut ()).unwrap();
+        assert_eq!(tree.children[0].value, 6);
+
+        scheduler.collect(&tree).unwrap();
+        assert_eq!(scheduler.order().unwrap(), vec!["data"]);
+        scheduler.update(&mut tree, &mut ()).unwrap();
+        assert_eq!(tree.children[0].value, 8);
+
+        tree.children[0].schedule = Some(UpdateSchedule { name: "data", after: &["missing"] });
+        assert_eq!(scheduler.collect(&tree).err(), Some(ScheduleError::UnknownUpdate("missing")));
+    }
+}